@@ -0,0 +1,282 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Derive macros for Masonry.
+//!
+//! This crate is re-exported by Masonry; use the macros through
+//! `masonry::widget::StoreInWidgetMut` rather than depending on it directly.
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::spanned::Spanned;
+use syn::{
+    parse_macro_input, Data, DeriveInput, Error, Fields, GenericArgument, Ident, Lit, Meta,
+    NestedMeta, PathArguments, Type,
+};
+
+/// Generate the `WidgetMut` boilerplate of a widget.
+///
+/// See `masonry::widget::StoreInWidgetMut` for documentation.
+#[proc_macro_derive(StoreInWidgetMut, attributes(widget_mut))]
+pub fn derive_store_in_widget_mut(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// What to invalidate after a generated setter changes a field.
+enum Invalidate {
+    Layout,
+    Paint,
+}
+
+/// The `#[widget_mut(...)]` options of a field.
+#[derive(Default)]
+struct FieldOptions {
+    child: bool,
+    set: Option<Invalidate>,
+}
+
+fn expand(input: DeriveInput) -> Result<TokenStream, Error> {
+    let widget_name = &input.ident;
+    let vis = &input.vis;
+    let mut_name = struct_options(&input)?
+        .unwrap_or_else(|| format_ident!("{}Mut", widget_name, span = widget_name.span()));
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new(
+                    input.span(),
+                    "StoreInWidgetMut can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new(
+                input.span(),
+                "StoreInWidgetMut can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut accessors = Vec::new();
+    let mut children = Vec::new();
+    for field in fields {
+        let options = field_options(field)?;
+        let field_name = field.ident.as_ref().unwrap();
+        let field_ty = &field.ty;
+
+        if options.child {
+            let child_ty = widget_pod_arg(field_ty)?;
+            let getter = format_ident!("{}_mut", field_name);
+            let doc = format!("Get a `WidgetMut` to the `{}` child.", field_name);
+            accessors.push(quote! {
+                #[doc = #doc]
+                pub fn #getter(&mut self) -> ::masonry::widget::WidgetMut<'_, 'b, #child_ty>
+                where
+                    #child_ty: ::masonry::widget::StoreInWidgetMut,
+                {
+                    self.ctx.get_mut(&mut self.widget.#field_name)
+                }
+            });
+            children.push(quote! { self.#field_name.as_dyn() });
+        }
+
+        if let Some(invalidate) = options.set {
+            let setter = format_ident!("set_{}", field_name);
+            let doc = format!("Set the `{}` field.", field_name);
+            let request = match invalidate {
+                Invalidate::Layout => quote! { self.ctx.request_layout(); },
+                Invalidate::Paint => quote! { self.ctx.request_paint(); },
+            };
+            accessors.push(quote! {
+                #[doc = #doc]
+                pub fn #setter(&mut self, #field_name: impl ::std::convert::Into<#field_ty>) {
+                    self.widget.#field_name = #field_name.into();
+                    #request
+                }
+            });
+        }
+    }
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let params = input.generics.params.iter();
+    let mut_params = quote! { 'a, 'b, #(#params),* };
+    let ty_args = input.generics.params.iter().map(|param| match param {
+        syn::GenericParam::Type(param) => {
+            let ident = &param.ident;
+            quote! { #ident }
+        }
+        syn::GenericParam::Lifetime(param) => {
+            let lifetime = &param.lifetime;
+            quote! { #lifetime }
+        }
+        syn::GenericParam::Const(param) => {
+            let ident = &param.ident;
+            quote! { #ident }
+        }
+    });
+    let mut_args = quote! { 'a, 'b, #(#ty_args),* };
+    let struct_doc = format!(
+        "A mutable reference to a [`{}`], created through `WidgetMut`.",
+        widget_name
+    );
+
+    let pod_children = if children.is_empty() {
+        quote! {}
+    } else {
+        quote! {
+            impl #impl_generics #widget_name #ty_generics #where_clause {
+                /// The children stored in fields marked with `#[widget_mut(child)]`.
+                ///
+                /// Can be returned from [`Widget::children`](::masonry::Widget::children).
+                #[allow(dead_code)]
+                fn pod_children(
+                    &self,
+                ) -> ::masonry::__private::SmallVec<
+                    [::masonry::widget::WidgetRef<'_, dyn ::masonry::Widget>; 16],
+                > {
+                    let mut children = ::masonry::__private::SmallVec::new();
+                    #(children.push(#children);)*
+                    children
+                }
+            }
+        }
+    };
+
+    Ok(quote! {
+        #[doc = #struct_doc]
+        #vis struct #mut_name<#mut_params> #where_clause {
+            ctx: ::masonry::WidgetCtx<'a, 'b>,
+            widget: &'a mut #widget_name #ty_generics,
+        }
+
+        impl #impl_generics ::masonry::widget::StoreInWidgetMut for #widget_name #ty_generics #where_clause {
+            type Mut<'a, 'b: 'a> = #mut_name<#mut_args>;
+
+            fn get_widget_and_ctx<'s: 'r, 'a: 'r, 'b: 'a, 'r>(
+                widget_mut: &'s mut Self::Mut<'a, 'b>,
+            ) -> (&'r mut Self, &'r mut ::masonry::WidgetCtx<'a, 'b>) {
+                (widget_mut.widget, &mut widget_mut.ctx)
+            }
+
+            fn from_widget_and_ctx<'a, 'b>(
+                widget: &'a mut Self,
+                ctx: ::masonry::WidgetCtx<'a, 'b>,
+            ) -> Self::Mut<'a, 'b> {
+                #mut_name { ctx, widget }
+            }
+        }
+
+        impl<#mut_params> ::std::ops::Deref for #mut_name<#mut_args> #where_clause {
+            type Target = #widget_name #ty_generics;
+
+            fn deref(&self) -> &Self::Target {
+                self.widget
+            }
+        }
+
+        impl<#mut_params> #mut_name<#mut_args> #where_clause {
+            #(#accessors)*
+        }
+
+        #pod_children
+    })
+}
+
+/// Parse `#[widget_mut(name = "FooMut")]` on the struct.
+fn struct_options(input: &DeriveInput) -> Result<Option<Ident>, Error> {
+    let mut name = None;
+    for nested in widget_mut_attrs(&input.attrs)? {
+        match nested {
+            NestedMeta::Meta(Meta::NameValue(name_value)) if name_value.path.is_ident("name") => {
+                let Lit::Str(lit) = &name_value.lit else {
+                    return Err(Error::new(name_value.lit.span(), "expected a string"));
+                };
+                name = Some(Ident::new(&lit.value(), lit.span()));
+            }
+            other => return Err(Error::new(other.span(), "unknown widget_mut option")),
+        }
+    }
+    Ok(name)
+}
+
+/// Parse `#[widget_mut(child)]`, `#[widget_mut(set)]` and `#[widget_mut(set(paint))]`
+/// on a field.
+fn field_options(field: &syn::Field) -> Result<FieldOptions, Error> {
+    let mut options = FieldOptions::default();
+    for nested in widget_mut_attrs(&field.attrs)? {
+        match nested {
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("child") => options.child = true,
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("set") => {
+                options.set = Some(Invalidate::Layout);
+            }
+            NestedMeta::Meta(Meta::List(list)) if list.path.is_ident("set") => {
+                let invalidate = match list.nested.iter().next() {
+                    Some(NestedMeta::Meta(Meta::Path(path))) if path.is_ident("layout") => {
+                        Invalidate::Layout
+                    }
+                    Some(NestedMeta::Meta(Meta::Path(path))) if path.is_ident("paint") => {
+                        Invalidate::Paint
+                    }
+                    _ => {
+                        return Err(Error::new(
+                            list.span(),
+                            "expected `set(layout)` or `set(paint)`",
+                        ))
+                    }
+                };
+                options.set = Some(invalidate);
+            }
+            other => return Err(Error::new(other.span(), "unknown widget_mut option")),
+        }
+    }
+    Ok(options)
+}
+
+fn widget_mut_attrs(attrs: &[syn::Attribute]) -> Result<Vec<NestedMeta>, Error> {
+    let mut nested = Vec::new();
+    for attr in attrs {
+        if !attr.path.is_ident("widget_mut") {
+            continue;
+        }
+        match attr.parse_meta()? {
+            Meta::List(list) => nested.extend(list.nested),
+            meta => {
+                return Err(Error::new(
+                    meta.span(),
+                    "expected `#[widget_mut(...)]` with a list of options",
+                ))
+            }
+        }
+    }
+    Ok(nested)
+}
+
+/// Extract `T` from `WidgetPod<T>`.
+fn widget_pod_arg(ty: &Type) -> Result<&Type, Error> {
+    let error = || {
+        Error::new(
+            ty.span(),
+            "`#[widget_mut(child)]` can only be used on `WidgetPod<T>` fields",
+        )
+    };
+    let Type::Path(path) = ty else {
+        return Err(error());
+    };
+    let segment = path.path.segments.last().ok_or_else(error)?;
+    if segment.ident != "WidgetPod" {
+        return Err(error());
+    }
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return Err(error());
+    };
+    match args.args.first() {
+        Some(GenericArgument::Type(child_ty)) => Ok(child_ty),
+        _ => Err(error()),
+    }
+}
@@ -0,0 +1,172 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Access keys, also called mnemonics: activating a widget with Alt and a letter.
+//!
+//! A [`Label`](crate::widget::Label) declares an access key with
+//! [`Label::with_access_key`](crate::widget::Label::with_access_key). While Alt is
+//! held, the window sends [`SHOW_ACCESS_KEYS`] to these labels, which underline the
+//! key in their text. When Alt and the key are pressed, and no widget handles the
+//! key press, the window sends [`ACTIVATE`] to the widget associated with the label:
+//! by default the label's parent, eg the [`Button`](crate::widget::Button) it's in.
+//!
+//! Access keys are registered per window. A widget such as a menu or a dialog can
+//! also be a scope, with [`LifeCycleCtx::register_access_key_scope`]: the access
+//! keys of its descendants are only active while the focus is inside it, and the
+//! other keys of the window are inactive meanwhile.
+//!
+//! If several enabled widgets of the active scope share an access key, pressing it
+//! moves the focus from one to the next instead of activating them.
+//!
+//! [`LifeCycleCtx::register_access_key_scope`]: crate::LifeCycleCtx::register_access_key_scope
+
+use tracing::warn;
+
+use crate::widget::{WidgetRef, WidgetState};
+use crate::{Selector, Widget, WidgetId};
+
+/// Sent to a widget when its access key is pressed.
+///
+/// Widgets which can be clicked should behave as if they were, and mark the
+/// command as handled. Widgets which take text input should request the focus.
+pub const ACTIVATE: Selector = Selector::new("masonry-builtin.activate");
+
+/// Sent to the labels with an access key when Alt is pressed or released, with
+/// whether the key should be underlined.
+pub const SHOW_ACCESS_KEYS: Selector<bool> = Selector::new("masonry-builtin.show-access-keys");
+
+#[derive(Clone, Copy, Debug)]
+struct AccessKey {
+    key: char,
+    label: WidgetId,
+    target: WidgetId,
+}
+
+/// The access keys and scopes registered in a window.
+#[derive(Debug, Default)]
+pub(crate) struct AccessKeys {
+    keys: Vec<AccessKey>,
+    scopes: Vec<WidgetId>,
+    /// Whether the labels were told to underline their key.
+    is_shown: bool,
+}
+
+/// Access keys are matched regardless of case.
+fn normalize(key: char) -> char {
+    key.to_lowercase().next().unwrap_or(key)
+}
+
+impl AccessKeys {
+    /// Register the access key of `label`, which activates `target`.
+    ///
+    /// This replaces the key previously registered by `label`, if any.
+    pub(crate) fn register(&mut self, key: char, label: WidgetId, target: WidgetId) {
+        self.keys.retain(|access_key| access_key.label != label);
+        self.keys.push(AccessKey {
+            key: normalize(key),
+            label,
+            target,
+        });
+    }
+
+    /// Register `id` as a scope for the access keys of its descendants.
+    pub(crate) fn register_scope(&mut self, id: WidgetId) {
+        if !self.scopes.contains(&id) {
+            self.scopes.push(id);
+        }
+    }
+
+    /// Add the keys and scopes registered in `other`, eg by a widget which wasn't in
+    /// the tree yet.
+    pub(crate) fn append(&mut self, other: AccessKeys) {
+        for access_key in other.keys {
+            self.register(access_key.key, access_key.label, access_key.target);
+        }
+        for scope in other.scopes {
+            self.register_scope(scope);
+        }
+    }
+
+    /// Drop the keys and scopes of widgets which were removed from the tree.
+    ///
+    /// As with command listeners, the bloom filter can return false positives.
+    fn retain_in_tree(&mut self, root_state: &WidgetState) {
+        let is_in_tree =
+            |id: &WidgetId| *id == root_state.id || root_state.children.may_contain(id);
+        self.keys.retain(|access_key| is_in_tree(&access_key.label));
+        self.scopes.retain(is_in_tree);
+    }
+
+    /// Set whether the keys are shown, and return the labels to notify if it changed.
+    pub(crate) fn set_shown(&mut self, is_shown: bool, root_state: &WidgetState) -> Vec<WidgetId> {
+        if self.is_shown == is_shown {
+            return Vec::new();
+        }
+        self.is_shown = is_shown;
+        self.retain_in_tree(root_state);
+        self.keys
+            .iter()
+            .map(|access_key| access_key.label)
+            .collect()
+    }
+
+    /// The enabled widgets activated by `key`, in the scope containing `focus`.
+    pub(crate) fn targets(
+        &mut self,
+        key: char,
+        focus: Option<WidgetId>,
+        root: WidgetRef<'_, dyn Widget>,
+    ) -> Vec<WidgetId> {
+        self.retain_in_tree(root.state());
+        let key = normalize(key);
+        let active_scope = focus.and_then(|focus| self.scope_of(focus, root));
+
+        let mut targets = Vec::new();
+        for access_key in &self.keys {
+            if access_key.key != key
+                || targets.contains(&access_key.target)
+                || self.scope_of(access_key.label, root) != active_scope
+            {
+                continue;
+            }
+            // Targets which aren't in the widget tree, eg because their parent doesn't
+            // list them as children, are assumed to be enabled.
+            let is_disabled = root
+                .find_widget_by_id(access_key.target)
+                .map_or(false, |target| target.state().is_disabled());
+            if !is_disabled {
+                targets.push(access_key.target);
+            }
+        }
+        if targets.len() > 1 {
+            warn!(
+                "Access key '{}' is shared by {} widgets, moving the focus instead of activating one",
+                key,
+                targets.len()
+            );
+        }
+        targets
+    }
+
+    /// The innermost scope containing `id`, or `None` if it's only in the window's scope.
+    fn scope_of(&self, id: WidgetId, root: WidgetRef<'_, dyn Widget>) -> Option<WidgetId> {
+        let containing: Vec<_> = self
+            .scopes
+            .iter()
+            .filter_map(|&scope| {
+                let state = root.find_widget_by_id(scope)?.state();
+                (scope == id || state.children.may_contain(&id)).then_some((scope, state))
+            })
+            .collect();
+        // The innermost scope is inside all the others.
+        containing
+            .iter()
+            .find(|(scope, _)| {
+                containing
+                    .iter()
+                    .all(|(other, state)| other == scope || state.children.may_contain(scope))
+            })
+            .map(|(scope, _)| *scope)
+    }
+}
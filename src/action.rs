@@ -3,10 +3,13 @@
 // details.
 
 use std::any::Any;
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
+use std::time::Duration;
 
-use crate::{WidgetId, WindowId};
+use crate::ui_state::UiState;
+use crate::widget::FieldId;
+use crate::{Data, Value, WidgetId, WindowId};
 
 // TODO - Refactor - See issue #1
 
@@ -21,6 +24,21 @@ pub enum Action {
     TextChanged(String),
     TextEntered(String),
     CheckboxChecked(bool),
+    VideoPositionChanged(Duration),
+    ChartItemSelected(usize),
+    /// A suggestion of an [`Autocomplete`](crate::widget::Autocomplete) was accepted.
+    SuggestionAccepted(String),
+    /// The zoom factor of a [`Portal`](crate::widget::Portal) was changed by the user.
+    ZoomChanged(f64),
+    /// The content of a [`Portal`](crate::widget::Portal) with pull-to-refresh was pulled
+    /// down past its top. The app should reload it, then send
+    /// [`REFRESH_FINISHED`](crate::widget::REFRESH_FINISHED) to the portal.
+    RefreshRequested,
+    FormSubmitted(HashMap<FieldId, Value>),
+    UiStateSaved(UiState),
+    /// A framework invariant was violated, and the [`InvariantPolicy`](crate::InvariantPolicy)
+    /// is `EmitAction`.
+    InvariantViolation(String),
     // FIXME - This is a huge hack
     Other(Arc<dyn Any>),
 }
@@ -32,6 +50,26 @@ impl PartialEq for Action {
             (Self::TextChanged(l0), Self::TextChanged(r0)) => l0 == r0,
             (Self::TextEntered(l0), Self::TextEntered(r0)) => l0 == r0,
             (Self::CheckboxChecked(l0), Self::CheckboxChecked(r0)) => l0 == r0,
+            (Self::VideoPositionChanged(l0), Self::VideoPositionChanged(r0)) => l0 == r0,
+            (Self::ChartItemSelected(l0), Self::ChartItemSelected(r0)) => l0 == r0,
+            (Self::SuggestionAccepted(l0), Self::SuggestionAccepted(r0)) => l0 == r0,
+            (Self::ZoomChanged(l0), Self::ZoomChanged(r0)) => l0 == r0,
+            (Self::RefreshRequested, Self::RefreshRequested) => true,
+            (Self::InvariantViolation(l0), Self::InvariantViolation(r0)) => l0 == r0,
+            (Self::FormSubmitted(l0), Self::FormSubmitted(r0)) => {
+                l0.len() == r0.len()
+                    && l0
+                        .iter()
+                        .all(|(id, value)| r0.get(id).map_or(false, |other| value.same(other)))
+            }
+            (Self::UiStateSaved(l0), Self::UiStateSaved(r0)) => {
+                l0.geometry == r0.geometry
+                    && l0.focus == r0.focus
+                    && l0.widgets.len() == r0.widgets.len()
+                    && l0.widgets.iter().all(|(id, value)| {
+                        r0.widgets.get(id).map_or(false, |other| value.same(other))
+                    })
+            }
             #[allow(clippy::vtable_address_comparisons)]
             (Self::Other(val_l), Self::Other(val_r)) => Arc::ptr_eq(val_l, val_r),
             _ => false,
@@ -46,6 +84,22 @@ impl std::fmt::Debug for Action {
             Self::TextChanged(text) => f.debug_tuple("TextChanged").field(text).finish(),
             Self::TextEntered(text) => f.debug_tuple("TextEntered").field(text).finish(),
             Self::CheckboxChecked(b) => f.debug_tuple("CheckboxChecked").field(b).finish(),
+            Self::VideoPositionChanged(pos) => {
+                f.debug_tuple("VideoPositionChanged").field(pos).finish()
+            }
+            Self::ChartItemSelected(index) => {
+                f.debug_tuple("ChartItemSelected").field(index).finish()
+            }
+            Self::SuggestionAccepted(text) => {
+                f.debug_tuple("SuggestionAccepted").field(text).finish()
+            }
+            Self::ZoomChanged(zoom) => f.debug_tuple("ZoomChanged").field(zoom).finish(),
+            Self::RefreshRequested => write!(f, "RefreshRequested"),
+            Self::FormSubmitted(values) => f.debug_tuple("FormSubmitted").field(values).finish(),
+            Self::UiStateSaved(state) => f.debug_tuple("UiStateSaved").field(state).finish(),
+            Self::InvariantViolation(message) => {
+                f.debug_tuple("InvariantViolation").field(message).finish()
+            }
             Self::Other(_) => write!(f, "Other(...)"),
         }
     }
@@ -2,19 +2,51 @@
 // "as-is" basis without warranties of any kind. See the LICENSE file for
 // details.
 
+use std::cell::RefCell;
+#[cfg(feature = "remote_debug")]
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::rc::Rc;
+
 use druid_shell::{Application as AppHandle, Error as PlatformError};
+use tracing::warn;
 
 use crate::app_delegate::AppDelegate;
 use crate::app_root::AppRoot;
+use crate::app_store::{AnyAppStore, AppStore};
+use crate::backdrop::BackdropEffects;
+use crate::debug_switches;
 use crate::ext_event::{ExtEventQueue, ExtEventSink};
+use crate::hot_reload::{self, EnvFileOverrides};
+use crate::image_cache::ImageCache;
+use crate::jump_list::{JumpList, JumpListPublisher};
+use crate::open_request::{self, OpenSource};
 use crate::platform::{MasonryAppHandler, WindowDescription};
-use crate::Env;
+use crate::single_instance::{self, Instance, InstanceActivation};
+use crate::style::StyleSheet;
+use crate::telemetry::{SharedTelemetryHook, TelemetryHook};
+use crate::{set_invariant_policy, Env, InvariantPolicy};
 
 /// Handles initial setup of an application, and starts the runloop.
 pub struct AppLauncher {
     windows: Vec<WindowDescription>,
     app_delegate: Option<Box<dyn AppDelegate>>,
     ext_event_queue: ExtEventQueue,
+    style_sheet: StyleSheet,
+    app_store: Option<Rc<dyn AnyAppStore>>,
+    image_cache: ImageCache,
+    env_file: Option<PathBuf>,
+    style_sheet_file: Option<PathBuf>,
+    invariant_policy: Option<InvariantPolicy>,
+    low_power_mode: bool,
+    single_instance: Option<String>,
+    open_requests_from_args: bool,
+    value_options: Vec<String>,
+    jump_list: Option<(JumpList, Box<dyn JumpListPublisher>)>,
+    telemetry: Option<SharedTelemetryHook>,
+    backdrop_effects: Option<Rc<dyn BackdropEffects>>,
+    #[cfg(feature = "remote_debug")]
+    remote_debugger: Option<SocketAddr>,
 }
 
 impl AppLauncher {
@@ -24,6 +56,21 @@ impl AppLauncher {
             windows: vec![window],
             app_delegate: None,
             ext_event_queue: ExtEventQueue::new(),
+            style_sheet: StyleSheet::new(),
+            app_store: None,
+            image_cache: ImageCache::new(),
+            env_file: None,
+            style_sheet_file: None,
+            invariant_policy: None,
+            low_power_mode: false,
+            single_instance: None,
+            open_requests_from_args: false,
+            value_options: Vec::new(),
+            jump_list: None,
+            telemetry: None,
+            backdrop_effects: None,
+            #[cfg(feature = "remote_debug")]
+            remote_debugger: None,
         }
     }
 
@@ -35,6 +82,184 @@ impl AppLauncher {
         self
     }
 
+    /// Set the [`StyleSheet`] used to style widgets.
+    ///
+    /// It can be replaced later with the
+    /// [`SET_STYLE_SHEET`](crate::command::SET_STYLE_SHEET) command. To load it from
+    /// a file instead, see [`with_style_sheet_file`](Self::with_style_sheet_file).
+    pub fn with_style_sheet(mut self, style_sheet: StyleSheet) -> Self {
+        self.style_sheet = style_sheet;
+        self
+    }
+
+    /// Set the [`AppStore`] that widgets can subscribe to.
+    ///
+    /// Keep a clone of the store to update the state from outside the widget tree.
+    pub fn with_store<T: 'static>(mut self, store: AppStore<T>) -> Self {
+        self.app_store = Some(Rc::new(store));
+        self
+    }
+
+    /// Set the [`ImageCache`] shared by the app's widgets.
+    ///
+    /// Use this to change the cache's budgets, or to insert images before launch.
+    pub fn with_image_cache(mut self, image_cache: ImageCache) -> Self {
+        self.image_cache = image_cache;
+        self
+    }
+
+    /// Load [`Env`] values, eg theme colors and metrics, from a file.
+    ///
+    /// The file is a list of `key = value` lines, where keys are the raw names of
+    /// existing [`Key`](crate::Key)s:
+    ///
+    /// ```text
+    /// # Comments start with '#'
+    /// org.masonry.theme.window_background_color = #3a3a3a
+    /// org.masonry.theme.button_radius = 6.0
+    /// ```
+    ///
+    /// In debug builds, the file is watched for changes, and the new values are applied
+    /// to every window as soon as it's saved; removing a line restores the key's previous
+    /// value. Since style sheet values can be `Env` keys, this lets you iterate on the
+    /// look of an app without restarting it. To reload the style sheet as well, see
+    /// [`with_style_sheet_file`](Self::with_style_sheet_file).
+    pub fn with_env_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.env_file = Some(path.into());
+        self
+    }
+
+    /// Load the [`StyleSheet`] used to style widgets from a file, replacing the one
+    /// set with [`with_style_sheet`](Self::with_style_sheet).
+    ///
+    /// The file is a list of rules, written like CSS rules, with one property per line:
+    ///
+    /// ```text
+    /// # Comments start with '#'
+    /// * {
+    ///     padding = 4.0
+    /// }
+    ///
+    /// Button.primary:hover {
+    ///     background = #ff8000
+    ///     border = #000000, 2.0
+    ///     corner_radius = 6.0
+    /// }
+    /// ```
+    ///
+    /// A selector is an optional widget type, or `*`, followed by `.class` and `:state`
+    /// conditions, where the states are `hover`, `active`, `focus` and `disabled`. The
+    /// properties are those of [`StyleProperties`](crate::style::StyleProperties):
+    /// `background` is a color, `border` a color and a width, `corner_radius` and
+    /// `padding` one number, or four comma-separated numbers. Unlike in a style sheet
+    /// built in code, values can't be `Env` keys.
+    ///
+    /// In debug builds, the file is watched for changes, and the new style sheet is
+    /// applied to every window as soon as it's saved.
+    pub fn with_style_sheet_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.style_sheet_file = Some(path.into());
+        self
+    }
+
+    /// Start a debug server on `addr`, which an external inspector can connect to.
+    ///
+    /// The inspector can request snapshots of the widget tree and images of the main
+    /// window, and inject input events. See [`remote_debug`](crate::remote_debug)
+    /// for the protocol.
+    ///
+    /// The server accepts any connection, so `addr` should usually be a loopback
+    /// address, eg `127.0.0.1:9230`; forward the port to reach a remote device.
+    #[cfg(feature = "remote_debug")]
+    pub fn with_remote_debugger(mut self, addr: SocketAddr) -> Self {
+        self.remote_debugger = Some(addr);
+        self
+    }
+
+    /// Set what happens when Masonry detects a bug in widget code.
+    ///
+    /// See [`InvariantPolicy`] for details.
+    pub fn with_invariant_policy(mut self, policy: InvariantPolicy) -> Self {
+        self.invariant_policy = Some(policy);
+        self
+    }
+
+    /// Run the app in low-power mode, to save battery.
+    ///
+    /// Animation frames are capped to a lower rate, and widgets skip non-essential
+    /// animations. See [`Env::LOW_POWER_MODE`] for details.
+    pub fn with_low_power_mode(mut self, low_power_mode: bool) -> Self {
+        self.low_power_mode = low_power_mode;
+        self
+    }
+
+    /// Only run one instance of the app at a time.
+    ///
+    /// If an instance with the same `app_id` is already running, [`launch`](Self::launch)
+    /// forwards the command-line arguments to it and returns without opening a window.
+    /// The running instance then brings its main window to the front and submits
+    /// [`INSTANCE_ACTIVATED`](single_instance::INSTANCE_ACTIVATED).
+    ///
+    /// `app_id` should be unique to the app, eg a reverse domain name.
+    pub fn with_single_instance(mut self, app_id: impl Into<String>) -> Self {
+        self.single_instance = Some(app_id.into());
+        self
+    }
+
+    /// Submit the files and URLs passed as command-line arguments as an
+    /// [`OPEN_REQUEST`](crate::open_request::OPEN_REQUEST) command.
+    ///
+    /// This includes the arguments the app is launched with, and the arguments of
+    /// other instances if [`with_single_instance`](Self::with_single_instance) is set.
+    /// Arguments starting with `-` are ignored, and so are the values of the options
+    /// set with [`with_value_options`](Self::with_value_options).
+    pub fn with_open_requests_from_args(mut self, enabled: bool) -> Self {
+        self.open_requests_from_args = enabled;
+        self
+    }
+
+    /// Set the command-line options which take a value as the next argument, eg
+    /// `--config`, so that the value isn't opened as a file.
+    ///
+    /// See [`with_open_requests_from_args`](Self::with_open_requests_from_args).
+    pub fn with_value_options(
+        mut self,
+        options: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.value_options = options.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Keep track of the app's recent documents and tasks, and pass them to
+    /// `publisher`, which shows them in the OS.
+    ///
+    /// `jump_list` is the initial list, eg restored from the previous launch. It's
+    /// published when the app starts, then every time it changes. Masonry doesn't
+    /// provide a publisher; see [`jump_list`](crate::jump_list) for details.
+    pub fn with_jump_list(
+        mut self,
+        jump_list: JumpList,
+        publisher: impl JumpListPublisher + 'static,
+    ) -> Self {
+        self.jump_list = Some((jump_list, Box::new(publisher)));
+        self
+    }
+
+    /// Install a hook observing the actions and events of every window.
+    ///
+    /// See [`telemetry`](crate::telemetry) for details.
+    pub fn with_telemetry(mut self, hook: impl TelemetryHook + 'static) -> Self {
+        self.telemetry = Some(Rc::new(RefCell::new(hook)));
+        self
+    }
+
+    /// Apply window backdrops, eg blur-behind, with the platform's API.
+    ///
+    /// See [`backdrop`](crate::backdrop) for details.
+    pub fn with_backdrop_effects(mut self, effects: impl BackdropEffects + 'static) -> Self {
+        self.backdrop_effects = Some(Rc::new(effects));
+        self
+    }
+
     /// Initialize a minimal tracing subscriber with DEBUG max level for printing logs out to
     /// stderr.
     ///
@@ -82,13 +307,90 @@ impl AppLauncher {
     /// Returns an error if a window cannot be instantiated. This is usually
     /// a fatal error.
     pub fn launch(self) -> Result<(), PlatformError> {
+        let mut instance_listener = None;
+        // Removes the port file when the app exits.
+        let mut _port_file = None;
+        if let Some(app_id) = &self.single_instance {
+            match single_instance::acquire(app_id, &InstanceActivation::current()) {
+                Some(Instance::Secondary) => return Ok(()),
+                Some(Instance::Primary(listener, port_file)) => {
+                    instance_listener = Some(listener);
+                    _port_file = Some(port_file);
+                }
+                None => (),
+            }
+        }
+
         let app = AppHandle::new()?;
+        if let Some(policy) = self.invariant_policy {
+            set_invariant_policy(policy);
+        }
+
+        let mut env = Env::with_theme();
+        env.set(Env::LOW_POWER_MODE, self.low_power_mode);
+        let mut env_file_overrides = EnvFileOverrides::default();
+        if let Some(path) = self.env_file {
+            match std::fs::read_to_string(&path) {
+                Ok(contents) => env = env_file_overrides.apply(&env, &contents),
+                Err(err) => warn!("Can't read env file {}: {}", path.display(), err),
+            }
+            if cfg!(debug_assertions) {
+                hot_reload::watch_env_file(path, self.ext_event_queue.make_sink());
+            }
+        }
+        let mut style_sheet = self.style_sheet;
+        if let Some(path) = self.style_sheet_file {
+            match std::fs::read_to_string(&path) {
+                Ok(contents) => style_sheet = hot_reload::parse_style_sheet(&contents),
+                Err(err) => warn!("Can't read style sheet {}: {}", path.display(), err),
+            }
+            if cfg!(debug_assertions) {
+                hot_reload::watch_style_sheet_file(path, self.ext_event_queue.make_sink());
+            }
+        }
+        {
+            // Delivered once the first window is connected.
+            let activation = InstanceActivation::current();
+            let _ = open_request::submit_args(
+                &activation.args,
+                activation.cwd.as_deref(),
+                OpenSource::Launch,
+                self.open_requests_from_args,
+                &self.value_options,
+                &self.ext_event_queue.make_sink(),
+            );
+        }
+        if let Some(listener) = instance_listener {
+            single_instance::start_listening(
+                listener,
+                self.ext_event_queue.make_sink(),
+                self.open_requests_from_args,
+                self.value_options.clone(),
+            );
+        }
+        #[cfg(feature = "gamepad")]
+        crate::gamepad::start_polling(self.ext_event_queue.make_sink());
+        #[cfg(feature = "remote_debug")]
+        if let Some(addr) = self.remote_debugger {
+            crate::remote_debug::start_server(addr, self.ext_event_queue.make_sink());
+        }
+        if cfg!(debug_assertions) {
+            env = debug_switches::init_from_env_var(env, self.ext_event_queue.make_sink());
+        }
+
         let state = AppRoot::create(
             app.clone(),
             self.windows,
             self.app_delegate,
             self.ext_event_queue,
-            Env::with_theme(),
+            style_sheet,
+            self.app_store,
+            self.image_cache,
+            self.jump_list,
+            self.telemetry,
+            self.backdrop_effects,
+            env,
+            env_file_overrides,
         )?;
         let handler = MasonryAppHandler::new(state);
 
@@ -9,45 +9,89 @@
 #![allow(dead_code)]
 
 use std::cell::{RefCell, RefMut};
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::ops::DerefMut;
 use std::rc::Rc;
+use std::time::Duration;
 
 use druid_shell::text::InputHandler;
 // TODO - rename Application to AppHandle in glazier
 // See https://github.com/linebender/glazier/issues/44
 use druid_shell::{Application as AppHandle, WindowHandle};
 use druid_shell::{
-    Cursor, FileDialogToken, FileInfo, Region, TextFieldToken, TimerToken, WindowBuilder,
+    Code, Cursor, FileDialogToken, FileInfo, KbKey, KeyEvent, KeyState, Modifiers, MouseButton,
+    Region, Scale, TextFieldToken, TimerToken, WindowBuilder, WindowState,
 };
 // Automatically defaults to std::time::Instant on non Wasm platforms
 use instant::Instant;
-use tracing::{error, info, info_span};
+use tracing::{error, info, info_span, trace, warn};
 
-use crate::action::ActionQueue;
+use crate::access_keys::{self, AccessKeys};
+use crate::action::{Action, ActionQueue};
 use crate::app_delegate::{AppDelegate, DelegateCtx, NullDelegate};
-use crate::command::CommandQueue;
-use crate::contexts::GlobalPassCtx;
+use crate::app_store::AnyAppStore;
+use crate::backdrop::{self, BackdropEffects, WindowBackdrop};
+use crate::broadcast::BroadcastListeners;
+use crate::command::{CaptureWidget, CommandQueue, PrintPages};
+use crate::context_menu::ContextMenu;
+use crate::contexts::{GlobalPassCtx, MutateCallback};
+use crate::damage::{self, PresentStats};
 use crate::debug_logger::DebugLogger;
 use crate::ext_event::{ExtEventQueue, ExtEventSink, ExtMessage};
-use crate::kurbo::{Point, Size};
-use crate::piet::{Color, Piet, RenderContext};
+use crate::frame_stats::{FrameStats, PassTimings};
+use crate::gamepad::{self, GamepadEvent, GamepadNavigation, NavDirection, Navigation};
+use crate::headless;
+use crate::hot_reload::{self, EnvFileOverrides};
+use crate::image_cache::{ImageCache, IMAGE_DECODED};
+use crate::jump_list::{self, JumpList, JumpListPublisher};
+use crate::kurbo::{Insets, Point, Rect, RoundedRect, RoundedRectRadii, Size, Vec2};
+use crate::pass::{Pass, PassTracker};
+use crate::piet::{Color, ImageBuf, Piet, RenderContext};
 use crate::platform::{
     DialogInfo, WindowConfig, WindowSizePolicy, EXT_EVENT_IDLE_TOKEN, RUN_COMMANDS_TOKEN,
 };
+use crate::popup::{self, PopupAnchor, PopupUpdate};
+use crate::print::{PageSetup, PrintJob};
+#[cfg(feature = "remote_debug")]
+use crate::remote_debug::{self, RemoteButton, RemoteInput, RemoteRequest};
+use crate::render_backend::{self, OffscreenDevices, RenderBackend, SoftwareRenderer};
+use crate::request::Request;
+use crate::screen;
+use crate::shortcuts::{ChordMatch, KeyStroke, Shortcuts, PENDING_CHORD_CHANGED};
+use crate::single_instance;
+use crate::sound::{SoundId, SoundPlayer};
+use crate::style::StyleSheet;
+use crate::telemetry::{Observed, SharedTelemetryHook, TelemetryRecord};
 use crate::testing::MockTimerQueue;
-use crate::text::TextFieldRegistration;
-use crate::widget::{FocusChange, StoreInWidgetMut, WidgetMut, WidgetRef, WidgetState};
+use crate::text::{TextFieldRegistration, TextMetricsCache};
+use crate::theme;
+use crate::time_travel::FrameHistory;
+use crate::ui_state::{collect_widget_states, UiState, WindowGeometry};
+use crate::util;
+use crate::widget::{
+    titlebar_zone_at, FocusChange, StoreInWidgetMut, TitlebarZone, WidgetMut, WidgetRef,
+    WidgetState, TITLEBAR_ZONE_HOVERED,
+};
 use crate::{
-    command as sys_cmd, ArcStr, BoxConstraints, Command, Env, Event, EventCtx, Handled,
-    InternalEvent, InternalLifeCycle, LayoutCtx, LifeCycle, LifeCycleCtx, MasonryWinHandler,
-    PaintCtx, PlatformError, Target, Widget, WidgetCtx, WidgetId, WidgetPod, WindowDescription,
-    WindowId,
+    command as sys_cmd, ArcStr, BoxConstraints, Command, Data, Env, Event, EventCtx, Handled,
+    InternalEvent, InternalLifeCycle, KeyRepeat, LayoutCtx, LifeCycle, LifeCycleCtx,
+    MasonryWinHandler, PaintCtx, PlatformError, Target, TimerEvent, Widget, WidgetCapture,
+    WidgetCtx, WidgetId, WidgetPod, WindowDescription, WindowId,
 };
 
 /// The type of a function that will be called once an IME field is updated.
 pub type ImeUpdateFn = dyn FnOnce(druid_shell::text::Event);
 
+/// The shortest time between animation frames in low-power mode, ie 30 frames per second.
+const LOW_POWER_FRAME_INTERVAL: Duration = Duration::from_nanos(1_000_000_000 / 30);
+
+/// The range of a window's UI scale.
+const MIN_UI_SCALE: f64 = 0.5;
+const MAX_UI_SCALE: f64 = 3.0;
+/// The number of zoom shortcut presses between a UI scale of 0 and 1, ie each
+/// press changes the scale by 10%.
+const UI_SCALE_STEPS: f64 = 10.0;
+
 // TODO - Add AppRootEvent type
 
 // TODO - Explain and document re-entrancy and when locks should be used - See issue #16
@@ -80,16 +124,37 @@ struct AppRootInner {
     /// is the window that's currently in charge of the app menu.
     #[allow(unused)]
     menu_window: Option<WindowId>,
+    sound_player: SoundPlayer,
+    style_sheet: Rc<StyleSheet>,
+    app_store: Option<Rc<dyn AnyAppStore>>,
+    image_cache: ImageCache,
+    /// The recent documents and tasks, and how to show them in the OS.
+    jump_list: Option<(JumpList, Box<dyn JumpListPublisher>)>,
+    telemetry: Option<SharedTelemetryHook>,
+    backdrop_effects: Option<Rc<dyn BackdropEffects>>,
     env: Env,
+    /// The values the env file replaced; see `AppLauncher::with_env_file`.
+    env_file_overrides: EnvFileOverrides,
+    /// The platform's high-contrast setting when it was last read.
+    platform_high_contrast: Option<bool>,
+    /// The platform's "reduce motion" setting when it was last read.
+    platform_reduced_motion: Option<bool>,
 }
 
 /// The parts of a window, pending construction, that are dependent on top level app state
 /// or are not part of druid-shell's windowing abstraction.
 struct PendingWindow {
-    root: Box<dyn Widget>,
+    root: WidgetPod<Box<dyn Widget>>,
     title: ArcStr,
     transparent: bool,
+    is_surface_transparent: bool,
+    backdrop: WindowBackdrop,
     size_policy: WindowSizePolicy,
+    render_backend: RenderBackend,
+    command_broadcast_opt_in: bool,
+    integer_layout: bool,
+    shortcuts: Shortcuts,
+    popup_anchor: Option<PopupAnchor>,
 }
 
 // TODO - refactor out again
@@ -101,6 +166,8 @@ pub struct WindowRoot {
     pub(crate) root: WidgetPod<Box<dyn Widget>>,
     pub(crate) title: ArcStr,
     size_policy: WindowSizePolicy,
+    pub(crate) render_backend: RenderBackend,
+    software_renderer: SoftwareRenderer,
     size: Size,
     invalid: Region,
     // Is `Some` if the most recently displayed frame was an animation frame.
@@ -109,12 +176,127 @@ pub struct WindowRoot {
     pub(crate) focus: Option<WidgetId>,
     pub(crate) ext_event_sink: ExtEventSink,
     pub(crate) handle: WindowHandle,
-    pub(crate) timers: HashMap<TimerToken, WidgetId>,
+    // The widget which requested each timer, and the timer's deadline.
+    pub(crate) timers: HashMap<TimerToken, (WidgetId, Instant)>,
+    pub(crate) broadcast_listeners: BroadcastListeners,
+    pub(crate) access_keys: AccessKeys,
+    /// Whether commands sent to the window only reach registered listeners.
+    pub(crate) command_broadcast_opt_in: bool,
+    /// Whether layout is computed in physical pixels.
+    integer_layout: bool,
+    pub(crate) shortcuts: Shortcuts,
+    pending_chord: PendingChord,
+    // The context menu shown most recently, whose items the platform reports by index.
+    context_menu: Option<ContextMenu>,
     // Used in unit tests - see `src/testing/mock_timer_queue.rs`
     pub(crate) mock_timer_queue: Option<MockTimerQueue>,
     pub(crate) transparent: bool,
     pub(crate) ime_handlers: Vec<(TextFieldToken, TextFieldRegistration)>,
     pub(crate) ime_focus_change: Option<Option<TextFieldToken>>,
+    pub(crate) style_sheet: Rc<StyleSheet>,
+    /// The app's store, shared by every window.
+    pub(crate) app_store: Option<Rc<dyn AnyAppStore>>,
+    /// The app's image cache, shared by every window.
+    pub(crate) image_cache: ImageCache,
+    /// The font metrics measured by widgets; see `LayoutCtx::font_metrics`.
+    pub(crate) text_metrics: TextMetricsCache,
+    pub(crate) offscreen_devices: OffscreenDevices,
+    /// The app's telemetry hook, shared by every window.
+    pub(crate) telemetry: Option<SharedTelemetryHook>,
+    /// Whether the platform window is transparent, which backdrops need.
+    is_surface_transparent: bool,
+    backdrop: WindowBackdrop,
+    /// The app's backdrop effects, shared by every window.
+    backdrop_effects: Option<Rc<dyn BackdropEffects>>,
+    /// The regions last passed to the backdrop effects, and whether they were applied.
+    ///
+    /// They're collected whenever the widgets' window origins are updated.
+    backdrop_regions: Option<(Vec<Rect>, bool)>,
+    /// The zone of the custom titlebar under the pointer.
+    hovered_titlebar_zone: Option<TitlebarZone>,
+    /// The widget this window is a popup of; see [`crate::popup`].
+    popup_anchor: Option<PopupAnchor>,
+    /// The window origins of the widgets of this window which anchor popups, or
+    /// `None` for those which are gone or stashed.
+    ///
+    /// They're updated whenever the widgets' window origins are.
+    popup_anchor_origins: HashMap<WidgetId, Option<Point>>,
+    pub(crate) is_window_focused: bool,
+    /// Whether the window is shown; `false` eg while it's minimized.
+    pub(crate) is_window_visible: bool,
+    /// Overrides the app's `Env::LOW_POWER_MODE` for this window.
+    low_power_override: Option<bool>,
+    /// Requests the next animation frame, when frames are capped in low-power mode.
+    low_power_frame_timer: Option<TimerToken>,
+    /// The timers which fired while the window was hidden, delivered once it's shown again.
+    deferred_timers: Vec<TimerEvent>,
+    /// The widget timers pending when the window was hidden; those requested since
+    /// are coalesced.
+    timers_before_hidden: HashSet<TimerToken>,
+    /// The platform timers requested when the window was shown, to fire coalesced
+    /// timers at their deadline, with the widget timer each stands for.
+    rearmed_timers: HashMap<TimerToken, TimerToken>,
+    /// The coalesced platform timers replaced when the window was shown, which are
+    /// ignored when they fire.
+    superseded_timers: HashSet<TimerToken>,
+    caret_blink: CaretBlinkState,
+    key_repeat: KeyRepeatState,
+    last_click: Option<LastClick>,
+    gamepad_nav: GamepadNavigation,
+    pub(crate) mutate_callbacks: Vec<MutateCallback>,
+    is_running_mutate_callbacks: bool,
+    /// Number of frames painted with paint flashing enabled, used to pick the tint.
+    paint_flash_count: u64,
+    /// The insets of the content area obscured by the platform.
+    pub(crate) safe_area: Insets,
+    present_stats: PresentStats,
+    pub(crate) frame_stats: FrameStats,
+    /// The timings of the frame being prepared, recorded once it's painted.
+    frame_timings: PassTimings,
+    /// The zoom factor the user chose for the window; see `Env::UI_SCALE`.
+    pub(crate) ui_scale: f64,
+    /// The app's env, and the env passed to the widgets; see `widget_env`.
+    widget_env_cache: Option<(Env, Env)>,
+    /// The last painted frames, kept while `DebugSwitch::FrameHistory` is enabled.
+    pub(crate) frame_history: FrameHistory,
+    /// The pass currently running; see [`crate::pass`] for the order passes run in.
+    passes: PassTracker,
+}
+
+/// The strokes typed so far of a multi-stroke shortcut.
+#[derive(Debug, Default)]
+struct PendingChord {
+    strokes: Vec<KeyStroke>,
+    /// When the chord is cancelled if no stroke continues it.
+    expires_at: Option<Instant>,
+    timer: Option<TimerToken>,
+}
+
+/// The key held down while the focused widget uses [`KeyRepeat::Custom`].
+#[derive(Debug, Default)]
+struct KeyRepeatState {
+    held: Option<KeyEvent>,
+    /// When the held key is next repeated.
+    next_repeat: Option<Instant>,
+    timer: Option<TimerToken>,
+}
+
+/// The last mouse-down, to count the clicks of a double- or triple-click.
+#[derive(Debug)]
+struct LastClick {
+    time: Instant,
+    pos: Point,
+    button: MouseButton,
+    count: u8,
+}
+
+/// The blink cycle of the focused widget's text caret.
+#[derive(Debug, Default)]
+struct CaretBlinkState {
+    timer: Option<TimerToken>,
+    is_visible: bool,
+    /// The caret was reset since the timer was requested; keep it visible for another cycle.
+    skip_next_toggle: bool,
 }
 
 // ---
@@ -130,8 +312,18 @@ impl AppRoot {
         windows: Vec<WindowDescription>,
         app_delegate: Option<Box<dyn AppDelegate>>,
         ext_event_queue: ExtEventQueue,
+        style_sheet: StyleSheet,
+        app_store: Option<Rc<dyn AnyAppStore>>,
+        image_cache: ImageCache,
+        mut jump_list: Option<(JumpList, Box<dyn JumpListPublisher>)>,
+        telemetry: Option<SharedTelemetryHook>,
+        backdrop_effects: Option<Rc<dyn BackdropEffects>>,
         env: Env,
+        env_file_overrides: EnvFileOverrides,
     ) -> Result<Self, PlatformError> {
+        if let Some((jump_list, publisher)) = &mut jump_list {
+            publisher.publish(jump_list);
+        }
         let inner = Rc::new(RefCell::new(AppRootInner {
             app_handle: app,
             debug_logger: DebugLogger::new(false),
@@ -143,7 +335,17 @@ impl AppRoot {
             // FIXME - this is awful
             main_window_id: windows.first().unwrap().id,
             menu_window: None,
+            sound_player: SoundPlayer::new(),
+            style_sheet: Rc::new(style_sheet),
+            app_store,
+            image_cache,
+            jump_list,
+            telemetry,
+            backdrop_effects,
             env,
+            env_file_overrides,
+            platform_high_contrast: None,
+            platform_reduced_motion: None,
             window_requests: VecDeque::new(),
             pending_windows: Default::default(),
             active_windows: Default::default(),
@@ -168,7 +370,7 @@ impl AppRoot {
             let inner = inner.deref_mut();
 
             if let Some(pending) = inner.pending_windows.remove(&window_id) {
-                let win = WindowRoot::new(
+                let mut win = WindowRoot::new(
                     window_id,
                     handle,
                     inner.ext_event_queue.make_sink(),
@@ -176,8 +378,25 @@ impl AppRoot {
                     pending.title,
                     pending.transparent,
                     pending.size_policy,
+                    pending.render_backend,
                     None,
+                    inner.style_sheet.clone(),
                 );
+                win.command_broadcast_opt_in = pending.command_broadcast_opt_in;
+                win.integer_layout = pending.integer_layout;
+                win.shortcuts = pending.shortcuts;
+                win.app_store = inner.app_store.clone();
+                win.image_cache = inner.image_cache.clone();
+                win.telemetry = inner.telemetry.clone();
+                win.is_surface_transparent = pending.is_surface_transparent;
+                win.backdrop = pending.backdrop;
+                win.backdrop_effects = inner.backdrop_effects.clone();
+                if let Some(anchor) = &pending.popup_anchor {
+                    if let Some(parent) = inner.active_windows.get_mut(&anchor.window) {
+                        parent.track_popup_anchor(anchor.widget);
+                    }
+                }
+                win.popup_anchor = pending.popup_anchor;
                 let existing = inner.active_windows.insert(window_id, win);
                 debug_assert!(existing.is_none(), "duplicate window");
             } else {
@@ -192,6 +411,7 @@ impl AppRoot {
         }
 
         self.with_delegate(|delegate, ctx, env| delegate.on_window_added(ctx, window_id, env));
+        self.read_platform_settings();
 
         let event = Event::WindowConnected;
         self.do_window_event(window_id, event);
@@ -210,6 +430,8 @@ impl AppRoot {
 
         let mut inner = self.inner.borrow_mut();
         inner.active_windows.remove(&window_id);
+        // Close the popups of the window.
+        inner.update_popups();
 
         // If there are no active or pending windows, we quit the run loop.
         if inner.active_windows.is_empty() && inner.pending_windows.is_empty() {
@@ -231,8 +453,264 @@ impl AppRoot {
     }
 
     /// Notify the app that a window has acquired focus (eg the user clicked on it).
-    pub fn window_got_focus(&mut self, _window_id: WindowId) {
+    pub fn window_got_focus(&mut self, window_id: WindowId) {
         // TODO - menu stuff
+        self.set_window_focused(window_id, true);
+    }
+
+    /// Notify the app that a window has lost focus.
+    pub fn window_lost_focus(&mut self, window_id: WindowId) {
+        self.set_window_focused(window_id, false);
+    }
+
+    /// Notify the app that it was moved to the background.
+    ///
+    /// This sends [`Event::Suspend`] to every window. druid-shell doesn't report app
+    /// lifecycle changes, so Masonry never calls this on its own; it's meant to be
+    /// called by platform integrations on mobile targets.
+    pub fn app_suspended(&mut self) {
+        self.broadcast_event(Event::Suspend);
+    }
+
+    /// Notify the app that it was moved back to the foreground.
+    ///
+    /// This sends [`Event::Resume`] to every window.
+    pub fn app_resumed(&mut self) {
+        self.broadcast_event(Event::Resume);
+    }
+
+    /// Notify the app that the platform is running low on memory.
+    ///
+    /// This purges the [`ImageCache`], and sends [`Event::MemoryWarning`] to every window;
+    /// widget pods release their paint caches when they receive it. druid-shell doesn't
+    /// report memory pressure, so this is only called by platform integrations, or by
+    /// the app itself.
+    pub fn memory_warning(&mut self) {
+        self.inner().image_cache.purge();
+        self.broadcast_event(Event::MemoryWarning);
+    }
+
+    fn broadcast_event(&mut self, event: Event) {
+        let window_ids: Vec<_> = self.inner().active_windows.keys().copied().collect();
+        for window_id in window_ids {
+            self.do_window_event(window_id, event.clone());
+        }
+
+        self.process_commands_and_actions();
+        self.inner().invalidate_paint_regions();
+        self.process_ime_changes();
+        self.process_window_requests();
+    }
+
+    /// Notify the app that part of a window's content area is obscured, eg by a
+    /// notch, an auto-hiding taskbar, or an on-screen keyboard.
+    ///
+    /// `safe_area` is the obscured part, as insets from the edges of the content area.
+    /// Widgets can read it with [`LifeCycleCtx::safe_area`] and are notified with
+    /// [`LifeCycle::SafeAreaChanged`].
+    ///
+    /// druid-shell doesn't report obscured regions, so Masonry never calls this on
+    /// its own: the safe area stays empty unless a platform integration sets it.
+    pub fn set_safe_area(&mut self, window_id: WindowId, safe_area: Insets) {
+        {
+            let mut inner = self.inner.borrow_mut();
+            let inner = inner.deref_mut();
+            if let Some(window) = inner.active_windows.get_mut(&window_id) {
+                window.set_safe_area(
+                    safe_area,
+                    &mut inner.debug_logger,
+                    &mut inner.command_queue,
+                    &mut inner.action_queue,
+                    &inner.env,
+                );
+            }
+        }
+
+        self.process_commands_and_actions();
+        self.inner().invalidate_paint_regions();
+        self.process_ime_changes();
+        self.process_window_requests();
+    }
+
+    /// The part of the custom titlebar under `pos`, in the window's content area.
+    ///
+    /// This is for platform integrations which hit-test undecorated windows
+    /// themselves, eg to report the maximize button to Windows 11 so it shows its
+    /// snap layouts. See [`TitlebarRegion`](crate::widget::TitlebarRegion).
+    pub fn titlebar_zone_at(&self, window_id: WindowId, pos: Point) -> Option<TitlebarZone> {
+        let inner = self.inner.borrow();
+        let window = inner.active_windows.get(&window_id)?;
+        titlebar_zone_at(window.root.as_dyn(), pos)
+    }
+
+    /// Hit-test the custom titlebar of a window under the pointer, at `pos`, and
+    /// submit [`TITLEBAR_ZONE_HOVERED`] if the zone under it changed.
+    ///
+    /// The command is processed with the pointer event which follows.
+    pub(crate) fn update_hovered_titlebar_zone(&mut self, window_id: WindowId, pos: Option<Point>) {
+        let zone = pos.and_then(|pos| self.titlebar_zone_at(window_id, pos));
+        let mut inner = self.inner.borrow_mut();
+        let inner = inner.deref_mut();
+        let Some(window) = inner.active_windows.get_mut(&window_id) else {
+            return;
+        };
+        if zone != window.hovered_titlebar_zone {
+            window.hovered_titlebar_zone = zone;
+            inner.command_queue.push_back(
+                TITLEBAR_ZONE_HOVERED
+                    .with(zone)
+                    .to(Target::Window(window_id)),
+            );
+        }
+    }
+
+    /// Hide a window if the platform reports it as minimized, or if its content area
+    /// is empty, and show it otherwise.
+    ///
+    /// `size` is the window's new size, if it changed.
+    pub(crate) fn update_window_visibility(&mut self, window_id: WindowId, size: Option<Size>) {
+        let is_visible = {
+            let inner = self.inner.borrow();
+            let Some(window) = inner.active_windows.get(&window_id) else {
+                return;
+            };
+            let size = size.unwrap_or(window.size);
+            window.handle.get_window_state() != WindowState::Minimized && size.area() > 0.0
+        };
+        self.set_window_visible(window_id, is_visible);
+    }
+
+    /// Notify the app that a window was hidden or shown, eg minimized and restored.
+    ///
+    /// While a window is hidden, its animations are paused and the timers which fire
+    /// are held back until it's shown again. Widgets can check
+    /// [`EventCtx::is_window_visible`].
+    ///
+    /// Masonry hides the windows the platform reports as minimized, or which are
+    /// resized to zero. It doesn't detect windows fully covered by other windows,
+    /// since druid-shell doesn't report occlusion: platform integrations which can
+    /// detect it should call this.
+    pub fn set_window_visible(&mut self, window_id: WindowId, is_visible: bool) {
+        {
+            let mut inner = self.inner.borrow_mut();
+            let inner = inner.deref_mut();
+            if let Some(window) = inner.active_windows.get_mut(&window_id) {
+                window.set_window_visible(
+                    is_visible,
+                    &mut inner.debug_logger,
+                    &mut inner.command_queue,
+                    &mut inner.action_queue,
+                    &inner.env,
+                );
+            }
+        }
+
+        self.process_commands_and_actions();
+        self.inner().invalidate_paint_regions();
+        self.process_ime_changes();
+        self.process_window_requests();
+    }
+
+    /// Set whether widgets skip non-essential animations, as if the user asked the
+    /// platform to reduce motion.
+    ///
+    /// This sets [`Env::PREFERS_REDUCED_MOTION`], which widgets read through
+    /// [`EventCtx::reduced_motion`].
+    ///
+    /// Masonry reads the platform's "reduce motion" setting when its first window is
+    /// connected, and calls this when it changes, as for
+    /// [`set_high_contrast`](Self::set_high_contrast): on Windows it's the client
+    /// area animations setting, on GTK `gtk-enable-animations`.
+    pub fn set_prefers_reduced_motion(&mut self, prefers_reduced_motion: bool) {
+        {
+            let mut inner = self.inner();
+            if inner.env.get(Env::PREFERS_REDUCED_MOTION) == prefers_reduced_motion {
+                return;
+            }
+            let env = inner
+                .env
+                .clone()
+                .adding(Env::PREFERS_REDUCED_MOTION, prefers_reduced_motion);
+            inner.set_env(env);
+        }
+
+        self.process_commands_and_actions();
+        self.inner().invalidate_paint_regions();
+        self.process_ime_changes();
+        self.process_window_requests();
+    }
+
+    /// Set whether the app uses high-contrast colors.
+    ///
+    /// This sets [`Env::HIGH_CONTRAST`], which replaces the colors of the
+    /// [`theme`](crate::theme) with a high-contrast palette.
+    ///
+    /// Masonry reads the platform's high-contrast setting on Windows, macOS and GTK,
+    /// and calls this when it changes. druid-shell doesn't notify settings changes,
+    /// so it's read when a window is connected and whenever a window gains focus, eg
+    /// when the user comes back from the platform settings. Elsewhere, it's only
+    /// applied when a platform integration, or the app itself, calls this. A value set
+    /// by the app is kept until the platform setting changes.
+    pub fn set_high_contrast(&mut self, high_contrast: bool) {
+        {
+            let mut inner = self.inner();
+            if inner.env.get(Env::HIGH_CONTRAST) == high_contrast {
+                return;
+            }
+            let env = inner.env.clone().adding(Env::HIGH_CONTRAST, high_contrast);
+            inner.set_env(env);
+        }
+
+        self.process_commands_and_actions();
+        self.inner().invalidate_paint_regions();
+        self.process_ime_changes();
+        self.process_window_requests();
+    }
+
+    /// Apply the accessibility settings of the platform which changed since they
+    /// were last read.
+    ///
+    /// druid-shell doesn't notify settings changes, so this is called whenever a
+    /// window gains focus.
+    fn read_platform_settings(&mut self) {
+        fn changed(last: &mut Option<bool>, current: Option<bool>) -> Option<bool> {
+            let last = std::mem::replace(last, current);
+            current.filter(|current| last != Some(*current))
+        }
+
+        let high_contrast = crate::platform::settings::high_contrast();
+        let reduced_motion = crate::platform::settings::prefers_reduced_motion();
+        let (high_contrast, reduced_motion) = {
+            let mut inner = self.inner();
+            (
+                changed(&mut inner.platform_high_contrast, high_contrast),
+                changed(&mut inner.platform_reduced_motion, reduced_motion),
+            )
+        };
+        if let Some(high_contrast) = high_contrast {
+            self.set_high_contrast(high_contrast);
+        }
+        if let Some(reduced_motion) = reduced_motion {
+            self.set_prefers_reduced_motion(reduced_motion);
+        }
+    }
+
+    fn set_window_focused(&mut self, window_id: WindowId, is_focused: bool) {
+        if is_focused {
+            self.read_platform_settings();
+        }
+        let caret_event = {
+            let mut inner = self.inner.borrow_mut();
+            let inner = inner.deref_mut();
+            let env = &inner.env;
+            inner
+                .active_windows
+                .get_mut(&window_id)
+                .and_then(|window| window.set_window_focused(is_focused, env))
+        };
+        if let Some(event) = caret_event {
+            self.handle_event(event, window_id);
+        }
     }
 
     /// Send an event to the widget hierarchy.
@@ -269,8 +747,22 @@ impl AppRoot {
     /// the `window_id` will be `Some(_)`, otherwise (such as if no window
     /// is open but a menu exists, as on macOS) it will be `None`.
     pub fn handle_system_cmd(&mut self, cmd_id: u32, window_id: Option<WindowId>) {
-        #![allow(unused_variables)]
-        todo!();
+        // Masonry doesn't create app or window menus, so the only menu items are
+        // those of context menus.
+        let cmd = window_id.and_then(|id| {
+            self.inner()
+                .active_windows
+                .get_mut(&id)
+                .and_then(|window| window.take_context_menu_command(cmd_id))
+        });
+        if let Some(cmd) = cmd {
+            self.do_cmd(cmd);
+            self.process_commands_and_actions();
+            self.process_ime_changes();
+            self.inner().invalidate_paint_regions();
+        } else {
+            warn!("unknown menu item {}", cmd_id);
+        }
     }
 
     // TODO - Promises
@@ -282,6 +774,9 @@ impl AppRoot {
         let dialog_info = self.inner().file_dialogs.remove(&token);
         if let Some(dialog_info) = dialog_info {
             let cmd = if let Some(info) = file_info {
+                let path = info.path().to_path_buf();
+                self.inner()
+                    .update_jump_list(|jump_list| jump_list.add_recent(path));
                 dialog_info.accept_cmd.with(info).to(dialog_info.id)
             } else {
                 dialog_info.cancel_cmd.to(dialog_info.id)
@@ -417,6 +912,17 @@ impl AppRoot {
                 continue;
             }
 
+            let changed_widgets = self.inner().take_store_changed_widgets();
+            if !changed_widgets.is_empty() {
+                let mut inner = self.inner();
+                for id in changed_widgets {
+                    inner
+                        .command_queue
+                        .push_back(sys_cmd::STORE_CHANGED.to(Target::Widget(id)));
+                }
+                continue;
+            }
+
             // else - no more commands or actions
             break;
         }
@@ -463,6 +969,119 @@ impl AppRoot {
         }
     }
 
+    /// Handle a message from the remote inspector, in the main window.
+    #[cfg(feature = "remote_debug")]
+    fn remote_input(&mut self, request: &RemoteRequest) {
+        use druid_shell::text::Selection;
+        use druid_shell::{Modifiers, MouseButtons, RawMods};
+
+        let window_id = self.inner().main_window_id;
+        let mouse_event =
+            |x: f64, y: f64, button: MouseButton, buttons: MouseButtons| crate::MouseEvent {
+                pos: Point::new(x, y),
+                window_pos: Point::new(x, y),
+                buttons,
+                mods: Modifiers::default(),
+                count: 1,
+                focus: false,
+                button,
+                wheel_delta: crate::Vec2::ZERO,
+            };
+        let mouse_button = |button: RemoteButton| match button {
+            RemoteButton::Left => MouseButton::Left,
+            RemoteButton::Right => MouseButton::Right,
+            RemoteButton::Middle => MouseButton::Middle,
+        };
+
+        match &request.input {
+            RemoteInput::Snapshot => {
+                let mut inner = self.inner();
+                let inner = inner.deref_mut();
+                let Some(window) = inner.active_windows.get_mut(&window_id) else {
+                    return;
+                };
+                if let Some(frame) = window.frame_history.scrubbed_frame() {
+                    let scene = frame.scene.clone();
+                    let _ = request
+                        .reply
+                        .send(remote_debug::encode_snapshot(scene, Some(&frame.image)));
+                    return;
+                }
+                let frame = window.capture_widget(
+                    window.root.id(),
+                    1.0,
+                    &mut inner.debug_logger,
+                    &mut inner.command_queue,
+                    &mut inner.action_queue,
+                    &inner.env,
+                );
+                let scene = headless::scene_node(window.root.as_dyn(), &inner.env);
+                // The inspector may have disconnected since.
+                let _ = request
+                    .reply
+                    .send(remote_debug::encode_snapshot(scene, frame.as_ref()));
+            }
+            RemoteInput::MouseMove { x, y } => {
+                let event = mouse_event(*x, *y, MouseButton::None, MouseButtons::default());
+                self.do_window_event(window_id, Event::MouseMove(event));
+            }
+            RemoteInput::MouseDown { x, y, button } => {
+                let button = mouse_button(*button);
+                let mut buttons = MouseButtons::default();
+                buttons.insert(button);
+                let event = mouse_event(*x, *y, button, buttons);
+                self.do_window_event(window_id, Event::MouseDown(event));
+            }
+            RemoteInput::MouseUp { x, y, button } => {
+                let event = mouse_event(*x, *y, mouse_button(*button), MouseButtons::default());
+                self.do_window_event(window_id, Event::MouseUp(event));
+            }
+            RemoteInput::Wheel { x, y, dx, dy } => {
+                let mut event = mouse_event(*x, *y, MouseButton::None, MouseButtons::default());
+                event.wheel_delta = crate::Vec2::new(*dx, *dy);
+                self.do_window_event(window_id, Event::Wheel(event));
+            }
+            RemoteInput::Text { text } => {
+                for c in text.chars() {
+                    let mut buf = [0; 4];
+                    let c: &str = c.encode_utf8(&mut buf);
+                    let event = KeyEvent::for_test(RawMods::None, c);
+                    if self.do_window_event(window_id, Event::KeyDown(event.clone())) == Handled::No
+                    {
+                        // Text fields get their input through the IME.
+                        let modified_widget = {
+                            let mut inner = self.inner();
+                            let Some(window) = inner.active_windows.get_mut(&window_id) else {
+                                return;
+                            };
+                            match window.get_focused_ime_handler(true) {
+                                Some(mut input_handler) => {
+                                    let selection = input_handler.selection();
+                                    input_handler.replace_range(selection.range(), c);
+                                    let caret = selection.min() + c.len();
+                                    input_handler.set_selection(Selection::caret(caret));
+                                    window.release_focused_ime_handler()
+                                }
+                                None => None,
+                            }
+                        };
+                        if let Some(widget_id) = modified_widget {
+                            let event =
+                                Event::Internal(InternalEvent::RouteImeStateChange(widget_id));
+                            self.do_window_event(window_id, event);
+                        }
+                    }
+                    self.do_window_event(window_id, Event::KeyUp(event));
+                }
+            }
+            RemoteInput::Scrub { frames_back } => {
+                if let Some(window) = self.inner().active_windows.get_mut(&window_id) {
+                    window.scrub_frame(*frames_back);
+                }
+            }
+        }
+    }
+
     /// Handle a command. Top level commands (e.g. for creating and destroying
     /// windows) have their logic here; other commands are passed to the window.
     fn do_cmd(&mut self, cmd: Command) {
@@ -484,24 +1103,153 @@ impl AppRoot {
                 self.inner().request_new_window(cmd);
             }
             _ if cmd.is(sys_cmd::CLOSE_ALL_WINDOWS) => self.inner().request_close_all_windows(),
-            //T::Window(id) if cmd.is(sys_cmd::INVALIDATE_IME) => self.inner().invalidate_ime(cmd, id),
+            _ if cmd.is(sys_cmd::PLAY_SOUND) => {
+                self.inner().play_sound(cmd.get(sys_cmd::PLAY_SOUND));
+            }
+            _ if cmd.is(sys_cmd::SET_STYLE_SHEET) => {
+                if let Some(style_sheet) = cmd.get(sys_cmd::SET_STYLE_SHEET).take() {
+                    self.inner().set_style_sheet(style_sheet);
+                } else {
+                    tracing::warn!("SET_STYLE_SHEET command has already been handled.");
+                }
+            }
+            _ if cmd.is(sys_cmd::SET_ENV) => {
+                if let Some(env) = cmd.get(sys_cmd::SET_ENV).take() {
+                    self.inner().set_env(env);
+                } else {
+                    tracing::warn!("SET_ENV command has already been handled.");
+                }
+            }
+            _ if cmd.is(sys_cmd::TOGGLE_DEBUG_SWITCH) => {
+                let mut inner = self.inner();
+                let env = cmd.get(sys_cmd::TOGGLE_DEBUG_SWITCH).toggle(&inner.env);
+                inner.set_env(env);
+            }
+            _ if cmd.is(hot_reload::RELOAD_ENV_FILE) => {
+                let mut inner = self.inner();
+                let inner = inner.deref_mut();
+                let env = inner
+                    .env_file_overrides
+                    .apply(&inner.env, cmd.get(hot_reload::RELOAD_ENV_FILE));
+                inner.set_env(env);
+            }
+            _ if cmd.is(sys_cmd::CAPTURE_WIDGET) => {
+                self.inner()
+                    .capture_widget(cmd.get(sys_cmd::CAPTURE_WIDGET));
+            }
+            _ if cmd.is(sys_cmd::PRINT_PAGES) => {
+                self.inner().print_widget(cmd.get(sys_cmd::PRINT_PAGES));
+            }
+            #[cfg(feature = "remote_debug")]
+            _ if cmd.is(remote_debug::REMOTE_INPUT) => {
+                self.remote_input(cmd.get(remote_debug::REMOTE_INPUT));
+            }
+            _ if cmd.is(jump_list::ADD_RECENT_DOCUMENT) => {
+                let path = cmd.get(jump_list::ADD_RECENT_DOCUMENT).clone();
+                self.inner()
+                    .update_jump_list(|jump_list| jump_list.add_recent(path));
+            }
+            _ if cmd.is(jump_list::CLEAR_RECENT_DOCUMENTS) => {
+                self.inner().update_jump_list(JumpList::clear_recent);
+            }
+            _ if cmd.is(jump_list::SET_JUMP_TASKS) => {
+                let tasks = cmd.get(jump_list::SET_JUMP_TASKS).clone();
+                self.inner()
+                    .update_jump_list(|jump_list| jump_list.set_tasks(tasks));
+            }
+            _ if cmd.is(single_instance::INSTANCE_ACTIVATED) => {
+                let mut inner = self.inner();
+                inner.show_main_window();
+                inner.dispatch_cmd(cmd);
+            }
+            _ if cmd.is(gamepad::GAMEPAD_EVENT) => {
+                self.inner()
+                    .gamepad_event(cmd.get(gamepad::GAMEPAD_EVENT).clone());
+            }
+            _ if cmd.is(IMAGE_DECODED) => {
+                let mut inner = self.inner();
+                let loaded = inner.image_cache.finish_decode(cmd.get(IMAGE_DECODED));
+                inner.command_queue.extend(loaded);
+            }
+            T::Window(id) if cmd.is(sys_cmd::INVALIDATE_IME) => {
+                let invalidation = cmd.get(sys_cmd::INVALIDATE_IME);
+                // The platform may call back into the app, so the update runs
+                // after the app state is released.
+                if let Some(update) = self.ime_update_fn(id, invalidation.widget) {
+                    update(invalidation.event);
+                }
+            }
             // these should come from a window
             // FIXME: we need to be able to open a file without a window handle
             // TODO - uncomment
             //T::Window(id) if cmd.is(sys_cmd::SHOW_OPEN_PANEL) => self.inner().show_open_panel(cmd, id),
             //T::Window(id) if cmd.is(sys_cmd::SHOW_SAVE_PANEL) => self.inner().show_save_panel(cmd, id),
-            //T::Window(id) if cmd.is(sys_cmd::CONFIGURE_WINDOW) => self.inner().request_configure_window(cmd, id),
+            T::Window(id) if cmd.is(sys_cmd::CONFIGURE_WINDOW) => {
+                let config = cmd.get(sys_cmd::CONFIGURE_WINDOW);
+                if let Some(window) = self.inner().active_windows.get_mut(&id) {
+                    config.apply_to_handle(&mut window.handle);
+                }
+            }
             T::Window(id) if cmd.is(sys_cmd::CLOSE_WINDOW) => {
                 self.inner().request_close_window(id);
             }
+            T::Window(id) if cmd.is(sys_cmd::FORCE_CLOSE_WINDOW) => {
+                self.inner().close_window(id);
+            }
             T::Window(id) if cmd.is(sys_cmd::SHOW_WINDOW) => self.inner().request_show_window(id),
+            T::Window(id) if cmd.is(sys_cmd::SAVE_UI_STATE) => self.inner().save_ui_state(id),
+            T::Window(id) if cmd.is(sys_cmd::SET_LOW_POWER_MODE) => {
+                let low_power = *cmd.get(sys_cmd::SET_LOW_POWER_MODE);
+                self.inner().set_low_power_override(id, low_power);
+            }
+            T::Window(id) if cmd.is(sys_cmd::SET_UI_SCALE) => {
+                let ui_scale = *cmd.get(sys_cmd::SET_UI_SCALE);
+                self.inner().set_ui_scale(id, ui_scale);
+            }
+            T::Window(id) if cmd.is(sys_cmd::SET_WINDOW_BACKDROP) => {
+                let backdrop = *cmd.get(sys_cmd::SET_WINDOW_BACKDROP);
+                if let Some(window) = self.inner().active_windows.get_mut(&id) {
+                    window.set_backdrop(backdrop);
+                }
+            }
+            T::Window(id) if cmd.is(sys_cmd::SCRUB_FRAME) => {
+                let frames_back = *cmd.get(sys_cmd::SCRUB_FRAME);
+                if let Some(window) = self.inner().active_windows.get_mut(&id) {
+                    window.scrub_frame(frames_back);
+                }
+            }
+            T::Window(id) if cmd.is(sys_cmd::RESTORE_UI_STATE) => {
+                if let Some(state) = cmd.get(sys_cmd::RESTORE_UI_STATE).take() {
+                    self.inner().restore_ui_state(id, state);
+                } else {
+                    tracing::warn!("RESTORE_UI_STATE command has already been handled.");
+                }
+            }
             //T::Window(id) if cmd.is(sys_cmd::PASTE) => self.inner().do_paste(id),
             _ if cmd.is(sys_cmd::CLOSE_WINDOW) => {
                 tracing::warn!("CLOSE_WINDOW command must target a window.")
             }
+            _ if cmd.is(sys_cmd::FORCE_CLOSE_WINDOW) => {
+                tracing::warn!("FORCE_CLOSE_WINDOW command must target a window.")
+            }
+            _ if cmd.is(sys_cmd::CONFIGURE_WINDOW) => {
+                tracing::warn!("CONFIGURE_WINDOW command must target a window.")
+            }
             _ if cmd.is(sys_cmd::SHOW_WINDOW) => {
                 tracing::warn!("SHOW_WINDOW command must target a window.")
             }
+            _ if cmd.is(sys_cmd::SAVE_UI_STATE) || cmd.is(sys_cmd::RESTORE_UI_STATE) => {
+                tracing::warn!("UI state commands must target a window.")
+            }
+            _ if cmd.is(sys_cmd::SCRUB_FRAME) => {
+                tracing::warn!("SCRUB_FRAME command must target a window.")
+            }
+            _ if cmd.is(sys_cmd::SET_UI_SCALE) => {
+                tracing::warn!("SET_UI_SCALE command must target a window.")
+            }
+            _ if cmd.is(sys_cmd::SET_WINDOW_BACKDROP) => {
+                tracing::warn!("SET_WINDOW_BACKDROP command must target a window.")
+            }
             // TODO - uncomment
             /*
             _ if cmd.is(sys_cmd::SHOW_OPEN_PANEL) => {
@@ -564,10 +1312,24 @@ impl AppRoot {
                 &mut inner.command_queue,
                 &mut inner.action_queue,
                 &mut window.timers,
+                &mut window.broadcast_listeners,
+                &mut window.access_keys,
                 window.mock_timer_queue.as_mut(),
                 &window.handle,
                 inner.main_window_id,
                 window.focus,
+                &window.style_sheet,
+                window.app_store.clone(),
+                window.image_cache.clone(),
+                window.text_metrics.clone(),
+                &mut window.offscreen_devices,
+                &mut window.mutate_callbacks,
+                window.safe_area,
+                window.frame_stats,
+                window.is_window_visible,
+                window.reduced_motion(&inner.env),
+                window.ui_scale,
+                window.telemetry.clone(),
             );
             fake_widget_state = window.root.state.clone();
 
@@ -606,6 +1368,7 @@ impl AppRoot {
     // -- Handle "new window" requests --
 
     fn process_window_requests(&mut self) {
+        self.inner().update_popups();
         let window_requests = std::mem::take(&mut self.inner.borrow_mut().window_requests);
         for window_desc in window_requests.into_iter() {
             match self.build_native_window(window_desc) {
@@ -623,6 +1386,10 @@ impl AppRoot {
         let root = desc.root;
         let title = desc.title;
         let config = desc.config;
+        let command_broadcast_opt_in = desc.command_broadcast_opt_in;
+        let integer_layout = desc.integer_layout;
+        let shortcuts = desc.shortcuts;
+        let popup_anchor = desc.popup_anchor;
         let id = desc.id;
 
         let mut builder = WindowBuilder::new(self.inner.borrow().app_handle.clone());
@@ -636,7 +1403,16 @@ impl AppRoot {
             root,
             title,
             transparent: config.transparent.unwrap_or(false),
+            is_surface_transparent: config.is_transparent(),
+            backdrop: config.backdrop.unwrap_or_default(),
             size_policy: config.size_policy,
+            render_backend: config
+                .render_backend
+                .unwrap_or_else(RenderBackend::from_env),
+            command_broadcast_opt_in,
+            integer_layout,
+            shortcuts,
+            popup_anchor,
         };
 
         let existing = self.inner.borrow_mut().pending_windows.insert(id, pending);
@@ -689,66 +1465,297 @@ impl AppRootInner {
     /// window handle; the platform should close the window, and then call
     /// our handlers `destroy()` method, at which point we can do our cleanup.
     fn request_close_window(&mut self, window_id: WindowId) {
+        let Some(window) = self.active_windows.get_mut(&window_id) else {
+            tracing::warn!("Failed to close {window_id:?}: no active window with this id");
+            return;
+        };
+        let handled = window.event(
+            Event::WindowCloseRequested,
+            &mut self.debug_logger,
+            &mut self.command_queue,
+            &mut self.action_queue,
+            &self.env,
+        );
+        if handled.is_handled() {
+            trace!("Closing {window_id:?} was prevented");
+        } else {
+            self.close_window(window_id);
+        }
+    }
+
+    /// Close a window without asking its widgets first.
+    fn close_window(&mut self, window_id: WindowId) {
         if let Some(window) = self.active_windows.get_mut(&window_id) {
-            let handled = window.event(
-                Event::WindowCloseRequested,
+            window.event(
+                Event::WindowDisconnected,
                 &mut self.debug_logger,
                 &mut self.command_queue,
                 &mut self.action_queue,
                 &self.env,
             );
-            if !handled.is_handled() {
-                window.event(
-                    Event::WindowDisconnected,
-                    &mut self.debug_logger,
-                    &mut self.command_queue,
-                    &mut self.action_queue,
-                    &self.env,
-                );
-                window.handle.close();
-            }
+            window.handle.close();
         } else {
             tracing::warn!("Failed to close {window_id:?}: no active window with this id");
         }
     }
 
-    // TODO - same confirmation code as request_close_window?
+    /// Move the popup windows whose anchor moved, and close those whose anchor is gone.
+    ///
+    /// See [`crate::popup`].
+    fn update_popups(&mut self) {
+        // Stop tracking the anchors of closed popups.
+        let anchors: HashSet<_> = self
+            .active_windows
+            .values()
+            .filter_map(|popup| popup.popup_anchor.as_ref())
+            .map(|anchor| (anchor.window, anchor.widget))
+            .collect();
+        for (&id, window) in &mut self.active_windows {
+            window
+                .popup_anchor_origins
+                .retain(|&widget, _| anchors.contains(&(id, widget)));
+        }
+
+        let mut moved = Vec::new();
+        let mut orphaned = Vec::new();
+        for (&id, popup) in &self.active_windows {
+            let Some(anchor) = &popup.popup_anchor else {
+                continue;
+            };
+            match anchor.update(self.active_windows.get(&anchor.window)) {
+                PopupUpdate::Keep => {}
+                PopupUpdate::Move(rect) => moved.push((id, rect)),
+                PopupUpdate::Close => orphaned.push(id),
+            }
+        }
+
+        for (id, anchor_rect) in moved {
+            let Some(popup) = self.active_windows.get_mut(&id) else {
+                continue;
+            };
+            let position = screen::place_near(anchor_rect, popup.size, &screen::work_areas());
+            popup.handle.set_position(position);
+            if let Some(anchor) = &mut popup.popup_anchor {
+                anchor.placed_at = Some(anchor_rect);
+            }
+        }
+        for id in orphaned {
+            trace!("Closing popup {id:?}, whose anchor is gone");
+            if let Some(popup) = self.active_windows.get_mut(&id) {
+                // The window stays active until the platform destroys it.
+                popup.popup_anchor = None;
+            }
+            self.close_window(id);
+        }
+    }
+
     /// Requests the platform to close all windows.
+    ///
+    /// Each window can prevent its own close, as with [`CLOSE_WINDOW`](sys_cmd::CLOSE_WINDOW).
     fn request_close_all_windows(&mut self) {
-        for win in self.active_windows.values_mut() {
-            win.handle.close();
+        let window_ids: Vec<_> = self.active_windows.keys().copied().collect();
+        for window_id in window_ids {
+            self.request_close_window(window_id);
         }
     }
 
-    fn request_show_window(&mut self, id: WindowId) {
-        if let Some(win) = self.active_windows.get_mut(&id) {
-            win.handle.bring_to_front_and_focus();
+    fn play_sound(&mut self, sound: &SoundId) {
+        if let Some(path) = sound.resolve(&self.env) {
+            self.sound_player.play(&path);
         }
     }
 
-    fn request_configure_window(&mut self, config: &WindowConfig, id: WindowId) {
-        if let Some(win) = self.active_windows.get_mut(&id) {
-            config.apply_to_handle(&mut win.handle);
+    fn set_style_sheet(&mut self, style_sheet: StyleSheet) {
+        self.style_sheet = Rc::new(style_sheet);
+        for window in self.active_windows.values_mut() {
+            window.set_style_sheet(self.style_sheet.clone());
         }
     }
 
-    fn dispatch_cmd(&mut self, cmd: Command) -> Handled {
-        self.invalidate_paint_regions();
-        match cmd.target() {
-            Target::Global => {
-                for w in self.active_windows.values_mut() {
-                    if w.event(
-                        Event::Command(cmd.clone()),
-                        &mut self.debug_logger,
-                        &mut self.command_queue,
-                        &mut self.action_queue,
-                        &self.env,
-                    )
-                    .is_handled()
-                    {
-                        return Handled::Yes;
-                    }
-                }
+    /// The widgets to send [`STORE_CHANGED`](sys_cmd::STORE_CHANGED) to, after the
+    /// app's store was updated.
+    fn take_store_changed_widgets(&mut self) -> Vec<WidgetId> {
+        let Some(store) = &self.app_store else {
+            return Vec::new();
+        };
+        let windows = &self.active_windows;
+        store.take_changed_widgets(&|id| windows.values().any(|w| w.may_contain_widget(id)))
+    }
+
+    fn set_env(&mut self, env: Env) {
+        let changed_keys = self.env.changed_keys(&env);
+        self.env = env;
+        if changed_keys.is_empty() {
+            return;
+        }
+        let event = LifeCycle::Internal(InternalLifeCycle::RouteEnvChanged(Rc::new(changed_keys)));
+        for window in self.active_windows.values_mut() {
+            window.lifecycle(
+                &event,
+                &mut self.debug_logger,
+                &mut self.command_queue,
+                &mut self.action_queue,
+                &self.env,
+                false,
+            );
+        }
+    }
+
+    fn set_ui_scale(&mut self, id: WindowId, ui_scale: f64) {
+        if let Some(win) = self.active_windows.get_mut(&id) {
+            win.set_ui_scale(
+                ui_scale,
+                &mut self.debug_logger,
+                &mut self.command_queue,
+                &mut self.action_queue,
+                &self.env,
+            );
+        }
+    }
+
+    fn set_low_power_override(&mut self, id: WindowId, low_power: Option<bool>) {
+        if let Some(win) = self.active_windows.get_mut(&id) {
+            win.set_low_power_override(
+                low_power,
+                &mut self.debug_logger,
+                &mut self.command_queue,
+                &mut self.action_queue,
+                &self.env,
+            );
+        }
+    }
+
+    fn save_ui_state(&mut self, id: WindowId) {
+        if let Some(win) = self.active_windows.get(&id) {
+            let state = win.save_ui_state();
+            self.action_queue
+                .push_back((Action::UiStateSaved(state), win.root.id(), id));
+        }
+    }
+
+    fn restore_ui_state(&mut self, id: WindowId, state: UiState) {
+        if let Some(win) = self.active_windows.get_mut(&id) {
+            win.restore_ui_state(
+                state,
+                &mut self.debug_logger,
+                &mut self.command_queue,
+                &mut self.action_queue,
+                &self.env,
+            );
+        }
+    }
+
+    fn capture_widget(&mut self, request: &Request<CaptureWidget, ImageBuf>) {
+        let CaptureWidget { widget, scale } = *request.payload();
+        for window in self.active_windows.values_mut() {
+            let image = window.capture_widget(
+                widget,
+                scale,
+                &mut self.debug_logger,
+                &mut self.command_queue,
+                &mut self.action_queue,
+                &self.env,
+            );
+            if let Some(image) = image {
+                request.respond(image);
+                return;
+            }
+        }
+        warn!("CAPTURE_WIDGET: can't paint widget {:?}", widget);
+    }
+
+    fn print_widget(&mut self, request: &Request<PrintPages, Vec<ImageBuf>>) {
+        let PrintPages { widget, setup } = *request.payload();
+        for window in self.active_windows.values_mut() {
+            let pages = window.print_widget(
+                widget,
+                setup,
+                &mut self.debug_logger,
+                &mut self.command_queue,
+                &mut self.action_queue,
+                &self.env,
+            );
+            if let Some(pages) = pages {
+                request.respond(pages);
+                return;
+            }
+        }
+        warn!("PRINT_PAGES: can't print widget {:?}", widget);
+    }
+
+    /// Send a gamepad event to the focused window, or the main window if none is.
+    fn gamepad_event(&mut self, event: GamepadEvent) {
+        let window_id = self
+            .active_windows
+            .values()
+            .find(|window| window.is_window_focused)
+            .map_or(self.main_window_id, |window| window.id);
+        if let Some(window) = self.active_windows.get_mut(&window_id) {
+            window.gamepad_event(
+                event,
+                &mut self.debug_logger,
+                &mut self.command_queue,
+                &mut self.action_queue,
+                &self.env,
+            );
+        }
+    }
+
+    /// Change the jump list, and publish it to the OS.
+    ///
+    /// Does nothing if the app doesn't have a jump list.
+    fn update_jump_list(&mut self, update: impl FnOnce(&mut JumpList)) {
+        let Some((jump_list, publisher)) = &mut self.jump_list else {
+            return;
+        };
+        let old_jump_list = jump_list.clone();
+        update(jump_list);
+        if *jump_list != old_jump_list {
+            publisher.publish(jump_list);
+        }
+    }
+
+    /// Bring the main window to the front, or another window if it was closed.
+    fn show_main_window(&mut self) {
+        let window_id = if self.active_windows.contains_key(&self.main_window_id) {
+            Some(self.main_window_id)
+        } else {
+            self.active_windows.keys().next().copied()
+        };
+        if let Some(window) = window_id.and_then(|id| self.active_windows.get_mut(&id)) {
+            window.handle.bring_to_front_and_focus();
+        }
+    }
+
+    fn request_show_window(&mut self, id: WindowId) {
+        if let Some(win) = self.active_windows.get_mut(&id) {
+            win.handle.bring_to_front_and_focus();
+        }
+    }
+
+    fn request_configure_window(&mut self, config: &WindowConfig, id: WindowId) {
+        if let Some(win) = self.active_windows.get_mut(&id) {
+            config.apply_to_handle(&mut win.handle);
+        }
+    }
+
+    fn dispatch_cmd(&mut self, cmd: Command) -> Handled {
+        self.invalidate_paint_regions();
+        match cmd.target() {
+            Target::Global => {
+                for w in self.active_windows.values_mut() {
+                    if w.event(
+                        Event::Command(cmd.clone()),
+                        &mut self.debug_logger,
+                        &mut self.command_queue,
+                        &mut self.action_queue,
+                        &self.env,
+                    )
+                    .is_handled()
+                    {
+                        return Handled::Yes;
+                    }
+                }
                 return Handled::No;
             }
             Target::Window(id) => {
@@ -861,16 +1868,20 @@ impl WindowRoot {
         id: WindowId,
         handle: WindowHandle,
         ext_event_sink: ExtEventSink,
-        root: Box<dyn Widget>,
+        root: WidgetPod<Box<dyn Widget>>,
         title: ArcStr,
         transparent: bool,
         size_policy: WindowSizePolicy,
+        render_backend: RenderBackend,
         mock_timer_queue: Option<MockTimerQueue>,
+        style_sheet: Rc<StyleSheet>,
     ) -> WindowRoot {
         WindowRoot {
             id,
-            root: WidgetPod::new(root),
+            root,
             size_policy,
+            render_backend,
+            software_renderer: SoftwareRenderer::default(),
             size: Size::ZERO,
             invalid: Region::EMPTY,
             title,
@@ -881,23 +1892,450 @@ impl WindowRoot {
             ext_event_sink,
             handle,
             timers: HashMap::new(),
+            broadcast_listeners: BroadcastListeners::default(),
+            access_keys: AccessKeys::default(),
+            command_broadcast_opt_in: false,
+            integer_layout: false,
+            shortcuts: Shortcuts::default(),
+            pending_chord: PendingChord::default(),
+            context_menu: None,
             mock_timer_queue,
             ime_handlers: Vec::new(),
             ime_focus_change: None,
+            style_sheet,
+            app_store: None,
+            image_cache: ImageCache::new(),
+            text_metrics: TextMetricsCache::new(),
+            offscreen_devices: OffscreenDevices::default(),
+            telemetry: None,
+            is_surface_transparent: transparent,
+            backdrop: WindowBackdrop::None,
+            backdrop_effects: None,
+            backdrop_regions: None,
+            hovered_titlebar_zone: None,
+            popup_anchor: None,
+            popup_anchor_origins: HashMap::new(),
+            is_window_focused: true,
+            is_window_visible: true,
+            low_power_override: None,
+            low_power_frame_timer: None,
+            deferred_timers: Vec::new(),
+            timers_before_hidden: HashSet::new(),
+            rearmed_timers: HashMap::new(),
+            superseded_timers: HashSet::new(),
+            caret_blink: CaretBlinkState::default(),
+            key_repeat: KeyRepeatState::default(),
+            last_click: None,
+            gamepad_nav: GamepadNavigation::default(),
+            mutate_callbacks: Vec::new(),
+            is_running_mutate_callbacks: false,
+            paint_flash_count: 0,
+            safe_area: Insets::ZERO,
+            present_stats: PresentStats::default(),
+            frame_stats: FrameStats::default(),
+            ui_scale: 1.0,
+            widget_env_cache: None,
+            frame_history: FrameHistory::default(),
+            frame_timings: PassTimings::default(),
+            passes: PassTracker::default(),
+        }
+    }
+
+    /// Change the safe area, and notify every widget if it changed.
+    pub(crate) fn set_safe_area(
+        &mut self,
+        safe_area: Insets,
+        debug_logger: &mut DebugLogger,
+        command_queue: &mut CommandQueue,
+        action_queue: &mut ActionQueue,
+        env: &Env,
+    ) {
+        if self.safe_area == safe_area {
+            return;
+        }
+        self.safe_area = safe_area;
+        self.lifecycle(
+            &LifeCycle::SafeAreaChanged(safe_area),
+            debug_logger,
+            command_queue,
+            action_queue,
+            env,
+            false,
+        );
+    }
+
+    /// Hide or show the window, eg when it's minimized and restored.
+    ///
+    /// While the window is hidden, no animation frames are requested, the caret
+    /// stops blinking, and the timers which fire are held back. When it's shown
+    /// again, the held back timers are delivered in the order of their deadlines,
+    /// and animations resume where they were paused rather than jumping ahead.
+    ///
+    /// The timers requested while the window is hidden are coalesced: they fire on
+    /// a coarse grid, so the app wakes up less often. See
+    /// [`HIDDEN_WINDOW_TIMER_INTERVAL`](crate::contexts::HIDDEN_WINDOW_TIMER_INTERVAL).
+    /// When the window is shown, those which are due are delivered, and the others
+    /// fire at their deadline.
+    pub(crate) fn set_window_visible(
+        &mut self,
+        is_visible: bool,
+        debug_logger: &mut DebugLogger,
+        command_queue: &mut CommandQueue,
+        action_queue: &mut ActionQueue,
+        env: &Env,
+    ) {
+        if self.is_window_visible == is_visible {
+            return;
+        }
+        self.is_window_visible = is_visible;
+        if !is_visible {
+            self.timers_before_hidden = self.timers.keys().copied().collect();
+        }
+        // Update the visibility of every widget.
+        self.lifecycle(
+            &LifeCycle::Internal(InternalLifeCycle::ParentWindowOrigin),
+            debug_logger,
+            command_queue,
+            action_queue,
+            env,
+            false,
+        );
+        if !is_visible {
+            return;
+        }
+
+        self.last_anim = None;
+        self.reset_caret_blink(env);
+
+        let mut deferred_timers = std::mem::take(&mut self.deferred_timers);
+        deferred_timers.sort_by_key(|timer| timer.deadline);
+        for timer in deferred_timers {
+            self.event(
+                Event::Timer(timer),
+                debug_logger,
+                command_queue,
+                action_queue,
+                env,
+            );
+        }
+
+        // The platform timers of the timers requested while hidden were delayed.
+        let now = self.now();
+        let mut coalesced_timers: Vec<_> = self
+            .timers
+            .iter()
+            .filter(|(token, _)| !self.timers_before_hidden.contains(token))
+            .map(|(token, (_, deadline))| (*token, *deadline))
+            .collect();
+        self.timers_before_hidden.clear();
+        coalesced_timers.sort_by_key(|(_, deadline)| *deadline);
+        for (token, deadline) in coalesced_timers {
+            if deadline <= now {
+                let timer = TimerEvent {
+                    token,
+                    deadline,
+                    fired_at: now,
+                };
+                self.event(
+                    Event::Timer(timer),
+                    debug_logger,
+                    command_queue,
+                    action_queue,
+                    env,
+                );
+            } else {
+                let rearmed = self.request_window_timer(deadline - now);
+                self.rearmed_timers.insert(rearmed, token);
+            }
+            self.superseded_timers.insert(token);
+        }
+
+        self.request_anim_frame(env);
+    }
+
+    /// Override the app's low-power mode for this window; `None` removes the override.
+    ///
+    /// The widgets which read [`Env::LOW_POWER_MODE`], eg through
+    /// [`EventCtx::reduced_motion`](crate::EventCtx::reduced_motion), receive
+    /// [`LifeCycle::EnvChanged`](crate::LifeCycle::EnvChanged) if the window's
+    /// low-power mode changes.
+    pub(crate) fn set_low_power_override(
+        &mut self,
+        low_power: Option<bool>,
+        debug_logger: &mut DebugLogger,
+        command_queue: &mut CommandQueue,
+        action_queue: &mut ActionQueue,
+        env: &Env,
+    ) {
+        if low_power == self.low_power_override {
+            return;
+        }
+        let old_env = self.widget_env(env);
+        self.low_power_override = low_power;
+        let changed_keys = old_env.changed_keys(&self.widget_env(env));
+        let event = LifeCycle::Internal(InternalLifeCycle::RouteEnvChanged(Rc::new(changed_keys)));
+        self.lifecycle(
+            &event,
+            debug_logger,
+            command_queue,
+            action_queue,
+            env,
+            false,
+        );
+    }
+
+    /// Show an earlier frame instead of the widgets; see
+    /// [`SCRUB_FRAME`](sys_cmd::SCRUB_FRAME).
+    pub(crate) fn scrub_frame(&mut self, frames_back: Option<usize>) {
+        self.frame_history.scrub(frames_back);
+        if let Some(frame) = self.frame_history.scrubbed_frame() {
+            info!("Showing frame {}", frame.number);
+        }
+        self.invalid.union_with(&Region::from(self.size.to_rect()));
+    }
+
+    /// Change the material shown through the window's
+    /// [`BackdropRegion`](crate::widget::BackdropRegion) widgets; see
+    /// [`SET_WINDOW_BACKDROP`](sys_cmd::SET_WINDOW_BACKDROP).
+    pub(crate) fn set_backdrop(&mut self, backdrop: WindowBackdrop) {
+        if backdrop != WindowBackdrop::None && !self.is_surface_transparent {
+            warn!(
+                "Window {:?} wasn't created with a backdrop or as transparent, so it can't show {:?}",
+                self.id, backdrop
+            );
+            return;
+        }
+        if backdrop == self.backdrop {
+            return;
+        }
+        self.backdrop = backdrop;
+        self.backdrop_regions = None;
+        if backdrop == WindowBackdrop::None {
+            if let Some(effects) = &self.backdrop_effects {
+                effects.apply(&self.handle, WindowBackdrop::None, &[]);
+            }
+        }
+        self.update_backdrop();
+        self.invalid.union_with(&Region::from(self.size.to_rect()));
+    }
+
+    /// Pass the backdrop regions to the backdrop effects if they've changed since they
+    /// were last collected.
+    ///
+    /// This walks the whole tree, so it only runs when the widgets' window origins
+    /// are updated, ie after layout or when a container moves its children.
+    fn update_backdrop(&mut self) {
+        if self.backdrop == WindowBackdrop::None {
+            return;
+        }
+        let Some(effects) = self.backdrop_effects.clone() else {
+            return;
+        };
+        let regions = backdrop::backdrop_regions(self.root.as_dyn());
+        match &self.backdrop_regions {
+            Some((applied_regions, _)) if *applied_regions == regions => {}
+            previous => {
+                let is_applied = effects.apply(&self.handle, self.backdrop, &regions);
+                if !is_applied && previous.is_none() {
+                    warn!(
+                        "Backdrop {:?} isn't supported, window {:?} is painted opaque",
+                        self.backdrop, self.id
+                    );
+                }
+                self.backdrop_regions = Some((regions, is_applied));
+                // The regions are painted again with or without the backdrop.
+                self.invalid.union_with(&Region::from(self.size.to_rect()));
+            }
+        }
+    }
+
+    /// Cache the window origin of `widget`, which anchors a popup, until the popup
+    /// closes; see [`crate::popup`].
+    pub(crate) fn track_popup_anchor(&mut self, widget: WidgetId) {
+        let origin = popup::visible_window_origin(self.root.as_dyn(), widget);
+        self.popup_anchor_origins.insert(widget, origin);
+    }
+
+    /// The cached window origin of `widget`, or `None` if it's gone, stashed, or
+    /// doesn't anchor a popup.
+    pub(crate) fn popup_anchor_origin(&self, widget: WidgetId) -> Option<Point> {
+        self.popup_anchor_origins.get(&widget).copied().flatten()
+    }
+
+    fn update_popup_anchor_origins(&mut self) {
+        let root = self.root.as_dyn();
+        for (&widget, origin) in &mut self.popup_anchor_origins {
+            *origin = popup::visible_window_origin(root, widget);
+        }
+    }
+
+    /// The regions the backdrop shows through.
+    fn shown_backdrop_regions(&self) -> &[Rect] {
+        match &self.backdrop_regions {
+            Some((regions, true)) if self.backdrop != WindowBackdrop::None => regions,
+            _ => &[],
+        }
+    }
+
+    /// Set the window's [UI scale](Env::UI_SCALE), clamped to a sensible range.
+    ///
+    /// The widgets which read a scaled metric receive
+    /// [`LifeCycle::EnvChanged`](crate::LifeCycle::EnvChanged), and are laid out
+    /// again.
+    pub(crate) fn set_ui_scale(
+        &mut self,
+        ui_scale: f64,
+        debug_logger: &mut DebugLogger,
+        command_queue: &mut CommandQueue,
+        action_queue: &mut ActionQueue,
+        env: &Env,
+    ) {
+        let ui_scale = ui_scale.clamp(MIN_UI_SCALE, MAX_UI_SCALE);
+        if ui_scale == self.ui_scale {
+            return;
+        }
+        let old_env = self.widget_env(env);
+        self.ui_scale = ui_scale;
+        let changed_keys = old_env.changed_keys(&self.widget_env(env));
+        let event = LifeCycle::Internal(InternalLifeCycle::RouteEnvChanged(Rc::new(changed_keys)));
+        self.lifecycle(
+            &event,
+            debug_logger,
+            command_queue,
+            action_queue,
+            env,
+            false,
+        );
+    }
+
+    /// The env passed to the widgets: the app's `env`, with its metrics multiplied
+    /// by the UI scale, the high-contrast palette if it's enabled, and the window's
+    /// low-power mode.
+    fn widget_env(&mut self, env: &Env) -> Env {
+        let low_power = self.is_low_power(env);
+        match &self.widget_env_cache {
+            Some((app_env, widget_env))
+                if app_env.same(env)
+                    && widget_env.get(Env::UI_SCALE) == self.ui_scale
+                    && widget_env.get(Env::LOW_POWER_MODE) == low_power =>
+            {
+                widget_env.clone()
+            }
+            _ => {
+                let mut widget_env = theme::scale_metrics(env, self.ui_scale);
+                if env.get(Env::HIGH_CONTRAST) {
+                    widget_env = theme::add_high_contrast_palette(widget_env);
+                }
+                if low_power != env.get(Env::LOW_POWER_MODE) {
+                    widget_env = widget_env.adding(Env::LOW_POWER_MODE, low_power);
+                }
+                self.widget_env_cache = Some((env.clone(), widget_env.clone()));
+                widget_env
+            }
+        }
+    }
+
+    fn is_low_power(&self, env: &Env) -> bool {
+        self.low_power_override
+            .unwrap_or_else(|| env.get(Env::LOW_POWER_MODE))
+    }
+
+    /// Whether widgets should skip non-essential animations.
+    pub(crate) fn reduced_motion(&self, env: &Env) -> bool {
+        self.is_low_power(env) || env.get(Env::PREFERS_REDUCED_MOTION)
+    }
+
+    /// Ask the platform for an animation frame if a widget wants one.
+    ///
+    /// In low-power mode, frames closer than [`LOW_POWER_FRAME_INTERVAL`] to the
+    /// last one are delayed with a timer.
+    fn request_anim_frame(&mut self, env: &Env) {
+        if !self.wants_animation_frame() || self.low_power_frame_timer.is_some() {
+            return;
         }
+        if self.is_low_power(env) {
+            if let Some(last_anim) = self.last_anim {
+                let since_last = self.now().saturating_duration_since(last_anim);
+                if since_last < LOW_POWER_FRAME_INTERVAL {
+                    let timer = self.request_window_timer(LOW_POWER_FRAME_INTERVAL - since_last);
+                    self.low_power_frame_timer = Some(timer);
+                    return;
+                }
+            }
+        }
+        self.handle.request_anim_frame();
+    }
+
+    /// Set whether layout is computed in physical pixels, and lay out the window again.
+    ///
+    /// See [`WindowDescription::integer_layout`].
+    pub(crate) fn set_integer_layout(&mut self, integer_layout: bool) {
+        self.integer_layout = integer_layout;
+        self.root.state.needs_layout = true;
+    }
+
+    /// Replace the style sheet, and recompute the style of every widget.
+    pub(crate) fn set_style_sheet(&mut self, style_sheet: Rc<StyleSheet>) {
+        self.style_sheet = style_sheet;
+        self.root.state.needs_layout = true;
     }
 
     // TODO - Add 'get_global_ctx() -> GlobalPassCtx' method
 
-    /// `true` iff any child requested an animation frame since the last `AnimFrame` event.
+    /// `true` iff any child requested an animation frame since the last `AnimFrame` event,
+    /// and the window is visible.
+    ///
+    /// Animations of hidden windows are paused, see [`WindowRoot::set_window_visible`].
     pub(crate) fn wants_animation_frame(&self) -> bool {
-        self.root.state().request_anim
+        self.is_window_visible && self.root.state().request_anim
     }
 
     pub(crate) fn focus_chain(&self) -> &[WidgetId] {
         &self.root.state().focus_chain
     }
 
+    /// Send a broadcast command directly to the widgets which registered for it.
+    ///
+    /// Every listener receives the command, even if an earlier one handled it.
+    ///
+    /// Returns `None` if the event isn't a broadcast command, or if it has no
+    /// registered listeners; the event should then be dispatched normally.
+    fn route_to_command_listeners(
+        &mut self,
+        event: &Event,
+        debug_logger: &mut DebugLogger,
+        command_queue: &mut CommandQueue,
+        action_queue: &mut ActionQueue,
+        env: &Env,
+    ) -> Option<Handled> {
+        let cmd = match event {
+            Event::Command(cmd) => cmd,
+            Event::Internal(InternalEvent::TargetedCommand(cmd))
+                if matches!(cmd.target(), Target::Global | Target::Window(_)) =>
+            {
+                cmd
+            }
+            _ => return None,
+        };
+
+        let listeners = self.broadcast_listeners.command_listeners(
+            cmd.symbol(),
+            self.root.state(),
+            self.command_broadcast_opt_in,
+        )?;
+        let mut handled = Handled::No;
+        for id in listeners {
+            let event = Event::Internal(InternalEvent::TargetedCommand(cmd.clone().to(id)));
+            if self
+                .event(event, debug_logger, command_queue, action_queue, env)
+                .is_handled()
+            {
+                handled = Handled::Yes;
+            }
+        }
+        Some(handled)
+    }
+
     /// Returns `true` if the provided widget may be in this window,
     /// but it may also be a false positive.
     /// However when this returns `false` the widget is definitely not in this window.
@@ -942,10 +2380,44 @@ impl WindowRoot {
                 env,
                 false,
             );
+
+            // The focused widget may have been moved to another parent (see
+            // `WidgetPod::take`); refresh `has_focus` along its new path before
+            // the focus chain is rebuilt, so the old parent doesn't resign focus.
+            if let Some(focus) = self.focus {
+                self.lifecycle(
+                    &LifeCycle::Internal(InternalLifeCycle::RouteFocusChanged {
+                        old: Some(focus),
+                        new: Some(focus),
+                    }),
+                    debug_logger,
+                    command_queue,
+                    action_queue,
+                    env,
+                    false,
+                );
+            }
+        }
+
+        if !self.mutate_callbacks.is_empty() && !self.is_running_mutate_callbacks {
+            self.run_mutate_callbacks(debug_logger, command_queue, action_queue, env);
         }
 
         if debug_logger.layout_tree.root.is_none() {
-            debug_logger.layout_tree.root = Some(self.root.id().to_raw() as u32);
+            debug_logger.layout_tree.root = Some(self.root.id().to_raw());
+        }
+
+        if self.root.state().needs_refine_layout && !self.root.state().needs_layout {
+            let event =
+                LifeCycle::Internal(InternalLifeCycle::RouteRefineLayout(self.last_mouse_pos));
+            self.lifecycle(
+                &event,
+                debug_logger,
+                command_queue,
+                action_queue,
+                env,
+                false,
+            );
         }
 
         if self.root.state().needs_window_origin && !self.root.state().needs_layout {
@@ -988,13 +2460,28 @@ impl WindowRoot {
             );
         }
 
+        let old_focus = self.focus;
         self.update_focus(widget_state, debug_logger, command_queue, action_queue, env);
+        if std::mem::take(&mut widget_state.reset_caret_blink) || self.focus != old_focus {
+            self.reset_caret_blink(env);
+        }
+        // Make sure keyboard navigation never lands on a widget scrolled out of view.
+        if let Some(focus) = self.focus.filter(|focus| Some(*focus) != old_focus) {
+            self.event(
+                Event::Internal(InternalEvent::RoutePanToWidget(focus, None)),
+                debug_logger,
+                command_queue,
+                action_queue,
+                env,
+            );
+        }
 
         // If we need a new paint pass, make sure druid-shell knows it.
-        if self.wants_animation_frame() {
-            self.handle.request_anim_frame();
-        }
+        self.request_anim_frame(env);
         self.invalid.union_with(&widget_state.invalid);
+        for message in util::take_emitted_violations() {
+            action_queue.push_back((Action::InvariantViolation(message), self.root.id(), self.id));
+        }
         for ime_field in widget_state.text_registrations.drain(..) {
             let token = self.handle.add_text_field();
             tracing::debug!("{:?} added", token);
@@ -1032,17 +2519,122 @@ impl WindowRoot {
         }
 
         let event = match event {
-            Event::Timer(token) => {
-                if let Some(widget_id) = self.timers.get(&token) {
-                    Event::Internal(InternalEvent::RouteTimer(token, *widget_id))
+            Event::MouseDown(mut mouse) => {
+                mouse.count = self.count_clicks(&mouse, env);
+                Event::MouseDown(mouse)
+            }
+            other => other,
+        };
+
+        if let Event::KeyDown(key) | Event::KeyUp(key) = &event {
+            if self.filter_key_repeat(key) {
+                return Handled::No;
+            }
+        }
+
+        if let Event::KeyDown(key) = &event {
+            if self.handle_shortcut(key, command_queue) {
+                return Handled::Yes;
+            }
+        }
+
+        if let Event::Command(cmd) | Event::Internal(InternalEvent::TargetedCommand(cmd)) = &event {
+            if let Some((menu, position)) = cmd.try_get(sys_cmd::SHOW_CONTEXT_MENU) {
+                self.show_context_menu(menu.clone(), *position);
+                return Handled::Yes;
+            }
+        }
+
+        // A timer re-armed when the window was shown stands for a coalesced timer.
+        let event = match event {
+            Event::Timer(timer) => match self.rearmed_timers.remove(&timer.token) {
+                Some(token) => Event::Timer(TimerEvent { token, ..timer }),
+                None if self.superseded_timers.remove(&timer.token) => return Handled::No,
+                None => Event::Timer(timer),
+            },
+            other => other,
+        };
+
+        let event = match event {
+            Event::Command(cmd) | Event::Internal(InternalEvent::TargetedCommand(cmd))
+                if cmd.is(sys_cmd::PAN_TO_RECT) =>
+            {
+                let (widget_id, rect) = *cmd.get(sys_cmd::PAN_TO_RECT);
+                Event::Internal(InternalEvent::RoutePanToWidget(widget_id, Some(rect)))
+            }
+            Event::Timer(timer) if Some(timer.token) == self.pending_chord.timer => {
+                self.pending_chord.timer = None;
+                self.check_chord_timeout(command_queue);
+                return Handled::No;
+            }
+            Event::Timer(timer) if Some(timer.token) == self.low_power_frame_timer => {
+                self.low_power_frame_timer = None;
+                if self.wants_animation_frame() {
+                    self.handle.request_anim_frame();
+                }
+                return Handled::No;
+            }
+            Event::Timer(timer) if Some(timer.token) == self.gamepad_nav.repeat_timer => {
+                let (direction, next_timer) = self.gamepad_nav.on_repeat_timer(self.now());
+                if let Some(delay) = next_timer {
+                    self.gamepad_nav.repeat_timer = Some(self.request_window_timer(delay));
+                }
+                if let Some(direction) = direction {
+                    self.move_focus(
+                        FocusChange::Direction(direction),
+                        debug_logger,
+                        command_queue,
+                        action_queue,
+                        env,
+                    );
+                }
+                return Handled::No;
+            }
+            Event::Timer(timer) if Some(timer.token) == self.key_repeat.timer => {
+                self.key_repeat.timer = None;
+                match self.next_key_repeat() {
+                    Some(key) => Event::KeyDown(key),
+                    None => return Handled::No,
+                }
+            }
+            Event::Timer(timer) if Some(timer.token) == self.caret_blink.timer => {
+                self.caret_blink.timer = None;
+                if !self.advance_caret_blink(env) {
+                    return Handled::No;
+                }
+                Event::CaretBlink(self.caret_blink.is_visible)
+            }
+            Event::Timer(timer)
+                if !self.is_window_visible && self.timers.contains_key(&timer.token) =>
+            {
+                trace!(
+                    "Deferring timer {:?} until the window is shown",
+                    timer.token
+                );
+                self.deferred_timers.push(timer);
+                return Handled::No;
+            }
+            Event::Timer(timer) => {
+                if let Some((widget_id, deadline)) = self.timers.get(&timer.token) {
+                    let timer = TimerEvent {
+                        deadline: *deadline,
+                        ..timer
+                    };
+                    Event::Internal(InternalEvent::RouteTimer(timer, *widget_id))
                 } else {
-                    error!("No widget found for timer {:?}", token);
+                    error!("No widget found for timer {:?}", timer.token);
                     return Handled::No;
                 }
             }
             other => other,
         };
 
+        if let Some(handled) =
+            self.route_to_command_listeners(&event, debug_logger, command_queue, action_queue, env)
+        {
+            return handled;
+        }
+
         if let Event::WindowConnected = event {
             self.lifecycle(
                 &LifeCycle::Internal(InternalLifeCycle::RouteWidgetAdded),
@@ -1054,19 +2646,46 @@ impl WindowRoot {
             );
         }
 
+        let pass = match event {
+            Event::AnimFrame(_) => Pass::AnimFrame,
+            _ => Pass::Event,
+        };
+        self.passes.begin(pass, self.root.state());
+
+        let widget_env = self.widget_env(env);
+        let telemetry = self.telemetry.clone().filter(|telemetry| {
+            !matches!(event, Event::Internal(_)) && telemetry.borrow().observes_event(&event)
+        });
+
         let mut widget_state = WidgetState::new(self.root.id(), Some(self.size), "<root>");
-        let is_handled = {
+        let (mut is_handled, event_handler) = {
             let mut global_state = GlobalPassCtx::new(
                 self.ext_event_sink.clone(),
                 debug_logger,
                 command_queue,
                 action_queue,
                 &mut self.timers,
+                &mut self.broadcast_listeners,
+                &mut self.access_keys,
                 self.mock_timer_queue.as_mut(),
                 &self.handle,
                 self.id,
                 self.focus,
+                &self.style_sheet,
+                self.app_store.clone(),
+                self.image_cache.clone(),
+                self.text_metrics.clone(),
+                &mut self.offscreen_devices,
+                &mut self.mutate_callbacks,
+                self.safe_area,
+                self.frame_stats,
+                self.is_window_visible,
+                self.reduced_motion(env),
+                self.ui_scale,
+                self.telemetry.clone(),
             );
+            global_state.pass = self.passes.current();
+            global_state.track_event_handler = telemetry.is_some();
             let mut notifications = VecDeque::new();
 
             let mut ctx = EventCtx {
@@ -1082,8 +2701,11 @@ impl WindowRoot {
                 ctx.global_state
                     .debug_logger
                     .push_important_span(&format!("EVENT {}", event.short_name()));
+                ctx.global_state
+                    .debug_logger
+                    .begin_event_trace(event.short_name());
                 let _span = info_span!("event").entered();
-                self.root.on_event(&mut ctx, &event, env);
+                self.root.on_event(&mut ctx, &event, &widget_env);
                 ctx.global_state.debug_logger.pop_span();
             }
 
@@ -1094,13 +2716,28 @@ impl WindowRoot {
                 }
             }
 
-            Handled::from(ctx.is_handled)
+            let close_prevented =
+                matches!(event, Event::WindowCloseRequested) && ctx.global_state.close_prevented;
+            (
+                Handled::from(ctx.is_handled || close_prevented),
+                ctx.global_state.event_handler,
+            )
         };
+        self.passes.end(pass);
+
+        if let Some(telemetry) = telemetry {
+            telemetry.borrow_mut().observe(TelemetryRecord {
+                observed: Observed::Event(&event),
+                widget_id: event_handler,
+                window_id: self.id,
+                time: self.now(),
+            });
+        }
 
         // Clean up the timer token and do it immediately after the event handling
         // because the token may be reused and re-added in a lifecycle pass below.
-        if let Event::Internal(InternalEvent::RouteTimer(token, _)) = event {
-            self.timers.remove(&token);
+        if let Event::Internal(InternalEvent::RouteTimer(timer, _)) = event {
+            self.timers.remove(&timer.token);
         }
 
         if let Some(cursor) = &widget_state.cursor {
@@ -1112,15 +2749,6 @@ impl WindowRoot {
             self.handle.set_cursor(&Cursor::Arrow);
         }
 
-        if matches!(
-            (event, self.size_policy),
-            (Event::WindowSize(_), WindowSizePolicy::Content)
-        ) {
-            // Because our initial size can be zero, the window system won't ask us to paint.
-            // So layout ourselves and hopefully we resize
-            self.layout(debug_logger, command_queue, action_queue, env);
-        }
-
         self.post_event_processing(
             &mut widget_state,
             debug_logger,
@@ -1130,6 +2758,33 @@ impl WindowRoot {
             false,
         );
 
+        if let (Event::KeyDown(key), Handled::No) = (&event, is_handled) {
+            let zoom_shortcut = zoom_shortcut(key, self.ui_scale)
+                .filter(|_| env.get(theme::ZOOM_SHORTCUTS_ENABLED));
+            if let Some(ui_scale) = zoom_shortcut {
+                self.set_ui_scale(ui_scale, debug_logger, command_queue, action_queue, env);
+                is_handled = Handled::Yes;
+            }
+        }
+
+        if let Event::KeyDown(key) | Event::KeyUp(key) = &event {
+            self.show_access_keys(key, command_queue);
+        }
+        if let (Event::KeyDown(key), Handled::No) = (&event, is_handled) {
+            if self.handle_access_key(key, debug_logger, command_queue, action_queue, env) {
+                is_handled = Handled::Yes;
+            }
+        }
+
+        if matches!(
+            (event, self.size_policy),
+            (Event::WindowSize(_), WindowSizePolicy::Content)
+        ) {
+            // Because our initial size can be zero, the window system won't ask us to paint.
+            // So layout ourselves and hopefully we resize
+            self.layout(debug_logger, command_queue, action_queue, env);
+        }
+
         self.root.as_dyn().debug_validate(false);
 
         is_handled
@@ -1144,6 +2799,28 @@ impl WindowRoot {
         env: &Env,
         process_commands: bool,
     ) {
+        self.passes.begin(Pass::LifeCycle, self.root.state());
+        let old_widget_env = self
+            .widget_env_cache
+            .as_ref()
+            .map(|(_, widget_env)| widget_env.clone());
+        let widget_env = self.widget_env(env);
+        // Changing the app's env can change other keys in the widgets' env, eg
+        // enabling high contrast changes the colors.
+        let widget_event;
+        let event = match (event, old_widget_env) {
+            (
+                LifeCycle::Internal(InternalLifeCycle::RouteEnvChanged(changed_keys)),
+                Some(old_widget_env),
+            ) if !old_widget_env.same(&widget_env) => {
+                let mut changed_keys = (**changed_keys).clone();
+                changed_keys.extend(old_widget_env.changed_keys(&widget_env));
+                widget_event =
+                    LifeCycle::Internal(InternalLifeCycle::RouteEnvChanged(Rc::new(changed_keys)));
+                &widget_event
+            }
+            _ => event,
+        };
         let mut widget_state = WidgetState::new(self.root.id(), Some(self.size), "<root>");
         let mut global_state = GlobalPassCtx::new(
             self.ext_event_sink.clone(),
@@ -1151,11 +2828,26 @@ impl WindowRoot {
             command_queue,
             action_queue,
             &mut self.timers,
+            &mut self.broadcast_listeners,
+            &mut self.access_keys,
             self.mock_timer_queue.as_mut(),
             &self.handle,
             self.id,
             self.focus,
+            &self.style_sheet,
+            self.app_store.clone(),
+            self.image_cache.clone(),
+            self.text_metrics.clone(),
+            &mut self.offscreen_devices,
+            &mut self.mutate_callbacks,
+            self.safe_area,
+            self.frame_stats,
+            self.is_window_visible,
+            self.reduced_motion(env),
+            self.ui_scale,
+            self.telemetry.clone(),
         );
+        global_state.pass = self.passes.current();
         let mut ctx = LifeCycleCtx {
             global_state: &mut global_state,
             widget_state: &mut widget_state,
@@ -1166,9 +2858,15 @@ impl WindowRoot {
                 .debug_logger
                 .push_important_span(&format!("LIFECYCLE {}", event.short_name()));
             let _span = info_span!("lifecycle").entered();
-            self.root.lifecycle(&mut ctx, event, env);
+            self.root.lifecycle(&mut ctx, event, &widget_env);
             ctx.global_state.debug_logger.pop_span();
         }
+        self.passes.end(Pass::LifeCycle);
+
+        if let LifeCycle::Internal(InternalLifeCycle::ParentWindowOrigin) = event {
+            self.update_backdrop();
+            self.update_popup_anchor_origins();
+        }
 
         self.post_event_processing(
             &mut widget_state,
@@ -1180,18 +2878,202 @@ impl WindowRoot {
         );
     }
 
+    /// Collect the UI state of the window, to be restored later.
+    ///
+    /// See [`ui_state`](crate::ui_state) for details.
+    pub fn save_ui_state(&self) -> UiState {
+        let mut widgets = HashMap::new();
+        collect_widget_states(self.root.as_dyn(), &mut widgets);
+        UiState {
+            geometry: Some(WindowGeometry {
+                position: self.handle.get_position(),
+                size: self.size,
+                state: self.handle.get_window_state(),
+            }),
+            focus: self.focus,
+            widgets,
+        }
+    }
+
+    /// Restore a [`UiState`] previously returned by [`save_ui_state`](Self::save_ui_state).
+    ///
+    /// Widget states are restored before focus, so that eg a scroll area can restore its
+    /// offset before the focused widget is scrolled into view.
+    pub(crate) fn restore_ui_state(
+        &mut self,
+        state: UiState,
+        debug_logger: &mut DebugLogger,
+        command_queue: &mut CommandQueue,
+        action_queue: &mut ActionQueue,
+        env: &Env,
+    ) {
+        if let Some(geometry) = state.geometry {
+            self.handle.set_position(geometry.position);
+            self.handle.set_size(geometry.size);
+            self.handle.set_window_state(geometry.state);
+        }
+
+        if !state.widgets.is_empty() {
+            let event =
+                LifeCycle::Internal(InternalLifeCycle::RouteRestoreState(Rc::new(state.widgets)));
+            self.lifecycle(
+                &event,
+                debug_logger,
+                command_queue,
+                action_queue,
+                env,
+                false,
+            );
+        }
+
+        if let Some(focus) = state.focus {
+            if self.may_contain_widget(focus) {
+                let mut widget_state = WidgetState::new(self.root.id(), Some(self.size), "<root>");
+                widget_state.request_focus = Some(FocusChange::Focus(focus));
+                self.post_event_processing(
+                    &mut widget_state,
+                    debug_logger,
+                    command_queue,
+                    action_queue,
+                    env,
+                    false,
+                );
+            } else {
+                warn!(
+                    "Cannot restore focus to widget {:?}, it doesn't exist",
+                    focus
+                );
+            }
+        }
+    }
+
+    /// Paint a widget and its children into a new bitmap, with `scale` pixels per
+    /// display point.
+    ///
+    /// Returns `None` if the widget isn't in this window, or is stashed.
+    pub(crate) fn capture_widget(
+        &mut self,
+        widget_id: WidgetId,
+        scale: f64,
+        debug_logger: &mut DebugLogger,
+        command_queue: &mut CommandQueue,
+        action_queue: &mut ActionQueue,
+        env: &Env,
+    ) -> Option<ImageBuf> {
+        if !self.may_contain_widget(widget_id) {
+            return None;
+        }
+        if self.root.state().needs_layout {
+            self.layout(debug_logger, command_queue, action_queue, env);
+        }
+        let capture = Rc::new(WidgetCapture::new(widget_id, scale));
+        let event = LifeCycle::Internal(InternalLifeCycle::RouteCapture(capture.clone()));
+        self.lifecycle(
+            &event,
+            debug_logger,
+            command_queue,
+            action_queue,
+            env,
+            false,
+        );
+        capture.image.take()
+    }
+
+    /// Lay out and paint a widget and its children onto pages.
+    ///
+    /// Returns `None` if the widget isn't in this window, or is stashed.
+    pub(crate) fn print_widget(
+        &mut self,
+        widget_id: WidgetId,
+        setup: PageSetup,
+        debug_logger: &mut DebugLogger,
+        command_queue: &mut CommandQueue,
+        action_queue: &mut ActionQueue,
+        env: &Env,
+    ) -> Option<Vec<ImageBuf>> {
+        if !self.may_contain_widget(widget_id) {
+            return None;
+        }
+        if self.root.state().needs_layout {
+            self.layout(debug_logger, command_queue, action_queue, env);
+        }
+        let job = Rc::new(PrintJob::new(widget_id, setup));
+        let event = LifeCycle::Internal(InternalLifeCycle::RoutePrint(job.clone()));
+        self.lifecycle(
+            &event,
+            debug_logger,
+            command_queue,
+            action_queue,
+            env,
+            false,
+        );
+        let pages = job.pages.take()?;
+        // Lay out the printed widget in the window again.
+        self.layout(debug_logger, command_queue, action_queue, env);
+        self.handle.invalidate();
+        Some(pages)
+    }
+
+    /// Run the mutations scheduled with [`EventCtx::mutate_later`] and friends.
+    ///
+    /// Mutations scheduled while these run are left for the next pass.
+    fn run_mutate_callbacks(
+        &mut self,
+        debug_logger: &mut DebugLogger,
+        command_queue: &mut CommandQueue,
+        action_queue: &mut ActionQueue,
+        env: &Env,
+    ) {
+        let root = self.root.as_dyn();
+        self.mutate_callbacks.retain(|callback| {
+            let target_exists = root.find_widget_by_id(callback.id).is_some();
+            if !target_exists {
+                warn!(
+                    "Widget {:?} was removed, skipping scheduled mutation",
+                    callback.id
+                );
+            }
+            target_exists
+        });
+        if self.mutate_callbacks.is_empty() {
+            return;
+        }
+
+        self.is_running_mutate_callbacks = true;
+        self.lifecycle(
+            &LifeCycle::Internal(InternalLifeCycle::RouteMutations),
+            debug_logger,
+            command_queue,
+            action_queue,
+            env,
+            false,
+        );
+        self.is_running_mutate_callbacks = false;
+    }
+
     pub(crate) fn invalidate_paint_region(&mut self) {
         if self.root.state().needs_layout {
             // TODO - this might be too coarse
             self.handle.invalidate();
         } else {
-            for rect in self.invalid.rects() {
+            let damage = damage::coalesce(&self.invalid, self.size.to_rect());
+            for rect in damage.rects() {
                 self.handle.invalidate_rect(*rect);
             }
         }
         self.invalid.clear();
     }
 
+    /// Counters of how much of the window was presented in each frame.
+    pub fn present_stats(&self) -> PresentStats {
+        self.present_stats
+    }
+
+    /// Timings and counters of the frames painted by the window.
+    pub fn frame_stats(&self) -> FrameStats {
+        self.frame_stats
+    }
+
     #[allow(dead_code)]
     pub(crate) fn invalid(&self) -> &Region {
         &self.invalid
@@ -1216,7 +3098,15 @@ impl WindowRoot {
         //
         // See https://github.com/linebender/druid/issues/85 for discussion.
         let last = self.last_anim.take();
+        // The platform may paint more often than the low-power frame rate, eg on resize.
+        if let Some(last) = last.filter(|_| self.is_low_power(env)) {
+            if now.saturating_duration_since(last) < LOW_POWER_FRAME_INTERVAL {
+                self.last_anim = Some(last);
+                return;
+            }
+        }
         let elapsed_ns = last.map(|t| now.duration_since(t).as_nanos()).unwrap_or(0) as u64;
+        let elapsed_ns = (elapsed_ns as f64 * env.get(Env::DEBUG_ANIMATION_SPEED)) as u64;
 
         if self.wants_animation_frame() {
             self.event(
@@ -1227,6 +3117,7 @@ impl WindowRoot {
                 env,
             );
             self.last_anim = Some(now);
+            self.frame_timings.anim_frame = now.elapsed();
         }
     }
 
@@ -1239,19 +3130,116 @@ impl WindowRoot {
         action_queue: &mut ActionQueue,
         env: &Env,
     ) {
+        let layout_start = Instant::now();
         if self.root.state().needs_layout {
             self.layout(debug_logger, command_queue, action_queue, env);
         }
+        self.frame_timings.layout = layout_start.elapsed();
+        self.present_stats.record(invalid, self.size);
+        self.frame_history
+            .set_capacity(env.get(Env::DEBUG_FRAME_HISTORY) as usize);
+
+        let paint_start = Instant::now();
+        let background = self.background_color(env);
+        if let Some(frame) = self.frame_history.scrubbed_frame() {
+            for &rect in invalid.rects() {
+                piet.clear(Some(rect), background.clone());
+            }
+            frame.paint(piet, self.size, invalid);
+        } else {
+            self.paint_widgets(
+                piet,
+                invalid,
+                debug_logger,
+                command_queue,
+                action_queue,
+                env,
+            );
+        }
+        self.frame_timings.paint = paint_start.elapsed();
+        self.frame_stats.record_frame(
+            Instant::now(),
+            std::mem::take(&mut self.frame_timings),
+            damage::presented_area(invalid, self.size),
+            self.size.area(),
+        );
+
+        if self.frame_history.is_recording() {
+            self.record_frame(debug_logger, command_queue, action_queue, env);
+        }
+
+        // Mutations scheduled during paint can only be shown in the next frame.
+        if !self.mutate_callbacks.is_empty() {
+            self.run_mutate_callbacks(debug_logger, command_queue, action_queue, env);
+            if self.root.state().needs_layout || !self.invalid.is_empty() {
+                self.handle.request_anim_frame();
+            }
+        }
+    }
+
+    /// Paint the widget tree over the invalid region, with the window's render backend.
+    fn paint_widgets(
+        &mut self,
+        piet: &mut Piet,
+        invalid: &Region,
+        debug_logger: &mut DebugLogger,
+        command_queue: &mut CommandQueue,
+        action_queue: &mut ActionQueue,
+        env: &Env,
+    ) {
+        match self.render_backend {
+            RenderBackend::Platform => {
+                self.paint_invalid(
+                    piet,
+                    invalid,
+                    debug_logger,
+                    command_queue,
+                    action_queue,
+                    env,
+                );
+            }
+            RenderBackend::Software => {
+                let scale = self.handle.get_scale().map_or(1.0, |scale| scale.x());
+                let size = self.size;
+                let mut renderer = std::mem::take(&mut self.software_renderer);
+                let frame = renderer.render(size, scale, |piet| {
+                    self.paint_invalid(
+                        piet,
+                        invalid,
+                        debug_logger,
+                        command_queue,
+                        action_queue,
+                        env,
+                    );
+                });
+                self.software_renderer = renderer;
+                if let Some(frame) = frame {
+                    render_backend::present_software_frame(piet, &frame, scale, invalid);
+                }
+            }
+        }
+    }
 
+    /// Clear the invalid region, and paint the widget tree over it.
+    fn paint_invalid(
+        &mut self,
+        piet: &mut Piet,
+        invalid: &Region,
+        debug_logger: &mut DebugLogger,
+        command_queue: &mut CommandQueue,
+        action_queue: &mut ActionQueue,
+        env: &Env,
+    ) {
+        let background = self.background_color(env);
         for &r in invalid.rects() {
-            piet.clear(
-                Some(r),
-                if self.transparent {
-                    Color::TRANSPARENT
-                } else {
-                    env.get(crate::theme::WINDOW_BACKGROUND_COLOR)
-                },
-            );
+            piet.clear(Some(r), background.clone());
+            // The backdrop shows through the regions, under their tint.
+            for region in self.shown_backdrop_regions() {
+                let hole = r.intersect(*region);
+                if hole.area() > 0.0 {
+                    piet.clear(Some(hole), Color::TRANSPARENT);
+                }
+            }
         }
         self.paint(
             piet,
@@ -1261,6 +3249,46 @@ impl WindowRoot {
             action_queue,
             env,
         );
+
+        if env.get(Env::DEBUG_PAINT_FLASHING) {
+            self.paint_flash_count += 1;
+            let tint = env.get_debug_color(self.paint_flash_count).with_alpha(0.3);
+            for &rect in invalid.rects() {
+                piet.fill(rect, &tint);
+            }
+        }
+    }
+
+    fn background_color(&mut self, env: &Env) -> Color {
+        if self.transparent {
+            Color::TRANSPARENT
+        } else {
+            self.widget_env(env).get(theme::WINDOW_BACKGROUND_COLOR)
+        }
+    }
+
+    /// Keep an image of the window and the layout of its widgets, for
+    /// [`SCRUB_FRAME`](sys_cmd::SCRUB_FRAME).
+    fn record_frame(
+        &mut self,
+        debug_logger: &mut DebugLogger,
+        command_queue: &mut CommandQueue,
+        action_queue: &mut ActionQueue,
+        env: &Env,
+    ) {
+        let scale = self.handle.get_scale().map_or(1.0, |scale| scale.x());
+        let Some(image) = self.capture_widget(
+            self.root.id(),
+            scale,
+            debug_logger,
+            command_queue,
+            action_queue,
+            env,
+        ) else {
+            return;
+        };
+        let scene = headless::scene_node(self.root.as_dyn(), env);
+        self.frame_history.record(scene, image);
     }
 
     pub(crate) fn layout(
@@ -1270,6 +3298,8 @@ impl WindowRoot {
         action_queue: &mut ActionQueue,
         env: &Env,
     ) {
+        self.passes.begin(Pass::Layout, self.root.state());
+        let widget_env = self.widget_env(env);
         let mut widget_state = WidgetState::new(self.root.id(), Some(self.size), "<root>");
         let mut global_state = GlobalPassCtx::new(
             self.ext_event_sink.clone(),
@@ -1277,15 +3307,35 @@ impl WindowRoot {
             command_queue,
             action_queue,
             &mut self.timers,
+            &mut self.broadcast_listeners,
+            &mut self.access_keys,
             self.mock_timer_queue.as_mut(),
             &self.handle,
             self.id,
             self.focus,
+            &self.style_sheet,
+            self.app_store.clone(),
+            self.image_cache.clone(),
+            self.text_metrics.clone(),
+            &mut self.offscreen_devices,
+            &mut self.mutate_callbacks,
+            self.safe_area,
+            self.frame_stats,
+            self.is_window_visible,
+            self.reduced_motion(env),
+            self.ui_scale,
+            self.telemetry.clone(),
         );
+        global_state.pass = self.passes.current();
+        if self.integer_layout {
+            let scale = self.handle.get_scale().unwrap_or(Scale::new(1.0, 1.0));
+            global_state.pixel_scale = Some(scale);
+        }
         let mut layout_ctx = LayoutCtx {
             global_state: &mut global_state,
             widget_state: &mut widget_state,
             mouse_pos: self.last_mouse_pos,
+            viewport: None,
         };
         let bc = match self.size_policy {
             WindowSizePolicy::User => BoxConstraints::tight(self.size),
@@ -1298,7 +3348,7 @@ impl WindowRoot {
                 .debug_logger
                 .push_important_span("LAYOUT");
             let _span = info_span!("layout").entered();
-            self.root.layout(&mut layout_ctx, &bc, env)
+            self.root.layout(&mut layout_ctx, &bc, &widget_env)
         };
         layout_ctx.global_state.debug_logger.pop_span();
 
@@ -1310,7 +3360,8 @@ impl WindowRoot {
                 self.handle.set_size(full_size)
             }
         }
-        layout_ctx.place_child(&mut self.root, Point::ORIGIN, env);
+        layout_ctx.place_child(&mut self.root, Point::ORIGIN, &widget_env);
+        self.passes.end(Pass::Layout);
         self.lifecycle(
             &LifeCycle::Internal(InternalLifeCycle::ParentWindowOrigin),
             debug_logger,
@@ -1338,6 +3389,9 @@ impl WindowRoot {
         action_queue: &mut ActionQueue,
         env: &Env,
     ) {
+        self.passes.begin(Pass::Paint, self.root.state());
+        let widget_env = self.widget_env(env);
+        let focus_ring = self.focus_ring(&widget_env);
         let widget_state = WidgetState::new(self.root.id(), Some(self.size), "<root>");
         let mut global_state = GlobalPassCtx::new(
             self.ext_event_sink.clone(),
@@ -1345,11 +3399,26 @@ impl WindowRoot {
             command_queue,
             action_queue,
             &mut self.timers,
+            &mut self.broadcast_listeners,
+            &mut self.access_keys,
             self.mock_timer_queue.as_mut(),
             &self.handle,
             self.id,
             self.focus,
+            &self.style_sheet,
+            self.app_store.clone(),
+            self.image_cache.clone(),
+            self.text_metrics.clone(),
+            &mut self.offscreen_devices,
+            &mut self.mutate_callbacks,
+            self.safe_area,
+            self.frame_stats,
+            self.is_window_visible,
+            self.reduced_motion(env),
+            self.ui_scale,
+            self.telemetry.clone(),
         );
+        global_state.pass = self.passes.current();
         let mut ctx = PaintCtx {
             render_ctx: piet,
             global_state: &mut global_state,
@@ -1361,7 +3430,7 @@ impl WindowRoot {
 
         let root = &mut self.root;
         info_span!("paint").in_scope(|| {
-            ctx.with_child_ctx(invalid.clone(), |ctx| root.paint_raw(ctx, env));
+            ctx.with_child_ctx(invalid.clone(), |ctx| root.paint_raw(ctx, &widget_env));
         });
 
         let mut z_ops = std::mem::take(&mut ctx.z_ops);
@@ -1376,9 +3445,19 @@ impl WindowRoot {
             });
         }
 
-        if self.wants_animation_frame() {
-            self.handle.request_anim_frame();
+        if let Some((ring, clip)) = focus_ring {
+            ctx.with_save(|ctx| {
+                ctx.clip(clip);
+                ctx.stroke(
+                    ring,
+                    &widget_env.get(theme::FOCUS_RING_COLOR),
+                    widget_env.get(theme::FOCUS_RING_WIDTH),
+                );
+            });
         }
+        self.passes.end(Pass::Paint);
+
+        self.request_anim_frame(env);
     }
 
     pub(crate) fn get_ime_handler(
@@ -1430,7 +3509,9 @@ impl WindowRoot {
                     env,
                     false,
                 );
+                self.invalidate_focus_ring(env);
                 self.focus = new;
+                self.invalidate_focus_ring(env);
                 // check if the newly focused widget has an IME session, and
                 // notify the system if so.
                 //
@@ -1460,6 +3541,442 @@ impl WindowRoot {
         }
     }
 
+    /// The focus ring around the focused widget, in window coordinates, and the
+    /// rect it should be clipped to.
+    ///
+    /// The ring follows the widget's border box and corner radius, and is clipped
+    /// by the scroll areas containing the widget. Returns `None` if no widget has
+    /// focus or if the focused widget draws its own focus indicator.
+    fn focus_ring(&self, env: &Env) -> Option<(RoundedRect, Rect)> {
+        let window_rect = self.size.to_rect();
+        let (widget, clip) = find_widget_and_clip(self.root.as_dyn(), self.focus?, window_rect)?;
+        let state = widget.state();
+        if state.draws_own_focus_ring || state.is_stashed {
+            return None;
+        }
+
+        let offset = env.get(theme::FOCUS_RING_OFFSET) + env.get(theme::FOCUS_RING_WIDTH) / 2.0;
+        let rect = (state.window_layout_rect() + state.style_insets).inflate(offset, offset);
+        let radii = state.computed_style.corner_radius.resolve(env);
+        let outset = |radius: f64| if radius > 0.0 { radius + offset } else { 0.0 };
+        let radii = RoundedRectRadii::new(
+            outset(radii.top_left),
+            outset(radii.top_right),
+            outset(radii.bottom_right),
+            outset(radii.bottom_left),
+        );
+        Some((rect.to_rounded_rect(radii), clip))
+    }
+
+    fn invalidate_focus_ring(&mut self, env: &Env) {
+        if let Some((ring, clip)) = self.focus_ring(env) {
+            let half_width = env.get(theme::FOCUS_RING_WIDTH) / 2.0;
+            let rect = ring.rect().inflate(half_width, half_width).intersect(clip);
+            if rect.area() != 0.0 {
+                self.invalid.add_rect(rect);
+            }
+        }
+    }
+
+    /// Notify the window that it gained or lost focus.
+    ///
+    /// Returns the [`Event::CaretBlink`] to send to the focused widget, if any.
+    pub(crate) fn set_window_focused(&mut self, is_focused: bool, env: &Env) -> Option<Event> {
+        self.is_window_focused = is_focused;
+        if !self.focused_widget_wants_caret_blink() {
+            return None;
+        }
+        if is_focused {
+            self.reset_caret_blink(env);
+        } else {
+            self.caret_blink.is_visible = false;
+        }
+        Some(Event::CaretBlink(self.caret_blink.is_visible))
+    }
+
+    fn focused_widget_wants_caret_blink(&self) -> bool {
+        self.focused_widget()
+            .map_or(false, |widget| widget.state().wants_caret_blink)
+    }
+
+    /// Make the caret visible, and make sure the blink timer is running if needed.
+    fn reset_caret_blink(&mut self, env: &Env) {
+        self.caret_blink.is_visible = true;
+        self.caret_blink.skip_next_toggle = true;
+        if self.caret_blink.timer.is_none()
+            && self.is_window_focused
+            && self.focused_widget_wants_caret_blink()
+        {
+            self.request_caret_blink_timer(env);
+        }
+    }
+
+    /// Toggle the caret after the blink timer fired.
+    ///
+    /// Returns `false` if the blink cycle stopped, because the window or the
+    /// widget lost focus.
+    fn advance_caret_blink(&mut self, env: &Env) -> bool {
+        if !self.is_window_focused
+            || !self.is_window_visible
+            || !self.focused_widget_wants_caret_blink()
+        {
+            return false;
+        }
+        if std::mem::take(&mut self.caret_blink.skip_next_toggle) {
+            self.caret_blink.is_visible = true;
+        } else {
+            self.caret_blink.is_visible = !self.caret_blink.is_visible;
+        }
+        self.request_caret_blink_timer(env);
+        true
+    }
+
+    fn request_caret_blink_timer(&mut self, env: &Env) {
+        let interval = Duration::from_millis(env.get(theme::CURSOR_BLINK_INTERVAL));
+        self.caret_blink.timer = Some(self.request_window_timer(interval));
+    }
+
+    fn focused_key_repeat(&self) -> KeyRepeat {
+        self.focused_widget()
+            .map_or(KeyRepeat::Platform, |widget| widget.state().key_repeat)
+    }
+
+    /// The number of clicks a mouse-down is part of, eg `2` for the second press of a
+    /// double-click.
+    ///
+    /// Presses of the same button count as one multi-click while each follows the last
+    /// one within `theme::DOUBLE_CLICK_INTERVAL`, and the mouse moves less than
+    /// `theme::DRAG_THRESHOLD`.
+    fn count_clicks(&mut self, mouse: &crate::MouseEvent, env: &Env) -> u8 {
+        let now = self.now();
+        let interval = Duration::from_millis(env.get(theme::DOUBLE_CLICK_INTERVAL));
+        let count = match &self.last_click {
+            Some(last)
+                if last.button == mouse.button
+                    && now.saturating_duration_since(last.time) <= interval
+                    && last.pos.distance(mouse.pos) < env.get(theme::DRAG_THRESHOLD) =>
+            {
+                last.count.saturating_add(1)
+            }
+            _ => 1,
+        };
+        self.last_click = Some(LastClick {
+            time: now,
+            pos: mouse.pos,
+            button: mouse.button,
+            count,
+        });
+        count
+    }
+
+    /// Apply the focused widget's [`KeyRepeat`] to a key event from the platform.
+    ///
+    /// Returns `true` if the event is a repeat that mustn't be sent to the widget tree.
+    fn filter_key_repeat(&mut self, key: &KeyEvent) -> bool {
+        if key.state == KeyState::Up {
+            if self.key_repeat.held.as_ref().map(|held| held.code) == Some(key.code) {
+                self.key_repeat.held = None;
+                self.key_repeat.next_repeat = None;
+            }
+            return false;
+        }
+        match self.focused_key_repeat() {
+            KeyRepeat::Platform => false,
+            KeyRepeat::Disabled => key.repeat,
+            KeyRepeat::Custom { delay, .. } => {
+                if key.repeat {
+                    return true;
+                }
+                self.key_repeat.held = Some(key.clone());
+                self.key_repeat.next_repeat = Some(self.now() + delay);
+                if self.key_repeat.timer.is_none() {
+                    self.key_repeat.timer = Some(self.request_window_timer(delay));
+                }
+                false
+            }
+        }
+    }
+
+    /// Return the repeat of the held key to send, if it's time.
+    fn next_key_repeat(&mut self) -> Option<KeyEvent> {
+        let KeyRepeat::Custom { interval, .. } = self.focused_key_repeat() else {
+            // Focus moved to a widget that doesn't repeat keys itself.
+            self.key_repeat.held = None;
+            return None;
+        };
+        let held = self.key_repeat.held.clone()?;
+        let next_repeat = self.key_repeat.next_repeat?;
+        let now = self.now();
+        if now < next_repeat {
+            self.key_repeat.timer = Some(self.request_window_timer(next_repeat - now));
+            return None;
+        }
+        self.key_repeat.next_repeat = Some(now + interval);
+        self.key_repeat.timer = Some(self.request_window_timer(interval));
+        Some(KeyEvent {
+            repeat: true,
+            ..held
+        })
+    }
+
+    /// Request a timer handled by the window itself, rather than by a widget.
+    fn request_window_timer(&mut self, interval: Duration) -> TimerToken {
+        if let Some(timer_queue) = self.mock_timer_queue.as_mut() {
+            timer_queue.add_timer(interval)
+        } else {
+            self.handle.request_timer(interval)
+        }
+    }
+
+    fn now(&self) -> Instant {
+        match &self.mock_timer_queue {
+            Some(timer_queue) => timer_queue.now(),
+            None => Instant::now(),
+        }
+    }
+
+    /// Match a key press against the window's shortcuts.
+    ///
+    /// Returns `true` if the key press was part of a shortcut, and mustn't be sent
+    /// to the widget tree.
+    fn handle_shortcut(&mut self, key: &KeyEvent, command_queue: &mut CommandQueue) -> bool {
+        if self.shortcuts.is_empty() || KeyStroke::is_modifier(&key.key) {
+            return false;
+        }
+        let stroke = KeyStroke::from_event(key);
+        match self
+            .shortcuts
+            .match_stroke(&self.pending_chord.strokes, &stroke)
+        {
+            ChordMatch::None => false,
+            ChordMatch::Cancelled => {
+                info!("Cancelled shortcut chord: {} is not bound", stroke);
+                self.set_pending_chord(Vec::new(), command_queue);
+                true
+            }
+            ChordMatch::Complete(command) => {
+                let command = command.clone().default_to(Target::Window(self.id));
+                command_queue.push_back(command);
+                self.set_pending_chord(Vec::new(), command_queue);
+                true
+            }
+            ChordMatch::Prefix => {
+                let mut strokes = std::mem::take(&mut self.pending_chord.strokes);
+                strokes.push(stroke);
+                self.set_pending_chord(strokes, command_queue);
+
+                let timeout = self.shortcuts.chord_timeout();
+                self.pending_chord.expires_at = Some(self.now() + timeout);
+                if self.pending_chord.timer.is_none() {
+                    self.pending_chord.timer = Some(self.request_window_timer(timeout));
+                }
+                true
+            }
+        }
+    }
+
+    /// Tell the labels with an access key to underline it while Alt is held.
+    fn show_access_keys(&mut self, key: &KeyEvent, command_queue: &mut CommandQueue) {
+        let is_shown = match key.state {
+            KeyState::Down => key.key == KbKey::Alt || key.mods.alt(),
+            KeyState::Up => key.key != KbKey::Alt && key.mods.alt(),
+        };
+        for label in self.access_keys.set_shown(is_shown, self.root.state()) {
+            command_queue.push_back(
+                access_keys::SHOW_ACCESS_KEYS
+                    .with(is_shown)
+                    .to(Target::Widget(label)),
+            );
+        }
+    }
+
+    /// Activate the widget whose access key was pressed with Alt.
+    ///
+    /// Returns `true` if the key press matched an access key.
+    fn handle_access_key(
+        &mut self,
+        key: &KeyEvent,
+        debug_logger: &mut DebugLogger,
+        command_queue: &mut CommandQueue,
+        action_queue: &mut ActionQueue,
+        env: &Env,
+    ) -> bool {
+        let mods = key.mods & (Modifiers::CONTROL | Modifiers::ALT | Modifiers::META);
+        let KbKey::Character(text) = &key.key else {
+            return false;
+        };
+        let mut chars = text.chars();
+        let (Some(access_key), None) = (chars.next(), chars.next()) else {
+            return false;
+        };
+        if mods != Modifiers::ALT {
+            return false;
+        }
+
+        let targets = self
+            .access_keys
+            .targets(access_key, self.focus, self.root.as_dyn());
+        match targets[..] {
+            [] => false,
+            [target] => {
+                command_queue.push_back(access_keys::ACTIVATE.to(Target::Widget(target)));
+                true
+            }
+            _ => {
+                // The key is ambiguous, so it only moves the focus to the next widget.
+                let next = self
+                    .focus
+                    .and_then(|focus| targets.iter().position(|target| *target == focus))
+                    .map_or(0, |idx| (idx + 1) % targets.len());
+                self.move_focus(
+                    FocusChange::Focus(targets[next]),
+                    debug_logger,
+                    command_queue,
+                    action_queue,
+                    env,
+                );
+                true
+            }
+        }
+    }
+
+    /// Send a gamepad event to the focused widget, and navigate with it if it
+    /// isn't handled.
+    pub(crate) fn gamepad_event(
+        &mut self,
+        event: GamepadEvent,
+        debug_logger: &mut DebugLogger,
+        command_queue: &mut CommandQueue,
+        action_queue: &mut ActionQueue,
+        env: &Env,
+    ) -> Handled {
+        let navigation = self.gamepad_nav.navigate(&event);
+        let handled = self.event(
+            Event::Gamepad(event),
+            debug_logger,
+            command_queue,
+            action_queue,
+            env,
+        );
+        if handled.is_handled() {
+            return handled;
+        }
+
+        let (key, code, pressed) = match navigation {
+            Some(Navigation::Move(direction)) => {
+                if let Some(delay) = self.gamepad_nav.start_repeat(self.now()) {
+                    self.gamepad_nav.repeat_timer = Some(self.request_window_timer(delay));
+                }
+                self.move_focus(
+                    FocusChange::Direction(direction),
+                    debug_logger,
+                    command_queue,
+                    action_queue,
+                    env,
+                );
+                return Handled::Yes;
+            }
+            Some(Navigation::Activate { pressed }) => (KbKey::Enter, Code::Enter, pressed),
+            Some(Navigation::Back { pressed }) => (KbKey::Escape, Code::Escape, pressed),
+            None => return Handled::No,
+        };
+        let key_event = KeyEvent {
+            key,
+            code,
+            state: if pressed {
+                KeyState::Down
+            } else {
+                KeyState::Up
+            },
+            ..Default::default()
+        };
+        let event = if pressed {
+            Event::KeyDown(key_event)
+        } else {
+            Event::KeyUp(key_event)
+        };
+        self.event(event, debug_logger, command_queue, action_queue, env)
+    }
+
+    /// Change focus from outside of a widget pass, eg for gamepad navigation.
+    fn move_focus(
+        &mut self,
+        focus: FocusChange,
+        debug_logger: &mut DebugLogger,
+        command_queue: &mut CommandQueue,
+        action_queue: &mut ActionQueue,
+        env: &Env,
+    ) {
+        let mut widget_state = WidgetState::new(self.root.id(), Some(self.size), "<root>");
+        widget_state.request_focus = Some(focus);
+        self.post_event_processing(
+            &mut widget_state,
+            debug_logger,
+            command_queue,
+            action_queue,
+            env,
+            true,
+        );
+    }
+
+    /// Cancel the pending chord if it expired, or wait for the rest of its timeout.
+    fn check_chord_timeout(&mut self, command_queue: &mut CommandQueue) {
+        let expires_at = match self.pending_chord.expires_at {
+            Some(expires_at) => expires_at,
+            None => return,
+        };
+        let now = self.now();
+        if now >= expires_at {
+            self.set_pending_chord(Vec::new(), command_queue);
+        } else {
+            // A stroke extended the chord since the timer was requested.
+            self.pending_chord.timer = Some(self.request_window_timer(expires_at - now));
+        }
+    }
+
+    fn set_pending_chord(&mut self, strokes: Vec<KeyStroke>, command_queue: &mut CommandQueue) {
+        if strokes.is_empty() {
+            self.pending_chord.expires_at = None;
+            if self.pending_chord.strokes.is_empty() {
+                return;
+            }
+        }
+        self.pending_chord.strokes = strokes;
+        let chord = self.pending_chord.strokes.clone().into();
+        command_queue.push_back(
+            PENDING_CHORD_CHANGED
+                .with(chord)
+                .to(Target::Window(self.id)),
+        );
+    }
+
+    /// The strokes typed so far of a multi-stroke shortcut.
+    pub fn pending_chord(&self) -> &[KeyStroke] {
+        &self.pending_chord.strokes
+    }
+
+    fn show_context_menu(&mut self, menu: ContextMenu, position: Point) {
+        if menu.is_empty() {
+            return;
+        }
+        self.handle
+            .show_context_menu(menu.to_platform_menu(), position);
+        self.context_menu = Some(menu);
+    }
+
+    /// The context menu shown most recently in this window, if the user hasn't
+    /// picked one of its items yet.
+    pub fn context_menu(&self) -> Option<&ContextMenu> {
+        self.context_menu.as_ref()
+    }
+
+    /// The command of the context menu item the platform reported as picked.
+    pub(crate) fn take_context_menu_command(&mut self, id: u32) -> Option<Command> {
+        let menu = self.context_menu.take()?;
+        let command = menu.command(id)?.clone();
+        Some(command.default_to(self.id.into()))
+    }
+
     /// Create a function that can invalidate the provided widget's text state.
     ///
     /// This will be called from outside the main app state in order to avoid
@@ -1503,9 +4020,46 @@ impl WindowRoot {
             FocusChange::Focus(id) => Some(id),
             FocusChange::Next => self.widget_from_focus_chain(true),
             FocusChange::Previous => self.widget_from_focus_chain(false),
+            FocusChange::Direction(direction) => self.widget_in_direction(direction),
         }
     }
 
+    /// Find the focusable widget closest to the focused one in the given direction.
+    ///
+    /// Widgets roughly aligned with the focused one are preferred. If no widget
+    /// is focused, returns the first widget in the focus chain. If there's no
+    /// widget in that direction, focus doesn't change.
+    fn widget_in_direction(&self, direction: NavDirection) -> Option<WidgetId> {
+        let Some(from) = self
+            .focus
+            .and_then(|focus| self.find_widget_by_id(focus))
+            .map(|widget| widget.state().window_layout_rect())
+        else {
+            return self.focus_chain().first().copied();
+        };
+        let from_center = from.center();
+        let closest = self
+            .focus_chain()
+            .iter()
+            .filter(|id| Some(**id) != self.focus)
+            .filter_map(|id| {
+                let rect = self.find_widget_by_id(*id)?.state().window_layout_rect();
+                let offset = rect.center() - from_center;
+                let (along, across) = match direction {
+                    NavDirection::Up => (-offset.y, offset.x),
+                    NavDirection::Down => (offset.y, offset.x),
+                    NavDirection::Left => (-offset.x, offset.y),
+                    NavDirection::Right => (offset.x, offset.y),
+                };
+                if along <= 0.0 {
+                    return None;
+                }
+                Some((along + 2.0 * across.abs(), *id))
+            })
+            .min_by(|(a, _), (b, _)| a.total_cmp(b));
+        closest.map(|(_, id)| id).or(self.focus)
+    }
+
     fn widget_from_focus_chain(&self, forward: bool) -> Option<WidgetId> {
         self.focus.and_then(|focus| {
             self.focus_chain()
@@ -1539,6 +4093,12 @@ impl WindowRoot {
         self.root.as_dyn()
     }
 
+    /// The position of the top left corner of the content area on the virtual screen.
+    pub(crate) fn content_origin(&self) -> Point {
+        let insets = self.handle.content_insets();
+        self.handle.get_position() + Vec2::new(insets.x0, insets.y0)
+    }
+
     /// Try to return the widget with the given id.
     pub fn find_widget_by_id(&self, id: WidgetId) -> Option<WidgetRef<'_, dyn Widget>> {
         self.root.as_dyn().find_widget_by_id(id)
@@ -1554,3 +4114,54 @@ impl WindowRoot {
         self.find_widget_by_id(self.focus?)
     }
 }
+
+/// The UI scale after a zoom shortcut, if `event` is one: Ctrl+= or Ctrl++ zooms in,
+/// Ctrl+- zooms out and Ctrl+0 resets the scale (Cmd on macOS).
+fn zoom_shortcut(event: &KeyEvent, ui_scale: f64) -> Option<f64> {
+    let mods =
+        event.mods & (Modifiers::CONTROL | Modifiers::ALT | Modifiers::META | Modifiers::SHIFT);
+    let primary = if cfg!(target_os = "macos") {
+        Modifiers::META
+    } else {
+        Modifiers::CONTROL
+    };
+    let KbKey::Character(text) = &event.key else {
+        return None;
+    };
+    // Shift is only allowed for `+`, since some layouts need it to type it.
+    let allows_shift = text == "+";
+    if mods != primary && !(allows_shift && mods == primary | Modifiers::SHIFT) {
+        return None;
+    }
+    // Counting in steps avoids accumulating rounding errors.
+    let steps = (ui_scale * UI_SCALE_STEPS).round();
+    let steps = match text.as_str() {
+        "=" | "+" => steps + 1.0,
+        "-" => steps - 1.0,
+        "0" => UI_SCALE_STEPS,
+        _ => return None,
+    };
+    Some(steps / UI_SCALE_STEPS)
+}
+
+/// Find the widget with the given id, along with the window-space rect that the
+/// scroll areas containing it clip it to.
+fn find_widget_and_clip<'w>(
+    widget: WidgetRef<'w, dyn Widget>,
+    id: WidgetId,
+    clip: Rect,
+) -> Option<(WidgetRef<'w, dyn Widget>, Rect)> {
+    let state = widget.state();
+    if state.id == id {
+        return Some((widget, clip));
+    }
+    let clip = if state.is_portal {
+        clip.intersect(state.window_layout_rect())
+    } else {
+        clip
+    };
+    widget
+        .children()
+        .into_iter()
+        .find_map(|child| find_widget_and_clip(child, id, clip))
+}
@@ -0,0 +1,203 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Application state shared by the widgets which subscribe to it.
+
+use std::any::Any;
+use std::cell::{Ref, RefCell};
+use std::rc::Rc;
+
+use crate::{Data, WidgetId};
+
+/// Application state, shared by the widgets which display it.
+///
+/// This is a middle ground between setting the values of each widget through its
+/// [`WidgetMut`](crate::widget::WidgetMut) and a full reactive framework. The app
+/// creates a store with its state and passes it to [`AppLauncher::with_store`].
+///
+/// Widgets get the store with [`EventCtx::app_store`], and subscribe to the part of
+/// the state they display with [`LifeCycleCtx::subscribe_to_store`], in response to
+/// [`LifeCycle::WidgetAdded`]. When the state is modified with [`update`], the
+/// widgets whose part changed receive the [`STORE_CHANGED`] command, and are painted
+/// again; other widgets aren't visited.
+///
+/// A store is a handle: its clones share the same state, so the app can keep one,
+/// eg in its [`AppDelegate`], to update the state from outside the widget tree.
+///
+/// [`AppLauncher::with_store`]: crate::AppLauncher::with_store
+/// [`EventCtx::app_store`]: crate::EventCtx::app_store
+/// [`LifeCycleCtx::subscribe_to_store`]: crate::LifeCycleCtx::subscribe_to_store
+/// [`LifeCycle::WidgetAdded`]: crate::LifeCycle::WidgetAdded
+/// [`update`]: AppStore::update
+/// [`STORE_CHANGED`]: crate::command::STORE_CHANGED
+/// [`AppDelegate`]: crate::AppDelegate
+pub struct AppStore<T> {
+    inner: Rc<StoreInner<T>>,
+}
+
+struct StoreInner<T> {
+    state: RefCell<T>,
+    subscriptions: RefCell<Vec<Subscription<T>>>,
+    /// The widgets to send `STORE_CHANGED` to after the current pass.
+    changed_widgets: RefCell<Vec<WidgetId>>,
+}
+
+struct Subscription<T> {
+    widget_id: WidgetId,
+    /// Returns `true` if the selected part of the state changed since the last call.
+    has_changed: Box<dyn FnMut(&T) -> bool>,
+}
+
+impl<T> Clone for AppStore<T> {
+    fn clone(&self) -> Self {
+        AppStore {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T: 'static> AppStore<T> {
+    /// Create a store holding `state`.
+    pub fn new(state: T) -> Self {
+        AppStore {
+            inner: Rc::new(StoreInner {
+                state: RefCell::new(state),
+                subscriptions: RefCell::new(Vec::new()),
+                changed_widgets: RefCell::new(Vec::new()),
+            }),
+        }
+    }
+
+    /// Borrow the state.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called from the closure passed to [`update`](Self::update).
+    pub fn get(&self) -> Ref<'_, T> {
+        self.inner.state.borrow()
+    }
+
+    /// Modify the state, and return the result of `f`.
+    ///
+    /// The widgets whose selection changed receive
+    /// [`STORE_CHANGED`](crate::command::STORE_CHANGED) once the current pass is over.
+    pub fn update<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        let result = f(&mut self.inner.state.borrow_mut());
+
+        let state = self.inner.state.borrow();
+        let mut changed_widgets = self.inner.changed_widgets.borrow_mut();
+        for subscription in self.inner.subscriptions.borrow_mut().iter_mut() {
+            if (subscription.has_changed)(&state)
+                && !changed_widgets.contains(&subscription.widget_id)
+            {
+                changed_widgets.push(subscription.widget_id);
+            }
+        }
+        result
+    }
+
+    pub(crate) fn subscribe<S: Data>(
+        &self,
+        widget_id: WidgetId,
+        select: impl Fn(&T) -> S + 'static,
+    ) {
+        let mut selection = select(&self.inner.state.borrow());
+        let has_changed = move |state: &T| {
+            let new_selection = select(state);
+            if new_selection.same(&selection) {
+                false
+            } else {
+                selection = new_selection;
+                true
+            }
+        };
+        self.inner.subscriptions.borrow_mut().push(Subscription {
+            widget_id,
+            has_changed: Box::new(has_changed),
+        });
+    }
+}
+
+/// An [`AppStore`] of any type, as held by the app and its windows.
+pub(crate) trait AnyAppStore {
+    fn as_any(&self) -> &dyn Any;
+
+    /// Take the widgets to send `STORE_CHANGED` to.
+    ///
+    /// The subscriptions of the widgets for which `is_in_tree` returns `false` are
+    /// dropped.
+    fn take_changed_widgets(&self, is_in_tree: &dyn Fn(WidgetId) -> bool) -> Vec<WidgetId>;
+}
+
+impl<T: 'static> AnyAppStore for AppStore<T> {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn take_changed_widgets(&self, is_in_tree: &dyn Fn(WidgetId) -> bool) -> Vec<WidgetId> {
+        self.inner
+            .subscriptions
+            .borrow_mut()
+            .retain(|subscription| is_in_tree(subscription.widget_id));
+        let mut changed_widgets = self.inner.changed_widgets.borrow_mut();
+        changed_widgets.retain(|id| is_in_tree(*id));
+        std::mem::take(&mut *changed_widgets)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::command::STORE_CHANGED;
+    use crate::testing::{ModularWidget, TestHarness};
+    use crate::widget::Flex;
+    use crate::{Event, LifeCycle};
+
+    struct Counters {
+        a: u64,
+        b: u64,
+    }
+
+    fn subscriber(select: fn(&Counters) -> u64, changes: Rc<Cell<u32>>) -> ModularWidget<()> {
+        ModularWidget::new(())
+            .lifecycle_fn(move |_, ctx, event, _| {
+                if let LifeCycle::WidgetAdded = event {
+                    ctx.subscribe_to_store(select);
+                }
+            })
+            .event_fn(move |_, ctx, event, _| {
+                if let Event::Command(cmd) = event {
+                    if cmd.is(STORE_CHANGED) {
+                        changes.set(changes.get() + 1);
+                        ctx.set_handled();
+                    }
+                }
+            })
+    }
+
+    #[test]
+    fn only_changed_selections_are_notified() {
+        let store = AppStore::new(Counters { a: 0, b: 0 });
+        let a_changes = Rc::new(Cell::new(0));
+        let b_changes = Rc::new(Cell::new(0));
+
+        let widget = Flex::row()
+            .with_child(subscriber(|counters| counters.a, a_changes.clone()))
+            .with_child(subscriber(|counters| counters.b, b_changes.clone()));
+        let mut harness = TestHarness::create_with_store(widget, store.clone());
+
+        harness.edit_root_widget(|_, _| store.update(|counters| counters.a += 1));
+        assert_eq!((a_changes.get(), b_changes.get()), (1, 0));
+
+        // Setting the same value isn't a change.
+        harness.edit_root_widget(|_, _| store.update(|counters| counters.b = 0));
+        assert_eq!((a_changes.get(), b_changes.get()), (1, 0));
+
+        harness.edit_root_widget(|_, _| store.update(|counters| counters.b = 5));
+        assert_eq!((a_changes.get(), b_changes.get()), (1, 1));
+        assert_eq!(store.get().b, 5);
+    }
+}
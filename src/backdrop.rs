@@ -0,0 +1,79 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Translucent window backgrounds, which blur what's behind the window.
+//!
+//! A window created with a [`WindowBackdrop`], with
+//! [`WindowDescription::backdrop`](crate::WindowDescription::backdrop), shows the
+//! blurred desktop through its [`BackdropRegion`] widgets, eg a sidebar; the rest of
+//! the window is painted with its usual background. The backdrop can be changed while
+//! the window is open with [`SET_WINDOW_BACKDROP`](crate::command::SET_WINDOW_BACKDROP).
+//!
+//! The windowing layer can't apply these effects itself: they're applied by the
+//! [`BackdropEffects`] set with
+//! [`AppLauncher::with_backdrop_effects`](crate::AppLauncher::with_backdrop_effects),
+//! using the platform's API (eg DWM system backdrops on Windows, visual effect views on
+//! macOS). Masonry doesn't ship an implementation, so the effects are provided by the
+//! app. Where no effects are set or the platform doesn't support the backdrop, the
+//! regions are painted opaque, so the window looks the same as without a backdrop.
+//!
+//! [`BackdropRegion`]: crate::widget::BackdropRegion
+
+use druid_shell::WindowHandle;
+
+use crate::kurbo::Rect;
+use crate::widget::{BackdropRegion, WidgetRef};
+use crate::Widget;
+
+/// The material shown through the translucent parts of a window.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum WindowBackdrop {
+    /// The window is opaque.
+    #[default]
+    None,
+    /// What's behind the window is blurred.
+    Blur,
+    /// What's behind the window is blurred, tinted and textured, like Windows acrylic.
+    Acrylic,
+    /// The window is tinted with the desktop wallpaper, like Windows mica. This is
+    /// cheaper than a blur, but doesn't show other windows.
+    Mica,
+    /// The system material for sidebars, like macOS vibrancy.
+    Vibrancy,
+}
+
+/// Applies window backdrops with the platform's API, implemented by the app.
+///
+/// See the [module documentation](self).
+pub trait BackdropEffects {
+    /// Show `backdrop` through the `regions` of `window`, in display points relative to
+    /// the window's content area.
+    ///
+    /// This is called when a window with a backdrop is shown, and every time its
+    /// backdrop or regions change; with [`WindowBackdrop::None`] when the backdrop is
+    /// removed. Returns `false` if the platform doesn't support `backdrop`, in which
+    /// case the regions are painted opaque.
+    fn apply(&self, window: &WindowHandle, backdrop: WindowBackdrop, regions: &[Rect]) -> bool;
+}
+
+/// The window rects of the backdrop regions in the tree under `root`.
+pub(crate) fn backdrop_regions(root: WidgetRef<'_, dyn Widget>) -> Vec<Rect> {
+    fn visit(widget: WidgetRef<'_, dyn Widget>, regions: &mut Vec<Rect>) {
+        if widget.state().is_stashed {
+            return;
+        }
+        if widget.downcast::<BackdropRegion>().is_some() {
+            regions.push(widget.state().window_layout_rect());
+            // A region inside a region adds nothing.
+            return;
+        }
+        for child in widget.children() {
+            visit(child, regions);
+        }
+    }
+
+    let mut regions = Vec::new();
+    visit(root, &mut regions);
+    regions
+}
@@ -2,6 +2,8 @@
 // "as-is" basis without warranties of any kind. See the LICENSE file for
 // details.
 
+use druid_shell::{Scalable, Scale};
+
 use crate::kurbo::Size;
 
 /// Constraints for layout.
@@ -156,6 +158,18 @@ impl BoxConstraints {
         BoxConstraints::new(min, max)
     }
 
+    /// Round these constraints to the nearest physical pixels, for the integer layout
+    /// mode.
+    ///
+    /// See [`WindowDescription::integer_layout`](crate::WindowDescription::integer_layout).
+    pub(crate) fn snap_to_pixels(&self, scale: Scale) -> BoxConstraints {
+        // Rounding both bounds to the nearest pixel keeps min <= max.
+        BoxConstraints {
+            min: self.min.to_px(scale).round().to_dp(scale),
+            max: self.max.to_px(scale).round().to_dp(scale),
+        }
+    }
+
     /// Test whether these constraints contain the given `Size`.
     pub fn contains(&self, size: impl Into<Size>) -> bool {
         let size = size.into();
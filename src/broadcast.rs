@@ -0,0 +1,82 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Per-window lists of the widgets interested in broadcast events.
+//!
+//! Some events are sent to every widget which asked for them, rather than to a
+//! single target. Each class is routed from a per-window record, so the event
+//! dispatch only visits the subtrees containing an interested widget:
+//!
+//! - [`Event::AnimFrame`](crate::Event::AnimFrame) goes to the widgets which called
+//!   `request_anim_frame`; the request flag is merged up to every ancestor.
+//! - [`Event::Timer`](crate::Event::Timer) goes to the widget which called
+//!   `request_timer`, looked up in the window's timer list.
+//! - [`Event::Command`](crate::Event::Command) goes to the widgets in [`BroadcastListeners`].
+
+use std::collections::HashMap;
+
+use crate::command::SelectorSymbol;
+use crate::widget::WidgetState;
+use crate::WidgetId;
+
+/// The widgets of a window which want commands sent to the window.
+#[derive(Debug, Default)]
+pub(crate) struct BroadcastListeners {
+    /// The widgets which registered a handler for each selector.
+    pub(crate) command_handlers: HashMap<SelectorSymbol, Vec<WidgetId>>,
+    /// The widgets which registered for every command, in windows that opted in
+    /// to command listeners.
+    pub(crate) commands: Vec<WidgetId>,
+}
+
+impl BroadcastListeners {
+    /// Add `id` to `list` if it isn't already in it.
+    pub(crate) fn add(list: &mut Vec<WidgetId>, id: WidgetId) {
+        if !list.contains(&id) {
+            list.push(id);
+        }
+    }
+
+    /// Add the listeners registered in `other`, eg by a widget which wasn't in the
+    /// tree yet.
+    pub(crate) fn append(&mut self, other: BroadcastListeners) {
+        for (symbol, ids) in other.command_handlers {
+            let handlers = self.command_handlers.entry(symbol).or_default();
+            for id in ids {
+                Self::add(handlers, id);
+            }
+        }
+        for id in other.commands {
+            Self::add(&mut self.commands, id);
+        }
+    }
+
+    /// The listeners a command with the given selector should be sent to, or `None`
+    /// if it should be broadcast to the whole tree.
+    ///
+    /// Listeners which were removed from the tree are dropped from the lists. The
+    /// bloom filter can return false positives, so a few may linger; commands sent
+    /// to them are ignored.
+    pub(crate) fn command_listeners(
+        &mut self,
+        symbol: SelectorSymbol,
+        root_state: &WidgetState,
+        opt_in: bool,
+    ) -> Option<Vec<WidgetId>> {
+        let is_in_tree =
+            |id: &WidgetId| *id == root_state.id || root_state.children.may_contain(id);
+
+        if let Some(handlers) = self.command_handlers.get_mut(symbol) {
+            handlers.retain(is_in_tree);
+            if !handlers.is_empty() {
+                return Some(handlers.clone());
+            }
+        }
+        if opt_in {
+            self.commands.retain(is_in_tree);
+            return Some(self.commands.clone());
+        }
+        None
+    }
+}
@@ -0,0 +1,44 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Blocking input to a subtree while an operation is in progress.
+
+use std::num::NonZeroU64;
+
+use crate::promise::PromiseTokenId;
+
+/// A token identifying a period during which a widget is busy.
+///
+/// Returned by [`EventCtx::begin_busy`](crate::EventCtx::begin_busy); pass it to
+/// [`EventCtx::end_busy`](crate::EventCtx::end_busy) to unblock the widget.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub struct BusyGuardToken(NonZeroU64);
+
+/// A reason for a widget to be busy.
+#[derive(Clone, Debug)]
+pub(crate) struct BusyGuard {
+    pub(crate) token: BusyGuardToken,
+    /// Whether to paint a veil over the widget.
+    pub(crate) veil: bool,
+    /// The guard is released when this promise resolves.
+    pub(crate) promise: Option<PromiseTokenId>,
+}
+
+impl BusyGuardToken {
+    pub(crate) fn next() -> BusyGuardToken {
+        use druid_shell::Counter;
+        static BUSY_GUARD_COUNTER: Counter = Counter::new();
+        BusyGuardToken(BUSY_GUARD_COUNTER.next_nonzero())
+    }
+}
+
+impl BusyGuard {
+    pub(crate) fn new(veil: bool) -> Self {
+        BusyGuard {
+            token: BusyGuardToken::next(),
+            veil,
+            promise: None,
+        }
+    }
+}
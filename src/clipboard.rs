@@ -0,0 +1,125 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Access to the system clipboard and the primary selection.
+//!
+//! druid-shell only exposes the system clipboard, so Masonry accesses the primary
+//! selection through GTK. When GTK isn't running, eg in the
+//! [`TestHarness`](crate::testing::TestHarness), the selection is kept in memory and
+//! only shared between the windows of the app.
+
+use std::cell::RefCell;
+
+/// Whether the current platform has a primary selection.
+///
+/// On X11 and Wayland, selecting text copies it to the primary selection, and
+/// middle-clicking pastes it. Other platforms don't have this concept.
+///
+/// This is `false` without the `gtk` feature: druid-shell's X11 backend doesn't
+/// expose the primary selection.
+pub const HAS_PRIMARY_SELECTION: bool = cfg!(all(
+    feature = "gtk",
+    any(
+        target_os = "linux",
+        target_os = "freebsd",
+        target_os = "dragonfly",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    )
+));
+
+thread_local! {
+    /// The primary selection when GTK isn't running.
+    static PRIMARY_SELECTION: RefCell<Option<String>> = RefCell::new(None);
+}
+
+#[cfg(all(
+    feature = "gtk",
+    any(
+        target_os = "linux",
+        target_os = "freebsd",
+        target_os = "dragonfly",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    )
+))]
+mod gtk_selection {
+    /// Put text on the primary selection, returning `false` if GTK isn't running.
+    pub(super) fn put_string(text: &str) -> bool {
+        if !gtk::is_initialized_main_thread() {
+            return false;
+        }
+        gtk::Clipboard::get(&gtk::gdk::SELECTION_PRIMARY).set_text(text);
+        true
+    }
+
+    /// Get the text on the primary selection, or `None` if GTK isn't running.
+    pub(super) fn get_string() -> Option<Option<String>> {
+        if !gtk::is_initialized_main_thread() {
+            return None;
+        }
+        let text = gtk::Clipboard::get(&gtk::gdk::SELECTION_PRIMARY).wait_for_text();
+        Some(text.map(String::from))
+    }
+}
+
+#[cfg(not(all(
+    feature = "gtk",
+    any(
+        target_os = "linux",
+        target_os = "freebsd",
+        target_os = "dragonfly",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    )
+)))]
+mod gtk_selection {
+    pub(super) fn put_string(_text: &str) -> bool {
+        false
+    }
+
+    pub(super) fn get_string() -> Option<Option<String>> {
+        None
+    }
+}
+
+/// Which clipboard to read from or write to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClipboardKind {
+    /// The clipboard used by the copy, cut and paste commands.
+    Clipboard,
+    /// The selection pasted by middle-clicking, on platforms that have one.
+    ///
+    /// See [`HAS_PRIMARY_SELECTION`].
+    PrimarySelection,
+}
+
+/// Put text on the given clipboard.
+///
+/// This does nothing for the primary selection on platforms without one.
+pub(crate) fn put_string(kind: ClipboardKind, text: &str) {
+    match kind {
+        ClipboardKind::Clipboard => {
+            druid_shell::Application::global()
+                .clipboard()
+                .put_string(text);
+        }
+        ClipboardKind::PrimarySelection if HAS_PRIMARY_SELECTION => {
+            if !gtk_selection::put_string(text) {
+                PRIMARY_SELECTION
+                    .with(|selection| *selection.borrow_mut() = Some(text.to_string()));
+            }
+        }
+        ClipboardKind::PrimarySelection => (),
+    }
+}
+
+/// Get the text on the given clipboard, if any.
+pub(crate) fn get_string(kind: ClipboardKind) -> Option<String> {
+    match kind {
+        ClipboardKind::Clipboard => druid_shell::Application::global().clipboard().get_string(),
+        ClipboardKind::PrimarySelection => gtk_selection::get_string()
+            .unwrap_or_else(|| PRIMARY_SELECTION.with(|selection| selection.borrow().clone())),
+    }
+}
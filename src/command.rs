@@ -156,8 +156,17 @@ mod sys {
     use druid_shell::FileInfo;
 
     use super::{Selector, SingleUse};
+    use crate::backdrop::WindowBackdrop;
+    use crate::context_menu::ContextMenu;
+    use crate::debug_switches::DebugSwitch;
+    use crate::piet::ImageBuf;
     use crate::platform::WindowConfig;
-    use crate::WidgetId;
+    use crate::print::PageSetup;
+    use crate::request::Request;
+    use crate::sound::SoundId;
+    use crate::style::StyleSheet;
+    use crate::ui_state::UiState;
+    use crate::{Env, Point, Rect, WidgetId};
 
     /// Quit the running application. This command is handled by the Masonry library.
     pub const QUIT_APP: Selector = Selector::new("masonry-builtin.quit-app");
@@ -187,6 +196,16 @@ mod sys {
     /// will automatically target the window containing the widget.
     pub const CLOSE_WINDOW: Selector = Selector::new("masonry-builtin.close-window");
 
+    /// Close a window without sending [`Event::WindowCloseRequested`] first.
+    ///
+    /// Submit this once the user has confirmed they want to close a window whose
+    /// close was prevented, eg after a "discard unsaved changes?" prompt.
+    ///
+    /// The command must target a specific window.
+    ///
+    /// [`Event::WindowCloseRequested`]: crate::Event::WindowCloseRequested
+    pub const FORCE_CLOSE_WINDOW: Selector = Selector::new("masonry-builtin.force-close-window");
+
     /// Close all windows.
     pub const CLOSE_ALL_WINDOWS: Selector = Selector::new("masonry-builtin.close-all-windows");
 
@@ -270,6 +289,129 @@ mod sys {
     /// Select all.
     pub const SELECT_ALL: Selector = Selector::new("masonry-builtin.menu-select-all");
 
+    /// Play a UI sound. The sound file is resolved through the `Env`.
+    ///
+    /// This is usually sent with [`EventCtx::play_sound`](crate::EventCtx::play_sound).
+    pub const PLAY_SOUND: Selector<SoundId> = Selector::new("masonry-builtin.play-sound");
+
+    /// Replace the app's [`StyleSheet`].
+    ///
+    /// The style of every widget is recomputed, in every window.
+    pub const SET_STYLE_SHEET: Selector<SingleUse<StyleSheet>> =
+        Selector::new("masonry-builtin.set-style-sheet");
+
+    /// Replace the app's [`Env`].
+    ///
+    /// The widgets which read a key whose value changed receive
+    /// [`LifeCycle::EnvChanged`](crate::LifeCycle::EnvChanged), and are laid out and
+    /// painted again, in every window.
+    pub const SET_ENV: Selector<SingleUse<Env>> = Selector::new("masonry-builtin.set-env");
+
+    /// Toggle a [`DebugSwitch`], in every window.
+    pub const TOGGLE_DEBUG_SWITCH: Selector<DebugSwitch> =
+        Selector::new("masonry-builtin.toggle-debug-switch");
+
+    /// Collect the [`UiState`] of the target window.
+    ///
+    /// The state is sent back as an [`Action::UiStateSaved`](crate::Action::UiStateSaved),
+    /// from the window's root widget.
+    pub const SAVE_UI_STATE: Selector = Selector::new("masonry-builtin.save-ui-state");
+
+    /// Restore a [`UiState`] in the target window.
+    pub const RESTORE_UI_STATE: Selector<SingleUse<UiState>> =
+        Selector::new("masonry-builtin.restore-ui-state");
+
+    /// Sent to a widget when the part of the [`AppStore`](crate::AppStore) it
+    /// subscribed to has changed.
+    ///
+    /// The widget is painted again after handling it.
+    pub const STORE_CHANGED: Selector = Selector::new("masonry-builtin.store-changed");
+
+    /// Override the app's [low-power mode](crate::Env::LOW_POWER_MODE) for a window.
+    ///
+    /// This must target a window. `None` removes the override.
+    pub const SET_LOW_POWER_MODE: Selector<Option<bool>> =
+        Selector::new("masonry-builtin.set-low-power-mode");
+
+    /// Set the [UI scale](crate::Env::UI_SCALE) of a window, eg from a zoom menu.
+    ///
+    /// The scale is clamped between 0.5 and 3. This must target a window.
+    pub const SET_UI_SCALE: Selector<f64> = Selector::new("masonry-builtin.set-ui-scale");
+
+    /// Change the [backdrop](crate::backdrop) shown through the translucent parts of
+    /// a window.
+    ///
+    /// The window must have been created with a backdrop, or transparent; otherwise
+    /// this is ignored, with a warning. This must target a window.
+    pub const SET_WINDOW_BACKDROP: Selector<WindowBackdrop> =
+        Selector::new("masonry-builtin.set-window-backdrop");
+
+    /// Show a frame painted earlier instead of the widgets of a window, or `None` to
+    /// show the widgets again.
+    ///
+    /// The payload is the number of frames to go back from the last painted one,
+    /// which is `Some(0)`. Frames are only kept while
+    /// [`DebugSwitch::FrameHistory`] is enabled, and aren't recorded while an earlier
+    /// frame is shown, but the widgets keep receiving events and being laid out.
+    ///
+    /// This must target a window.
+    pub const SCRUB_FRAME: Selector<Option<usize>> = Selector::new("masonry-builtin.scrub-frame");
+
+    /// Sent to a widget which called [`request_prewarm`](crate::EventCtx::request_prewarm),
+    /// once the window has handled the events pending at that point.
+    ///
+    /// Widgets build the subtrees they'll show later in response, with
+    /// [`EventCtx::prewarm_child`](crate::EventCtx::prewarm_child).
+    pub const PREWARM: Selector = Selector::new("masonry-builtin.prewarm");
+
+    /// Paint a widget and its children into a bitmap, eg for drag previews or
+    /// exporting part of the UI as an image.
+    ///
+    /// Send it with [`EventCtx::send_request`](crate::EventCtx::send_request);
+    /// the bitmap is the response. The widget can be in any window. If it can't
+    /// be found or painted, the request is left unanswered.
+    pub const CAPTURE_WIDGET: Selector<Request<CaptureWidget, ImageBuf>> =
+        Selector::new("masonry-builtin.capture-widget");
+
+    /// Lay out and paint a widget and its children onto pages, eg to print a
+    /// report or export it as a PDF.
+    ///
+    /// Send it with [`EventCtx::send_request`](crate::EventCtx::send_request);
+    /// the page bitmaps are the response. See the [`print`](crate::print) module.
+    /// If the widget can't be found, the request is left unanswered.
+    pub const PRINT_PAGES: Selector<Request<PrintPages, Vec<ImageBuf>>> =
+        Selector::new("masonry-builtin.print-pages");
+
+    /// The payload of [`PRINT_PAGES`].
+    #[derive(Debug, Clone, Copy)]
+    pub struct PrintPages {
+        /// The widget to print.
+        pub widget: WidgetId,
+        /// The pages to print onto.
+        pub setup: PageSetup,
+    }
+
+    /// The payload of [`CAPTURE_WIDGET`].
+    #[derive(Debug, Clone, Copy)]
+    pub struct CaptureWidget {
+        /// The widget to paint.
+        pub widget: WidgetId,
+        /// The number of pixels per display point.
+        pub scale: f64,
+    }
+
+    /// Show a context menu at a point in the target window.
+    ///
+    /// This is sent by [`EventCtx::show_context_menu`](crate::EventCtx::show_context_menu).
+    pub(crate) const SHOW_CONTEXT_MENU: Selector<(ContextMenu, Point)> =
+        Selector::new("masonry-builtin.show-context-menu");
+
+    /// Scroll a rect of a widget, in the widget's coordinates, into view.
+    ///
+    /// This is sent by `request_pan_to_rect`, eg [`WidgetCtx::request_pan_to_rect`](crate::WidgetCtx::request_pan_to_rect).
+    pub(crate) const PAN_TO_RECT: Selector<(WidgetId, Rect)> =
+        Selector::new("masonry-builtin.pan-to-rect");
+
     /// Text input state has changed, and we need to notify the platform.
     pub(crate) const INVALIDATE_IME: Selector<ImeInvalidation> =
         Selector::new("masonry-builtin.invalidate-ime");
@@ -385,6 +527,11 @@ impl Command {
         self.symbol == selector.symbol()
     }
 
+    /// The symbol of the command's selector.
+    pub(crate) fn symbol(&self) -> SelectorSymbol {
+        self.symbol
+    }
+
     /// Returns `Some(&T)` (this `Command`'s payload) if the selector matches.
     ///
     /// Returns `None` when `self.is(selector) == false`.
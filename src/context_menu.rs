@@ -0,0 +1,118 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Context menus, shown by the platform next to the mouse.
+//!
+//! A widget shows a [`ContextMenu`] with [`EventCtx::show_context_menu`], usually
+//! in response to a right click. When the user picks an item, the item's command
+//! is submitted; commands sent to [`Target::Auto`](crate::Target::Auto) are sent
+//! to the window.
+//!
+//! Only one context menu is shown at a time in each window.
+//!
+//! [`EventCtx::show_context_menu`]: crate::EventCtx::show_context_menu
+
+use druid_shell::Menu;
+
+use crate::{ArcStr, Command};
+
+/// A list of items shown as a context menu.
+#[derive(Clone, Debug, Default)]
+pub struct ContextMenu {
+    items: Vec<ContextMenuItem>,
+}
+
+/// An entry in a [`ContextMenu`].
+#[derive(Clone, Debug)]
+pub enum ContextMenuItem {
+    /// An item which submits a command when picked.
+    Command {
+        /// The text of the item.
+        label: ArcStr,
+        /// The command submitted when the item is picked.
+        command: Command,
+    },
+    /// An item which can't be picked, eg to explain why a menu has no other items.
+    Disabled {
+        /// The text of the item.
+        label: ArcStr,
+    },
+    /// A line between groups of items.
+    Separator,
+}
+
+impl ContextMenu {
+    /// Create an empty menu.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builder-style method to add an item which submits `command` when picked.
+    pub fn with_item(mut self, label: impl Into<ArcStr>, command: impl Into<Command>) -> Self {
+        self.items.push(ContextMenuItem::Command {
+            label: label.into(),
+            command: command.into(),
+        });
+        self
+    }
+
+    /// Builder-style method to add an item which can't be picked.
+    pub fn with_disabled_item(mut self, label: impl Into<ArcStr>) -> Self {
+        self.items.push(ContextMenuItem::Disabled {
+            label: label.into(),
+        });
+        self
+    }
+
+    /// Builder-style method to add a separator.
+    pub fn with_separator(mut self) -> Self {
+        self.items.push(ContextMenuItem::Separator);
+        self
+    }
+
+    /// The items of the menu, from top to bottom.
+    pub fn items(&self) -> &[ContextMenuItem] {
+        &self.items
+    }
+
+    /// Returns `true` if the menu has no items.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// The platform menu; each item's id is its index.
+    pub(crate) fn to_platform_menu(&self) -> Menu {
+        let mut menu = Menu::new_for_popup();
+        for (id, item) in self.items.iter().enumerate() {
+            match item {
+                ContextMenuItem::Command { label, .. } => {
+                    menu.add_item(id as u32, label, None, None, true)
+                }
+                ContextMenuItem::Disabled { label } => {
+                    menu.add_item(id as u32, label, None, None, false)
+                }
+                ContextMenuItem::Separator => menu.add_separator(),
+            }
+        }
+        menu
+    }
+
+    /// The command of the item with the platform id `id`.
+    pub(crate) fn command(&self, id: u32) -> Option<&Command> {
+        match self.items.get(id as usize)? {
+            ContextMenuItem::Command { command, .. } => Some(command),
+            ContextMenuItem::Disabled { .. } | ContextMenuItem::Separator => None,
+        }
+    }
+
+    /// The platform id of the enabled item labelled `label`.
+    pub(crate) fn item_id(&self, label: &str) -> Option<u32> {
+        self.items
+            .iter()
+            .position(|item| {
+                matches!(item, ContextMenuItem::Command { label: item_label, .. } if &**item_label == label)
+            })
+            .map(|index| index as u32)
+    }
+}
@@ -11,21 +11,48 @@ use std::rc::Rc;
 use std::time::Duration;
 
 use druid_shell::text::Event as ImeInvalidation;
-use druid_shell::{Cursor, Region, TimerToken, WindowHandle};
+use druid_shell::{
+    Cursor, Monitor, Region, Scalable, Scale, Screen, TimerToken, WindowHandle, WindowLevel,
+};
+use instant::Instant;
+use once_cell::sync::Lazy;
 use tracing::{error, trace, warn};
 
+use crate::access_keys::AccessKeys;
 use crate::action::{Action, ActionQueue};
+use crate::app_store::{AnyAppStore, AppStore};
+use crate::broadcast::BroadcastListeners;
+use crate::busy::{BusyGuard, BusyGuardToken};
+use crate::clipboard::{self, ClipboardKind};
 use crate::command::{Command, CommandQueue, Notification, SingleUse};
+use crate::context_menu::ContextMenu;
 use crate::debug_logger::DebugLogger;
+use crate::env;
 use crate::ext_event::ExtEventSink;
-use crate::piet::{Piet, PietText, RenderContext};
+use crate::frame_stats::FrameStats;
+use crate::image_cache::{AnimatedImage, ImageCache, ImageSource};
+use crate::pass::{self, Pass};
+use crate::piet::{ImageBuf, Piet, PietText, RenderContext};
 use crate::platform::WindowDescription;
+use crate::popup::PopupAnchor;
+use crate::print::PageBreak;
 use crate::promise::PromiseToken;
+use crate::render_backend::{self, OffscreenDevices};
+use crate::request::{Request, RequestToken};
+use crate::screen;
+use crate::sound::SoundId;
+use crate::style::StyleSheet;
+use crate::telemetry::{Observed, SharedTelemetryHook, TelemetryRecord};
 use crate::testing::MockTimerQueue;
-use crate::text::{ImeHandlerRef, TextFieldRegistration};
-use crate::widget::{CursorChange, FocusChange, StoreInWidgetMut, WidgetMut, WidgetState};
+use crate::text::{
+    FontDescriptor, FontMetrics, ImeHandlerRef, TextFieldRegistration, TextMetricsCache,
+};
+use crate::widget::{
+    CursorChange, FocusChange, StoreInWidgetMut, ToastSpec, WidgetMut, WidgetState, SHOW_TOAST,
+};
 use crate::{
-    Affine, Env, Insets, Point, Rect, Size, Target, Vec2, Widget, WidgetId, WidgetPod, WindowId,
+    Affine, BoxConstraints, Data, Env, Insets, KeyOrValue, LifeCycle, Point, Rect, Selector, Size,
+    Target, Vec2, Widget, WidgetId, WidgetPod, WindowId,
 };
 
 /// A macro for implementing methods on multiple contexts.
@@ -52,7 +79,9 @@ pub(crate) struct GlobalPassCtx<'a> {
     pub(crate) action_queue: &'a mut ActionQueue,
     // TODO - merge queues
     // Associate timers with widgets that requested them.
-    pub(crate) timers: &'a mut HashMap<TimerToken, WidgetId>,
+    pub(crate) timers: &'a mut HashMap<TimerToken, (WidgetId, Instant)>,
+    pub(crate) broadcast_listeners: &'a mut BroadcastListeners,
+    pub(crate) access_keys: &'a mut AccessKeys,
     // Used in Harness for unit tests - see `src/testing/mock_timer_queue.rs`
     pub(crate) mock_timer_queue: Option<&'a mut MockTimerQueue>,
     pub(crate) window_id: WindowId,
@@ -60,6 +89,48 @@ pub(crate) struct GlobalPassCtx<'a> {
     pub(crate) text: PietText,
     /// The id of the widget that currently has focus.
     pub(crate) focus_widget: Option<WidgetId>,
+    pub(crate) style_sheet: &'a StyleSheet,
+    pub(crate) app_store: Option<Rc<dyn AnyAppStore>>,
+    pub(crate) image_cache: ImageCache,
+    pub(crate) text_metrics: TextMetricsCache,
+    pub(crate) offscreen_devices: &'a mut OffscreenDevices,
+    // Mutations scheduled with `mutate_later`, run by the window after the current pass.
+    pub(crate) mutate_callbacks: &'a mut Vec<MutateCallback>,
+    /// The part of the window that isn't obscured, as insets from its edges.
+    pub(crate) safe_area: Insets,
+    /// The stats of the frames painted before the current pass.
+    pub(crate) frame_stats: FrameStats,
+    /// Whether the window is shown, eg not minimized.
+    pub(crate) is_window_visible: bool,
+    /// Whether widgets should skip non-essential animations.
+    pub(crate) reduced_motion: bool,
+    /// The zoom factor the user chose for the window.
+    pub(crate) ui_scale: f64,
+    /// A widget called [`EventCtx::prevent_close`] during the current event.
+    pub(crate) close_prevented: bool,
+    /// The window's scale, if layout is computed in physical pixels.
+    ///
+    /// Only set during the layout pass.
+    pub(crate) pixel_scale: Option<Scale>,
+    /// The app's telemetry hook, which observes actions.
+    pub(crate) telemetry: Option<SharedTelemetryHook>,
+    /// Whether to record the widget which handles the current event, for the
+    /// telemetry hook.
+    pub(crate) track_event_handler: bool,
+    /// The innermost widget which handled the current event, if tracked.
+    pub(crate) event_handler: Option<WidgetId>,
+    /// The pass the window is running, checked by operations which are only
+    /// allowed in some passes.
+    pub(crate) pass: Option<Pass>,
+}
+
+type MutateFn =
+    Box<dyn for<'a, 'b> FnOnce(&'a mut dyn Widget, WidgetCtx<'a, 'b>, &'a mut WidgetState)>;
+
+/// A widget mutation scheduled with [`EventCtx::mutate_later`].
+pub(crate) struct MutateCallback {
+    pub(crate) id: WidgetId,
+    callback: MutateFn,
 }
 
 /// A context provided to implementors of [`StoreInWidgetMut`].
@@ -105,6 +176,7 @@ pub struct LayoutCtx<'a, 'b> {
     pub(crate) global_state: &'a mut GlobalPassCtx<'b>,
     pub(crate) widget_state: &'a mut WidgetState,
     pub(crate) mouse_pos: Option<Point>,
+    pub(crate) viewport: Option<Rect>,
 }
 
 /// Z-order paint operations with transformations.
@@ -144,6 +216,17 @@ impl_context_method!(
             self.widget_state.id
         }
 
+        /// The `WidgetId` of the current widget's parent.
+        ///
+        /// This is set before the widget receives [`LifeCycle::WidgetAdded`], and
+        /// updated when the widget is moved to another parent. It's `None` for the
+        /// root widget of a window.
+        ///
+        /// [`LifeCycle::WidgetAdded`]: crate::LifeCycle::WidgetAdded
+        pub fn parent_id(&self) -> Option<WidgetId> {
+            self.widget_state.parent_id
+        }
+
         /// Returns a reference to the current `WindowHandle`.
         pub fn window(&self) -> &WindowHandle {
             self.global_state.window
@@ -154,6 +237,104 @@ impl_context_method!(
             self.global_state.window_id
         }
 
+        /// The insets of the window's content area that are obscured by the platform,
+        /// eg by a notch, an auto-hiding taskbar, or an on-screen keyboard.
+        ///
+        /// Root layouts can use this to avoid placing controls under obscured regions.
+        /// Masonry doesn't detect these regions itself: this is [`Insets::ZERO`] unless
+        /// a platform integration calls [`AppRoot::set_safe_area`](crate::AppRoot::set_safe_area).
+        /// Widgets are notified of changes with
+        /// [`LifeCycle::SafeAreaChanged`](crate::LifeCycle::SafeAreaChanged).
+        pub fn safe_area(&self) -> Insets {
+            self.global_state.safe_area
+        }
+
+        /// Timings and counters of the frames painted by the window so far.
+        ///
+        /// During a paint pass, this doesn't include the frame being painted.
+        pub fn frame_stats(&self) -> FrameStats {
+            self.global_state.frame_stats
+        }
+
+        /// Whether the window is shown.
+        ///
+        /// This is `false` while the window is minimized, but not while it's covered by
+        /// other windows, which isn't detected. Animation frames and
+        /// timers are held back until it's shown again, but widgets doing other
+        /// periodic work, eg polling, can check this to skip it.
+        pub fn is_window_visible(&self) -> bool {
+            self.global_state.is_window_visible
+        }
+
+        /// Whether non-essential animations should be skipped.
+        ///
+        /// This is `true` in [low-power mode](Env::LOW_POWER_MODE), and if the user
+        /// [prefers reduced motion](Env::PREFERS_REDUCED_MOTION). Widgets should then
+        /// jump to the end of decorative transitions instead of animating them.
+        ///
+        /// Widgets which call this receive [`LifeCycle::EnvChanged`] when either
+        /// setting changes, including when a window overrides its low-power mode with
+        /// [`SET_LOW_POWER_MODE`](crate::command::SET_LOW_POWER_MODE).
+        ///
+        /// [`LifeCycle::EnvChanged`]: crate::LifeCycle::EnvChanged
+        pub fn reduced_motion(&self) -> bool {
+            env::record_key_read(&Env::LOW_POWER_MODE);
+            env::record_key_read(&Env::PREFERS_REDUCED_MOTION);
+            self.global_state.reduced_motion
+        }
+
+        /// The zoom factor the user chose for the window, `1.0` by default.
+        ///
+        /// The text sizes and metrics of the [`theme`](crate::theme) are already
+        /// multiplied by it; widgets only need it for sizes they don't read from the
+        /// [`Env`]. Widgets which call this receive [`LifeCycle::EnvChanged`] when it
+        /// changes. See [`Env::UI_SCALE`].
+        ///
+        /// [`LifeCycle::EnvChanged`]: crate::LifeCycle::EnvChanged
+        pub fn ui_scale(&self) -> f64 {
+            env::record_key_read(&Env::UI_SCALE);
+            self.global_state.ui_scale
+        }
+
+        /// The app's [`AppStore`], if it has one holding a `T`.
+        pub fn app_store<T: 'static>(&self) -> Option<AppStore<T>> {
+            let store = self.global_state.app_store.as_ref()?;
+            store.as_any().downcast_ref::<AppStore<T>>().cloned()
+        }
+
+        /// The app's [`ImageCache`].
+        pub fn image_cache(&self) -> ImageCache {
+            self.global_state.image_cache.clone()
+        }
+
+        /// The image for `source` at the window's scale factor, if it's decoded.
+        ///
+        /// Otherwise, this starts decoding it in the background, and the widget
+        /// receives an [`IMAGE_LOADED`] command when it's done. For animated
+        /// images, this is the first frame.
+        ///
+        /// [`IMAGE_LOADED`]: crate::IMAGE_LOADED
+        pub fn load_image(&self, source: &ImageSource) -> Option<ImageBuf> {
+            self.load_animated_image(source)
+                .map(|image| image.first_frame().clone())
+        }
+
+        /// All the frames of the image for `source`, if it's decoded.
+        ///
+        /// See [`load_image`](Self::load_image).
+        pub fn load_animated_image(&self, source: &ImageSource) -> Option<AnimatedImage> {
+            let scale = self
+                .global_state
+                .window
+                .get_scale()
+                .map_or(1.0, |scale| scale.x());
+            self.global_state.image_cache.request(
+                source.select(scale),
+                self.widget_state.id,
+                &self.global_state.ext_event_sink,
+            )
+        }
+
         /// Get an object which can create text layouts.
         pub fn text(&mut self) -> &mut PietText {
             &mut self.global_state.text
@@ -173,6 +354,39 @@ impl_context_method!(
     }
 );
 
+// methods on the contexts of passes which recurse into every child
+impl_context_method!(
+    EventCtx<'_, '_>,
+    LifeCycleCtx<'_, '_>,
+    PaintCtx<'_, '_, '_>,
+    {
+        /// Call `visit` on each of the given children.
+        ///
+        /// Children that `visit` doesn't forward the pass to are marked as deliberately
+        /// skipped, as with [`skip_child`](Self::skip_child). Containers which pass all
+        /// their children to this method can't trip the framework's "child not visited"
+        /// checks, even if they only recurse into some of them (eg the visible ones).
+        ///
+        /// Note that every child must still receive [`LifeCycle::WidgetAdded`](crate::LifeCycle::WidgetAdded).
+        ///
+        /// ```ignore
+        /// ctx.visit_children(self.children.iter_mut(), |child, ctx| {
+        ///     child.on_event(ctx, event, env);
+        /// });
+        /// ```
+        pub fn visit_children<'c, W: Widget + 'c>(
+            &mut self,
+            children: impl IntoIterator<Item = &'c mut WidgetPod<W>>,
+            mut visit: impl FnMut(&mut WidgetPod<W>, &mut Self),
+        ) {
+            for child in children {
+                visit(child, self);
+                self.skip_child(child);
+            }
+        }
+    }
+);
+
 // methods on everyone but layoutctx
 impl_context_method!(
     WidgetCtx<'_, '_>,
@@ -202,8 +416,10 @@ impl_context_method!(
         /// Convert a point from the widget's coordinate space to the window's.
         ///
         /// The returned point is relative to the content area; it excludes window chrome.
+        /// It accounts for the [scale](LayoutCtx::set_child_scale) of the widget's
+        /// ancestors, eg the zoom of a [`Portal`](crate::widget::Portal).
         pub fn to_window(&self, widget_point: Point) -> Point {
-            self.window_origin() + widget_point.to_vec2()
+            self.window_origin() + widget_point.to_vec2() * self.widget_state.window_scale
         }
 
         /// Convert a point from the widget's coordinate space to the screen's.
@@ -216,6 +432,25 @@ impl_context_method!(
             content_origin + self.to_window(widget_point).to_vec2()
         }
 
+        /// The monitors of the system, eg to show a window on another display.
+        ///
+        /// See the [`screen`](crate::screen) module.
+        pub fn monitors(&self) -> Vec<Monitor> {
+            Screen::get_monitors()
+        }
+
+        /// The monitor showing most of the widget, or the closest one if it's
+        /// offscreen.
+        pub fn monitor(&self) -> Option<Monitor> {
+            let rect = self.size().to_rect() + self.to_screen(Point::ORIGIN).to_vec2();
+            let monitors = self.monitors();
+            let rects: Vec<Rect> = monitors.iter().map(Monitor::virtual_rect).collect();
+            let area = screen::area_showing(rect, &rects)?;
+            monitors
+                .into_iter()
+                .find(|monitor| monitor.virtual_rect() == area)
+        }
+
         /// The "hot" (aka hover) status of a widget.
         ///
         /// A widget is "hot" when the mouse is hovered over it. Widgets will
@@ -301,6 +536,18 @@ impl_context_method!(
         pub fn is_stashed(&self) -> bool {
             self.widget_state.is_stashed
         }
+
+        /// Check whether the widget can currently be seen.
+        ///
+        /// A widget is invisible if it or one of its ancestors is stashed, if it's
+        /// outside of the window or scrolled out of a [`Portal`](crate::widget::Portal),
+        /// or if the window is minimized. This is updated after each layout pass, and
+        /// the widget receives [`LifeCycle::VisibilityChanged`] when it changes.
+        ///
+        /// [`LifeCycle::VisibilityChanged`]: crate::LifeCycle::VisibilityChanged
+        pub fn is_visible(&self) -> bool {
+            self.widget_state.is_visible
+        }
     }
 );
 
@@ -402,6 +649,79 @@ impl<'a, 'b> LifeCycleCtx<'a, 'b> {
     }
 }
 
+impl_context_method!(
+    EventCtx<'_, '_>,
+    LifeCycleCtx<'_, '_>,
+    LayoutCtx<'_, '_>,
+    PaintCtx<'_, '_, '_>,
+    {
+        /// Schedule a mutation of this widget, to be run once the current pass is over.
+        ///
+        /// This lets widgets make changes (eg adding or removing children) from
+        /// passes where they can't mutate themselves, like [`layout`] or [`paint`].
+        ///
+        /// `W` must be the type of the current widget; otherwise the mutation is
+        /// skipped, with a warning.
+        ///
+        /// [`layout`]: crate::Widget::layout
+        /// [`paint`]: crate::Widget::paint
+        pub fn mutate_self_later<W: Widget + StoreInWidgetMut>(
+            &mut self,
+            f: impl FnOnce(WidgetMut<'_, '_, W>) + 'static,
+        ) {
+            let id = self.widget_state.id;
+            self.mutate_later(id, f);
+        }
+
+        /// Schedule a mutation of the given widget, to be run once the current pass is over.
+        ///
+        /// The target can be any widget in the same window. If it is removed before
+        /// the mutation runs, or isn't of type `W`, the mutation is skipped, with a warning.
+        ///
+        /// See [`mutate_self_later`](Self::mutate_self_later) for details.
+        pub fn mutate_later<W: Widget + StoreInWidgetMut>(
+            &mut self,
+            target: WidgetId,
+            f: impl FnOnce(WidgetMut<'_, '_, W>) + 'static,
+        ) {
+            trace!("mutate_later({:?})", target);
+            self.global_state
+                .mutate_callbacks
+                .push(MutateCallback::new(target, f));
+        }
+    }
+);
+
+impl_context_method!(
+    WidgetCtx<'_, '_>,
+    EventCtx<'_, '_>,
+    LifeCycleCtx<'_, '_>,
+    LayoutCtx<'_, '_>,
+    {
+        /// Set the z-index of a child, relative to its siblings.
+        ///
+        /// Where siblings overlap, the pointer hits the one with the highest z-index,
+        /// and the others aren't hot. Siblings with the same z-index are hit-tested by
+        /// the container, usually in tree order. The default z-index is 0.
+        ///
+        /// Containers should also paint their children with
+        /// [`PaintCtx::paint_children_in_z_order`], so the child receiving the clicks is
+        /// the one drawn on top. The standard containers, eg [`Flex`](crate::widget::Flex),
+        /// do.
+        pub fn set_child_z_index(&mut self, child: &mut WidgetPod<impl Widget>, z_index: i32) {
+            if child.state.z_index == z_index {
+                return;
+            }
+            trace!("set_child_z_index({:?}, {})", child.id(), z_index);
+            child.state.z_index = z_index;
+            self.widget_state.has_z_indexed_children |= z_index != 0;
+            self.widget_state.invalid.set_rect(
+                self.widget_state.paint_rect() - self.widget_state.layout_rect().origin().to_vec2(),
+            );
+        }
+    }
+);
+
 // methods on event and lifecycle
 impl_context_method!(WidgetCtx<'_, '_>, EventCtx<'_, '_>, LifeCycleCtx<'_, '_>, {
     /// Request a [`paint`] pass. This is equivalent to calling
@@ -440,17 +760,61 @@ impl_context_method!(WidgetCtx<'_, '_>, EventCtx<'_, '_>, LifeCycleCtx<'_, '_>,
         self.widget_state.needs_layout = true;
     }
 
+    /// Request that this widget be laid out again, with the constraints of its
+    /// last layout.
+    ///
+    /// This is meant for widgets whose size depends on an async measurement, eg an
+    /// image being decoded or a font being loaded: their [`layout`] can return an
+    /// estimated size right away, and call this method once the measurement is done.
+    ///
+    /// Unlike [`request_layout`](Self::request_layout), only the widget and its
+    /// children are laid out again. Its parents are only laid out if its new size is
+    /// different from the estimate.
+    ///
+    /// [`layout`]: crate::Widget::layout
+    pub fn request_refine_layout(&mut self) {
+        trace!("request_refine_layout");
+        self.widget_state.request_refine_layout = true;
+        self.widget_state.needs_refine_layout = true;
+    }
+
     /// Request an animation frame.
     pub fn request_anim_frame(&mut self) {
         trace!("request_anim_frame");
         self.widget_state.request_anim = true;
     }
 
+    /// Put text on the system clipboard or the primary selection.
+    ///
+    /// Putting text on the primary selection does nothing on platforms without one;
+    /// see [`clipboard::HAS_PRIMARY_SELECTION`].
+    pub fn set_clipboard_text(&mut self, kind: ClipboardKind, text: &str) {
+        trace!("set_clipboard_text kind={:?}", kind);
+        clipboard::put_string(kind, text);
+    }
+
+    /// Get the text on the system clipboard or the primary selection, if any.
+    pub fn clipboard_text(&self, kind: ClipboardKind) -> Option<String> {
+        clipboard::get_string(kind)
+    }
+
+    /// Make the text caret visible and restart its blink cycle.
+    ///
+    /// Text widgets should call this whenever their text or selection changes, so
+    /// that the caret stays visible while the user is typing.
+    ///
+    /// See [`LifeCycleCtx::register_for_caret_blink`].
+    pub fn reset_caret_blink(&mut self) {
+        trace!("reset_caret_blink");
+        self.widget_state.reset_caret_blink = true;
+    }
+
     /// Indicate that your children have changed.
     ///
     /// Widgets must call this method after adding a new child or removing a child.
     pub fn children_changed(&mut self) {
         trace!("children_changed");
+        pass::check_tree_change(self.global_state.pass, "children_changed");
         self.widget_state.children_changed = true;
         self.widget_state.update_focus_chain = true;
         self.request_layout();
@@ -466,11 +830,94 @@ impl_context_method!(WidgetCtx<'_, '_>, EventCtx<'_, '_>, LifeCycleCtx<'_, '_>,
     /// [`LifeCycle::DisabledChanged`]: struct.LifeCycle.html#variant.DisabledChanged
     /// [`is_disabled`]: EventCtx::is_disabled
     pub fn set_disabled(&mut self, disabled: bool) {
+        pass::check_tree_change(self.global_state.pass, "set_disabled");
         // widget_state.children_disabled_changed is not set because we want to be able to delete
         // changes that happened during DisabledChanged.
         self.widget_state.is_explicitly_disabled_new = disabled;
     }
 
+    /// Block input to this widget and its descendants until [`end_busy`] is called
+    /// with the returned token.
+    ///
+    /// While a widget is busy, mouse, pen, keyboard and gamepad events aren't sent
+    /// to it or its descendants, and clicks on it don't fall through to the widgets
+    /// below. Pointer interactions already in progress are allowed to finish. Other
+    /// events, eg commands, timers and promise results, are still delivered.
+    ///
+    /// The widget stays busy as long as any of its tokens hasn't been released, so
+    /// several operations can overlap. Use this to disable a form while it's being
+    /// saved, without disabling each of its fields; called by the root widget, it
+    /// blocks input to the whole window.
+    ///
+    /// [`end_busy`]: EventCtx::end_busy
+    pub fn begin_busy(&mut self) -> BusyGuardToken {
+        self.begin_busy_impl(false)
+    }
+
+    /// Like [`begin_busy`](EventCtx::begin_busy), but also paint a translucent veil
+    /// over the widget while it's busy.
+    ///
+    /// See [`theme::BUSY_VEIL_COLOR`](crate::theme::BUSY_VEIL_COLOR) for the color
+    /// of the veil.
+    pub fn begin_busy_with_veil(&mut self) -> BusyGuardToken {
+        self.begin_busy_impl(true)
+    }
+
+    fn begin_busy_impl(&mut self, veil: bool) -> BusyGuardToken {
+        let guard = BusyGuard::new(veil);
+        let token = guard.token;
+        trace!("begin_busy {:?}", token);
+        self.widget_state.busy_guards.push(guard);
+        if veil {
+            self.request_paint();
+        }
+        token
+    }
+
+    /// Release a token returned by [`begin_busy`](EventCtx::begin_busy).
+    ///
+    /// Does nothing if the token was already released.
+    pub fn end_busy(&mut self, token: BusyGuardToken) {
+        let guards = &mut self.widget_state.busy_guards;
+        let Some(index) = guards.iter().position(|guard| guard.token == token) else {
+            return;
+        };
+        trace!("end_busy {:?}", token);
+        if guards.remove(index).veil {
+            self.request_paint();
+        }
+    }
+
+    /// Release a token returned by [`begin_busy`](EventCtx::begin_busy) once this
+    /// widget receives the result of the given promise.
+    ///
+    /// The promise must be one this widget will receive, eg one returned by
+    /// [`compute_in_background`](EventCtx::compute_in_background) or
+    /// [`send_request`](EventCtx::send_request). The token can still be released
+    /// earlier with [`end_busy`](EventCtx::end_busy).
+    pub fn end_busy_on_promise<T: Any + Send>(
+        &mut self,
+        token: BusyGuardToken,
+        promise: PromiseToken<T>,
+    ) {
+        match self
+            .widget_state
+            .busy_guards
+            .iter_mut()
+            .find(|guard| guard.token == token)
+        {
+            Some(guard) => guard.promise = Some(promise.id()),
+            None => warn!("end_busy_on_promise: {:?} was already released", token),
+        }
+    }
+
+    /// Return `true` if this widget is busy, see [`begin_busy`](EventCtx::begin_busy).
+    ///
+    /// A widget can also be blocked by a busy ancestor, which this doesn't check.
+    pub fn is_busy(&self) -> bool {
+        self.widget_state.is_busy()
+    }
+
     /// Mark child widget as stashed.
     ///
     /// **Note:** Stashed widgets are a WIP feature
@@ -519,6 +966,17 @@ impl_context_method!(
             self.global_state.submit_command(cmd.into())
         }
 
+        /// Ask to receive [`PREWARM`](crate::command::PREWARM) on idle time, to
+        /// build a subtree before it's shown.
+        ///
+        /// See [`EventCtx::prewarm_child`].
+        pub fn request_prewarm(&mut self) {
+            trace!("request_prewarm");
+            let id = self.widget_state.id;
+            self.global_state
+                .submit_command(crate::command::PREWARM.to(id));
+        }
+
         /// Submit an [`Action`].
         ///
         /// Note: Actions are still a WIP feature.
@@ -528,6 +986,41 @@ impl_context_method!(
                 .submit_action(action, self.widget_state.id)
         }
 
+        /// Request that a UI sound be played.
+        ///
+        /// The sound file is looked up in the [`Env`] once the current pass is
+        /// over; by default, no sound files are configured.
+        pub fn play_sound(&mut self, sound: SoundId) {
+            trace!("play_sound {:?}", sound);
+            self.global_state
+                .submit_command(crate::command::PLAY_SOUND.with(sound).to(Target::Global))
+        }
+
+        /// Scroll `rect`, in this widget's coordinates, into view once the current
+        /// pass is over.
+        ///
+        /// Each ancestor [`Portal`](crate::widget::Portal) pans to show as much of
+        /// the rect as fits. Unlike [`EventCtx::request_pan_to_this`], this can be
+        /// called from a [`WidgetMut`](crate::widget::WidgetMut) method.
+        pub fn request_pan_to_rect(&mut self, rect: Rect) {
+            trace!("request_pan_to_rect {:?}", rect);
+            let widget_id = self.widget_state.id;
+            self.global_state.submit_command(
+                crate::command::PAN_TO_RECT
+                    .with((widget_id, rect))
+                    .to(Target::Window(self.global_state.window_id)),
+            )
+        }
+
+        /// Get an [`ExtEventSink`] that can be moved to other threads.
+        ///
+        /// This is useful for widgets that own a long-running worker (for
+        /// instance a media decoder) and need to send it a way to reach
+        /// the main thread.
+        pub fn get_external_handle(&self) -> ExtEventSink {
+            self.global_state.ext_event_sink.clone()
+        }
+
         /// Run the provided function in the background.
         ///
         /// The function takes an [`ExtEventSink`] which it can use to send
@@ -572,6 +1065,30 @@ impl_context_method!(
             token
         }
 
+        /// Send a request, as a global command.
+        ///
+        /// Whoever handles the command responds with [`Request::respond`]; the
+        /// response is then sent back to this widget as an
+        /// [`Event::PromiseResult`](crate::Event::PromiseResult) matching the
+        /// returned token. If nobody responds, the result is
+        /// [`RequestError::Unanswered`](crate::request::RequestError::Unanswered).
+        pub fn send_request<Req: Any, Res: Any + Send>(
+            &mut self,
+            selector: Selector<Request<Req, Res>>,
+            payload: Req,
+        ) -> RequestToken<Res> {
+            let token = RequestToken::<Res>::new();
+            let request = Request::new(
+                payload,
+                token,
+                self.global_state.ext_event_sink.clone(),
+                self.widget_state.id,
+                self.global_state.window_id,
+            );
+            self.submit_command(selector.with(request).to(Target::Global));
+            token
+        }
+
         /// Request a timer event.
         ///
         /// The return value is a token, which can be used to associate the
@@ -585,6 +1102,76 @@ impl_context_method!(
 );
 
 impl EventCtx<'_, '_> {
+    /// Initialize, lay out and paint a child which isn't in the widget tree yet,
+    /// eg the next page of a wizard, so that showing it later doesn't stall a frame
+    /// on loading fonts and images and laying out text.
+    ///
+    /// This is usually done in response to [`PREWARM`](crate::command::PREWARM). The
+    /// child is laid out with `bc` and painted into a discarded bitmap, detached from
+    /// this widget: it mustn't be in [`children`](crate::Widget::children) yet. To
+    /// show it, add it to the children in a mutation and pass it to
+    /// [`WidgetPod::adopt`]; it doesn't receive [`LifeCycle::WidgetAdded`] again.
+    ///
+    /// The commands, actions, timers, access keys, command listeners and input
+    /// fields the child registers meanwhile are kept with it, and only reach the
+    /// window once it's adopted. A timer which fires before then is dropped.
+    ///
+    /// [`LifeCycle::WidgetAdded`]: crate::LifeCycle::WidgetAdded
+    pub fn prewarm_child(
+        &mut self,
+        child: &mut WidgetPod<impl Widget>,
+        bc: &BoxConstraints,
+        env: &Env,
+    ) {
+        trace!("prewarm_child {}", child.id());
+        // The child is parented to this widget, but nothing it requests reaches the
+        // window until it's adopted: its requests are collected in the window's
+        // swapped-out queues.
+        let mut detached_state = WidgetState::new(self.widget_state.id, None, "Prewarm");
+        let mut effects = child.detached_effects.take().unwrap_or_default();
+        effects.swap(self.global_state);
+        if !child.is_initialized() {
+            let mut lifecycle_ctx = LifeCycleCtx {
+                global_state: self.global_state,
+                widget_state: &mut detached_state,
+            };
+            child.lifecycle(&mut lifecycle_ctx, &LifeCycle::WidgetAdded, env);
+        }
+
+        // The child runs its own passes, nested in this one.
+        let pass = self.global_state.pass;
+        self.global_state.pass = Some(Pass::Layout);
+        let mut layout_ctx = LayoutCtx {
+            global_state: self.global_state,
+            widget_state: &mut detached_state,
+            mouse_pos: None,
+            viewport: None,
+        };
+        let size = child.layout(&mut layout_ctx, bc, env);
+        layout_ctx.place_child(child, Point::ORIGIN, env);
+
+        self.global_state.pass = Some(Pass::Paint);
+        if !size.is_empty() {
+            let scale = self
+                .global_state
+                .window
+                .get_scale()
+                .map_or(1.0, |scale| scale.x());
+            paint_offscreen(self.global_state, &detached_state, 0, size, scale, |ctx| {
+                child.paint_always(ctx, env)
+            });
+        }
+        self.global_state.pass = pass;
+
+        effects.swap(self.global_state);
+        effects
+            .text_registrations
+            .append(&mut detached_state.text_registrations);
+        child.detached_effects = Some(Box::new(effects));
+        // Its window origin is only known once it's placed by its real parent.
+        child.state.needs_window_origin = true;
+    }
+
     /// Submit a [`Notification`].
     ///
     /// The provided argument can be a [`Selector`] or a [`Command`]; this lets
@@ -624,9 +1211,68 @@ impl EventCtx<'_, '_> {
         );
     }
 
+    /// Create a new window next to `widget_rect`, in the widget's coordinate space.
+    ///
+    /// The window is placed below the rect, or above it if there's more room there,
+    /// and kept inside the work area of the monitor showing the rect. See
+    /// [`WindowDescription::place_near`].
+    pub fn open_window_near(&mut self, widget_rect: Rect, desc: WindowDescription) {
+        let anchor = widget_rect + self.to_screen(Point::ORIGIN).to_vec2();
+        self.new_window(desc.place_near(anchor));
+    }
+
+    /// Open a popup window next to `widget_rect`, in the widget's coordinate space,
+    /// eg for a menu which doesn't fit in the window.
+    ///
+    /// The popup has no titlebar, and follows this widget until it's closed; it's
+    /// closed automatically when this widget is removed or stashed. See the
+    /// [`popup`](crate::popup) module. Returns the id of the popup window, eg to close
+    /// it with [`CLOSE_WINDOW`](crate::command::CLOSE_WINDOW).
+    pub fn open_popup(&mut self, widget_rect: Rect, desc: WindowDescription) -> WindowId {
+        trace!("open_popup");
+        let mut desc = desc
+            .show_titlebar(false)
+            .resizable(false)
+            .set_level(WindowLevel::DropDown(self.window().clone()));
+        desc.popup_anchor = Some(PopupAnchor::new(
+            self.window_id(),
+            self.widget_id(),
+            widget_rect,
+        ));
+        let id = desc.id;
+        self.open_window_near(widget_rect, desc);
+        id
+    }
+
+    /// Show a toast in the window's [`Toasts`](crate::widget::Toasts) overlay.
+    ///
+    /// If the window's root widget isn't wrapped in a `Toasts`, this does nothing.
+    pub fn show_toast(&mut self, spec: ToastSpec) {
+        trace!("show_toast");
+        self.submit_command(SHOW_TOAST.with(spec).to(Target::Window(self.window_id())));
+    }
+
+    /// Show a context menu at `position`, in the widget's coordinate space.
+    ///
+    /// The menu replaces any context menu already shown in the window. Empty menus
+    /// aren't shown.
+    pub fn show_context_menu(&mut self, menu: ContextMenu, position: Point) {
+        trace!("show_context_menu");
+        let position = self.to_window(position);
+        self.submit_command(
+            crate::command::SHOW_CONTEXT_MENU
+                .with((menu, position))
+                .to(Target::Window(self.window_id())),
+        );
+    }
+
     /// Send a signal to parent widgets to scroll this widget into view.
+    ///
+    /// Focused widgets are scrolled into view automatically.
     pub fn request_pan_to_this(&mut self) {
-        self.request_pan_to_child = Some(self.widget_state.layout_rect());
+        // The rect is translated into each ancestor's coordinate space
+        // as the request moves up the tree.
+        self.request_pan_to_child = Some(self.widget_state.size.to_rect());
     }
 
     /// Set the "active" state of the widget.
@@ -638,6 +1284,40 @@ impl EventCtx<'_, '_> {
         // TODO: plumb mouse grab through to platform (through druid-shell)
     }
 
+    /// Take over the current mouse gesture from this widget's descendants.
+    ///
+    /// This makes the widget active and marks the event as handled. The descendants
+    /// that were active lose their active state, and receive an
+    /// [`Event::PointerCaptureLost`]; the rest of the gesture is only sent to this
+    /// widget.
+    ///
+    /// This is meant to be called while handling an [`Event::MouseFilter`], eg when
+    /// a scroll area sees the mouse being dragged past a threshold.
+    ///
+    /// [`Event::PointerCaptureLost`]: crate::Event::PointerCaptureLost
+    /// [`Event::MouseFilter`]: crate::Event::MouseFilter
+    pub fn capture_pointer(&mut self) {
+        trace!("capture_pointer");
+        self.widget_state.is_active = true;
+        self.widget_state.is_capturing_pointer = true;
+        self.is_handled = true;
+    }
+
+    /// Keep the window open, while handling [`Event::WindowCloseRequested`].
+    ///
+    /// Unlike [`set_handled`](Self::set_handled), this doesn't stop the event from
+    /// reaching other widgets. It has no effect while handling other events.
+    ///
+    /// The usual flow is to prevent the close, ask the user whether to discard their
+    /// unsaved changes, then submit [`FORCE_CLOSE_WINDOW`] if they do.
+    ///
+    /// [`Event::WindowCloseRequested`]: crate::Event::WindowCloseRequested
+    /// [`FORCE_CLOSE_WINDOW`]: crate::command::FORCE_CLOSE_WINDOW
+    pub fn prevent_close(&mut self) {
+        trace!("prevent_close");
+        self.global_state.close_prevented = true;
+    }
+
     /// Set the event as "handled", which stops its propagation to other
     /// widgets.
     pub fn set_handled(&mut self) {
@@ -753,6 +1433,137 @@ impl LifeCycleCtx<'_, '_> {
         self.widget_state.focus_chain.push(self.widget_id());
     }
 
+    /// Register this widget to see key events before its focused descendants.
+    ///
+    /// Once registered, the widget receives an [`Event::KeyFilter`] before every
+    /// key event routed through it. Calling [`EventCtx::set_handled`] while
+    /// handling the filter event stops the key event from reaching the focused
+    /// widget, eg so that a dialog can intercept Escape.
+    ///
+    /// This should be called in response to [`LifeCycle::WidgetAdded`](crate::LifeCycle::WidgetAdded).
+    ///
+    /// [`Event::KeyFilter`]: crate::Event::KeyFilter
+    pub fn register_key_filter(&mut self) {
+        trace!("register_key_filter");
+        self.widget_state.is_key_filter = true;
+    }
+
+    /// Register this widget to see mouse events before its descendants.
+    ///
+    /// Once registered, the widget receives an [`Event::MouseFilter`] before every
+    /// mouse event routed through it. This works like
+    /// [`register_key_filter`](Self::register_key_filter); see
+    /// [`EventCtx::capture_pointer`] to take over a gesture from a descendant.
+    ///
+    /// This should be called in response to [`LifeCycle::WidgetAdded`](crate::LifeCycle::WidgetAdded).
+    ///
+    /// [`Event::MouseFilter`]: crate::Event::MouseFilter
+    pub fn register_pointer_filter(&mut self) {
+        trace!("register_pointer_filter");
+        self.widget_state.is_pointer_filter = true;
+    }
+
+    /// Register this widget to receive [`Event::CaretBlink`] while it's focused.
+    ///
+    /// The window owns a single blink timer for the focused widget, and pauses it
+    /// while the window itself isn't focused, so text widgets don't need to run
+    /// their own timers. The blink rate is set by [`theme::CURSOR_BLINK_INTERVAL`].
+    ///
+    /// This should be called in response to [`LifeCycle::WidgetAdded`](crate::LifeCycle::WidgetAdded).
+    ///
+    /// [`Event::CaretBlink`]: crate::Event::CaretBlink
+    /// [`theme::CURSOR_BLINK_INTERVAL`]: crate::theme::CURSOR_BLINK_INTERVAL
+    pub fn register_for_caret_blink(&mut self) {
+        trace!("register_for_caret_blink");
+        self.widget_state.wants_caret_blink = true;
+    }
+
+    /// Register this widget as the handler of commands with the given selector.
+    ///
+    /// Commands with a selector that has registered handlers aren't broadcast to the
+    /// whole widget tree when sent to a window or [`Target::Global`]; they're delivered
+    /// directly to each handler instead. Every handler receives the command, even if
+    /// another one marked it as handled.
+    /// This avoids visiting every widget of a large tree for commands that only a
+    /// few widgets care about.
+    ///
+    /// Commands sent to a specific widget are unaffected.
+    ///
+    /// This should be called in response to [`LifeCycle::WidgetAdded`](crate::LifeCycle::WidgetAdded).
+    pub fn register_command_handler<T>(&mut self, selector: Selector<T>) {
+        trace!("register_command_handler {}", selector);
+        let id = self.widget_id();
+        let handlers = self
+            .global_state
+            .broadcast_listeners
+            .command_handlers
+            .entry(selector.symbol())
+            .or_default();
+        BroadcastListeners::add(handlers, id);
+    }
+
+    /// Register this widget to receive every command sent to its window.
+    ///
+    /// By default, commands sent to a window or to [`Target::Global`] are broadcast to
+    /// the whole widget tree. In windows created with
+    /// [`WindowDescription::command_broadcast_opt_in`], they're only delivered to the
+    /// widgets which called this method, and to the handlers registered with
+    /// [`register_command_handler`](Self::register_command_handler). In large trees,
+    /// this saves visiting thousands of widgets for every command.
+    ///
+    /// In other windows, this has no effect.
+    ///
+    /// This should be called in response to [`LifeCycle::WidgetAdded`](crate::LifeCycle::WidgetAdded).
+    pub fn register_for_commands(&mut self) {
+        trace!("register_for_commands");
+        let id = self.widget_id();
+        BroadcastListeners::add(&mut self.global_state.broadcast_listeners.commands, id);
+    }
+
+    /// Register an access key for this label, which sends
+    /// [`ACTIVATE`](crate::access_keys::ACTIVATE) to `target` when pressed with Alt.
+    ///
+    /// This replaces the access key previously registered by this widget. See the
+    /// [`access_keys`](crate::access_keys) module.
+    ///
+    /// This should be called in response to [`LifeCycle::WidgetAdded`](crate::LifeCycle::WidgetAdded).
+    pub fn register_access_key(&mut self, key: char, target: WidgetId) {
+        trace!("register_access_key {:?}", key);
+        let id = self.widget_id();
+        self.global_state.access_keys.register(key, id, target);
+    }
+
+    /// Make this widget a scope for the access keys of its descendants, eg for a menu.
+    ///
+    /// The access keys inside the scope are only active while the focus is inside
+    /// it, and the keys outside of it are inactive meanwhile.
+    ///
+    /// This should be called in response to [`LifeCycle::WidgetAdded`](crate::LifeCycle::WidgetAdded).
+    pub fn register_access_key_scope(&mut self) {
+        trace!("register_access_key_scope");
+        let id = self.widget_id();
+        self.global_state.access_keys.register_scope(id);
+    }
+
+    /// Subscribe to the part of the app's [`AppStore`] picked by `select`.
+    ///
+    /// When the value returned by `select` changes, this widget receives the
+    /// [`STORE_CHANGED`](crate::command::STORE_CHANGED) command, and is painted again.
+    ///
+    /// Does nothing, and logs a warning, if the app has no store holding a `T`.
+    ///
+    /// This should be called in response to [`LifeCycle::WidgetAdded`](crate::LifeCycle::WidgetAdded).
+    pub fn subscribe_to_store<T: 'static, S: Data>(&mut self, select: impl Fn(&T) -> S + 'static) {
+        trace!("subscribe_to_store");
+        match self.app_store::<T>() {
+            Some(store) => store.subscribe(self.widget_id(), select),
+            None => warn!(
+                "subscribe_to_store: the app has no store of type {}",
+                std::any::type_name::<T>()
+            ),
+        }
+    }
+
     /// Register this widget as accepting text input.
     pub fn register_text_input(&mut self, document: impl ImeHandlerRef + 'static) {
         let registration = TextFieldRegistration {
@@ -762,6 +1573,47 @@ impl LifeCycleCtx<'_, '_> {
         self.widget_state.text_registrations.push(registration);
     }
 
+    /// Opt out of the focus ring the window paints around this widget.
+    ///
+    /// Widgets that draw their own focus indicator (eg by changing their border
+    /// color) should call this, typically in response to
+    /// [`LifeCycle::WidgetAdded`](crate::LifeCycle::WidgetAdded).
+    ///
+    /// See [`theme::FOCUS_RING_COLOR`](crate::theme::FOCUS_RING_COLOR) for the look of
+    /// the default focus ring.
+    pub fn set_draws_own_focus_ring(&mut self, draws_own_focus_ring: bool) {
+        trace!("set_draws_own_focus_ring {}", draws_own_focus_ring);
+        self.widget_state.draws_own_focus_ring = draws_own_focus_ring;
+    }
+
+    /// Set how held keys repeat while this widget is focused.
+    ///
+    /// By default, repeated key presses come from the platform. Widgets that treat
+    /// keys as buttons, eg games or musical keyboards, can disable repeats, or have
+    /// the window repeat held keys at a fixed rate; see [`KeyRepeat`].
+    ///
+    /// This should be called in response to [`LifeCycle::WidgetAdded`](crate::LifeCycle::WidgetAdded).
+    ///
+    /// [`KeyRepeat`]: crate::KeyRepeat
+    pub fn set_key_repeat(&mut self, key_repeat: crate::KeyRepeat) {
+        trace!("set_key_repeat {:?}", key_repeat);
+        self.widget_state.key_repeat = key_repeat;
+    }
+
+    /// Set whether at most one child of this widget is hot at a time.
+    ///
+    /// By default, every child containing the pointer is hot, so hover highlights
+    /// light up on all the overlapping children of stacked layouts. With exclusive
+    /// hot state, only the topmost child under the pointer is hot and receives pointer
+    /// events: the one with the highest z-index, or among those, the last one in
+    /// [`Widget::children`](crate::Widget::children) order.
+    ///
+    /// This should be called in response to [`LifeCycle::WidgetAdded`](crate::LifeCycle::WidgetAdded).
+    pub fn set_exclusive_hot(&mut self, exclusive_hot: bool) {
+        trace!("set_exclusive_hot {}", exclusive_hot);
+        self.widget_state.exclusive_hot = exclusive_hot;
+    }
+
     // TODO - remove - See issue #15
     /// Register this widget as a portal.
     ///
@@ -772,6 +1624,36 @@ impl LifeCycleCtx<'_, '_> {
 }
 
 impl LayoutCtx<'_, '_> {
+    /// Set the factor this widget scales `child` by when painting it, eg to zoom.
+    ///
+    /// The scale applies around this widget's origin, to the child's origin as well
+    /// as its content. It's taken into account by the window and screen positions of
+    /// the child and its descendants, eg by [`EventCtx::to_screen`], so popups
+    /// and windows opened next to them are placed where they're painted. The default
+    /// is 1.0.
+    ///
+    /// Mouse events and the child's paint region aren't scaled; the widget must do
+    /// this itself.
+    pub fn set_child_scale(&mut self, child: &mut WidgetPod<impl Widget>, scale: f64) {
+        if child.state.scale == scale {
+            return;
+        }
+        trace!("set_child_scale({:?}, {})", child.id(), scale);
+        child.state.scale = scale;
+        child.state.needs_window_origin = true;
+        self.widget_state.needs_window_origin = true;
+    }
+
+    /// The part of this widget which is visible through the nearest enclosing
+    /// [`Portal`](crate::widget::Portal), in this widget's coordinates.
+    ///
+    /// Returns `None` if the widget isn't inside a portal. Like the mouse
+    /// position, this is computed from the widget's origin in the previous
+    /// layout pass.
+    pub fn viewport_rect(&self) -> Option<Rect> {
+        self.viewport
+    }
+
     /// Set explicit paint [`Insets`] for this widget.
     ///
     /// You are not required to set explicit paint bounds unless you need
@@ -803,27 +1685,59 @@ impl LayoutCtx<'_, '_> {
         self.widget_state.baseline_offset = baseline
     }
 
+    /// The vertical metrics of `font`, eg to align an icon with a label's text.
+    ///
+    /// The font is usually [`theme::UI_FONT`](crate::theme::UI_FONT), or the font
+    /// of a [`TextLayout`](crate::text::TextLayout) the widget draws. The metrics are
+    /// measured once per font and window.
+    pub fn font_metrics(
+        &mut self,
+        font: impl Into<KeyOrValue<FontDescriptor>>,
+        env: &Env,
+    ) -> FontMetrics {
+        let font = font.into().resolve(env);
+        self.global_state
+            .text_metrics
+            .font_metrics(&mut self.global_state.text, &font)
+    }
+
+    /// Set how this widget may be split across pages when printed.
+    ///
+    /// This is reset before every layout pass, so widgets which need it should
+    /// call it in every [`layout`](crate::Widget::layout). See the [`print`](crate::print)
+    /// module.
+    pub fn set_page_break(&mut self, page_break: PageBreak) {
+        self.widget_state.page_break = page_break;
+    }
+
     /// Set the position of a child widget, in the paren't coordinate space. This
     /// will also implicitly change "hot" status and affect the parent's display rect.
     ///
     /// Container widgets must call this method with each non-stashed child in their
     /// layout method, after calling `child.layout(...)`.
     pub fn place_child(&mut self, child: &mut WidgetPod<impl Widget>, origin: Point, env: &Env) {
-        child.state.origin = origin;
+        let style_insets = child.state.style_insets;
+        child.state.origin = self
+            .global_state
+            .snap_point(origin + Vec2::new(style_insets.x0, style_insets.y0));
         child.state.is_expecting_place_child_call = false;
         let layout_rect = child.layout_rect();
 
         self.widget_state.local_paint_rect =
             self.widget_state.local_paint_rect.union(child.paint_rect());
+        // The child's elevation may have changed since it was added.
+        self.widget_state.has_z_indexed_children |= child.state.stacking_order() != 0;
 
         // if the widget has moved, it may have moved under the mouse, in which
         // case we need to handle that.
+        let is_occluded = self.widget_state.occluded_children.contains(&child.id());
         if WidgetPod::update_hot_state(
             &mut child.inner,
             &mut child.state,
             self.global_state,
             layout_rect,
             self.mouse_pos,
+            is_occluded,
             env,
         ) {
             self.widget_state.merge_up(&mut child.state);
@@ -832,6 +1746,25 @@ impl LayoutCtx<'_, '_> {
 }
 
 impl PaintCtx<'_, '_, '_> {
+    /// Paint the given children, from the lowest z-index to the highest.
+    ///
+    /// Children with the same z-index are painted in the given order. A child's
+    /// [elevation](WidgetPod::with_elevation) is added to its z-index.
+    /// See [`EventCtx::set_child_z_index`].
+    pub fn paint_children_in_z_order<'c, W: Widget + 'c>(
+        &mut self,
+        children: impl IntoIterator<Item = &'c mut WidgetPod<W>>,
+        env: &Env,
+    ) {
+        let mut children: Vec<_> = children.into_iter().collect();
+        if self.widget_state.has_z_indexed_children {
+            children.sort_by_key(|child| child.state.stacking_order());
+        }
+        for child in children {
+            child.paint(self, env);
+        }
+    }
+
     /// The depth in the tree of the currently painting widget.
     ///
     /// This may be used in combination with [`paint_with_z_index`](Self::paint_with_z_index) in order
@@ -917,6 +1850,72 @@ impl PaintCtx<'_, '_, '_> {
             transform: current_transform,
         })
     }
+
+    /// Paint into an offscreen bitmap of the given size, and return it.
+    ///
+    /// `f` is called with a context which paints into the bitmap, where the whole
+    /// bitmap is visible. `scale` is the number of pixels per display point; use a
+    /// scale lower than 1.0 to get a scaled-down copy of the content.
+    ///
+    /// The bitmap can be kept to avoid painting expensive content on every frame.
+    /// Returns `None` if the bitmap couldn't be created.
+    pub fn paint_to_image(
+        &mut self,
+        size: Size,
+        scale: f64,
+        f: impl FnOnce(&mut PaintCtx),
+    ) -> Option<ImageBuf> {
+        paint_offscreen(
+            self.global_state,
+            self.widget_state,
+            self.depth,
+            size,
+            scale,
+            f,
+        )
+    }
+}
+
+/// Paint into an offscreen bitmap, with a [`PaintCtx`] for the given widget.
+///
+/// See [`PaintCtx::paint_to_image`].
+pub(crate) fn paint_offscreen(
+    global_state: &mut GlobalPassCtx,
+    widget_state: &WidgetState,
+    depth: u32,
+    size: Size,
+    scale: f64,
+    f: impl FnOnce(&mut PaintCtx),
+) -> Option<ImageBuf> {
+    let mut device = global_state.offscreen_devices.take()?;
+    let image = render_backend::render_to_image(&mut device, size, scale, |piet| {
+        let mut ctx = PaintCtx {
+            global_state,
+            widget_state,
+            render_ctx: piet,
+            z_ops: Vec::new(),
+            region: size.to_rect().into(),
+            depth,
+        };
+        f(&mut ctx);
+
+        let mut z_ops = std::mem::take(&mut ctx.z_ops);
+        z_ops.sort_by_key(|k| k.z_index);
+        for z_op in z_ops {
+            ctx.with_save(|ctx| {
+                ctx.render_ctx.transform(z_op.transform);
+                (z_op.paint_func)(ctx);
+            });
+        }
+    });
+    global_state.offscreen_devices.give_back(device);
+    match image {
+        Ok(image) => Some(image),
+        Err(err) => {
+            error!("Offscreen painting failed: {}", err);
+            None
+        }
+    }
 }
 
 impl<'a> GlobalPassCtx<'a> {
@@ -926,11 +1925,25 @@ impl<'a> GlobalPassCtx<'a> {
         debug_logger: &'a mut DebugLogger,
         command_queue: &'a mut CommandQueue,
         action_queue: &'a mut ActionQueue,
-        timers: &'a mut HashMap<TimerToken, WidgetId>,
+        timers: &'a mut HashMap<TimerToken, (WidgetId, Instant)>,
+        broadcast_listeners: &'a mut BroadcastListeners,
+        access_keys: &'a mut AccessKeys,
         mock_timer_queue: Option<&'a mut MockTimerQueue>,
         window: &'a WindowHandle,
         window_id: WindowId,
         focus_widget: Option<WidgetId>,
+        style_sheet: &'a StyleSheet,
+        app_store: Option<Rc<dyn AnyAppStore>>,
+        image_cache: ImageCache,
+        text_metrics: TextMetricsCache,
+        offscreen_devices: &'a mut OffscreenDevices,
+        mutate_callbacks: &'a mut Vec<MutateCallback>,
+        safe_area: Insets,
+        frame_stats: FrameStats,
+        is_window_visible: bool,
+        reduced_motion: bool,
+        ui_scale: f64,
+        telemetry: Option<SharedTelemetryHook>,
     ) -> Self {
         GlobalPassCtx {
             ext_event_sink,
@@ -938,22 +1951,77 @@ impl<'a> GlobalPassCtx<'a> {
             command_queue,
             action_queue,
             timers,
+            broadcast_listeners,
+            access_keys,
             mock_timer_queue,
             window,
             window_id,
             focus_widget,
+            style_sheet,
+            app_store,
+            image_cache,
+            text_metrics,
+            offscreen_devices,
+            mutate_callbacks,
+            safe_area,
+            frame_stats,
+            is_window_visible,
+            reduced_motion,
+            ui_scale,
+            close_prevented: false,
+            pixel_scale: None,
+            telemetry,
+            track_event_handler: false,
+            event_handler: None,
+            pass: None,
             text: window.text(),
         }
     }
 
+    /// Round `point` to the nearest physical pixel, in integer layout mode.
+    pub(crate) fn snap_point(&self, point: Point) -> Point {
+        match self.pixel_scale {
+            Some(scale) => point.to_px(scale).round().to_dp(scale),
+            None => point,
+        }
+    }
+
+    /// Round `size` to the nearest physical pixel, in integer layout mode.
+    pub(crate) fn snap_size(&self, size: Size) -> Size {
+        match self.pixel_scale {
+            Some(scale) => size.to_px(scale).round().to_dp(scale),
+            None => size,
+        }
+    }
+
     pub(crate) fn submit_command(&mut self, command: Command) {
         trace!("submit_command");
         self.command_queue
             .push_back(command.default_to(self.window_id.into()));
     }
 
+    /// Record that `widget_id` handled the current event, unless one of its
+    /// descendants did.
+    pub(crate) fn set_event_handler(&mut self, widget_id: WidgetId) {
+        if self.track_event_handler && self.event_handler.is_none() {
+            self.event_handler = Some(widget_id);
+        }
+    }
+
     pub(crate) fn submit_action(&mut self, action: Action, widget_id: WidgetId) {
         trace!("submit_action");
+        if let Some(telemetry) = &self.telemetry {
+            let time = match &self.mock_timer_queue {
+                Some(timer_queue) => timer_queue.now(),
+                None => Instant::now(),
+            };
+            telemetry.borrow_mut().observe(TelemetryRecord {
+                observed: Observed::Action(&action),
+                widget_id: Some(widget_id),
+                window_id: self.window_id,
+                time,
+            });
+        }
         self.action_queue
             .push_back((action, widget_id, self.window_id));
     }
@@ -961,19 +2029,95 @@ impl<'a> GlobalPassCtx<'a> {
     pub(crate) fn request_timer(&mut self, duration: Duration, widget_id: WidgetId) -> TimerToken {
         trace!("request_timer duration={:?}", duration);
 
-        let timer_token = if let Some(timer_queue) = self.mock_timer_queue.as_mut() {
+        // The widget still sees the deadline it asked for; only the platform timer
+        // of a hidden window is delayed.
+        let is_window_visible = self.is_window_visible;
+        let coalesce = |elapsed| {
+            if is_window_visible {
+                duration
+            } else {
+                coalesce_hidden_timer(elapsed, duration)
+            }
+        };
+        let (timer_token, now) = if let Some(timer_queue) = self.mock_timer_queue.as_mut() {
             // Path taken in unit tests, because we don't want to use platform timers
-            timer_queue.add_timer(duration)
+            let platform_duration = coalesce(timer_queue.current_time);
+            (timer_queue.add_timer(platform_duration), timer_queue.now())
         } else {
             // Normal path
-            self.window.request_timer(duration)
+            let now = Instant::now();
+            let platform_duration = coalesce(now - *TIMER_EPOCH);
+            (self.window.request_timer(platform_duration), now)
         };
 
-        self.timers.insert(timer_token, widget_id);
+        self.timers.insert(timer_token, (widget_id, now + duration));
         timer_token
     }
 }
 
+/// The interval the timers requested by the widgets of a hidden window fire at.
+///
+/// Their events are held back until the window is shown, so they don't need to
+/// fire on time: delaying them to the next multiple of this interval makes the
+/// timers of all hidden windows fire together.
+pub(crate) const HIDDEN_WINDOW_TIMER_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The time [`HIDDEN_WINDOW_TIMER_INTERVAL`] is counted from.
+static TIMER_EPOCH: Lazy<Instant> = Lazy::new(Instant::now);
+
+/// The duration of a timer requested `elapsed` after the timer epoch, delayed to
+/// the next multiple of [`HIDDEN_WINDOW_TIMER_INTERVAL`].
+fn coalesce_hidden_timer(elapsed: Duration, duration: Duration) -> Duration {
+    let interval = HIDDEN_WINDOW_TIMER_INTERVAL.as_nanos();
+    let deadline = (elapsed + duration).as_nanos();
+    let coalesced_deadline = (deadline + interval - 1) / interval * interval;
+    duration + Duration::from_nanos((coalesced_deadline - deadline) as u64)
+}
+
+/// The side effects of a widget which isn't in the tree yet, eg one being
+/// [prewarmed](EventCtx::prewarm_child), applied once it's
+/// [adopted](WidgetPod::adopt).
+#[derive(Default)]
+pub(crate) struct DetachedEffects {
+    commands: CommandQueue,
+    actions: ActionQueue,
+    timers: HashMap<TimerToken, (WidgetId, Instant)>,
+    broadcast_listeners: BroadcastListeners,
+    access_keys: AccessKeys,
+    text_registrations: Vec<TextFieldRegistration>,
+}
+
+impl DetachedEffects {
+    /// Exchange the effects with the window's queues and registrations.
+    ///
+    /// Calling this before and after a pass on a detached widget collects the
+    /// effects of the pass.
+    fn swap(&mut self, global_state: &mut GlobalPassCtx) {
+        std::mem::swap(&mut self.commands, global_state.command_queue);
+        std::mem::swap(&mut self.actions, global_state.action_queue);
+        std::mem::swap(&mut self.timers, global_state.timers);
+        std::mem::swap(
+            &mut self.broadcast_listeners,
+            global_state.broadcast_listeners,
+        );
+        std::mem::swap(&mut self.access_keys, global_state.access_keys);
+    }
+
+    /// Apply the effects to the window, and to the state of the widget's new parent.
+    pub(crate) fn apply(self, global_state: &mut GlobalPassCtx, parent_state: &mut WidgetState) {
+        global_state.command_queue.extend(self.commands);
+        global_state.action_queue.extend(self.actions);
+        global_state.timers.extend(self.timers);
+        global_state
+            .broadcast_listeners
+            .append(self.broadcast_listeners);
+        global_state.access_keys.append(self.access_keys);
+        parent_state
+            .text_registrations
+            .extend(self.text_registrations);
+    }
+}
+
 impl<'c> Deref for PaintCtx<'_, '_, 'c> {
     type Target = Piet<'c>;
 
@@ -987,3 +2131,41 @@ impl<'c> DerefMut for PaintCtx<'_, '_, 'c> {
         self.render_ctx
     }
 }
+
+impl MutateCallback {
+    fn new<W: Widget + StoreInWidgetMut>(
+        id: WidgetId,
+        f: impl FnOnce(WidgetMut<'_, '_, W>) + 'static,
+    ) -> Self {
+        let callback = move |widget: &mut dyn Widget,
+                             ctx: WidgetCtx<'_, '_>,
+                             parent_widget_state: &mut WidgetState| {
+            let Some(widget) = widget.as_mut_any().downcast_mut::<W>() else {
+                warn!(
+                    "Widget {:?} is not a {}, skipping scheduled mutation",
+                    id,
+                    std::any::type_name::<W>()
+                );
+                return;
+            };
+            f(WidgetMut {
+                parent_widget_state,
+                inner: W::from_widget_and_ctx(widget, ctx),
+            });
+        };
+        MutateCallback {
+            id,
+            callback: Box::new(callback),
+        }
+    }
+
+    /// Run the mutation on `widget`, whose parent has the given state.
+    pub(crate) fn call(
+        self,
+        widget: &mut dyn Widget,
+        ctx: WidgetCtx<'_, '_>,
+        parent_widget_state: &mut WidgetState,
+    ) {
+        (self.callback)(widget, ctx, parent_widget_state);
+    }
+}
@@ -0,0 +1,161 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Turning the damaged regions of a window into what's presented to the compositor.
+
+use druid_shell::Region;
+
+use crate::{Rect, Size};
+
+/// Above this many rects, the damage region is replaced by its bounding box.
+///
+/// Each rect is a separate invalidation request to the platform; past a handful
+/// of them, the overhead costs more than repainting the space between them.
+const MAX_DAMAGE_RECTS: usize = 8;
+
+/// Above this fraction of the window's area, the whole window is presented.
+const FULL_PRESENT_THRESHOLD: f64 = 0.75;
+
+/// Simplify a damage region into the region presented to the platform.
+///
+/// The returned region is clipped to `window`, has at most [`MAX_DAMAGE_RECTS`]
+/// rects, and is the whole window if the damage covers most of it.
+pub(crate) fn coalesce(region: &Region, window: Rect) -> Region {
+    let rects: Vec<Rect> = region
+        .rects()
+        .iter()
+        .map(|rect| rect.intersect(window))
+        .filter(|rect| rect.area() > 0.0)
+        .collect();
+
+    let mut coalesced = Region::EMPTY;
+    if rects.len() > MAX_DAMAGE_RECTS {
+        coalesced.add_rect(region.bounding_box().intersect(window));
+    } else {
+        for rect in rects {
+            coalesced.add_rect(rect);
+        }
+    }
+
+    if area(&coalesced) >= window.area() * FULL_PRESENT_THRESHOLD {
+        return Region::from(window);
+    }
+    coalesced
+}
+
+/// The total area of the rects of a region.
+///
+/// Overlapping rects are counted twice, so this is an upper bound.
+fn area(region: &Region) -> f64 {
+    region.rects().iter().map(Rect::area).sum()
+}
+
+/// The area of a region inside the window, capped to the window's area.
+pub(crate) fn presented_area(region: &Region, window_size: Size) -> f64 {
+    let window = window_size.to_rect();
+    let area: f64 = region
+        .rects()
+        .iter()
+        .map(|rect| rect.intersect(window).area())
+        .sum();
+    area.min(window.area())
+}
+
+/// Counters of how much of a window was presented to the platform.
+///
+/// These measure the savings of partial presentation: a frame where a single
+/// button changed should only present that button, not the whole window.
+///
+/// See [`WindowRoot::present_stats`](crate::WindowRoot::present_stats).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PresentStats {
+    /// Number of frames painted.
+    pub frames: u64,
+    /// Number of frames where the whole window was presented.
+    pub full_frames: u64,
+    /// Sum of the area presented in each frame, in display points.
+    pub presented_area: f64,
+    /// Sum of the area of the window in each frame, in display points.
+    pub window_area: f64,
+}
+
+impl PresentStats {
+    /// Record a frame which presented `invalid` in a window of the given size.
+    pub(crate) fn record(&mut self, invalid: &Region, window_size: Size) {
+        let presented = presented_area(invalid, window_size);
+        let window_area = window_size.area();
+
+        self.frames += 1;
+        if presented >= window_area {
+            self.full_frames += 1;
+        }
+        self.presented_area += presented;
+        self.window_area += window_area;
+    }
+
+    /// The fraction of the window's area which didn't need to be presented,
+    /// between 0.0 and 1.0.
+    pub fn savings(&self) -> f64 {
+        if self.window_area == 0.0 {
+            return 0.0;
+        }
+        1.0 - self.presented_area / self.window_area
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{widget_ids, TestHarness};
+    use crate::widget::{Button, Flex};
+
+    const WINDOW: Rect = Rect::new(0.0, 0.0, 100.0, 100.0);
+
+    #[test]
+    fn coalesce_damage() {
+        let mut region = Region::EMPTY;
+        region.add_rect(Rect::new(10.0, 10.0, 20.0, 20.0));
+        region.add_rect(Rect::new(90.0, 90.0, 120.0, 120.0));
+        let coalesced = coalesce(&region, WINDOW);
+        assert_eq!(
+            coalesced.rects(),
+            &[
+                Rect::new(10.0, 10.0, 20.0, 20.0),
+                Rect::new(90.0, 90.0, 100.0, 100.0),
+            ]
+        );
+
+        let mut region = Region::EMPTY;
+        for i in 0..10 {
+            let x = i as f64 * 5.0;
+            region.add_rect(Rect::new(x, 0.0, x + 1.0, 1.0));
+        }
+        let coalesced = coalesce(&region, WINDOW);
+        assert_eq!(coalesced.rects(), &[Rect::new(0.0, 0.0, 46.0, 1.0)]);
+
+        let region = Region::from(Rect::new(0.0, 0.0, 100.0, 80.0));
+        let coalesced = coalesce(&region, WINDOW);
+        assert_eq!(coalesced.rects(), &[WINDOW]);
+    }
+
+    #[test]
+    fn small_change_presents_small_area() {
+        let [button_id] = widget_ids();
+        let widget = Flex::row().with_child_id(Button::new("Hello"), button_id);
+
+        let mut harness = TestHarness::create(widget);
+        harness.render();
+        let stats = harness.window().present_stats();
+        assert_eq!(stats.frames, 1);
+        assert_eq!(stats.full_frames, 1);
+
+        // Hovering the button only repaints the button.
+        harness.mouse_move_to(button_id);
+        harness.render();
+        let stats = harness.window().present_stats();
+        assert_eq!(stats.frames, 2);
+        assert_eq!(stats.full_frames, 1);
+        assert!(stats.savings() > 0.0);
+    }
+}
@@ -12,7 +12,7 @@ use crate::debug_values::{
     LayoutInfo, LayoutTree, LogId, MyWidgetId, Snapshot, StateTree, Timeline, Value,
 };
 use crate::widget::WidgetRef;
-use crate::Widget;
+use crate::{BoxConstraints, Size, Widget, WidgetId};
 
 #[derive(Debug)]
 pub struct DebugLog {
@@ -21,6 +21,87 @@ pub struct DebugLog {
     children: Vec<LogId>,
 }
 
+/// What a [`WidgetPod`](crate::WidgetPod) did with an event.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventRouting {
+    /// The event wasn't relevant to the widget, eg the pointer wasn't over it, or it
+    /// didn't have focus.
+    Skipped,
+    /// Another widget had already handled the event.
+    AlreadyHandled,
+    /// The widget received the event, and didn't handle it.
+    Delivered,
+    /// The widget received the event, and marked it as handled.
+    Handled,
+}
+
+/// A widget pod visited while routing an event.
+#[derive(Clone, Debug)]
+pub struct EventTraceEntry {
+    pub widget_id: WidgetId,
+    pub widget_name: &'static str,
+    /// The depth of the widget in the tree, the root being 0.
+    pub depth: usize,
+    pub routing: EventRouting,
+}
+
+/// The path of an event through the widget tree.
+///
+/// Entries are in the order widgets were visited, parents before their children.
+#[derive(Clone, Debug)]
+pub struct EventTrace {
+    pub event_name: &'static str,
+    pub entries: Vec<EventTraceEntry>,
+}
+
+impl EventTrace {
+    /// Find how the event was routed to the given widget.
+    ///
+    /// Returns `None` if the event never reached the widget's pod, eg because its
+    /// parent skipped it.
+    pub fn routing_of(&self, widget_id: WidgetId) -> Option<EventRouting> {
+        self.entries
+            .iter()
+            .find(|entry| entry.widget_id == widget_id)
+            .map(|entry| entry.routing)
+    }
+
+    /// The widget which marked the event as handled, if any.
+    pub fn handled_by(&self) -> Option<WidgetId> {
+        self.entries
+            .iter()
+            .find(|entry| entry.routing == EventRouting::Handled)
+            .map(|entry| entry.widget_id)
+    }
+}
+
+/// A widget layout which broke the [`BoxConstraints`] protocol.
+///
+/// Violations are only detected in debug builds.
+#[derive(Clone, Debug)]
+pub struct LayoutViolation {
+    pub widget_id: WidgetId,
+    pub widget_name: &'static str,
+    pub kind: LayoutViolationKind,
+    /// The constraints passed to the widget's `layout` method.
+    pub constraints: BoxConstraints,
+    /// The size returned by the widget's `layout` method.
+    pub size: Size,
+}
+
+/// The kind of a [`LayoutViolation`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LayoutViolationKind {
+    /// The constraints had a negative, infinite or NaN minimum, a NaN maximum, or a
+    /// minimum larger than their maximum.
+    InvalidConstraints,
+    /// The returned size was infinite or NaN.
+    NonFiniteSize,
+    /// The returned size was smaller than the minimum or larger than the maximum
+    /// of the constraints.
+    SizeOutsideConstraints,
+}
+
 #[derive(Debug)]
 pub struct DebugLogger {
     pub activated: bool,
@@ -35,6 +116,30 @@ pub struct DebugLogger {
     pub snapshots: HashMap<LogId, Snapshot>,
     pub span_stack: Vec<LogId>,
     pub log_id_counter: LogId,
+
+    /// Whether to record an [`EventTrace`] for each event.
+    pub record_event_traces: bool,
+    pub event_traces: Vec<EventTrace>,
+    event_depth: usize,
+
+    /// Whether to keep the [`LayoutViolation`]s reported in `layout_violations`.
+    pub record_layout_violations: bool,
+    pub layout_violations: Vec<LayoutViolation>,
+
+    /// The parent and path segment of each logged widget, eg `Button[1]`, from which
+    /// its debug path is built.
+    ///
+    /// Unlike ids, paths are the same across runs, so they can be used to match the
+    /// widgets of two logs or traces.
+    widget_paths: HashMap<WidgetId, PathSegment>,
+    /// The children of each logged widget, to forget the paths of removed widgets.
+    widget_children: HashMap<WidgetId, Vec<WidgetId>>,
+}
+
+#[derive(Debug)]
+struct PathSegment {
+    parent: Option<WidgetId>,
+    name: String,
 }
 
 // ---
@@ -52,6 +157,13 @@ impl DebugLogger {
             snapshots: Default::default(),
             span_stack: Vec::new(),
             log_id_counter: LogId(0),
+            record_event_traces: false,
+            event_traces: Vec::new(),
+            event_depth: 0,
+            record_layout_violations: false,
+            layout_violations: Vec::new(),
+            widget_paths: HashMap::new(),
+            widget_children: HashMap::new(),
         };
         new_self.push_log(false, "initial value");
         new_self
@@ -145,6 +257,102 @@ impl DebugLogger {
         self.span_stack.pop();
     }
 
+    /// Start recording the trace of a new event.
+    pub fn begin_event_trace(&mut self, event_name: &'static str) {
+        if !self.record_event_traces {
+            return;
+        }
+        self.event_depth = 0;
+        self.event_traces.push(EventTrace {
+            event_name,
+            entries: Vec::new(),
+        });
+    }
+
+    /// Record that an event reached a widget pod.
+    ///
+    /// Returns the index to pass to [`end_event_route`](Self::end_event_route).
+    pub fn begin_event_route(
+        &mut self,
+        widget_id: WidgetId,
+        widget_name: &'static str,
+    ) -> Option<usize> {
+        if !self.record_event_traces {
+            return None;
+        }
+        let trace = self.event_traces.last_mut()?;
+        trace.entries.push(EventTraceEntry {
+            widget_id,
+            widget_name,
+            depth: self.event_depth,
+            routing: EventRouting::Skipped,
+        });
+        self.event_depth += 1;
+        Some(trace.entries.len() - 1)
+    }
+
+    /// Record what a widget pod did with an event.
+    ///
+    /// If `routing` is [`EventRouting::Handled`] but a descendant of the widget
+    /// already handled the event, the widget is recorded as
+    /// [`Delivered`](EventRouting::Delivered) instead.
+    pub fn end_event_route(&mut self, index: Option<usize>, routing: EventRouting) {
+        let (Some(index), Some(trace)) = (index, self.event_traces.last_mut()) else {
+            return;
+        };
+        self.event_depth -= 1;
+        let handled_by_descendant = trace.entries[index + 1..]
+            .iter()
+            .any(|entry| entry.routing == EventRouting::Handled);
+        let entry = &mut trace.entries[index];
+        entry.routing = if routing == EventRouting::Handled && handled_by_descendant {
+            EventRouting::Delivered
+        } else {
+            routing
+        };
+
+        if self.activated {
+            let message = format!("{} {:?}", entry.widget_name, entry.routing);
+            self.push_log(false, &message);
+        }
+    }
+
+    /// Report a widget which broke the layout protocol.
+    ///
+    /// The violation is logged as a warning, and as an important log if the logger
+    /// is activated.
+    pub fn report_layout_violation(&mut self, violation: LayoutViolation) {
+        let message = match violation.kind {
+            LayoutViolationKind::InvalidConstraints => format!(
+                "Widget '{}' #{} received invalid constraints {:?}",
+                violation.widget_name,
+                violation.widget_id.to_raw(),
+                violation.constraints,
+            ),
+            LayoutViolationKind::NonFiniteSize => format!(
+                "Widget '{}' #{} returned non-finite size {:?} for constraints {:?}",
+                violation.widget_name,
+                violation.widget_id.to_raw(),
+                violation.size,
+                violation.constraints,
+            ),
+            LayoutViolationKind::SizeOutsideConstraints => format!(
+                "Widget '{}' #{} returned size {:?} outside of constraints {:?}",
+                violation.widget_name,
+                violation.widget_id.to_raw(),
+                violation.size,
+                violation.constraints,
+            ),
+        };
+        tracing::warn!("{}", message);
+        if self.activated {
+            self.push_log(true, &message);
+        }
+        if self.record_layout_violations {
+            self.layout_violations.push(violation);
+        }
+    }
+
     fn push_snapshot(&mut self) {
         if !self.activated {
             return;
@@ -166,19 +374,20 @@ impl DebugLogger {
         if !self.activated {
             return;
         }
-        let widget_id = widget.state().id.to_raw() as u32;
+        let widget_id = widget.state().id.to_raw();
         let layout_info = LayoutInfo {
             layout_rect: widget.state().layout_rect(),
             typename: widget.deref().short_type_name().into(),
             children: widget
                 .children()
                 .into_iter()
-                .map(|child| child.state().id.to_raw() as u32)
+                .map(|child| child.state().id.to_raw())
                 .collect(),
         };
 
         self.widget_states
             .insert(widget_id, Self::get_widget_state(widget));
+        self.record_widget_path(widget);
 
         // TODO
         let mut widgets = (*self.layout_tree.widgets).clone();
@@ -186,6 +395,77 @@ impl DebugLogger {
         self.layout_tree.widgets = widgets.into();
     }
 
+    /// The debug path of the widget, eg `Flex/Button[1]` for the second child of the
+    /// root `Flex`, if it was logged.
+    pub fn widget_path(&self, widget_id: WidgetId) -> Option<String> {
+        let mut segment = self.widget_paths.get(&widget_id)?;
+        let mut names = vec![segment.name.as_str()];
+        while let Some(parent) = segment.parent {
+            segment = self.widget_paths.get(&parent)?;
+            names.push(&segment.name);
+        }
+        names.reverse();
+        Some(names.join("/"))
+    }
+
+    /// The widget with the debug path `path`, if it was logged.
+    ///
+    /// This goes through every logged widget, so it's meant for tests and tools.
+    pub fn widget_at_path(&self, path: &str) -> Option<WidgetId> {
+        self.widget_paths
+            .keys()
+            .copied()
+            .find(|id| self.widget_path(*id).as_deref() == Some(path))
+    }
+
+    /// Record the path segments of `widget`'s children, and forget the paths of its
+    /// removed children.
+    ///
+    /// A widget whose parent hasn't been logged yet is considered a root.
+    fn record_widget_path(&mut self, widget: WidgetRef<'_, dyn Widget>) {
+        let id = widget.state().id;
+        self.widget_paths.entry(id).or_insert_with(|| PathSegment {
+            parent: None,
+            name: widget.deref().short_type_name().to_string(),
+        });
+
+        let children = widget.children();
+        let child_ids: Vec<_> = children.iter().map(|child| child.state().id).collect();
+        for (index, child) in children.into_iter().enumerate() {
+            let segment = PathSegment {
+                parent: Some(id),
+                name: format!("{}[{}]", child.deref().short_type_name(), index),
+            };
+            self.widget_paths.insert(child.state().id, segment);
+        }
+
+        let old_child_ids = self
+            .widget_children
+            .insert(id, child_ids.clone())
+            .unwrap_or_default();
+        for old_child in old_child_ids {
+            if !child_ids.contains(&old_child) {
+                self.forget_widget_path(old_child, id);
+            }
+        }
+    }
+
+    /// Forget the paths of `widget_id` and its descendants, unless the widget was
+    /// moved to another parent.
+    fn forget_widget_path(&mut self, widget_id: WidgetId, parent: WidgetId) {
+        let is_still_child = self
+            .widget_paths
+            .get(&widget_id)
+            .map_or(false, |segment| segment.parent == Some(parent));
+        if !is_still_child {
+            return;
+        }
+        self.widget_paths.remove(&widget_id);
+        for child in self.widget_children.remove(&widget_id).unwrap_or_default() {
+            self.forget_widget_path(child, widget_id);
+        }
+    }
+
     pub fn get_widget_state(widget: WidgetRef<'_, dyn Widget>) -> StateTree {
         let mut state = StateTree::default();
         let w_state = widget.state();
@@ -241,12 +521,12 @@ impl DebugLogger {
             };
 
             for child in widget.children() {
-                let child_id = child.state().id.to_raw() as u32;
+                let child_id = child.state().id.to_raw();
                 layout_info.children.insert(child_id);
                 add_to_tree(widgets_map, widget_states, child);
             }
 
-            let id = widget.state().id.to_raw() as u32;
+            let id = widget.state().id.to_raw();
             widgets_map.insert(id, layout_info);
             widget_states.insert(id, DebugLogger::get_widget_state(widget));
         }
@@ -257,7 +537,7 @@ impl DebugLogger {
 
         (
             LayoutTree {
-                root: Some(root_widget.state().id.to_raw() as u32),
+                root: Some(root_widget.state().id.to_raw()),
                 widgets: Arc::new(widgets_map),
             },
             widget_states,
@@ -0,0 +1,236 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Debugging aids which can be toggled while the app is running.
+//!
+//! Each [`DebugSwitch`] is toggled by submitting
+//! [`TOGGLE_DEBUG_SWITCH`](crate::command::TOGGLE_DEBUG_SWITCH) with it.
+//!
+//! In debug builds, switches can also be toggled without changing the app's code,
+//! through the `MASONRY_DEBUG` environment variable. It holds a comma-separated list
+//! of switch names, which are enabled at launch:
+//!
+//! ```text
+//! MASONRY_DEBUG=layout,ids cargo run --example calc
+//! ```
+//!
+//! If the list contains `stdin`, each line written to the app's standard input is read
+//! as a switch name, and toggles that switch.
+
+use std::io::BufRead;
+
+use tracing::{info, warn};
+
+use crate::ext_event::ExtEventSink;
+use crate::time_travel::FRAME_HISTORY_LENGTH;
+use crate::{command, Env, Target};
+
+const ENV_VAR: &str = "MASONRY_DEBUG";
+
+/// The animation speed set by [`DebugSwitch::SlowAnimations`].
+const SLOW_ANIMATION_SPEED: f64 = 0.1;
+
+/// A debugging aid which can be toggled at runtime.
+///
+/// See the [module-level documentation](self) for details.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DebugSwitch {
+    /// Paint the layout rectangle of every widget. Name: `layout`.
+    LayoutRects,
+    /// Paint the id of the hovered widget. Name: `ids`.
+    WidgetIds,
+    /// Tint every repainted region with a color that changes every frame. Name: `flash`.
+    ///
+    /// Regions which are repainted more often than they should, eg the whole window
+    /// when a single button changes, stand out as flickering colors.
+    PaintFlashing,
+    /// Run animations ten times slower. Name: `slow`.
+    SlowAnimations,
+    /// Keep the last frames painted by each window, to step back through them with
+    /// [`SCRUB_FRAME`](crate::command::SCRUB_FRAME). Name: `history`.
+    FrameHistory,
+    /// Estimate the memory retained by each widget and its descendants, and report
+    /// it in the widget tree sent to the [remote inspector](crate::remote_debug).
+    /// Name: `memory`.
+    ///
+    /// See [`Widget::retained_memory`](crate::Widget::retained_memory).
+    MemoryAccounting,
+}
+
+impl DebugSwitch {
+    /// All debug switches.
+    pub const ALL: &'static [DebugSwitch] = &[
+        DebugSwitch::LayoutRects,
+        DebugSwitch::WidgetIds,
+        DebugSwitch::PaintFlashing,
+        DebugSwitch::SlowAnimations,
+        DebugSwitch::FrameHistory,
+        DebugSwitch::MemoryAccounting,
+    ];
+
+    /// The name of the switch, as written in `MASONRY_DEBUG`.
+    pub fn name(self) -> &'static str {
+        match self {
+            DebugSwitch::LayoutRects => "layout",
+            DebugSwitch::WidgetIds => "ids",
+            DebugSwitch::PaintFlashing => "flash",
+            DebugSwitch::SlowAnimations => "slow",
+            DebugSwitch::FrameHistory => "history",
+            DebugSwitch::MemoryAccounting => "memory",
+        }
+    }
+
+    /// Find a switch by its name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|switch| switch.name() == name)
+    }
+
+    /// Whether the switch is enabled in `env`.
+    pub fn is_enabled(self, env: &Env) -> bool {
+        match self {
+            DebugSwitch::LayoutRects => env.get(Env::DEBUG_PAINT),
+            DebugSwitch::WidgetIds => env.get(Env::DEBUG_WIDGET_ID),
+            DebugSwitch::PaintFlashing => env.get(Env::DEBUG_PAINT_FLASHING),
+            DebugSwitch::SlowAnimations => env.get(Env::DEBUG_ANIMATION_SPEED) != 1.0,
+            DebugSwitch::FrameHistory => env.get(Env::DEBUG_FRAME_HISTORY) != 0,
+            DebugSwitch::MemoryAccounting => env.get(Env::DEBUG_MEMORY),
+        }
+    }
+
+    /// Return a copy of `env` with the switch toggled.
+    pub(crate) fn toggle(self, env: &Env) -> Env {
+        let mut env = env.clone();
+        let enable = !self.is_enabled(&env);
+        match self {
+            DebugSwitch::LayoutRects => env.set(Env::DEBUG_PAINT, enable),
+            DebugSwitch::WidgetIds => env.set(Env::DEBUG_WIDGET_ID, enable),
+            DebugSwitch::PaintFlashing => env.set(Env::DEBUG_PAINT_FLASHING, enable),
+            DebugSwitch::SlowAnimations => {
+                let speed = if enable { SLOW_ANIMATION_SPEED } else { 1.0 };
+                env.set(Env::DEBUG_ANIMATION_SPEED, speed);
+            }
+            DebugSwitch::FrameHistory => {
+                let length = if enable { FRAME_HISTORY_LENGTH } else { 0 };
+                env.set(Env::DEBUG_FRAME_HISTORY, length);
+            }
+            DebugSwitch::MemoryAccounting => env.set(Env::DEBUG_MEMORY, enable),
+        }
+        info!("Debug switch '{}' set to {}", self.name(), enable);
+        env
+    }
+}
+
+/// Enable the switches listed in `MASONRY_DEBUG`, and start reading switches
+/// from stdin if requested.
+pub(crate) fn init_from_env_var(env: Env, sink: ExtEventSink) -> Env {
+    let Ok(names) = std::env::var(ENV_VAR) else {
+        return env;
+    };
+
+    let mut env = env;
+    let mut read_stdin = false;
+    for name in names
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+    {
+        if name == "stdin" {
+            read_stdin = true;
+            continue;
+        }
+        match DebugSwitch::from_name(name) {
+            Some(switch) => env = switch.toggle(&env),
+            None => warn!("{}: unknown debug switch '{}'", ENV_VAR, name),
+        }
+    }
+
+    if read_stdin {
+        let spawn_result = std::thread::Builder::new()
+            .name("masonry-debug-switches".into())
+            .spawn(move || {
+                for line in std::io::stdin().lock().lines() {
+                    let Ok(line) = line else {
+                        return;
+                    };
+                    let name = line.trim();
+                    let Some(switch) = DebugSwitch::from_name(name) else {
+                        warn!("unknown debug switch '{}'", name);
+                        continue;
+                    };
+                    if sink
+                        .submit_command(command::TOGGLE_DEBUG_SWITCH, switch, Target::Global)
+                        .is_err()
+                    {
+                        return;
+                    }
+                }
+            });
+        if let Err(err) = spawn_result {
+            warn!("Can't start reading debug switches from stdin: {}", err);
+        }
+    }
+
+    env
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::testing::{ModularWidget, TestHarness};
+    use crate::widget::SizedBox;
+    use crate::{Event, Selector, Size};
+
+    #[test]
+    fn switch_names() {
+        for &switch in DebugSwitch::ALL {
+            assert_eq!(DebugSwitch::from_name(switch.name()), Some(switch));
+        }
+        assert_eq!(DebugSwitch::from_name("stdin"), None);
+    }
+
+    #[test]
+    fn toggle_from_widget() {
+        const TOGGLE_IDS: Selector = Selector::new("masonry-test.toggle-ids");
+
+        let ids_enabled = Rc::new(Cell::new(false));
+        let widget = ModularWidget::new(ids_enabled.clone())
+            .event_fn(|_, ctx, event, _| {
+                if let Event::Command(cmd) = event {
+                    if cmd.is(TOGGLE_IDS) {
+                        ctx.submit_command(
+                            command::TOGGLE_DEBUG_SWITCH.with(DebugSwitch::WidgetIds),
+                        );
+                    }
+                }
+            })
+            .layout_fn(|ids_enabled, _, _, env| {
+                ids_enabled.set(env.get(Env::DEBUG_WIDGET_ID));
+                Size::ZERO
+            });
+        let mut harness = TestHarness::create(widget);
+        assert!(!ids_enabled.get());
+
+        harness.submit_command(TOGGLE_IDS);
+        assert!(ids_enabled.get());
+
+        harness.submit_command(TOGGLE_IDS);
+        assert!(!ids_enabled.get());
+    }
+
+    #[test]
+    fn paint_flashing_tints_repainted_regions() {
+        let mut harness = TestHarness::create(SizedBox::empty());
+        let plain = harness.render();
+
+        harness.set_env(DebugSwitch::PaintFlashing.toggle(&Env::with_theme()));
+        let flashed = harness.render();
+        assert_ne!(plain[..4], flashed[..4]);
+    }
+}
@@ -12,7 +12,7 @@ use std::sync::Arc;
 use crate::{Data, Rect};
 use serde::{Deserialize, Serialize};
 
-pub type MyWidgetId = u32;
+pub type MyWidgetId = u64;
 
 #[derive(Clone, Copy, Default, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
 pub struct LogId(pub i32);
@@ -8,8 +8,9 @@
 
 use std::any::{self, Any};
 use std::borrow::Borrow;
+use std::cell::RefCell;
 use std::collections::hash_map::Entry;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::{Debug, Formatter};
 use std::marker::PhantomData;
 use std::ops::Deref;
@@ -49,6 +50,42 @@ struct EnvImpl {
     map: HashMap<ArcStr, Value>,
 }
 
+thread_local! {
+    /// The keys read by each widget method being run, innermost last.
+    static READ_KEYS: RefCell<Vec<HashSet<ArcStr>>> = RefCell::new(Vec::new());
+}
+
+/// Run `f`, and return the keys it read from any [`Env`] with its result.
+///
+/// When calls are nested, a key is only returned by the innermost call which read
+/// it. This is how widget pods find the keys read by their own widget, and not by
+/// its descendants.
+pub(crate) fn record_reads<R>(f: impl FnOnce() -> R) -> (R, HashSet<ArcStr>) {
+    READ_KEYS.with(|stack| stack.borrow_mut().push(HashSet::new()));
+    let result = f();
+    let keys = READ_KEYS
+        .with(|stack| stack.borrow_mut().pop())
+        .unwrap_or_default();
+    (result, keys)
+}
+
+/// Record that the current widget depends on `key`, as if it had read it from an [`Env`].
+///
+/// This is for values derived from the `Env` outside of widget code.
+pub(crate) fn record_key_read<T>(key: &Key<T>) {
+    record_read(key.key);
+}
+
+fn record_read(key: &str) {
+    READ_KEYS.with(|stack| {
+        if let Some(keys) = stack.borrow_mut().last_mut() {
+            if !keys.contains(key) {
+                keys.insert(key.into());
+            }
+        }
+    });
+}
+
 /// A typed [`Env`] key.
 ///
 /// This lets you retrieve values of a given type. The parameter
@@ -217,6 +254,29 @@ impl Env {
     pub(crate) const DEBUG_WIDGET_ID: Key<bool> =
         Key::new("org.linebender.masonry.built-in.debug-widget-id");
 
+    /// State for whether or not to tint repainted regions with a different color
+    /// every frame. Set by
+    /// [`DebugSwitch::PaintFlashing`](crate::debug_switches::DebugSwitch::PaintFlashing).
+    pub(crate) const DEBUG_PAINT_FLASHING: Key<bool> =
+        Key::new("org.linebender.masonry.built-in.debug-paint-flashing");
+
+    /// A factor applied to the time elapsed between animation frames.
+    ///
+    /// Values below 1.0 slow animations down. Set by
+    /// [`DebugSwitch::SlowAnimations`](crate::debug_switches::DebugSwitch::SlowAnimations).
+    pub(crate) const DEBUG_ANIMATION_SPEED: Key<f64> =
+        Key::new("org.linebender.masonry.built-in.debug-animation-speed");
+
+    /// The number of painted frames each window keeps, zero to keep none. Set by
+    /// [`DebugSwitch::FrameHistory`](crate::debug_switches::DebugSwitch::FrameHistory).
+    pub(crate) const DEBUG_FRAME_HISTORY: Key<u64> =
+        Key::new("org.linebender.masonry.built-in.debug-frame-history");
+
+    /// Whether to estimate the memory retained by each widget subtree. Set by
+    /// [`DebugSwitch::MemoryAccounting`](crate::debug_switches::DebugSwitch::MemoryAccounting).
+    pub(crate) const DEBUG_MEMORY: Key<bool> =
+        Key::new("org.linebender.masonry.built-in.debug-memory");
+
     /// A key used to tell widgets to print additional debug information.
     ///
     /// This does nothing by default; however you can check this key while
@@ -240,6 +300,53 @@ impl Env {
     /// [`WidgetExt::debug_widget`]: trait.WidgetExt.html#method.debug_widget
     pub const DEBUG_WIDGET: Key<bool> = Key::new("org.linebender.masonry.built-in.debug-widget");
 
+    /// Whether the app runs in low-power mode.
+    ///
+    /// In low-power mode, animation frames are capped to a lower rate, and widgets
+    /// skip non-essential animations (see [`EventCtx::reduced_motion`]). Windows can
+    /// override this with [`SET_LOW_POWER_MODE`].
+    ///
+    /// Set by [`AppLauncher::with_low_power_mode`].
+    ///
+    /// [`EventCtx::reduced_motion`]: crate::EventCtx::reduced_motion
+    /// [`SET_LOW_POWER_MODE`]: crate::command::SET_LOW_POWER_MODE
+    /// [`AppLauncher::with_low_power_mode`]: crate::AppLauncher::with_low_power_mode
+    pub const LOW_POWER_MODE: Key<bool> =
+        Key::new("org.linebender.masonry.built-in.low-power-mode");
+
+    /// Whether the user prefers apps to reduce motion.
+    ///
+    /// This follows the platform's "reduce motion" setting on Windows, macOS and GTK,
+    /// see
+    /// [`AppRoot::set_prefers_reduced_motion`](crate::AppRoot::set_prefers_reduced_motion).
+    /// Elsewhere, it's `false` unless set by that method or
+    /// [`SET_ENV`](crate::command::SET_ENV).
+    pub const PREFERS_REDUCED_MOTION: Key<bool> =
+        Key::new("org.linebender.masonry.built-in.prefers-reduced-motion");
+
+    /// Whether the app uses high-contrast colors.
+    ///
+    /// While it's set, the colors of the [`theme`](crate::theme) are replaced by a
+    /// high-contrast palette, so widgets painting with them need no change. Widgets
+    /// with colors of their own should read this key, and use the theme colors
+    /// instead.
+    ///
+    /// This follows the platform's high-contrast setting on Windows, macOS and GTK,
+    /// see [`AppRoot::set_high_contrast`](crate::AppRoot::set_high_contrast).
+    /// Elsewhere, it's `false` unless set by that method or
+    /// [`SET_ENV`](crate::command::SET_ENV).
+    pub const HIGH_CONTRAST: Key<bool> = Key::new("org.linebender.masonry.built-in.high-contrast");
+
+    /// The zoom factor the user chose for a window, independently of the display's
+    /// DPI. The text sizes and metrics of the [`theme`](crate::theme) are already
+    /// multiplied by it.
+    ///
+    /// This is set by each window, and changed with
+    /// [`SET_UI_SCALE`](crate::command::SET_UI_SCALE), or with Ctrl+= and Ctrl+-
+    /// (Cmd on macOS) unless
+    /// [`ZOOM_SHORTCUTS_ENABLED`](crate::theme::ZOOM_SHORTCUTS_ENABLED) is `false`.
+    pub const UI_SCALE: Key<f64> = Key::new("org.linebender.masonry.built-in.ui-scale");
+
     /// Gets a value from the environment, expecting it to be present.
     ///
     /// Note that the return value is a reference for "expensive" types such
@@ -264,6 +371,7 @@ impl Env {
     ///
     /// Panics if the value for the key is found, but has the wrong type.
     pub fn try_get<V: ValueType>(&self, key: impl Borrow<Key<V>>) -> Result<V, MissingKeyError> {
+        record_read(key.borrow().key);
         self.0
             .map
             .get(key.borrow().key)
@@ -300,6 +408,7 @@ impl Env {
     ///
     /// [`Value`]: enum.Value.html
     pub fn try_get_untyped<V>(&self, key: impl Borrow<Key<V>>) -> Result<&Value, MissingKeyError> {
+        record_read(key.borrow().key);
         self.0.map.get(key.borrow().key).ok_or(MissingKeyError {
             key: key.borrow().key.into(),
         })
@@ -313,6 +422,36 @@ impl Env {
         self.0.map.iter()
     }
 
+    /// The keys whose values differ between `self` and `other`, including the keys
+    /// only one of them has.
+    pub fn changed_keys(&self, other: &Env) -> HashSet<ArcStr> {
+        if Arc::ptr_eq(&self.0, &other.0) {
+            return HashSet::new();
+        }
+        let mut changed: HashSet<ArcStr> = self
+            .0
+            .map
+            .iter()
+            .filter(|(key, value)| {
+                other
+                    .0
+                    .map
+                    .get(*key)
+                    .map_or(true, |other_value| !value.same(other_value))
+            })
+            .map(|(key, _)| key.clone())
+            .collect();
+        changed.extend(
+            other
+                .0
+                .map
+                .keys()
+                .filter(|key| !self.0.map.contains_key(*key))
+                .cloned(),
+        );
+        changed
+    }
+
     /// Adds a key/value, acting like a builder.
     pub fn adding<V: ValueType>(mut self, key: Key<V>, value: impl Into<V>) -> Env {
         let env = Arc::make_mut(&mut self.0);
@@ -357,6 +496,17 @@ impl Env {
         Ok(())
     }
 
+    /// Gets a value from the environment by its raw key, eg when loading a theme file.
+    pub(crate) fn get_raw_by_name(&self, key: &str) -> Option<&Value> {
+        self.0.map.get(key)
+    }
+
+    /// Sets a value by its raw key, without checking its type.
+    pub(crate) fn set_raw_by_name(&mut self, key: &str, raw: Value) {
+        let env = Arc::make_mut(&mut self.0);
+        env.map.insert(ArcStr::from(key), raw);
+    }
+
     /// Given an id, returns one of 18 distinct colors
     #[doc(hidden)]
     pub fn get_debug_color(&self, id: u64) -> Color {
@@ -520,7 +670,15 @@ impl Env {
         let env = Env::empty()
             .adding(Env::DEBUG_PAINT, false)
             .adding(Env::DEBUG_WIDGET_ID, false)
-            .adding(Env::DEBUG_WIDGET, false);
+            .adding(Env::DEBUG_PAINT_FLASHING, false)
+            .adding(Env::DEBUG_ANIMATION_SPEED, 1.0)
+            .adding(Env::DEBUG_FRAME_HISTORY, 0_u64)
+            .adding(Env::DEBUG_MEMORY, false)
+            .adding(Env::DEBUG_WIDGET, false)
+            .adding(Env::LOW_POWER_MODE, false)
+            .adding(Env::PREFERS_REDUCED_MOTION, false)
+            .adding(Env::HIGH_CONTRAST, false)
+            .adding(Env::UI_SCALE, 1.0);
 
         crate::theme::add_to_env(env)
     }
@@ -691,4 +849,27 @@ mod tests {
 
         assert_send_sync::<Key<()>>();
     }
+
+    #[test]
+    fn changed_keys_and_nested_reads() {
+        const A: Key<f64> = Key::new("org.linebender.test.a");
+        const B: Key<f64> = Key::new("org.linebender.test.b");
+        const C: Key<bool> = Key::new("org.linebender.test.c");
+        let env = Env::empty().adding(A, 1.0).adding(B, 2.0);
+        let new_env = env.clone().adding(B, 3.0).adding(C, true);
+
+        let changed = env.changed_keys(&new_env);
+        assert_eq!(changed.len(), 2);
+        assert!(changed.contains(B.raw()) && changed.contains(C.raw()));
+        assert!(env.changed_keys(&env.clone()).is_empty());
+
+        let ((_, inner_keys), outer_keys) = record_reads(|| {
+            env.get(A);
+            record_reads(|| env.get(B))
+        });
+        assert_eq!(outer_keys.len(), 1);
+        assert!(outer_keys.contains(A.raw()));
+        assert_eq!(inner_keys.len(), 1);
+        assert!(inner_keys.contains(B.raw()));
+    }
 }
@@ -4,13 +4,22 @@
 
 //! Events.
 
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
 use druid_shell::{Clipboard, KeyEvent, TimerToken};
+use instant::{Duration, Instant};
 
-use crate::kurbo::{Rect, Size};
+use crate::gamepad::GamepadEvent;
+use crate::kurbo::{Insets, Point, Rect, Size};
 use crate::mouse::MouseEvent;
+use crate::pen::PenEvent;
+use crate::piet::ImageBuf;
+use crate::print::PrintJob;
 // TODO - See issue #14
 use crate::promise::PromiseResult;
-use crate::{Command, Notification, WidgetId};
+use crate::{ArcStr, Command, Notification, Value, WidgetId};
 
 /// An event, propagated downwards during event flow.
 ///
@@ -46,10 +55,15 @@ pub enum Event {
 
     /// Sent to all widgets in a given window when the system requests to close the window.
     ///
-    /// If the event is handled (with [`EventCtx::set_handled`](crate::EventCtx::set_handled)), the window will not be closed.
-    /// All widgets are given an opportunity to handle this event; your widget should not assume
-    /// that the window *will* close just because this event is received; for instance, you should
-    /// avoid destructive side effects such as cleaning up resources.
+    /// If a widget calls [`EventCtx::prevent_close`](crate::EventCtx::prevent_close), or
+    /// handles the event (with [`EventCtx::set_handled`](crate::EventCtx::set_handled)), the
+    /// window will not be closed. All widgets are given an opportunity to handle this event;
+    /// your widget should not assume that the window *will* close just because this event is
+    /// received; for instance, you should avoid destructive side effects such as cleaning up
+    /// resources.
+    ///
+    /// To close the window once the user confirmed it, submit
+    /// [`FORCE_CLOSE_WINDOW`](crate::command::FORCE_CLOSE_WINDOW).
     WindowCloseRequested,
 
     /// Sent to all widgets in a given window when the system is going to close that window.
@@ -58,6 +72,30 @@ pub enum Event {
     /// do any other cleanup.
     WindowDisconnected,
 
+    /// Sent to all widgets when the app is moved to the background, eg on Android
+    /// and iOS when the user switches to another app.
+    ///
+    /// The app may be killed without further notice while suspended; widgets should
+    /// stop animations and save any state they don't want to lose.
+    ///
+    /// Only sent when a platform integration calls
+    /// [`AppRoot::app_suspended`](crate::AppRoot::app_suspended); druid-shell doesn't
+    /// report it, so apps launched with [`AppLauncher`](crate::AppLauncher) never receive it.
+    Suspend,
+
+    /// Sent to all widgets when the app comes back to the foreground after a [`Suspend`](Event::Suspend).
+    Resume,
+
+    /// Sent to all widgets when the platform is running low on memory.
+    ///
+    /// Widgets should drop any cache they can rebuild later. Masonry releases its own
+    /// paint caches and purges the [`ImageCache`](crate::ImageCache) automatically.
+    ///
+    /// Only sent when a platform integration calls
+    /// [`AppRoot::memory_warning`](crate::AppRoot::memory_warning); druid-shell doesn't
+    /// report memory pressure.
+    MemoryWarning,
+
     /// Called on the root widget when the window size changes.
     ///
     /// **Note:** it's not obvious this should be propagated to user
@@ -90,7 +128,49 @@ pub enum Event {
     /// Called when the mouse wheel or trackpad is scrolled.
     Wheel(MouseEvent),
 
+    /// Sent to pointer filters before a mouse event reaches their descendants.
+    ///
+    /// Widgets that called [`LifeCycleCtx::register_pointer_filter`] receive this
+    /// event, wrapping a [`MouseDown`](Event::MouseDown), [`MouseUp`](Event::MouseUp),
+    /// [`MouseMove`](Event::MouseMove) or [`Wheel`](Event::Wheel) event in local
+    /// coordinates, right before the mouse event itself. Filters are called from
+    /// the outermost ancestor inwards; if a filter marks the event as handled, the
+    /// mouse event isn't sent to the widgets below it.
+    ///
+    /// A filter can take over a gesture from its descendants with
+    /// [`EventCtx::capture_pointer`], eg when a scroll area detects a drag.
+    ///
+    /// [`LifeCycleCtx::register_pointer_filter`]: crate::LifeCycleCtx::register_pointer_filter
+    /// [`EventCtx::capture_pointer`]: crate::EventCtx::capture_pointer
+    MouseFilter(Box<Event>),
+
+    /// Sent when an ancestor took over the pointer with [`EventCtx::capture_pointer`].
+    ///
+    /// This is sent to the active widgets below the ancestor, which are no longer
+    /// active and won't see the rest of the gesture. They should reset any
+    /// in-progress interaction, eg a button should stop looking pressed.
+    ///
+    /// [`EventCtx::capture_pointer`]: crate::EventCtx::capture_pointer
+    PointerCaptureLost,
+
+    /// Called when a pen or stylus touches the surface, moves, or leaves it.
+    ///
+    /// Like mouse events, pen events are sent to the active widget, and to the
+    /// widgets under the pen. Positions are in the receiver's coordinate space.
+    ///
+    /// druid-shell doesn't report pen input: on every platform, the pen sends
+    /// mouse events instead. This event is only sent by platform integrations
+    /// which read the pen themselves and pass it to
+    /// [`AppRoot::handle_event`](crate::AppRoot::handle_event), and by the
+    /// [`TestHarness`](crate::testing::TestHarness).
+    Pen(PenEvent),
+
     /// Called when a key is pressed.
+    ///
+    /// [`KeyEvent::key`] is the logical key, which depends on the keyboard layout;
+    /// [`KeyEvent::code`] is the physical key (the scan code), which doesn't.
+    /// Events sent while a key is held have [`KeyEvent::repeat`] set; see
+    /// [`KeyRepeat`] to change how keys repeat for a widget.
     KeyDown(KeyEvent),
 
     /// Called when a key is released.
@@ -99,6 +179,35 @@ pub enum Event {
     /// a corresponding `KeyUp` is sent.
     KeyUp(KeyEvent),
 
+    /// Sent to key filters before a key event reaches the focused widget.
+    ///
+    /// Widgets that called [`LifeCycleCtx::register_key_filter`] receive this
+    /// event for every [`KeyDown`](Event::KeyDown) and [`KeyUp`](Event::KeyUp)
+    /// sent to one of their focused descendants, right before the key event itself.
+    /// Filters are called from the outermost ancestor inwards; if a filter marks
+    /// the event as handled, the key event isn't sent to the widgets below it.
+    ///
+    /// Use [`KeyEvent::state`] to tell key presses from key releases.
+    ///
+    /// [`LifeCycleCtx::register_key_filter`]: crate::LifeCycleCtx::register_key_filter
+    KeyFilter(KeyEvent),
+
+    /// Toggles the visibility of the text caret of the focused widget.
+    ///
+    /// This is only sent to widgets that called
+    /// [`LifeCycleCtx::register_for_caret_blink`], while they're focused. The
+    /// value is whether the caret should currently be drawn; it is `false`
+    /// while the window doesn't have focus.
+    ///
+    /// [`LifeCycleCtx::register_for_caret_blink`]: crate::LifeCycleCtx::register_for_caret_blink
+    CaretBlink(bool),
+
+    /// Called when a gamepad button is pressed or released, or a stick moves.
+    ///
+    /// Like key events, this is sent to the focused widget. Events no widget
+    /// handles are used for navigation; see [`gamepad`](crate::gamepad).
+    Gamepad(GamepadEvent),
+
     /// Called when a paste command is received.
     Paste(Clipboard),
 
@@ -139,7 +248,10 @@ pub enum Event {
     /// Note that timer events from other widgets may be delivered as well. Use
     /// the token returned from the `request_timer()` call to filter events more
     /// precisely.
-    Timer(TimerToken),
+    ///
+    /// Timers can fire late when the app is busy; the [`TimerEvent`] says by
+    /// how much, so that animations driven by timers can compensate.
+    Timer(TimerEvent),
 
     /// Called when a promise returns.
     ///
@@ -191,13 +303,20 @@ pub enum InternalEvent {
     TargetedCommand(Command),
 
     /// Used for routing timer events.
-    RouteTimer(TimerToken, WidgetId),
+    RouteTimer(TimerEvent, WidgetId),
 
     /// Used for routing promise results.
     RoutePromiseResult(PromiseResult, WidgetId),
 
     /// Route an IME change event.
     RouteImeStateChange(WidgetId),
+
+    /// Used to scroll a widget into view, eg when it gains focus.
+    ///
+    /// The target widget requests a pan to itself, as with
+    /// [`EventCtx::request_pan_to_this`](crate::EventCtx::request_pan_to_this),
+    /// or to the given rect in its own coordinates.
+    RoutePanToWidget(WidgetId, Option<Rect>),
 }
 
 /// Application life cycle events.
@@ -254,6 +373,34 @@ pub enum LifeCycle {
     /// [`EventCtx::request_pan_to_this`](crate::EventCtx::request_pan_to_this).
     RequestPanToChild(Rect),
 
+    /// Sent when the app's [`Env`](crate::Env) is replaced, eg with
+    /// [`SET_ENV`](crate::command::SET_ENV) or when an env file is reloaded.
+    ///
+    /// Only the widgets which read one of the keys whose value changed, in any of
+    /// their methods, receive this event; they are laid out and painted again
+    /// afterwards. Widgets only need to handle this event if they cache values
+    /// derived from the `Env` outside of `layout` and `paint`.
+    ///
+    /// Unlike other lifecycle events, this event isn't passed down by containers:
+    /// a container forwarding it to its children has no effect.
+    EnvChanged,
+
+    /// Sent to every widget when the window's safe area changes, with the new value of
+    /// [`safe_area`](crate::LifeCycleCtx::safe_area).
+    ///
+    /// Widgets which read the safe area in `layout` should request a layout pass.
+    SafeAreaChanged(Insets),
+
+    /// Called when the widget starts or stops being visible, with the new value of
+    /// [`is_visible`](crate::LifeCycleCtx::is_visible).
+    ///
+    /// Widgets doing work only the user can notice, eg playing a video or an
+    /// animation, can pause it while they're invisible.
+    ///
+    /// Like [`EnvChanged`](Self::EnvChanged), this event is sent to each widget whose
+    /// visibility changed; a container forwarding it to its children has no effect.
+    VisibilityChanged(bool),
+
     /// Internal Masonry lifecycle event.
     ///
     /// This should always be passed down to descendant [`WidgetPod`]s.
@@ -262,6 +409,51 @@ pub enum LifeCycle {
     Internal(InternalLifeCycle),
 }
 
+/// How held keys repeat for a widget; see [`LifeCycleCtx::set_key_repeat`].
+///
+/// [`LifeCycleCtx::set_key_repeat`]: crate::LifeCycleCtx::set_key_repeat
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum KeyRepeat {
+    /// Repeated key presses are sent as the platform reports them, with the
+    /// delay and rate set by the user.
+    #[default]
+    Platform,
+    /// Held keys don't repeat; the widget only gets one [`Event::KeyDown`] per
+    /// key press, eg for games or musical keyboards.
+    Disabled,
+    /// Held keys repeat with the given timing, instead of the platform's.
+    Custom {
+        /// The time between the key press and the first repeat.
+        delay: Duration,
+        /// The time between repeats.
+        interval: Duration,
+    },
+}
+
+/// A timer firing, sent with [`Event::Timer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimerEvent {
+    /// The token returned by `request_timer()`.
+    pub token: TimerToken,
+    /// When the timer was scheduled to fire.
+    pub deadline: Instant,
+    /// When the timer actually fired.
+    pub fired_at: Instant,
+}
+
+impl TimerEvent {
+    /// How late the timer fired compared to its deadline.
+    pub fn lateness(&self) -> Duration {
+        self.fired_at.saturating_duration_since(self.deadline)
+    }
+}
+
+impl PartialEq<TimerToken> for TimerEvent {
+    fn eq(&self, token: &TimerToken) -> bool {
+        self.token == *token
+    }
+}
+
 /// Internal lifecycle events used by Masonry inside [`WidgetPod`].
 ///
 /// These events are translated into regular [`LifeCycle`] events
@@ -287,6 +479,58 @@ pub enum InternalLifeCycle {
 
     /// The parents widget origin in window coordinate space has changed.
     ParentWindowOrigin,
+
+    /// Used to route mutations scheduled with
+    /// [`EventCtx::mutate_later`](crate::EventCtx::mutate_later) to their target widgets.
+    RouteMutations,
+
+    /// Used to send the states collected in a [`UiState`] back to their widgets.
+    ///
+    /// [`UiState`]: crate::ui_state::UiState
+    RouteRestoreState(Rc<HashMap<WidgetId, Value>>),
+
+    /// Used to send [`LifeCycle::EnvChanged`] to the widgets which read one of
+    /// these [`Env`](crate::Env) keys.
+    RouteEnvChanged(Rc<HashSet<ArcStr>>),
+
+    /// Used to lay out again the widgets which called
+    /// [`request_refine_layout`](crate::EventCtx::request_refine_layout), with the
+    /// mouse position in window coordinates.
+    RouteRefineLayout(Option<Point>),
+
+    /// Used to paint a widget and its children into a bitmap, eg for
+    /// [`CAPTURE_WIDGET`](crate::command::CAPTURE_WIDGET).
+    RouteCapture(Rc<WidgetCapture>),
+
+    /// Used to lay out and paint a widget and its children onto pages, eg for
+    /// [`PRINT_PAGES`](crate::command::PRINT_PAGES).
+    RoutePrint(Rc<PrintJob>),
+}
+
+/// A widget to paint into a bitmap, and the resulting bitmap.
+pub struct WidgetCapture {
+    pub(crate) target: WidgetId,
+    pub(crate) scale: f64,
+    pub(crate) image: RefCell<Option<ImageBuf>>,
+}
+
+impl WidgetCapture {
+    pub(crate) fn new(target: WidgetId, scale: f64) -> Self {
+        WidgetCapture {
+            target,
+            scale,
+            image: RefCell::new(None),
+        }
+    }
+}
+
+impl std::fmt::Debug for WidgetCapture {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WidgetCapture")
+            .field("target", &self.target)
+            .field("scale", &self.scale)
+            .finish_non_exhaustive()
+    }
 }
 
 /// Event indicating status changes within the widget hierarchy.
@@ -339,6 +583,9 @@ impl Event {
             | Event::WindowCloseRequested
             | Event::WindowDisconnected
             | Event::WindowSize(_)
+            | Event::Suspend
+            | Event::Resume
+            | Event::MemoryWarning
             | Event::Timer(_)
             | Event::AnimFrame(_)
             | Event::Command(_)
@@ -349,14 +596,40 @@ impl Event {
             | Event::MouseUp(_)
             | Event::MouseMove(_)
             | Event::Wheel(_)
+            | Event::MouseFilter(_)
+            | Event::PointerCaptureLost
+            | Event::Pen(_)
             | Event::KeyDown(_)
             | Event::KeyUp(_)
+            | Event::KeyFilter(_)
+            | Event::CaretBlink(_)
+            | Event::Gamepad(_)
             | Event::Paste(_)
             | Event::ImeStateChange
             | Event::Zoom(_) => false,
         }
     }
 
+    /// Whether the event comes directly from the user, eg a click or a key press.
+    ///
+    /// These are the events which aren't sent to busy widgets, see
+    /// [`EventCtx::begin_busy`](crate::EventCtx::begin_busy).
+    pub fn is_user_input(&self) -> bool {
+        matches!(
+            self,
+            Event::MouseDown(_)
+                | Event::MouseUp(_)
+                | Event::MouseMove(_)
+                | Event::Wheel(_)
+                | Event::Pen(_)
+                | Event::KeyDown(_)
+                | Event::KeyUp(_)
+                | Event::Gamepad(_)
+                | Event::Paste(_)
+                | Event::Zoom(_)
+        )
+    }
+
     /// Short name, for debug logging.
     ///
     /// Essentially returns the enum variant name.
@@ -368,11 +641,15 @@ impl Event {
                 InternalEvent::RouteTimer(_, _) => "RouteTimer",
                 InternalEvent::RoutePromiseResult(_, _) => "RoutePromiseResult",
                 InternalEvent::RouteImeStateChange(_) => "RouteImeStateChange",
+                InternalEvent::RoutePanToWidget(..) => "RoutePanToWidget",
             },
             Event::WindowConnected => "WindowConnected",
             Event::WindowCloseRequested => "WindowCloseRequested",
             Event::WindowDisconnected => "WindowDisconnected",
             Event::WindowSize(_) => "WindowSize",
+            Event::Suspend => "Suspend",
+            Event::Resume => "Resume",
+            Event::MemoryWarning => "MemoryWarning",
             Event::Timer(_) => "Timer",
             Event::AnimFrame(_) => "AnimFrame",
             Event::Command(_) => "Command",
@@ -382,8 +659,14 @@ impl Event {
             Event::MouseUp(_) => "MouseUp",
             Event::MouseMove(_) => "MouseMove",
             Event::Wheel(_) => "Wheel",
+            Event::MouseFilter(_) => "MouseFilter",
+            Event::PointerCaptureLost => "PointerCaptureLost",
+            Event::Pen(_) => "Pen",
             Event::KeyDown(_) => "KeyDown",
             Event::KeyUp(_) => "KeyUp",
+            Event::KeyFilter(_) => "KeyFilter",
+            Event::CaretBlink(_) => "CaretBlink",
+            Event::Gamepad(_) => "Gamepad",
             Event::Paste(_) => "Paste",
             Event::ImeStateChange => "ImeStateChange",
             Event::Zoom(_) => "Zoom",
@@ -408,6 +691,9 @@ impl LifeCycle {
             LifeCycle::DisabledChanged(_) => true,
             LifeCycle::BuildFocusChain => false,
             LifeCycle::RequestPanToChild(_) => false,
+            LifeCycle::EnvChanged => true,
+            LifeCycle::SafeAreaChanged(_) => true,
+            LifeCycle::VisibilityChanged(_) => true,
         }
     }
 
@@ -421,11 +707,20 @@ impl LifeCycle {
                 InternalLifeCycle::RouteFocusChanged { .. } => "RouteFocusChanged",
                 InternalLifeCycle::RouteDisabledChanged => "RouteDisabledChanged",
                 InternalLifeCycle::ParentWindowOrigin => "ParentWindowOrigin",
+                InternalLifeCycle::RouteMutations => "RouteMutations",
+                InternalLifeCycle::RouteRestoreState(_) => "RouteRestoreState",
+                InternalLifeCycle::RouteEnvChanged(_) => "RouteEnvChanged",
+                InternalLifeCycle::RouteRefineLayout(_) => "RouteRefineLayout",
+                InternalLifeCycle::RouteCapture(_) => "RouteCapture",
+                InternalLifeCycle::RoutePrint(_) => "RoutePrint",
             },
             LifeCycle::WidgetAdded => "WidgetAdded",
             LifeCycle::DisabledChanged(_) => "DisabledChanged",
             LifeCycle::BuildFocusChain => "BuildFocusChain",
             LifeCycle::RequestPanToChild(_) => "RequestPanToChild",
+            LifeCycle::EnvChanged => "EnvChanged",
+            LifeCycle::SafeAreaChanged(_) => "SafeAreaChanged",
+            LifeCycle::VisibilityChanged(_) => "VisibilityChanged",
         }
     }
 }
@@ -443,8 +738,16 @@ impl InternalLifeCycle {
         match self {
             InternalLifeCycle::RouteWidgetAdded
             | InternalLifeCycle::RouteFocusChanged { .. }
-            | InternalLifeCycle::RouteDisabledChanged => true,
-            InternalLifeCycle::ParentWindowOrigin => false,
+            | InternalLifeCycle::RouteDisabledChanged
+            | InternalLifeCycle::RouteMutations
+            | InternalLifeCycle::RouteRestoreState(_)
+            | InternalLifeCycle::RouteEnvChanged(_) => true,
+            // Stashed widgets become invisible.
+            InternalLifeCycle::ParentWindowOrigin => true,
+            // Stashed widgets can't be laid out or painted.
+            InternalLifeCycle::RouteRefineLayout(_)
+            | InternalLifeCycle::RouteCapture(_)
+            | InternalLifeCycle::RoutePrint(_) => false,
         }
     }
 }
@@ -0,0 +1,154 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Timings and counters of the frames painted by a window.
+
+use std::time::{Duration, Instant};
+
+/// The time a frame can take at 60 frames per second.
+const FRAME_BUDGET: Duration = Duration::from_micros(16_667);
+
+/// How often [`FrameStats::fps`] is recomputed.
+const FPS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The time spent in each pass of a frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PassTimings {
+    /// Time spent sending [`Event::AnimFrame`](crate::Event::AnimFrame) to the widget tree.
+    pub anim_frame: Duration,
+    /// Time spent in the layout pass.
+    pub layout: Duration,
+    /// Time spent in the paint pass.
+    pub paint: Duration,
+}
+
+impl PassTimings {
+    /// The time spent in all passes.
+    pub fn total(&self) -> Duration {
+        self.anim_frame + self.layout + self.paint
+    }
+}
+
+/// Timings and counters of the frames painted by a window.
+///
+/// This can be read from any context with `ctx.frame_stats()`, from
+/// [`WindowRoot::frame_stats`](crate::WindowRoot::frame_stats), or displayed
+/// on top of the app with [`FrameStatsOverlay`](crate::widget::FrameStatsOverlay).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FrameStats {
+    /// Number of frames painted since the window was created.
+    pub frame_count: u64,
+    /// Number of frames which took longer than a 60Hz frame to produce.
+    pub dropped_frames: u64,
+    /// Frames painted per second, measured over the last second or so.
+    ///
+    /// Masonry only paints when something changed, so this is low when the app is idle.
+    pub fps: f64,
+    /// The time spent in each pass of the last frame.
+    pub last_frame: PassTimings,
+    /// The area repainted in the last frame, in display points.
+    pub damage_area: f64,
+    /// The area of the window in the last frame, in display points.
+    pub window_area: f64,
+    fps_interval_start: Option<Instant>,
+    fps_interval_frames: u32,
+}
+
+impl FrameStats {
+    /// Record a frame painted at `now`.
+    pub(crate) fn record_frame(
+        &mut self,
+        now: Instant,
+        timings: PassTimings,
+        damage_area: f64,
+        window_area: f64,
+    ) {
+        self.frame_count += 1;
+        if timings.total() > FRAME_BUDGET {
+            self.dropped_frames += 1;
+        }
+        self.last_frame = timings;
+        self.damage_area = damage_area;
+        self.window_area = window_area;
+
+        let Some(start) = self.fps_interval_start else {
+            self.fps_interval_start = Some(now);
+            return;
+        };
+        self.fps_interval_frames += 1;
+        let elapsed = now.saturating_duration_since(start);
+        if elapsed >= FPS_INTERVAL {
+            self.fps = self.fps_interval_frames as f64 / elapsed.as_secs_f64();
+            self.fps_interval_start = Some(now);
+            self.fps_interval_frames = 0;
+        }
+    }
+
+    /// The fraction of the window repainted in the last frame, between 0.0 and 1.0.
+    pub fn damage_fraction(&self) -> f64 {
+        if self.window_area == 0.0 {
+            return 0.0;
+        }
+        self.damage_area / self.window_area
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::testing::{ModularWidget, TestHarness};
+    use crate::{Rect, Size};
+
+    #[test]
+    fn fps_and_dropped_frames() {
+        let start = Instant::now();
+        let mut stats = FrameStats::default();
+        let fast = PassTimings {
+            paint: Duration::from_millis(1),
+            ..Default::default()
+        };
+        let slow = PassTimings {
+            layout: Duration::from_millis(30),
+            ..Default::default()
+        };
+
+        stats.record_frame(start, fast, 10.0, 100.0);
+        for i in 1..=20 {
+            stats.record_frame(start + Duration::from_millis(50 * i), fast, 10.0, 100.0);
+        }
+        stats.record_frame(start + Duration::from_millis(1100), slow, 100.0, 100.0);
+
+        assert_eq!(stats.frame_count, 22);
+        assert_eq!(stats.dropped_frames, 1);
+        assert_eq!(stats.fps, 20.0);
+        assert_eq!(stats.last_frame, slow);
+        assert_eq!(stats.damage_fraction(), 1.0);
+    }
+
+    #[test]
+    fn stats_readable_from_context() {
+        let frame_count = Rc::new(Cell::new(0));
+        let widget = ModularWidget::new(frame_count.clone()).paint_fn(|frame_count, ctx, _| {
+            frame_count.set(ctx.frame_stats().frame_count);
+        });
+
+        let mut harness = TestHarness::create_with_size(widget, Size::new(50.0, 50.0));
+        harness.render();
+        assert_eq!(frame_count.get(), 0);
+        assert_eq!(harness.window().frame_stats().frame_count, 1);
+        assert_eq!(harness.window().frame_stats().damage_area, 2500.0);
+
+        harness
+            .window_mut()
+            .invalid_mut()
+            .add_rect(Rect::new(0.0, 0.0, 10.0, 10.0));
+        harness.render();
+        assert_eq!(frame_count.get(), 1);
+        assert_eq!(harness.window().frame_stats().frame_count, 2);
+        assert_eq!(harness.window().frame_stats().damage_area, 100.0);
+    }
+}
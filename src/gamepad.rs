@@ -0,0 +1,404 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Gamepad input.
+//!
+//! Gamepad events are first sent to the focused widget as [`Event::Gamepad`].
+//! If no widget handles them, the window uses them to navigate:
+//!
+//! - The D-pad and the left stick move focus to the nearest focusable widget in
+//!   that direction. Holding them repeats the movement.
+//! - The south button (A on Xbox controllers) is sent as an Enter key press.
+//! - The east button (B on Xbox controllers) is sent as an Escape key press.
+//!
+//! With the `gamepad` feature, connected gamepads are polled in the background
+//! while the app runs.
+//!
+//! [`Event::Gamepad`]: crate::Event::Gamepad
+
+use instant::{Duration, Instant};
+
+use crate::Selector;
+
+/// Sent by the polling thread for each gamepad event.
+pub(crate) const GAMEPAD_EVENT: Selector<GamepadEvent> =
+    Selector::new("masonry-builtin.gamepad-event");
+
+/// How far the left stick must be pushed to move focus, from 0.0 to 1.0.
+const STICK_THRESHOLD: f64 = 0.5;
+
+/// How long a direction is held before focus movement repeats.
+pub(crate) const REPEAT_DELAY: Duration = Duration::from_millis(400);
+
+/// The time between repeated focus movements while a direction is held.
+pub(crate) const REPEAT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Identifies a connected gamepad.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct GamepadId(pub usize);
+
+/// An event from a gamepad.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GamepadEvent {
+    /// The gamepad the event comes from.
+    pub gamepad: GamepadId,
+    /// What happened.
+    pub kind: GamepadEventKind,
+}
+
+/// The different kinds of [`GamepadEvent`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GamepadEventKind {
+    /// The gamepad was plugged in.
+    Connected,
+    /// The gamepad was unplugged.
+    Disconnected,
+    /// A button was pressed.
+    ButtonPressed(GamepadButton),
+    /// A button was released.
+    ButtonReleased(GamepadButton),
+    /// A stick moved. The value is between -1.0 and 1.0; positive values are
+    /// right and up.
+    AxisChanged(GamepadAxis, f64),
+}
+
+/// A gamepad button, named after its position on the controller.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GamepadButton {
+    /// The bottom face button, eg A on Xbox controllers.
+    South,
+    /// The right face button, eg B on Xbox controllers.
+    East,
+    /// The top face button, eg Y on Xbox controllers.
+    North,
+    /// The left face button, eg X on Xbox controllers.
+    West,
+    /// The left shoulder button.
+    LeftShoulder,
+    /// The right shoulder button.
+    RightShoulder,
+    /// The left trigger.
+    LeftTrigger,
+    /// The right trigger.
+    RightTrigger,
+    /// The left menu button, eg Back or Select.
+    Select,
+    /// The right menu button, eg Start.
+    Start,
+    /// Pressing the left stick.
+    LeftStick,
+    /// Pressing the right stick.
+    RightStick,
+    /// The D-pad up button.
+    DPadUp,
+    /// The D-pad down button.
+    DPadDown,
+    /// The D-pad left button.
+    DPadLeft,
+    /// The D-pad right button.
+    DPadRight,
+}
+
+/// A gamepad stick axis.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GamepadAxis {
+    /// The horizontal axis of the left stick.
+    LeftStickX,
+    /// The vertical axis of the left stick.
+    LeftStickY,
+    /// The horizontal axis of the right stick.
+    RightStickX,
+    /// The vertical axis of the right stick.
+    RightStickY,
+}
+
+/// A direction focus can move in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum NavDirection {
+    /// Towards the top of the window.
+    Up,
+    /// Towards the bottom of the window.
+    Down,
+    /// Towards the left of the window.
+    Left,
+    /// Towards the right of the window.
+    Right,
+}
+
+/// What the window does with a gamepad event no widget handled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Navigation {
+    /// Move focus in the given direction.
+    Move(NavDirection),
+    /// Press or release Enter.
+    Activate { pressed: bool },
+    /// Press or release Escape.
+    Back { pressed: bool },
+}
+
+/// The gamepad navigation state of a window.
+#[derive(Debug, Default)]
+pub(crate) struct GamepadNavigation {
+    left_stick: (f64, f64),
+    /// The direction currently held, with the D-pad or the stick.
+    held: Option<NavDirection>,
+    /// When focus next moves if the direction is still held.
+    repeat_at: Option<Instant>,
+    pub(crate) repeat_timer: Option<crate::TimerToken>,
+}
+
+impl GamepadNavigation {
+    /// Translate an event into navigation.
+    ///
+    /// When this returns [`Navigation::Move`], the caller must call
+    /// [`start_repeat`](Self::start_repeat).
+    pub(crate) fn navigate(&mut self, event: &GamepadEvent) -> Option<Navigation> {
+        match event.kind {
+            GamepadEventKind::ButtonPressed(button) => {
+                if let Some(direction) = dpad_direction(button) {
+                    self.held = Some(direction);
+                    return Some(Navigation::Move(direction));
+                }
+                match button {
+                    GamepadButton::South => Some(Navigation::Activate { pressed: true }),
+                    GamepadButton::East => Some(Navigation::Back { pressed: true }),
+                    _ => None,
+                }
+            }
+            GamepadEventKind::ButtonReleased(button) => {
+                if let Some(direction) = dpad_direction(button) {
+                    if self.held == Some(direction) {
+                        self.held = None;
+                    }
+                    return None;
+                }
+                match button {
+                    GamepadButton::South => Some(Navigation::Activate { pressed: false }),
+                    GamepadButton::East => Some(Navigation::Back { pressed: false }),
+                    _ => None,
+                }
+            }
+            GamepadEventKind::AxisChanged(axis, value) => {
+                match axis {
+                    GamepadAxis::LeftStickX => self.left_stick.0 = value,
+                    GamepadAxis::LeftStickY => self.left_stick.1 = value,
+                    _ => return None,
+                }
+                let direction = stick_direction(self.left_stick);
+                if direction == self.held {
+                    return None;
+                }
+                self.held = direction;
+                direction.map(Navigation::Move)
+            }
+            GamepadEventKind::Disconnected => {
+                self.left_stick = (0.0, 0.0);
+                self.held = None;
+                None
+            }
+            GamepadEventKind::Connected => None,
+        }
+    }
+
+    /// Schedule focus to move again after [`REPEAT_DELAY`] if the direction is
+    /// still held.
+    ///
+    /// Returns the delay of the timer to request, if there's none pending already.
+    pub(crate) fn start_repeat(&mut self, now: Instant) -> Option<Duration> {
+        self.repeat_at = Some(now + REPEAT_DELAY);
+        self.repeat_timer.is_none().then_some(REPEAT_DELAY)
+    }
+
+    /// Handle the repeat timer firing.
+    ///
+    /// Returns the direction to move focus in, if it's time, and the delay of the
+    /// next timer to request, if any.
+    pub(crate) fn on_repeat_timer(
+        &mut self,
+        now: Instant,
+    ) -> (Option<NavDirection>, Option<Duration>) {
+        self.repeat_timer = None;
+        let (Some(direction), Some(repeat_at)) = (self.held, self.repeat_at) else {
+            self.repeat_at = None;
+            return (None, None);
+        };
+        if now < repeat_at {
+            return (None, Some(repeat_at - now));
+        }
+        self.repeat_at = Some(now + REPEAT_INTERVAL);
+        (Some(direction), Some(REPEAT_INTERVAL))
+    }
+}
+
+fn dpad_direction(button: GamepadButton) -> Option<NavDirection> {
+    match button {
+        GamepadButton::DPadUp => Some(NavDirection::Up),
+        GamepadButton::DPadDown => Some(NavDirection::Down),
+        GamepadButton::DPadLeft => Some(NavDirection::Left),
+        GamepadButton::DPadRight => Some(NavDirection::Right),
+        _ => None,
+    }
+}
+
+fn stick_direction((x, y): (f64, f64)) -> Option<NavDirection> {
+    if x.abs().max(y.abs()) < STICK_THRESHOLD {
+        None
+    } else if x.abs() > y.abs() {
+        Some(if x > 0.0 {
+            NavDirection::Right
+        } else {
+            NavDirection::Left
+        })
+    } else {
+        Some(if y > 0.0 {
+            NavDirection::Up
+        } else {
+            NavDirection::Down
+        })
+    }
+}
+
+/// Start a thread polling connected gamepads, and sending their events to the app.
+///
+/// The thread runs until the app exits.
+#[cfg(feature = "gamepad")]
+pub(crate) fn start_polling(sink: crate::ext_event::ExtEventSink) {
+    use gilrs::{Axis, Button, EventType};
+    use tracing::warn;
+
+    fn button(button: Button) -> Option<GamepadButton> {
+        let button = match button {
+            Button::South => GamepadButton::South,
+            Button::East => GamepadButton::East,
+            Button::North => GamepadButton::North,
+            Button::West => GamepadButton::West,
+            Button::LeftTrigger => GamepadButton::LeftShoulder,
+            Button::RightTrigger => GamepadButton::RightShoulder,
+            Button::LeftTrigger2 => GamepadButton::LeftTrigger,
+            Button::RightTrigger2 => GamepadButton::RightTrigger,
+            Button::Select => GamepadButton::Select,
+            Button::Start => GamepadButton::Start,
+            Button::LeftThumb => GamepadButton::LeftStick,
+            Button::RightThumb => GamepadButton::RightStick,
+            Button::DPadUp => GamepadButton::DPadUp,
+            Button::DPadDown => GamepadButton::DPadDown,
+            Button::DPadLeft => GamepadButton::DPadLeft,
+            Button::DPadRight => GamepadButton::DPadRight,
+            _ => return None,
+        };
+        Some(button)
+    }
+
+    fn axis(axis: Axis) -> Option<GamepadAxis> {
+        let axis = match axis {
+            Axis::LeftStickX => GamepadAxis::LeftStickX,
+            Axis::LeftStickY => GamepadAxis::LeftStickY,
+            Axis::RightStickX => GamepadAxis::RightStickX,
+            Axis::RightStickY => GamepadAxis::RightStickY,
+            _ => return None,
+        };
+        Some(axis)
+    }
+
+    let spawn_result = std::thread::Builder::new()
+        .name("masonry-gamepad".into())
+        .spawn(move || {
+            let mut gilrs = match gilrs::Gilrs::new() {
+                Ok(gilrs) => gilrs,
+                Err(err) => {
+                    warn!("Can't access gamepads: {}", err);
+                    return;
+                }
+            };
+            loop {
+                let Some(event) = gilrs.next_event_blocking(None) else {
+                    continue;
+                };
+                let kind = match event.event {
+                    EventType::Connected => GamepadEventKind::Connected,
+                    EventType::Disconnected => GamepadEventKind::Disconnected,
+                    EventType::ButtonPressed(b, _) => match button(b) {
+                        Some(b) => GamepadEventKind::ButtonPressed(b),
+                        None => continue,
+                    },
+                    EventType::ButtonReleased(b, _) => match button(b) {
+                        Some(b) => GamepadEventKind::ButtonReleased(b),
+                        None => continue,
+                    },
+                    EventType::AxisChanged(a, value, _) => match axis(a) {
+                        Some(a) => GamepadEventKind::AxisChanged(a, value as f64),
+                        None => continue,
+                    },
+                    _ => continue,
+                };
+                let event = GamepadEvent {
+                    gamepad: GamepadId(event.id.into()),
+                    kind,
+                };
+                if sink
+                    .submit_command(GAMEPAD_EVENT, event, crate::Target::Global)
+                    .is_err()
+                {
+                    return;
+                }
+            }
+        });
+    if let Err(err) = spawn_result {
+        warn!("Can't start gamepad polling: {}", err);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(kind: GamepadEventKind) -> GamepadEvent {
+        GamepadEvent {
+            gamepad: GamepadId(0),
+            kind,
+        }
+    }
+
+    #[test]
+    fn stick_moves_once_per_push() {
+        let mut nav = GamepadNavigation::default();
+        let push = |nav: &mut GamepadNavigation, axis, value| {
+            nav.navigate(&event(GamepadEventKind::AxisChanged(axis, value)))
+        };
+
+        assert_eq!(push(&mut nav, GamepadAxis::LeftStickX, 0.3), None);
+        assert_eq!(
+            push(&mut nav, GamepadAxis::LeftStickX, 0.8),
+            Some(Navigation::Move(NavDirection::Right))
+        );
+        // Still held right: no new movement until the repeat timer fires.
+        assert_eq!(push(&mut nav, GamepadAxis::LeftStickY, 0.2), None);
+        assert_eq!(push(&mut nav, GamepadAxis::LeftStickX, 0.1), None);
+        assert_eq!(
+            push(&mut nav, GamepadAxis::LeftStickY, -0.9),
+            Some(Navigation::Move(NavDirection::Down))
+        );
+    }
+
+    #[test]
+    fn held_direction_repeats() {
+        let mut nav = GamepadNavigation::default();
+        let start = Instant::now();
+        let pressed = nav.navigate(&event(GamepadEventKind::ButtonPressed(
+            GamepadButton::DPadLeft,
+        )));
+        assert_eq!(pressed, Some(Navigation::Move(NavDirection::Left)));
+        assert_eq!(nav.start_repeat(start), Some(REPEAT_DELAY));
+
+        let (direction, next) = nav.on_repeat_timer(start + REPEAT_DELAY);
+        assert_eq!(direction, Some(NavDirection::Left));
+        assert_eq!(next, Some(REPEAT_INTERVAL));
+
+        nav.navigate(&event(GamepadEventKind::ButtonReleased(
+            GamepadButton::DPadLeft,
+        )));
+        let (direction, next) = nav.on_repeat_timer(start + REPEAT_DELAY + REPEAT_INTERVAL);
+        assert_eq!((direction, next), (None, None));
+    }
+}
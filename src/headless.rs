@@ -0,0 +1,307 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Running a widget tree without a window.
+//!
+//! A [`HeadlessApp`] runs the same passes as a windowed app, but events are fed
+//! in by the caller and time only moves forward when asked to. Its output is
+//! either a bitmap of the window, or a [`SceneNode`] tree describing the
+//! widgets and their layout, which can be serialized.
+//!
+//! This is useful for thumbnail generation, documentation screenshots, and
+//! server-side rendering.
+
+use std::rc::Rc;
+
+use instant::Duration;
+use serde::{Deserialize, Serialize};
+
+use crate::action::Action;
+use crate::debug_switches::DebugSwitch;
+use crate::image_cache::ImageCache;
+use crate::kurbo::{Point, Size, Vec2};
+use crate::piet::{Error as PietError, ImageBuf};
+use crate::shell::MouseButton;
+use crate::testing::TestHarness;
+use crate::widget::{MemoryUsage, WidgetRef};
+use crate::{AppStore, Command, Env, Event, Widget, WidgetId};
+
+/// A widget tree running without a window.
+///
+/// Unlike [`TestHarness`], which it shares its implementation with, a headless
+/// app doesn't assume it's running in a test: it reports missing widgets and
+/// rendering failures to the caller instead of panicking, and moves animations
+/// forward along with timers.
+pub struct HeadlessApp {
+    harness: TestHarness,
+    window_size: Size,
+    scale: f64,
+}
+
+/// A serializable description of a widget and its children, as laid out.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SceneNode {
+    /// The short type name of the widget, eg `"Label"`.
+    pub widget: String,
+    /// The raw value of the widget's id.
+    pub id: u64,
+    /// The position of the widget's top-left corner, in window coordinates.
+    pub origin: (f64, f64),
+    /// The size of the widget.
+    pub size: (f64, f64),
+    /// The text the widget displays, if any.
+    pub text: Option<String>,
+    /// The widget's children, in paint order.
+    pub children: Vec<SceneNode>,
+    /// An estimate of the heap memory retained by the widget and its descendants,
+    /// in bytes, with shared buffers counted once.
+    ///
+    /// Only set while
+    /// [`DebugSwitch::MemoryAccounting`](crate::debug_switches::DebugSwitch::MemoryAccounting)
+    /// is enabled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory: Option<usize>,
+}
+
+impl HeadlessApp {
+    /// Create a headless app with the given root widget and window size.
+    pub fn new(root: impl Widget, window_size: Size) -> Self {
+        Self::with_resources(root, window_size, None, ImageCache::new())
+    }
+
+    /// Create a headless app with an [`AppStore`] its widgets can subscribe to.
+    pub fn with_store<T: 'static>(
+        root: impl Widget,
+        window_size: Size,
+        store: AppStore<T>,
+    ) -> Self {
+        Self::with_resources(root, window_size, Some(Rc::new(store)), ImageCache::new())
+    }
+
+    /// Create a headless app sharing the given [`ImageCache`].
+    ///
+    /// Insert images in the cache beforehand so they're shown on the first render.
+    pub fn with_image_cache(root: impl Widget, window_size: Size, image_cache: ImageCache) -> Self {
+        Self::with_resources(root, window_size, None, image_cache)
+    }
+
+    fn with_resources(
+        root: impl Widget,
+        window_size: Size,
+        app_store: Option<Rc<dyn crate::app_store::AnyAppStore>>,
+        image_cache: ImageCache,
+    ) -> Self {
+        HeadlessApp {
+            harness: TestHarness::create_with_app_store(root, window_size, app_store, image_cache),
+            window_size,
+            scale: 1.0,
+        }
+    }
+
+    /// Builder-style method to set the number of pixels per display point in
+    /// rendered bitmaps.
+    ///
+    /// The default is 1.0.
+    pub fn with_scale(mut self, scale: f64) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    // --- Input ---
+
+    /// Send an event to the widget tree, and process the resulting commands
+    /// and lifecycle events.
+    pub fn process_event(&mut self, event: Event) {
+        self.harness.process_event(event);
+    }
+
+    /// Submit a command to the widget tree.
+    pub fn submit_command(&mut self, command: impl Into<Command>) {
+        self.harness.submit_command(command);
+    }
+
+    /// Move the mouse to the given position, in window coordinates.
+    pub fn mouse_move(&mut self, pos: impl Into<Point>) {
+        self.harness.mouse_move(pos);
+    }
+
+    /// Press the given mouse button at the current mouse position.
+    pub fn mouse_button_press(&mut self, button: MouseButton) {
+        self.harness.mouse_button_press(button);
+    }
+
+    /// Release the given mouse button at the current mouse position.
+    pub fn mouse_button_release(&mut self, button: MouseButton) {
+        self.harness.mouse_button_release(button);
+    }
+
+    /// Scroll the mouse wheel at the current mouse position.
+    pub fn mouse_wheel(&mut self, wheel_delta: Vec2) {
+        self.harness.mouse_wheel(wheel_delta);
+    }
+
+    /// Click the center of the given widget.
+    ///
+    /// Does nothing if the widget isn't in the tree.
+    pub fn click_widget(&mut self, id: WidgetId) {
+        if self.harness.try_get_widget(id).is_some() {
+            self.harness.mouse_click_on(id);
+        }
+    }
+
+    /// Type the given text into the focused widget.
+    pub fn type_text(&mut self, text: &str) {
+        self.harness.keyboard_type_chars(text);
+    }
+
+    /// Resize the window.
+    pub fn resize(&mut self, window_size: Size) {
+        self.window_size = window_size;
+        self.harness.set_window_size(window_size);
+    }
+
+    /// Move time forward, firing the timers that expire and sending an
+    /// animation frame to the widgets which requested one.
+    pub fn advance_time(&mut self, duration: Duration) {
+        self.harness.move_timers_forward(duration);
+        if self.harness.root_widget().state().request_anim {
+            self.harness
+                .process_event(Event::AnimFrame(duration.as_nanos() as u64));
+        }
+    }
+
+    // --- Output ---
+
+    /// Paint the whole window into a new bitmap.
+    ///
+    /// Returns an error if no graphics device is available, or painting failed.
+    pub fn render(&mut self) -> Result<ImageBuf, PietError> {
+        *self.harness.window_mut().invalid_mut() = self.window_size.to_rect().into();
+        self.harness.try_render_to_image_with_scale(self.scale)
+    }
+
+    /// Paint the given widget and its children into a new bitmap, the size of
+    /// the widget.
+    ///
+    /// Returns `None` if the widget isn't in the tree, is stashed, or can't be painted.
+    pub fn render_widget(&mut self, id: WidgetId) -> Option<ImageBuf> {
+        let widget = self.harness.try_get_widget(id)?;
+        if widget.state().is_stashed {
+            return None;
+        }
+        self.harness.try_render_widget_to_image(id)
+    }
+
+    /// Describe the widget tree as currently laid out.
+    pub fn scene(&self) -> SceneNode {
+        scene_node(self.harness.root_widget(), self.harness.env())
+    }
+
+    /// Pop the next action submitted by a widget, oldest first.
+    pub fn pop_action(&mut self) -> Option<(Action, WidgetId)> {
+        self.harness.pop_action()
+    }
+
+    // --- Getters ---
+
+    /// Return the root widget.
+    pub fn root_widget(&self) -> WidgetRef<'_, dyn Widget> {
+        self.harness.root_widget()
+    }
+
+    /// Return the widget with the given id, if it's in the tree.
+    pub fn get_widget(&self, id: WidgetId) -> Option<WidgetRef<'_, dyn Widget>> {
+        self.harness.try_get_widget(id)
+    }
+}
+
+/// Describe `widget` and its descendants; see [`SceneNode`].
+///
+/// The memory of each subtree is estimated if `env` enables
+/// [`DebugSwitch::MemoryAccounting`].
+pub(crate) fn scene_node(widget: WidgetRef<'_, dyn Widget>, env: &Env) -> SceneNode {
+    let count_memory = DebugSwitch::MemoryAccounting.is_enabled(env);
+    scene_subtree(widget, Point::ORIGIN, count_memory).0
+}
+
+/// Describe `widget` and its descendants, and count the memory they retain,
+/// bottom-up so each widget is only visited once.
+fn scene_subtree(
+    widget: WidgetRef<'_, dyn Widget>,
+    parent_origin: Point,
+    count_memory: bool,
+) -> (SceneNode, MemoryUsage) {
+    let state = widget.state();
+    let origin = parent_origin + state.origin.to_vec2();
+    let mut memory = MemoryUsage::default();
+    if count_memory {
+        widget.retained_memory(&mut memory);
+    }
+    let children = widget
+        .children()
+        .into_iter()
+        .filter(|child| !child.state().is_stashed)
+        .map(|child| {
+            let (node, child_memory) = scene_subtree(child, origin, count_memory);
+            memory.merge(&child_memory);
+            node
+        })
+        .collect();
+    let node = SceneNode {
+        widget: widget.short_type_name().to_string(),
+        id: state.id.to_raw(),
+        origin: (origin.x, origin.y),
+        size: (state.size.width, state.size.height),
+        text: widget.get_debug_text(),
+        children,
+        memory: count_memory.then(|| memory.bytes()),
+    };
+    (node, memory)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command;
+    use crate::piet::ImageFormat;
+    use crate::widget::{Button, Flex, Image, Label};
+
+    #[test]
+    fn scene_and_render() {
+        let widget = Flex::column()
+            .with_child(Label::new("Title"))
+            .with_child(Button::new("Go"));
+        let mut app = HeadlessApp::new(widget, Size::new(200.0, 100.0)).with_scale(2.0);
+
+        let scene = app.scene();
+        assert_eq!(scene.widget, "Flex");
+        assert_eq!(scene.children.len(), 2);
+        assert_eq!(scene.children[0].text.as_deref(), Some("Title"));
+        assert!(scene.children[1].origin.1 > 0.0);
+
+        let image = app.render().unwrap();
+        assert_eq!((image.width(), image.height()), (400, 200));
+
+        let button_id = app.root_widget().children()[1].id();
+        assert_eq!(scene.children[1].id, button_id.to_raw());
+        app.click_widget(button_id);
+        assert_eq!(app.pop_action(), Some((Action::ButtonPressed, button_id)));
+    }
+
+    #[test]
+    fn memory_accounting_is_opt_in() {
+        let image_data = ImageBuf::from_raw(vec![255; 3 * 8 * 8], ImageFormat::Rgb, 8, 8);
+        let widget = Flex::column()
+            .with_child(Image::new(image_data.clone()))
+            .with_child(Image::new(image_data));
+        let mut app = HeadlessApp::new(widget, Size::new(200.0, 100.0));
+        assert_eq!(app.scene().memory, None);
+
+        app.submit_command(command::TOGGLE_DEBUG_SWITCH.with(DebugSwitch::MemoryAccounting));
+        let scene = app.scene();
+        assert_eq!(scene.children[0].memory, Some(3 * 8 * 8));
+        // The pixels shared by both images are counted once.
+        assert_eq!(scene.memory, Some(3 * 8 * 8));
+    }
+}
@@ -0,0 +1,579 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Loading [`Env`] values and style sheets from files, and reloading them when the
+//! files change.
+//!
+//! See [`AppLauncher::with_env_file`](crate::AppLauncher::with_env_file) and
+//! [`AppLauncher::with_style_sheet_file`](crate::AppLauncher::with_style_sheet_file).
+//!
+//! An env file is a list of `key = value` lines, where the key is the raw name of
+//! an existing [`Key`](crate::Key). Empty lines and lines starting with `#` are ignored.
+//!
+//! ```text
+//! # Make the window background a bit lighter
+//! org.masonry.theme.window_background_color = #3a3a3a
+//! org.masonry.theme.button_radius = 6.0
+//! org.masonry.theme.text_size_normal = 16
+//! ```
+//!
+//! Values are parsed according to the type of the value they replace:
+//!
+//! - Colors are written in hex, eg `#ff8000` or `#ff800080`.
+//! - Floats and integers are written as numbers.
+//! - Bools are written as `true` or `false`.
+//! - Strings are written as-is, optionally surrounded with double quotes.
+//! - Points and sizes are written as two comma-separated numbers.
+//! - Rects are written as four comma-separated numbers (`x0, y0, x1, y1`).
+//! - Insets and corner radii are written as one number, or four comma-separated numbers.
+//!
+//! Fonts and custom values can't be set from a file.
+//!
+//! A style sheet file is a list of rules. Each rule starts with a selector followed
+//! by `{` on its own line, then has one `property = value` line per property, and
+//! ends with a `}` line:
+//!
+//! ```text
+//! # Every widget gets some padding
+//! * {
+//!     padding = 4.0
+//! }
+//!
+//! Button.primary:hover {
+//!     background = #ff8000
+//!     border = #000000, 2.0
+//!     corner_radius = 6.0
+//! }
+//! ```
+//!
+//! Selectors are written as in CSS: an optional widget type, or `*`, followed by
+//! `.class` and `:state` conditions, where the states are `hover`, `active`, `focus`
+//! and `disabled`. The properties are those of [`StyleProperties`]: `background` is
+//! a color, `border` a color and a width, `corner_radius` and `padding` one number,
+//! or four comma-separated numbers. Unlike the properties of a style sheet built in
+//! code, they can't refer to `Env` keys.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use tracing::{info, warn};
+
+use crate::command::{sys as sys_cmd, SingleUse};
+use crate::ext_event::ExtEventSink;
+use crate::kurbo::RoundedRectRadii;
+use crate::style::{PseudoClass, StyleProperties, StyleSelector, StyleSheet};
+use crate::{ArcStr, Color, Env, Insets, Point, Rect, Selector, Size, Target, Value};
+
+/// How often the watcher thread checks whether the file was modified.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Sent by the watcher thread with the new contents of the env file.
+pub(crate) const RELOAD_ENV_FILE: Selector<String> =
+    Selector::new("masonry-builtin.reload-env-file");
+
+/// The values an env file replaced, restored once their lines are removed from the file.
+#[derive(Debug, Default)]
+pub(crate) struct EnvFileOverrides {
+    replaced: HashMap<String, Value>,
+}
+
+impl EnvFileOverrides {
+    /// Apply the values of an env file on top of `env`.
+    ///
+    /// Keys which a previous version of the file set, but this one doesn't, get back
+    /// the value they had before the file first replaced it.
+    ///
+    /// Invalid lines are logged and skipped.
+    pub(crate) fn apply(&mut self, env: &Env, contents: &str) -> Env {
+        let mut env = env.clone();
+        let mut file_keys = Vec::new();
+        for (key, value) in parse_env_file(&env, contents) {
+            if let Some(current_value) = env.get_raw_by_name(&key) {
+                self.replaced
+                    .entry(key.clone())
+                    .or_insert_with(|| current_value.clone());
+            }
+            env.set_raw_by_name(&key, value);
+            file_keys.push(key);
+        }
+        self.replaced.retain(|key, default_value| {
+            if file_keys.contains(key) {
+                return true;
+            }
+            env.set_raw_by_name(key, default_value.clone());
+            false
+        });
+        env
+    }
+}
+
+/// The keys and values of an env file, parsed according to the types of the
+/// values of `env`.
+///
+/// Invalid lines are logged and skipped.
+fn parse_env_file(env: &Env, contents: &str) -> Vec<(String, Value)> {
+    let mut values = Vec::new();
+    for (line_number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            warn!("env file, line {}: expected 'key = value'", line_number + 1);
+            continue;
+        };
+        let key = key.trim();
+        let Some(current_value) = env.get_raw_by_name(key) else {
+            warn!("env file, line {}: unknown key '{}'", line_number + 1, key);
+            continue;
+        };
+        match parse_value(current_value, value.trim()) {
+            Some(value) => values.push((key.to_string(), value)),
+            None => warn!(
+                "env file, line {}: can't parse '{}' as a value of the same type as {:?}",
+                line_number + 1,
+                value.trim(),
+                current_value,
+            ),
+        }
+    }
+    values
+}
+
+fn parse_value(current_value: &Value, text: &str) -> Option<Value> {
+    let value = match current_value {
+        Value::Point(_) => {
+            let [x, y] = parse_floats(text)?;
+            Value::Point(Point::new(x, y))
+        }
+        Value::Size(_) => {
+            let [width, height] = parse_floats(text)?;
+            Value::Size(Size::new(width, height))
+        }
+        Value::Rect(_) => {
+            let [x0, y0, x1, y1] = parse_floats(text)?;
+            Value::Rect(Rect::new(x0, y0, x1, y1))
+        }
+        Value::Insets(_) => match parse_floats(text) {
+            Some([x0, y0, x1, y1]) => Value::Insets(Insets::new(x0, y0, x1, y1)),
+            None => Value::Insets(Insets::uniform(text.parse().ok()?)),
+        },
+        Value::RoundedRectRadii(_) => match parse_floats(text) {
+            Some([top_left, top_right, bottom_right, bottom_left]) => Value::RoundedRectRadii(
+                RoundedRectRadii::new(top_left, top_right, bottom_right, bottom_left),
+            ),
+            None => {
+                Value::RoundedRectRadii(RoundedRectRadii::from_single_radius(text.parse().ok()?))
+            }
+        },
+        Value::Color(_) => Value::Color(Color::from_hex_str(text).ok()?),
+        Value::Float(_) => Value::Float(text.parse().ok()?),
+        Value::Bool(_) => Value::Bool(text.parse().ok()?),
+        Value::UnsignedInt(_) => Value::UnsignedInt(text.parse().ok()?),
+        Value::String(_) => {
+            let text = text
+                .strip_prefix('"')
+                .and_then(|text| text.strip_suffix('"'))
+                .unwrap_or(text);
+            Value::String(ArcStr::from(text))
+        }
+        Value::Font(_) | Value::Other(_) => return None,
+    };
+    Some(value)
+}
+
+fn parse_floats<const N: usize>(text: &str) -> Option<[f64; N]> {
+    let mut values = [0.0; N];
+    let mut parts = text.split(',');
+    for value in &mut values {
+        *value = parts.next()?.trim().parse().ok()?;
+    }
+    if parts.next().is_some() {
+        return None;
+    }
+    Some(values)
+}
+
+/// The rules of a style sheet file.
+///
+/// Invalid lines are logged and skipped.
+pub(crate) fn parse_style_sheet(contents: &str) -> StyleSheet {
+    let mut style_sheet = StyleSheet::new();
+    let mut rule: Option<(StyleSelector, StyleProperties)> = None;
+    for (line_number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line == "}" {
+            match rule.take() {
+                Some((selector, properties)) => style_sheet.add_rule(selector, properties),
+                None => warn!("style sheet, line {}: unexpected '}}'", line_number + 1),
+            }
+            continue;
+        }
+        let Some((selector, properties)) = &mut rule else {
+            let selector = line
+                .strip_suffix('{')
+                .and_then(|selector| parse_selector(selector.trim()));
+            match selector {
+                Some(selector) => rule = Some((selector, StyleProperties::new())),
+                None => warn!(
+                    "style sheet, line {}: expected 'selector {{'",
+                    line_number + 1
+                ),
+            }
+            continue;
+        };
+        let Some((name, value)) = line.split_once('=') else {
+            warn!(
+                "style sheet, line {}: expected 'property = value' in rule {:?}",
+                line_number + 1,
+                selector,
+            );
+            continue;
+        };
+        let (name, value) = (name.trim(), value.trim());
+        let new_properties = match name {
+            "background" => Color::from_hex_str(value)
+                .ok()
+                .map(|color| properties.clone().background(color)),
+            "border" => value.split_once(',').and_then(|(color, width)| {
+                let color = Color::from_hex_str(color.trim()).ok()?;
+                let width: f64 = width.trim().parse().ok()?;
+                Some(properties.clone().border(color, width))
+            }),
+            "corner_radius" => match parse_floats(value) {
+                Some([top_left, top_right, bottom_right, bottom_left]) => {
+                    Some(properties.clone().corner_radius(RoundedRectRadii::new(
+                        top_left,
+                        top_right,
+                        bottom_right,
+                        bottom_left,
+                    )))
+                }
+                None => value.parse().ok().map(|radius| {
+                    properties
+                        .clone()
+                        .corner_radius(RoundedRectRadii::from_single_radius(radius))
+                }),
+            },
+            "padding" => match parse_floats(value) {
+                Some([x0, y0, x1, y1]) => {
+                    Some(properties.clone().padding(Insets::new(x0, y0, x1, y1)))
+                }
+                None => value
+                    .parse()
+                    .ok()
+                    .map(|padding| properties.clone().padding(Insets::uniform(padding))),
+            },
+            _ => {
+                warn!(
+                    "style sheet, line {}: unknown property '{}'",
+                    line_number + 1,
+                    name
+                );
+                continue;
+            }
+        };
+        match new_properties {
+            Some(new_properties) => *properties = new_properties,
+            None => warn!(
+                "style sheet, line {}: can't parse '{}' as a value of '{}'",
+                line_number + 1,
+                value,
+                name,
+            ),
+        }
+    }
+    if let Some((selector, _)) = rule {
+        warn!("style sheet: rule {:?} isn't closed", selector);
+    }
+    style_sheet
+}
+
+/// Parse a selector like `Button.primary:hover`.
+fn parse_selector(text: &str) -> Option<StyleSelector> {
+    let type_end = text.find(['.', ':']).unwrap_or(text.len());
+    let (widget_type, mut conditions) = text.split_at(type_end);
+    let mut selector = match widget_type {
+        "" | "*" => StyleSelector::any(),
+        widget_type if widget_type.chars().all(|c| c.is_alphanumeric() || c == '_') => {
+            StyleSelector::widget(widget_type)
+        }
+        _ => return None,
+    };
+    while !conditions.is_empty() {
+        let end = conditions[1..]
+            .find(['.', ':'])
+            .map_or(conditions.len(), |end| end + 1);
+        let (condition, rest) = conditions.split_at(end);
+        conditions = rest;
+        let (kind, name) = condition.split_at(1);
+        if name.is_empty() {
+            return None;
+        }
+        selector = match (kind, name) {
+            (".", class) => selector.class(class),
+            (_, "hover") => selector.state(PseudoClass::Hover),
+            (_, "active") => selector.state(PseudoClass::Active),
+            (_, "focus") => selector.state(PseudoClass::Focus),
+            (_, "disabled") => selector.state(PseudoClass::Disabled),
+            _ => return None,
+        };
+    }
+    Some(selector)
+}
+
+/// Start a thread watching the env file at `path`, and sending its contents to the
+/// app every time it's modified.
+///
+/// The thread runs until the app exits.
+pub(crate) fn watch_env_file(path: PathBuf, sink: ExtEventSink) {
+    watch_file(path, "env file", sink, |sink, contents| {
+        sink.submit_command(RELOAD_ENV_FILE, contents, Target::Global)
+            .is_ok()
+    });
+}
+
+/// Start a thread watching the style sheet file at `path`, and replacing the app's
+/// style sheet every time it's modified.
+///
+/// The thread runs until the app exits.
+pub(crate) fn watch_style_sheet_file(path: PathBuf, sink: ExtEventSink) {
+    watch_file(path, "style sheet", sink, |sink, contents| {
+        let style_sheet = parse_style_sheet(&contents);
+        sink.submit_command(
+            sys_cmd::SET_STYLE_SHEET,
+            SingleUse::new(style_sheet),
+            Target::Global,
+        )
+        .is_ok()
+    });
+}
+
+/// Start a thread polling the file at `path`, and calling `reload` with its contents
+/// when they change, until it returns `false`.
+///
+/// While the file is missing, eg while an editor replaces it, it's left alone.
+fn watch_file(
+    path: PathBuf,
+    description: &'static str,
+    sink: ExtEventSink,
+    reload: impl Fn(&ExtEventSink, String) -> bool + Send + 'static,
+) {
+    let spawn_result = std::thread::Builder::new()
+        .name(format!("masonry-{}-watcher", description.replace(' ', "-")))
+        .spawn(move || {
+            let mut last_modified = modified_time(&path);
+            let mut last_contents = std::fs::read_to_string(&path).ok();
+            loop {
+                std::thread::sleep(POLL_INTERVAL);
+                let Some(modified) = modified_time(&path) else {
+                    continue;
+                };
+                if last_modified == Some(modified) {
+                    continue;
+                }
+                last_modified = Some(modified);
+
+                match std::fs::read_to_string(&path) {
+                    // Eg the file was touched, or saved without changes.
+                    Ok(contents) if last_contents.as_ref() == Some(&contents) => (),
+                    Ok(contents) => {
+                        info!("Reloading {} {}", description, path.display());
+                        last_contents = Some(contents.clone());
+                        if !reload(&sink, contents) {
+                            return;
+                        }
+                    }
+                    Err(err) => warn!("Can't read {} {}: {}", description, path.display(), err),
+                }
+            }
+        });
+    if let Err(err) = spawn_result {
+        warn!("Can't start {} watcher: {}", description, err);
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::style::{BorderStyle, PseudoClassState, WidgetStyle};
+    use crate::testing::{widget_ids, ModularWidget, TestHarness};
+    use crate::widget::Flex;
+    use crate::{theme, LifeCycle};
+
+    #[test]
+    fn parse_env_file() {
+        let env = Env::with_theme();
+        let contents = "
+            # A comment
+            org.masonry.theme.window_background_color = #ff0000
+            org.masonry.theme.button_radius = 6.5
+            org.masonry.theme.no_such_key = 1.0
+            org.masonry.theme.button_border_width = thick
+        ";
+
+        let new_env = EnvFileOverrides::default().apply(&env, contents);
+        assert_eq!(
+            new_env.get(theme::WINDOW_BACKGROUND_COLOR),
+            Color::rgb8(255, 0, 0)
+        );
+        assert_eq!(new_env.get(theme::BUTTON_BORDER_RADIUS), 6.5);
+        assert_eq!(
+            new_env.get(theme::BUTTON_BORDER_WIDTH),
+            env.get(theme::BUTTON_BORDER_WIDTH)
+        );
+    }
+
+    #[test]
+    fn removed_lines_restore_previous_values() {
+        let env = Env::with_theme();
+        let mut overrides = EnvFileOverrides::default();
+
+        let new_env = overrides.apply(
+            &env,
+            "org.masonry.theme.button_radius = 6.5
+            org.masonry.theme.button_border_width = 3.0",
+        );
+        assert_eq!(new_env.get(theme::BUTTON_BORDER_RADIUS), 6.5);
+        assert_eq!(new_env.get(theme::BUTTON_BORDER_WIDTH), 3.0);
+
+        let new_env = overrides.apply(&new_env, "org.masonry.theme.button_radius = 8.0");
+        assert_eq!(new_env.get(theme::BUTTON_BORDER_RADIUS), 8.0);
+        assert_eq!(
+            new_env.get(theme::BUTTON_BORDER_WIDTH),
+            env.get(theme::BUTTON_BORDER_WIDTH)
+        );
+
+        let new_env = overrides.apply(&new_env, "");
+        assert_eq!(
+            new_env.get(theme::BUTTON_BORDER_RADIUS),
+            env.get(theme::BUTTON_BORDER_RADIUS)
+        );
+    }
+
+    #[test]
+    fn parse_style_sheet() {
+        let contents = "
+            # A comment
+            * {
+                padding = 4.0
+            }
+
+            Button.primary {
+                background = #ff0000
+                border = #00ff00, 2.0
+                corner_radius = 1.0, 2.0, 3.0, 4.0
+                shadow = 3.0
+            }
+
+            Button.primary:hover {
+                background = #0000ff
+            }
+
+            Button:pressed {
+                background = #ffffff
+            }
+        ";
+        let style_sheet = super::parse_style_sheet(contents);
+
+        let primary = [ArcStr::from("primary")];
+        let idle = PseudoClassState::default();
+        let own_style = WidgetStyle::default();
+
+        let style = style_sheet.resolve("Label", &primary, idle, &own_style);
+        assert_eq!(style.padding, Insets::uniform(4.0).into());
+        assert_eq!(style.background, None);
+
+        let style = style_sheet.resolve("Button", &primary, idle, &own_style);
+        assert_eq!(style.background, Some(Color::rgb8(255, 0, 0).into()));
+        assert_eq!(
+            style.border,
+            Some(BorderStyle {
+                width: 2.0.into(),
+                color: Color::rgb8(0, 255, 0).into(),
+            })
+        );
+        assert_eq!(
+            style.corner_radius,
+            RoundedRectRadii::new(1.0, 2.0, 3.0, 4.0).into()
+        );
+
+        let style = style_sheet.resolve("Button", &[], idle, &own_style);
+        assert_eq!(style.background, None);
+        assert!(style_sheet.depends_on_state("Button", &primary));
+        assert!(!style_sheet.depends_on_state("Button", &[]));
+    }
+
+    #[test]
+    fn env_change_relayouts_tree() {
+        let layout_count = Rc::new(Cell::new(0));
+        let env_changed = Rc::new(Cell::new(false));
+
+        let widget = ModularWidget::new((layout_count.clone(), env_changed.clone()))
+            .lifecycle_fn(|(_, env_changed), _, event, _| {
+                if let LifeCycle::EnvChanged = event {
+                    env_changed.set(true);
+                }
+            })
+            .layout_fn(|(layout_count, _), _, _, env| {
+                layout_count.set(layout_count.get() + 1);
+                Size::new(env.get(theme::BUTTON_BORDER_RADIUS), 10.0)
+            });
+        let [widget_id] = widget_ids();
+        let mut harness = TestHarness::create(Flex::row().with_child_id(widget, widget_id));
+        let layout_count_before = layout_count.get();
+
+        let env = EnvFileOverrides::default()
+            .apply(&Env::with_theme(), "org.masonry.theme.button_radius = 42");
+        harness.set_env(env);
+
+        assert!(env_changed.get());
+        assert_eq!(layout_count.get(), layout_count_before + 1);
+        assert_eq!(
+            harness.get_widget(widget_id).state().layout_rect().width(),
+            42.0
+        );
+    }
+
+    #[test]
+    fn env_change_skips_widgets_which_didnt_read_the_key() {
+        let radius_changed = Rc::new(Cell::new(false));
+        let width_changed = Rc::new(Cell::new(false));
+
+        let reads_radius = ModularWidget::new(radius_changed.clone())
+            .lifecycle_fn(|changed, _, event, _| {
+                if let LifeCycle::EnvChanged = event {
+                    changed.set(true);
+                }
+            })
+            .layout_fn(|_, _, _, env| Size::new(env.get(theme::BUTTON_BORDER_RADIUS), 10.0));
+        let reads_width = ModularWidget::new(width_changed.clone())
+            .lifecycle_fn(|changed, _, event, _| {
+                if let LifeCycle::EnvChanged = event {
+                    changed.set(true);
+                }
+            })
+            .layout_fn(|_, _, _, env| Size::new(env.get(theme::BUTTON_BORDER_WIDTH), 10.0));
+        let mut harness =
+            TestHarness::create(Flex::row().with_child(reads_radius).with_child(reads_width));
+
+        let env = EnvFileOverrides::default()
+            .apply(&Env::with_theme(), "org.masonry.theme.button_radius = 42");
+        harness.set_env(env);
+
+        assert!(radius_changed.get());
+        assert!(!width_changed.get());
+    }
+}
@@ -0,0 +1,654 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! An app-wide cache of decoded images.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::panic;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use tracing::{trace, warn};
+
+use crate::ext_event::ExtEventSink;
+use crate::piet::ImageBuf;
+use crate::{ArcStr, Command, Selector, Target, WidgetId};
+
+/// Sent to a widget once an image it requested with
+/// [`EventCtx::load_image`](crate::EventCtx::load_image) is decoded, or failed to.
+pub const IMAGE_LOADED: Selector<ImageLoaded> = Selector::new("masonry-builtin.image-loaded");
+
+/// Sent by decoding threads back to the app.
+pub(crate) const IMAGE_DECODED: Selector<DecodedImage> =
+    Selector::new("masonry-builtin.image-decoded");
+
+const DEFAULT_BYTE_BUDGET: usize = 256 * 1024 * 1024;
+const DEFAULT_DECODE_THREADS: usize = 2;
+
+/// The payload of [`IMAGE_LOADED`].
+#[derive(Clone, Debug)]
+pub struct ImageLoaded {
+    /// The file that was decoded.
+    pub path: PathBuf,
+    /// Why the image couldn't be loaded, if it couldn't.
+    pub error: Option<ArcStr>,
+}
+
+pub(crate) struct DecodedImage {
+    path: PathBuf,
+    result: Result<AnimatedImage, ArcStr>,
+}
+
+/// One frame of an [`AnimatedImage`].
+#[derive(Clone)]
+pub struct AnimationFrame {
+    /// The pixels of the frame, the size of the whole image.
+    pub image: ImageBuf,
+    /// How long the frame is shown.
+    pub delay: Duration,
+}
+
+/// A sequence of frames decoded from an animated GIF, PNG or WebP file.
+///
+/// Still images are animations with a single frame. Like [`ImageBuf`], this is
+/// cheap to clone.
+#[derive(Clone)]
+pub struct AnimatedImage {
+    frames: Arc<[AnimationFrame]>,
+}
+
+impl AnimatedImage {
+    /// Create an animation from its frames.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `frames` is empty.
+    pub fn new(frames: Vec<AnimationFrame>) -> Self {
+        assert!(
+            !frames.is_empty(),
+            "an animated image needs at least one frame"
+        );
+        AnimatedImage {
+            frames: frames.into(),
+        }
+    }
+
+    /// The frames of the animation, in order.
+    pub fn frames(&self) -> &[AnimationFrame] {
+        &self.frames
+    }
+
+    /// The image shown before the animation starts.
+    pub fn first_frame(&self) -> &ImageBuf {
+        &self.frames[0].image
+    }
+
+    /// Whether the image has more than one frame.
+    pub fn is_animated(&self) -> bool {
+        self.frames.len() > 1
+    }
+
+    /// The number of bytes taken by the pixels of every frame.
+    pub fn byte_size(&self) -> usize {
+        self.frames
+            .iter()
+            .map(|frame| frame.image.raw_pixels().len())
+            .sum()
+    }
+}
+
+impl From<ImageBuf> for AnimatedImage {
+    fn from(image: ImageBuf) -> Self {
+        AnimatedImage::new(vec![AnimationFrame {
+            image,
+            delay: Duration::ZERO,
+        }])
+    }
+}
+
+/// Where to load an image from, with optional variants for high-DPI screens.
+///
+/// ```ignore
+/// let source = ImageSource::new("icons/save.png").with_variant(2.0, "icons/save@2x.png");
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImageSource {
+    path: PathBuf,
+    variants: Vec<(f64, PathBuf)>,
+}
+
+impl ImageSource {
+    /// An image file, meant for screens with a scale factor of 1.0.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        ImageSource {
+            path: path.into(),
+            variants: Vec::new(),
+        }
+    }
+
+    /// Builder-style method to add a version of the image meant for screens with
+    /// the given scale factor.
+    pub fn with_variant(mut self, scale: f64, path: impl Into<PathBuf>) -> Self {
+        self.variants.push((scale, path.into()));
+        self
+    }
+
+    /// The file to use on a screen with the given scale factor.
+    ///
+    /// This is the variant with the smallest scale that's at least `scale`, or the
+    /// largest variant if none is big enough.
+    pub fn select(&self, scale: f64) -> &Path {
+        let candidates = std::iter::once((1.0, &self.path))
+            .chain(self.variants.iter().map(|(scale, path)| (*scale, path)));
+        let mut best_fit: Option<(f64, &PathBuf)> = None;
+        let mut largest: Option<(f64, &PathBuf)> = None;
+        for (variant_scale, path) in candidates {
+            if variant_scale >= scale && best_fit.map_or(true, |(best, _)| variant_scale < best) {
+                best_fit = Some((variant_scale, path));
+            }
+            if largest.map_or(true, |(largest, _)| variant_scale > largest) {
+                largest = Some((variant_scale, path));
+            }
+        }
+        best_fit.or(largest).map_or(&self.path, |(_, path)| path)
+    }
+}
+
+/// An app-wide cache of decoded images.
+///
+/// Images are decoded on a pool of worker threads, including every frame of
+/// animated GIF, PNG and WebP files. They're kept until the decoded pixels exceed
+/// the cache's byte budget; the least recently used images are then evicted.
+/// Widgets get the cache with [`EventCtx::image_cache`], and usually load
+/// images with [`EventCtx::load_image`], which notifies them with [`IMAGE_LOADED`]
+/// once the image is ready. The [`Image`](crate::widget::Image) widget does this
+/// when created with [`Image::from_source`](crate::widget::Image::from_source).
+///
+/// The cache is a handle: its clones share the same images. Give it to the app
+/// with [`AppLauncher::with_image_cache`] to change its budgets.
+///
+/// [`EventCtx::image_cache`]: crate::EventCtx::image_cache
+/// [`EventCtx::load_image`]: crate::EventCtx::load_image
+/// [`AppLauncher::with_image_cache`]: crate::AppLauncher::with_image_cache
+#[derive(Clone)]
+pub struct ImageCache {
+    inner: Rc<RefCell<CacheInner>>,
+}
+
+struct CacheInner {
+    entries: HashMap<PathBuf, Entry>,
+    byte_budget: usize,
+    used_bytes: usize,
+    decode_threads: usize,
+    /// Started on the first request.
+    pool: Option<DecodePool>,
+    /// Incremented every time an image is used, to find the least recently used one.
+    clock: u64,
+}
+
+enum Entry {
+    Loading {
+        waiters: Vec<WidgetId>,
+    },
+    Loaded {
+        image: AnimatedImage,
+        last_used: u64,
+    },
+    Failed(ArcStr),
+}
+
+struct DecodeJob {
+    path: PathBuf,
+    sink: ExtEventSink,
+}
+
+struct DecodePool {
+    jobs: mpsc::Sender<DecodeJob>,
+}
+
+impl ImageCache {
+    /// Create an empty cache, with a 256 MiB budget and two decoding threads.
+    pub fn new() -> Self {
+        ImageCache {
+            inner: Rc::new(RefCell::new(CacheInner {
+                entries: HashMap::new(),
+                byte_budget: DEFAULT_BYTE_BUDGET,
+                used_bytes: 0,
+                decode_threads: DEFAULT_DECODE_THREADS,
+                pool: None,
+                clock: 0,
+            })),
+        }
+    }
+
+    /// Builder-style method to set how many bytes of decoded pixels the cache keeps.
+    pub fn with_byte_budget(self, bytes: usize) -> Self {
+        self.inner.borrow_mut().byte_budget = bytes;
+        self.inner.borrow_mut().evict(None);
+        self
+    }
+
+    /// Builder-style method to set how many threads decode images.
+    ///
+    /// This bounds the CPU time spent decoding in the background. It has no effect
+    /// once the first image was requested.
+    pub fn with_decode_threads(self, threads: usize) -> Self {
+        self.inner.borrow_mut().decode_threads = threads.max(1);
+        self
+    }
+
+    /// The decoded image at `path`, if it's in the cache.
+    ///
+    /// For animated images, this is the first frame. This counts as a use of the
+    /// image for eviction purposes.
+    pub fn get(&self, path: &Path) -> Option<ImageBuf> {
+        self.get_animated(path)
+            .map(|image| image.first_frame().clone())
+    }
+
+    /// All the frames of the decoded image at `path`, if it's in the cache.
+    ///
+    /// This counts as a use of the image for eviction purposes.
+    pub fn get_animated(&self, path: &Path) -> Option<AnimatedImage> {
+        let mut inner = self.inner.borrow_mut();
+        inner.clock += 1;
+        let clock = inner.clock;
+        match inner.entries.get_mut(path)? {
+            Entry::Loaded { image, last_used } => {
+                *last_used = clock;
+                Some(image.clone())
+            }
+            Entry::Loading { .. } | Entry::Failed(_) => None,
+        }
+    }
+
+    /// Why the image at `path` couldn't be loaded, if it was requested and failed.
+    pub fn error(&self, path: &Path) -> Option<ArcStr> {
+        match self.inner.borrow().entries.get(path)? {
+            Entry::Failed(error) => Some(error.clone()),
+            _ => None,
+        }
+    }
+
+    /// Add an image decoded by the app, eg one generated in code.
+    ///
+    /// `path` only serves as a key; nothing is read from it.
+    pub fn insert(&self, path: impl Into<PathBuf>, image: impl Into<AnimatedImage>) {
+        self.inner
+            .borrow_mut()
+            .insert(path.into(), Ok(image.into()));
+    }
+
+    /// Drop the image at `path`, so the next request decodes it again.
+    pub fn remove(&self, path: &Path) {
+        let mut inner = self.inner.borrow_mut();
+        if let Some(Entry::Loaded { image, .. }) = inner.entries.remove(path) {
+            inner.used_bytes -= image.byte_size();
+        }
+    }
+
+    /// The number of bytes of decoded pixels held by the cache.
+    pub fn used_bytes(&self) -> usize {
+        self.inner.borrow().used_bytes
+    }
+
+    /// Drop every decoded image, eg when the platform is low on memory.
+    ///
+    /// Images which widgets still display are freed once the widgets drop them. Failed
+    /// images are forgotten too, so they're decoded again when next requested.
+    pub fn purge(&self) {
+        let mut inner = self.inner.borrow_mut();
+        trace!("Purging {} bytes of images", inner.used_bytes);
+        inner
+            .entries
+            .retain(|_, entry| matches!(entry, Entry::Loading { .. }));
+        inner.used_bytes = 0;
+    }
+
+    /// Decode the image at `path` in the background, unless it's already cached or
+    /// being decoded.
+    ///
+    /// `widget_id` receives [`IMAGE_LOADED`] when it's done. Returns the image if
+    /// it's already decoded.
+    pub(crate) fn request(
+        &self,
+        path: &Path,
+        widget_id: WidgetId,
+        sink: &ExtEventSink,
+    ) -> Option<AnimatedImage> {
+        if let Some(image) = self.get_animated(path) {
+            return Some(image);
+        }
+
+        let mut inner = self.inner.borrow_mut();
+        match inner.entries.get_mut(path) {
+            Some(Entry::Loading { waiters }) => {
+                if !waiters.contains(&widget_id) {
+                    waiters.push(widget_id);
+                }
+            }
+            Some(Entry::Failed(error)) => {
+                // Report the error again, so the widget still receives `IMAGE_LOADED`.
+                let job = DecodeJob {
+                    path: path.to_path_buf(),
+                    sink: sink.clone(),
+                };
+                job.finish(Err(error.clone()));
+                inner.entries.insert(
+                    path.to_path_buf(),
+                    Entry::Loading {
+                        waiters: vec![widget_id],
+                    },
+                );
+            }
+            Some(Entry::Loaded { .. }) => unreachable!(),
+            None => {
+                trace!("Decoding image {}", path.display());
+                inner.entries.insert(
+                    path.to_path_buf(),
+                    Entry::Loading {
+                        waiters: vec![widget_id],
+                    },
+                );
+                let decode_threads = inner.decode_threads;
+                let pool = inner
+                    .pool
+                    .get_or_insert_with(|| DecodePool::new(decode_threads));
+                let job = DecodeJob {
+                    path: path.to_path_buf(),
+                    sink: sink.clone(),
+                };
+                if let Err(mpsc::SendError(job)) = pool.jobs.send(job) {
+                    job.finish(Err("the image decoding threads have stopped".into()));
+                }
+            }
+        }
+        None
+    }
+
+    /// Store the result of a background decode, and return the [`IMAGE_LOADED`]
+    /// commands to send to the widgets waiting for it.
+    pub(crate) fn finish_decode(&self, decoded: &DecodedImage) -> Vec<Command> {
+        let waiters = self
+            .inner
+            .borrow_mut()
+            .insert(decoded.path.clone(), decoded.result.clone());
+        let loaded = ImageLoaded {
+            path: decoded.path.clone(),
+            error: decoded.result.as_ref().err().cloned(),
+        };
+        waiters
+            .into_iter()
+            .map(|widget_id| IMAGE_LOADED.with(loaded.clone()).to(widget_id))
+            .collect()
+    }
+}
+
+impl Default for ImageCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CacheInner {
+    /// Store an image or a decoding error, and return the widgets waiting for it.
+    fn insert(&mut self, path: PathBuf, result: Result<AnimatedImage, ArcStr>) -> Vec<WidgetId> {
+        let entry = match result {
+            Ok(image) => {
+                self.clock += 1;
+                self.used_bytes += image.byte_size();
+                Entry::Loaded {
+                    image,
+                    last_used: self.clock,
+                }
+            }
+            Err(error) => {
+                warn!("Can't load image {}: {}", path.display(), error);
+                Entry::Failed(error)
+            }
+        };
+        let waiters = match self.entries.insert(path.clone(), entry) {
+            Some(Entry::Loading { waiters }) => waiters,
+            Some(Entry::Loaded { image, .. }) => {
+                self.used_bytes -= image.byte_size();
+                Vec::new()
+            }
+            Some(Entry::Failed(_)) | None => Vec::new(),
+        };
+        self.evict(Some(&path));
+        waiters
+    }
+
+    /// Drop the least recently used images until the cache fits its budget.
+    ///
+    /// `keep` is never evicted, even if it's bigger than the whole budget.
+    fn evict(&mut self, keep: Option<&Path>) {
+        while self.used_bytes > self.byte_budget {
+            let oldest = self
+                .entries
+                .iter()
+                .filter(|(path, _)| Some(path.as_path()) != keep)
+                .filter_map(|(path, entry)| match entry {
+                    Entry::Loaded { last_used, .. } => Some((*last_used, path)),
+                    _ => None,
+                })
+                .min_by_key(|(last_used, _)| *last_used)
+                .map(|(_, path)| path.clone());
+            let Some(oldest) = oldest else {
+                break;
+            };
+            trace!("Evicting image {}", oldest.display());
+            if let Some(Entry::Loaded { image, .. }) = self.entries.remove(&oldest) {
+                self.used_bytes -= image.byte_size();
+            }
+        }
+    }
+}
+
+impl DecodeJob {
+    /// Send the result of the job back to the app, which wakes the widgets waiting
+    /// for it.
+    fn finish(self, result: Result<AnimatedImage, ArcStr>) {
+        let decoded = DecodedImage {
+            path: self.path,
+            result,
+        };
+        // The app may have exited since.
+        let _ = self
+            .sink
+            .submit_command(IMAGE_DECODED, Box::new(decoded), Target::Global);
+    }
+}
+
+impl DecodePool {
+    fn new(threads: usize) -> Self {
+        let (jobs, receiver) = mpsc::channel::<DecodeJob>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        for i in 0..threads {
+            let receiver = receiver.clone();
+            let spawned = thread::Builder::new()
+                .name(format!("masonry-image-decode-{}", i))
+                .spawn(move || loop {
+                    // The threads stop once the cache, which holds the sender, is dropped.
+                    let Ok(job) = receiver.lock().unwrap().recv() else {
+                        break;
+                    };
+                    let result = panic::catch_unwind(|| decode(&job.path))
+                        .unwrap_or_else(|_| Err("the image decoder panicked".into()));
+                    job.finish(result);
+                });
+            if let Err(err) = spawned {
+                warn!("Can't start image decoding thread: {}", err);
+            }
+        }
+        DecodePool { jobs }
+    }
+}
+
+#[cfg(feature = "image")]
+fn decode(path: &Path) -> Result<AnimatedImage, ArcStr> {
+    let data = std::fs::read(path).map_err(|err| ArcStr::from(err.to_string()))?;
+    decode_frames(&data).map_err(|err| ArcStr::from(err.to_string()))
+}
+
+#[cfg(not(feature = "image"))]
+fn decode(_path: &Path) -> Result<AnimatedImage, ArcStr> {
+    Err("decoding images requires the `image` feature".into())
+}
+
+/// Frames shorter than this are shown for [`DEFAULT_FRAME_DELAY`] instead, as
+/// browsers do; many GIFs rely on it.
+#[cfg(feature = "image")]
+const MIN_FRAME_DELAY: Duration = Duration::from_millis(20);
+#[cfg(feature = "image")]
+const DEFAULT_FRAME_DELAY: Duration = Duration::from_millis(100);
+
+#[cfg(feature = "image")]
+fn decode_frames(data: &[u8]) -> image::ImageResult<AnimatedImage> {
+    use image::codecs::gif::GifDecoder;
+    use image::codecs::png::PngDecoder;
+    use image::codecs::webp::WebPDecoder;
+    use image::{AnimationDecoder, ImageFormat};
+    use std::io::Cursor;
+
+    let frames = match image::guess_format(data)? {
+        ImageFormat::Gif => GifDecoder::new(Cursor::new(data))?
+            .into_frames()
+            .collect_frames()?,
+        ImageFormat::Png => {
+            let decoder = PngDecoder::new(Cursor::new(data))?;
+            if !decoder.is_apng() {
+                return decode_still(data);
+            }
+            decoder.apng().into_frames().collect_frames()?
+        }
+        ImageFormat::WebP => {
+            // Still images decode as a single frame, or not at all.
+            let decoder = WebPDecoder::new(Cursor::new(data))?;
+            match decoder.into_frames().collect_frames() {
+                Ok(frames) if frames.len() > 1 => frames,
+                _ => return decode_still(data),
+            }
+        }
+        _ => return decode_still(data),
+    };
+
+    let frames: Vec<_> = frames
+        .into_iter()
+        .map(|frame| {
+            let (numer, denom) = frame.delay().numer_denom_ms();
+            let delay = Duration::from_secs_f64(numer as f64 / denom.max(1) as f64 / 1000.0);
+            let delay = if delay < MIN_FRAME_DELAY {
+                DEFAULT_FRAME_DELAY
+            } else {
+                delay
+            };
+            AnimationFrame {
+                image: rgba_to_image_buf(frame.into_buffer()),
+                delay,
+            }
+        })
+        .collect();
+    if frames.is_empty() {
+        return decode_still(data);
+    }
+    Ok(AnimatedImage::new(frames))
+}
+
+#[cfg(feature = "image")]
+fn decode_still(data: &[u8]) -> image::ImageResult<AnimatedImage> {
+    let image = image::load_from_memory(data)?;
+    Ok(rgba_to_image_buf(image.into_rgba8()).into())
+}
+
+#[cfg(feature = "image")]
+fn rgba_to_image_buf(buffer: image::RgbaImage) -> ImageBuf {
+    let (width, height) = buffer.dimensions();
+    ImageBuf::from_raw(
+        buffer.into_raw(),
+        crate::piet::ImageFormat::RgbaSeparate,
+        width as usize,
+        height as usize,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ext_event::{ExtEventQueue, ExtMessage};
+    use crate::piet::ImageFormat;
+
+    fn image(width: usize, height: usize) -> ImageBuf {
+        ImageBuf::from_raw(
+            vec![0; width * height * 4],
+            ImageFormat::RgbaSeparate,
+            width,
+            height,
+        )
+    }
+
+    #[test]
+    fn least_recently_used_images_are_evicted() {
+        // Room for two 8x8 images.
+        let cache = ImageCache::new().with_byte_budget(2 * 8 * 8 * 4);
+        cache.insert("a.png", image(8, 8));
+        cache.insert("b.png", image(8, 8));
+        assert!(cache.get(Path::new("a.png")).is_some());
+
+        cache.insert("c.png", image(8, 8));
+        assert!(cache.get(Path::new("a.png")).is_some());
+        assert!(cache.get(Path::new("b.png")).is_none());
+        assert!(cache.get(Path::new("c.png")).is_some());
+        assert_eq!(cache.used_bytes(), 2 * 8 * 8 * 4);
+    }
+
+    #[test]
+    fn purge_drops_decoded_images() {
+        let cache = ImageCache::new();
+        cache.insert("a.png", image(8, 8));
+        cache.insert("b.png", image(8, 8));
+
+        cache.purge();
+        assert!(cache.get(Path::new("a.png")).is_none());
+        assert_eq!(cache.used_bytes(), 0);
+    }
+
+    #[test]
+    fn failed_images_wake_new_waiters() {
+        let mut queue = ExtEventQueue::new();
+        let cache = ImageCache::new();
+        let path = Path::new("broken.png");
+        cache
+            .inner
+            .borrow_mut()
+            .insert(path.to_path_buf(), Err("broken".into()));
+
+        let widget_id = WidgetId::next();
+        assert!(cache.request(path, widget_id, &queue.make_sink()).is_none());
+
+        let Some(ExtMessage::Command(_, payload, _)) = queue.recv() else {
+            panic!("no IMAGE_DECODED command");
+        };
+        let decoded = payload.downcast::<DecodedImage>().unwrap();
+        let loaded = cache.finish_decode(&decoded);
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].target(), Target::Widget(widget_id));
+        assert_eq!(loaded[0].get(IMAGE_LOADED).error.as_deref(), Some("broken"));
+    }
+
+    #[test]
+    fn select_dpi_variant() {
+        let source = ImageSource::new("icon.png")
+            .with_variant(2.0, "icon@2x.png")
+            .with_variant(3.0, "icon@3x.png");
+        assert_eq!(source.select(1.0), Path::new("icon.png"));
+        assert_eq!(source.select(1.5), Path::new("icon@2x.png"));
+        assert_eq!(source.select(2.0), Path::new("icon@2x.png"));
+        assert_eq!(source.select(4.0), Path::new("icon@3x.png"));
+    }
+}
@@ -0,0 +1,209 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Recent documents and jump-list tasks published to the OS.
+//!
+//! The app keeps a [`JumpList`], updated with commands like [`ADD_RECENT_DOCUMENT`].
+//! Every change is passed to the [`JumpListPublisher`] set with
+//! [`AppLauncher::with_jump_list`](crate::AppLauncher::with_jump_list). Files chosen
+//! in file dialogs are added automatically.
+//!
+//! Masonry only keeps the list and handles the launches it leads to. It doesn't ship
+//! a publisher: showing the list in the OS (eg Windows jump lists, macOS recent
+//! items) and persisting it between launches is up to the app's publisher, since
+//! druid-shell has no API for either.
+//!
+//! When the user picks an item, the OS launches the app:
+//! - recent documents are passed as a file argument, and are delivered as an
+//!   [`OPEN_REQUEST`](crate::open_request::OPEN_REQUEST);
+//! - tasks are passed as their [`launch_argument`](JumpTask::launch_argument), and are
+//!   delivered as [`JUMP_TASK_SELECTED`].
+//!
+//! If the app is already running, use
+//! [`AppLauncher::with_single_instance`](crate::AppLauncher::with_single_instance) to
+//! deliver them to the running instance instead.
+
+use std::path::PathBuf;
+
+use crate::Selector;
+
+/// Add a document to the front of the recent documents.
+pub const ADD_RECENT_DOCUMENT: Selector<PathBuf> =
+    Selector::new("masonry-builtin.add-recent-document");
+
+/// Remove all recent documents.
+pub const CLEAR_RECENT_DOCUMENTS: Selector =
+    Selector::new("masonry-builtin.clear-recent-documents");
+
+/// Replace the tasks of the jump list.
+pub const SET_JUMP_TASKS: Selector<Vec<JumpTask>> = Selector::new("masonry-builtin.set-jump-tasks");
+
+/// Submitted with [`Target::Global`](crate::Target::Global) when the app is launched
+/// from a task of the jump list. The payload is the [`JumpTask::id`].
+pub const JUMP_TASK_SELECTED: Selector<String> =
+    Selector::new("masonry-builtin.jump-task-selected");
+
+/// The command-line argument the app is launched with for a task.
+const TASK_ARG_PREFIX: &str = "--masonry-jump-task=";
+
+/// The default maximum number of recent documents.
+const DEFAULT_MAX_RECENT: usize = 10;
+
+/// An action shown in the jump list, eg "New window".
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JumpTask {
+    /// The id delivered in [`JUMP_TASK_SELECTED`].
+    pub id: String,
+    /// The label shown to the user.
+    pub title: String,
+    /// A longer description, shown as a tooltip on platforms which support it.
+    pub description: Option<String>,
+}
+
+/// The recent documents and tasks of the app.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JumpList {
+    recent_documents: Vec<PathBuf>,
+    tasks: Vec<JumpTask>,
+    max_recent: usize,
+}
+
+/// Shows a [`JumpList`] in the OS, implemented by the app.
+///
+/// Masonry has no implementation of its own, because druid-shell doesn't support
+/// jump lists. Apps can use `ICustomDestinationList` on Windows, or
+/// `NSDocumentController.noteNewRecentDocumentURL` on macOS.
+pub trait JumpListPublisher {
+    /// Show the given list, replacing the one previously published.
+    ///
+    /// Called on the main thread, each time the list changes.
+    fn publish(&mut self, jump_list: &JumpList);
+}
+
+impl JumpTask {
+    /// Create a task with the given id and title.
+    pub fn new(id: impl Into<String>, title: impl Into<String>) -> Self {
+        JumpTask {
+            id: id.into(),
+            title: title.into(),
+            description: None,
+        }
+    }
+
+    /// Builder-style method to set the description of the task.
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// The command-line argument the OS should launch the app with for this task.
+    pub fn launch_argument(&self) -> String {
+        format!("{}{}", TASK_ARG_PREFIX, self.id)
+    }
+
+    /// Return the id of the task the app was launched for, if `arg` is the
+    /// [`launch_argument`](Self::launch_argument) of a task.
+    pub fn parse_launch_argument(arg: &str) -> Option<&str> {
+        arg.strip_prefix(TASK_ARG_PREFIX)
+    }
+}
+
+impl JumpList {
+    /// Create an empty jump list.
+    pub fn new() -> Self {
+        JumpList {
+            recent_documents: Vec::new(),
+            tasks: Vec::new(),
+            max_recent: DEFAULT_MAX_RECENT,
+        }
+    }
+
+    /// Builder-style method to set the recent documents, most recent first.
+    ///
+    /// Use this to restore the list saved by the [`JumpListPublisher`].
+    pub fn with_recent_documents(mut self, recent_documents: Vec<PathBuf>) -> Self {
+        self.recent_documents = recent_documents;
+        self.recent_documents.truncate(self.max_recent);
+        self
+    }
+
+    /// Builder-style method to set the tasks.
+    pub fn with_tasks(mut self, tasks: Vec<JumpTask>) -> Self {
+        self.tasks = tasks;
+        self
+    }
+
+    /// Builder-style method to set how many recent documents are kept.
+    ///
+    /// The default is 10.
+    pub fn with_max_recent(mut self, max_recent: usize) -> Self {
+        self.max_recent = max_recent;
+        self.recent_documents.truncate(max_recent);
+        self
+    }
+
+    /// The recent documents, most recent first.
+    pub fn recent_documents(&self) -> &[PathBuf] {
+        &self.recent_documents
+    }
+
+    /// The tasks, in display order.
+    pub fn tasks(&self) -> &[JumpTask] {
+        &self.tasks
+    }
+
+    /// Move `path` to the front of the recent documents, adding it if needed.
+    pub fn add_recent(&mut self, path: PathBuf) {
+        self.recent_documents.retain(|recent| *recent != path);
+        self.recent_documents.insert(0, path);
+        self.recent_documents.truncate(self.max_recent);
+    }
+
+    /// Remove all recent documents.
+    pub fn clear_recent(&mut self) {
+        self.recent_documents.clear();
+    }
+
+    /// Replace the tasks.
+    pub fn set_tasks(&mut self, tasks: Vec<JumpTask>) {
+        self.tasks = tasks;
+    }
+}
+
+impl Default for JumpList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recent_documents_are_deduplicated_and_capped() {
+        let mut jump_list = JumpList::new().with_max_recent(2);
+        jump_list.add_recent("a.txt".into());
+        jump_list.add_recent("b.txt".into());
+        jump_list.add_recent("a.txt".into());
+        assert_eq!(
+            jump_list.recent_documents(),
+            [PathBuf::from("a.txt"), PathBuf::from("b.txt")]
+        );
+
+        jump_list.add_recent("c.txt".into());
+        assert_eq!(
+            jump_list.recent_documents(),
+            [PathBuf::from("c.txt"), PathBuf::from("a.txt")]
+        );
+    }
+
+    #[test]
+    fn task_launch_argument() {
+        let task = JumpTask::new("new-window", "New window");
+        let arg = task.launch_argument();
+        assert_eq!(JumpTask::parse_launch_argument(&arg), Some("new-window"));
+        assert_eq!(JumpTask::parse_launch_argument("notes.txt"), None);
+    }
+}
@@ -92,6 +92,9 @@
 
 // TODO - Add logo
 
+// Lets the code generated by `masonry_derive` refer to `::masonry` inside this crate.
+extern crate self as masonry;
+
 pub use druid_shell as shell;
 #[doc(inline)]
 pub use druid_shell::{kurbo, piet};
@@ -99,47 +102,98 @@ pub use druid_shell::{kurbo, piet};
 #[macro_use]
 mod util;
 
+pub mod access_keys;
 mod action;
 mod app_delegate;
 mod app_launcher;
 mod app_root;
+mod app_store;
+pub mod backdrop;
 mod bloom;
 mod box_constraints;
+mod broadcast;
+mod busy;
+pub mod clipboard;
 pub mod command;
+pub mod context_menu;
 mod contexts;
+mod damage;
 mod data;
 pub mod env;
 mod event;
 pub mod ext_event;
+mod frame_stats;
+pub mod gamepad;
+pub mod headless;
+mod hot_reload;
+mod image_cache;
+pub mod jump_list;
 mod mouse;
+pub mod open_request;
+mod pass;
+mod pen;
 mod platform;
+pub mod popup;
+pub mod print;
 pub mod promise;
+#[cfg(feature = "remote_debug")]
+pub mod remote_debug;
+mod render_backend;
+pub mod request;
+pub mod screen;
+pub mod selection;
+pub mod shortcuts;
+pub mod single_instance;
+pub mod sound;
+pub mod style;
+pub mod telemetry;
 pub mod testing;
 pub mod text;
 pub mod theme;
+mod time_travel;
+pub mod ui_state;
 pub mod widget;
 
 // TODO
 pub mod debug_logger;
+pub mod debug_switches;
 pub mod debug_values;
 
 pub use action::Action;
 pub use app_delegate::{AppDelegate, DelegateCtx};
 pub use app_launcher::AppLauncher;
 pub use app_root::{AppRoot, WindowRoot};
+pub use app_store::AppStore;
 pub use box_constraints::BoxConstraints;
+pub use busy::BusyGuardToken;
 pub use command::{Command, Notification, Selector, SingleUse, Target};
 pub use contexts::{EventCtx, LayoutCtx, LifeCycleCtx, PaintCtx, WidgetCtx};
+pub use damage::PresentStats;
 pub use data::Data;
 pub use druid_shell::Error as PlatformError;
 pub use env::{Env, Key, KeyOrValue, Value, ValueType, ValueTypeError};
-pub use event::{Event, InternalEvent, InternalLifeCycle, LifeCycle, StatusChange};
+pub use event::{
+    Event, InternalEvent, InternalLifeCycle, KeyRepeat, LifeCycle, StatusChange, TimerEvent,
+    WidgetCapture,
+};
+pub use frame_stats::{FrameStats, PassTimings};
+pub use image_cache::{
+    AnimatedImage, AnimationFrame, ImageCache, ImageLoaded, ImageSource, IMAGE_LOADED,
+};
 pub use kurbo::{Affine, Insets, Point, Rect, Size, Vec2};
 pub use mouse::MouseEvent;
+pub use pen::{PenEvent, PenEventKind, PenSample};
 pub use piet::{Color, ImageBuf, LinearGradient, RadialGradient, RenderContext, UnitPoint};
 pub use platform::{
     MasonryWinHandler, WindowConfig, WindowDescription, WindowId, WindowSizePolicy,
 };
+pub use render_backend::RenderBackend;
 pub use text::ArcStr;
-pub use util::{AsAny, Handled};
+pub use util::{invariant_policy, set_invariant_policy, AsAny, Handled, InvariantPolicy};
 pub use widget::{BackgroundBrush, Widget, WidgetId, WidgetPod, WidgetState};
+
+/// Items used by the code generated by `masonry_derive`. Not public API.
+#[doc(hidden)]
+pub mod __private {
+    pub use smallvec::SmallVec;
+}
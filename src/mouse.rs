@@ -39,6 +39,9 @@ pub struct MouseEvent {
     pub mods: Modifiers,
     /// The number of mouse clicks associated with this event. This will always
     /// be `0` for a mouse-up and mouse-move events.
+    ///
+    /// For a mouse-down, the window counts the clicks itself, using
+    /// [`DOUBLE_CLICK_INTERVAL`](crate::theme::DOUBLE_CLICK_INTERVAL).
     pub count: u8,
     /// Focus is `true` on macOS when the mouse-down event (or its companion mouse-up event)
     /// with `MouseButton::Left` was the event that caused the window to gain focus.
@@ -0,0 +1,250 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Requests to open files or URLs with the app.
+//!
+//! An app is asked to open documents through its command-line arguments, either
+//! when it's launched or when a second instance of the app is launched (see
+//! [`AppLauncher::with_single_instance`](crate::AppLauncher::with_single_instance)).
+//!
+//! Both are submitted as an [`OPEN_REQUEST`] command with [`Target::Global`], so
+//! document-based apps can handle them in one place, usually their
+//! [`AppDelegate`](crate::AppDelegate) or root widget. Command-line arguments are
+//! only forwarded if the app opts in with
+//! [`AppLauncher::with_open_requests_from_args`](crate::AppLauncher::with_open_requests_from_args).
+//!
+//! druid-shell doesn't report the other ways platforms open documents, eg macOS's
+//! `openFiles` and `openURLs` delegate methods or files dropped on the app's icon,
+//! so those aren't handled.
+
+use std::path::{Path, PathBuf};
+
+use url::Url;
+
+use crate::ext_event::{ExtEventError, ExtEventSink};
+use crate::jump_list::{JumpTask, JUMP_TASK_SELECTED};
+use crate::{Selector, Target};
+
+/// Submitted with [`Target::Global`] when the app is asked to open files or URLs.
+pub const OPEN_REQUEST: Selector<OpenRequest> = Selector::new("masonry-builtin.open-request");
+
+/// A file or URL the app is asked to open.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OpenItem {
+    /// A path in the local file system. Always absolute.
+    File(PathBuf),
+    /// A URL with a scheme other than `file`, eg a deep link into the app
+    /// (`myapp://notes/42`).
+    Url(String),
+}
+
+/// Where an [`OpenRequest`] comes from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OpenSource {
+    /// The command-line arguments the app was launched with.
+    Launch,
+    /// The arguments of another instance of the app, which was launched while this
+    /// one was running.
+    OtherInstance,
+}
+
+/// A request to open files or URLs, sent as [`OPEN_REQUEST`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OpenRequest {
+    /// The items to open, in the order they were given.
+    pub items: Vec<OpenItem>,
+    /// Where the request comes from.
+    pub source: OpenSource,
+}
+
+impl OpenItem {
+    /// Interpret a command-line argument which isn't an option.
+    ///
+    /// Strings with a URL scheme are URLs, except `file://` URLs which are converted
+    /// to paths. Other strings are paths; relative paths are resolved against `cwd`.
+    /// Returns `None` for an empty string, or a `file://` URL which isn't a local
+    /// path on this platform.
+    pub fn parse(arg: &str, cwd: Option<&Path>) -> Option<OpenItem> {
+        if arg.is_empty() {
+            return None;
+        }
+        if has_url_scheme(arg) {
+            return match Url::parse(arg) {
+                Ok(url) if url.scheme() == "file" => url.to_file_path().ok().map(OpenItem::File),
+                _ => Some(OpenItem::Url(arg.to_string())),
+            };
+        }
+        let path = Path::new(arg);
+        Some(OpenItem::File(match cwd {
+            Some(cwd) if path.is_relative() => cwd.join(path),
+            _ => path.to_path_buf(),
+        }))
+    }
+}
+
+impl OpenRequest {
+    /// Create a request to open the given items.
+    pub fn new(items: Vec<OpenItem>, source: OpenSource) -> Self {
+        OpenRequest { items, source }
+    }
+
+    /// Create a request from command-line arguments, without the program name.
+    ///
+    /// Options, ie arguments starting with `-`, are skipped, and so is the argument
+    /// following an option in `value_options`, eg the file name after `--config`.
+    /// Arguments after `--` are never options.
+    ///
+    /// Returns `None` if no argument is a file or URL.
+    pub fn from_args(
+        args: &[String],
+        cwd: Option<&Path>,
+        source: OpenSource,
+        value_options: &[String],
+    ) -> Option<Self> {
+        let mut items = Vec::new();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if arg == "--" {
+                items.extend(args.filter_map(|arg| OpenItem::parse(arg, cwd)));
+                break;
+            }
+            if arg.starts_with('-') {
+                if value_options.contains(arg) {
+                    args.next();
+                }
+                continue;
+            }
+            items.extend(OpenItem::parse(arg, cwd));
+        }
+        if items.is_empty() {
+            None
+        } else {
+            Some(OpenRequest::new(items, source))
+        }
+    }
+
+    /// Submit the request to the app, as an [`OPEN_REQUEST`] command.
+    pub fn submit(self, sink: &ExtEventSink) -> Result<(), ExtEventError> {
+        sink.submit_command(OPEN_REQUEST, Box::new(self), Target::Global)
+    }
+}
+
+/// Submit the commands for the command-line arguments of an instance of the app.
+///
+/// Jump-list tasks are submitted as [`JUMP_TASK_SELECTED`]; files and URLs are
+/// submitted as an [`OPEN_REQUEST`] if `open_requests` is set.
+pub(crate) fn submit_args(
+    args: &[String],
+    cwd: Option<&Path>,
+    source: OpenSource,
+    open_requests: bool,
+    value_options: &[String],
+    sink: &ExtEventSink,
+) -> Result<(), ExtEventError> {
+    for task_id in args
+        .iter()
+        .filter_map(|arg| JumpTask::parse_launch_argument(arg))
+    {
+        sink.submit_command(
+            JUMP_TASK_SELECTED,
+            Box::new(task_id.to_string()),
+            Target::Global,
+        )?;
+    }
+    if !open_requests {
+        return Ok(());
+    }
+    match OpenRequest::from_args(args, cwd, source, value_options) {
+        Some(request) => request.submit(sink),
+        None => Ok(()),
+    }
+}
+
+/// Whether `s` starts with a URL scheme, eg `https:`.
+///
+/// Single letters are rejected, so Windows paths like `C:\notes.txt` aren't URLs.
+fn has_url_scheme(s: &str) -> bool {
+    let Some((scheme, _)) = s.split_once(':') else {
+        return false;
+    };
+    scheme.len() > 1
+        && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_args() {
+        let cwd = Path::new("/home/user");
+        let args = to_args(&[
+            "--verbose",
+            "notes.txt",
+            "/tmp/a.txt",
+            "file:///tmp/my%20file.txt",
+            "myapp://notes/42",
+        ]);
+
+        let request = OpenRequest::from_args(&args, Some(cwd), OpenSource::Launch, &[]).unwrap();
+        assert_eq!(
+            request.items,
+            [
+                OpenItem::File("/home/user/notes.txt".into()),
+                OpenItem::File("/tmp/a.txt".into()),
+                OpenItem::File("/tmp/my file.txt".into()),
+                OpenItem::Url("myapp://notes/42".into()),
+            ]
+        );
+        assert_eq!(
+            OpenItem::parse("C:", None),
+            Some(OpenItem::File("C:".into()))
+        );
+        assert!(OpenRequest::from_args(&to_args(&["-v"]), None, OpenSource::Launch, &[]).is_none());
+    }
+
+    #[test]
+    fn option_values_are_skipped() {
+        let args = to_args(&[
+            "--config",
+            "settings.toml",
+            "--size=3",
+            "/tmp/a.txt",
+            "--",
+            "-dashed.txt",
+        ]);
+        let value_options = to_args(&["--config"]);
+
+        let request = OpenRequest::from_args(
+            &args,
+            Some(Path::new("/tmp")),
+            OpenSource::Launch,
+            &value_options,
+        )
+        .unwrap();
+        assert_eq!(
+            request.items,
+            [
+                OpenItem::File("/tmp/a.txt".into()),
+                OpenItem::File("/tmp/-dashed.txt".into()),
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn windows_file_url() {
+        assert_eq!(
+            OpenItem::parse("file:///C:/notes/my%20file.txt", None),
+            Some(OpenItem::File(r"C:\notes\my file.txt".into()))
+        );
+    }
+
+    fn to_args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+}
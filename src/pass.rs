@@ -0,0 +1,151 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! The order in which a window runs passes over its widget tree.
+//!
+//! Between frames, the window runs event passes when the platform sends input,
+//! timers or commands, each followed by the lifecycle passes needed to bring the
+//! tree up to date (eg `WidgetAdded` for new widgets, focus changes).
+//!
+//! A frame then runs, in order:
+//!
+//! - an [`AnimFrame`](Pass::AnimFrame) pass, if a widget requested one,
+//! - a [`Layout`](Pass::Layout) pass, if a widget requested one,
+//! - a [`Paint`](Pass::Paint) pass.
+//!
+//! A layout requested after the frame's layout pass, eg by a command handled
+//! once it ended, runs in the next frame.
+//!
+//! Passes never overlap: a pass can't start while another one is running, and
+//! a pass can't start before the passes it depends on have run. The widget tree
+//! can't change during the layout and paint passes: widgets can't add, remove,
+//! stash or disable children then, and should use
+//! [`mutate_later`](crate::EventCtx::mutate_later) instead. Violations are
+//! reported according to the [`InvariantPolicy`](crate::InvariantPolicy).
+
+use crate::widget::WidgetState;
+
+/// A pass over the widget tree.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Pass {
+    /// [`Event::AnimFrame`](crate::Event::AnimFrame) is sent to the tree.
+    AnimFrame,
+    /// Any other event is sent to the tree.
+    Event,
+    /// A [`LifeCycle`](crate::LifeCycle) event is sent to the tree.
+    LifeCycle,
+    /// Widgets compute their size and position their children.
+    Layout,
+    /// Widgets paint themselves.
+    Paint,
+}
+
+/// Tracks the pass a window is running, and checks that passes run in order.
+#[derive(Debug, Default)]
+pub(crate) struct PassTracker {
+    current: Option<Pass>,
+}
+
+impl PassTracker {
+    /// Record the start of `pass`, given the state of the window's root widget.
+    pub(crate) fn begin(&mut self, pass: Pass, root_state: &WidgetState) {
+        if let Some(current) = self.current {
+            debug_panic!(
+                "Pass ordering error: {:?} pass started while a {:?} pass was running. \
+                Passes can't be nested; widgets should use commands or `mutate_later` instead.",
+                pass,
+                current,
+            );
+        }
+
+        if pass == Pass::Layout && root_state.children_changed {
+            debug_panic!(
+                "Pass ordering error: layout pass started before new widgets received \
+                LifeCycle::WidgetAdded."
+            );
+        }
+
+        self.current = Some(pass);
+    }
+
+    /// The pass currently running.
+    pub(crate) fn current(&self) -> Option<Pass> {
+        self.current
+    }
+
+    /// Record the end of `pass`.
+    pub(crate) fn end(&mut self, pass: Pass) {
+        if self.current != Some(pass) {
+            debug_panic!(
+                "Pass ordering error: ending {:?} pass, but the current pass is {:?}.",
+                pass,
+                self.current,
+            );
+        }
+        self.current = None;
+    }
+}
+
+/// Check that `operation`, which changes the widget tree, is allowed in `pass`.
+pub(crate) fn check_tree_change(pass: Option<Pass>, operation: &str) {
+    if let Some(pass @ (Pass::Layout | Pass::Paint)) = pass {
+        debug_panic!(
+            "Pass ordering error: `{}` called during the {:?} pass. The widget tree can't \
+            change during layout and paint; use `mutate_later` instead.",
+            operation,
+            pass,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{widget_ids, ModularWidget, TestHarness};
+    use crate::widget::{Flex, SizedBox};
+    use crate::{Point, Size, StatusChange, WidgetId};
+
+    #[test]
+    #[should_panic(expected = "Layout pass started while a Paint pass was running")]
+    fn nested_pass() {
+        let state = WidgetState::new(WidgetId::next(), None, "<root>");
+        let mut passes = PassTracker::default();
+        passes.begin(Pass::Paint, &state);
+        passes.begin(Pass::Layout, &state);
+    }
+
+    #[test]
+    #[should_panic(expected = "layout pass started before new widgets received")]
+    fn layout_before_widget_added() {
+        let mut state = WidgetState::new(WidgetId::next(), None, "<root>");
+        state.children_changed = true;
+        PassTracker::default().begin(Pass::Layout, &state);
+    }
+
+    #[test]
+    #[should_panic(expected = "`children_changed` called during the Layout pass")]
+    fn children_changed_during_layout() {
+        let [spacer_id] = widget_ids();
+        // The widget adds a child when it stops being hot, eg because the layout
+        // moved it away from the mouse.
+        let widget = ModularWidget::new(())
+            .layout_fn(|_, _, _, _| Size::new(50.0, 50.0))
+            .status_change_fn(|_, ctx, event, _| {
+                if let StatusChange::HotChanged(false) = event {
+                    ctx.children_changed();
+                }
+            });
+        let root = Flex::column()
+            .with_child_id(SizedBox::empty().height(0.0), spacer_id)
+            .with_child(widget);
+        let mut harness = TestHarness::create_with_size(root, Size::new(100.0, 200.0));
+        harness.mouse_move(Point::new(50.0, 10.0));
+
+        harness.edit_root_widget(|mut root, _| {
+            let mut root = root.downcast::<Flex>().unwrap();
+            let mut spacer = root.child_mut(0).unwrap();
+            spacer.downcast::<SizedBox>().unwrap().set_height(100.0);
+        });
+    }
+}
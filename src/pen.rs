@@ -0,0 +1,197 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Pen and stylus input.
+//!
+//! druid-shell doesn't report pen input, so Masonry never produces
+//! [`Event::Pen`](crate::Event::Pen) itself: the pen sends mouse events instead.
+//! Platform integrations which read the pen, eg from Windows pointer messages,
+//! build [`PenEvent`]s and pass them to
+//! [`AppRoot::handle_event`](crate::AppRoot::handle_event).
+
+use druid_shell::Modifiers;
+use instant::Duration;
+
+use crate::kurbo::{Point, Vec2};
+
+/// What happened in a [`PenEvent`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PenEventKind {
+    /// The pen touched the surface.
+    Down,
+    /// The pen moved, either touching the surface or hovering above it.
+    Move,
+    /// The pen left the surface.
+    Up,
+}
+
+/// One position reported by the pen.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PenSample {
+    /// The position of the pen in the coordinate space of the receiver.
+    pub pos: Point,
+    /// How hard the pen is pressed, from 0.0 (hovering) to 1.0.
+    pub pressure: f64,
+    /// The angle between the pen and the normal of the surface, in degrees,
+    /// along the x and y axes. Both are 0.0 when the pen is upright.
+    pub tilt: Vec2,
+    /// When the sample was taken, relative to an arbitrary origin.
+    ///
+    /// Use it to compare samples, eg to compute the speed of a stroke.
+    pub time: Duration,
+}
+
+/// The state of the pen for an [`Event::Pen`](crate::Event::Pen).
+///
+/// Pens report positions much more often than the display refreshes. The
+/// platform coalesces the positions received since the previous event into
+/// a single event; drawing widgets should use every sample in
+/// [`coalesced`](Self::coalesced) to draw smooth strokes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PenEvent {
+    /// Whether the pen touched the surface, moved, or left it.
+    pub kind: PenEventKind,
+    /// The position of the pen in the coordinate space of the receiver.
+    pub pos: Point,
+    /// The position of the pen in the coordinate space of the window.
+    pub window_pos: Point,
+    /// How hard the pen is pressed, from 0.0 (hovering) to 1.0.
+    pub pressure: f64,
+    /// The tilt of the pen, in degrees; see [`PenSample::tilt`].
+    pub tilt: Vec2,
+    /// Whether the pen's eraser end is used.
+    pub is_eraser: bool,
+    /// Whether the pen's barrel button is pressed.
+    pub barrel_button: bool,
+    /// Keyboard modifiers at the time of the event.
+    pub mods: Modifiers,
+    /// The samples received since the previous pen event, oldest first.
+    ///
+    /// The last sample is the one described by the event itself. This is never
+    /// empty.
+    pub coalesced: Vec<PenSample>,
+}
+
+impl PenEvent {
+    /// Create an event from the samples received since the previous one.
+    ///
+    /// Positions are in window coordinates.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `samples` is empty.
+    pub fn new(kind: PenEventKind, samples: Vec<PenSample>) -> Self {
+        let last = *samples
+            .last()
+            .expect("a pen event needs at least one sample");
+        PenEvent {
+            kind,
+            pos: last.pos,
+            window_pos: last.pos,
+            pressure: last.pressure,
+            tilt: last.tilt,
+            is_eraser: false,
+            barrel_button: false,
+            mods: Modifiers::default(),
+            coalesced: samples,
+        }
+    }
+
+    /// Builder-style method to mark the event as coming from the eraser end of the pen.
+    pub fn with_eraser(mut self, is_eraser: bool) -> Self {
+        self.is_eraser = is_eraser;
+        self
+    }
+
+    /// Builder-style method to set whether the pen's barrel button is pressed.
+    pub fn with_barrel_button(mut self, barrel_button: bool) -> Self {
+        self.barrel_button = barrel_button;
+        self
+    }
+
+    /// Builder-style method to set the keyboard modifiers.
+    pub fn with_mods(mut self, mods: Modifiers) -> Self {
+        self.mods = mods;
+        self
+    }
+
+    /// Move the event into the coordinate space of a child at `offset`.
+    pub(crate) fn translated(&self, offset: Vec2) -> PenEvent {
+        let mut event = self.clone();
+        event.pos -= offset;
+        for sample in &mut event.coalesced {
+            sample.pos -= offset;
+        }
+        event
+    }
+}
+
+impl PenSample {
+    /// Create a sample of an upright pen at the given position.
+    pub fn new(pos: impl Into<Point>, pressure: f64) -> Self {
+        PenSample {
+            pos: pos.into(),
+            pressure,
+            tilt: Vec2::ZERO,
+            time: Duration::ZERO,
+        }
+    }
+
+    /// Builder-style method to set the tilt of the pen.
+    pub fn with_tilt(mut self, tilt: Vec2) -> Self {
+        self.tilt = tilt;
+        self
+    }
+
+    /// Builder-style method to set when the sample was taken.
+    pub fn with_time(mut self, time: Duration) -> Self {
+        self.time = time;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{Record, Recording, TestHarness, TestWidgetExt as _};
+    use crate::widget::{Flex, SizedBox};
+    use crate::Event;
+
+    #[test]
+    fn pen_samples_are_in_local_coordinates() {
+        let record = Recording::default();
+        let widget = Flex::column()
+            .with_spacer(50.0)
+            .with_child(SizedBox::empty().width(100.0).height(100.0).record(&record));
+        let mut harness = TestHarness::create(widget);
+
+        harness.pen_stroke(&[
+            PenSample::new((60.0, 60.0), 0.2),
+            PenSample::new((70.0, 65.0), 0.5).with_tilt(Vec2::new(10.0, 0.0)),
+            PenSample::new((80.0, 70.0), 0.8),
+        ]);
+
+        let pen_events: Vec<PenEvent> = record
+            .drain()
+            .into_iter()
+            .filter_map(|record| match record {
+                Record::E(Event::Pen(event)) => Some(event),
+                _ => None,
+            })
+            .collect();
+        let kinds: Vec<_> = pen_events.iter().map(|event| event.kind).collect();
+        assert_eq!(
+            kinds,
+            [PenEventKind::Down, PenEventKind::Move, PenEventKind::Up]
+        );
+
+        let stroke = &pen_events[1];
+        assert_eq!(stroke.pos, Point::new(80.0, 20.0));
+        assert_eq!(stroke.window_pos, Point::new(80.0, 70.0));
+        assert_eq!(stroke.pressure, 0.8);
+        let positions: Vec<_> = stroke.coalesced.iter().map(|sample| sample.pos).collect();
+        assert_eq!(positions, [Point::new(70.0, 15.0), Point::new(80.0, 20.0)]);
+        assert_eq!(stroke.coalesced[0].tilt, Vec2::new(10.0, 0.0));
+    }
+}
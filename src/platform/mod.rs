@@ -2,6 +2,8 @@
 // "as-is" basis without warranties of any kind. See the LICENSE file for
 // details.
 
+#[cfg(not(tarpaulin_include))]
+pub(crate) mod settings;
 #[cfg(not(tarpaulin_include))]
 mod win_handler;
 #[cfg(not(tarpaulin_include))]
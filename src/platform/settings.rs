@@ -0,0 +1,144 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! The accessibility settings of the platform, which druid-shell doesn't report.
+
+/// Whether the user asked the platform for high-contrast colors.
+///
+/// Returns `None` where the setting can't be read: on GTK, before it's initialized,
+/// and on platforms other than Windows, macOS and GTK.
+pub(crate) fn high_contrast() -> Option<bool> {
+    imp::high_contrast()
+}
+
+/// Whether the user asked the platform to reduce motion.
+///
+/// Returns `None` where the setting can't be read, as [`high_contrast`].
+pub(crate) fn prefers_reduced_motion() -> Option<bool> {
+    imp::prefers_reduced_motion()
+}
+
+#[cfg(target_os = "windows")]
+mod imp {
+    use winapi::shared::minwindef::{BOOL, FALSE};
+    use winapi::um::winuser::{
+        SystemParametersInfoW, HCF_HIGHCONTRASTON, HIGHCONTRASTW, SPI_GETCLIENTAREAANIMATION,
+        SPI_GETHIGHCONTRAST,
+    };
+
+    pub(super) fn high_contrast() -> Option<bool> {
+        let mut info = HIGHCONTRASTW {
+            cbSize: std::mem::size_of::<HIGHCONTRASTW>() as u32,
+            dwFlags: 0,
+            lpszDefaultScheme: std::ptr::null_mut(),
+        };
+        // SAFETY: `info` is a HIGHCONTRASTW whose size is passed as `uiParam`, as
+        // SPI_GETHIGHCONTRAST requires.
+        let ok = unsafe {
+            SystemParametersInfoW(
+                SPI_GETHIGHCONTRAST,
+                info.cbSize,
+                &mut info as *mut HIGHCONTRASTW as *mut _,
+                0,
+            )
+        };
+        (ok != 0).then_some(info.dwFlags & HCF_HIGHCONTRASTON != 0)
+    }
+
+    pub(super) fn prefers_reduced_motion() -> Option<bool> {
+        let mut animations: BOOL = FALSE;
+        // SAFETY: SPI_GETCLIENTAREAANIMATION writes a BOOL to `pvParam`.
+        let ok = unsafe {
+            SystemParametersInfoW(
+                SPI_GETCLIENTAREAANIMATION,
+                0,
+                &mut animations as *mut BOOL as *mut _,
+                0,
+            )
+        };
+        (ok != 0).then_some(animations == FALSE)
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod imp {
+    use objc::runtime::{Object, BOOL, NO};
+    use objc::{class, msg_send, sel, sel_impl};
+
+    pub(super) fn high_contrast() -> Option<bool> {
+        // SAFETY: NSWorkspace is part of AppKit, which druid-shell links, and both
+        // messages take no arguments.
+        let increase_contrast: BOOL = unsafe {
+            let workspace: *mut Object = msg_send![class!(NSWorkspace), sharedWorkspace];
+            msg_send![workspace, accessibilityDisplayShouldIncreaseContrast]
+        };
+        Some(increase_contrast != NO)
+    }
+
+    pub(super) fn prefers_reduced_motion() -> Option<bool> {
+        // SAFETY: see `high_contrast`.
+        let reduce_motion: BOOL = unsafe {
+            let workspace: *mut Object = msg_send![class!(NSWorkspace), sharedWorkspace];
+            msg_send![workspace, accessibilityDisplayShouldReduceMotion]
+        };
+        Some(reduce_motion != NO)
+    }
+}
+
+#[cfg(all(
+    feature = "gtk",
+    any(
+        target_os = "linux",
+        target_os = "freebsd",
+        target_os = "dragonfly",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    )
+))]
+mod imp {
+    use gtk::prelude::*;
+
+    /// The settings of the default screen, if GTK is initialized on this thread.
+    fn settings() -> Option<gtk::Settings> {
+        if !gtk::is_initialized_main_thread() {
+            return None;
+        }
+        gtk::Settings::default()
+    }
+
+    pub(super) fn high_contrast() -> Option<bool> {
+        // GNOME switches to the HighContrast theme, or HighContrastInverse, when
+        // high contrast is enabled.
+        let theme = settings()?.gtk_theme_name()?;
+        Some(theme.starts_with("HighContrast"))
+    }
+
+    pub(super) fn prefers_reduced_motion() -> Option<bool> {
+        Some(!settings()?.is_gtk_enable_animations())
+    }
+}
+
+#[cfg(not(any(
+    target_os = "windows",
+    target_os = "macos",
+    all(
+        feature = "gtk",
+        any(
+            target_os = "linux",
+            target_os = "freebsd",
+            target_os = "dragonfly",
+            target_os = "netbsd",
+            target_os = "openbsd"
+        )
+    )
+)))]
+mod imp {
+    pub(super) fn high_contrast() -> Option<bool> {
+        None
+    }
+
+    pub(super) fn prefers_reduced_motion() -> Option<bool> {
+        None
+    }
+}
@@ -11,11 +11,12 @@ use druid_shell::{
     AppHandler, FileDialogToken, FileInfo, IdleToken, KeyEvent, MouseEvent, Region, Scale,
     TextFieldToken, TimerToken, WinHandler, WindowHandle,
 };
+use instant::Instant;
 
 use crate::app_root::AppRoot;
 use crate::kurbo::Size;
 use crate::piet::Piet;
-use crate::{command as sys_cmd, Event, InternalEvent, Selector, WindowId};
+use crate::{command as sys_cmd, Event, InternalEvent, Selector, TimerEvent, WindowId};
 
 pub(crate) const RUN_COMMANDS_TOKEN: IdleToken = IdleToken::new(1);
 
@@ -104,9 +105,18 @@ impl WinHandler for MasonryWinHandler {
     }
 
     fn got_focus(&mut self) {
+        self.app_state
+            .update_window_visibility(self.window_id, None);
         self.app_state.window_got_focus(self.window_id);
     }
 
+    fn lost_focus(&mut self) {
+        self.app_state.window_lost_focus(self.window_id);
+        // Minimizing a window takes its focus away, but may not resize it.
+        self.app_state
+            .update_window_visibility(self.window_id, None);
+    }
+
     fn prepare_paint(&mut self) {
         self.app_state.prepare_paint(self.window_id);
     }
@@ -116,6 +126,8 @@ impl WinHandler for MasonryWinHandler {
     }
 
     fn size(&mut self, size: Size) {
+        self.app_state
+            .update_window_visibility(self.window_id, Some(size));
         let event = Event::WindowSize(size);
         self.app_state.handle_event(event, self.window_id);
     }
@@ -148,11 +160,15 @@ impl WinHandler for MasonryWinHandler {
     }
 
     fn mouse_move(&mut self, event: &MouseEvent) {
+        self.app_state
+            .update_hovered_titlebar_zone(self.window_id, Some(event.pos));
         let event = Event::MouseMove(event.clone().into());
         self.app_state.handle_event(event, self.window_id);
     }
 
     fn mouse_leave(&mut self) {
+        self.app_state
+            .update_hovered_titlebar_zone(self.window_id, None);
         self.app_state
             .handle_event(Event::Internal(InternalEvent::MouseLeave), self.window_id);
     }
@@ -179,8 +195,15 @@ impl WinHandler for MasonryWinHandler {
     }
 
     fn timer(&mut self, token: TimerToken) {
+        // The deadline is filled in by the window, which knows when the timer was requested.
+        let now = Instant::now();
+        let timer = TimerEvent {
+            token,
+            deadline: now,
+            fired_at: now,
+        };
         self.app_state
-            .handle_event(Event::Timer(token), self.window_id);
+            .handle_event(Event::Timer(timer), self.window_id);
     }
 
     fn idle(&mut self, token: IdleToken) {
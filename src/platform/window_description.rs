@@ -4,8 +4,13 @@
 
 use druid_shell::{Counter, WindowBuilder, WindowHandle, WindowLevel, WindowState};
 
-use crate::kurbo::{Point, Size};
-use crate::{ArcStr, Widget};
+use crate::backdrop::WindowBackdrop;
+use crate::kurbo::{Point, Rect, Size};
+use crate::popup::PopupAnchor;
+use crate::screen;
+use crate::shortcuts::Shortcuts;
+use crate::ui_state::WindowGeometry;
+use crate::{ArcStr, RenderBackend, Widget, WidgetPod};
 
 /// A unique identifier for a window.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
@@ -23,9 +28,13 @@ pub struct WindowId(u64);
 ///     .window_size((400.0, 400.0));
 /// ```
 pub struct WindowDescription {
-    pub(crate) root: Box<dyn Widget>,
+    pub(crate) root: WidgetPod<Box<dyn Widget>>,
     pub(crate) title: ArcStr,
     pub(crate) config: WindowConfig,
+    pub(crate) command_broadcast_opt_in: bool,
+    pub(crate) integer_layout: bool,
+    pub(crate) shortcuts: Shortcuts,
+    pub(crate) popup_anchor: Option<PopupAnchor>,
     /// The `WindowId` that will be assigned to this window.
     ///
     /// This can be used to track a window from when it is launched to when
@@ -57,10 +66,12 @@ pub struct WindowConfig {
     pub(crate) position: Option<Point>,
     pub(crate) resizable: Option<bool>,
     pub(crate) transparent: Option<bool>,
+    pub(crate) backdrop: Option<WindowBackdrop>,
     pub(crate) show_titlebar: Option<bool>,
     pub(crate) level: Option<WindowLevel>,
     // TODO - Remove?
     pub(crate) state: Option<WindowState>,
+    pub(crate) render_backend: Option<RenderBackend>,
 }
 
 // ---
@@ -79,11 +90,25 @@ impl WindowDescription {
     where
         W: Widget + 'static,
     {
+        Self::from_pod(WidgetPod::new(root).boxed())
+    }
+
+    /// Create a new `WindowDescription` for a root widget taken from a container
+    /// with [`WidgetPod::take`], eg a panel torn out of a
+    /// [`DockArea`](crate::widget::DockArea).
+    ///
+    /// The widget keeps its id and state, and doesn't receive
+    /// [`LifeCycle::WidgetAdded`](crate::LifeCycle::WidgetAdded) again.
+    pub fn from_pod(root: WidgetPod<Box<dyn Widget>>) -> WindowDescription {
         WindowDescription {
-            root: Box::new(root),
+            root,
             // FIXME - add argument instead
             title: "Masonry application".into(),
             config: WindowConfig::default(),
+            command_broadcast_opt_in: false,
+            integer_layout: false,
+            shortcuts: Shortcuts::default(),
+            popup_anchor: None,
             id: WindowId::next(),
         }
     }
@@ -158,6 +183,15 @@ impl WindowDescription {
         self
     }
 
+    /// Set the material shown through the window's
+    /// [`BackdropRegion`](crate::widget::BackdropRegion) widgets, eg a blur.
+    ///
+    /// This makes the window transparent. See the [`backdrop`](crate::backdrop) module.
+    pub fn backdrop(mut self, backdrop: WindowBackdrop) -> Self {
+        self.config = self.config.backdrop(backdrop);
+        self
+    }
+
     /// Set the initial window position in [display points](druid_shell::Scale), relative to the origin
     /// of the [virtual screen](druid_shell::Screen).
     pub fn set_position(mut self, position: impl Into<Point>) -> Self {
@@ -165,6 +199,24 @@ impl WindowDescription {
         self
     }
 
+    /// Set the initial window position next to `anchor`, a rect on the
+    /// [virtual screen](druid_shell::Screen), eg the screen rect of a button.
+    ///
+    /// The window is placed below `anchor`, or above it if there's more room there,
+    /// and kept inside the work area of the monitor showing `anchor`. If the window's
+    /// size isn't set, its minimum size is used to keep it inside, so set the size
+    /// first.
+    pub fn place_near(mut self, anchor: Rect) -> Self {
+        let size = self
+            .config
+            .size
+            .or(self.config.min_size)
+            .unwrap_or(Size::ZERO);
+        let position = screen::place_near(anchor, size, &screen::work_areas());
+        self.config = self.config.set_position(position);
+        self
+    }
+
     /// Set the [`WindowLevel`] of the window.
     pub fn set_level(mut self, level: WindowLevel) -> Self {
         self.config = self.config.set_level(level);
@@ -177,6 +229,59 @@ impl WindowDescription {
         self
     }
 
+    /// Set the initial position, size and state of the window, eg to restore the
+    /// geometry saved in a [`UiState`](crate::ui_state::UiState).
+    pub fn with_geometry(mut self, geometry: WindowGeometry) -> Self {
+        self.config = self
+            .config
+            .set_position(geometry.position)
+            .window_size(geometry.size)
+            .set_window_state(geometry.state);
+        self
+    }
+
+    /// Set how the window's content is rendered.
+    ///
+    /// By default, this is picked with [`RenderBackend::from_env`].
+    pub fn render_backend(mut self, render_backend: RenderBackend) -> Self {
+        self.config = self.config.render_backend(render_backend);
+        self
+    }
+
+    /// Only deliver commands sent to the window to the widgets which registered for them.
+    ///
+    /// By default, commands sent to a window or to [`Target::Global`](crate::Target::Global)
+    /// are broadcast to every widget in the window. With this option, they're only
+    /// delivered to the widgets which called
+    /// [`LifeCycleCtx::register_for_commands`](crate::LifeCycleCtx::register_for_commands)
+    /// or [`LifeCycleCtx::register_command_handler`](crate::LifeCycleCtx::register_command_handler),
+    /// so large widget trees aren't fully traversed for every command.
+    pub fn command_broadcast_opt_in(mut self, opt_in: bool) -> Self {
+        self.command_broadcast_opt_in = opt_in;
+        self
+    }
+
+    /// Compute the layout of the window in physical pixels.
+    ///
+    /// By default, constraints are rounded to display points, so at fractional scale
+    /// factors (eg 150%), widgets can land between physical pixels, and deep trees
+    /// accumulate fractional positions. In integer layout mode, the constraints passed
+    /// to each widget, the sizes they return, and the positions of children are
+    /// rounded to the nearest physical pixel. Widgets still work in display points.
+    pub fn integer_layout(mut self, integer_layout: bool) -> Self {
+        self.integer_layout = integer_layout;
+        self
+    }
+
+    /// Set the keyboard [`Shortcuts`] of the window.
+    ///
+    /// Shortcuts are matched before key presses are sent to the focused widget.
+    /// See the [`shortcuts`](crate::shortcuts) module.
+    pub fn shortcuts(mut self, shortcuts: Shortcuts) -> Self {
+        self.shortcuts = shortcuts;
+        self
+    }
+
     /// Set the [`WindowConfig`] of the window.
     pub fn with_config(mut self, config: WindowConfig) -> Self {
         self.config = config;
@@ -273,6 +378,31 @@ impl WindowConfig {
         self
     }
 
+    /// Set the material shown through the window's backdrop regions.
+    ///
+    /// Unless the window's transparency is set explicitly, a backdrop other than
+    /// [`WindowBackdrop::None`] makes it transparent. This only applies when the window
+    /// is created; use [`SET_WINDOW_BACKDROP`](crate::command::SET_WINDOW_BACKDROP) to
+    /// change the backdrop of an open window.
+    pub fn backdrop(mut self, backdrop: WindowBackdrop) -> Self {
+        self.backdrop = Some(backdrop);
+        self
+    }
+
+    /// Whether the window's surface is created transparent.
+    pub(crate) fn is_transparent(&self) -> bool {
+        self.transparent.unwrap_or_else(|| {
+            self.backdrop
+                .map_or(false, |backdrop| backdrop != WindowBackdrop::None)
+        })
+    }
+
+    /// Set how the window's content is rendered.
+    pub fn render_backend(mut self, render_backend: RenderBackend) -> Self {
+        self.render_backend = Some(render_backend);
+        self
+    }
+
     /// Apply this window configuration to the given WindowBuilder
     pub fn apply_to_builder(&self, builder: &mut WindowBuilder) {
         if let Some(resizable) = self.resizable {
@@ -293,8 +423,8 @@ impl WindowConfig {
             builder.set_position(position);
         }
 
-        if let Some(transparent) = self.transparent {
-            builder.set_transparent(transparent);
+        if self.transparent.is_some() || self.backdrop.is_some() {
+            builder.set_transparent(self.is_transparent());
         }
 
         if let Some(level) = &self.level {
@@ -349,6 +479,7 @@ impl std::fmt::Debug for WindowConfig {
             .field("position", &self.position)
             .field("resizable", &self.resizable)
             .field("transparent", &self.transparent)
+            .field("backdrop", &self.backdrop)
             .field("show_titlebar", &self.show_titlebar)
             .field(
                 "level",
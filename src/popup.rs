@@ -0,0 +1,188 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Popup windows anchored to a widget of another window.
+//!
+//! Menus and pickers which don't fit in their window, eg a long dropdown near the
+//! bottom of a small window, can be shown in a popup window opened with
+//! [`EventCtx::open_popup`](crate::EventCtx::open_popup). The popup is placed next to
+//! its anchor widget, inside the work area of the anchor's monitor (see
+//! [`screen`](crate::screen)), and:
+//!
+//! - follows the anchor when it moves, or when the parent window is resized or moved;
+//! - closes when the anchor is removed or stashed, or when the parent window closes.
+//!
+//! druid-shell doesn't report window moves, so after the parent window is moved, the
+//! popup catches up on the next event or frame.
+
+use crate::app_root::WindowRoot;
+use crate::kurbo::{Point, Rect};
+use crate::widget::WidgetRef;
+use crate::{Widget, WidgetId, WindowId};
+
+/// The widget a popup window is anchored to.
+#[derive(Clone, Debug)]
+pub(crate) struct PopupAnchor {
+    pub(crate) window: WindowId,
+    pub(crate) widget: WidgetId,
+    /// The rect the popup is placed next to, in the widget's coordinate space.
+    pub(crate) rect: Rect,
+    /// The screen rect of the anchor when the popup was last placed.
+    pub(crate) placed_at: Option<Rect>,
+}
+
+impl PopupAnchor {
+    pub(crate) fn new(window: WindowId, widget: WidgetId, rect: Rect) -> Self {
+        PopupAnchor {
+            window,
+            widget,
+            rect,
+            placed_at: None,
+        }
+    }
+
+    /// The rect of the anchor on the virtual screen, or `None` if the anchor widget
+    /// is gone or stashed.
+    ///
+    /// This uses the window origin of the anchor cached by the parent window.
+    pub(crate) fn screen_rect(&self, parent: &WindowRoot) -> Option<Rect> {
+        let origin = parent.popup_anchor_origin(self.widget)?;
+        let origin = parent.content_origin() + origin.to_vec2();
+        Some(self.rect + origin.to_vec2())
+    }
+
+    /// What to do with the popup, given its parent window if it's still open.
+    pub(crate) fn update(&self, parent: Option<&WindowRoot>) -> PopupUpdate {
+        match parent.and_then(|parent| self.screen_rect(parent)) {
+            Some(rect) if self.placed_at != Some(rect) => PopupUpdate::Move(rect),
+            Some(_) => PopupUpdate::Keep,
+            None => PopupUpdate::Close,
+        }
+    }
+}
+
+/// How a popup follows its anchor.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum PopupUpdate {
+    /// The anchor didn't move.
+    Keep,
+    /// The anchor moved to this screen rect.
+    Move(Rect),
+    /// The anchor is gone or stashed, or the parent window closed.
+    Close,
+}
+
+/// The window origin of the widget `id` under `root`, or `None` if it's not in the
+/// tree, or it or one of its ancestors is stashed.
+pub(crate) fn visible_window_origin(
+    root: WidgetRef<'_, dyn Widget>,
+    id: WidgetId,
+) -> Option<Point> {
+    let state = root.state();
+    if state.is_stashed {
+        return None;
+    }
+    if state.id == id {
+        return Some(state.window_origin());
+    }
+    root.children()
+        .into_iter()
+        .find_map(|child| visible_window_origin(child, id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kurbo::{Size, Vec2};
+    use crate::testing::{widget_ids, TestHarness};
+    use crate::widget::{DockArea, Flex, SizedBox};
+
+    fn anchor_box() -> SizedBox {
+        SizedBox::empty().width(40.0).height(20.0)
+    }
+
+    /// Place a popup anchored to `anchor_id`, as the app does when it opens.
+    fn place_popup(harness: &mut TestHarness, anchor_id: WidgetId) -> PopupAnchor {
+        harness.window_mut().track_popup_anchor(anchor_id);
+        let mut anchor = PopupAnchor::new(WindowId::next(), anchor_id, anchor_box_rect());
+        let PopupUpdate::Move(rect) = anchor.update(Some(harness.window())) else {
+            panic!("the popup wasn't placed");
+        };
+        anchor.placed_at = Some(rect);
+        assert_eq!(anchor.update(Some(harness.window())), PopupUpdate::Keep);
+        anchor
+    }
+
+    fn anchor_box_rect() -> Rect {
+        Rect::new(0.0, 0.0, 40.0, 20.0)
+    }
+
+    #[test]
+    fn popup_follows_moved_anchor() {
+        let [anchor_id] = widget_ids();
+        let root = Flex::column()
+            .with_child(SizedBox::empty().height(10.0))
+            .with_child_id(anchor_box(), anchor_id);
+        let mut harness = TestHarness::create_with_size(root, Size::new(200.0, 200.0));
+        let anchor = place_popup(&mut harness, anchor_id);
+
+        harness.edit_root_widget(|mut root, _| {
+            let mut root = root.downcast::<Flex>().unwrap();
+            let mut spacer = root.child_mut(0).unwrap();
+            spacer.downcast::<SizedBox>().unwrap().set_height(50.0);
+        });
+
+        let moved = anchor.placed_at.unwrap() + Vec2::new(0.0, 40.0);
+        assert_eq!(
+            anchor.update(Some(harness.window())),
+            PopupUpdate::Move(moved)
+        );
+    }
+
+    #[test]
+    fn popup_closes_when_anchor_is_removed() {
+        let [anchor_id] = widget_ids();
+        let root = Flex::column().with_child_id(anchor_box(), anchor_id);
+        let mut harness = TestHarness::create_with_size(root, Size::new(200.0, 200.0));
+        let anchor = place_popup(&mut harness, anchor_id);
+
+        harness.edit_root_widget(|mut root, _| {
+            root.downcast::<Flex>().unwrap().remove_child(0);
+        });
+
+        assert_eq!(anchor.update(Some(harness.window())), PopupUpdate::Close);
+    }
+
+    #[test]
+    fn popup_closes_when_anchor_is_stashed() {
+        let [anchor_id, other_tab] = widget_ids();
+        // The anchor is inside a tab, which is stashed when another tab is selected.
+        let dock = DockArea::new()
+            .with_panel(
+                "Anchor",
+                Flex::column().with_child_id(anchor_box(), anchor_id),
+            )
+            .with_panel_id("Other", SizedBox::empty(), other_tab);
+        let mut harness = TestHarness::create_with_size(dock, Size::new(200.0, 200.0));
+        let anchor = place_popup(&mut harness, anchor_id);
+
+        harness.edit_root_widget(|mut dock, _| {
+            dock.downcast::<DockArea>().unwrap().select_panel(other_tab);
+        });
+
+        assert_eq!(anchor.update(Some(harness.window())), PopupUpdate::Close);
+    }
+
+    #[test]
+    fn popup_closes_with_parent_window() {
+        let [anchor_id] = widget_ids();
+        let mut harness = TestHarness::create_with_size(
+            Flex::column().with_child_id(anchor_box(), anchor_id),
+            Size::new(200.0, 200.0),
+        );
+        let anchor = place_popup(&mut harness, anchor_id);
+
+        assert_eq!(anchor.update(None), PopupUpdate::Close);
+    }
+}
@@ -47,6 +47,10 @@ impl<T: Any + Send> PromiseToken<T> {
         Self::new()
     }
 
+    pub(crate) fn id(&self) -> PromiseTokenId {
+        self.0
+    }
+
     pub fn make_result(&self, payload: T) -> PromiseResult {
         PromiseResult {
             token_id: self.0,
@@ -56,6 +60,10 @@ impl<T: Any + Send> PromiseToken<T> {
 }
 
 impl PromiseResult {
+    pub(crate) fn token_id(&self) -> PromiseTokenId {
+        self.token_id
+    }
+
     pub(crate) fn get_payload(&self) -> Box<dyn Any + Send> {
         self.payload
             .lock()
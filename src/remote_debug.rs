@@ -0,0 +1,247 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! A debug server mirroring the app to an external inspector.
+//!
+//! Enabled with the `remote_debug` feature, and started with
+//! [`AppLauncher::with_remote_debugger`](crate::AppLauncher::with_remote_debugger).
+//!
+//! The server accepts one TCP connection at a time. The inspector sends
+//! [`RemoteInput`] messages, encoded as one JSON object per line, eg:
+//!
+//! ```text
+//! {"type":"snapshot"}
+//! {"type":"mouse_down","x":120.0,"y":40.0,"button":"left"}
+//! {"type":"text","text":"hello"}
+//! ```
+//!
+//! Input messages are injected in the main window as if they came from the
+//! platform. In response to a `snapshot` message, the app sends a
+//! [`RemoteMessage::Scene`] line describing the widget tree, then a
+//! [`RemoteMessage::Frame`] line followed by the RGBA pixels of the window.
+//!
+//! With [`DebugSwitch::FrameHistory`](crate::debug_switches::DebugSwitch::FrameHistory)
+//! enabled, a `scrub` message steps back through the last painted frames, eg
+//! `{"type":"scrub","frames_back":3}`. Snapshots then describe that frame, until a
+//! `scrub` message without `frames_back` goes back to the live widgets.
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::mpsc::{self, Sender};
+
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::ext_event::ExtEventSink;
+use crate::headless::SceneNode;
+use crate::piet::ImageBuf;
+use crate::{Selector, Target};
+
+/// Sent by the server thread for each message received from the inspector.
+pub(crate) const REMOTE_INPUT: Selector<RemoteRequest> =
+    Selector::new("masonry-builtin.remote-input");
+
+/// A message sent by the inspector.
+///
+/// Positions are in window coordinates.
+#[allow(missing_docs)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RemoteInput {
+    /// Ask for the widget tree and an image of the main window.
+    Snapshot,
+    /// Move the mouse to the given position.
+    MouseMove { x: f64, y: f64 },
+    /// Press a mouse button at the given position.
+    MouseDown {
+        x: f64,
+        y: f64,
+        button: RemoteButton,
+    },
+    /// Release a mouse button at the given position.
+    MouseUp {
+        x: f64,
+        y: f64,
+        button: RemoteButton,
+    },
+    /// Scroll the mouse wheel at the given position.
+    Wheel { x: f64, y: f64, dx: f64, dy: f64 },
+    /// Type the given text in the focused widget, one key press per character.
+    Text { text: String },
+    /// Show an earlier frame instead of the widgets, as with
+    /// [`SCRUB_FRAME`](crate::command::SCRUB_FRAME).
+    Scrub {
+        #[serde(default)]
+        frames_back: Option<usize>,
+    },
+}
+
+/// A mouse button, in a [`RemoteInput`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RemoteButton {
+    /// The primary button.
+    Left,
+    /// The secondary button.
+    Right,
+    /// The wheel button.
+    Middle,
+}
+
+/// A message sent to the inspector.
+#[allow(missing_docs)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RemoteMessage {
+    /// The widget tree of the main window, as laid out.
+    Scene { scene: SceneNode },
+    /// An image of the main window. The line is followed by `len` bytes of
+    /// RGBA pixels, row by row.
+    Frame {
+        width: usize,
+        height: usize,
+        len: usize,
+    },
+}
+
+/// An input message, and where to send the reply.
+pub(crate) struct RemoteRequest {
+    pub(crate) input: RemoteInput,
+    pub(crate) reply: Sender<Vec<u8>>,
+}
+
+/// Encode a snapshot of the app, as sent in reply to [`RemoteInput::Snapshot`].
+pub(crate) fn encode_snapshot(scene: SceneNode, frame: Option<&ImageBuf>) -> Vec<u8> {
+    let mut bytes = encode_message(&RemoteMessage::Scene { scene });
+    if let Some(frame) = frame {
+        let pixels = frame.raw_pixels();
+        bytes.extend(encode_message(&RemoteMessage::Frame {
+            width: frame.width(),
+            height: frame.height(),
+            len: pixels.len(),
+        }));
+        bytes.extend_from_slice(pixels);
+    }
+    bytes
+}
+
+fn encode_message(message: &RemoteMessage) -> Vec<u8> {
+    let mut bytes = serde_json::to_vec(message).unwrap();
+    bytes.push(b'\n');
+    bytes
+}
+
+/// Start a thread listening for inspector connections on `addr`.
+///
+/// The thread runs until the app exits.
+pub(crate) fn start_server(addr: SocketAddr, sink: ExtEventSink) {
+    let spawn_result = std::thread::Builder::new()
+        .name("masonry-remote-debug".into())
+        .spawn(move || {
+            let listener = match TcpListener::bind(addr) {
+                Ok(listener) => listener,
+                Err(err) => {
+                    warn!("Can't start remote debug server on {}: {}", addr, err);
+                    return;
+                }
+            };
+            info!("Remote debug server listening on {}", addr);
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => {
+                        if serve_inspector(stream, &sink).is_err() {
+                            // The app is gone.
+                            return;
+                        }
+                    }
+                    Err(err) => warn!("Remote debug connection failed: {}", err),
+                }
+            }
+        });
+    if let Err(err) = spawn_result {
+        warn!("Can't start remote debug server: {}", err);
+    }
+}
+
+/// Forward the messages of one inspector to the app until it disconnects.
+///
+/// Returns an error if the app can no longer receive messages.
+fn serve_inspector(stream: TcpStream, sink: &ExtEventSink) -> Result<(), ()> {
+    let peer = stream.peer_addr().ok();
+    info!("Remote inspector connected from {:?}", peer);
+
+    let (reply, replies) = mpsc::channel::<Vec<u8>>();
+    let mut writer = match stream.try_clone() {
+        Ok(writer) => writer,
+        Err(err) => {
+            warn!("Remote debug connection failed: {}", err);
+            return Ok(());
+        }
+    };
+    std::thread::spawn(move || {
+        for bytes in replies {
+            if writer.write_all(&bytes).is_err() {
+                return;
+            }
+        }
+    });
+
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let input = match serde_json::from_str::<RemoteInput>(&line) {
+            Ok(input) => input,
+            Err(err) => {
+                warn!("Invalid remote debug message '{}': {}", line, err);
+                continue;
+            }
+        };
+        let request = RemoteRequest {
+            input,
+            reply: reply.clone(),
+        };
+        sink.submit_command(REMOTE_INPUT, Box::new(request), Target::Global)
+            .map_err(|_| ())?;
+    }
+    info!("Remote inspector {:?} disconnected", peer);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_inputs_and_encode_snapshot() {
+        let input: RemoteInput =
+            serde_json::from_str(r#"{"type":"mouse_down","x":1.0,"y":2.0,"button":"left"}"#)
+                .unwrap();
+        assert_eq!(
+            input,
+            RemoteInput::MouseDown {
+                x: 1.0,
+                y: 2.0,
+                button: RemoteButton::Left
+            }
+        );
+
+        let scene = SceneNode {
+            widget: "Label".into(),
+            id: 1,
+            origin: (0.0, 0.0),
+            size: (10.0, 5.0),
+            text: Some("Hi".into()),
+            children: Vec::new(),
+            memory: Some(1024),
+        };
+        let bytes = encode_snapshot(scene.clone(), None);
+        let message: RemoteMessage = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(message, RemoteMessage::Scene { scene });
+        assert_eq!(bytes.last(), Some(&b'\n'));
+    }
+}
@@ -0,0 +1,214 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Choosing how a window's content is rendered.
+
+use druid_shell::Region;
+use tracing::{error, warn};
+
+use crate::piet::{Device, Error, ImageBuf, ImageFormat, InterpolationMode, Piet, RenderContext};
+use crate::{Rect, Size};
+
+const ENV_VAR: &str = "MASONRY_RENDERER";
+
+/// How a window's content is rendered.
+///
+/// See [`WindowDescription::render_backend`](crate::WindowDescription::render_backend).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RenderBackend {
+    /// Paint directly with the render context provided by the platform, which is
+    /// usually hardware accelerated.
+    #[default]
+    Platform,
+    /// Paint into a bitmap on the CPU, then copy the damaged regions to the window.
+    ///
+    /// Widgets paint into a bitmap from a [`piet::Device`](crate::piet::Device), not
+    /// into the platform's render context, which is only used to copy the finished
+    /// frame to the window. This is slower, but works where hardware acceleration
+    /// doesn't, eg in some VMs and CI runners. It's also the path [`TestHarness`](crate::testing::TestHarness)
+    /// uses to render screenshots.
+    Software,
+}
+
+impl RenderBackend {
+    /// The backend selected by the `MASONRY_RENDERER` environment variable.
+    ///
+    /// `MASONRY_RENDERER=software` selects [`Software`](Self::Software); any other
+    /// value, or no value, selects [`Platform`](Self::Platform).
+    ///
+    /// This is the backend used by windows which don't set one explicitly.
+    pub fn from_env() -> Self {
+        match std::env::var(ENV_VAR).as_deref() {
+            Ok("software") => RenderBackend::Software,
+            Ok("platform") | Err(_) => RenderBackend::Platform,
+            Ok(other) => {
+                warn!("{}: unknown renderer '{}'", ENV_VAR, other);
+                RenderBackend::Platform
+            }
+        }
+    }
+}
+
+/// Paint into a new bitmap of the given size (in display points) and return its pixels.
+pub(crate) fn render_to_image(
+    device: &mut Device,
+    size: Size,
+    scale: f64,
+    paint: impl FnOnce(&mut Piet),
+) -> Result<ImageBuf, Error> {
+    /// A way to clean up resources when our render context goes out of
+    /// scope, even during a panic.
+    struct RenderContextGuard<'a>(Piet<'a>);
+
+    impl Drop for RenderContextGuard<'_> {
+        fn drop(&mut self) {
+            // We need to call finish even if a test assert failed
+            if let Err(err) = self.0.finish() {
+                // We can't panic, because we might already be panicking
+                error!("piet finish failed: {}", err);
+            }
+        }
+    }
+
+    let width = (size.width * scale).ceil() as usize;
+    let height = (size.height * scale).ceil() as usize;
+    let mut render_target = device.bitmap_target(width, height, scale)?;
+    {
+        let mut piet = RenderContextGuard(render_target.render_context());
+        paint(&mut piet.0);
+    }
+    render_target.to_image_buf(ImageFormat::RgbaPremul)
+}
+
+/// The state of the [`Software`](RenderBackend::Software) backend for a window.
+///
+/// The device is created on the first frame and reused for the following ones.
+#[derive(Default)]
+pub(crate) struct SoftwareRenderer {
+    device: Option<Device>,
+}
+
+impl SoftwareRenderer {
+    /// Paint a frame of the given size into a CPU bitmap and return its pixels.
+    ///
+    /// This doesn't use the platform's render context.
+    pub(crate) fn render(
+        &mut self,
+        size: Size,
+        scale: f64,
+        paint: impl FnOnce(&mut Piet),
+    ) -> Option<ImageBuf> {
+        if self.device.is_none() {
+            match Device::new() {
+                Ok(device) => self.device = Some(device),
+                Err(err) => {
+                    error!("Can't create software rendering device: {}", err);
+                    return None;
+                }
+            }
+        }
+        let device = self.device.as_mut()?;
+        match render_to_image(device, size, scale, paint) {
+            Ok(image_buf) => Some(image_buf),
+            Err(err) => {
+                error!("Software rendering failed: {}", err);
+                None
+            }
+        }
+    }
+}
+
+/// The devices used to paint offscreen bitmaps in a window, eg for
+/// [`PaintCtx::paint_to_image`](crate::PaintCtx::paint_to_image).
+///
+/// Creating a device is slow, so they're kept between frames. Offscreen painting can
+/// be nested, eg a portal snapshotting a widget which caches its own bitmap, so each
+/// level takes its own device from the pool.
+#[derive(Default)]
+pub(crate) struct OffscreenDevices {
+    devices: Vec<Device>,
+}
+
+impl OffscreenDevices {
+    /// Take a device from the pool, or create one if they're all in use.
+    pub(crate) fn take(&mut self) -> Option<Device> {
+        if let Some(device) = self.devices.pop() {
+            return Some(device);
+        }
+        match Device::new() {
+            Ok(device) => Some(device),
+            Err(err) => {
+                error!("Can't create offscreen rendering device: {}", err);
+                None
+            }
+        }
+    }
+
+    /// Return a device taken with [`take`](Self::take) to the pool.
+    pub(crate) fn give_back(&mut self, device: Device) {
+        self.devices.push(device);
+    }
+}
+
+/// Copy the `invalid` region of a frame painted by [`SoftwareRenderer::render`] to
+/// the window's render context.
+pub(crate) fn present_software_frame(
+    piet: &mut Piet,
+    frame: &ImageBuf,
+    scale: f64,
+    invalid: &Region,
+) {
+    let image = match piet.make_image(
+        frame.width(),
+        frame.height(),
+        frame.raw_pixels(),
+        ImageFormat::RgbaPremul,
+    ) {
+        Ok(image) => image,
+        Err(err) => {
+            error!("Can't upload software-rendered frame: {}", err);
+            return;
+        }
+    };
+
+    // The bitmap is blank outside of the invalid region, so only that part is copied.
+    for &rect in invalid.rects() {
+        let source = Rect::new(
+            rect.x0 * scale,
+            rect.y0 * scale,
+            rect.x1 * scale,
+            rect.y1 * scale,
+        );
+        piet.draw_image_area(&image, source, rect, InterpolationMode::NearestNeighbor);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestHarness;
+    use crate::widget::{Flex, SizedBox};
+    use crate::Color;
+
+    #[test]
+    fn software_backend_matches_platform() {
+        let widget = || {
+            Flex::row().with_child(
+                SizedBox::empty()
+                    .width(20.0)
+                    .height(20.0)
+                    .background(Color::rgb8(255, 0, 0)),
+            )
+        };
+
+        let mut harness = TestHarness::create(widget());
+        let platform_pixels = harness.render();
+
+        let mut harness = TestHarness::create(widget());
+        harness.window_mut().render_backend = RenderBackend::Software;
+        let software_pixels = harness.render();
+
+        assert_eq!(platform_pixels, software_pixels);
+    }
+}
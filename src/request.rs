@@ -0,0 +1,194 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Commands which expect a response.
+//!
+//! A widget sends a request with [`EventCtx::send_request`](crate::EventCtx::send_request)
+//! and gets a [`RequestToken`]. The request is submitted as a global command, so
+//! the [`AppDelegate`](crate::AppDelegate) and every widget can see it; whoever
+//! handles it calls [`Request::respond`]. The response is then delivered to the
+//! widget which sent the request as an [`Event::PromiseResult`](crate::Event::PromiseResult),
+//! which can be matched against the token.
+//!
+//! A response is always delivered: if the request is dropped without being
+//! answered, the requester receives [`RequestError::Unanswered`].
+
+use std::any::Any;
+use std::sync::Mutex;
+
+use tracing::warn;
+
+use crate::ext_event::ExtEventSink;
+use crate::promise::PromiseToken;
+use crate::WidgetId;
+use druid_shell::WindowId;
+
+/// A token identifying the response to a request.
+///
+/// Use [`PromiseResult::try_get`](crate::promise::PromiseResult::try_get) to
+/// get the response from an [`Event::PromiseResult`](crate::Event::PromiseResult).
+pub type RequestToken<Res> = PromiseToken<Result<Res, RequestError>>;
+
+/// The reason a request didn't get a response.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RequestError {
+    /// The request was dropped without anyone responding to it.
+    Unanswered,
+}
+
+/// The payload of a command which expects a response of type `Res`.
+///
+/// Requests are sent with a `Selector<Request<Req, Res>>`.
+pub struct Request<Req, Res: Any + Send> {
+    payload: Req,
+    // Commands are shared, so the responder is taken by whoever responds first.
+    responder: Mutex<Option<Responder<Res>>>,
+}
+
+/// Where the response to a request goes.
+struct Responder<Res: Any + Send> {
+    token: RequestToken<Res>,
+    sink: ExtEventSink,
+    widget_id: WidgetId,
+    window_id: WindowId,
+}
+
+impl<Req, Res: Any + Send> Request<Req, Res> {
+    pub(crate) fn new(
+        payload: Req,
+        token: RequestToken<Res>,
+        sink: ExtEventSink,
+        widget_id: WidgetId,
+        window_id: WindowId,
+    ) -> Self {
+        Request {
+            payload,
+            responder: Mutex::new(Some(Responder {
+                token,
+                sink,
+                widget_id,
+                window_id,
+            })),
+        }
+    }
+
+    /// The payload the request was sent with.
+    pub fn payload(&self) -> &Req {
+        &self.payload
+    }
+
+    /// Returns `true` if someone already responded to this request.
+    pub fn is_answered(&self) -> bool {
+        self.responder.lock().unwrap().is_none()
+    }
+
+    /// Send the response to the widget which made the request.
+    ///
+    /// Only the first response is delivered; this returns `false` if the
+    /// request was already answered.
+    pub fn respond(&self, response: Res) -> bool {
+        let responder = self.responder.lock().unwrap().take();
+        match responder {
+            Some(responder) => {
+                responder.send(Ok(response));
+                true
+            }
+            None => {
+                warn!("Request was already answered; ignoring response.");
+                false
+            }
+        }
+    }
+}
+
+impl<Res: Any + Send> Responder<Res> {
+    fn send(self, response: Result<Res, RequestError>) {
+        let result = self.token.make_result(response);
+        if self
+            .sink
+            .resolve_promise(result, self.widget_id, self.window_id)
+            .is_err()
+        {
+            warn!("Could not deliver response to {:?}", self.widget_id);
+        }
+    }
+}
+
+impl<Req, Res: Any + Send> Drop for Request<Req, Res> {
+    fn drop(&mut self) {
+        let responder = match self.responder.get_mut() {
+            Ok(responder) => responder.take(),
+            Err(poisoned) => poisoned.into_inner().take(),
+        };
+        if let Some(responder) = responder {
+            responder.send(Err(RequestError::Unanswered));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::testing::{widget_ids, ModularWidget, TestHarness};
+    use crate::widget::{Flex, SizedBox};
+    use crate::{Event, Selector, Size, Widget};
+
+    const DOUBLE: Selector<Request<u32, u32>> = Selector::new("masonry-test.double");
+
+    type Response = Rc<RefCell<Option<Result<u32, RequestError>>>>;
+
+    fn requester(response: Response) -> impl Widget {
+        let state = (None::<RequestToken<u32>>, response);
+        ModularWidget::new(state)
+            .event_fn(|(token, response), ctx, event, _| match event {
+                Event::MouseDown(_) => *token = Some(ctx.send_request(DOUBLE, 21)),
+                Event::PromiseResult(result) => {
+                    if let Some(value) = token.and_then(|token| result.try_get(token)) {
+                        *response.borrow_mut() = Some(value);
+                    }
+                }
+                _ => {}
+            })
+            .layout_fn(|_, _, _, _| Size::new(20.0, 20.0))
+    }
+
+    fn responder() -> impl Widget {
+        ModularWidget::new(()).event_fn(|_, _, event, _| {
+            if let Event::Command(cmd) = event {
+                if let Some(request) = cmd.try_get(DOUBLE) {
+                    request.respond(request.payload() * 2);
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn response_reaches_requester() {
+        let [requester_id] = widget_ids();
+        let response = Response::default();
+        let widget = Flex::row()
+            .with_child_id(requester(response.clone()), requester_id)
+            .with_child(responder());
+
+        let mut harness = TestHarness::create(widget);
+        harness.mouse_click_on(requester_id);
+        assert_eq!(*response.borrow(), Some(Ok(42)));
+    }
+
+    #[test]
+    fn unanswered_request_fails() {
+        let [requester_id] = widget_ids();
+        let response = Response::default();
+        let widget = Flex::row()
+            .with_child_id(requester(response.clone()), requester_id)
+            .with_child(SizedBox::empty());
+
+        let mut harness = TestHarness::create(widget);
+        harness.mouse_click_on(requester_id);
+        assert_eq!(*response.borrow(), Some(Err(RequestError::Unanswered)));
+    }
+}
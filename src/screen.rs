@@ -0,0 +1,126 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Placing windows on the displays of a multi-monitor setup.
+//!
+//! All rects are in display points, on the [virtual screen](druid_shell::Screen),
+//! which spans every monitor. The displays are listed by
+//! [`WidgetCtx::monitors`](crate::WidgetCtx::monitors), and a popout window can be
+//! opened next to a widget with
+//! [`EventCtx::open_window_near`](crate::EventCtx::open_window_near), which keeps it
+//! inside the work area of the widget's monitor, ie away from the taskbar or dock.
+
+use druid_shell::{Monitor, Screen};
+
+use crate::kurbo::{Point, Rect, Size};
+
+/// The work areas of the monitors, ie their rects minus the taskbar, dock or menu bar.
+pub fn work_areas() -> Vec<Rect> {
+    Screen::get_monitors()
+        .iter()
+        .map(Monitor::virtual_work_rect)
+        .collect()
+}
+
+/// The area among `areas` which shows most of `rect`.
+///
+/// If `rect` is outside every area, this is the closest one. Returns `None` if `areas`
+/// is empty.
+pub fn area_showing(rect: Rect, areas: &[Rect]) -> Option<Rect> {
+    let overlap = |area: &Rect| rect.intersect(*area).area();
+    let best_overlap = areas
+        .iter()
+        .copied()
+        .max_by(|a, b| overlap(a).total_cmp(&overlap(b)));
+    if best_overlap.map_or(0.0, |area| overlap(&area)) > 0.0 {
+        return best_overlap;
+    }
+    let center = rect.center();
+    let distance = |area: &Rect| {
+        let closest = Point::new(
+            center.x.clamp(area.x0, area.x1),
+            center.y.clamp(area.y0, area.y1),
+        );
+        closest.distance(center)
+    };
+    areas
+        .iter()
+        .copied()
+        .min_by(|a, b| distance(a).total_cmp(&distance(b)))
+}
+
+/// The position of a window of `size` opened next to `anchor`.
+///
+/// The window is placed below `anchor`, aligned with its left edge, or above it if
+/// there's more room there. It's then moved into the work area showing most of
+/// `anchor`; if it's larger than the work area, its top left corner is kept inside.
+pub fn place_near(anchor: Rect, size: Size, work_areas: &[Rect]) -> Point {
+    let below = Point::new(anchor.x0, anchor.y1);
+    let Some(area) = area_showing(anchor, work_areas) else {
+        return below;
+    };
+
+    let room_below = area.y1 - anchor.y1;
+    let room_above = anchor.y0 - area.y0;
+    let y = if size.height > room_below && room_above > room_below {
+        anchor.y0 - size.height
+    } else {
+        below.y
+    };
+    Point::new(
+        clamp_start(below.x, size.width, area.x0, area.x1),
+        clamp_start(y, size.height, area.y0, area.y1),
+    )
+}
+
+/// Move a span starting at `start` into `min..max`, keeping its start inside if it's
+/// too long.
+fn clamp_start(start: f64, length: f64, min: f64, max: f64) -> f64 {
+    start.min(max - length).max(min)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEFT: Rect = Rect::new(0.0, 0.0, 1920.0, 1040.0);
+    const RIGHT: Rect = Rect::new(1920.0, 0.0, 3840.0, 1040.0);
+
+    #[test]
+    fn popout_stays_on_anchor_monitor() {
+        let size = Size::new(300.0, 200.0);
+
+        // Below the anchor, aligned with it.
+        let anchor = Rect::new(100.0, 100.0, 200.0, 120.0);
+        assert_eq!(
+            place_near(anchor, size, &[LEFT, RIGHT]),
+            Point::new(100.0, 120.0)
+        );
+
+        // Near the right edge of the left monitor: moved left instead of spanning both.
+        let anchor = Rect::new(1800.0, 100.0, 1900.0, 120.0);
+        assert_eq!(
+            place_near(anchor, size, &[LEFT, RIGHT]),
+            Point::new(1620.0, 120.0)
+        );
+
+        // Near the bottom: flipped above the anchor.
+        let anchor = Rect::new(2000.0, 1000.0, 2100.0, 1020.0);
+        assert_eq!(
+            place_near(anchor, size, &[LEFT, RIGHT]),
+            Point::new(2000.0, 800.0)
+        );
+    }
+
+    #[test]
+    fn offscreen_anchor_uses_closest_monitor() {
+        let anchor = Rect::new(4000.0, 500.0, 4100.0, 520.0);
+        assert_eq!(area_showing(anchor, &[LEFT, RIGHT]), Some(RIGHT));
+        assert_eq!(
+            place_near(anchor, Size::new(300.0, 200.0), &[LEFT, RIGHT]),
+            Point::new(3540.0, 520.0)
+        );
+        assert_eq!(area_showing(anchor, &[]), None);
+    }
+}
@@ -0,0 +1,309 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Selection of items in lists, tables and trees.
+//!
+//! A [`Selection`] implements the usual selection semantics, so that every widget
+//! showing a collection of items behaves the same way:
+//! - a click selects the clicked item;
+//! - Shift+click selects the range between the anchor (the last item clicked
+//!   without Shift) and the clicked item;
+//! - Ctrl+click (Cmd+click on macOS) adds or removes the clicked item;
+//! - Ctrl+A (Cmd+A on macOS) selects every item;
+//! - Up, Down, Home and End move the focused item and select it, extending the
+//!   range with Shift, or keeping the selection with Ctrl; Space toggles the
+//!   focused item.
+//!
+//! Which of these are available depends on the [`SelectionMode`].
+//!
+//! Items are identified by their index. The widget owning the selection forwards
+//! clicks and key presses to it, and repaints the items when it reports a change.
+
+use std::collections::BTreeSet;
+
+use druid_shell::{KbKey, KeyEvent, Modifiers};
+
+use crate::Handled;
+
+/// How many items a [`Selection`] can hold, and how clicks change it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SelectionMode {
+    /// At most one item is selected.
+    #[default]
+    Single,
+    /// Any number of items are selected, with Shift for ranges and Ctrl to add or
+    /// remove single items.
+    Multiple,
+    /// Any number of items are selected, and a click adds or removes the clicked
+    /// item, like a list of checkboxes.
+    Toggle,
+}
+
+/// The selected items of a list-like widget, and the focused item moved with the
+/// keyboard.
+///
+/// See the [module-level documentation](self).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Selection {
+    mode: SelectionMode,
+    selected: BTreeSet<usize>,
+    anchor: Option<usize>,
+    focus: Option<usize>,
+}
+
+impl Selection {
+    /// Create an empty selection.
+    pub fn new(mode: SelectionMode) -> Self {
+        Selection {
+            mode,
+            ..Default::default()
+        }
+    }
+
+    /// The selection mode.
+    pub fn mode(&self) -> SelectionMode {
+        self.mode
+    }
+
+    /// Whether the item at `index` is selected.
+    pub fn is_selected(&self, index: usize) -> bool {
+        self.selected.contains(&index)
+    }
+
+    /// The indices of the selected items, in increasing order.
+    pub fn selected(&self) -> impl Iterator<Item = usize> + '_ {
+        self.selected.iter().copied()
+    }
+
+    /// The number of selected items.
+    pub fn len(&self) -> usize {
+        self.selected.len()
+    }
+
+    /// Whether no item is selected.
+    pub fn is_empty(&self) -> bool {
+        self.selected.is_empty()
+    }
+
+    /// The item moved with the keyboard, usually drawn with a focus outline.
+    pub fn focus(&self) -> Option<usize> {
+        self.focus
+    }
+
+    /// Select only the item at `index`, and focus it.
+    pub fn select(&mut self, index: usize) {
+        self.selected.clear();
+        self.selected.insert(index);
+        self.anchor = Some(index);
+        self.focus = Some(index);
+    }
+
+    /// Unselect every item.
+    pub fn clear(&mut self) {
+        self.selected.clear();
+        self.anchor = None;
+    }
+
+    /// Select the `item_count` items, unless the mode is [`SelectionMode::Single`].
+    ///
+    /// Returns `true` if the selection changed.
+    pub fn select_all(&mut self, item_count: usize) -> bool {
+        if self.mode == SelectionMode::Single || self.selected.len() == item_count {
+            return false;
+        }
+        self.selected = (0..item_count).collect();
+        true
+    }
+
+    /// Forget the items at `item_count` and after, eg after the last items of the
+    /// list were removed.
+    pub fn truncate(&mut self, item_count: usize) {
+        self.selected.split_off(&item_count);
+        self.anchor = self.anchor.filter(|anchor| *anchor < item_count);
+        self.focus = self.focus.filter(|focus| *focus < item_count);
+    }
+
+    /// Update the selection for a click on the item at `index`, with the modifiers
+    /// held during the click.
+    ///
+    /// Returns `true` if the selection changed.
+    pub fn click(&mut self, index: usize, mods: Modifiers) -> bool {
+        let old_selected = self.selected.clone();
+        match self.mode {
+            SelectionMode::Single => self.select(index),
+            SelectionMode::Multiple if mods.shift() => {
+                let anchor = self.anchor.unwrap_or(index);
+                self.select_range(anchor, index, is_primary(mods));
+                self.focus = Some(index);
+            }
+            SelectionMode::Multiple if is_primary(mods) => self.toggle(index),
+            SelectionMode::Multiple => self.select(index),
+            SelectionMode::Toggle => self.toggle(index),
+        }
+        self.selected != old_selected
+    }
+
+    /// Handle the navigation and selection keys, for a widget showing `item_count`
+    /// items in a single column.
+    ///
+    /// Returns [`Handled::Yes`] if the key was used, even if the selection didn't
+    /// change; the widget should then mark the event as handled and repaint.
+    pub fn key_down(&mut self, event: &KeyEvent, item_count: usize) -> Handled {
+        if item_count == 0 {
+            return Handled::No;
+        }
+        let last = item_count - 1;
+        let target = match &event.key {
+            KbKey::ArrowUp => self.focus.map_or(last, |focus| focus.saturating_sub(1)),
+            KbKey::ArrowDown => self.focus.map_or(0, |focus| (focus + 1).min(last)),
+            KbKey::Home => 0,
+            KbKey::End => last,
+            KbKey::Character(c) if c == " " => {
+                let Some(focus) = self.focus else {
+                    return Handled::No;
+                };
+                if self.mode == SelectionMode::Single {
+                    self.select(focus);
+                } else {
+                    self.toggle(focus);
+                }
+                return Handled::Yes;
+            }
+            KbKey::Character(c) if c.eq_ignore_ascii_case("a") && is_primary(event.mods) => {
+                if self.mode == SelectionMode::Single {
+                    return Handled::No;
+                }
+                self.select_all(item_count);
+                return Handled::Yes;
+            }
+            _ => return Handled::No,
+        };
+
+        match self.mode {
+            SelectionMode::Multiple if event.mods.shift() => {
+                let anchor = self.anchor.or(self.focus).unwrap_or(target);
+                self.select_range(anchor, target, false);
+                self.focus = Some(target);
+            }
+            // Move the focus without changing the selection.
+            SelectionMode::Multiple if is_primary(event.mods) => self.focus = Some(target),
+            SelectionMode::Toggle => self.focus = Some(target),
+            SelectionMode::Single | SelectionMode::Multiple => self.select(target),
+        }
+        Handled::Yes
+    }
+
+    /// Add or remove the item at `index`, and make it the anchor of the next range.
+    fn toggle(&mut self, index: usize) {
+        if !self.selected.remove(&index) {
+            self.selected.insert(index);
+        }
+        self.anchor = Some(index);
+        self.focus = Some(index);
+    }
+
+    /// Select the items between `anchor` and `index` included, keeping the anchor.
+    fn select_range(&mut self, anchor: usize, index: usize, keep_selection: bool) {
+        if !keep_selection {
+            self.selected.clear();
+        }
+        self.selected.extend(anchor.min(index)..=anchor.max(index));
+        self.anchor = Some(anchor);
+    }
+}
+
+/// Whether the platform's primary modifier is held: Cmd on macOS, Ctrl elsewhere.
+fn is_primary(mods: Modifiers) -> bool {
+    if cfg!(target_os = "macos") {
+        mods.meta()
+    } else {
+        mods.ctrl()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use druid_shell::RawMods;
+
+    use super::*;
+
+    fn primary() -> Modifiers {
+        if cfg!(target_os = "macos") {
+            Modifiers::META
+        } else {
+            Modifiers::CONTROL
+        }
+    }
+
+    fn selected(selection: &Selection) -> Vec<usize> {
+        selection.selected().collect()
+    }
+
+    #[test]
+    fn multiple_selection_clicks() {
+        let mut selection = Selection::new(SelectionMode::Multiple);
+        assert!(selection.click(2, Modifiers::empty()));
+        assert!(selection.click(5, Modifiers::SHIFT));
+        assert_eq!(selected(&selection), [2, 3, 4, 5]);
+
+        // The range is recomputed from the same anchor.
+        assert!(selection.click(0, Modifiers::SHIFT));
+        assert_eq!(selected(&selection), [0, 1, 2]);
+
+        assert!(selection.click(1, primary()));
+        assert_eq!(selected(&selection), [0, 2]);
+        assert!(selection.click(7, primary()));
+        assert_eq!(selected(&selection), [0, 2, 7]);
+
+        assert!(selection.click(4, Modifiers::empty()));
+        assert_eq!(selected(&selection), [4]);
+        assert!(!selection.click(4, Modifiers::empty()));
+    }
+
+    #[test]
+    fn keyboard_navigation() {
+        let key = |mods, key| KeyEvent::for_test(mods, key);
+        let mut selection = Selection::new(SelectionMode::Multiple);
+
+        assert_eq!(
+            selection.key_down(&key(RawMods::None, KbKey::ArrowDown), 4),
+            Handled::Yes
+        );
+        assert_eq!(selected(&selection), [0]);
+
+        selection.key_down(&key(RawMods::Shift, KbKey::End), 4);
+        assert_eq!(selected(&selection), [0, 1, 2, 3]);
+        assert_eq!(selection.focus(), Some(3));
+
+        selection.key_down(&key(RawMods::None, KbKey::ArrowUp), 4);
+        assert_eq!(selected(&selection), [2]);
+
+        selection.clear();
+        let select_all = if cfg!(target_os = "macos") {
+            RawMods::Meta
+        } else {
+            RawMods::Ctrl
+        };
+        selection.key_down(&key(select_all, KbKey::Character("a".into())), 4);
+        assert_eq!(selected(&selection), [0, 1, 2, 3]);
+
+        let mut single = Selection::new(SelectionMode::Single);
+        assert_eq!(
+            single.key_down(&key(select_all, KbKey::Character("a".into())), 4),
+            Handled::No
+        );
+    }
+
+    #[test]
+    fn toggle_selection() {
+        let mut selection = Selection::new(SelectionMode::Toggle);
+        selection.click(1, Modifiers::empty());
+        selection.click(3, Modifiers::empty());
+        selection.click(1, Modifiers::empty());
+        assert_eq!(selected(&selection), [3]);
+
+        selection.truncate(2);
+        assert!(selection.is_empty());
+    }
+}
@@ -0,0 +1,351 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Window-level keyboard shortcuts, including multi-stroke chords.
+//!
+//! A [`Shortcuts`] table maps key chords to commands, and is set on a window with
+//! [`WindowDescription::shortcuts`](crate::WindowDescription::shortcuts). A chord is
+//! a sequence of one or more key strokes, eg `"Ctrl+S"` or `"Ctrl+K Ctrl+C"`.
+//!
+//! Shortcuts are matched before key presses are sent to the focused widget. Once a
+//! stroke starts a longer chord, the window waits for the next stroke; the keys
+//! pressed in the meantime aren't sent to widgets. The partial chord is cancelled
+//! if the next stroke doesn't continue it, or after [`Shortcuts::chord_timeout`].
+//!
+//! Each time the partial chord changes, the window receives a [`PENDING_CHORD_CHANGED`]
+//! command, which a status widget can use to display it.
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use druid_shell::{KbKey, KeyEvent, Modifiers};
+
+use crate::{Command, Selector};
+
+/// Sent to the window each time the partial chord changes, with the strokes typed so far.
+///
+/// The chord is empty once it's completed, cancelled or timed out.
+pub const PENDING_CHORD_CHANGED: Selector<KeyChord> =
+    Selector::new("masonry-builtin.pending-chord-changed");
+
+/// The default time the window waits for the next stroke of a chord.
+const DEFAULT_CHORD_TIMEOUT: Duration = Duration::from_millis(1500);
+
+/// A key pressed with a set of modifiers, eg `Ctrl+Shift+P`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyStroke {
+    mods: Modifiers,
+    key: KbKey,
+}
+
+/// A sequence of key strokes, eg `Ctrl+K Ctrl+C`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct KeyChord(Vec<KeyStroke>);
+
+/// An error parsing a [`KeyStroke`] or [`KeyChord`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseShortcutError {
+    input: String,
+}
+
+/// A table of chords, and the command each one submits.
+#[derive(Clone, Debug)]
+pub struct Shortcuts {
+    bindings: Vec<(KeyChord, Command)>,
+    chord_timeout: Duration,
+}
+
+/// The result of adding a stroke to the partial chord.
+pub(crate) enum ChordMatch<'a> {
+    /// The stroke isn't part of a shortcut.
+    None,
+    /// The stroke doesn't continue the partial chord, which is cancelled.
+    Cancelled,
+    /// The stroke starts or continues a longer chord.
+    Prefix,
+    /// The stroke completes a chord.
+    Complete(&'a Command),
+}
+
+// --- KeyStroke ---
+
+impl KeyStroke {
+    /// Create a key stroke.
+    ///
+    /// Letters are matched regardless of case; use [`Modifiers::SHIFT`] to require
+    /// shift. Shift is ignored for other characters, since it's usually needed to
+    /// type them.
+    pub fn new(mods: Modifiers, key: KbKey) -> Self {
+        // Lock keys and the like are ignored.
+        let mut mods =
+            mods & (Modifiers::CONTROL | Modifiers::ALT | Modifiers::SHIFT | Modifiers::META);
+        let key = match key {
+            KbKey::Character(c) => {
+                if !c.chars().any(char::is_alphabetic) {
+                    mods.remove(Modifiers::SHIFT);
+                }
+                KbKey::Character(c.to_lowercase())
+            }
+            key => key,
+        };
+        KeyStroke { mods, key }
+    }
+
+    /// The key stroke of a key press.
+    pub fn from_event(event: &KeyEvent) -> Self {
+        KeyStroke::new(event.mods, event.key.clone())
+    }
+
+    /// Returns `true` if `key` is a modifier, which is never a stroke on its own.
+    pub(crate) fn is_modifier(key: &KbKey) -> bool {
+        matches!(
+            key,
+            KbKey::Control
+                | KbKey::Shift
+                | KbKey::Alt
+                | KbKey::AltGraph
+                | KbKey::Meta
+                | KbKey::Super
+                | KbKey::CapsLock
+        )
+    }
+}
+
+impl FromStr for KeyStroke {
+    type Err = ParseShortcutError;
+
+    /// Parse a stroke such as `"Ctrl+Shift+P"`, `"Alt+F4"` or `"Cmd+,"`.
+    ///
+    /// `Cmd` is the command key on macOS, and control elsewhere.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseShortcutError {
+            input: s.to_string(),
+        };
+        // The key itself may be a '+'.
+        let (mods, key) = match s.strip_suffix("++") {
+            Some(mods) => (mods, "+"),
+            None if s == "+" => ("", s),
+            None => s.rsplit_once('+').unwrap_or(("", s)),
+        };
+
+        let mut modifiers = Modifiers::empty();
+        for modifier in mods.split('+').filter(|m| !m.is_empty()) {
+            modifiers |= match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => Modifiers::CONTROL,
+                "alt" | "option" => Modifiers::ALT,
+                "shift" => Modifiers::SHIFT,
+                "meta" | "super" | "win" => Modifiers::META,
+                "cmd" | "cmdorctrl" if cfg!(target_os = "macos") => Modifiers::META,
+                "cmd" | "cmdorctrl" => Modifiers::CONTROL,
+                _ => return Err(err()),
+            };
+        }
+
+        let key = match key.chars().count() {
+            0 => return Err(err()),
+            1 => KbKey::Character(key.to_string()),
+            _ => key.parse().map_err(|_| err())?,
+        };
+        Ok(KeyStroke::new(modifiers, key))
+    }
+}
+
+impl fmt::Display for KeyStroke {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let meta = if cfg!(target_os = "macos") {
+            "Cmd"
+        } else {
+            "Meta"
+        };
+        for (modifier, name) in [
+            (Modifiers::CONTROL, "Ctrl"),
+            (Modifiers::ALT, "Alt"),
+            (Modifiers::SHIFT, "Shift"),
+            (Modifiers::META, meta),
+        ] {
+            if self.mods.contains(modifier) {
+                write!(f, "{}+", name)?;
+            }
+        }
+        match &self.key {
+            KbKey::Character(c) => write!(f, "{}", c.to_uppercase()),
+            key => write!(f, "{}", key),
+        }
+    }
+}
+
+// --- KeyChord ---
+
+impl KeyChord {
+    /// The strokes of the chord, in order.
+    pub fn strokes(&self) -> &[KeyStroke] {
+        &self.0
+    }
+
+    /// Returns `true` if the chord has no strokes.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl From<Vec<KeyStroke>> for KeyChord {
+    fn from(strokes: Vec<KeyStroke>) -> Self {
+        KeyChord(strokes)
+    }
+}
+
+impl FromStr for KeyChord {
+    type Err = ParseShortcutError;
+
+    /// Parse a chord of strokes separated by spaces, eg `"Ctrl+K Ctrl+C"`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let strokes = s
+            .split_whitespace()
+            .map(KeyStroke::from_str)
+            .collect::<Result<Vec<_>, _>>()?;
+        if strokes.is_empty() {
+            return Err(ParseShortcutError {
+                input: s.to_string(),
+            });
+        }
+        Ok(KeyChord(strokes))
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, stroke) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}", stroke)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ParseShortcutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid shortcut: '{}'", self.input)
+    }
+}
+
+impl std::error::Error for ParseShortcutError {}
+
+// --- Shortcuts ---
+
+impl Shortcuts {
+    /// Create an empty table.
+    pub fn new() -> Self {
+        Shortcuts {
+            bindings: Vec::new(),
+            chord_timeout: DEFAULT_CHORD_TIMEOUT,
+        }
+    }
+
+    /// Builder-style method to submit `command` when `chord` is typed.
+    ///
+    /// Commands sent to [`Target::Auto`](crate::Target::Auto) are sent to the window.
+    pub fn with_shortcut(mut self, chord: KeyChord, command: impl Into<Command>) -> Self {
+        self.add(chord, command);
+        self
+    }
+
+    /// Builder-style method to set how long the window waits for the next stroke of a chord.
+    ///
+    /// The default is 1.5 seconds.
+    pub fn with_chord_timeout(mut self, timeout: Duration) -> Self {
+        self.chord_timeout = timeout;
+        self
+    }
+
+    /// Submit `command` when `chord` is typed.
+    ///
+    /// If `chord` was already bound, the new command replaces the old one.
+    pub fn add(&mut self, chord: KeyChord, command: impl Into<Command>) {
+        let command = command.into();
+        if chord.is_empty() {
+            return;
+        }
+        match self.bindings.iter_mut().find(|(bound, _)| *bound == chord) {
+            Some(binding) => binding.1 = command,
+            None => self.bindings.push((chord, command)),
+        }
+    }
+
+    /// Remove the shortcut for `chord`.
+    pub fn remove(&mut self, chord: &KeyChord) {
+        self.bindings.retain(|(bound, _)| bound != chord);
+    }
+
+    /// How long the window waits for the next stroke of a chord.
+    pub fn chord_timeout(&self) -> Duration {
+        self.chord_timeout
+    }
+
+    /// Returns `true` if no shortcut is bound.
+    pub fn is_empty(&self) -> bool {
+        self.bindings.is_empty()
+    }
+
+    /// Match the partial chord `pending`, followed by `stroke`, against the table.
+    pub(crate) fn match_stroke(&self, pending: &[KeyStroke], stroke: &KeyStroke) -> ChordMatch<'_> {
+        let typed = || pending.iter().chain(std::iter::once(stroke));
+        let mut is_prefix = false;
+        for (chord, command) in &self.bindings {
+            if chord.0.len() <= pending.len() || !chord.0.iter().zip(typed()).all(|(a, b)| a == b) {
+                continue;
+            }
+            if chord.0.len() == pending.len() + 1 {
+                return ChordMatch::Complete(command);
+            }
+            is_prefix = true;
+        }
+
+        if is_prefix {
+            ChordMatch::Prefix
+        } else if !pending.is_empty() {
+            ChordMatch::Cancelled
+        } else {
+            ChordMatch::None
+        }
+    }
+}
+
+impl Default for Shortcuts {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stroke(s: &str) -> KeyStroke {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_and_display() {
+        assert_eq!(
+            stroke("ctrl+shift+p"),
+            KeyStroke::new(
+                Modifiers::CONTROL | Modifiers::SHIFT,
+                KbKey::Character("P".into())
+            )
+        );
+        assert_eq!(stroke("Alt+F4"), KeyStroke::new(Modifiers::ALT, KbKey::F4));
+        assert_eq!(stroke("Ctrl++").to_string(), "Ctrl++");
+        assert_eq!(stroke("Shift+?"), stroke("?"));
+
+        let chord: KeyChord = "Ctrl+K  Ctrl+C".parse().unwrap();
+        assert_eq!(chord.strokes().len(), 2);
+        assert_eq!(chord.to_string(), "Ctrl+K Ctrl+C");
+
+        assert!("Hyper+K".parse::<KeyStroke>().is_err());
+        assert!("".parse::<KeyChord>().is_err());
+    }
+}
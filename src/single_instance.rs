@@ -0,0 +1,308 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Forwarding later launches of an app to the instance already running.
+//!
+//! Enabled with [`AppLauncher::with_single_instance`](crate::AppLauncher::with_single_instance).
+//! The first instance listens on a loopback port, which it writes to a file named
+//! after the app id in the user's runtime directory (`XDG_RUNTIME_DIR`, or the
+//! temporary directory), and removes when it exits. Later instances connect to it,
+//! send their command-line arguments, and exit without opening a window.
+//!
+//! The running instance brings its main window to the front, and submits
+//! [`INSTANCE_ACTIVATED`] to the [`AppDelegate`](crate::AppDelegate) and every
+//! widget, eg to open the files passed as arguments.
+//!
+//! Any local process can connect to the port, so the arguments shouldn't be trusted
+//! more than a file dropped on the app.
+
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::ext_event::ExtEventSink;
+use crate::open_request::{self, OpenSource};
+use crate::{Selector, Target};
+
+/// Submitted with [`Target::Global`] when another instance of the app is launched.
+pub const INSTANCE_ACTIVATED: Selector<InstanceActivation> =
+    Selector::new("masonry-builtin.instance-activated");
+
+/// How long a new instance waits for the running one to accept its arguments.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How long to wait for the port file of an instance starting at the same time
+/// to be written.
+const PORT_FILE_WRITE_DELAY: Duration = Duration::from_millis(50);
+
+/// The launch of another instance of the app.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstanceActivation {
+    /// The command-line arguments of the new instance, without the program name.
+    ///
+    /// Arguments which aren't valid Unicode are converted lossily.
+    pub args: Vec<String>,
+    /// The working directory of the new instance, to resolve relative paths in `args`.
+    pub cwd: Option<PathBuf>,
+}
+
+/// The outcome of [`acquire`].
+pub(crate) enum Instance {
+    /// No other instance is running; this one should start, and forward the
+    /// activations received on the listener. The port file is removed when the
+    /// [`PortFile`] is dropped.
+    Primary(TcpListener, PortFile),
+    /// Another instance is running and received our arguments; this one should exit.
+    Secondary,
+}
+
+impl InstanceActivation {
+    /// The activation describing the current process.
+    pub(crate) fn current() -> Self {
+        InstanceActivation {
+            args: std::env::args_os()
+                .skip(1)
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect(),
+            cwd: std::env::current_dir().ok(),
+        }
+    }
+}
+
+/// The port file of the primary instance, removed when dropped.
+pub(crate) struct PortFile(PathBuf);
+
+impl Drop for PortFile {
+    fn drop(&mut self) {
+        if let Err(err) = std::fs::remove_file(&self.0) {
+            warn!("Can't remove {}: {}", self.0.display(), err);
+        }
+    }
+}
+
+/// The directory of the port files, which only the user can write to.
+///
+/// The temporary directory is per-user on macOS and Windows, but usually shared on
+/// Linux, where the file name includes the user name when `XDG_RUNTIME_DIR` isn't set.
+fn runtime_dir() -> (PathBuf, Option<String>) {
+    match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) => (PathBuf::from(dir), None),
+        None => (std::env::temp_dir(), std::env::var("USER").ok()),
+    }
+}
+
+fn port_file(app_id: &str) -> PathBuf {
+    let file_name: String = app_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    match runtime_dir() {
+        (dir, Some(user)) => dir.join(format!("{}.{}.instance", file_name, user)),
+        (dir, None) => dir.join(format!("{}.instance", file_name)),
+    }
+}
+
+/// The port in the port file, waiting a bit for an instance which just created it
+/// to write it.
+fn read_port(port_file: &Path) -> Option<u16> {
+    let read = || {
+        std::fs::read_to_string(port_file)
+            .ok()
+            .and_then(|port| port.trim().parse::<u16>().ok())
+    };
+    read().or_else(|| {
+        std::thread::sleep(PORT_FILE_WRITE_DELAY);
+        read()
+    })
+}
+
+/// Create the port file, failing if it already exists.
+fn create_port_file(port_file: &Path, port: u16) -> std::io::Result<PortFile> {
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(port_file)?;
+    let port_file = PortFile(port_file.to_path_buf());
+    file.write_all(port.to_string().as_bytes())?;
+    Ok(port_file)
+}
+
+/// Forward `activation` to the running instance of `app_id`, or become that instance.
+///
+/// Errors in the single-instance mechanism aren't fatal: the app then runs as if it
+/// were the only instance.
+pub(crate) fn acquire(app_id: &str, activation: &InstanceActivation) -> Option<Instance> {
+    let listener = match TcpListener::bind((Ipv4Addr::LOCALHOST, 0)) {
+        Ok(listener) => listener,
+        Err(err) => {
+            warn!("Can't listen for other instances of {}: {}", app_id, err);
+            return None;
+        }
+    };
+    let port = listener.local_addr().ok()?.port();
+
+    // The file is created atomically, so of two instances launched at the same
+    // time, one creates it and the other forwards its arguments. A file left behind
+    // by an instance which is gone is replaced once.
+    let port_file = port_file(app_id);
+    for _ in 0..2 {
+        match create_port_file(&port_file, port) {
+            Ok(port_file) => return Some(Instance::Primary(listener, port_file)),
+            Err(err) if err.kind() == ErrorKind::AlreadyExists => {}
+            Err(err) => {
+                warn!("Can't write {}: {}", port_file.display(), err);
+                return None;
+            }
+        }
+        if let Some(running_port) = read_port(&port_file) {
+            let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, running_port));
+            match send_activation(addr, activation) {
+                Ok(()) => return Some(Instance::Secondary),
+                Err(err) => info!("No running instance of {} ({}), starting", app_id, err),
+            }
+        }
+        if let Err(err) = std::fs::remove_file(&port_file) {
+            warn!("Can't remove {}: {}", port_file.display(), err);
+            return None;
+        }
+    }
+    warn!("Can't create {}", port_file.display());
+    None
+}
+
+fn send_activation(addr: SocketAddr, activation: &InstanceActivation) -> std::io::Result<()> {
+    let mut stream = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)?;
+    stream.set_read_timeout(Some(CONNECT_TIMEOUT))?;
+    let mut message = serde_json::to_vec(activation)?;
+    message.push(b'\n');
+    stream.write_all(&message)?;
+
+    // Wait for the acknowledgment, so we don't exit before the message was read.
+    let mut ack = String::new();
+    BufReader::new(stream).read_line(&mut ack)?;
+    if ack.trim() == "ok" {
+        Ok(())
+    } else {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "unexpected reply",
+        ))
+    }
+}
+
+/// Start a thread submitting [`INSTANCE_ACTIVATED`] for each instance launched later.
+///
+/// The jump-list tasks in the arguments are also submitted, and so are the files and
+/// URLs if `open_requests` is set, skipping the values of `value_options`; see
+/// [`open_request`].
+///
+/// The thread runs until the app exits.
+pub(crate) fn start_listening(
+    listener: TcpListener,
+    sink: ExtEventSink,
+    open_requests: bool,
+    value_options: Vec<String>,
+) {
+    let spawn_result = std::thread::Builder::new()
+        .name("masonry-single-instance".into())
+        .spawn(move || {
+            for stream in listener.incoming() {
+                let activation = match stream.and_then(receive_activation) {
+                    Ok(activation) => activation,
+                    Err(err) => {
+                        warn!("Invalid activation from another instance: {}", err);
+                        continue;
+                    }
+                };
+                let submitted = open_request::submit_args(
+                    &activation.args,
+                    activation.cwd.as_deref(),
+                    OpenSource::OtherInstance,
+                    open_requests,
+                    &value_options,
+                    &sink,
+                )
+                .and_then(|()| {
+                    sink.submit_command(INSTANCE_ACTIVATED, Box::new(activation), Target::Global)
+                });
+                if submitted.is_err() {
+                    // The app is gone.
+                    return;
+                }
+            }
+        });
+    if let Err(err) = spawn_result {
+        warn!("Can't listen for other instances: {}", err);
+    }
+}
+
+fn receive_activation(stream: TcpStream) -> std::io::Result<InstanceActivation> {
+    stream.set_read_timeout(Some(CONNECT_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let activation = serde_json::from_str(&line)?;
+    (&stream).write_all(b"ok\n")?;
+    Ok(activation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn second_instance_forwards_its_arguments() {
+        let app_id = format!("org.masonry.test-single-instance-{}", std::process::id());
+        let first = InstanceActivation {
+            args: Vec::new(),
+            cwd: None,
+        };
+        let Some(Instance::Primary(listener, port_file)) = acquire(&app_id, &first) else {
+            panic!("the first instance should be the primary one");
+        };
+
+        let second = InstanceActivation {
+            args: vec!["notes.txt".into()],
+            cwd: Some("/home/user".into()),
+        };
+        let sender = std::thread::spawn({
+            let app_id = app_id.clone();
+            let second = second.clone();
+            move || matches!(acquire(&app_id, &second), Some(Instance::Secondary))
+        });
+        let (stream, _) = listener.accept().unwrap();
+        assert_eq!(receive_activation(stream).unwrap(), second);
+        assert!(sender.join().unwrap());
+
+        drop(port_file);
+        assert!(!super::port_file(&app_id).exists());
+    }
+
+    #[test]
+    fn stale_port_file_is_replaced() {
+        let app_id = format!("org.masonry.test-stale-instance-{}", std::process::id());
+        std::fs::write(super::port_file(&app_id), "not a port").unwrap();
+
+        let activation = InstanceActivation {
+            args: Vec::new(),
+            cwd: None,
+        };
+        let Some(Instance::Primary(listener, _port_file)) = acquire(&app_id, &activation) else {
+            panic!("the stale file should be replaced");
+        };
+        let port = listener.local_addr().unwrap().port();
+        assert_eq!(read_port(&super::port_file(&app_id)), Some(port));
+    }
+}
@@ -0,0 +1,170 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! UI sound feedback.
+//!
+//! Widgets request sounds with `ctx.play_sound(SoundId::Click)`. The request
+//! is turned into a [`PLAY_SOUND`](crate::command::sys::PLAY_SOUND) command,
+//! and the app resolves the sound file through the [`Env`] (see
+//! [`theme::SOUND_CLICK`] and friends) before playing it.
+//!
+//! Actual audio output requires the `sound` feature. Without it, sound
+//! requests are resolved and then dropped.
+
+use crate::{theme, ArcStr, Env, Key};
+
+/// Identifies a UI sound.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SoundId {
+    /// A button or other control was activated.
+    Click,
+    /// An operation failed, or invalid input was rejected.
+    Error,
+    /// Something needs the user's attention.
+    Notification,
+    /// A sound whose file path is stored under a custom [`Env`] key.
+    Custom(Key<ArcStr>),
+}
+
+impl SoundId {
+    /// The [`Env`] key holding the path of the sound file for this id.
+    pub fn key(&self) -> Key<ArcStr> {
+        match self {
+            SoundId::Click => theme::SOUND_CLICK,
+            SoundId::Error => theme::SOUND_ERROR,
+            SoundId::Notification => theme::SOUND_NOTIFICATION,
+            SoundId::Custom(key) => key.clone(),
+        }
+    }
+
+    /// The path of the sound file for this id, or `None` if sounds are
+    /// disabled or no file is configured.
+    pub fn resolve(&self, env: &Env) -> Option<ArcStr> {
+        if !env.get(theme::SOUNDS_ENABLED) {
+            return None;
+        }
+        let path = env.try_get(self.key()).ok()?;
+        if path.is_empty() {
+            None
+        } else {
+            Some(path)
+        }
+    }
+}
+
+/// A decoded sound, shared by all the times it's played.
+#[cfg(feature = "sound")]
+type CachedSound = rodio::source::Buffered<
+    rodio::source::SamplesConverter<rodio::Decoder<std::io::BufReader<std::fs::File>>, f32>,
+>;
+
+/// Plays sound files on the default output device.
+///
+/// The output stream is opened lazily, the first time a sound is played. Each file
+/// is decoded once, then its samples are reused; files which can't be opened or
+/// decoded are only reported the first time.
+#[derive(Default)]
+pub(crate) struct SoundPlayer {
+    #[cfg(feature = "sound")]
+    output: Option<(rodio::OutputStream, rodio::OutputStreamHandle)>,
+    /// Whether opening the output stream failed, so it isn't retried.
+    #[cfg(feature = "sound")]
+    output_failed: bool,
+    /// The sounds played so far, by path, or `None` for invalid files.
+    #[cfg(feature = "sound")]
+    sounds: std::collections::HashMap<String, Option<CachedSound>>,
+}
+
+impl SoundPlayer {
+    pub(crate) fn new() -> Self {
+        Default::default()
+    }
+
+    #[cfg(feature = "sound")]
+    pub(crate) fn play(&mut self, path: &str) {
+        if self.output.is_none() {
+            if self.output_failed {
+                return;
+            }
+            match rodio::OutputStream::try_default() {
+                Ok(output) => self.output = Some(output),
+                Err(err) => {
+                    tracing::warn!("cannot open audio output: {}", err);
+                    self.output_failed = true;
+                    return;
+                }
+            }
+        }
+        let (_, handle) = self.output.as_ref().unwrap();
+
+        let sound = self
+            .sounds
+            .entry(path.to_string())
+            .or_insert_with(|| Self::load(path));
+        let Some(sound) = sound else {
+            return;
+        };
+        if let Err(err) = handle.play_raw(sound.clone()) {
+            tracing::warn!("cannot play sound file '{}': {}", path, err);
+        }
+    }
+
+    /// Open and decode a sound file, logging why it's invalid.
+    #[cfg(feature = "sound")]
+    fn load(path: &str) -> Option<CachedSound> {
+        use std::fs::File;
+        use std::io::BufReader;
+
+        use rodio::Source as _;
+
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(err) => {
+                tracing::warn!("cannot open sound file '{}': {}", path, err);
+                return None;
+            }
+        };
+        match rodio::Decoder::new(BufReader::new(file)) {
+            Ok(source) => Some(source.convert_samples().buffered()),
+            Err(err) => {
+                tracing::warn!("cannot decode sound file '{}': {}", path, err);
+                None
+            }
+        }
+    }
+
+    #[cfg(not(feature = "sound"))]
+    pub(crate) fn play(&mut self, path: &str) {
+        tracing::trace!("'sound' feature disabled, not playing '{}'", path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_sounds_are_silent() {
+        let env = Env::with_theme();
+        assert_eq!(SoundId::Click.resolve(&env), None);
+        assert_eq!(SoundId::Error.resolve(&env), None);
+    }
+
+    #[test]
+    fn resolve_sound_path() {
+        const BEEP: Key<ArcStr> = Key::new("org.masonry.test.beep");
+
+        let env = Env::with_theme()
+            .adding(theme::SOUND_CLICK, "click.ogg")
+            .adding(BEEP, "beep.wav");
+        assert_eq!(SoundId::Click.resolve(&env).as_deref(), Some("click.ogg"));
+        assert_eq!(
+            SoundId::Custom(BEEP).resolve(&env).as_deref(),
+            Some("beep.wav")
+        );
+
+        let env = env.adding(theme::SOUNDS_ENABLED, false);
+        assert_eq!(SoundId::Click.resolve(&env), None);
+    }
+}
@@ -0,0 +1,449 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Decorations painted by a [`WidgetPod`](crate::WidgetPod) around its widget.
+//!
+//! A [`WidgetStyle`] lets any widget get a background, a border, rounded corners
+//! and padding without being wrapped in extra container widgets. Styles are set
+//! with the builder methods on `WidgetPod` (eg [`WidgetPod::with_background`]) or
+//! at runtime through [`WidgetMut`](crate::widget::WidgetMut).
+//!
+//! The widget itself is laid out and painted in the "content box": its coordinate
+//! space doesn't include the padding and border, which are added around it by the
+//! pod.
+//!
+//! Styles can also come from a [`StyleSheet`], which maps selectors (widget type,
+//! class, and states like hover or disabled) to style properties. The style sheet
+//! of an app is set with [`AppLauncher::with_style_sheet`] and can be replaced at
+//! runtime by submitting [`SET_STYLE_SHEET`](crate::command::SET_STYLE_SHEET).
+//! Classes are added to widgets with [`WidgetPod::with_class`] or
+//! [`WidgetMut::add_class`](crate::widget::WidgetMut::add_class).
+//!
+//! Properties set directly on a pod take precedence over the style sheet.
+//!
+//! [`WidgetPod::with_background`]: crate::WidgetPod::with_background
+//! [`WidgetPod::with_class`]: crate::WidgetPod::with_class
+//! [`AppLauncher::with_style_sheet`]: crate::AppLauncher::with_style_sheet
+
+use crate::kurbo::{Insets, RoundedRectRadii};
+use crate::{ArcStr, Color, Env, KeyOrValue, PaintCtx, RenderContext, WidgetState};
+
+/// The border of a [`WidgetStyle`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct BorderStyle {
+    /// The width of the border, in logical pixels.
+    pub width: KeyOrValue<f64>,
+    /// The color of the border.
+    pub color: KeyOrValue<Color>,
+}
+
+/// Style properties applied by a [`WidgetPod`](crate::WidgetPod) to its widget.
+///
+/// The default style has no background, no border and no padding.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WidgetStyle {
+    /// The color painted behind the widget, its padding and its border.
+    pub background: Option<KeyOrValue<Color>>,
+    /// The border drawn around the widget's padding.
+    pub border: Option<BorderStyle>,
+    /// The radius of the corners of the background and border.
+    pub corner_radius: KeyOrValue<RoundedRectRadii>,
+    /// The space between the border and the widget.
+    pub padding: KeyOrValue<Insets>,
+}
+
+impl Default for WidgetStyle {
+    fn default() -> Self {
+        WidgetStyle {
+            background: None,
+            border: None,
+            corner_radius: RoundedRectRadii::from_single_radius(0.0).into(),
+            padding: Insets::ZERO.into(),
+        }
+    }
+}
+
+impl WidgetStyle {
+    /// The total space added around the widget by padding and border.
+    pub fn insets(&self, env: &Env) -> Insets {
+        let padding = self.padding.resolve(env).nonnegative();
+        let border_width = self.border_width(env);
+        Insets::new(
+            padding.x0 + border_width,
+            padding.y0 + border_width,
+            padding.x1 + border_width,
+            padding.y1 + border_width,
+        )
+    }
+
+    fn border_width(&self, env: &Env) -> f64 {
+        self.border
+            .as_ref()
+            .map(|border| border.width.resolve(env).max(0.0))
+            .unwrap_or(0.0)
+    }
+
+    /// Paint the background and border around the current widget.
+    ///
+    /// `insets` are the insets previously returned by [`insets`](Self::insets);
+    /// they're applied to the widget's size to get the painted area.
+    pub(crate) fn paint(&self, ctx: &mut PaintCtx, insets: Insets, env: &Env) {
+        if self.background.is_none() && self.border.is_none() {
+            return;
+        }
+
+        let corner_radius = self.corner_radius.resolve(env);
+        let border_box = ctx.size().to_rect() + insets;
+
+        if let Some(background) = &self.background {
+            let shape = border_box.to_rounded_rect(corner_radius);
+            ctx.fill(shape, &background.resolve(env));
+        }
+
+        if let Some(border) = &self.border {
+            let border_width = border.width.resolve(env);
+            let shape = border_box
+                .inset(border_width / -2.0)
+                .to_rounded_rect(corner_radius);
+            ctx.stroke(shape, &border.color.resolve(env), border_width);
+        }
+    }
+}
+
+// --- STYLE SHEETS ---
+
+/// A widget state that a [`StyleSelector`] can depend on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PseudoClass {
+    /// The mouse is over the widget.
+    Hover,
+    /// The widget is active, eg a button being pressed.
+    Active,
+    /// The widget or one of its descendants has keyboard focus.
+    Focus,
+    /// The widget or one of its ancestors is disabled.
+    Disabled,
+}
+
+/// The states of a widget relevant to style resolution.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct PseudoClassState {
+    hover: bool,
+    active: bool,
+    focus: bool,
+    disabled: bool,
+}
+
+impl PseudoClassState {
+    pub(crate) fn new(state: &WidgetState) -> Self {
+        PseudoClassState {
+            hover: state.is_hot,
+            active: state.is_active,
+            focus: state.has_focus,
+            disabled: state.is_disabled(),
+        }
+    }
+
+    fn has(&self, pseudo_class: PseudoClass) -> bool {
+        match pseudo_class {
+            PseudoClass::Hover => self.hover,
+            PseudoClass::Active => self.active,
+            PseudoClass::Focus => self.focus,
+            PseudoClass::Disabled => self.disabled,
+        }
+    }
+}
+
+/// Describes which widgets a style sheet rule applies to.
+///
+/// A selector matches a widget if every one of its conditions holds. The default
+/// selector has no condition and matches every widget.
+///
+/// ```
+/// # use masonry::style::StyleSelector;
+/// // Equivalent to the CSS selector `Button.primary:hover`
+/// let selector = StyleSelector::widget("Button").class("primary").hover();
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StyleSelector {
+    widget_type: Option<ArcStr>,
+    classes: Vec<ArcStr>,
+    pseudo_classes: Vec<PseudoClass>,
+}
+
+impl StyleSelector {
+    /// A selector matching every widget.
+    pub fn any() -> Self {
+        Self::default()
+    }
+
+    /// A selector matching widgets of the given type.
+    ///
+    /// The type is given by its short name, without module path or generic
+    /// arguments, eg `"Button"` or `"Label"`.
+    pub fn widget(widget_type: impl Into<ArcStr>) -> Self {
+        StyleSelector {
+            widget_type: Some(widget_type.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Builder-style method to only match widgets with the given class.
+    pub fn class(mut self, class: impl Into<ArcStr>) -> Self {
+        self.classes.push(class.into());
+        self
+    }
+
+    /// Builder-style method to only match widgets in the given state.
+    pub fn state(mut self, pseudo_class: PseudoClass) -> Self {
+        self.pseudo_classes.push(pseudo_class);
+        self
+    }
+
+    /// Shorthand for `state(PseudoClass::Hover)`.
+    pub fn hover(self) -> Self {
+        self.state(PseudoClass::Hover)
+    }
+
+    /// Shorthand for `state(PseudoClass::Active)`.
+    pub fn active(self) -> Self {
+        self.state(PseudoClass::Active)
+    }
+
+    /// Shorthand for `state(PseudoClass::Focus)`.
+    pub fn focus(self) -> Self {
+        self.state(PseudoClass::Focus)
+    }
+
+    /// Shorthand for `state(PseudoClass::Disabled)`.
+    pub fn disabled(self) -> Self {
+        self.state(PseudoClass::Disabled)
+    }
+
+    /// Whether the selector matches a widget, ignoring its state.
+    fn matches_widget(&self, widget_type: &str, classes: &[ArcStr]) -> bool {
+        let type_matches = match &self.widget_type {
+            Some(expected) => &**expected == widget_type,
+            None => true,
+        };
+        type_matches && self.classes.iter().all(|class| classes.contains(class))
+    }
+
+    fn matches(&self, widget_type: &str, classes: &[ArcStr], state: PseudoClassState) -> bool {
+        self.matches_widget(widget_type, classes)
+            && self.pseudo_classes.iter().all(|&pc| state.has(pc))
+    }
+}
+
+/// A set of style properties applied by a style sheet rule.
+///
+/// Properties left to `None` aren't changed by the rule.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StyleProperties {
+    /// See [`WidgetStyle::background`].
+    pub background: Option<KeyOrValue<Color>>,
+    /// See [`WidgetStyle::border`].
+    pub border: Option<BorderStyle>,
+    /// See [`WidgetStyle::corner_radius`].
+    pub corner_radius: Option<KeyOrValue<RoundedRectRadii>>,
+    /// See [`WidgetStyle::padding`].
+    pub padding: Option<KeyOrValue<Insets>>,
+}
+
+impl StyleProperties {
+    /// Create an empty set of properties.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builder-style method for setting the background color.
+    pub fn background(mut self, color: impl Into<KeyOrValue<Color>>) -> Self {
+        self.background = Some(color.into());
+        self
+    }
+
+    /// Builder-style method for setting the border.
+    pub fn border(
+        mut self,
+        color: impl Into<KeyOrValue<Color>>,
+        width: impl Into<KeyOrValue<f64>>,
+    ) -> Self {
+        self.border = Some(BorderStyle {
+            color: color.into(),
+            width: width.into(),
+        });
+        self
+    }
+
+    /// Builder-style method for setting the corner radius.
+    pub fn corner_radius(mut self, radius: impl Into<KeyOrValue<RoundedRectRadii>>) -> Self {
+        self.corner_radius = Some(radius.into());
+        self
+    }
+
+    /// Builder-style method for setting the padding.
+    pub fn padding(mut self, padding: impl Into<KeyOrValue<Insets>>) -> Self {
+        self.padding = Some(padding.into());
+        self
+    }
+
+    fn apply_to(&self, style: &mut WidgetStyle) {
+        if let Some(background) = &self.background {
+            style.background = Some(background.clone());
+        }
+        if let Some(border) = &self.border {
+            style.border = Some(border.clone());
+        }
+        if let Some(corner_radius) = &self.corner_radius {
+            style.corner_radius = corner_radius.clone();
+        }
+        if let Some(padding) = &self.padding {
+            style.padding = padding.clone();
+        }
+    }
+}
+
+/// A list of rules mapping [selectors](StyleSelector) to [style properties](StyleProperties).
+///
+/// Rules are applied in the order they were added, so when several rules set the
+/// same property for a widget, the last one wins.
+///
+/// Values can be [`Key`](crate::Key)s, so style sheets can refer to theme values
+/// and follow [`Env`] changes.
+#[derive(Clone, Debug, Default)]
+pub struct StyleSheet {
+    rules: Vec<(StyleSelector, StyleProperties)>,
+}
+
+impl StyleSheet {
+    /// Create an empty style sheet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builder-style method for adding a rule.
+    pub fn with_rule(mut self, selector: StyleSelector, properties: StyleProperties) -> Self {
+        self.add_rule(selector, properties);
+        self
+    }
+
+    /// Add a rule, with a higher precedence than existing rules.
+    pub fn add_rule(&mut self, selector: StyleSelector, properties: StyleProperties) {
+        self.rules.push((selector, properties));
+    }
+
+    /// Whether some rule applying to the given widget depends on its state.
+    ///
+    /// If so, the widget's style must be recomputed when it changes state.
+    pub(crate) fn depends_on_state(&self, widget_type: &str, classes: &[ArcStr]) -> bool {
+        self.rules.iter().any(|(selector, _)| {
+            !selector.pseudo_classes.is_empty() && selector.matches_widget(widget_type, classes)
+        })
+    }
+
+    /// Compute the style of a widget.
+    ///
+    /// `own_style` is the style set on the widget's pod; its properties that
+    /// differ from the default style override the ones set by the style sheet.
+    pub(crate) fn resolve(
+        &self,
+        widget_type: &str,
+        classes: &[ArcStr],
+        state: PseudoClassState,
+        own_style: &WidgetStyle,
+    ) -> WidgetStyle {
+        if self.rules.is_empty() {
+            return own_style.clone();
+        }
+
+        let mut style = WidgetStyle::default();
+        for (selector, properties) in &self.rules {
+            if selector.matches(widget_type, classes, state) {
+                properties.apply_to(&mut style);
+            }
+        }
+
+        let default_style = WidgetStyle::default();
+        if own_style.background.is_some() {
+            style.background = own_style.background.clone();
+        }
+        if own_style.border.is_some() {
+            style.border = own_style.border.clone();
+        }
+        if own_style.corner_radius != default_style.corner_radius {
+            style.corner_radius = own_style.corner_radius.clone();
+        }
+        if own_style.padding != default_style.padding {
+            style.padding = own_style.padding.clone();
+        }
+        style
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn later_rules_override_earlier_ones() {
+        let sheet = StyleSheet::new()
+            .with_rule(
+                StyleSelector::any(),
+                StyleProperties::new()
+                    .background(Color::BLACK)
+                    .padding(Insets::uniform(4.0)),
+            )
+            .with_rule(
+                StyleSelector::widget("Button"),
+                StyleProperties::new().background(Color::WHITE),
+            )
+            .with_rule(
+                StyleSelector::widget("Button").class("danger").hover(),
+                StyleProperties::new().background(Color::RED),
+            );
+
+        let danger = [ArcStr::from("danger")];
+        let idle = PseudoClassState::default();
+        let hovered = PseudoClassState {
+            hover: true,
+            ..Default::default()
+        };
+        let own_style = WidgetStyle::default();
+
+        let label_style = sheet.resolve("Label", &danger, hovered, &own_style);
+        assert_eq!(label_style.background, Some(Color::BLACK.into()));
+        assert_eq!(label_style.padding, Insets::uniform(4.0).into());
+
+        let button_style = sheet.resolve("Button", &danger, idle, &own_style);
+        assert_eq!(button_style.background, Some(Color::WHITE.into()));
+
+        let button_style = sheet.resolve("Button", &danger, hovered, &own_style);
+        assert_eq!(button_style.background, Some(Color::RED.into()));
+
+        assert!(sheet.depends_on_state("Button", &danger));
+        assert!(!sheet.depends_on_state("Button", &[]));
+        assert!(!sheet.depends_on_state("Label", &danger));
+    }
+
+    #[test]
+    fn own_style_overrides_style_sheet() {
+        let sheet = StyleSheet::new().with_rule(
+            StyleSelector::any(),
+            StyleProperties::new()
+                .background(Color::BLACK)
+                .corner_radius(RoundedRectRadii::from_single_radius(3.0)),
+        );
+        let own_style = WidgetStyle {
+            background: Some(Color::WHITE.into()),
+            ..Default::default()
+        };
+
+        let style = sheet.resolve("Label", &[], PseudoClassState::default(), &own_style);
+        assert_eq!(style.background, Some(Color::WHITE.into()));
+        assert_eq!(
+            style.corner_radius,
+            RoundedRectRadii::from_single_radius(3.0).into()
+        );
+    }
+}
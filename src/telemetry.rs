@@ -0,0 +1,129 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Observing actions and events, for analytics and usage telemetry.
+//!
+//! A [`TelemetryHook`] installed with
+//! [`AppLauncher::with_telemetry`](crate::AppLauncher::with_telemetry) sees every
+//! [`Action`] submitted by a widget, and the events it selects with
+//! [`TelemetryHook::observes_event`], along with the id of the widget involved and a
+//! timestamp. Widgets don't need to be instrumented.
+//!
+//! Without a hook, nothing is recorded and widgets don't track which of them handled
+//! an event.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use instant::Instant;
+
+use crate::{Action, Event, WidgetId, WindowId};
+
+/// Receives the actions and events of the app, eg to send usage statistics.
+///
+/// The hook is called synchronously during the event passes, so it should only store
+/// the records, and send them elsewhere later.
+pub trait TelemetryHook {
+    /// Whether [`observe`](Self::observe) should be called for `event`.
+    ///
+    /// Called before the event is handled. Internal events are never observed. The
+    /// default observes no event, only actions.
+    fn observes_event(&self, event: &Event) -> bool {
+        let _ = event;
+        false
+    }
+
+    /// Record an action, or an event selected with
+    /// [`observes_event`](Self::observes_event) once it's handled.
+    fn observe(&mut self, record: TelemetryRecord<'_>);
+}
+
+/// What a [`TelemetryHook`] observes.
+#[derive(Clone, Copy, Debug)]
+pub enum Observed<'a> {
+    /// An action submitted by a widget.
+    Action(&'a Action),
+    /// An event sent to a window.
+    Event(&'a Event),
+}
+
+/// An action or event passed to a [`TelemetryHook`].
+#[derive(Clone, Copy, Debug)]
+pub struct TelemetryRecord<'a> {
+    /// The action or event.
+    pub observed: Observed<'a>,
+    /// The widget which submitted the action, or handled the event.
+    ///
+    /// `None` if no widget handled the event.
+    pub widget_id: Option<WidgetId>,
+    /// The window the action or event comes from.
+    pub window_id: WindowId,
+    /// When the action was submitted, or the event handled.
+    pub time: Instant,
+}
+
+/// The hook installed on the app, shared by its windows.
+pub(crate) type SharedTelemetryHook = Rc<RefCell<dyn TelemetryHook>>;
+
+#[cfg(test)]
+mod tests {
+    use druid_shell::MouseButton;
+
+    use super::*;
+    use crate::testing::{widget_ids, ModularWidget, TestHarness, TestWidgetExt};
+    use crate::widget::{Flex, SizedBox};
+    use crate::Size;
+
+    struct Recorder(Rc<RefCell<Vec<(String, Option<WidgetId>)>>>);
+
+    impl TelemetryHook for Recorder {
+        fn observes_event(&self, event: &Event) -> bool {
+            matches!(event, Event::MouseDown(_))
+        }
+
+        fn observe(&mut self, record: TelemetryRecord<'_>) {
+            let name = match record.observed {
+                Observed::Action(action) => format!("{:?}", action),
+                Observed::Event(event) => event.short_name().to_string(),
+            };
+            self.0.borrow_mut().push((name, record.widget_id));
+        }
+    }
+
+    #[test]
+    fn observe_actions_and_handled_events() {
+        let [clickable_id, empty_id] = widget_ids();
+        let clickable = ModularWidget::new(())
+            .event_fn(|_, ctx, event, _| {
+                if let Event::MouseDown(_) = event {
+                    ctx.submit_action(Action::ButtonPressed);
+                    ctx.set_handled();
+                }
+            })
+            .layout_fn(|_, _, _, _| Size::new(50.0, 50.0))
+            .with_id(clickable_id);
+        let widget = Flex::row()
+            .with_child(clickable)
+            .with_child(SizedBox::empty().width(50.0).height(50.0).with_id(empty_id));
+
+        let mut harness = TestHarness::create(widget);
+        let records = Rc::new(RefCell::new(Vec::new()));
+        harness.set_telemetry_hook(Recorder(records.clone()));
+
+        harness.mouse_click_on(clickable_id);
+        assert_eq!(
+            *records.borrow(),
+            [
+                ("ButtonPressed".to_string(), Some(clickable_id)),
+                ("MouseDown".to_string(), Some(clickable_id)),
+            ]
+        );
+
+        // Events which no widget handles are observed without a widget id.
+        records.borrow_mut().clear();
+        harness.mouse_move_to(empty_id);
+        harness.mouse_button_press(MouseButton::Left);
+        assert_eq!(*records.borrow(), [("MouseDown".to_string(), None)]);
+    }
+}
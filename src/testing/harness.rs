@@ -4,7 +4,9 @@
 
 //! Tools and infrastructure for testing widgets.
 
+use std::cell::RefCell;
 use std::collections::{HashMap, VecDeque};
+use std::rc::Rc;
 use std::sync::Arc;
 
 use druid_shell::{KeyEvent, Modifiers, MouseButton, MouseButtons};
@@ -13,19 +15,28 @@ pub use druid_shell::{
     WindowState,
 };
 use image::io::Reader as ImageReader;
-use instant::Duration;
+use instant::{Duration, Instant};
 use shell::text::Selection;
 
 use super::screenshots::{get_image_diff, get_rgba_image};
 use super::snapshot_utils::get_cargo_workspace;
 use super::MockTimerQueue;
 use crate::action::{Action, ActionQueue};
+use crate::app_store::AnyAppStore;
 //use crate::ext_event::ExtEventHost;
 use crate::command::CommandQueue;
 use crate::contexts::GlobalPassCtx;
-use crate::debug_logger::DebugLogger;
-use crate::ext_event::ExtEventQueue;
-use crate::piet::{BitmapTarget, Device, ImageFormat, Piet};
+use crate::debug_logger::{DebugLogger, EventTrace, LayoutViolation};
+use crate::ext_event::{ExtEventQueue, ExtMessage};
+use crate::gamepad::GamepadEvent;
+use crate::image_cache::IMAGE_DECODED;
+use crate::piet::{Device, Error as PietError, Piet};
+use crate::print::PageSetup;
+use crate::render_backend;
+use crate::sound::SoundId;
+use crate::style::StyleSheet;
+use crate::telemetry::TelemetryHook;
+use crate::ui_state::UiState;
 use crate::widget::{StoreInWidgetMut, WidgetMut, WidgetRef};
 use crate::*;
 
@@ -146,7 +157,10 @@ struct MockAppRoot {
     window: WindowRoot,
     command_queue: CommandQueue,
     action_queue: ActionQueue,
+    played_sounds: VecDeque<SoundId>,
     debug_logger: DebugLogger,
+    // Messages sent with the window's `ExtEventSink`, eg promise results.
+    ext_event_queue: ExtEventQueue,
 }
 
 impl TestHarness {
@@ -159,22 +173,52 @@ impl TestHarness {
 
     /// Builds harness with given root widget and window size.
     pub fn create_with_size(root: impl Widget, window_size: Size) -> Self {
-        //let ext_host = ExtEventHost::default();
-        //let ext_handle = ext_host.make_sink();
+        Self::create_with_app_store(root, window_size, None, ImageCache::new())
+    }
+
+    /// Builds harness with given root widget, and an [`AppStore`] its widgets can
+    /// subscribe to.
+    ///
+    /// Window size will be [`HARNESS_DEFAULT_SIZE`].
+    pub fn create_with_store<T: 'static>(root: impl Widget, store: AppStore<T>) -> Self {
+        Self::create_with_app_store(
+            root,
+            HARNESS_DEFAULT_SIZE,
+            Some(Rc::new(store)),
+            ImageCache::new(),
+        )
+    }
 
-        // FIXME
+    /// Builds harness with given root widget, sharing the given [`ImageCache`].
+    ///
+    /// Images inserted in the cache beforehand are available to the widgets as soon
+    /// as they're added. Window size will be [`HARNESS_DEFAULT_SIZE`].
+    pub fn create_with_image_cache(root: impl Widget, image_cache: ImageCache) -> Self {
+        Self::create_with_app_store(root, HARNESS_DEFAULT_SIZE, None, image_cache)
+    }
+
+    pub(crate) fn create_with_app_store(
+        root: impl Widget,
+        window_size: Size,
+        app_store: Option<Rc<dyn AnyAppStore>>,
+        image_cache: ImageCache,
+    ) -> Self {
         let event_queue = ExtEventQueue::new();
 
-        let window = WindowRoot::new(
+        let mut window = WindowRoot::new(
             WindowId::next(),
             Default::default(),
             event_queue.make_sink(),
-            Box::new(root),
+            WidgetPod::new(root).boxed(),
             "Masonry test app".into(),
             false,
             WindowSizePolicy::User,
+            RenderBackend::Platform,
             Some(MockTimerQueue::new()),
+            Default::default(),
         );
+        window.app_store = app_store;
+        window.image_cache = image_cache;
 
         let mouse_state = MouseEvent {
             pos: Point::ZERO,
@@ -187,13 +231,19 @@ impl TestHarness {
             wheel_delta: Vec2::ZERO,
         };
 
+        let mut debug_logger = DebugLogger::new(false);
+        debug_logger.record_event_traces = true;
+        debug_logger.record_layout_violations = true;
+
         let mut harness = TestHarness {
             mock_app: MockAppRoot {
                 env: Env::with_theme(),
                 window,
                 command_queue: VecDeque::new(),
                 action_queue: VecDeque::new(),
-                debug_logger: DebugLogger::new(false),
+                played_sounds: VecDeque::new(),
+                debug_logger,
+                ext_event_queue: event_queue,
             },
             mouse_state,
             window_size,
@@ -215,69 +265,198 @@ impl TestHarness {
     /// as will any resulting commands. Commands created as a result of this event
     /// will also be dispatched.
     pub fn process_event(&mut self, event: Event) {
+        self.mock_app.debug_logger.event_traces.clear();
         self.mock_app.event(event);
 
         self.process_state_after_event();
     }
 
+    /// Install a hook observing the actions and events of the window.
+    ///
+    /// This is what [`AppLauncher::with_telemetry`] does at runtime.
+    pub fn set_telemetry_hook(&mut self, hook: impl TelemetryHook + 'static) {
+        self.mock_app.window.telemetry = Some(Rc::new(RefCell::new(hook)));
+    }
+
+    /// Simulate the user asking to close the window.
+    ///
+    /// Sends [`Event::WindowCloseRequested`], and returns `true` if the window
+    /// would close, ie if no widget prevented it.
+    pub fn request_close(&mut self) -> bool {
+        self.mock_app.debug_logger.event_traces.clear();
+        let handled = self.mock_app.event(Event::WindowCloseRequested);
+        self.process_state_after_event();
+        !handled.is_handled()
+    }
+
     fn process_state_after_event(&mut self) {
         loop {
-            let cmd = self.mock_app.command_queue.pop_front();
-            match cmd {
-                Some(cmd) => self
-                    .mock_app
-                    .event(Event::Internal(InternalEvent::TargetedCommand(cmd))),
-                None => break,
-            };
-        }
+            loop {
+                let cmd = self.mock_app.command_queue.pop_front();
+                match cmd {
+                    // Sounds are recorded instead of played; see `pop_sound`.
+                    Some(cmd) if cmd.is(command::PLAY_SOUND) => self
+                        .mock_app
+                        .played_sounds
+                        .push_back(cmd.get(command::PLAY_SOUND).clone()),
+                    Some(cmd) if cmd.is(command::SET_STYLE_SHEET) => {
+                        if let Some(style_sheet) = cmd.get(command::SET_STYLE_SHEET).take() {
+                            self.mock_app.window.set_style_sheet(Rc::new(style_sheet));
+                        }
+                    }
+                    Some(cmd) if cmd.is(command::TOGGLE_DEBUG_SWITCH) => {
+                        let switch = *cmd.get(command::TOGGLE_DEBUG_SWITCH);
+                        let env = switch.toggle(&self.mock_app.env);
+                        self.mock_app.set_env(env);
+                    }
+                    Some(cmd) if cmd.is(command::SET_LOW_POWER_MODE) => {
+                        let low_power = *cmd.get(command::SET_LOW_POWER_MODE);
+                        self.mock_app.set_low_power_override(low_power);
+                    }
+                    Some(cmd) if cmd.is(command::SET_UI_SCALE) => {
+                        let ui_scale = *cmd.get(command::SET_UI_SCALE);
+                        self.mock_app.set_ui_scale(ui_scale);
+                    }
+                    Some(cmd) if cmd.is(command::SCRUB_FRAME) => {
+                        let frames_back = *cmd.get(command::SCRUB_FRAME);
+                        self.mock_app.window.scrub_frame(frames_back);
+                    }
+                    Some(cmd) if cmd.is(command::CAPTURE_WIDGET) => {
+                        let request = cmd.get(command::CAPTURE_WIDGET);
+                        let command::CaptureWidget { widget, scale } = *request.payload();
+                        if let Some(image) = self.mock_app.capture_widget(widget, scale) {
+                            request.respond(image);
+                        }
+                    }
+                    Some(cmd) if cmd.is(command::PRINT_PAGES) => {
+                        let request = cmd.get(command::PRINT_PAGES);
+                        let command::PrintPages { widget, setup } = *request.payload();
+                        if let Some(pages) = self.mock_app.print_widget(widget, setup) {
+                            request.respond(pages);
+                        }
+                    }
+                    Some(cmd) if cmd.is(IMAGE_DECODED) => {
+                        let image_cache = &self.mock_app.window.image_cache;
+                        let loaded = image_cache.finish_decode(cmd.get(IMAGE_DECODED));
+                        self.mock_app.command_queue.extend(loaded);
+                    }
+                    Some(cmd) if cmd.is(command::SET_ENV) => {
+                        if let Some(env) = cmd.get(command::SET_ENV).take() {
+                            self.mock_app.set_env(env);
+                        }
+                    }
+                    Some(cmd) => self
+                        .mock_app
+                        .event(Event::Internal(InternalEvent::TargetedCommand(cmd))),
+                    None => match self.mock_app.ext_event_queue.recv() {
+                        Some(ExtMessage::Command(selector, payload, target)) => self
+                            .mock_app
+                            .command_queue
+                            .push_back(Command::from_ext(selector, payload, target)),
+                        Some(ExtMessage::Promise(result, widget_id, _)) => {
+                            self.mock_app
+                                .event(Event::Internal(InternalEvent::RoutePromiseResult(
+                                    result, widget_id,
+                                )))
+                        }
+                        None => {
+                            let changed_widgets = self.mock_app.take_store_changed_widgets();
+                            if changed_widgets.is_empty() {
+                                break;
+                            }
+                            for id in changed_widgets {
+                                self.mock_app
+                                    .command_queue
+                                    .push_back(command::STORE_CHANGED.to(Target::Widget(id)));
+                            }
+                        }
+                    },
+                };
+            }
 
-        // TODO - this might be too coarse
-        if self.root_widget().state().needs_layout {
+            // TODO - this might be too coarse
+            if !self.root_widget().state().needs_layout {
+                break;
+            }
             self.mock_app.layout();
             *self.window_mut().invalid_mut() = Region::from(self.window_size.to_rect());
-        }
-    }
-
-    fn render_to(&mut self, render_target: &mut BitmapTarget) {
-        /// A way to clean up resources when our render context goes out of
-        /// scope, even during a panic.
-        pub struct RenderContextGuard<'a>(Piet<'a>);
 
-        impl Drop for RenderContextGuard<'_> {
-            fn drop(&mut self) {
-                // We need to call finish even if a test assert failed
-                if let Err(err) = self.0.finish() {
-                    // We can't panic, because we might already be panicking
-                    tracing::error!("piet finish failed: {}", err);
-                }
+            // Widgets may submit commands during layout.
+            if self.mock_app.command_queue.is_empty() {
+                break;
             }
         }
+    }
 
-        let mut piet = RenderContextGuard(render_target.render_context());
+    /// Paint the invalid region of the window into a new bitmap.
+    ///
+    /// This uses the same code path as [`RenderBackend::Software`].
+    fn render_to_image(&mut self) -> ImageBuf {
+        self.render_to_image_with_scale(1.0)
+    }
 
+    pub(crate) fn render_to_image_with_scale(&mut self, scale: f64) -> ImageBuf {
+        self.try_render_to_image_with_scale(scale)
+            .expect("failed to render to bitmap")
+    }
+
+    /// Paint the invalid region of the window into a new bitmap, or return the error
+    /// if no graphics device is available.
+    pub(crate) fn try_render_to_image_with_scale(
+        &mut self,
+        scale: f64,
+    ) -> Result<ImageBuf, PietError> {
+        let mut device = Device::new()?;
         // FIXME - this doesn't make sense given we might render to a fresh surface
         let invalid = std::mem::replace(self.window_mut().invalid_mut(), Region::EMPTY);
-        self.mock_app.paint_region(&mut piet.0, &invalid);
+        let mock_app = &mut self.mock_app;
+        render_backend::render_to_image(&mut device, self.window_size, scale, |piet| {
+            mock_app.paint_region(piet, &invalid);
+        })
     }
 
     /// Create a Piet bitmap render context (an array of pixels), paint the
     /// window and return the bitmap.
     pub fn render(&mut self) -> Arc<[u8]> {
-        let mut device = Device::new().expect("harness failed to get device");
-        let mut render_target = device
-            .bitmap_target(
-                self.window_size.width as usize,
-                self.window_size.height as usize,
-                1.0,
-            )
-            .expect("failed to create bitmap_target");
+        self.render_to_image().raw_pixels_shared()
+    }
+
+    /// Paint the given widget and its children into a new bitmap, the size of
+    /// the widget.
+    ///
+    /// This is what [`CAPTURE_WIDGET`](command::CAPTURE_WIDGET) does at runtime.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the widget isn't in the tree, or is stashed.
+    pub fn render_widget_to_image(&mut self, id: WidgetId) -> ImageBuf {
+        self.try_render_widget_to_image(id)
+            .unwrap_or_else(|| panic!("could not paint widget {:?}", id))
+    }
 
-        self.render_to(&mut render_target);
+    /// Paint the given widget and its children into a new bitmap, or return `None`
+    /// if it can't be painted.
+    pub(crate) fn try_render_widget_to_image(&mut self, id: WidgetId) -> Option<ImageBuf> {
+        let image = self.mock_app.capture_widget(id, 1.0);
+        self.process_state_after_event();
+        image
+    }
 
-        render_target
-            .to_image_buf(ImageFormat::RgbaPremul)
-            .unwrap()
-            .raw_pixels_shared()
+    /// Lay out the given widget and its children at the width of a page, and
+    /// paint them onto as many pages as needed.
+    ///
+    /// This is what [`PRINT_PAGES`](command::PRINT_PAGES) does at runtime.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the widget isn't in the tree, or is stashed.
+    pub fn print_widget(&mut self, id: WidgetId, setup: PageSetup) -> Vec<ImageBuf> {
+        let pages = self
+            .mock_app
+            .print_widget(id, setup)
+            .unwrap_or_else(|| panic!("could not print widget {:?}", id));
+        self.process_state_after_event();
+        pages
     }
 
     // --- Event helpers ---
@@ -330,6 +509,37 @@ impl TestHarness {
         self.mouse_button_release(MouseButton::Left);
     }
 
+    /// Send a pen event made of the given samples, in window coordinates.
+    ///
+    /// The last sample is the event's position; all of them are in
+    /// [`PenEvent::coalesced`].
+    pub fn pen_event(&mut self, kind: PenEventKind, samples: impl IntoIterator<Item = PenSample>) {
+        let event = PenEvent::new(kind, samples.into_iter().collect());
+        self.process_event(Event::Pen(event));
+    }
+
+    /// Send events drawing a stroke through the given samples, in window coordinates.
+    ///
+    /// The pen touches down on the first sample, moves through the others in a
+    /// single coalesced event, then leaves the surface at the last one.
+    pub fn pen_stroke(&mut self, samples: &[PenSample]) {
+        let (first, rest) = samples
+            .split_first()
+            .expect("a stroke needs at least one sample");
+        self.pen_event(PenEventKind::Down, [*first]);
+        if !rest.is_empty() {
+            self.pen_event(PenEventKind::Move, rest.iter().copied());
+        }
+        let last = samples.last().unwrap();
+        self.pen_event(
+            PenEventKind::Up,
+            [PenSample {
+                pressure: 0.0,
+                ..*last
+            }],
+        );
+    }
+
     /// Use [`mouse_move`](Self::mouse_move) to set the internal mouse pos to the center of the given widget.
     pub fn mouse_move_to(&mut self, id: WidgetId) {
         // FIXME - handle case where the widget isn't visible
@@ -340,6 +550,23 @@ impl TestHarness {
         self.mouse_move(widget_center);
     }
 
+    /// Simulate the user picking the item labelled `label` in the window's context menu.
+    ///
+    /// ## Panics
+    ///
+    /// If no context menu is shown, or it has no enabled item with that label.
+    pub fn select_context_menu_item(&mut self, label: &str) {
+        let window = &mut self.mock_app.window;
+        let id = window
+            .context_menu()
+            .expect("no context menu is shown")
+            .item_id(label)
+            .unwrap_or_else(|| panic!("no context menu item labelled '{}'", label));
+        let cmd = window.take_context_menu_command(id).unwrap();
+        self.mock_app.command_queue.push_back(cmd);
+        self.process_state_after_event();
+    }
+
     // TODO - Handle complicated IME
 
     /// Simulate typing the given text.
@@ -378,6 +605,16 @@ impl TestHarness {
         self.process_state_after_event();
     }
 
+    /// Simulate a gamepad event.
+    ///
+    /// The event is sent to the focused widget, and used to navigate if it isn't
+    /// handled; see [`gamepad`](crate::gamepad).
+    pub fn gamepad_event(&mut self, event: GamepadEvent) {
+        self.mock_app.debug_logger.event_traces.clear();
+        self.mock_app.gamepad_event(event);
+        self.process_state_after_event();
+    }
+
     #[doc(alias = "send_command")]
     /// Send a command to a target.
     pub fn submit_command(&mut self, command: impl Into<Command>) {
@@ -386,6 +623,77 @@ impl TestHarness {
         self.process_event(event);
     }
 
+    /// Replace the style sheet, as if [`SET_STYLE_SHEET`](command::SET_STYLE_SHEET)
+    /// had been submitted.
+    pub fn set_style_sheet(&mut self, style_sheet: StyleSheet) {
+        self.mock_app.window.set_style_sheet(Rc::new(style_sheet));
+        self.process_state_after_event();
+    }
+
+    /// Restore a [`UiState`] previously returned by [`WindowRoot::save_ui_state`].
+    pub fn restore_ui_state(&mut self, state: UiState) {
+        self.mock_app.window.restore_ui_state(
+            state,
+            &mut self.mock_app.debug_logger,
+            &mut self.mock_app.command_queue,
+            &mut self.mock_app.action_queue,
+            &self.mock_app.env,
+        );
+        self.process_state_after_event();
+    }
+
+    /// Replace the [`Env`], as if [`SET_ENV`](command::SET_ENV) had been submitted.
+    pub fn set_env(&mut self, env: Env) {
+        self.mock_app.set_env(env);
+        self.process_state_after_event();
+    }
+
+    /// Simulate part of the window being obscured, as if the platform had reported
+    /// a new safe area.
+    ///
+    /// See [`LifeCycleCtx::safe_area`](crate::LifeCycleCtx::safe_area).
+    pub fn set_safe_area(&mut self, safe_area: Insets) {
+        let mock_app = &mut self.mock_app;
+        mock_app.window.set_safe_area(
+            safe_area,
+            &mut mock_app.debug_logger,
+            &mut mock_app.command_queue,
+            &mut mock_app.action_queue,
+            &mock_app.env,
+        );
+        self.process_state_after_event();
+    }
+
+    /// Simulate the window being minimized and restored.
+    ///
+    /// See [`AppRoot::set_window_visible`](crate::AppRoot::set_window_visible).
+    pub fn set_window_visible(&mut self, is_visible: bool) {
+        let mock_app = &mut self.mock_app;
+        mock_app.window.set_window_visible(
+            is_visible,
+            &mut mock_app.debug_logger,
+            &mut mock_app.command_queue,
+            &mut mock_app.action_queue,
+            &mock_app.env,
+        );
+        self.process_state_after_event();
+    }
+
+    /// Simulate the user resizing the window.
+    pub fn set_window_size(&mut self, window_size: Size) {
+        self.window_size = window_size;
+        self.process_event(Event::WindowSize(window_size));
+    }
+
+    /// Compute the layout of the window in physical pixels.
+    ///
+    /// The test window has a scale of 1, so sizes and positions are rounded to
+    /// integers. See [`WindowDescription::integer_layout`](crate::WindowDescription::integer_layout).
+    pub fn set_integer_layout(&mut self, integer_layout: bool) {
+        self.mock_app.window.set_integer_layout(integer_layout);
+        self.process_state_after_event();
+    }
+
     /// Simulate the passage of time.
     ///
     /// If you create any timer in a widget, this method is the only way to trigger
@@ -395,16 +703,36 @@ impl TestHarness {
     /// **(TODO - Doesn't move animations forward.)**
     pub fn move_timers_forward(&mut self, duration: Duration) {
         // TODO - handle animations
-        let tokens = self
-            .mock_app
-            .window
-            .mock_timer_queue
-            .as_mut()
-            .unwrap()
-            .move_forward(duration);
+        let timer_queue = self.mock_app.window.mock_timer_queue.as_mut().unwrap();
+        let tokens = timer_queue.move_forward(duration);
+        let now = timer_queue.now();
         for token in tokens {
-            self.process_event(Event::Timer(token));
+            // The window fills in the deadline.
+            self.process_event(Event::Timer(TimerEvent {
+                token,
+                deadline: now,
+                fired_at: now,
+            }));
+        }
+    }
+
+    /// Wait for a message from another thread, eg the result of
+    /// [`EventCtx::compute_in_background`], and process the messages received.
+    ///
+    /// ## Panics
+    ///
+    /// If nothing is received before `timeout`.
+    pub fn wait_for_external_events(&mut self, timeout: Duration) {
+        let deadline = Instant::now() + timeout;
+        while !self.mock_app.ext_event_queue.has_pending_items() {
+            assert!(
+                Instant::now() < deadline,
+                "no external event received in {:?}",
+                timeout
+            );
+            std::thread::sleep(Duration::from_millis(1));
         }
+        self.process_state_after_event();
     }
 
     // --- Getters ---
@@ -419,6 +747,11 @@ impl TestHarness {
         &mut self.mock_app.window
     }
 
+    /// The app's env, before the window applies its UI scale and palette.
+    pub(crate) fn env(&self) -> &Env {
+        &self.mock_app.env
+    }
+
     /// Return the root widget.
     pub fn root_widget(&self) -> WidgetRef<'_, dyn Widget> {
         self.mock_app.window.root.as_dyn()
@@ -480,10 +813,24 @@ impl TestHarness {
                 &mut self.mock_app.command_queue,
                 &mut self.mock_app.action_queue,
                 &mut timers,
+                &mut window.broadcast_listeners,
+                &mut window.access_keys,
                 window.mock_timer_queue.as_mut(),
                 &window.handle,
                 window.id,
                 window.focus,
+                &window.style_sheet,
+                window.app_store.clone(),
+                window.image_cache.clone(),
+                window.text_metrics.clone(),
+                &mut window.offscreen_devices,
+                &mut window.mutate_callbacks,
+                window.safe_area,
+                window.frame_stats,
+                window.is_window_visible,
+                window.reduced_motion(&self.mock_app.env),
+                window.ui_scale,
+                window.telemetry.clone(),
             );
             fake_widget_state = window.root.state.clone();
 
@@ -519,6 +866,38 @@ impl TestHarness {
         res
     }
 
+    /// Get the path through the widget tree of the last event the window processed.
+    ///
+    /// This shows which widgets saw the event, which skipped it, and which one
+    /// handled it.
+    ///
+    /// If the event passed to [`process_event`](Self::process_event) submitted
+    /// commands, this is the trace of the last command; see
+    /// [`event_traces`](Self::event_traces). Helpers which send several events (eg a
+    /// click is a `MouseDown` then a `MouseUp`) only keep the traces of the last one.
+    pub fn event_trace(&self) -> &EventTrace {
+        self.mock_app
+            .debug_logger
+            .event_traces
+            .last()
+            .expect("no event was processed")
+    }
+
+    /// Get the paths through the widget tree of the last event passed to
+    /// [`process_event`](Self::process_event) and of the commands it submitted, in
+    /// the order they were processed.
+    pub fn event_traces(&self) -> &[EventTrace] {
+        &self.mock_app.debug_logger.event_traces
+    }
+
+    /// Return the layout protocol violations reported since the last call, eg widgets
+    /// returning a size outside of their constraints.
+    ///
+    /// Violations are only detected in debug builds.
+    pub fn take_layout_violations(&mut self) -> Vec<LayoutViolation> {
+        std::mem::take(&mut self.mock_app.debug_logger.layout_violations)
+    }
+
     /// Pop next action from the queue
     ///
     /// Note: Actions are still a WIP feature.
@@ -527,6 +906,13 @@ impl TestHarness {
         Some((action, widget_id))
     }
 
+    /// Pop the next sound requested with `play_sound`.
+    ///
+    /// The harness never plays sounds; it records them so tests can check them.
+    pub fn pop_sound(&mut self) -> Option<SoundId> {
+        self.mock_app.played_sounds.pop_front()
+    }
+
     // --- Screenshots ---
 
     /// Method used by [`assert_render_snapshot`]. Use the macro instead.
@@ -553,18 +939,7 @@ impl TestHarness {
             return;
         }
 
-        let mut device = Device::new().expect("harness failed to get device");
-        let mut render_target = device
-            .bitmap_target(
-                self.window_size.width as usize,
-                self.window_size.height as usize,
-                1.0,
-            )
-            .expect("failed to create bitmap_target");
-
-        self.render_to(&mut render_target);
-
-        let new_image = get_rgba_image(&mut render_target, self.window_size);
+        let new_image = get_rgba_image(&self.render_to_image(), self.window_size);
 
         let workspace_path = get_cargo_workspace(manifest_dir);
         let test_file_path_abs = workspace_path.join(test_file_path);
@@ -639,6 +1014,24 @@ impl MockAppRoot {
         );
     }
 
+    fn take_store_changed_widgets(&mut self) -> Vec<WidgetId> {
+        let Some(store) = &self.window.app_store else {
+            return Vec::new();
+        };
+        let window = &self.window;
+        store.take_changed_widgets(&|id| window.may_contain_widget(id))
+    }
+
+    fn set_env(&mut self, env: Env) {
+        let changed_keys = self.env.changed_keys(&env);
+        self.env = env;
+        if !changed_keys.is_empty() {
+            self.lifecycle(LifeCycle::Internal(InternalLifeCycle::RouteEnvChanged(
+                Rc::new(changed_keys),
+            )));
+        }
+    }
+
     fn layout(&mut self) {
         self.window.layout(
             &mut self.debug_logger,
@@ -648,6 +1041,58 @@ impl MockAppRoot {
         );
     }
 
+    fn gamepad_event(&mut self, event: GamepadEvent) -> Handled {
+        self.window.gamepad_event(
+            event,
+            &mut self.debug_logger,
+            &mut self.command_queue,
+            &mut self.action_queue,
+            &self.env,
+        )
+    }
+
+    fn capture_widget(&mut self, widget_id: WidgetId, scale: f64) -> Option<ImageBuf> {
+        self.window.capture_widget(
+            widget_id,
+            scale,
+            &mut self.debug_logger,
+            &mut self.command_queue,
+            &mut self.action_queue,
+            &self.env,
+        )
+    }
+
+    fn print_widget(&mut self, widget_id: WidgetId, setup: PageSetup) -> Option<Vec<ImageBuf>> {
+        self.window.print_widget(
+            widget_id,
+            setup,
+            &mut self.debug_logger,
+            &mut self.command_queue,
+            &mut self.action_queue,
+            &self.env,
+        )
+    }
+
+    fn set_low_power_override(&mut self, low_power: Option<bool>) {
+        self.window.set_low_power_override(
+            low_power,
+            &mut self.debug_logger,
+            &mut self.command_queue,
+            &mut self.action_queue,
+            &self.env,
+        );
+    }
+
+    fn set_ui_scale(&mut self, ui_scale: f64) {
+        self.window.set_ui_scale(
+            ui_scale,
+            &mut self.debug_logger,
+            &mut self.command_queue,
+            &mut self.action_queue,
+            &self.env,
+        );
+    }
+
     fn paint_region(&mut self, piet: &mut Piet, invalid: &Region) {
         self.window.do_paint(
             piet,
@@ -5,7 +5,7 @@
 use std::collections::VecDeque;
 
 use druid_shell::TimerToken;
-use instant::Duration;
+use instant::{Duration, Instant};
 
 /// Handles timers for unit tests.
 ///
@@ -18,9 +18,14 @@ use instant::Duration;
 /// timer tokens and durations, and when the user calls [`TestHarness::move_timers_forward`],
 /// the timers are "manually" mutated and checked, and the matching events fired.
 ///
+/// Timers fire at the end of the `move_timers_forward` call that reaches their deadline,
+/// so a timer skipped over by a long step reports how late it is, like a platform timer
+/// delayed by a busy app would.
+///
 /// To avoid polluting the code with `#[cfg(test)]` annotations, MockTimerQueue is also
 /// present in non-test code, but it's always empty.
 pub(crate) struct MockTimerQueue {
+    pub start: Instant,
     pub current_time: Duration,
     pub queue: VecDeque<(Duration, TimerToken)>,
 }
@@ -28,11 +33,17 @@ pub(crate) struct MockTimerQueue {
 impl MockTimerQueue {
     pub(crate) fn new() -> Self {
         MockTimerQueue {
+            start: Instant::now(),
             current_time: Duration::ZERO,
             queue: VecDeque::new(),
         }
     }
 
+    /// The mocked current time.
+    pub(crate) fn now(&self) -> Instant {
+        self.start + self.current_time
+    }
+
     #[must_use]
     pub(crate) fn add_timer(&mut self, duration: Duration) -> TimerToken {
         let deadline = self.current_time + duration;
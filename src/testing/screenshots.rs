@@ -10,14 +10,10 @@ pub use druid_shell::{
 };
 use image::{GenericImageView as _, RgbaImage};
 
-use crate::piet::{BitmapTarget, ImageFormat};
-use crate::Size;
+use crate::{ImageBuf, Size};
 
-pub(crate) fn get_rgba_image(render_target: &mut BitmapTarget, window_size: Size) -> RgbaImage {
-    let pixels = render_target
-        .to_image_buf(ImageFormat::RgbaPremul)
-        .unwrap()
-        .raw_pixels_shared();
+pub(crate) fn get_rgba_image(image: &ImageBuf, window_size: Size) -> RgbaImage {
+    let pixels = image.raw_pixels_shared();
 
     RgbaImage::from_raw(
         window_size.width as u32,
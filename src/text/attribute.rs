@@ -6,6 +6,7 @@
 
 use std::ops::Range;
 
+use super::shaping::{FontFeature, ShapingAttribute};
 use super::FontDescriptor;
 use crate::piet::{Color, FontFamily, FontStyle, FontWeight, TextAttribute as PietAttr};
 use crate::{Env, KeyOrValue};
@@ -28,6 +29,10 @@ pub struct AttributeSpans {
     style: SpanSet<FontStyle>,
     underline: SpanSet<bool>,
     font_descriptor: SpanSet<KeyOrValue<FontDescriptor>>,
+    // One set of spans per feature tag, so that features don't override each other.
+    features: Vec<([u8; 4], SpanSet<u32>)>,
+    letter_spacing: SpanSet<KeyOrValue<f64>>,
+    word_spacing: SpanSet<KeyOrValue<f64>>,
 }
 
 /// A set of spans for a given attribute.
@@ -90,6 +95,15 @@ pub enum Attribute {
     Underline(bool),
     /// A [`FontDescriptor`](struct.FontDescriptor.html).
     Descriptor(KeyOrValue<FontDescriptor>),
+    /// An OpenType [`FontFeature`], such as tabular numbers.
+    ///
+    /// Settings for different features combine; a setting replaces earlier settings
+    /// of the same feature.
+    FontFeature(FontFeature),
+    /// Extra space added after each character, in points.
+    LetterSpacing(KeyOrValue<f64>),
+    /// Extra space added to each space character, in points.
+    WordSpacing(KeyOrValue<f64>),
 }
 
 impl Link {
@@ -120,7 +134,46 @@ impl AttributeSpans {
             Attribute::Style(attr) => self.style.add(Span::new(range, attr)),
             Attribute::Underline(attr) => self.underline.add(Span::new(range, attr)),
             Attribute::Descriptor(attr) => self.font_descriptor.add(Span::new(range, attr)),
+            Attribute::FontFeature(feature) => {
+                let span = Span::new(range, feature.value());
+                match self
+                    .features
+                    .iter_mut()
+                    .find(|(tag, _)| *tag == feature.tag())
+                {
+                    Some((_, spans)) => spans.add(span),
+                    None => {
+                        let mut spans = SpanSet::default();
+                        spans.add(span);
+                        self.features.push((feature.tag(), spans));
+                    }
+                }
+            }
+            Attribute::LetterSpacing(attr) => self.letter_spacing.add(Span::new(range, attr)),
+            Attribute::WordSpacing(attr) => self.word_spacing.add(Span::new(range, attr)),
+        }
+    }
+
+    /// The attributes which affect shaping, which Piet handles separately.
+    pub(crate) fn to_shaping_attrs(&self, env: &Env) -> Vec<(Range<usize>, ShapingAttribute)> {
+        let mut items = Vec::new();
+        for (tag, spans) in &self.features {
+            items.extend(spans.iter().map(|s| {
+                let feature = FontFeature::new(*tag, s.attr);
+                (s.range.clone(), ShapingAttribute::Feature(feature))
+            }));
         }
+        items.extend(self.letter_spacing.iter().map(|s| {
+            let spacing = s.attr.resolve(env);
+            (s.range.clone(), ShapingAttribute::LetterSpacing(spacing))
+        }));
+        items.extend(self.word_spacing.iter().map(|s| {
+            let spacing = s.attr.resolve(env);
+            (s.range.clone(), ShapingAttribute::WordSpacing(spacing))
+        }));
+
+        items.sort_by(|a, b| a.0.start.cmp(&b.0.start));
+        items
     }
 
     pub(crate) fn to_piet_attrs(&self, env: &Env) -> Vec<(Range<usize>, PietAttr)> {
@@ -325,6 +378,21 @@ impl Attribute {
     pub fn font_descriptor(font: impl Into<KeyOrValue<FontDescriptor>>) -> Self {
         Attribute::Descriptor(font.into())
     }
+
+    /// Create a new OpenType feature attribute.
+    pub fn font_feature(feature: FontFeature) -> Self {
+        Attribute::FontFeature(feature)
+    }
+
+    /// Create a new letter spacing attribute.
+    pub fn letter_spacing(spacing: impl Into<KeyOrValue<f64>>) -> Self {
+        Attribute::LetterSpacing(spacing.into())
+    }
+
+    /// Create a new word spacing attribute.
+    pub fn word_spacing(spacing: impl Into<KeyOrValue<f64>>) -> Self {
+        Attribute::WordSpacing(spacing.into())
+    }
 }
 
 impl<T> Default for SpanSet<T> {
@@ -353,6 +421,54 @@ mod tests {
         assert_eq!(&spans.spans, &vec![Span::new(0..5, 3), Span::new(5..20, 4)]);
     }
 
+    #[test]
+    fn spacing_spans_merge() {
+        let mut spans = AttributeSpans::new();
+        spans.add(0..10, Attribute::letter_spacing(1.0));
+        spans.add(3..5, Attribute::letter_spacing(2.0));
+        spans.add(4..8, Attribute::word_spacing(4.0));
+
+        let attrs = spans.to_shaping_attrs(&Env::empty());
+        assert_eq!(
+            attrs,
+            vec![
+                (0..3, ShapingAttribute::LetterSpacing(1.0)),
+                (3..5, ShapingAttribute::LetterSpacing(2.0)),
+                (4..8, ShapingAttribute::WordSpacing(4.0)),
+                (5..10, ShapingAttribute::LetterSpacing(1.0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn font_features_combine() {
+        let mut spans = AttributeSpans::new();
+        spans.add(0..10, Attribute::font_feature(FontFeature::TABULAR_NUMBERS));
+        spans.add(5..10, Attribute::font_feature(FontFeature::NO_LIGATURES));
+        spans.add(
+            8..10,
+            Attribute::font_feature(FontFeature::PROPORTIONAL_NUMBERS),
+        );
+        spans.add(0..4, Attribute::letter_spacing(1.5));
+
+        let attrs = spans.to_shaping_attrs(&Env::empty());
+        assert_eq!(
+            attrs,
+            vec![
+                (
+                    0..10,
+                    ShapingAttribute::Feature(FontFeature::TABULAR_NUMBERS)
+                ),
+                (0..4, ShapingAttribute::LetterSpacing(1.5)),
+                (5..10, ShapingAttribute::Feature(FontFeature::NO_LIGATURES)),
+                (
+                    8..10,
+                    ShapingAttribute::Feature(FontFeature::PROPORTIONAL_NUMBERS)
+                ),
+            ]
+        );
+    }
+
     #[test]
     fn edit_spans() {
         let mut spans = SpanSet::<u32>::default();
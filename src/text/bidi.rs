@@ -0,0 +1,255 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Bidirectional text: paragraph direction, visual order and caret movement.
+//!
+//! Text mixing left-to-right and right-to-left scripts is stored in logical order,
+//! and displayed in runs of each direction, following the [Unicode Bidirectional
+//! Algorithm]. The functions in this module work on the graphemes of one line,
+//! sorted in the order they are displayed, from left to right.
+//!
+//! [Unicode Bidirectional Algorithm]: https://unicode.org/reports/tr9/
+
+use std::ops::Range;
+
+use unicode_bidi::{bidi_class, BidiClass, BidiInfo};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::text::WritingDirection;
+
+/// How the left and right arrow keys move the caret in text mixing directions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaretMovement {
+    /// The caret moves to the grapheme displayed next to it, as most platforms do.
+    Visual,
+    /// The caret moves to the next or previous grapheme in the text, which may be
+    /// displayed elsewhere on the line. Right moves forward in left-to-right
+    /// paragraphs, and backward in right-to-left ones.
+    Logical,
+}
+
+impl Default for CaretMovement {
+    fn default() -> Self {
+        CaretMovement::Visual
+    }
+}
+
+/// A grapheme of a line, and the direction of the run it's displayed in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct VisualGrapheme {
+    pub range: Range<usize>,
+    pub is_rtl: bool,
+}
+
+/// The range of the paragraph containing `offset`, without its line break.
+fn paragraph_range(text: &str, offset: usize) -> Range<usize> {
+    let start = text[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let end = text[offset..].find('\n').map_or(text.len(), |i| offset + i);
+    start..end
+}
+
+/// The direction of the paragraph containing `offset`.
+///
+/// This is the direction of the paragraph's first strong character, or left-to-right
+/// if it has none. Paragraphs are separated by newlines.
+pub fn paragraph_direction(text: &str, offset: usize) -> WritingDirection {
+    let paragraph = &text[paragraph_range(text, offset)];
+    if crate::piet::util::first_strong_rtl(paragraph) {
+        WritingDirection::RightToLeft
+    } else {
+        WritingDirection::LeftToRight
+    }
+}
+
+/// Returns `true` if some of `text` may be displayed right-to-left.
+pub(crate) fn has_rtl(text: &str) -> bool {
+    text.chars().any(|c| {
+        matches!(
+            bidi_class(c),
+            BidiClass::R | BidiClass::AL | BidiClass::RLE | BidiClass::RLO | BidiClass::RLI
+        )
+    })
+}
+
+/// The graphemes of `line`, in display order.
+///
+/// The line's trailing line break isn't included.
+pub(crate) fn visual_graphemes(text: &str, line: Range<usize>) -> Vec<VisualGrapheme> {
+    let line_text = text[line.clone()].trim_end_matches(|c| c == '\n' || c == '\r');
+    if line_text.is_empty() {
+        return Vec::new();
+    }
+    let paragraph = paragraph_range(text, line.start);
+    let line = (line.start - paragraph.start)..(line.start - paragraph.start + line_text.len());
+
+    // Only the paragraph is analysed; its offsets are relative to its start.
+    let bidi = BidiInfo::new(&text[paragraph.clone()], None);
+    let para = match bidi
+        .paragraphs
+        .iter()
+        .find(|para| para.range.contains(&line.start))
+    {
+        Some(para) => para,
+        None => return Vec::new(),
+    };
+    let line = line.start..line.end.min(para.range.end);
+    let (levels, runs) = bidi.visual_runs(para, line);
+
+    let mut graphemes = Vec::new();
+    for run in runs {
+        let is_rtl = levels[run.start].is_rtl();
+        let run_start = graphemes.len();
+        let offset = paragraph.start + run.start;
+        graphemes.extend(bidi.text[run].grapheme_indices(true).map(|(i, grapheme)| {
+            VisualGrapheme {
+                range: (offset + i)..(offset + i + grapheme.len()),
+                is_rtl,
+            }
+        }));
+        if is_rtl {
+            graphemes[run_start..].reverse();
+        }
+    }
+    graphemes
+}
+
+/// The text offset of the caret displayed left of `graphemes[i]`, or right of the
+/// last one if `i == graphemes.len()`.
+///
+/// This is the offset at which the text layout draws the caret: the start of a
+/// left-to-right grapheme is its left edge, and the start of a right-to-left one
+/// its right edge.
+fn caret_offset(graphemes: &[VisualGrapheme], i: usize) -> usize {
+    match graphemes.get(i) {
+        Some(grapheme) if grapheme.is_rtl => grapheme.range.end,
+        Some(grapheme) => grapheme.range.start,
+        None => {
+            let last = &graphemes[i - 1];
+            if last.is_rtl {
+                last.range.start
+            } else {
+                last.range.end
+            }
+        }
+    }
+}
+
+/// The offset of the caret displayed next to the caret at `offset`, to its right or left.
+///
+/// Returns `None` if the caret is at that end of `line`, or isn't on it.
+pub(crate) fn visual_caret_move(
+    text: &str,
+    line: Range<usize>,
+    offset: usize,
+    to_right: bool,
+) -> Option<usize> {
+    let graphemes = visual_graphemes(text, line);
+    if graphemes.is_empty() {
+        return None;
+    }
+    let mut position = (0..=graphemes.len()).find(|i| caret_offset(&graphemes, *i) == offset)?;
+    // Where runs meet, neighbouring positions can have the same offset.
+    loop {
+        position = if to_right {
+            Some(position + 1).filter(|position| *position <= graphemes.len())?
+        } else {
+            position.checked_sub(1)?
+        };
+        let new_offset = caret_offset(&graphemes, position);
+        if new_offset != offset {
+            return Some(new_offset);
+        }
+    }
+}
+
+/// The x positions of the edges between `graphemes`, from left to right.
+///
+/// The layout only gives the x position of a grapheme's leading edge, from its start
+/// offset. The other edges are found by adding or subtracting the `width` of the
+/// grapheme laid out on its own.
+pub(crate) fn grapheme_edges(
+    graphemes: &[VisualGrapheme],
+    mut leading_edge: impl FnMut(usize) -> f64,
+    mut width: impl FnMut(Range<usize>) -> f64,
+) -> Vec<f64> {
+    if graphemes.is_empty() {
+        return Vec::new();
+    }
+    let mut known = vec![None; graphemes.len() + 1];
+    for (i, grapheme) in graphemes.iter().enumerate() {
+        let edge = if grapheme.is_rtl { i + 1 } else { i };
+        known[edge] = Some(leading_edge(grapheme.range.start));
+    }
+
+    let mut edges: Vec<f64> = Vec::with_capacity(known.len());
+    for (i, edge) in known.iter().enumerate() {
+        let x = match edge {
+            Some(x) => *x,
+            // The grapheme on the left is left-to-right, or it would have set this edge.
+            None if i > 0 => edges[i - 1] + width(graphemes[i - 1].range.clone()),
+            // The line starts with a right-to-left grapheme, which set its right edge.
+            None => known[1].unwrap_or(0.0) - width(graphemes[0].range.clone()),
+        };
+        edges.push(x);
+    }
+    edges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // "ab " followed by alef and bet, displayed as "ab " then bet, alef.
+    const MIXED: &str = "ab \u{5d0}\u{5d1}";
+
+    #[test]
+    fn paragraph_directions() {
+        let text = "abc\n\u{5d0}\u{5d1}\n123";
+        assert_eq!(paragraph_direction(text, 1), WritingDirection::LeftToRight);
+        assert_eq!(paragraph_direction(text, 6), WritingDirection::RightToLeft);
+        assert_eq!(paragraph_direction(text, 11), WritingDirection::LeftToRight);
+        assert!(has_rtl(text));
+        assert!(!has_rtl("abc 123"));
+    }
+
+    #[test]
+    fn visual_caret_positions() {
+        let line = 0..MIXED.len();
+        let graphemes = visual_graphemes(MIXED, line.clone());
+        let ranges: Vec<_> = graphemes.iter().map(|g| g.range.clone()).collect();
+        assert_eq!(ranges, [0..1, 1..2, 2..3, 5..7, 3..5]);
+
+        let mut offsets = vec![0];
+        while let Some(offset) =
+            visual_caret_move(MIXED, line.clone(), *offsets.last().unwrap(), true)
+        {
+            offsets.push(offset);
+        }
+        assert_eq!(offsets, [0, 1, 2, 7, 5, 3]);
+
+        let mut offsets = vec![3];
+        while let Some(offset) =
+            visual_caret_move(MIXED, line.clone(), *offsets.last().unwrap(), false)
+        {
+            offsets.push(offset);
+        }
+        assert_eq!(offsets, [3, 5, 7, 2, 1, 0]);
+    }
+
+    #[test]
+    fn edges_across_runs() {
+        let graphemes = visual_graphemes(MIXED, 0..MIXED.len());
+        // Each grapheme is 10 wide; the leading edge of alef is the right end of the line.
+        let leading_edge = |offset| match offset {
+            0 => 0.0,
+            1 => 10.0,
+            2 => 20.0,
+            3 => 50.0,
+            5 => 40.0,
+            _ => unreachable!(),
+        };
+        let edges = grapheme_edges(&graphemes, leading_edge, |_| 10.0);
+        assert_eq!(edges, [0.0, 10.0, 20.0, 30.0, 40.0, 50.0]);
+    }
+}
@@ -0,0 +1,326 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! A bare text editor, to build custom editing widgets on.
+
+use std::ops::Range;
+
+use smallvec::{smallvec, SmallVec};
+use tracing::{trace_span, Span};
+
+use super::{EditableText, ImeInvalidation, Movement, Selection, TextComponent, TextStorage};
+use crate::kurbo::Line;
+use crate::widget::{WidgetMut, WidgetRef};
+use crate::{
+    theme, BoxConstraints, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx,
+    Point, Rect, RenderContext, Size, StatusChange, Widget, WidgetPod,
+};
+
+/// Editable text, with a caret, selection and platform text input, but no
+/// decorations.
+///
+/// This has the editing machinery of [`TextBox`] without its background, border,
+/// placeholder or scrolling, for widgets such as code editors or terminals which
+/// draw those themselves. The widget takes the size of its text, paints the text,
+/// the selection and a blinking caret, and keeps the platform's input method in
+/// sync with the text.
+///
+/// The notifications of the inner [`TextComponent`], such as
+/// [`TextComponent::TEXT_CHANGED`] and [`TextComponent::RETURN`], aren't handled,
+/// so they reach the widget containing the editor.
+///
+/// [`TextBox`]: crate::widget::TextBox
+pub struct TextEditor<T> {
+    component: WidgetPod<TextComponent<T>>,
+    cursor_on: bool,
+}
+
+crate::declare_widget!(
+    TextEditorMut,
+    TextEditor<T: (TextStorage + EditableText)>
+);
+
+impl<T: TextStorage + EditableText> TextEditor<T> {
+    /// Create a new editor for `text`.
+    pub fn new(text: T) -> Self {
+        Self {
+            component: WidgetPod::new(TextComponent::new(text)),
+            cursor_on: false,
+        }
+    }
+
+    /// Builder-style method to let the editor insert newlines when return is pressed.
+    ///
+    /// By default, return sends the [`TextComponent::RETURN`] notification instead.
+    pub fn with_accepts_newlines(self, accepts_newlines: bool) -> Self {
+        {
+            let mut session = self.component.widget().borrow_mut();
+            session.set_accepts_newlines(accepts_newlines);
+            session.send_notification_on_return = !accepts_newlines;
+        }
+        self
+    }
+
+    /// The inner [`TextComponent`], to read its [`EditSession`](super::EditSession).
+    pub fn component(&self) -> &TextComponent<T> {
+        self.component.widget()
+    }
+
+    /// The edited text.
+    pub fn text(&self) -> T {
+        self.component
+            .widget()
+            .borrow()
+            .layout
+            .text()
+            .cloned()
+            .unwrap()
+    }
+
+    /// The current selection.
+    pub fn selection(&self) -> Selection {
+        self.component.widget().borrow().selection()
+    }
+
+    /// Returns `true` if an input method is composing text.
+    pub fn is_composing(&self) -> bool {
+        self.component.widget().is_composing()
+    }
+
+    /// The offset of the text under `point`, in the editor's coordinates.
+    pub fn text_position_for_point(&self, point: Point) -> usize {
+        self.component
+            .widget()
+            .borrow()
+            .layout
+            .text_position_for_point(point)
+    }
+
+    /// The bounding box of the text in `range`, in the editor's coordinates.
+    pub fn rect_for_range(&self, range: Range<usize>) -> Option<Rect> {
+        self.component.widget().borrow().rect_for_range(range)
+    }
+
+    /// The line the caret is drawn on, in the editor's coordinates.
+    pub fn caret_line(&self) -> Line {
+        let session = self.component.widget().borrow();
+        session.cursor_line_for_text_position(session.selection().active)
+    }
+
+    fn flush_ime_invalidation(&mut self, ctx: &mut EventCtx) {
+        let invalidation = self
+            .component
+            .widget()
+            .borrow_mut()
+            .pending_ime_invalidation();
+        if let Some(invalidation) = invalidation {
+            ctx.invalidate_text_input(invalidation);
+        }
+    }
+}
+
+impl<'a, 'b, T: TextStorage + EditableText> TextEditorMut<'a, 'b, T> {
+    /// The inner [`TextComponent`], eg to set the text color or font.
+    pub fn component_mut(&mut self) -> WidgetMut<'_, 'b, TextComponent<T>> {
+        self.ctx.get_mut(&mut self.widget.component)
+    }
+
+    /// Replace the text, leaving the selection where it is.
+    pub fn set_text(&mut self, new_text: impl Into<T>) {
+        if !self.widget.component.widget().can_write() {
+            tracing::warn!("set_text called with IME lock held.");
+            return;
+        }
+        self.component_mut().set_text(new_text);
+        self.flush_ime_invalidation();
+    }
+
+    /// Set the selection.
+    pub fn set_selection(&mut self, selection: Selection) {
+        if !self.widget.component.widget().can_write() {
+            tracing::warn!("set_selection called with IME lock held.");
+            return;
+        }
+        self.component_mut().set_selection(selection);
+        self.flush_ime_invalidation();
+    }
+
+    /// Move the caret, as the arrow keys would.
+    ///
+    /// If `extend` is `true`, the selection is extended to the new caret position.
+    pub fn move_caret(&mut self, movement: Movement, extend: bool) {
+        if !self.widget.component.widget().can_write() {
+            tracing::warn!("move_caret called with IME lock held.");
+            return;
+        }
+        self.component_mut().move_caret(movement, extend);
+        self.flush_ime_invalidation();
+    }
+
+    /// Replace the selection with `text`, and move the caret to the end of it.
+    ///
+    /// This doesn't send [`TextComponent::TEXT_CHANGED`].
+    pub fn insert_text(&mut self, text: &str) {
+        if !self.widget.component.widget().can_write() {
+            tracing::warn!("insert_text called with IME lock held.");
+            return;
+        }
+        self.component_mut().insert_text(text);
+        self.flush_ime_invalidation();
+    }
+
+    fn flush_ime_invalidation(&mut self) {
+        let invalidation = self
+            .widget
+            .component
+            .widget()
+            .borrow_mut()
+            .pending_ime_invalidation();
+        if let Some(invalidation) = invalidation {
+            self.ctx.invalidate_text_input(invalidation);
+        }
+    }
+}
+
+impl<T: TextStorage + EditableText> Widget for TextEditor<T> {
+    fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, env: &Env) {
+        match event {
+            Event::MouseDown(mouse) if self.component.widget().can_write() => {
+                if !ctx.is_disabled() && !mouse.focus {
+                    ctx.request_focus();
+                    self.cursor_on = true;
+                    ctx.reset_caret_blink();
+                }
+            }
+            Event::CaretBlink(visible) => {
+                self.cursor_on = *visible && !ctx.is_disabled();
+                ctx.request_paint();
+            }
+            Event::ImeStateChange => {
+                self.cursor_on = true;
+                ctx.reset_caret_blink();
+            }
+            _ => (),
+        }
+        self.component.on_event(ctx, event, env);
+        if self.component.widget().can_write() {
+            self.flush_ime_invalidation(ctx);
+        }
+    }
+
+    fn on_status_change(&mut self, ctx: &mut LifeCycleCtx, event: &StatusChange, _env: &Env) {
+        if let StatusChange::FocusChanged(focused) = event {
+            if !focused && self.component.widget().can_write() {
+                let selection = self.component.widget().borrow().selection();
+                let caret = Selection::caret(selection.active);
+                if self
+                    .component
+                    .widget()
+                    .borrow_mut()
+                    .set_selection(caret)
+                    .is_some()
+                {
+                    ctx.invalidate_text_input(ImeInvalidation::SelectionChanged);
+                }
+            }
+            ctx.get_mut(&mut self.component).set_focused(*focused);
+            self.cursor_on = *focused;
+            if *focused {
+                ctx.reset_caret_blink();
+            }
+            ctx.request_paint();
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, env: &Env) {
+        match event {
+            LifeCycle::WidgetAdded => {
+                ctx.register_text_input(self.component.widget().input_handler());
+                ctx.register_for_caret_blink();
+            }
+            LifeCycle::BuildFocusChain => {
+                ctx.register_for_focus();
+            }
+            _ => (),
+        }
+        self.component.lifecycle(ctx, event, env);
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, env: &Env) -> Size {
+        if !self.component.widget().can_write() {
+            tracing::warn!("Widget::layout called with outstanding IME lock.");
+        }
+        let size = self.component.layout(ctx, bc, env);
+        ctx.place_child(&mut self.component, Point::ORIGIN, env);
+
+        let metrics = self.component.widget().borrow().layout.layout_metrics();
+        ctx.set_baseline_offset(metrics.size.height - metrics.first_baseline);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, env: &Env) {
+        if !self.component.widget().can_read() {
+            tracing::warn!("Widget::paint called with outstanding IME lock, skipping");
+            return;
+        }
+        self.component.paint(ctx, env);
+
+        if ctx.is_focused() && self.cursor_on {
+            let mut caret = self.caret_line();
+            // Snap the caret to the pixel grid so it stays sharp.
+            caret.p0.x = caret.p0.x.trunc() + 0.5;
+            caret.p1.x = caret.p0.x;
+            ctx.stroke(caret, &env.get(theme::CURSOR_COLOR), 1.);
+        }
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        smallvec![self.component.as_dyn()]
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("TextEditor")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::testing::TestHarness;
+    use crate::text::Direction;
+
+    fn editor_text(harness: &TestHarness) -> String {
+        let editor = harness.root_widget();
+        let editor = editor.downcast::<TextEditor<Arc<String>>>().unwrap();
+        editor.text().to_string()
+    }
+
+    #[test]
+    fn edit_from_parent() {
+        let editor = TextEditor::new(Arc::new(String::from("world")));
+        let mut harness = TestHarness::create(editor);
+        let editor_id = harness.root_widget().id();
+
+        harness.mouse_click_on(editor_id);
+        assert_eq!(harness.focused_widget().unwrap().id(), editor_id);
+
+        harness.edit_root_widget(|mut editor, _| {
+            let mut editor = editor.downcast::<TextEditor<Arc<String>>>().unwrap();
+            editor.set_selection(Selection::caret(0));
+            editor.insert_text("hello ");
+        });
+        assert_eq!(editor_text(&harness), "hello world");
+
+        harness.edit_root_widget(|mut editor, _| {
+            let mut editor = editor.downcast::<TextEditor<Arc<String>>>().unwrap();
+            editor.move_caret(Movement::Grapheme(Direction::Right), true);
+            assert_eq!(editor.selection(), Selection::new(6, 7));
+        });
+
+        harness.keyboard_type_chars("W");
+        assert_eq!(editor_text(&harness), "hello World");
+    }
+}
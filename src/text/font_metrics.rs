@@ -0,0 +1,123 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Vertical metrics of a font, for aligning things with text.
+
+use super::FontDescriptor;
+use crate::piet::{PietText, PietTextLayout, Text as _, TextLayout as _, TextLayoutBuilder as _};
+
+/// The vertical metrics of a font at a given size.
+///
+/// These let a widget align an icon or a decoration with neighbouring text
+/// optically, eg center a glyph on the x-height, instead of guessing from the
+/// height of the text. All values are in display points, and are positive.
+///
+/// Get them from [`LayoutCtx::font_metrics`](crate::LayoutCtx::font_metrics).
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FontMetrics {
+    /// The distance from the top of a line to its baseline.
+    pub ascent: f64,
+    /// The distance from the baseline to the bottom of a line.
+    pub descent: f64,
+    /// The height of capital letters above the baseline, eg of "H".
+    pub cap_height: f64,
+    /// The height of lowercase letters above the baseline, eg of "x".
+    pub x_height: f64,
+    /// The distance between the baselines of consecutive lines.
+    pub line_spacing: f64,
+}
+
+impl FontMetrics {
+    /// Measure the metrics of `font`.
+    ///
+    /// The cap and x heights are measured from the ink of "H" and "x", so they're
+    /// accurate even for fonts which don't declare them.
+    pub fn measure(factory: &mut PietText, font: &FontDescriptor) -> Self {
+        let capital = Self::layout(factory, font, "H");
+        let lowercase = Self::layout(factory, font, "x");
+        let Some(line) = capital.line_metric(0) else {
+            return FontMetrics::default();
+        };
+        let ascent = line.baseline;
+        FontMetrics {
+            ascent,
+            descent: line.height - line.baseline,
+            cap_height: ascent - capital.image_bounds().y0,
+            x_height: ascent - lowercase.image_bounds().y0,
+            line_spacing: line.height,
+        }
+    }
+
+    fn layout(factory: &mut PietText, font: &FontDescriptor, text: &'static str) -> PietTextLayout {
+        factory
+            .new_text_layout(text)
+            .font(font.family.clone(), font.size)
+            .default_attribute(font.weight)
+            .default_attribute(font.style)
+            .build()
+            .unwrap()
+    }
+
+    /// The offset from the top of a line at which to place something `height` tall,
+    /// so that it's centered on the x-height, like lowercase text.
+    pub fn center_on_x_height(&self, height: f64) -> f64 {
+        self.ascent - (self.x_height + height) / 2.0
+    }
+
+    /// The offset from the top of a line at which to place something `height` tall,
+    /// so that it's centered on the cap height, like capital letters and digits.
+    pub fn center_on_cap_height(&self, height: f64) -> f64 {
+        self.ascent - (self.cap_height + height) / 2.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::kurbo::Size;
+    use crate::piet::{Device, RenderContext as _};
+    use crate::render_backend;
+    use crate::testing::{ModularWidget, TestHarness};
+
+    #[test]
+    fn letter_heights_are_ordered() {
+        let mut device = Device::new().unwrap();
+        let mut metrics = None;
+        render_backend::render_to_image(&mut device, Size::new(1.0, 1.0), 1.0, |piet| {
+            metrics = Some(FontMetrics::measure(
+                piet.text(),
+                &FontDescriptor::default().with_size(20.0),
+            ));
+        })
+        .unwrap();
+        let metrics = metrics.unwrap();
+
+        assert!(metrics.x_height > 0.0);
+        assert!(metrics.cap_height > metrics.x_height);
+        assert!(metrics.ascent >= metrics.cap_height);
+    }
+
+    #[test]
+    fn layout_ctx_measures_fonts() {
+        let measured = Rc::new(RefCell::new(Vec::new()));
+        let widget = ModularWidget::new(measured.clone()).layout_fn(|measured, ctx, _, env| {
+            for size in [20.0, 40.0] {
+                let font = FontDescriptor::default().with_size(size);
+                measured.borrow_mut().push(ctx.font_metrics(font, env));
+            }
+            Size::new(100.0, 100.0)
+        });
+        let _harness = TestHarness::create(widget);
+
+        let measured = measured.borrow();
+        let (small, large) = (measured[0], measured[1]);
+        assert!(small.x_height > 0.0);
+        // Doubling the size doubles the metrics, give or take hinting.
+        assert!((large.x_height / small.x_height - 2.0).abs() < 0.2);
+        assert!((large.cap_height / small.cap_height - 2.0).abs() < 0.2);
+    }
+}
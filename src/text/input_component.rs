@@ -6,22 +6,26 @@
 
 use std::cell::{Cell, Ref, RefCell, RefMut};
 use std::ops::Range;
+use std::rc::Rc;
 use std::sync::{Arc, Weak};
 
 use druid_shell::{Cursor, Modifiers};
 use smallvec::SmallVec;
 use tracing::{trace_span, Span};
 
+use super::spell_check::{self, SpellChecker, SpellingCorrection};
 use super::{
-    EditableText, ImeHandlerRef, ImeInvalidation, InputHandler, Movement, Selection, TextAction,
-    TextAlignment, TextLayout, TextStorage,
+    CaretMovement, EditableText, ImeHandlerRef, ImeInvalidation, InputHandler, Movement, Selection,
+    TextAction, TextAlignment, TextLayout, TextStorage,
 };
+use crate::clipboard::{self, ClipboardKind};
+use crate::context_menu::ContextMenu;
 use crate::kurbo::{Line, Point, Rect, Vec2};
 use crate::piet::TextLayout as _;
 use crate::widget::WidgetRef;
 use crate::{
     text, theme, BoxConstraints, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx,
-    PaintCtx, RenderContext, Selector, Size, StatusChange, Widget,
+    PaintCtx, RenderContext, Selector, Size, StatusChange, Widget, WidgetId,
 };
 
 /// A widget that accepts text input.
@@ -60,6 +64,8 @@ pub struct TextComponent<T> {
     ///
     /// The parent should update this when handling [`StatusChange::FocusChanged`].
     pub has_focus: bool,
+    /// Where the mouse was pressed, until it moves past the drag threshold.
+    drag_start: Option<Point>,
 }
 
 crate::declare_widget!(
@@ -94,7 +100,16 @@ pub struct EditSession<T> {
     /// If `true`, the component will send the [`TextComponent::CANCEL`]
     /// notification when the user cancels editing.
     pub send_notification_on_cancel: bool,
+    /// How the left and right arrow keys move the caret in text mixing
+    /// left-to-right and right-to-left runs. The default is [`CaretMovement::Visual`].
+    pub caret_movement: CaretMovement,
     selection: Selection,
+    spell_checker: Option<Rc<dyn SpellChecker>>,
+    /// The ranges of misspelled words, and the text they were found in.
+    misspelled: Vec<Range<usize>>,
+    spell_checked_text: Option<T>,
+    /// Ranges painted with a highlight behind them, eg search results.
+    highlights: Vec<Range<usize>>,
     accepts_newlines: bool,
     accepts_tabs: bool,
     alignment: TextAlignment,
@@ -212,6 +227,13 @@ impl TextComponent<()> {
     /// An ancestor can handle this event in order to do things like request
     /// a focus change.
     pub const BACKTAB: Selector = Selector::new("masonry-builtin.textbox-backtab");
+
+    /// Replace a misspelled word; sent by the spelling context menu.
+    pub const CORRECT_SPELLING: Selector<SpellingCorrection> =
+        Selector::new("masonry-builtin.textbox-correct-spelling");
+
+    /// Add a word to the spell checker's dictionary; sent by the spelling context menu.
+    pub const LEARN_WORD: Selector<String> = Selector::new("masonry-builtin.textbox-learn-word");
 }
 
 impl<T> TextComponent<T> {
@@ -291,13 +313,111 @@ impl<T: TextStorage + EditableText> TextComponentMut<'_, '_, T> {
         self.widget.has_focus = focused;
         self.ctx.request_paint();
     }
+
+    /// Set the selection, eg to move the caret to the end of the text.
+    pub fn set_selection(&mut self, selection: Selection) {
+        let _ = self.widget.borrow_mut().set_selection(selection);
+        self.ctx.request_paint();
+    }
+
+    /// Move the caret, as the arrow keys would.
+    ///
+    /// If `extend` is `true`, the selection is extended to the new caret position.
+    pub fn move_caret(&mut self, movement: Movement, extend: bool) {
+        if self.widget.borrow().layout.layout().is_none() {
+            tracing::warn!("move_caret called before the text was laid out.");
+            return;
+        }
+        let selection = {
+            let session = self.widget.borrow();
+            text::movement_with_mode(
+                movement,
+                session.selection,
+                &session.layout,
+                extend,
+                session.caret_movement,
+            )
+        };
+        let _ = self.widget.borrow_mut().set_selection(selection);
+        self.ctx.request_paint();
+    }
+
+    /// Replace the selection with `new_text`, and move the caret to the end of it.
+    pub fn insert_text(&mut self, new_text: &str) {
+        let Some(mut text) = self.widget.borrow().layout.text().cloned() else {
+            return;
+        };
+        {
+            let mut session = self.widget.borrow_mut();
+            let invalidation = session.insert_text(&mut text, new_text);
+            session.layout.set_text(text);
+            session.update_pending_invalidation(invalidation);
+        }
+        self.ctx.request_layout();
+        self.ctx.request_paint();
+    }
+
+    /// Set the spell checker used to underline misspelled words.
+    pub fn set_spell_checker(&mut self, checker: Option<Rc<dyn SpellChecker>>) {
+        self.widget.borrow_mut().set_spell_checker(checker);
+        self.ctx.request_layout();
+        self.ctx.request_paint();
+    }
+
+    /// Paint a highlight behind each range of text, replacing the previous ones.
+    pub fn set_highlight_ranges(&mut self, ranges: Vec<Range<usize>>) {
+        self.widget.borrow_mut().highlights = ranges;
+        self.ctx.request_paint();
+    }
+}
+
+impl<T: TextStorage + EditableText> TextComponent<T> {
+    /// Set the laid-out text after an edit which didn't come from the platform.
+    fn set_edited_text(&mut self, ctx: &mut EventCtx, new_text: T) {
+        self.borrow_mut().layout.set_text(new_text.clone());
+        self.borrow_mut()
+            .update_pending_invalidation(ImeInvalidation::Reset);
+        ctx.submit_notification(TextComponent::TEXT_CHANGED.with(new_text.as_str().to_string()));
+        ctx.request_layout();
+        ctx.request_paint();
+    }
 }
 
 impl<T: TextStorage + EditableText> Widget for TextComponent<T> {
-    fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, _env: &Env) {
+    fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, env: &Env) {
         match event {
+            Event::MouseDown(mouse)
+                if mouse.button.is_middle()
+                    && clipboard::HAS_PRIMARY_SELECTION
+                    && self.can_write()
+                    && !ctx.is_disabled() =>
+            {
+                let Some(pasted) = ctx.clipboard_text(ClipboardKind::PrimarySelection) else {
+                    return;
+                };
+                let new_text = self.borrow_mut().paste_at_point(mouse.pos, &pasted);
+                if let Some(new_text) = new_text {
+                    self.set_edited_text(ctx, new_text);
+                }
+            }
+            Event::MouseDown(mouse)
+                if mouse.button.is_right()
+                    && self.can_write()
+                    && !ctx.is_disabled()
+                    && self.borrow().misspelled_word_at(mouse.pos).is_some() =>
+            {
+                let menu = self.borrow_mut().spelling_menu(mouse.pos, ctx.widget_id());
+                if let Some(menu) = menu {
+                    self.borrow_mut()
+                        .update_pending_invalidation(ImeInvalidation::SelectionChanged);
+                    ctx.show_context_menu(menu, mouse.pos);
+                    ctx.request_paint();
+                    ctx.set_handled();
+                }
+            }
             Event::MouseDown(mouse) if self.can_write() && !ctx.is_disabled() => {
                 ctx.set_active(true);
+                self.drag_start = Some(mouse.pos);
                 self.borrow_mut()
                     .do_mouse_down(mouse.pos, mouse.mods, mouse.count);
                 self.borrow_mut()
@@ -308,7 +428,13 @@ impl<T: TextStorage + EditableText> Widget for TextComponent<T> {
             Event::MouseMove(mouse) if self.can_write() => {
                 if !ctx.is_disabled() {
                     ctx.set_cursor(&Cursor::IBeam);
-                    if ctx.is_active() {
+                    // Small movements while clicking shouldn't select text.
+                    if let Some(drag_start) = self.drag_start {
+                        if (mouse.pos - drag_start).hypot() >= env.get(theme::DRAG_THRESHOLD) {
+                            self.drag_start = None;
+                        }
+                    }
+                    if ctx.is_active() && self.drag_start.is_none() {
                         let pre_sel = self.borrow().selection();
                         self.borrow_mut().do_drag(mouse.pos);
                         if self.borrow().selection() != pre_sel {
@@ -325,7 +451,27 @@ impl<T: TextStorage + EditableText> Widget for TextComponent<T> {
             }
             Event::MouseUp(_) if ctx.is_active() => {
                 ctx.set_active(false);
+                self.drag_start = None;
+                let selected_text = self.borrow().selected_text();
+                if let Some(selected_text) = selected_text {
+                    ctx.set_clipboard_text(ClipboardKind::PrimarySelection, &selected_text);
+                }
+                ctx.request_paint();
+            }
+            Event::Command(cmd) if cmd.is(TextComponent::CORRECT_SPELLING) && self.can_write() => {
+                let correction = cmd.get(TextComponent::CORRECT_SPELLING);
+                let new_text = self.borrow_mut().apply_correction(correction);
+                if let Some(new_text) = new_text {
+                    self.set_edited_text(ctx, new_text);
+                }
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(TextComponent::LEARN_WORD) && self.can_write() => {
+                let word = cmd.get(TextComponent::LEARN_WORD);
+                self.borrow_mut().learn_word(word);
+                ctx.request_layout();
                 ctx.request_paint();
+                ctx.set_handled();
             }
             Event::ImeStateChange => {
                 assert!(
@@ -429,6 +575,7 @@ impl<T: TextStorage + EditableText> Widget for TextComponent<T> {
 
         self.borrow_mut().layout.set_wrap_width(bc.max().width);
         self.borrow_mut().layout.rebuild_if_needed(ctx.text(), env);
+        self.borrow_mut().check_spelling();
         let metrics = self.borrow().layout.layout_metrics();
         let width = if bc.max().width.is_infinite() || bc.max().width < f64::MAX {
             metrics.trailing_whitespace_width
@@ -461,9 +608,29 @@ impl<T: TextStorage + EditableText> Widget for TextComponent<T> {
         let cursor_color = env.get(theme::CURSOR_COLOR);
         let text_offset = Vec2::new(self.borrow().alignment_offset, 0.0);
 
+        let highlights = self.borrow().highlights.clone();
+        if !highlights.is_empty() {
+            let color = env.get(theme::TEXT_HIGHLIGHT_COLOR);
+            let text_len = self.borrow().layout.text_len();
+            for range in highlights {
+                // Highlights aren't updated when the text is edited.
+                let range = range.start.min(text_len)..range.end.min(text_len);
+                if range.is_empty() {
+                    continue;
+                }
+                let rects = self.borrow().layout.selection_rects(range, ctx.text(), env);
+                for region in rects {
+                    ctx.fill(region + text_offset, &color);
+                }
+            }
+        }
+
         let selection = self.borrow().selection();
         let composition = self.borrow().composition_range();
-        let sel_rects = self.borrow().layout.rects_for_range(selection.range());
+        let sel_rects = self
+            .borrow()
+            .layout
+            .selection_rects(selection.range(), ctx.text(), env);
         if let Some(composition) = composition {
             // I believe selection should always be contained in composition range while composing?
             assert!(composition.start <= selection.anchor && composition.end >= selection.active);
@@ -485,6 +652,19 @@ impl<T: TextStorage + EditableText> Widget for TextComponent<T> {
             }
         }
         self.borrow().layout.draw(ctx, text_offset.to_point());
+
+        let misspelled = self.borrow().misspelled.clone();
+        if !misspelled.is_empty() {
+            let color = env.get(theme::SPELLING_ERROR_COLOR);
+            for range in misspelled {
+                let rects = self.borrow().layout.selection_rects(range, ctx.text(), env);
+                for region in rects {
+                    let region = region + text_offset;
+                    let line = spell_check::squiggle(region.x0, region.x1, region.y1 - 1.5);
+                    ctx.stroke(line, &color, 1.0);
+                }
+            }
+        }
     }
 
     fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
@@ -592,6 +772,114 @@ impl<T> EditSession<T> {
 }
 
 impl<T: TextStorage + EditableText> EditSession<T> {
+    /// Set the spell checker used to find misspelled words.
+    pub fn set_spell_checker(&mut self, checker: Option<Rc<dyn SpellChecker>>) {
+        self.spell_checker = checker;
+        self.misspelled.clear();
+        self.spell_checked_text = None;
+    }
+
+    /// The ranges of the words the spell checker found misspelled.
+    ///
+    /// These are updated when the component is laid out.
+    pub fn misspelled_ranges(&self) -> &[Range<usize>] {
+        &self.misspelled
+    }
+
+    /// The ranges of text painted with a highlight behind them.
+    pub fn highlighted_ranges(&self) -> &[Range<usize>] {
+        &self.highlights
+    }
+
+    /// The bounding box of the text in `range`, in the component's coordinates.
+    pub fn rect_for_range(&self, range: Range<usize>) -> Option<Rect> {
+        let bounds = self.layout.range_bounds(range)?;
+        Some(bounds + Vec2::new(self.alignment_offset, 0.0))
+    }
+
+    /// Check the text again if it changed since it was last checked.
+    fn check_spelling(&mut self) {
+        let (checker, text) = match (&self.spell_checker, self.layout.text()) {
+            (Some(checker), Some(text)) => (checker, text),
+            _ => return,
+        };
+        let is_checked = self
+            .spell_checked_text
+            .as_ref()
+            .map_or(false, |checked| checked.same(text));
+        if is_checked {
+            return;
+        }
+        let misspelled = checker.check(text.as_str());
+        let text = text.clone();
+        self.misspelled = misspelled;
+        self.spell_checked_text = Some(text);
+    }
+
+    /// The range of the misspelled word at `point`, if any.
+    fn misspelled_word_at(&self, point: Point) -> Option<Range<usize>> {
+        let point = point - Vec2::new(self.alignment_offset, 0.0);
+        let pos = self.layout.text_position_for_point(point);
+        self.misspelled
+            .iter()
+            .find(|range| range.start <= pos && pos <= range.end)
+            .cloned()
+    }
+
+    /// Select the misspelled word at `point`, and return a menu of corrections.
+    fn spelling_menu(&mut self, point: Point, widget_id: WidgetId) -> Option<ContextMenu> {
+        let range = self.misspelled_word_at(point)?;
+        let checker = self.spell_checker.clone()?;
+        let word = self.layout.text()?.as_str().get(range.clone())?.to_string();
+        self.selection = Selection::new(range.start, range.end);
+
+        let mut menu = ContextMenu::new();
+        for replacement in checker
+            .suggestions(&word)
+            .into_iter()
+            .take(spell_check::MAX_SUGGESTIONS)
+        {
+            let correction = SpellingCorrection {
+                range: range.clone(),
+                word: word.clone(),
+                replacement: replacement.clone(),
+            };
+            let command = TextComponent::CORRECT_SPELLING
+                .with(correction)
+                .to(widget_id);
+            menu = menu.with_item(replacement, command);
+        }
+        if menu.is_empty() {
+            menu = menu.with_disabled_item("No Suggestions");
+        }
+        if checker.can_learn_words() {
+            let command = TextComponent::LEARN_WORD.with(word).to(widget_id);
+            menu = menu
+                .with_separator()
+                .with_item("Add to Dictionary", command);
+        }
+        Some(menu)
+    }
+
+    /// Replace a misspelled word, unless the text changed since it was found.
+    fn apply_correction(&mut self, correction: &SpellingCorrection) -> Option<T> {
+        let mut text = self.layout.text()?.clone();
+        if text.as_str().get(correction.range.clone()) != Some(correction.word.as_str()) {
+            return None;
+        }
+        self.selection = Selection::new(correction.range.start, correction.range.end);
+        let _ = self.insert_text(&mut text, &correction.replacement);
+        Some(text)
+    }
+
+    fn learn_word(&mut self, word: &str) {
+        if let Some(checker) = &self.spell_checker {
+            checker.learn_word(word);
+        }
+        // The text is checked again on the next layout.
+        self.spell_checked_text = None;
+    }
+
     /// Insert text *not* from the IME, replacing the current selection.
     ///
     /// The caller is responsible for notifying the platform of the change in
@@ -610,21 +898,35 @@ impl<T: TextStorage + EditableText> EditSession<T> {
     /// Returns `true` if the clipboard was set, and `false` if not (indicating)
     /// that the selection was empty.)
     pub fn set_clipboard(&self) -> bool {
-        if let Some(text) = self
-            .layout
-            .text()
-            .and_then(|txt| txt.slice(self.selection.range()))
-        {
-            if !text.is_empty() {
-                druid_shell::Application::global()
-                    .clipboard()
-                    .put_string(text);
-                return true;
-            }
+        if let Some(text) = self.selected_text() {
+            clipboard::put_string(ClipboardKind::Clipboard, &text);
+            return true;
         }
         false
     }
 
+    /// The text in the current selection, or `None` if the selection is empty.
+    pub fn selected_text(&self) -> Option<String> {
+        let text = self
+            .layout
+            .text()
+            .and_then(|txt| txt.slice(self.selection.range()))?;
+        (!text.is_empty()).then(|| text.into_owned())
+    }
+
+    /// Insert text at the given point, moving the caret to the end of the insertion.
+    ///
+    /// This is used for middle-click paste from the primary selection. Returns
+    /// the new text, which the caller is responsible for setting on the layout.
+    fn paste_at_point(&mut self, point: Point, new_text: &str) -> Option<T> {
+        let point = point - Vec2::new(self.alignment_offset, 0.0);
+        let pos = self.layout.text_position_for_point(point);
+        let mut text = self.layout.text()?.clone();
+        self.selection = Selection::caret(pos);
+        let _ = self.insert_text(&mut text, new_text);
+        Some(text)
+    }
+
     fn scroll_to_selection_end(&mut self, after_edit: bool) {
         self.external_scroll_to = Some(after_edit);
     }
@@ -632,12 +934,24 @@ impl<T: TextStorage + EditableText> EditSession<T> {
     fn do_action(&mut self, buffer: &mut T, action: TextAction) {
         match action {
             TextAction::Move(movement) => {
-                let sel = text::movement(movement, self.selection, &self.layout, false);
+                let sel = text::movement_with_mode(
+                    movement,
+                    self.selection,
+                    &self.layout,
+                    false,
+                    self.caret_movement,
+                );
                 self.external_selection_change = Some(sel);
                 self.scroll_to_selection_end(false);
             }
             TextAction::MoveSelecting(movement) => {
-                let sel = text::movement(movement, self.selection, &self.layout, true);
+                let sel = text::movement_with_mode(
+                    movement,
+                    self.selection,
+                    &self.layout,
+                    true,
+                    self.caret_movement,
+                );
                 self.external_selection_change = Some(sel);
                 self.scroll_to_selection_end(false);
             }
@@ -933,9 +1247,14 @@ impl<T: TextStorage> TextComponent<T> {
             external_action: None,
             pending_ime_invalidation: None,
             selection: Selection::caret(0),
+            spell_checker: None,
+            misspelled: Vec::new(),
+            spell_checked_text: None,
+            highlights: Vec::new(),
             composition_range: None,
             send_notification_on_return: false,
             send_notification_on_cancel: false,
+            caret_movement: CaretMovement::default(),
             accepts_newlines: false,
             accepts_tabs: false,
             alignment: TextAlignment::Start,
@@ -949,6 +1268,7 @@ impl<T: TextStorage> TextComponent<T> {
             edit_session: Arc::new(RefCell::new(inner)),
             lock: Arc::new(Cell::new(ImeLock::None)),
             has_focus: false,
+            drag_start: None,
         }
     }
 }
@@ -7,13 +7,17 @@
 use std::ops::Range;
 use std::rc::Rc;
 
+use super::bidi;
+use super::line_breaking::{hyphenation_points, system_locale, Hyphens, SOFT_HYPHEN};
+use super::measure::TextCacheKey;
+use super::shaping::{FontFeature, GraphemeShaping, PositionedRuns, RunStyle, ShapingAttribute};
 use super::{FontDescriptor, Link, TextStorage};
 use crate::kurbo::{Line, Point, Rect, Size};
 use crate::piet::{
     Color, PietText, PietTextLayout, Text as _, TextAlignment, TextAttribute, TextLayout as _,
     TextLayoutBuilder as _,
 };
-use crate::{Env, KeyOrValue, PaintCtx, RenderContext};
+use crate::{ArcStr, Env, KeyOrValue, PaintCtx, RenderContext};
 
 /// A component for displaying text on screen.
 ///
@@ -44,7 +48,16 @@ pub struct TextLayout<T> {
     // using a `FontDescriptor` in the `Env`.
     text_size_override: Option<KeyOrValue<f64>>,
     text_color: KeyOrValue<Color>,
+    font_features: Vec<FontFeature>,
+    letter_spacing: Option<KeyOrValue<f64>>,
+    word_spacing: Option<KeyOrValue<f64>>,
+    hyphens: Hyphens,
+    locale: Option<ArcStr>,
+    // The offsets in the text where soft hyphens were inserted in the layout.
+    soft_hyphens: Rc<[usize]>,
     layout: Option<PietTextLayout>,
+    // The lines of the layout split into runs, to apply the shaping attributes.
+    runs: Option<Rc<PositionedRuns>>,
     wrap_width: f64,
     alignment: TextAlignment,
     links: Rc<[(Rect, usize)]>,
@@ -75,7 +88,14 @@ impl<T> TextLayout<T> {
             font: crate::theme::UI_FONT.into(),
             text_color: crate::theme::TEXT_COLOR.into(),
             text_size_override: None,
+            font_features: Vec::new(),
+            letter_spacing: None,
+            word_spacing: None,
+            hyphens: Hyphens::default(),
+            locale: None,
+            soft_hyphens: Rc::new([]),
             layout: None,
+            runs: None,
             wrap_width: f64::INFINITY,
             alignment: Default::default(),
             links: Rc::new([]),
@@ -123,6 +143,68 @@ impl<T> TextLayout<T> {
         }
     }
 
+    /// Set the OpenType features applied to the whole text.
+    ///
+    /// Features set by the text's own attributes take precedence. Only some
+    /// features are supported, see [`FontFeature`].
+    pub fn set_font_features(&mut self, features: impl Into<Vec<FontFeature>>) {
+        let features = features.into();
+        if features != self.font_features {
+            self.font_features = features;
+            self.layout = None;
+        }
+    }
+
+    /// Set the extra space added after each character, in points.
+    ///
+    /// Spacing set by the text's own attributes takes precedence. It's ignored in
+    /// text with right-to-left runs.
+    pub fn set_letter_spacing(&mut self, spacing: impl Into<KeyOrValue<f64>>) {
+        let spacing = spacing.into();
+        if Some(&spacing) != self.letter_spacing.as_ref() {
+            self.letter_spacing = Some(spacing);
+            self.layout = None;
+        }
+    }
+
+    /// Set the extra space added to each space character, in points.
+    ///
+    /// Spacing set by the text's own attributes takes precedence. It's ignored in
+    /// text with right-to-left runs.
+    pub fn set_word_spacing(&mut self, spacing: impl Into<KeyOrValue<f64>>) {
+        let spacing = spacing.into();
+        if Some(&spacing) != self.word_spacing.as_ref() {
+            self.word_spacing = Some(spacing);
+            self.layout = None;
+        }
+    }
+
+    /// Set whether words may be hyphenated when wrapping lines.
+    ///
+    /// With [`Hyphens::Auto`], the text must implement [`TextStorage::attribute_spans`]
+    /// to be hyphenated.
+    pub fn set_hyphens(&mut self, hyphens: Hyphens) {
+        if hyphens != self.hyphens {
+            self.hyphens = hyphens;
+            self.layout = None;
+        }
+    }
+
+    /// Set the locale of the text, eg `"en-US"`, used to pick a hyphenation dictionary.
+    ///
+    /// If `None`, the [system locale](super::system_locale) is used.
+    pub fn set_locale(&mut self, locale: Option<ArcStr>) {
+        if locale != self.locale {
+            self.locale = locale;
+            self.layout = None;
+        }
+    }
+
+    /// The locale of the text.
+    pub fn locale(&self) -> ArcStr {
+        self.locale.clone().unwrap_or_else(system_locale)
+    }
+
     /// Set the width at which to wrap words.
     ///
     /// You may pass `f64::INFINITY` to disable word wrapping
@@ -154,6 +236,51 @@ impl<T> TextLayout<T> {
     pub fn text_is_rtl(&self) -> bool {
         self.text_is_rtl
     }
+
+    /// Convert an offset in the text to an offset in the laid-out text, which
+    /// may have soft hyphens inserted.
+    pub(crate) fn to_layout_offset(&self, offset: usize) -> usize {
+        let inserted = self.soft_hyphens.partition_point(|point| *point < offset);
+        offset + inserted * SOFT_HYPHEN.len_utf8()
+    }
+
+    /// Convert an offset in the laid-out text to an offset in the text.
+    ///
+    /// Offsets within or after an inserted soft hyphen map to the hyphen's position.
+    pub(crate) fn to_text_offset(&self, offset: usize) -> usize {
+        let hyphen_len = SOFT_HYPHEN.len_utf8();
+        let mut inserted = 0;
+        for (i, point) in self.soft_hyphens.iter().enumerate() {
+            let hyphen_start = point + i * hyphen_len;
+            if hyphen_start >= offset {
+                break;
+            }
+            if offset < hyphen_start + hyphen_len {
+                return *point;
+            }
+            inserted = i + 1;
+        }
+        offset - inserted * hyphen_len
+    }
+
+    /// The shaping settings of the whole text, before its own attributes.
+    fn shaping_defaults(&self, env: &Env) -> GraphemeShaping {
+        let mut shaping = GraphemeShaping::default();
+        for feature in &self.font_features {
+            shaping.apply(&ShapingAttribute::Feature(*feature));
+        }
+        if let Some(spacing) = &self.letter_spacing {
+            shaping.apply(&ShapingAttribute::LetterSpacing(spacing.resolve(env)));
+        }
+        if let Some(spacing) = &self.word_spacing {
+            shaping.apply(&ShapingAttribute::WordSpacing(spacing.resolve(env)));
+        }
+        shaping
+    }
+
+    fn to_layout_range(&self, range: Range<usize>) -> Range<usize> {
+        self.to_layout_offset(range.start)..self.to_layout_offset(range.end)
+    }
 }
 
 impl<T: TextStorage> TextLayout<T> {
@@ -199,8 +326,44 @@ impl<T: TextStorage> TextLayout<T> {
         }
     }
 
+    /// The key of this layout's metrics in a [`TextMetricsCache`], which it
+    /// shares with [`TextLayoutBuilder`].
+    ///
+    /// This is `None` for text with style spans or [`ShapingAttribute`]s, or with
+    /// [`Hyphens::Auto`].
+    ///
+    /// [`TextMetricsCache`]: super::TextMetricsCache
+    /// [`TextLayoutBuilder`]: super::TextLayoutBuilder
+    pub fn cache_key(&self, env: &Env) -> Option<TextCacheKey> {
+        let text = self.text.as_ref()?;
+        let has_spans = text
+            .attribute_spans(env)
+            .map_or(true, |spans| !spans.is_empty());
+        let has_shaping = !text.shaping_attributes(env).is_empty()
+            || self.shaping_defaults(env) != GraphemeShaping::default();
+        if has_spans || has_shaping || self.hyphens == Hyphens::Auto {
+            return None;
+        }
+
+        let mut font = self.font.resolve(env);
+        if let Some(size) = &self.text_size_override {
+            font = font.with_size(size.resolve(env));
+        }
+        Some(TextCacheKey::new(
+            text.as_str().into(),
+            &font,
+            self.wrap_width,
+            self.alignment,
+        ))
+    }
+
     /// Returns the inner Piet [`TextLayout`] type.
     ///
+    /// If the text was hyphenated, the inner layout's text has soft hyphens
+    /// inserted, so its offsets don't match the offsets in the text. The inner
+    /// layout doesn't apply [`ShapingAttribute`]s, which move its graphemes; use the
+    /// methods of this type to get their positions.
+    ///
     /// [`TextLayout`]: ./piet/trait.TextLayout.html
     pub fn layout(&self) -> Option<&PietTextLayout> {
         self.layout.as_ref()
@@ -212,10 +375,26 @@ impl<T: TextStorage> TextLayout<T> {
     ///
     /// [`rebuild_if_needed`]: #method.rebuild_if_needed
     pub fn size(&self) -> Size {
-        self.layout
-            .as_ref()
-            .map(|layout| layout.size())
-            .unwrap_or_default()
+        let Some(layout) = &self.layout else {
+            return Size::ZERO;
+        };
+        match &self.runs {
+            Some(runs) => Size::new(runs.width(), layout.size().height),
+            None => layout.size(),
+        }
+    }
+
+    /// A rough estimate, in bytes, of the memory retained by the built layout.
+    ///
+    /// Shaped text stores glyph runs and line metrics, which take much more room
+    /// than the text itself; this assumes a fixed cost per byte of text.
+    pub fn estimated_memory(&self) -> usize {
+        // Roughly one glyph (id, advance, offset and cluster) per byte of text.
+        const BYTES_PER_TEXT_BYTE: usize = 32;
+        let runs_len = self.runs.as_ref().map_or(0, |runs| runs.text_len());
+        self.layout.as_ref().map_or(0, |layout| {
+            (layout.text().len() + runs_len) * BYTES_PER_TEXT_BYTE
+        })
     }
 
     /// Return the text's [`LayoutMetrics`].
@@ -233,11 +412,14 @@ impl<T: TextStorage> TextLayout<T> {
 
         if let Some(layout) = self.layout.as_ref() {
             let first_baseline = layout.line_metric(0).unwrap().baseline;
-            let size = layout.size();
+            let trailing_whitespace_width = match &self.runs {
+                Some(runs) => runs.width(),
+                None => layout.trailing_whitespace_width(),
+            };
             LayoutMetrics {
-                size,
+                size: self.size(),
                 first_baseline,
-                trailing_whitespace_width: layout.trailing_whitespace_width(),
+                trailing_whitespace_width,
             }
         } else {
             LayoutMetrics::default()
@@ -247,10 +429,12 @@ impl<T: TextStorage> TextLayout<T> {
     /// For a given `Point` (relative to this object's origin), returns index
     /// into the underlying text of the nearest grapheme boundary.
     pub fn text_position_for_point(&self, point: Point) -> usize {
-        self.layout
-            .as_ref()
-            .map(|layout| layout.hit_test_point(point).idx)
-            .unwrap_or_default()
+        let offset = match (&self.layout, &self.runs) {
+            (_, Some(runs)) => runs.offset_for_point(point),
+            (Some(layout), None) => layout.hit_test_point(point).idx,
+            (None, None) => return 0,
+        };
+        self.to_text_offset(offset)
     }
 
     /// Given the utf-8 position of a character boundary in the underlying text,
@@ -261,10 +445,12 @@ impl<T: TextStorage> TextLayout<T> {
     ///
     /// Panics if `text_pos` is not a character boundary.
     pub fn point_for_text_position(&self, text_pos: usize) -> Point {
-        self.layout
-            .as_ref()
-            .map(|layout| layout.hit_test_text_position(text_pos).point)
-            .unwrap_or_default()
+        let text_pos = self.to_layout_offset(text_pos);
+        match (&self.layout, &self.runs) {
+            (_, Some(runs)) => runs.point_for_offset(text_pos),
+            (Some(layout), None) => layout.hit_test_text_position(text_pos).point,
+            (None, None) => Point::ZERO,
+        }
     }
 
     /// Given a utf-8 range in the underlying text, return a `Vec` of `Rect`s
@@ -274,10 +460,92 @@ impl<T: TextStorage> TextLayout<T> {
     ///
     /// Panics if the range start or end is not a character boundary.
     pub fn rects_for_range(&self, range: Range<usize>) -> Vec<Rect> {
-        self.layout
-            .as_ref()
-            .map(|layout| layout.rects_for_range(range))
-            .unwrap_or_default()
+        let range = self.to_layout_range(range);
+        match (&self.layout, &self.runs) {
+            (_, Some(runs)) => runs.rects_for_range(range),
+            (Some(layout), None) => layout.rects_for_range(range),
+            (None, None) => Vec::new(),
+        }
+    }
+
+    /// The bounding box of the text in a utf-8 range, spanning several lines if needed.
+    ///
+    /// Returns `None` if the layout hasn't been built, or has no text in the range.
+    pub fn range_bounds(&self, range: Range<usize>) -> Option<Rect> {
+        self.rects_for_range(range)
+            .into_iter()
+            .reduce(|bounds, rect| bounds.union(rect))
+    }
+
+    /// Given a utf-8 range in the underlying text, return the rects covering it
+    /// on screen, taking the direction of each run into account.
+    ///
+    /// Where right-to-left and left-to-right text is mixed on a line, a range of
+    /// text may be displayed in several parts; this returns a rect for each. The
+    /// `factory` and `env` are used to measure graphemes at the edges of runs.
+    ///
+    /// For text without right-to-left runs, this is the same as [`rects_for_range`].
+    ///
+    /// [`rects_for_range`]: TextLayout::rects_for_range
+    pub fn selection_rects(
+        &self,
+        range: Range<usize>,
+        factory: &mut PietText,
+        env: &Env,
+    ) -> Vec<Rect> {
+        let layout = match &self.layout {
+            Some(layout) if bidi::has_rtl(layout.text()) => layout,
+            _ => return self.rects_for_range(range),
+        };
+        let text = layout.text();
+        let range = self.to_layout_range(range);
+        let font = self.font.resolve(env);
+        let size = match &self.text_size_override {
+            Some(size) => size.resolve(env),
+            None => font.size,
+        };
+        let mut grapheme_width = |range: Range<usize>| {
+            factory
+                .new_text_layout(text[range].to_string())
+                .font(font.family.clone(), size)
+                .default_attribute(font.weight)
+                .default_attribute(font.style)
+                .build()
+                .map(|layout| layout.size().width)
+                .unwrap_or_default()
+        };
+
+        let mut rects = Vec::new();
+        for line in 0..layout.line_count() {
+            let lm = layout.line_metric(line).unwrap();
+            if lm.end_offset <= range.start || lm.start_offset >= range.end {
+                continue;
+            }
+            let graphemes = bidi::visual_graphemes(text, lm.start_offset..lm.end_offset);
+            let edges = bidi::grapheme_edges(
+                &graphemes,
+                |offset| layout.hit_test_text_position(offset).point.x,
+                &mut grapheme_width,
+            );
+
+            // Adjacent selected graphemes share a rect.
+            let mut current: Option<Rect> = None;
+            for (i, grapheme) in graphemes.iter().enumerate() {
+                if grapheme.range.start < range.start || grapheme.range.end > range.end {
+                    rects.extend(current.take());
+                    continue;
+                }
+                let (x0, x1) = (edges[i], edges[i + 1]);
+                match &mut current {
+                    Some(rect) => rect.x1 = x1,
+                    None => {
+                        current = Some(Rect::new(x0, lm.y_offset, x1, lm.y_offset + lm.height));
+                    }
+                }
+            }
+            rects.extend(current);
+        }
+        rects
     }
 
     /// Return a line suitable for underlining a range of text.
@@ -290,12 +558,13 @@ impl<T: TextStorage> TextLayout<T> {
         self.layout
             .as_ref()
             .map(|layout| {
-                let p1 = layout.hit_test_text_position(range.start);
-                let p2 = layout.hit_test_text_position(range.end);
+                let p1 = layout.hit_test_text_position(self.to_layout_offset(range.start));
                 let line_metric = layout.line_metric(p1.line).unwrap();
                 // heuristic; 1/5 of height is a rough guess at the descender pos?
                 let y_pos = line_metric.baseline + (line_metric.height / 5.0);
-                Line::new((p1.point.x, y_pos), (p2.point.x, y_pos))
+                let x0 = self.point_for_text_position(range.start).x;
+                let x1 = self.point_for_text_position(range.end).x;
+                Line::new((x0, y_pos), (x1, y_pos))
             })
             .unwrap_or_else(|| Line::new(Point::ZERO, Point::ZERO))
     }
@@ -303,10 +572,13 @@ impl<T: TextStorage> TextLayout<T> {
     /// Given the utf-8 position of a character boundary in the underlying text,
     /// return a `Line` suitable for drawing a vertical cursor at that boundary.
     pub fn cursor_line_for_text_position(&self, text_pos: usize) -> Line {
+        if let Some(runs) = &self.runs {
+            return runs.cursor_line(self.to_layout_offset(text_pos));
+        }
         self.layout
             .as_ref()
             .map(|layout| {
-                let pos = layout.hit_test_text_position(text_pos);
+                let pos = layout.hit_test_text_position(self.to_layout_offset(text_pos));
                 let line_metrics = layout.line_metric(pos.line).unwrap();
                 let p1 = (pos.point.x, line_metrics.y_offset);
                 let p2 = (pos.point.x, (line_metrics.y_offset + line_metrics.height));
@@ -356,29 +628,92 @@ impl<T: TextStorage> TextLayout<T> {
                     font
                 };
 
-                let builder = factory
-                    .new_text_layout(text.clone())
+                // Hyphenation inserts soft hyphens, which moves the attribute spans.
+                let points = match self.hyphens {
+                    Hyphens::Auto if self.wrap_width.is_finite() => {
+                        hyphenation_points(text.as_str(), &self.locale())
+                    }
+                    Hyphens::Auto | Hyphens::Manual => Vec::new(),
+                };
+                let attribute_spans = if points.is_empty() {
+                    None
+                } else {
+                    text.attribute_spans(env)
+                };
+                self.soft_hyphens = match attribute_spans {
+                    Some(_) => points.into(),
+                    None => Rc::new([]),
+                };
+
+                let builder = match attribute_spans {
+                    Some(_) => {
+                        let mut hyphenated = String::with_capacity(text.as_str().len());
+                        let mut start = 0;
+                        for point in self.soft_hyphens.iter() {
+                            hyphenated.push_str(&text.as_str()[start..*point]);
+                            hyphenated.push(SOFT_HYPHEN);
+                            start = *point;
+                        }
+                        hyphenated.push_str(&text.as_str()[start..]);
+                        factory.new_text_layout(ArcStr::from(hyphenated))
+                    }
+                    None => factory.new_text_layout(text.clone()),
+                };
+                let builder = builder
                     .max_width(self.wrap_width)
                     .alignment(self.alignment)
                     .font(descriptor.family.clone(), descriptor.size)
                     .default_attribute(descriptor.weight)
                     .default_attribute(descriptor.style)
-                    .default_attribute(TextAttribute::TextColor(color));
-                let layout = text.add_attributes(builder, env).build().unwrap();
+                    .default_attribute(TextAttribute::TextColor(color.clone()));
+                let builder = match attribute_spans {
+                    Some(spans) => spans.into_iter().fold(builder, |builder, (range, attr)| {
+                        builder.range_attribute(self.to_layout_range(range), attr)
+                    }),
+                    None => text.add_attributes(builder, env),
+                };
+                let layout = builder.build().unwrap();
+
+                let shaping_defaults = self.shaping_defaults(env);
+                let shaping_spans = text.shaping_attributes(env);
+                let runs =
+                    if shaping_spans.is_empty() && shaping_defaults == GraphemeShaping::default() {
+                        None
+                    } else {
+                        let style_spans: Vec<_> = text
+                            .attribute_spans(env)
+                            .unwrap_or_default()
+                            .into_iter()
+                            .map(|(range, attr)| (self.to_layout_range(range), attr))
+                            .collect();
+                        let style = RunStyle {
+                            font: &descriptor,
+                            color: &color,
+                            alignment: self.alignment,
+                            spans: &style_spans,
+                        };
+                        PositionedRuns::build(
+                            &layout,
+                            factory,
+                            &style,
+                            &shaping_defaults,
+                            &shaping_spans,
+                            |offset| self.to_text_offset(offset),
+                        )
+                    };
+                self.runs = runs.map(Rc::new);
+                self.layout = Some(layout);
 
                 self.links = text
                     .links()
                     .iter()
                     .enumerate()
                     .flat_map(|(i, link)| {
-                        layout
-                            .rects_for_range(link.range())
+                        self.rects_for_range(link.range())
                             .into_iter()
                             .map(move |rect| (rect, i))
                     })
                     .collect();
-
-                self.layout = Some(layout);
             }
         }
     }
@@ -400,8 +735,10 @@ impl<T: TextStorage> TextLayout<T> {
                 .map(|t| t.as_str())
                 .unwrap_or("layout is missing text")
         );
-        if let Some(layout) = self.layout.as_ref() {
-            ctx.draw_text(layout, point);
+        match (&self.layout, &self.runs) {
+            (_, Some(runs)) => runs.draw(ctx, point.into()),
+            (Some(layout), None) => ctx.draw_text(layout, point),
+            (None, None) => {}
         }
     }
 }
@@ -429,3 +766,32 @@ impl<T: TextStorage> Default for TextLayout<T> {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn soft_hyphen_offsets() {
+        // "hyphenation" laid out as "hy\u{AD}phen\u{AD}ation".
+        let mut layout = TextLayout::<ArcStr>::from_text("hyphenation");
+        layout.soft_hyphens = vec![2, 6].into();
+
+        assert_eq!(layout.to_layout_offset(0), 0);
+        assert_eq!(layout.to_layout_offset(2), 2);
+        assert_eq!(layout.to_layout_offset(3), 5);
+        assert_eq!(layout.to_layout_offset(11), 15);
+
+        assert_eq!(layout.to_text_offset(2), 2);
+        assert_eq!(layout.to_text_offset(3), 2);
+        assert_eq!(layout.to_text_offset(4), 2);
+        assert_eq!(layout.to_text_offset(5), 3);
+        assert_eq!(layout.to_text_offset(15), 11);
+        for offset in 0..=11 {
+            assert_eq!(
+                layout.to_text_offset(layout.to_layout_offset(offset)),
+                offset
+            );
+        }
+    }
+}
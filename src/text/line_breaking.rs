@@ -0,0 +1,139 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Hyphenation and locale-aware line breaking.
+
+use crate::ArcStr;
+
+/// The soft hyphen: an invisible character where a word may be broken, and
+/// which is displayed as a hyphen if it is.
+pub(crate) const SOFT_HYPHEN: char = '\u{AD}';
+
+/// Whether words may be hyphenated when wrapping lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hyphens {
+    /// Words are only broken at the soft hyphens (U+00AD) in the text.
+    Manual,
+    /// Words are also hyphenated with the dictionary of the text's language.
+    ///
+    /// Dictionaries are only available if Masonry is built with the `hyphenation`
+    /// feature; otherwise this is the same as `Manual`.
+    Auto,
+}
+
+impl Default for Hyphens {
+    fn default() -> Self {
+        Hyphens::Manual
+    }
+}
+
+/// The locale of the system, used for text which doesn't have a locale of its own.
+pub fn system_locale() -> ArcStr {
+    thread_local! {
+        static LOCALE: ArcStr = druid_shell::Application::get_locale().into();
+    }
+    LOCALE.with(|locale| locale.clone())
+}
+
+/// The offsets in `text` where words may be hyphenated, for the language of `locale`.
+///
+/// Words which already contain soft hyphens are left alone.
+pub(crate) fn hyphenation_points(text: &str, locale: &str) -> Vec<usize> {
+    #[cfg(feature = "hyphenation")]
+    {
+        dictionaries::hyphenation_points(text, locale)
+    }
+    #[cfg(not(feature = "hyphenation"))]
+    {
+        let _ = (text, locale);
+        Vec::new()
+    }
+}
+
+#[cfg(feature = "hyphenation")]
+mod dictionaries {
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::rc::Rc;
+
+    use hyphenation::{Hyphenator, Language, Load, Standard};
+    use tracing::warn;
+    use xi_unicode::LineBreakIterator;
+
+    /// Shorter words are never hyphenated.
+    const MIN_WORD_CHARS: usize = 5;
+
+    thread_local! {
+        static DICTIONARIES: RefCell<HashMap<Language, Option<Rc<Standard>>>> =
+            RefCell::new(HashMap::new());
+    }
+
+    /// The dictionary language for a locale such as `"en-US"` or `"de_DE"`.
+    fn language(locale: &str) -> Option<Language> {
+        let locale = locale.replace('_', "-").to_ascii_lowercase();
+        let mut subtags = locale.split('-');
+        let language = subtags.next()?;
+        let region = subtags.find(|subtag| subtag.len() == 2);
+        Some(match (language, region) {
+            ("en", Some("gb" | "au" | "nz" | "ie")) => Language::EnglishGB,
+            ("en", _) => Language::EnglishUS,
+            ("de", Some("ch")) => Language::GermanSwiss,
+            ("de", _) => Language::German1996,
+            ("fr", _) => Language::French,
+            ("es", _) => Language::Spanish,
+            ("it", _) => Language::Italian,
+            ("pt", _) => Language::Portuguese,
+            ("nl", _) => Language::Dutch,
+            ("sv", _) => Language::Swedish,
+            ("da", _) => Language::Danish,
+            ("nb" | "no", _) => Language::NorwegianBokmal,
+            ("fi", _) => Language::Finnish,
+            ("pl", _) => Language::Polish,
+            ("cs", _) => Language::Czech,
+            ("ru", _) => Language::Russian,
+            ("uk", _) => Language::Ukrainian,
+            _ => return None,
+        })
+    }
+
+    fn dictionary(language: Language) -> Option<Rc<Standard>> {
+        DICTIONARIES.with(|dictionaries| {
+            dictionaries
+                .borrow_mut()
+                .entry(language)
+                .or_insert_with(|| match Standard::from_embedded(language) {
+                    Ok(dictionary) => Some(Rc::new(dictionary)),
+                    Err(err) => {
+                        warn!("Cannot load hyphenation dictionary {:?}: {}", language, err);
+                        None
+                    }
+                })
+                .clone()
+        })
+    }
+
+    pub(super) fn hyphenation_points(text: &str, locale: &str) -> Vec<usize> {
+        let dictionary = match language(locale).and_then(dictionary) {
+            Some(dictionary) => dictionary,
+            None => return Vec::new(),
+        };
+
+        // Words are the segments between the line break opportunities of UAX #14,
+        // so that eg URLs and numbers aren't hyphenated.
+        let mut points = Vec::new();
+        let mut start = 0;
+        for (end, _is_hard_break) in LineBreakIterator::new(text) {
+            let word = text[start..end].trim_end();
+            // Soft hyphens aren't alphabetic, so words which have some are skipped.
+            let is_hyphenable =
+                word.chars().count() >= MIN_WORD_CHARS && word.chars().all(char::is_alphabetic);
+            if is_hyphenable {
+                let breaks = dictionary.hyphenate(word).breaks;
+                points.extend(breaks.into_iter().map(|offset| start + offset));
+            }
+            start = end;
+        }
+        points
+    }
+}
@@ -0,0 +1,273 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Measuring text outside of widgets.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use super::{FontDescriptor, FontMetrics, LayoutMetrics, TextLayout, TextStorage};
+use crate::kurbo::Size;
+use crate::piet::{Device, Error as PietError, PietText, RenderContext as _, TextAlignment};
+use crate::render_backend;
+use crate::{ArcStr, Color, Env};
+
+/// The cache is cleared once it holds this many entries.
+const MAX_CACHED_METRICS: usize = 10_000;
+
+/// Measures and lays out text without a widget context.
+///
+/// This is useful to measure text ahead of layout, eg to compute the row heights
+/// of a virtualized list, from the app driver or from a background thread. Each
+/// thread needs its own builder; they can share a [`TextMetricsCache`].
+///
+/// Text is laid out the same way as by a [`TextLayout`] with the same font, wrap
+/// width and alignment, so cached metrics can be used by both.
+pub struct TextLayoutBuilder {
+    factory: PietText,
+    font: FontDescriptor,
+    wrap_width: f64,
+    alignment: TextAlignment,
+    cache: Option<TextMetricsCache>,
+}
+
+/// Metrics of laid-out text and of fonts, shared between threads.
+///
+/// Cloning the cache gives another handle to the same metrics.
+#[derive(Clone, Default)]
+pub struct TextMetricsCache {
+    metrics: Arc<Mutex<HashMap<TextCacheKey, LayoutMetrics>>>,
+    fonts: Arc<Mutex<HashMap<FontCacheKey, FontMetrics>>>,
+}
+
+/// Everything which determines the metrics of plain text.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TextCacheKey {
+    text: ArcStr,
+    font: FontCacheKey,
+    wrap_width_bits: u64,
+    alignment: u8,
+}
+
+/// Everything which determines the metrics of a font.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct FontCacheKey {
+    family: ArcStr,
+    size_bits: u64,
+    weight: u16,
+    italic: bool,
+}
+
+// --- TextLayoutBuilder ---
+
+impl TextLayoutBuilder {
+    /// Create a builder with its own text factory.
+    ///
+    /// This fails if no graphics device is available.
+    pub fn new() -> Result<Self, PietError> {
+        let mut device = Device::new()?;
+        let mut factory = None;
+        render_backend::render_to_image(&mut device, Size::new(1.0, 1.0), 1.0, |piet| {
+            factory = Some(piet.text().clone());
+        })?;
+        // The closure is always called.
+        Ok(Self::with_factory(factory.unwrap()))
+    }
+
+    /// Create a builder using an existing text factory, eg [`LayoutCtx::text`](crate::LayoutCtx::text).
+    pub fn with_factory(factory: PietText) -> Self {
+        TextLayoutBuilder {
+            factory,
+            font: FontDescriptor::default(),
+            wrap_width: f64::INFINITY,
+            alignment: TextAlignment::default(),
+            cache: None,
+        }
+    }
+
+    /// Builder-style method to set the font.
+    pub fn font(mut self, font: FontDescriptor) -> Self {
+        self.font = font;
+        self
+    }
+
+    /// Builder-style method to set the width at which to wrap words.
+    ///
+    /// The default is `f64::INFINITY`, which disables wrapping.
+    pub fn wrap_width(mut self, width: f64) -> Self {
+        self.wrap_width = width.max(0.0);
+        self
+    }
+
+    /// Builder-style method to set the [`TextAlignment`].
+    pub fn alignment(mut self, alignment: TextAlignment) -> Self {
+        self.alignment = alignment;
+        self
+    }
+
+    /// Builder-style method to look up and store measurements in `cache`.
+    pub fn cache(mut self, cache: TextMetricsCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Lay out `text`, for instance to draw it later.
+    ///
+    /// The env is used to resolve theme values, such as the text color.
+    pub fn build<T: TextStorage>(&mut self, text: T, env: &Env) -> TextLayout<T> {
+        let mut layout = self.new_layout(text);
+        layout.rebuild_if_needed(&mut self.factory, env);
+        layout
+    }
+
+    fn new_layout<T: TextStorage>(&self, text: T) -> TextLayout<T> {
+        let mut layout = TextLayout::from_text(text);
+        layout.set_font(self.font.clone());
+        layout.set_wrap_width(self.wrap_width);
+        layout.set_text_alignment(self.alignment);
+        layout
+    }
+
+    /// The metrics of `text`, laid out with this builder's settings.
+    pub fn measure(&mut self, text: &str) -> LayoutMetrics {
+        let text: ArcStr = text.into();
+        let key = self
+            .cache
+            .as_ref()
+            .map(|_| TextCacheKey::new(text.clone(), &self.font, self.wrap_width, self.alignment));
+        if let (Some(cache), Some(key)) = (&self.cache, &key) {
+            if let Some(metrics) = cache.get(key) {
+                return metrics;
+            }
+        }
+
+        // The color doesn't change the metrics, and needn't be resolved from the theme.
+        let mut layout = self.new_layout(text);
+        layout.set_text_color(Color::BLACK);
+        layout.rebuild_if_needed(&mut self.factory, &Env::empty());
+        let metrics = layout.layout_metrics();
+        if let (Some(cache), Some(key)) = (&self.cache, key) {
+            cache.insert(key, metrics);
+        }
+        metrics
+    }
+
+    /// The size of `text`, laid out with this builder's settings.
+    pub fn measure_size(&mut self, text: &str) -> Size {
+        self.measure(text).size
+    }
+}
+
+// --- TextMetricsCache ---
+
+impl TextMetricsCache {
+    /// Create an empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The cached metrics for `key`.
+    pub fn get(&self, key: &TextCacheKey) -> Option<LayoutMetrics> {
+        self.metrics.lock().unwrap().get(key).copied()
+    }
+
+    /// Store the metrics for `key`, eg from a widget's [`TextLayout`].
+    pub fn insert(&self, key: TextCacheKey, metrics: LayoutMetrics) {
+        let mut cached = self.metrics.lock().unwrap();
+        if cached.len() >= MAX_CACHED_METRICS {
+            cached.clear();
+        }
+        cached.insert(key, metrics);
+    }
+
+    /// The vertical metrics of `font`, measured with `factory` the first time they're
+    /// requested.
+    pub fn font_metrics(&self, factory: &mut PietText, font: &FontDescriptor) -> FontMetrics {
+        let key = FontCacheKey::new(font);
+        if let Some(metrics) = self.fonts.lock().unwrap().get(&key) {
+            return *metrics;
+        }
+        let metrics = FontMetrics::measure(factory, font);
+        self.fonts.lock().unwrap().insert(key, metrics);
+        metrics
+    }
+
+    /// Remove all cached metrics, eg after fonts were loaded.
+    pub fn clear(&self) {
+        self.metrics.lock().unwrap().clear();
+        self.fonts.lock().unwrap().clear();
+    }
+
+    /// The number of cached text metrics.
+    pub fn len(&self) -> usize {
+        self.metrics.lock().unwrap().len()
+    }
+
+    /// Returns `true` if no metrics are cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+// --- TextCacheKey ---
+
+impl TextCacheKey {
+    pub(crate) fn new(
+        text: ArcStr,
+        font: &FontDescriptor,
+        wrap_width: f64,
+        alignment: TextAlignment,
+    ) -> Self {
+        TextCacheKey {
+            text,
+            font: FontCacheKey::new(font),
+            wrap_width_bits: wrap_width.to_bits(),
+            alignment: match alignment {
+                TextAlignment::Start => 0,
+                TextAlignment::End => 1,
+                TextAlignment::Center => 2,
+                TextAlignment::Justified => 3,
+            },
+        }
+    }
+}
+
+impl FontCacheKey {
+    fn new(font: &FontDescriptor) -> Self {
+        FontCacheKey {
+            family: font.family.name().into(),
+            size_bits: font.size.to_bits(),
+            weight: font.weight.to_raw(),
+            italic: font.style == crate::piet::FontStyle::Italic,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cached_measurements() {
+        let cache = TextMetricsCache::new();
+        let mut builder = TextLayoutBuilder::new().unwrap().cache(cache.clone());
+
+        let size = builder.measure_size("Hello world");
+        assert!(size.width > 0.0);
+        assert_eq!(cache.len(), 1);
+
+        // The same string is measured again with a different wrap width.
+        let mut builder = builder.wrap_width(20.0);
+        let wrapped = builder.measure_size("Hello world");
+        assert!(wrapped.height > size.height);
+        assert_eq!(cache.len(), 2);
+
+        // Widgets laying out the same text share the cached metrics.
+        let mut layout = TextLayout::<ArcStr>::from_text("Hello world");
+        layout.set_font(FontDescriptor::default());
+        layout.set_wrap_width(20.0);
+        let key = layout.cache_key(&Env::empty()).unwrap();
+        assert_eq!(cache.get(&key).map(|metrics| metrics.size), Some(wrapped));
+    }
+}
@@ -9,14 +9,21 @@
 
 mod attribute;
 mod backspace;
+mod bidi;
 mod editable_text;
+mod editor;
 mod font_descriptor;
+mod font_metrics;
 
 mod input_component;
 mod input_methods;
 mod layout;
+mod line_breaking;
+mod measure;
 mod movement;
 mod rich_text;
+mod shaping;
+mod spell_check;
 mod storage;
 
 pub use druid_shell::text::{
@@ -31,8 +38,15 @@ pub use storage::{ArcStr, TextStorage};
 
 pub use self::attribute::{Attribute, AttributeSpans, Link};
 pub use self::backspace::offset_for_delete_backwards;
+pub use self::bidi::{paragraph_direction, CaretMovement};
 pub use self::editable_text::{EditableText, EditableTextCursor, StringCursor};
+pub use self::editor::{TextEditor, TextEditorMut};
 pub use self::font_descriptor::FontDescriptor;
+pub use self::font_metrics::FontMetrics;
 pub use self::layout::{LayoutMetrics, TextLayout};
-pub use self::movement::movement;
+pub use self::line_breaking::{system_locale, Hyphens};
+pub use self::measure::{TextCacheKey, TextLayoutBuilder, TextMetricsCache};
+pub use self::movement::{movement, movement_with_mode};
+pub use self::shaping::{FontFeature, ShapingAttribute};
+pub use self::spell_check::{SpellChecker, SpellingCorrection};
 pub use crate::piet::{FontFamily, FontStyle, FontWeight, TextAlignment};
@@ -10,8 +10,9 @@ use unicode_segmentation::UnicodeSegmentation;
 
 use crate::kurbo::Point;
 use crate::piet::TextLayout as _;
+use crate::text::bidi::{self, CaretMovement};
 use crate::text::{
-    EditableText, Movement, Selection, TextLayout, TextStorage, VerticalMovement, WritingDirection,
+    Direction, EditableText, Movement, Selection, TextLayout, TextStorage, VerticalMovement,
 };
 
 /// Compute the result of a [`Movement`] on a [`Selection`].
@@ -21,11 +22,25 @@ use crate::text::{
 /// If `modify` is true, only the 'active' edge (the `end`) of the selection
 /// should be changed; this is the case when the user moves with the shift
 /// key pressed.
+///
+/// Left and right movements are logical; see [`movement_with_mode`].
 pub fn movement<T: EditableText + TextStorage>(
     m: Movement,
     s: Selection,
     layout: &TextLayout<T>,
     modify: bool,
+) -> Selection {
+    movement_with_mode(m, s, layout, modify, CaretMovement::Logical)
+}
+
+/// Compute the result of a [`Movement`] on a [`Selection`], moving left and right
+/// across text of mixed directions according to `mode`.
+pub fn movement_with_mode<T: EditableText + TextStorage>(
+    m: Movement,
+    s: Selection,
+    layout: &TextLayout<T>,
+    modify: bool,
+    mode: CaretMovement,
 ) -> Selection {
     let (text, layout) = match (layout.text(), layout.layout()) {
         (Some(text), Some(layout)) => (text, layout),
@@ -35,13 +50,29 @@ pub fn movement<T: EditableText + TextStorage>(
         }
     };
 
-    let writing_direction = if crate::piet::util::first_strong_rtl(text.as_str()) {
-        WritingDirection::RightToLeft
-    } else {
-        WritingDirection::LeftToRight
-    };
+    let writing_direction = bidi::paragraph_direction(text.as_str(), s.active);
 
     let (offset, h_pos) = match m {
+        Movement::Grapheme(d @ (Direction::Left | Direction::Right))
+            if mode == CaretMovement::Visual && (s.is_caret() || modify) =>
+        {
+            let hit = layout.hit_test_text_position(s.active);
+            let lm = layout.line_metric(hit.line).unwrap();
+            let line = lm.start_offset..lm.end_offset;
+            let to_right = matches!(d, Direction::Right);
+            match bidi::visual_caret_move(text.as_str(), line, s.active, to_right) {
+                Some(offset) => (offset, None),
+                // At the end of the line, the caret moves to the adjacent line.
+                None if d.is_upstream_for_direction(writing_direction) => text
+                    .prev_grapheme_offset(s.active)
+                    .map(|off| (off, None))
+                    .unwrap_or((0, s.h_pos)),
+                None => text
+                    .next_grapheme_offset(s.active)
+                    .map(|off| (off, None))
+                    .unwrap_or((s.active, s.h_pos)),
+            }
+        }
         Movement::Grapheme(d) if d.is_upstream_for_direction(writing_direction) => {
             if s.is_caret() || modify {
                 text.prev_grapheme_offset(s.active)
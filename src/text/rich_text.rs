@@ -8,10 +8,11 @@ use std::ops::{Range, RangeBounds};
 use std::sync::Arc;
 
 use super::attribute::Link;
+use super::shaping::{FontFeature, ShapingAttribute};
 use super::{Attribute, AttributeSpans, FontDescriptor, TextStorage};
 use crate::piet::{
-    util, Color, FontFamily, FontStyle, FontWeight, PietTextLayoutBuilder, TextLayoutBuilder,
-    TextStorage as PietTextStorage,
+    util, Color, FontFamily, FontStyle, FontWeight, PietTextLayoutBuilder, TextAttribute,
+    TextLayoutBuilder, TextStorage as PietTextStorage,
 };
 use crate::{ArcStr, Data, Env, KeyOrValue};
 
@@ -83,6 +84,14 @@ impl TextStorage for RichText {
         builder
     }
 
+    fn attribute_spans(&self, env: &Env) -> Option<Vec<(Range<usize>, TextAttribute)>> {
+        Some(self.attrs.to_piet_attrs(env))
+    }
+
+    fn shaping_attributes(&self, env: &Env) -> Vec<(Range<usize>, ShapingAttribute)> {
+        self.attrs.to_shaping_attrs(env)
+    }
+
     fn links(&self) -> &[Link] {
         &self.links
     }
@@ -233,5 +242,23 @@ impl AttributesAdder<'_> {
         self
     }
 
+    /// Add an OpenType feature attribute.
+    pub fn font_feature(&mut self, feature: FontFeature) -> &mut Self {
+        self.add_attr(Attribute::font_feature(feature));
+        self
+    }
+
+    /// Add a letter spacing attribute.
+    pub fn letter_spacing(&mut self, spacing: impl Into<KeyOrValue<f64>>) -> &mut Self {
+        self.add_attr(Attribute::letter_spacing(spacing));
+        self
+    }
+
+    /// Add a word spacing attribute.
+    pub fn word_spacing(&mut self, spacing: impl Into<KeyOrValue<f64>>) -> &mut Self {
+        self.add_attr(Attribute::word_spacing(spacing));
+        self
+    }
+
     //pub fn link(&mut self, command: impl Into<Command>) -> &mut Self;
 }
@@ -0,0 +1,484 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! OpenType features and spacing.
+
+use std::fmt;
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use tracing::warn;
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::line_breaking::SOFT_HYPHEN;
+use super::FontDescriptor;
+use crate::kurbo::{Line, Point, Rect};
+use crate::piet::{
+    Color, LineMetric, PietText, PietTextLayout, Text as _, TextAlignment, TextAttribute,
+    TextLayout as _, TextLayoutBuilder as _,
+};
+use crate::{PaintCtx, RenderContext as _};
+
+/// An OpenType feature setting, such as `liga=0` or `tnum=1`.
+///
+/// See the [OpenType feature registry] for the list of tags. Piet's text backends
+/// don't take features, so [`TextLayout`](super::TextLayout) emulates these:
+///
+/// - `tnum` gives each digit the width of the widest one, and `pnum` cancels it;
+/// - disabling `liga` or `calt` lays out each grapheme on its own, so that the font
+///   can't join them.
+///
+/// Other features are ignored, and a warning is logged the first time it happens.
+///
+/// [OpenType feature registry]: https://learn.microsoft.com/en-us/typography/opentype/spec/featurelist
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FontFeature {
+    tag: [u8; 4],
+    value: u32,
+}
+
+/// A shaping setting applied to a range of text.
+///
+/// [`TextLayout`](super::TextLayout) applies these by splitting the lines of its
+/// layout into runs, which are laid out separately and positioned one after the
+/// other. Letter spacing is added after each grapheme, and word spacing after each
+/// space; see [`FontFeature`] for the features.
+///
+/// Graphemes in separate runs aren't kerned or joined, so letter spacing shouldn't
+/// be used with scripts whose letters connect. Text with right-to-left runs is laid
+/// out as if no shaping attribute was set.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ShapingAttribute {
+    /// An OpenType feature.
+    Feature(FontFeature),
+    /// Extra space added after each character, in points. May be negative.
+    LetterSpacing(f64),
+    /// Extra space added to each space character, in points. May be negative.
+    WordSpacing(f64),
+}
+
+impl FontFeature {
+    /// Standard ligatures, such as "fi". Usually enabled by default.
+    pub const LIGATURES: Self = Self::new(*b"liga", 1);
+    /// Disable standard ligatures.
+    pub const NO_LIGATURES: Self = Self::new(*b"liga", 0);
+    /// Disable contextual alternates, which some fonts use for programming ligatures.
+    pub const NO_CONTEXTUAL_ALTERNATES: Self = Self::new(*b"calt", 0);
+    /// Digits of the same width, so columns of numbers line up.
+    pub const TABULAR_NUMBERS: Self = Self::new(*b"tnum", 1);
+    /// Digits of varying width.
+    pub const PROPORTIONAL_NUMBERS: Self = Self::new(*b"pnum", 1);
+    /// A zero with a slash through it.
+    pub const SLASHED_ZERO: Self = Self::new(*b"zero", 1);
+    /// Small capitals instead of lowercase letters.
+    pub const SMALL_CAPS: Self = Self::new(*b"smcp", 1);
+
+    /// Create a feature setting from its four-letter tag.
+    ///
+    /// For boolean features, `value` is 0 to disable the feature and 1 to enable it.
+    /// For others, it selects one of the feature's alternates.
+    pub const fn new(tag: [u8; 4], value: u32) -> Self {
+        FontFeature { tag, value }
+    }
+
+    /// The feature's four-letter tag.
+    pub fn tag(&self) -> [u8; 4] {
+        self.tag
+    }
+
+    /// The feature's value.
+    pub fn value(&self) -> u32 {
+        self.value
+    }
+}
+
+impl fmt::Display for FontFeature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", String::from_utf8_lossy(&self.tag), self.value)
+    }
+}
+
+impl fmt::Debug for FontFeature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FontFeature({})", self)
+    }
+}
+
+static WARNED_UNSUPPORTED: AtomicBool = AtomicBool::new(false);
+
+fn warn_unsupported(what: &dyn fmt::Debug) {
+    if !WARNED_UNSUPPORTED.swap(true, Ordering::Relaxed) {
+        warn!("{:?} ignored: the text backend doesn't support it", what);
+    }
+}
+
+/// The shaping settings of a grapheme, from the layout's defaults and the text's spans.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub(crate) struct GraphemeShaping {
+    letter_spacing: f64,
+    word_spacing: f64,
+    tabular_numbers: bool,
+    no_ligatures: bool,
+    no_contextual_alternates: bool,
+}
+
+impl GraphemeShaping {
+    /// Apply `attr` on top of the current settings.
+    pub(crate) fn apply(&mut self, attr: &ShapingAttribute) {
+        match attr {
+            ShapingAttribute::Feature(feature) => {
+                let enabled = feature.value() != 0;
+                match &feature.tag() {
+                    b"tnum" => self.tabular_numbers = enabled,
+                    b"pnum" if enabled => self.tabular_numbers = false,
+                    b"pnum" => {}
+                    b"liga" => self.no_ligatures = !enabled,
+                    b"calt" => self.no_contextual_alternates = !enabled,
+                    _ => warn_unsupported(feature),
+                }
+            }
+            ShapingAttribute::LetterSpacing(spacing) => self.letter_spacing = *spacing,
+            ShapingAttribute::WordSpacing(spacing) => self.word_spacing = *spacing,
+        }
+    }
+
+    /// The settings of the grapheme at `offset` in the text: `self`, overridden by
+    /// the `spans` containing it.
+    fn at(&self, spans: &[(Range<usize>, ShapingAttribute)], offset: usize) -> Self {
+        let mut shaping = *self;
+        for (_, attr) in spans.iter().filter(|(range, _)| range.contains(&offset)) {
+            shaping.apply(attr);
+        }
+        shaping
+    }
+}
+
+/// A part of a line, laid out on its own.
+#[derive(Clone)]
+struct PositionedRun {
+    layout: PietTextLayout,
+    origin: Point,
+}
+
+/// A line of a layout split into runs.
+#[derive(Clone)]
+struct RunLine {
+    metric: LineMetric,
+    /// The offset in the layout of each grapheme boundary on the line, up to the
+    /// line break, with its x position.
+    boundaries: Vec<(usize, f64)>,
+}
+
+impl RunLine {
+    /// The x position of the last grapheme boundary at or before `offset`.
+    fn x_at(&self, offset: usize) -> f64 {
+        let index = self
+            .boundaries
+            .partition_point(|(boundary, _)| *boundary <= offset);
+        self.boundaries[index.saturating_sub(1)].1
+    }
+
+    /// The offset of the end of the line, before its line break.
+    fn content_end(&self) -> usize {
+        self.boundaries.last().unwrap().0
+    }
+}
+
+/// The style of the runs, which the layout applied to the whole text.
+pub(crate) struct RunStyle<'a> {
+    pub(crate) font: &'a FontDescriptor,
+    pub(crate) color: &'a Color,
+    pub(crate) alignment: TextAlignment,
+    /// The style spans, as ranges of the layout's text.
+    pub(crate) spans: &'a [(Range<usize>, TextAttribute)],
+}
+
+/// The lines of a layout split into runs, to apply its [`ShapingAttribute`]s.
+///
+/// The layout is still used to break lines, so the spacing isn't taken into account
+/// when wrapping text.
+#[derive(Clone)]
+pub(crate) struct PositionedRuns {
+    runs: Vec<PositionedRun>,
+    lines: Vec<RunLine>,
+    width: f64,
+}
+
+impl PositionedRuns {
+    /// Split the lines of `layout` into runs, if shaping attributes change it.
+    ///
+    /// `defaults` are the settings of the whole text, which `spans` override.
+    /// `to_text_offset` converts an offset in the layout's text to an offset in
+    /// the text the spans refer to.
+    pub(crate) fn build(
+        layout: &PietTextLayout,
+        factory: &mut PietText,
+        style: &RunStyle,
+        defaults: &GraphemeShaping,
+        spans: &[(Range<usize>, ShapingAttribute)],
+        to_text_offset: impl Fn(usize) -> usize,
+    ) -> Option<Self> {
+        if spans.is_empty() && *defaults == GraphemeShaping::default() {
+            return None;
+        }
+        let text = layout.text();
+        if layout.line_count() == 0 {
+            return None;
+        }
+        if super::bidi::has_rtl(text) {
+            warn_unsupported(&"Shaping attributes in right-to-left text");
+            return None;
+        }
+
+        let mut digit_width = None;
+        let mut runs = Vec::new();
+        let mut lines = Vec::new();
+        for line in 0..layout.line_count() {
+            let metric = layout.line_metric(line).unwrap();
+            let line_text = &text[metric.start_offset..metric.end_offset];
+            let mut builder = LineBuilder::new(metric.start_offset..metric.end_offset);
+
+            for (i, grapheme) in line_text.grapheme_indices(true) {
+                if grapheme.ends_with(['\n', '\r']) {
+                    break;
+                }
+                let start = metric.start_offset + i;
+                let shaping = defaults.at(spans, to_text_offset(start));
+                let is_digit = grapheme.len() == 1 && grapheme.as_bytes()[0].is_ascii_digit();
+                let tabular_digit = shaping.tabular_numbers && is_digit;
+                let alone =
+                    tabular_digit || shaping.no_ligatures || shaping.no_contextual_alternates;
+
+                if alone {
+                    builder.finish_run(text, factory, style);
+                }
+                builder.boundaries.push((start, 0.0));
+                builder.run.end = start + grapheme.len();
+
+                let mut extra = shaping.letter_spacing;
+                if grapheme == " " {
+                    extra += shaping.word_spacing;
+                }
+                if alone {
+                    let advance = builder.finish_run(text, factory, style);
+                    if tabular_digit {
+                        let digit_width =
+                            *digit_width.get_or_insert_with(|| widest_digit(factory, style));
+                        extra += (digit_width - advance).max(0.0);
+                    }
+                }
+                if extra != 0.0 {
+                    builder.finish_run(text, factory, style);
+                    builder.x += extra;
+                    builder.added += extra;
+                }
+            }
+            builder.finish_run(text, factory, style);
+            builder.boundaries.push((builder.run.end, builder.x));
+
+            // The alignment placed the line for its width without spacing.
+            let line_start = layout.hit_test_text_position(metric.start_offset).point.x;
+            let x0 = match style.alignment {
+                TextAlignment::Start | TextAlignment::Justified => line_start,
+                TextAlignment::Center => line_start - builder.added / 2.0,
+                TextAlignment::End => line_start - builder.added,
+            };
+            let baseline = metric.y_offset + metric.baseline;
+            runs.extend(builder.runs.into_iter().map(|(layout, x)| {
+                let run_baseline = layout.line_metric(0).map_or(0.0, |lm| lm.baseline);
+                PositionedRun {
+                    layout,
+                    origin: Point::new(x0 + x, baseline - run_baseline),
+                }
+            }));
+            let boundaries = builder
+                .boundaries
+                .into_iter()
+                .map(|(offset, x)| (offset, x0 + x))
+                .collect();
+            lines.push(RunLine { metric, boundaries });
+        }
+
+        // Lines moved left to stay aligned may now start before the layout.
+        let min_x = lines
+            .iter()
+            .map(|line| line.boundaries[0].1)
+            .fold(0.0, f64::min);
+        for run in &mut runs {
+            run.origin.x -= min_x;
+        }
+        for (_, x) in lines.iter_mut().flat_map(|line| &mut line.boundaries) {
+            *x -= min_x;
+        }
+        let width = lines
+            .iter()
+            .map(|line| line.boundaries.last().unwrap().1)
+            .fold(0.0, f64::max);
+
+        Some(PositionedRuns { runs, lines, width })
+    }
+
+    /// The width of the widest line, including its spacing.
+    pub(crate) fn width(&self) -> f64 {
+        self.width
+    }
+
+    /// The length of the text laid out in the runs.
+    pub(crate) fn text_len(&self) -> usize {
+        self.runs.iter().map(|run| run.layout.text().len()).sum()
+    }
+
+    /// Draw the runs, with the layout's origin at `origin`.
+    pub(crate) fn draw(&self, ctx: &mut PaintCtx, origin: Point) {
+        for run in &self.runs {
+            ctx.draw_text(&run.layout, origin + run.origin.to_vec2());
+        }
+    }
+
+    fn line_for_offset(&self, offset: usize) -> &RunLine {
+        self.lines
+            .iter()
+            .find(|line| offset < line.metric.end_offset)
+            .unwrap_or_else(|| self.lines.last().unwrap())
+    }
+
+    /// The position of the grapheme boundary at `offset` in the layout, on the
+    /// line's baseline.
+    pub(crate) fn point_for_offset(&self, offset: usize) -> Point {
+        let line = self.line_for_offset(offset);
+        let y = line.metric.y_offset + line.metric.baseline;
+        Point::new(line.x_at(offset), y)
+    }
+
+    /// The vertical line at the grapheme boundary at `offset`, as tall as its line.
+    pub(crate) fn cursor_line(&self, offset: usize) -> Line {
+        let line = self.line_for_offset(offset);
+        let x = line.x_at(offset);
+        let y0 = line.metric.y_offset;
+        Line::new((x, y0), (x, y0 + line.metric.height))
+    }
+
+    /// The offset in the layout of the grapheme boundary closest to `point`.
+    pub(crate) fn offset_for_point(&self, point: Point) -> usize {
+        let line = self
+            .lines
+            .iter()
+            .find(|line| point.y < line.metric.y_offset + line.metric.height)
+            .unwrap_or_else(|| self.lines.last().unwrap());
+        let distance = |x: f64| (x - point.x).abs();
+        line.boundaries
+            .iter()
+            .min_by(|(_, a), (_, b)| distance(*a).total_cmp(&distance(*b)))
+            .unwrap()
+            .0
+    }
+
+    /// The rects covering `range` of the layout's text, one per line.
+    pub(crate) fn rects_for_range(&self, range: Range<usize>) -> Vec<Rect> {
+        self.lines
+            .iter()
+            .filter_map(|line| {
+                let start = range.start.max(line.metric.start_offset);
+                let end = range.end.min(line.content_end());
+                if start >= end {
+                    return None;
+                }
+                let y0 = line.metric.y_offset;
+                Some(Rect::new(
+                    line.x_at(start),
+                    y0,
+                    line.x_at(end),
+                    y0 + line.metric.height,
+                ))
+            })
+            .collect()
+    }
+}
+
+/// The runs and grapheme boundaries of a line being split, with x positions
+/// relative to the start of the line.
+struct LineBuilder {
+    line: Range<usize>,
+    /// The range of the layout's text in the current run.
+    run: Range<usize>,
+    runs: Vec<(PietTextLayout, f64)>,
+    boundaries: Vec<(usize, f64)>,
+    /// The x position of the end of the last run, including the spacing after it.
+    x: f64,
+    /// The spacing added to the line so far.
+    added: f64,
+}
+
+impl LineBuilder {
+    fn new(line: Range<usize>) -> Self {
+        LineBuilder {
+            run: line.start..line.start,
+            line,
+            runs: Vec::new(),
+            boundaries: Vec::new(),
+            x: 0.0,
+            added: 0.0,
+        }
+    }
+
+    /// Lay out the current run at the end of the line, and start the next one
+    /// after it.
+    ///
+    /// Returns the advance of the run, which is zero if it's empty.
+    fn finish_run(&mut self, text: &str, factory: &mut PietText, style: &RunStyle) -> f64 {
+        let run = self.run.clone();
+        if run.is_empty() {
+            return 0.0;
+        }
+        let mut run_text = text[run.clone()].to_string();
+        // A soft hyphen is only displayed where the line was broken.
+        if run.end == self.line.end && run_text.ends_with(SOFT_HYPHEN) {
+            run_text.pop();
+            run_text.push('-');
+        }
+        let builder = factory
+            .new_text_layout(run_text)
+            .font(style.font.family.clone(), style.font.size)
+            .default_attribute(style.font.weight)
+            .default_attribute(style.font.style)
+            .default_attribute(TextAttribute::TextColor(style.color.clone()));
+        let builder = style
+            .spans
+            .iter()
+            .filter(|(range, _)| range.start < run.end && run.start < range.end)
+            .fold(builder, |builder, (range, attr)| {
+                let start = range.start.max(run.start) - run.start;
+                let end = range.end.min(run.end) - run.start;
+                builder.range_attribute(start..end, attr.clone())
+            });
+        let layout = builder.build().unwrap();
+
+        for (offset, x) in &mut self.boundaries {
+            if run.contains(offset) {
+                *x = self.x + layout.hit_test_text_position(*offset - run.start).point.x;
+            }
+        }
+        let advance = layout.trailing_whitespace_width();
+        self.runs.push((layout, self.x));
+        self.x += advance;
+        self.run = run.end..run.end;
+        advance
+    }
+}
+
+/// The advance of the widest digit in the style's font.
+fn widest_digit(factory: &mut PietText, style: &RunStyle) -> f64 {
+    ('0'..='9')
+        .filter_map(|digit| {
+            factory
+                .new_text_layout(digit.to_string())
+                .font(style.font.family.clone(), style.font.size)
+                .default_attribute(style.font.weight)
+                .default_attribute(style.font.style)
+                .build()
+                .ok()
+        })
+        .map(|layout| layout.trailing_whitespace_width())
+        .fold(0.0, f64::max)
+}
@@ -0,0 +1,83 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Spell-checking hooks for editable text.
+
+use std::fmt;
+use std::ops::Range;
+
+use crate::kurbo::BezPath;
+
+/// A dictionary used by text widgets to find misspelled words.
+///
+/// Masonry doesn't ship dictionaries; apps implement this trait, eg on top of the
+/// platform's spell checker or a crate such as `hunspell`. A spell checker is set
+/// on a text widget with [`TextBox::with_spell_checker`], and can be shared by
+/// several widgets.
+///
+/// The widget checks its whole text each time it changes, underlines the ranges
+/// returned by [`check`](Self::check), and offers [`suggestions`](Self::suggestions)
+/// in a context menu when a misspelled word is right-clicked.
+///
+/// [`TextBox::with_spell_checker`]: crate::widget::TextBox::with_spell_checker
+pub trait SpellChecker {
+    /// The ranges of the misspelled words in `text`, in increasing order.
+    fn check(&self, text: &str) -> Vec<Range<usize>>;
+
+    /// Replacements for the misspelled `word`, best first.
+    fn suggestions(&self, word: &str) -> Vec<String>;
+
+    /// Returns `true` if words can be added to the dictionary with
+    /// [`learn_word`](Self::learn_word).
+    ///
+    /// If so, the context menu has an item to add the misspelled word.
+    fn can_learn_words(&self) -> bool {
+        false
+    }
+
+    /// Accept `word` as correctly spelled from now on.
+    fn learn_word(&self, word: &str) {
+        let _ = word;
+    }
+}
+
+impl fmt::Debug for dyn SpellChecker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SpellChecker")
+    }
+}
+
+/// A replacement for a misspelled word, picked from a text widget's context menu.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpellingCorrection {
+    /// The range of the word in the text.
+    pub range: Range<usize>,
+    /// The misspelled word.
+    ///
+    /// The correction is ignored if the text in `range` has changed since.
+    pub word: String,
+    /// The word to replace it with.
+    pub replacement: String,
+}
+
+/// The most suggestions shown in a context menu.
+pub(crate) const MAX_SUGGESTIONS: usize = 5;
+
+/// A wavy line from `x0` to `x1` around `y`, to underline a misspelled word.
+pub(crate) fn squiggle(x0: f64, x1: f64, y: f64) -> BezPath {
+    const HALF_PERIOD: f64 = 2.0;
+    const AMPLITUDE: f64 = 1.0;
+
+    let mut path = BezPath::new();
+    path.move_to((x0, y + AMPLITUDE));
+    let mut x = x0;
+    let mut up = true;
+    while x < x1 {
+        x = (x + HALF_PERIOD).min(x1);
+        let y = if up { y - AMPLITUDE } else { y + AMPLITUDE };
+        path.line_to((x, y));
+        up = !up;
+    }
+    path
+}
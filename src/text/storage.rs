@@ -4,10 +4,12 @@
 
 //! Storing text.
 
+use std::ops::Range;
 use std::sync::Arc;
 
 use super::attribute::Link;
-use crate::piet::{PietTextLayoutBuilder, TextStorage as PietTextStorage};
+use super::ShapingAttribute;
+use crate::piet::{PietTextLayoutBuilder, TextAttribute, TextStorage as PietTextStorage};
 use crate::{Data, Env};
 
 /// A type that represents text that can be displayed.
@@ -19,6 +21,27 @@ pub trait TextStorage: PietTextStorage + Data {
         builder
     }
 
+    /// The style spans added by [`add_attributes`](Self::add_attributes), if known.
+    ///
+    /// Layouts which insert characters into the text, such as soft hyphens for
+    /// hyphenation, use this to move the spans. They're disabled for text which
+    /// returns `None`, which is the default.
+    #[allow(unused_variables)]
+    fn attribute_spans(&self, env: &Env) -> Option<Vec<(Range<usize>, TextAttribute)>> {
+        None
+    }
+
+    /// The OpenType features and spacing of ranges of this text.
+    ///
+    /// Piet doesn't take these, so they're applied by the [`TextLayout`] instead
+    /// of [`add_attributes`](Self::add_attributes).
+    ///
+    /// [`TextLayout`]: super::TextLayout
+    #[allow(unused_variables)]
+    fn shaping_attributes(&self, env: &Env) -> Vec<(Range<usize>, ShapingAttribute)> {
+        Vec::new()
+    }
+
     /// Any additional [`Link`] attributes on this text.
     ///
     /// If this `TextStorage` object manages link attributes, it should implement this
@@ -40,8 +63,20 @@ pub trait TextStorage: PietTextStorage + Data {
 /// it cannot be mutated, but unlike `String` it can be cheaply cloned.
 pub type ArcStr = Arc<str>;
 
-impl TextStorage for ArcStr {}
+impl TextStorage for ArcStr {
+    fn attribute_spans(&self, _env: &Env) -> Option<Vec<(Range<usize>, TextAttribute)>> {
+        Some(Vec::new())
+    }
+}
 
-impl TextStorage for String {}
+impl TextStorage for String {
+    fn attribute_spans(&self, _env: &Env) -> Option<Vec<(Range<usize>, TextAttribute)>> {
+        Some(Vec::new())
+    }
+}
 
-impl TextStorage for Arc<String> {}
+impl TextStorage for Arc<String> {
+    fn attribute_spans(&self, _env: &Env) -> Option<Vec<(Range<usize>, TextAttribute)>> {
+        Some(Vec::new())
+    }
+}
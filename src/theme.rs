@@ -8,7 +8,7 @@
 
 use crate::piet::{Color, FontFamily, FontStyle, FontWeight};
 use crate::text::FontDescriptor;
-use crate::{Env, Insets, Key};
+use crate::{ArcStr, Env, Insets, Key};
 
 pub const WINDOW_BACKGROUND_COLOR: Key<Color> =
     Key::new("org.masonry.theme.window_background_color");
@@ -46,6 +46,35 @@ pub const SELECTED_TEXT_INACTIVE_BACKGROUND_COLOR: Key<Color> =
     Key::new("org.masonry.theme.selection_color_inactive");
 pub const SELECTION_TEXT_COLOR: Key<Color> = Key::new("org.masonry.theme.selection_text_color");
 pub const CURSOR_COLOR: Key<Color> = Key::new("org.masonry.theme.cursor_color");
+/// The color of the wavy line under misspelled words.
+pub const SPELLING_ERROR_COLOR: Key<Color> = Key::new("org.masonry.theme.spelling_error_color");
+/// The background of highlighted text, eg search results.
+pub const TEXT_HIGHLIGHT_COLOR: Key<Color> = Key::new("org.masonry.theme.text_highlight_color");
+
+/// The maximum delay between two clicks for them to count as a double-click, in milliseconds.
+///
+/// The window counts the clicks of [`MouseEvent::count`](crate::MouseEvent::count) with it.
+pub const DOUBLE_CLICK_INTERVAL: Key<u64> = Key::new("org.masonry.theme.double_click_interval");
+/// The distance the mouse must move while pressed before a drag gesture starts.
+pub const DRAG_THRESHOLD: Key<f64> = Key::new("org.masonry.theme.drag_threshold");
+/// The time between two toggles of a blinking text cursor, in milliseconds.
+pub const CURSOR_BLINK_INTERVAL: Key<u64> = Key::new("org.masonry.theme.cursor_blink_interval");
+
+/// The color of the ring the window paints around the focused widget.
+pub const FOCUS_RING_COLOR: Key<Color> = Key::new("org.masonry.theme.focus_ring_color");
+/// The stroke width of the focus ring.
+pub const FOCUS_RING_WIDTH: Key<f64> = Key::new("org.masonry.theme.focus_ring_width");
+/// The gap between the focused widget's layout rect and its focus ring.
+pub const FOCUS_RING_OFFSET: Key<f64> = Key::new("org.masonry.theme.focus_ring_offset");
+/// The color of the veil painted over busy widgets, see
+/// [`EventCtx::begin_busy_with_veil`](crate::EventCtx::begin_busy_with_veil).
+pub const BUSY_VEIL_COLOR: Key<Color> = Key::new("org.masonry.theme.busy_veil_color");
+/// The color painted over the window's backdrop, see
+/// [`BackdropRegion`](crate::widget::BackdropRegion).
+pub const BACKDROP_TINT_COLOR: Key<Color> = Key::new("org.masonry.theme.backdrop_tint_color");
+/// The color of the drop shadow of widgets with an elevation, see
+/// [`WidgetPod::with_elevation`](crate::WidgetPod::with_elevation).
+pub const SHADOW_COLOR: Key<Color> = Key::new("org.masonry.theme.shadow_color");
 
 pub const TEXT_SIZE_NORMAL: Key<f64> = Key::new("org.masonry.theme.text_size_normal");
 pub const TEXT_SIZE_LARGE: Key<f64> = Key::new("org.masonry.theme.text_size_large");
@@ -88,6 +117,133 @@ pub const SCROLLBAR_EDGE_WIDTH: Key<f64> = Key::new("org.masonry.theme.scrollbar
 /// Minimum length for any scrollbar to be when measured on that
 /// scrollbar's primary axis.
 pub const SCROLLBAR_MIN_SIZE: Key<f64> = Key::new("org.linebender.theme.scrollbar_min_size");
+/// The color of the glow painted by portals scrolled past their edge, see
+/// [`OverscrollEffect::Glow`](crate::widget::OverscrollEffect::Glow).
+pub const OVERSCROLL_GLOW_COLOR: Key<Color> = Key::new("org.masonry.theme.overscroll_glow_color");
+/// How far, in pixels, the content of a portal can be scrolled past its edge.
+pub const OVERSCROLL_MAX_DISTANCE: Key<f64> = Key::new("org.masonry.theme.overscroll_max_distance");
+
+/// Whether Ctrl+= (or Ctrl++), Ctrl+- and Ctrl+0 change the [UI scale](Env::UI_SCALE)
+/// of a window, with Cmd instead of Ctrl on macOS.
+///
+/// Shift is allowed with `+`, which some layouts need it to type, so Ctrl+Shift+-
+/// and Ctrl+Shift+0 remain free for the app. The shortcuts only apply to key presses
+/// no widget handled.
+pub const ZOOM_SHORTCUTS_ENABLED: Key<bool> = Key::new("org.masonry.theme.zoom_shortcuts_enabled");
+
+/// Whether UI sounds requested with [`play_sound`](crate::EventCtx::play_sound) are played.
+pub const SOUNDS_ENABLED: Key<bool> = Key::new("org.masonry.theme.sounds_enabled");
+/// Path of the sound file played when a button is clicked. Empty means silent.
+pub const SOUND_CLICK: Key<ArcStr> = Key::new("org.masonry.theme.sound_click");
+/// Path of the sound file played when an error is reported. Empty means silent.
+pub const SOUND_ERROR: Key<ArcStr> = Key::new("org.masonry.theme.sound_error");
+/// Path of the sound file played for notifications. Empty means silent.
+pub const SOUND_NOTIFICATION: Key<ArcStr> = Key::new("org.masonry.theme.sound_notification");
+
+/// The metrics multiplied by a window's [UI scale](Env::UI_SCALE).
+const SCALED_METRICS: &[Key<f64>] = &[
+    PROGRESS_BAR_RADIUS,
+    BUTTON_BORDER_RADIUS,
+    BUTTON_BORDER_WIDTH,
+    FOCUS_RING_WIDTH,
+    FOCUS_RING_OFFSET,
+    TEXT_SIZE_NORMAL,
+    TEXT_SIZE_LARGE,
+    BASIC_WIDGET_HEIGHT,
+    WIDE_WIDGET_WIDTH,
+    BORDERED_WIDGET_HEIGHT,
+    TEXTBOX_BORDER_RADIUS,
+    TEXTBOX_BORDER_WIDTH,
+    WIDGET_PADDING_HORIZONTAL,
+    WIDGET_PADDING_VERTICAL,
+    WIDGET_CONTROL_COMPONENT_PADDING,
+    SCROLLBAR_WIDTH,
+    SCROLLBAR_PAD,
+    SCROLLBAR_RADIUS,
+    SCROLLBAR_EDGE_WIDTH,
+    SCROLLBAR_MIN_SIZE,
+    DRAG_THRESHOLD,
+    OVERSCROLL_MAX_DISTANCE,
+];
+
+/// The fonts whose size is multiplied by a window's [UI scale](Env::UI_SCALE).
+const SCALED_FONTS: &[Key<FontDescriptor>] = &[UI_FONT, UI_FONT_BOLD, UI_FONT_ITALIC];
+
+/// Multiply the text sizes and metrics of `env` by `scale`, and set
+/// [`Env::UI_SCALE`].
+///
+/// Keys missing from `env` are skipped.
+pub(crate) fn scale_metrics(env: &Env, scale: f64) -> Env {
+    let mut env = env.clone();
+    for key in SCALED_METRICS {
+        if let Ok(value) = env.try_get(key) {
+            env.set(key.clone(), value * scale);
+        }
+    }
+    for key in SCALED_FONTS {
+        if let Ok(font) = env.try_get(key) {
+            let size = font.size * scale;
+            env.set(key.clone(), font.with_size(size));
+        }
+    }
+    if let Ok(insets) = env.try_get(TEXTBOX_INSETS) {
+        let insets = Insets::new(
+            insets.x0 * scale,
+            insets.y0 * scale,
+            insets.x1 * scale,
+            insets.y1 * scale,
+        );
+        env.set(TEXTBOX_INSETS, insets);
+    }
+    env.set(Env::UI_SCALE, scale);
+    env
+}
+
+/// Replace the colors of `env` with a high-contrast palette, for
+/// [`Env::HIGH_CONTRAST`].
+///
+/// The palette follows the "High Contrast Black" theme of Windows: white text on
+/// black, with cyan highlights and a yellow focus ring.
+pub(crate) fn add_high_contrast_palette(env: Env) -> Env {
+    let background = Color::BLACK;
+    let text = Color::WHITE;
+    let disabled = Color::rgb8(0x3f, 0xf2, 0x3f);
+    let highlight = Color::rgb8(0x1a, 0xeb, 0xff);
+    env.adding(WINDOW_BACKGROUND_COLOR, background.clone())
+        .adding(TEXT_COLOR, text.clone())
+        .adding(DISABLED_TEXT_COLOR, disabled.clone())
+        .adding(PLACEHOLDER_COLOR, disabled.clone())
+        .adding(PRIMARY_LIGHT, highlight.clone())
+        .adding(PRIMARY_DARK, highlight.clone())
+        .adding(BACKGROUND_LIGHT, background.clone())
+        .adding(BACKGROUND_DARK, background.clone())
+        .adding(FOREGROUND_LIGHT, text.clone())
+        .adding(FOREGROUND_DARK, text.clone())
+        .adding(DISABLED_FOREGROUND_LIGHT, disabled.clone())
+        .adding(DISABLED_FOREGROUND_DARK, disabled.clone())
+        .adding(BUTTON_DARK, background.clone())
+        .adding(BUTTON_LIGHT, background.clone())
+        .adding(DISABLED_BUTTON_DARK, background.clone())
+        .adding(DISABLED_BUTTON_LIGHT, background.clone())
+        .adding(BORDER_DARK, text.clone())
+        .adding(BORDER_LIGHT, highlight.clone())
+        .adding(SELECTED_TEXT_BACKGROUND_COLOR, highlight.clone())
+        .adding(SELECTED_TEXT_INACTIVE_BACKGROUND_COLOR, text.clone())
+        .adding(SELECTION_TEXT_COLOR, background.clone())
+        .adding(CURSOR_COLOR, text.clone())
+        .adding(TEXT_HIGHLIGHT_COLOR, highlight.clone())
+        .adding(FOCUS_RING_COLOR, Color::rgb8(0xff, 0xff, 0x00))
+        .adding(SCROLLBAR_COLOR, text.clone())
+        .adding(SCROLLBAR_BORDER_COLOR, background)
+        .adding(SCROLLBAR_MAX_OPACITY, 1.0)
+        .adding(OVERSCROLL_GLOW_COLOR, highlight)
+}
+
+// TODO - Read these from the system settings once druid-shell exposes them.
+// In the meantime, we use the default settings of each platform.
+const SYSTEM_DOUBLE_CLICK_INTERVAL: u64 = if cfg!(target_os = "linux") { 400 } else { 500 };
+const SYSTEM_DRAG_THRESHOLD: f64 = if cfg!(target_os = "macos") { 3.0 } else { 4.0 };
+const SYSTEM_CURSOR_BLINK_INTERVAL: u64 = if cfg!(target_os = "linux") { 600 } else { 530 };
 
 /// An initial theme.
 pub(crate) fn add_to_env(env: Env) -> Env {
@@ -119,6 +275,17 @@ pub(crate) fn add_to_env(env: Env) -> Env {
         .adding(SELECTED_TEXT_INACTIVE_BACKGROUND_COLOR, Color::grey8(0x74))
         .adding(SELECTION_TEXT_COLOR, Color::rgb8(0x00, 0x00, 0x00))
         .adding(CURSOR_COLOR, Color::WHITE)
+        .adding(SPELLING_ERROR_COLOR, Color::rgb8(0xf0, 0x50, 0x50))
+        .adding(TEXT_HIGHLIGHT_COLOR, Color::rgba8(0xff, 0xd7, 0x00, 0x80))
+        .adding(DOUBLE_CLICK_INTERVAL, SYSTEM_DOUBLE_CLICK_INTERVAL)
+        .adding(DRAG_THRESHOLD, SYSTEM_DRAG_THRESHOLD)
+        .adding(CURSOR_BLINK_INTERVAL, SYSTEM_CURSOR_BLINK_INTERVAL)
+        .adding(FOCUS_RING_COLOR, Color::rgb8(0x5c, 0xc4, 0xff))
+        .adding(FOCUS_RING_WIDTH, 2.)
+        .adding(FOCUS_RING_OFFSET, 1.)
+        .adding(BUSY_VEIL_COLOR, Color::rgba8(0x00, 0x00, 0x00, 0x60))
+        .adding(SHADOW_COLOR, Color::rgba8(0x00, 0x00, 0x00, 0x66))
+        .adding(BACKDROP_TINT_COLOR, Color::rgba8(0x29, 0x29, 0x29, 0x99))
         .adding(TEXT_SIZE_NORMAL, 15.0)
         .adding(TEXT_SIZE_LARGE, 24.0)
         .adding(BASIC_WIDGET_HEIGHT, 18.0)
@@ -136,9 +303,16 @@ pub(crate) fn add_to_env(env: Env) -> Env {
         .adding(SCROLLBAR_MIN_SIZE, 45.)
         .adding(SCROLLBAR_RADIUS, 5.)
         .adding(SCROLLBAR_EDGE_WIDTH, 1.)
+        .adding(OVERSCROLL_GLOW_COLOR, Color::rgba8(0x5c, 0xc4, 0xff, 0x80))
+        .adding(OVERSCROLL_MAX_DISTANCE, 80.)
         .adding(WIDGET_PADDING_VERTICAL, 10.0)
         .adding(WIDGET_PADDING_HORIZONTAL, 8.0)
         .adding(WIDGET_CONTROL_COMPONENT_PADDING, 4.0)
+        .adding(ZOOM_SHORTCUTS_ENABLED, true)
+        .adding(SOUNDS_ENABLED, true)
+        .adding(SOUND_CLICK, "")
+        .adding(SOUND_ERROR, "")
+        .adding(SOUND_NOTIFICATION, "")
         .adding(
             UI_FONT,
             FontDescriptor::new(FontFamily::SYSTEM_UI).with_size(15.0),
@@ -0,0 +1,166 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Recording the last frames of a window, to step back through them while debugging.
+//!
+//! When [`DebugSwitch::FrameHistory`](crate::debug_switches::DebugSwitch::FrameHistory)
+//! is enabled, each window keeps an image of itself and the layout of its widgets
+//! after every paint. [`SCRUB_FRAME`](crate::command::SCRUB_FRAME) then shows one of
+//! these frames instead of the live widgets, which makes it possible to find the
+//! frame where a widget stopped being repainted or laid out.
+//!
+//! Piet can't record draw calls to replay them later, so frames are kept as bitmaps,
+//! painted a second time on the CPU. This is slow and uses a lot of memory, and is
+//! only meant for debugging.
+
+use std::collections::VecDeque;
+
+use druid_shell::Region;
+use tracing::error;
+
+use crate::headless::SceneNode;
+use crate::piet::{ImageBuf, ImageFormat, InterpolationMode, Piet, RenderContext};
+use crate::{Rect, Size};
+
+/// The number of frames kept by
+/// [`DebugSwitch::FrameHistory`](crate::debug_switches::DebugSwitch::FrameHistory).
+pub(crate) const FRAME_HISTORY_LENGTH: u64 = 120;
+
+/// A frame painted by a window.
+pub(crate) struct RecordedFrame {
+    /// The number of frames recorded before this one, since the window was created.
+    pub(crate) number: u64,
+    /// The widgets of the window, as laid out for the frame.
+    #[cfg_attr(not(feature = "remote_debug"), allow(dead_code))]
+    pub(crate) scene: SceneNode,
+    /// The window, as painted.
+    pub(crate) image: ImageBuf,
+}
+
+impl RecordedFrame {
+    /// Paint the `invalid` region of the frame, for a window of the given size.
+    pub(crate) fn paint(&self, piet: &mut Piet, window_size: Size, invalid: &Region) {
+        let image = match piet.make_image(
+            self.image.width(),
+            self.image.height(),
+            self.image.raw_pixels(),
+            ImageFormat::RgbaPremul,
+        ) {
+            Ok(image) => image,
+            Err(err) => {
+                error!("Can't upload recorded frame: {}", err);
+                return;
+            }
+        };
+        let scale = self.image.width() as f64 / window_size.width;
+        for &rect in invalid.rects() {
+            let source = Rect::new(
+                rect.x0 * scale,
+                rect.y0 * scale,
+                rect.x1 * scale,
+                rect.y1 * scale,
+            );
+            piet.draw_image_area(&image, source, rect, InterpolationMode::NearestNeighbor);
+        }
+    }
+}
+
+/// The last frames painted by a window, and the one currently shown, if any.
+#[derive(Default)]
+pub(crate) struct FrameHistory {
+    frames: VecDeque<RecordedFrame>,
+    capacity: usize,
+    recorded: u64,
+    scrubbed: Option<usize>,
+}
+
+impl FrameHistory {
+    /// Set the number of frames to keep, dropping the oldest ones if needed.
+    ///
+    /// A capacity of zero stops recording.
+    pub(crate) fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.frames.len() > capacity {
+            self.frames.pop_front();
+        }
+        if capacity == 0 {
+            self.scrubbed = None;
+        }
+    }
+
+    /// Whether frames should be recorded.
+    pub(crate) fn is_recording(&self) -> bool {
+        self.capacity > 0 && self.scrubbed.is_none()
+    }
+
+    /// Add the last painted frame.
+    pub(crate) fn record(&mut self, scene: SceneNode, image: ImageBuf) {
+        if self.frames.len() == self.capacity {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(RecordedFrame {
+            number: self.recorded,
+            scene,
+            image,
+        });
+        self.recorded += 1;
+    }
+
+    /// Show the frame painted `frames_back` frames before the last one, or the live
+    /// widgets if `None`.
+    ///
+    /// Frames older than the oldest recorded one show the oldest one.
+    pub(crate) fn scrub(&mut self, frames_back: Option<usize>) {
+        self.scrubbed = match frames_back {
+            Some(_) if self.frames.is_empty() => None,
+            Some(frames_back) => Some(frames_back.min(self.frames.len() - 1)),
+            None => None,
+        };
+    }
+
+    /// The frame shown instead of the live widgets, if any.
+    pub(crate) fn scrubbed_frame(&self) -> Option<&RecordedFrame> {
+        let frames_back = self.scrubbed?;
+        self.frames.iter().rev().nth(frames_back)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command;
+    use crate::debug_switches::DebugSwitch;
+    use crate::testing::TestHarness;
+    use crate::widget::Label;
+
+    #[test]
+    fn scrub_back_to_earlier_frames() {
+        let mut harness =
+            TestHarness::create_with_size(Label::new("first"), Size::new(100.0, 40.0));
+        harness.submit_command(command::TOGGLE_DEBUG_SWITCH.with(DebugSwitch::FrameHistory));
+        harness.render();
+        harness.edit_root_widget(|mut label, _| {
+            label.downcast::<Label>().unwrap().set_text("second");
+        });
+        harness.render();
+
+        let shown_text = |harness: &TestHarness| {
+            let frame = harness.window().frame_history.scrubbed_frame()?;
+            frame.scene.text.clone()
+        };
+
+        harness.submit_command(command::SCRUB_FRAME.with(Some(1)));
+        let first = harness.render();
+        assert_eq!(shown_text(&harness).as_deref(), Some("first"));
+
+        // Frames aren't recorded while scrubbing.
+        harness.submit_command(command::SCRUB_FRAME.with(Some(0)));
+        let second = harness.render();
+        assert_eq!(shown_text(&harness).as_deref(), Some("second"));
+        assert_ne!(first, second);
+
+        harness.submit_command(command::SCRUB_FRAME.with(None));
+        assert_eq!(shown_text(&harness), None);
+    }
+}
@@ -0,0 +1,61 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Saving and restoring UI state across restarts.
+//!
+//! Some state is owned by the UI rather than by the app's data: the position and size
+//! of a window, which widget has focus, how far a list is scrolled. Masonry can
+//! collect this state into a [`UiState`], which the app is then free to store however
+//! it wants, and can restore it later.
+//!
+//! Widgets opt in by implementing [`Widget::save_state`] and [`Widget::restore_state`].
+//! States are keyed by [`WidgetId`], so widgets whose state should be restored must be
+//! created with the same id every time, eg with [`WidgetId::reserved`].
+
+use std::collections::HashMap;
+
+use druid_shell::WindowState;
+
+use crate::kurbo::{Point, Size};
+use crate::widget::WidgetRef;
+use crate::{Value, Widget, WidgetId};
+
+/// The position, size and state of a window.
+///
+/// See [`WindowDescription::with_geometry`](crate::WindowDescription::with_geometry).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WindowGeometry {
+    /// The position of the window, in virtual screen coordinates.
+    pub position: Point,
+    /// The size of the window's drawing area, in display points.
+    pub size: Size,
+    /// Whether the window is maximized, minimized, or neither.
+    pub state: WindowState,
+}
+
+/// The UI state of a window, collected by [`WindowRoot::save_ui_state`].
+///
+/// [`WindowRoot::save_ui_state`]: crate::WindowRoot::save_ui_state
+#[derive(Clone, Debug, Default)]
+pub struct UiState {
+    /// The geometry of the window, if it should be restored.
+    pub geometry: Option<WindowGeometry>,
+    /// The widget that had focus.
+    pub focus: Option<WidgetId>,
+    /// The states returned by [`Widget::save_state`].
+    pub widgets: HashMap<WidgetId, Value>,
+}
+
+/// Collect the saved states of `widget` and its descendants.
+pub(crate) fn collect_widget_states(
+    widget: WidgetRef<'_, dyn Widget>,
+    states: &mut HashMap<WidgetId, Value>,
+) {
+    if let Some(state) = widget.deref().save_state() {
+        states.insert(widget.id(), state);
+    }
+    for child in widget.children() {
+        collect_widget_states(child, states);
+    }
+}
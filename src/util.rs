@@ -7,31 +7,94 @@
 #![cfg(not(tarpaulin_include))]
 
 use std::any::Any;
+use std::cell::{Cell, RefCell};
 use std::hash::Hash;
 
-/// Panic in debug and tracing::error in release mode.
+/// Report a violation of a framework invariant, according to the [`InvariantPolicy`].
 ///
-/// This macro is in some way a combination of `panic` and `debug_assert`,
-/// but it will log the provided message instead of ignoring it in release builds.
+/// With the default policy, this macro is in some way a combination of `panic` and
+/// `debug_assert`: it panics in debug builds, but logs the provided message instead
+/// of ignoring it in release builds.
 ///
 /// It's useful when a backtrace would aid debugging but a crash can be avoided in release.
 macro_rules! debug_panic {
     () => { ... };
     ($msg:expr) => {
-        if cfg!(debug_assertions) {
-            panic!($msg);
-        } else {
-            tracing::error!($msg);
-        }
+        $crate::util::report_invariant_violation(format!($msg))
     };
     ($msg:expr,) => { debug_panic!($msg) };
     ($fmt:expr, $($arg:tt)+) => {
+        $crate::util::report_invariant_violation(format!($fmt, $($arg)*))
+    };
+}
+
+/// What Masonry does when it detects a violation of one of its invariants, eg a
+/// widget not forwarding `WidgetAdded` to its children.
+///
+/// These violations are bugs in widget code, but the app can usually keep running.
+/// The policy is set with [`set_invariant_policy`], or with
+/// [`AppLauncher::with_invariant_policy`](crate::AppLauncher::with_invariant_policy).
+///
+/// Since widget passes always run on the thread which launched the app, the policy is
+/// stored per thread.
+///
+/// The default policy is [`Panic`](Self::Panic) in debug builds, and
+/// [`Log`](Self::Log) in release builds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InvariantPolicy {
+    /// Panic with a description of the violation.
+    Panic,
+    /// Log the violation with `tracing::error`.
+    Log,
+    /// Log the violation, and send it to the app as an
+    /// [`Action::InvariantViolation`](crate::Action::InvariantViolation), eg to collect
+    /// telemetry.
+    ///
+    /// The action is emitted by the root widget of the window where the violation was
+    /// detected, at the end of the current pass.
+    EmitAction,
+}
+
+impl Default for InvariantPolicy {
+    fn default() -> Self {
         if cfg!(debug_assertions) {
-            panic!($fmt, $($arg)*);
+            InvariantPolicy::Panic
         } else {
-            tracing::error!($fmt, $($arg)*);
+            InvariantPolicy::Log
         }
-    };
+    }
+}
+
+thread_local! {
+    static INVARIANT_POLICY: Cell<Option<InvariantPolicy>> = Cell::new(None);
+    static EMITTED_VIOLATIONS: RefCell<Vec<String>> = RefCell::new(Vec::new());
+}
+
+/// Set the [`InvariantPolicy`] of the current thread.
+pub fn set_invariant_policy(policy: InvariantPolicy) {
+    INVARIANT_POLICY.with(|current| current.set(Some(policy)));
+}
+
+/// Get the [`InvariantPolicy`] of the current thread.
+pub fn invariant_policy() -> InvariantPolicy {
+    INVARIANT_POLICY.with(|current| current.get().unwrap_or_default())
+}
+
+#[track_caller]
+pub(crate) fn report_invariant_violation(message: String) {
+    match invariant_policy() {
+        InvariantPolicy::Panic => panic!("{}", message),
+        InvariantPolicy::Log => tracing::error!("{}", message),
+        InvariantPolicy::EmitAction => {
+            tracing::error!("{}", message);
+            EMITTED_VIOLATIONS.with(|violations| violations.borrow_mut().push(message));
+        }
+    }
+}
+
+/// Take the violations reported with [`InvariantPolicy::EmitAction`] since the last call.
+pub(crate) fn take_emitted_violations() -> Vec<String> {
+    EMITTED_VIOLATIONS.with(|violations| std::mem::take(&mut *violations.borrow_mut()))
 }
 
 // ---
@@ -0,0 +1,388 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! A text box which suggests completions as the user types.
+
+use std::sync::Arc;
+
+use smallvec::{smallvec, SmallVec};
+use tracing::{trace, trace_span, Span};
+
+use crate::action::Action;
+use crate::kurbo::Insets;
+use crate::promise::PromiseToken;
+use crate::selection::{self, SelectionMode};
+use crate::shell::KbKey;
+use crate::text::{Selection, TextLayout};
+use crate::widget::{TextBox, WidgetMut, WidgetPod, WidgetRef};
+use crate::{
+    theme, ArcStr, BoxConstraints, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx,
+    PaintCtx, Point, Rect, RenderContext, Size, StatusChange, Widget,
+};
+
+const ROW_PADDING: f64 = 4.0;
+const DEFAULT_MAX_VISIBLE: usize = 8;
+/// The suggestions are painted above the widgets that follow the text box.
+const POPUP_Z_INDEX: u32 = 1_000;
+
+type FetchFn = dyn Fn(&str) -> Vec<String> + Send + Sync;
+
+/// A [`TextBox`] which lists suggestions below it as the user types.
+///
+/// Each time the text is edited, suggestions for it are fetched on a background
+/// thread with the function passed to [`Autocomplete::new`]; results for text
+/// which has changed since are dropped. The suggestions are painted on top of the
+/// widgets below the text box, which keeps the focus:
+///
+/// - Up and Down highlight a suggestion, like in a list with a single
+///   [selection](crate::selection).
+/// - Tab accepts the highlighted suggestion, or the first one.
+/// - Enter accepts the highlighted suggestion; if there is none, the text box
+///   handles it as usual.
+/// - Escape hides the suggestions until the text is edited again.
+///
+/// Accepting a suggestion replaces the text with it, and emits
+/// [`Action::SuggestionAccepted`].
+///
+/// The suggestions are only navigated with the keyboard: they're painted outside of
+/// the widget's layout rect, which is where pointer events are hit-tested, so they
+/// can't be clicked.
+pub struct Autocomplete {
+    textbox: WidgetPod<TextBox>,
+    fetch: Arc<FetchFn>,
+    /// The text the suggestions were last fetched for.
+    query: String,
+    pending: Option<PromiseToken<Vec<String>>>,
+    suggestions: Vec<String>,
+    rows: Vec<TextLayout<ArcStr>>,
+    highlight: selection::Selection,
+    max_visible: usize,
+    popup_rect: Rect,
+}
+
+crate::declare_widget!(AutocompleteMut, Autocomplete);
+
+// --- METHODS ---
+
+impl Autocomplete {
+    /// Create a new autocomplete attachment for `textbox`.
+    ///
+    /// `fetch` returns the suggestions for the text, best first. It's called on a
+    /// background thread, so it can be slow, eg if it queries a server.
+    pub fn new(
+        textbox: TextBox,
+        fetch: impl Fn(&str) -> Vec<String> + Send + Sync + 'static,
+    ) -> Self {
+        let query = textbox.text();
+        Autocomplete {
+            textbox: WidgetPod::new(textbox),
+            fetch: Arc::new(fetch),
+            query,
+            pending: None,
+            suggestions: Vec::new(),
+            rows: Vec::new(),
+            highlight: selection::Selection::new(SelectionMode::Single),
+            max_visible: DEFAULT_MAX_VISIBLE,
+            popup_rect: Rect::ZERO,
+        }
+    }
+
+    /// Builder-style method to set how many suggestions are listed at most.
+    ///
+    /// The default is 8.
+    pub fn with_max_visible(mut self, max_visible: usize) -> Self {
+        self.max_visible = max_visible;
+        self
+    }
+
+    /// The text box the suggestions are for.
+    pub fn textbox(&self) -> WidgetRef<'_, TextBox> {
+        self.textbox.as_ref()
+    }
+
+    /// The suggestions currently listed, best first.
+    pub fn suggestions(&self) -> &[String] {
+        &self.suggestions[..self.rows.len()]
+    }
+
+    /// The index of the highlighted suggestion, if any.
+    pub fn highlighted(&self) -> Option<usize> {
+        self.highlight.selected().next()
+    }
+
+    fn is_open(&self) -> bool {
+        !self.rows.is_empty()
+    }
+
+    fn fetch_suggestions(&mut self, ctx: &mut EventCtx, text: String) {
+        if text.is_empty() {
+            self.pending = None;
+            self.close(ctx);
+            return;
+        }
+        trace!("fetching suggestions for {:?}", text);
+        let fetch = self.fetch.clone();
+        self.pending = Some(ctx.compute_in_background(move |_| fetch(&text)));
+    }
+
+    fn set_suggestions(&mut self, ctx: &mut EventCtx, suggestions: Vec<String>) {
+        self.rows = suggestions
+            .iter()
+            .take(self.max_visible)
+            .map(|suggestion| TextLayout::from_text(suggestion.as_str()))
+            .collect();
+        self.suggestions = suggestions;
+        self.highlight = selection::Selection::new(SelectionMode::Single);
+        ctx.request_layout();
+        ctx.request_paint();
+    }
+
+    fn close(&mut self, ctx: &mut EventCtx) {
+        self.set_suggestions(ctx, Vec::new());
+    }
+
+    fn accept(&mut self, ctx: &mut EventCtx, index: usize) {
+        let suggestion = self.suggestions[index].clone();
+        trace!("accepted suggestion {:?}", suggestion);
+        // The text box's text changes, but the suggestions shouldn't be fetched again.
+        self.query = suggestion.clone();
+        self.pending = None;
+        {
+            let mut textbox = ctx.get_mut(&mut self.textbox);
+            textbox.set_text(suggestion.clone());
+            textbox.set_selection(Selection::caret(suggestion.len()));
+        }
+        self.close(ctx);
+        ctx.submit_action(Action::SuggestionAccepted(suggestion));
+    }
+}
+
+impl<'a, 'b> AutocompleteMut<'a, 'b> {
+    /// Get a mutable reference to the text box.
+    pub fn textbox_mut(&mut self) -> WidgetMut<'_, 'b, TextBox> {
+        self.ctx.get_mut(&mut self.widget.textbox)
+    }
+
+    /// Set how many suggestions are listed at most.
+    pub fn set_max_visible(&mut self, max_visible: usize) {
+        self.widget.max_visible = max_visible;
+        let suggestions = &self.widget.suggestions;
+        self.widget.rows = suggestions
+            .iter()
+            .take(max_visible)
+            .map(|suggestion| TextLayout::from_text(suggestion.as_str()))
+            .collect();
+        self.widget.highlight.truncate(self.widget.rows.len());
+        self.ctx.request_layout();
+    }
+}
+
+// --- TRAIT IMPLS ---
+
+impl Widget for Autocomplete {
+    fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, env: &Env) {
+        match event {
+            Event::KeyDown(key) if self.is_open() && ctx.has_focus() => {
+                let handled = match &key.key {
+                    // The other selection keys, eg Home and End, move the caret.
+                    KbKey::ArrowDown | KbKey::ArrowUp => {
+                        self.highlight.key_down(key, self.rows.len());
+                        ctx.request_paint();
+                        true
+                    }
+                    KbKey::Tab if !key.mods.shift() => {
+                        self.accept(ctx, self.highlighted().unwrap_or(0));
+                        true
+                    }
+                    KbKey::Enter => match self.highlighted() {
+                        Some(index) => {
+                            self.accept(ctx, index);
+                            true
+                        }
+                        None => false,
+                    },
+                    KbKey::Escape => {
+                        self.close(ctx);
+                        true
+                    }
+                    _ => false,
+                };
+                if handled {
+                    ctx.set_handled();
+                    ctx.skip_child(&mut self.textbox);
+                    return;
+                }
+            }
+            Event::PromiseResult(result) => {
+                if let Some(suggestions) = self.pending.and_then(|token| result.try_get(token)) {
+                    self.pending = None;
+                    self.set_suggestions(ctx, suggestions);
+                    ctx.set_handled();
+                    ctx.skip_child(&mut self.textbox);
+                    return;
+                }
+            }
+            _ => {}
+        }
+
+        self.textbox.on_event(ctx, event, env);
+
+        let text = self.textbox.widget().text();
+        if text != self.query {
+            self.query = text.clone();
+            self.fetch_suggestions(ctx, text);
+        }
+    }
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange, _env: &Env) {}
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, env: &Env) {
+        self.textbox.lifecycle(ctx, event, env);
+
+        // The suggestions are hidden when the text box loses focus.
+        if self.is_open() && !self.textbox.has_focus() {
+            self.suggestions.clear();
+            self.rows.clear();
+            self.highlight = selection::Selection::new(SelectionMode::Single);
+            self.pending = None;
+            ctx.request_layout();
+            ctx.request_paint();
+        }
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, env: &Env) -> Size {
+        let size = self.textbox.layout(ctx, bc, env);
+        ctx.place_child(&mut self.textbox, Point::ORIGIN, env);
+
+        let mut popup_size = Size::new(size.width, 0.0);
+        for row in &mut self.rows {
+            row.rebuild_if_needed(ctx.text(), env);
+            let row_size = row.size();
+            popup_size.width = popup_size.width.max(row_size.width + 2.0 * ROW_PADDING);
+            popup_size.height += row_size.height + 2.0 * ROW_PADDING;
+        }
+        self.popup_rect = Rect::from_origin_size((0.0, size.height), popup_size);
+        ctx.set_paint_insets(Insets::new(
+            0.0,
+            0.0,
+            popup_size.width - size.width,
+            popup_size.height,
+        ));
+
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, env: &Env) {
+        self.textbox.paint(ctx, env);
+
+        if !self.is_open() {
+            return;
+        }
+        let rows = self.rows.clone();
+        let popup = self.popup_rect;
+        let highlight = self.highlight.clone();
+        let background_color = env.get(theme::BACKGROUND_LIGHT);
+        let border_color = env.get(theme::BORDER_DARK);
+        let highlight_color = env.get(theme::SELECTED_TEXT_BACKGROUND_COLOR);
+
+        ctx.paint_with_z_index(POPUP_Z_INDEX, move |ctx| {
+            ctx.fill(popup, &background_color);
+            let mut y = popup.y0;
+            for (index, row) in rows.iter().enumerate() {
+                let height = row.size().height + 2.0 * ROW_PADDING;
+                if highlight.is_selected(index) {
+                    ctx.fill(
+                        Rect::new(popup.x0, y, popup.x1, y + height),
+                        &highlight_color,
+                    );
+                }
+                row.draw(ctx, (popup.x0 + ROW_PADDING, y + ROW_PADDING));
+                y += height;
+            }
+            ctx.stroke(popup, &border_color, 1.0);
+        });
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        smallvec![self.textbox.as_dyn()]
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("Autocomplete")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::shell::{KeyEvent, RawMods};
+    use crate::testing::{widget_ids, TestHarness, TestWidgetExt as _};
+
+    const FRUITS: &[&str] = &["apple", "apricot", "banana", "cherry"];
+
+    fn fruits(text: &str) -> Vec<String> {
+        FRUITS
+            .iter()
+            .filter(|fruit| fruit.starts_with(text))
+            .map(|fruit| fruit.to_string())
+            .collect()
+    }
+
+    fn key_down(harness: &mut TestHarness, key: KbKey) {
+        harness.process_event(Event::KeyDown(KeyEvent::for_test(RawMods::None, key)));
+    }
+
+    #[test]
+    fn accept_highlighted_suggestion() {
+        let [autocomplete_id, textbox_id] = widget_ids();
+        let widget = Autocomplete::new(TextBox::new("").with_id(textbox_id), fruits)
+            .with_id(autocomplete_id);
+        let mut harness = TestHarness::create(widget);
+
+        harness.mouse_click_on(textbox_id);
+        // A single edit, so that only one fetch is pending.
+        harness.keyboard_type_chars("a");
+        harness.wait_for_external_events(Duration::from_secs(5));
+
+        let suggestions = |harness: &TestHarness| {
+            let widget = harness.get_widget(autocomplete_id);
+            let autocomplete = widget.downcast::<Autocomplete>().unwrap();
+            (
+                autocomplete.suggestions().to_vec(),
+                autocomplete.highlighted(),
+            )
+        };
+        assert_eq!(
+            suggestions(&harness),
+            (vec!["apple".to_string(), "apricot".to_string()], None)
+        );
+
+        key_down(&mut harness, KbKey::ArrowDown);
+        key_down(&mut harness, KbKey::ArrowDown);
+        assert_eq!(suggestions(&harness).1, Some(1));
+
+        // Drop the actions emitted while typing.
+        while harness.pop_action().is_some() {}
+        key_down(&mut harness, KbKey::Enter);
+        assert_eq!(
+            harness.pop_action(),
+            Some((
+                Action::SuggestionAccepted("apricot".to_string()),
+                autocomplete_id
+            ))
+        );
+        assert_eq!(suggestions(&harness), (vec![], None));
+        assert_eq!(
+            harness
+                .get_widget(textbox_id)
+                .downcast::<TextBox>()
+                .unwrap()
+                .text(),
+            "apricot"
+        );
+        assert_eq!(harness.focused_widget().unwrap().id(), textbox_id);
+    }
+}
@@ -0,0 +1,119 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! A widget marking where the window's backdrop shows through.
+
+use smallvec::{smallvec, SmallVec};
+use tracing::{trace, trace_span, Span};
+
+use crate::widget::{WidgetMut, WidgetRef};
+use crate::{
+    theme, BoxConstraints, Color, Env, Event, EventCtx, KeyOrValue, LayoutCtx, LifeCycle,
+    LifeCycleCtx, PaintCtx, Point, RenderContext, Size, StatusChange, Widget, WidgetPod,
+};
+
+/// A container whose background is the window's [backdrop](crate::backdrop), eg a
+/// translucent sidebar.
+///
+/// The window's background isn't painted behind the container, so the blurred desktop
+/// shows through, under a tint which keeps the content readable. Without a backdrop,
+/// the container looks like the rest of the window, with the tint.
+pub struct BackdropRegion {
+    child: WidgetPod<Box<dyn Widget>>,
+    tint: KeyOrValue<Color>,
+}
+
+crate::declare_widget!(BackdropRegionMut, BackdropRegion);
+
+// --- METHODS ---
+
+impl BackdropRegion {
+    /// Create a region containing `child`.
+    ///
+    /// The tint is [`theme::BACKDROP_TINT_COLOR`].
+    pub fn new(child: impl Widget) -> Self {
+        BackdropRegion {
+            child: WidgetPod::new(child).boxed(),
+            tint: theme::BACKDROP_TINT_COLOR.into(),
+        }
+    }
+
+    /// Builder-style method to set the color painted over the backdrop.
+    pub fn with_tint(mut self, tint: impl Into<KeyOrValue<Color>>) -> Self {
+        self.tint = tint.into();
+        self
+    }
+}
+
+impl<'a, 'b> BackdropRegionMut<'a, 'b> {
+    /// Set the color painted over the backdrop.
+    pub fn set_tint(&mut self, tint: impl Into<KeyOrValue<Color>>) {
+        self.widget.tint = tint.into();
+        self.ctx.request_paint();
+    }
+
+    pub fn child_mut(&mut self) -> WidgetMut<'_, 'b, Box<dyn Widget>> {
+        self.ctx.get_mut(&mut self.widget.child)
+    }
+}
+
+// --- TRAIT IMPLS ---
+
+impl Widget for BackdropRegion {
+    fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, env: &Env) {
+        self.child.on_event(ctx, event, env);
+    }
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange, _env: &Env) {}
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, env: &Env) {
+        self.child.lifecycle(ctx, event, env);
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, env: &Env) -> Size {
+        let size = self.child.layout(ctx, bc, env);
+        ctx.place_child(&mut self.child, Point::ORIGIN, env);
+        ctx.set_paint_insets(self.child.compute_parent_paint_insets(size));
+        ctx.set_baseline_offset(self.child.baseline_offset());
+        trace!("Computed layout: size={}", size);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, env: &Env) {
+        let rect = ctx.size().to_rect();
+        ctx.fill(rect, &self.tint.resolve(env));
+        self.child.paint(ctx, env);
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        smallvec![self.child.as_dyn()]
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("BackdropRegion")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backdrop::backdrop_regions;
+    use crate::testing::{widget_ids, TestHarness};
+    use crate::widget::{Flex, SizedBox};
+
+    #[test]
+    fn window_finds_backdrop_regions() {
+        let [sidebar] = widget_ids();
+        let sidebar_content = SizedBox::empty().width(80.0).height(100.0);
+        let widget = Flex::row()
+            .with_child(SizedBox::empty().width(50.0).height(100.0))
+            .with_child_id(BackdropRegion::new(sidebar_content), sidebar);
+        let harness = TestHarness::create(widget);
+
+        let regions = backdrop_regions(harness.root_widget());
+        let sidebar_rect = harness.get_widget(sidebar).state().window_layout_rect();
+        assert_eq!(regions, vec![sidebar_rect]);
+        assert_eq!(sidebar_rect.size(), Size::new(80.0, 100.0));
+    }
+}
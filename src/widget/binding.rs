@@ -0,0 +1,286 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! A wrapper that keeps a widget in sync with a part of the app store.
+
+use std::rc::Rc;
+
+use smallvec::{smallvec, SmallVec};
+use tracing::{trace, trace_span, warn, Span};
+
+use crate::action::Action;
+use crate::command::STORE_CHANGED;
+use crate::widget::{Checkbox, Label, StoreInWidgetMut, TextBox, WidgetMut, WidgetPod, WidgetRef};
+use crate::{
+    ArcStr, BoxConstraints, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx,
+    PaintCtx, Point, Size, StatusChange, Widget,
+};
+
+type ApplyFn<W, V> = Box<dyn for<'a, 'b> Fn(&mut WidgetMut<'a, 'b, W>, V)>;
+type ActionFn<T> = Box<dyn Fn(&Action, &mut T)>;
+
+/// A wrapper that binds a property of its child to a part of the [`AppStore`].
+///
+/// When the selected part of the store changes, the binding calls a setter on
+/// the child's [`WidgetMut`]. In the other direction, when the child emits an
+/// [`Action`], the binding can write the new value back to the store with
+/// [`AppStore::update`]. The action still reaches the app afterwards.
+///
+/// The convenience constructors cover the built-in widgets, eg:
+///
+/// ```ignore
+/// let name = Binding::textbox(TextBox::new(""), |app: &AppState| app.name.clone(), |app, name| app.name = name);
+/// let greeting = Binding::label_text(Label::new(""), |app: &AppState| format!("Hello, {}!", app.name).into());
+/// ```
+///
+/// The binding has the same size as its child, and is otherwise invisible.
+///
+/// [`AppStore`]: crate::AppStore
+/// [`AppStore::update`]: crate::AppStore::update
+pub struct Binding<W: Widget + StoreInWidgetMut, T, V> {
+    child: WidgetPod<W>,
+    select: Rc<dyn Fn(&T) -> V>,
+    apply: ApplyFn<W, V>,
+    on_action: Option<ActionFn<T>>,
+    /// The last value applied to, or read back from, the child.
+    current: Option<V>,
+}
+
+crate::declare_widget!(
+    BindingMut,
+    Binding<W: (Widget + StoreInWidgetMut), T: ('static), V: (Data)>
+);
+
+impl<W: Widget + StoreInWidgetMut, T: 'static, V: Data> Binding<W, T, V> {
+    /// Create a one-way binding, which calls `apply` on the child whenever
+    /// the value returned by `select` changes.
+    pub fn new(
+        child: W,
+        select: impl Fn(&T) -> V + 'static,
+        apply: impl for<'a, 'b> Fn(&mut WidgetMut<'a, 'b, W>, V) + 'static,
+    ) -> Self {
+        Binding {
+            child: WidgetPod::new(child),
+            select: Rc::new(select),
+            apply: Box::new(apply),
+            on_action: None,
+            current: None,
+        }
+    }
+
+    /// Builder-style method to write the actions of the child back to the store.
+    ///
+    /// `on_action` is called with every action the child submits; actions it
+    /// doesn't care about should be ignored.
+    pub fn with_on_action(mut self, on_action: impl Fn(&Action, &mut T) + 'static) -> Self {
+        self.on_action = Some(Box::new(on_action));
+        self
+    }
+
+    fn apply_value(&mut self, ctx: &mut EventCtx, value: V) {
+        if self
+            .current
+            .as_ref()
+            .map_or(false, |current| current.same(&value))
+        {
+            return;
+        }
+        self.current = Some(value.clone());
+        (self.apply)(&mut ctx.get_mut(&mut self.child), value);
+    }
+}
+
+impl<T: 'static> Binding<Label, T, ArcStr> {
+    /// Bind the text of a [`Label`].
+    pub fn label_text(label: Label, select: impl Fn(&T) -> ArcStr + 'static) -> Self {
+        Binding::new(label, select, |label, text| label.set_text(text))
+    }
+}
+
+impl<T: 'static> Binding<Checkbox, T, bool> {
+    /// Bind the state of a [`Checkbox`] both ways.
+    ///
+    /// `set` is called with the new state when the user toggles the checkbox.
+    pub fn checkbox(
+        checkbox: Checkbox,
+        select: impl Fn(&T) -> bool + 'static,
+        set: impl Fn(&mut T, bool) + 'static,
+    ) -> Self {
+        Binding::new(checkbox, select, |checkbox, checked| {
+            checkbox.set_checked(checked)
+        })
+        .with_on_action(move |action, state| {
+            if let Action::CheckboxChecked(checked) = action {
+                set(state, *checked);
+            }
+        })
+    }
+}
+
+impl<T: 'static> Binding<TextBox, T, String> {
+    /// Bind the text of a [`TextBox`] both ways.
+    ///
+    /// `set` is called with the new text whenever the user edits it.
+    pub fn textbox(
+        textbox: TextBox,
+        select: impl Fn(&T) -> String + 'static,
+        set: impl Fn(&mut T, String) + 'static,
+    ) -> Self {
+        Binding::new(textbox, select, |textbox, text| textbox.set_text(text)).with_on_action(
+            move |action, state| {
+                if let Action::TextChanged(text) = action {
+                    set(state, text.clone());
+                }
+            },
+        )
+    }
+}
+
+impl<'a, 'b, W: Widget + StoreInWidgetMut, T: 'static, V: Data> BindingMut<'a, 'b, W, T, V> {
+    /// Get a mutable reference to the bound widget.
+    ///
+    /// Values set this way are overwritten the next time the selected part of
+    /// the store changes.
+    pub fn child_mut(&mut self) -> WidgetMut<'_, 'b, W> {
+        self.ctx.get_mut(&mut self.widget.child)
+    }
+}
+
+impl<W: Widget + StoreInWidgetMut, T: 'static, V: Data> Widget for Binding<W, T, V> {
+    fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, env: &Env) {
+        if let Event::Command(cmd) = event {
+            if cmd.is(STORE_CHANGED) {
+                if let Some(store) = ctx.app_store::<T>() {
+                    let value = (self.select)(&store.get());
+                    self.apply_value(ctx, value);
+                }
+                ctx.set_handled();
+                ctx.skip_child(&mut self.child);
+                return;
+            }
+        }
+
+        let first_action = ctx.global_state.action_queue.len();
+        self.child.on_event(ctx, event, env);
+
+        let Some(on_action) = &self.on_action else {
+            return;
+        };
+        let child_id = self.child.id();
+        let has_child_actions = ctx
+            .global_state
+            .action_queue
+            .iter()
+            .skip(first_action)
+            .any(|(_, widget_id, _)| *widget_id == child_id);
+        if !has_child_actions {
+            return;
+        }
+        let Some(store) = ctx.app_store::<T>() else {
+            warn!(
+                "Binding: the app has no store of type {}",
+                std::any::type_name::<T>()
+            );
+            return;
+        };
+
+        trace!(
+            "Binding {:?} writing child actions to the store",
+            ctx.widget_id()
+        );
+        let select = &self.select;
+        let value = store.update(|state| {
+            let child_actions = ctx
+                .global_state
+                .action_queue
+                .iter()
+                .skip(first_action)
+                .filter(|(_, widget_id, _)| *widget_id == child_id);
+            for (action, _, _) in child_actions {
+                on_action(action, state);
+            }
+            select(state)
+        });
+        // The child already shows this value; don't set it again when the
+        // store notifies us.
+        self.current = Some(value);
+    }
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange, _env: &Env) {}
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, env: &Env) {
+        self.child.lifecycle(ctx, event, env);
+
+        if let LifeCycle::WidgetAdded = event {
+            let select = self.select.clone();
+            ctx.subscribe_to_store(move |state: &T| select(state));
+            if let Some(store) = ctx.app_store::<T>() {
+                let value = (self.select)(&store.get());
+                self.current = Some(value.clone());
+                (self.apply)(&mut ctx.get_mut(&mut self.child), value);
+            }
+        }
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, env: &Env) -> Size {
+        let size = self.child.layout(ctx, bc, env);
+        ctx.place_child(&mut self.child, Point::ORIGIN, env);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, env: &Env) {
+        self.child.paint(ctx, env);
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        smallvec![self.child.as_dyn()]
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("Binding")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestHarness;
+    use crate::AppStore;
+
+    struct Settings {
+        dark_mode: bool,
+    }
+
+    fn is_checked(harness: &TestHarness) -> bool {
+        let binding = harness.root_widget();
+        let binding = binding
+            .downcast::<Binding<Checkbox, Settings, bool>>()
+            .unwrap();
+        binding.child.widget().is_checked()
+    }
+
+    #[test]
+    fn checkbox_follows_store_both_ways() {
+        let store = AppStore::new(Settings { dark_mode: true });
+        let widget = Binding::checkbox(
+            Checkbox::new(false, "Dark mode"),
+            |settings: &Settings| settings.dark_mode,
+            |settings, checked| settings.dark_mode = checked,
+        );
+        let mut harness = TestHarness::create_with_store(widget, store.clone());
+        let binding_id = harness.root_widget().id();
+        assert!(is_checked(&harness));
+
+        harness.edit_root_widget(|_, _| store.update(|settings| settings.dark_mode = false));
+        assert!(!is_checked(&harness));
+
+        harness.mouse_click_on(binding_id);
+        assert!(is_checked(&harness));
+        assert!(store.get().dark_mode);
+        assert_eq!(
+            harness.pop_action().map(|(action, _)| action),
+            Some(Action::CheckboxChecked(true))
+        );
+    }
+}
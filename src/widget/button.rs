@@ -7,11 +7,14 @@
 use smallvec::SmallVec;
 use tracing::{trace, trace_span, Span};
 
+use crate::access_keys::ACTIVATE;
 use crate::action::Action;
-use crate::widget::{Label, WidgetMut, WidgetPod, WidgetRef};
+use crate::sound::SoundId;
+use crate::widget::{InteractionState, Label, WidgetMut, WidgetPod, WidgetRef};
 use crate::{
-    theme, ArcStr, BoxConstraints, Env, Event, EventCtx, Insets, LayoutCtx, LifeCycle,
-    LifeCycleCtx, LinearGradient, PaintCtx, RenderContext, Size, StatusChange, UnitPoint, Widget,
+    theme, ArcStr, BoxConstraints, Env, Event, EventCtx, Insets, InternalEvent, LayoutCtx,
+    LifeCycle, LifeCycleCtx, LinearGradient, PaintCtx, RenderContext, Size, StatusChange,
+    UnitPoint, Widget,
 };
 
 // the minimum padding added to a button.
@@ -21,9 +24,10 @@ const LABEL_INSETS: Insets = Insets::uniform_xy(8., 2.);
 
 /// A button with a text label.
 ///
-/// Emits [`Action::ButtonPressed`] when pressed.
+/// Emits [`Action::ButtonPressed`] and plays [`SoundId::Click`] when pressed.
 pub struct Button {
     label: WidgetPod<Label>,
+    interaction: InteractionState,
 }
 
 crate::declare_widget!(ButtonMut, Button);
@@ -56,6 +60,7 @@ impl Button {
     pub fn from_label(label: Label) -> Button {
         Button {
             label: WidgetPod::new(label),
+            interaction: InteractionState::new(),
         }
     }
 }
@@ -72,7 +77,7 @@ impl<'a, 'b> ButtonMut<'a, 'b> {
 }
 
 impl Widget for Button {
-    fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, _env: &Env) {
+    fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, env: &Env) {
         match event {
             Event::MouseDown(_) => {
                 if !ctx.is_disabled() {
@@ -84,20 +89,35 @@ impl Widget for Button {
             Event::MouseUp(_) => {
                 if ctx.is_active() && !ctx.is_disabled() {
                     ctx.submit_action(Action::ButtonPressed);
+                    ctx.play_sound(SoundId::Click);
                     ctx.request_paint();
                     trace!("Button {:?} released", ctx.widget_id());
                 }
                 ctx.set_active(false);
             }
+            Event::Command(cmd) if cmd.is(ACTIVATE) => {
+                if !ctx.is_disabled() {
+                    ctx.submit_action(Action::ButtonPressed);
+                    ctx.play_sound(SoundId::Click);
+                    ctx.set_handled();
+                    trace!("Button {:?} activated", ctx.widget_id());
+                }
+            }
+            // Route the commands sent to the label, eg to show its access key.
+            Event::Internal(InternalEvent::TargetedCommand(_)) => {
+                self.label.on_event(ctx, event, env);
+            }
             _ => (),
         }
+        self.interaction.on_event(ctx, event);
     }
 
-    fn on_status_change(&mut self, ctx: &mut LifeCycleCtx, _event: &StatusChange, _env: &Env) {
-        ctx.request_paint();
+    fn on_status_change(&mut self, ctx: &mut LifeCycleCtx, event: &StatusChange, _env: &Env) {
+        self.interaction.on_status_change(ctx, event);
     }
 
     fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, env: &Env) {
+        self.interaction.lifecycle(ctx, event);
         self.label.lifecycle(ctx, event, env)
     }
 
@@ -127,8 +147,6 @@ impl Widget for Button {
     }
 
     fn paint(&mut self, ctx: &mut PaintCtx, env: &Env) {
-        let is_active = ctx.is_active() && !ctx.is_disabled();
-        let is_hot = ctx.is_hot();
         let size = ctx.size();
         let stroke_width = env.get(theme::BUTTON_BORDER_WIDTH);
 
@@ -146,25 +164,26 @@ impl Widget for Button {
                     env.get(theme::DISABLED_BUTTON_DARK),
                 ),
             )
-        } else if is_active {
-            LinearGradient::new(
-                UnitPoint::TOP,
-                UnitPoint::BOTTOM,
-                (env.get(theme::BUTTON_DARK), env.get(theme::BUTTON_LIGHT)),
-            )
         } else {
+            // The gradient is reversed while the button is pressed.
+            let light = env.get(theme::BUTTON_LIGHT);
+            let dark = env.get(theme::BUTTON_DARK);
             LinearGradient::new(
                 UnitPoint::TOP,
                 UnitPoint::BOTTOM,
-                (env.get(theme::BUTTON_LIGHT), env.get(theme::BUTTON_DARK)),
+                (
+                    self.interaction
+                        .color(light.clone(), light.clone(), dark.clone()),
+                    self.interaction.color(dark.clone(), dark, light),
+                ),
             )
         };
 
-        let border_color = if is_hot && !ctx.is_disabled() {
-            env.get(theme::BORDER_LIGHT)
-        } else {
-            env.get(theme::BORDER_DARK)
-        };
+        let border_color = self.interaction.color(
+            env.get(theme::BORDER_DARK),
+            env.get(theme::BORDER_LIGHT),
+            env.get(theme::BORDER_LIGHT),
+        );
 
         ctx.stroke(rounded_rect, &border_color, stroke_width);
         ctx.fill(rounded_rect, &bg_gradient);
@@ -211,6 +230,7 @@ mod tests {
             harness.pop_action(),
             Some((Action::ButtonPressed, button_id))
         );
+        assert_eq!(harness.pop_sound(), Some(SoundId::Click));
     }
 
     #[test]
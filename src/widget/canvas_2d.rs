@@ -0,0 +1,606 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! A container which places its children anywhere on an unbounded plane.
+
+use std::collections::HashMap;
+
+use smallvec::SmallVec;
+use tracing::{trace, trace_span, Span};
+
+use crate::widget::{WidgetMut, WidgetPod, WidgetRef};
+use crate::{
+    BoxConstraints, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx, Point,
+    Rect, Size, StatusChange, Widget, WidgetId,
+};
+
+/// The size of the cells of the spatial index, in canvas coordinates.
+const CELL_SIZE: f64 = 256.0;
+
+/// Children overlapping more cells than this are kept out of the grid, and
+/// checked by every query instead.
+const MAX_CELLS_PER_CHILD: i64 = 256;
+
+/// A container for node-editor style apps, where each child is placed at an
+/// arbitrary position on an unbounded plane.
+///
+/// Children are laid out with unbounded constraints. Positions are in *canvas
+/// coordinates*; the canvas is as large as the box containing all its children
+/// and the canvas origin, and its top-left corner is at [`canvas_origin`](Self::canvas_origin),
+/// so placing a child at a negative position grows the canvas up or to the left.
+///
+/// Put the canvas in a [`Portal`](crate::widget::Portal) to pan and zoom around it.
+/// The canvas keeps a spatial index of its children, which it uses to hit-test mouse
+/// events, and to skip children outside the portal's viewport during paint and
+/// layout. Children outside the viewport are only laid out again once they request it.
+pub struct Canvas2D {
+    children: Vec<CanvasChild>,
+    index: SpatialIndex,
+    bounds: Rect,
+    // Children which were hot or active after the last mouse event; they receive
+    // mouse events even when they aren't under the mouse, so they can lose hot status.
+    pointer_children: Vec<usize>,
+    exclusive_hot: bool,
+}
+crate::declare_widget!(Canvas2DMut, Canvas2D);
+
+struct CanvasChild {
+    pod: WidgetPod<Box<dyn Widget>>,
+    pos: Point,
+}
+
+/// A uniform grid over the canvas, mapping each cell to the children overlapping it.
+#[derive(Default)]
+struct SpatialIndex {
+    cells: HashMap<(i64, i64), Vec<usize>>,
+    large: Vec<usize>,
+    rects: Vec<Rect>,
+}
+
+// --- SpatialIndex ---
+
+impl SpatialIndex {
+    /// The range of cells overlapping `rect`, as `(x0, y0, x1, y1)`, inclusive.
+    fn cell_range(rect: Rect) -> (i64, i64, i64, i64) {
+        let cell = |v: f64| (v / CELL_SIZE).floor() as i64;
+        (cell(rect.x0), cell(rect.y0), cell(rect.x1), cell(rect.y1))
+    }
+
+    fn cell_count((x0, y0, x1, y1): (i64, i64, i64, i64)) -> i64 {
+        (x1 - x0 + 1).saturating_mul(y1 - y0 + 1)
+    }
+
+    /// Set the rect of child `idx`, which must be at most the number of children.
+    fn set(&mut self, idx: usize, rect: Rect) {
+        if idx < self.rects.len() {
+            if self.rects[idx] == rect {
+                return;
+            }
+            self.unlink(idx);
+            self.rects[idx] = rect;
+        } else {
+            self.rects.push(rect);
+        }
+        self.link(idx);
+    }
+
+    /// Remove child `idx`, shifting the indices of the children after it.
+    fn remove(&mut self, idx: usize) {
+        self.rects.remove(idx);
+        self.cells.clear();
+        self.large.clear();
+        for idx in 0..self.rects.len() {
+            self.link(idx);
+        }
+    }
+
+    fn link(&mut self, idx: usize) {
+        let range = Self::cell_range(self.rects[idx]);
+        if !self.rects[idx].is_finite() || Self::cell_count(range) > MAX_CELLS_PER_CHILD {
+            self.large.push(idx);
+            return;
+        }
+        let (x0, y0, x1, y1) = range;
+        for x in x0..=x1 {
+            for y in y0..=y1 {
+                self.cells.entry((x, y)).or_default().push(idx);
+            }
+        }
+    }
+
+    fn unlink(&mut self, idx: usize) {
+        if let Some(pos) = self.large.iter().position(|large| *large == idx) {
+            self.large.swap_remove(pos);
+            return;
+        }
+        let (x0, y0, x1, y1) = Self::cell_range(self.rects[idx]);
+        for x in x0..=x1 {
+            for y in y0..=y1 {
+                if let Some(cell) = self.cells.get_mut(&(x, y)) {
+                    cell.retain(|child| *child != idx);
+                    if cell.is_empty() {
+                        self.cells.remove(&(x, y));
+                    }
+                }
+            }
+        }
+    }
+
+    /// The children overlapping `rect`, sorted by index.
+    fn query(&self, rect: Rect) -> Vec<usize> {
+        let overlaps = |child: &Rect| {
+            child.x0 <= rect.x1 && rect.x0 <= child.x1 && child.y0 <= rect.y1 && rect.y0 <= child.y1
+        };
+
+        let mut found = self.large.clone();
+        let range = Self::cell_range(rect);
+        if !rect.is_finite() || Self::cell_count(range) > self.cells.len() as i64 {
+            // Visiting the occupied cells is cheaper than visiting the cells in the rect.
+            found.extend(self.cells.values().flatten());
+        } else {
+            let (x0, y0, x1, y1) = range;
+            for x in x0..=x1 {
+                for y in y0..=y1 {
+                    found.extend(self.cells.get(&(x, y)).into_iter().flatten());
+                }
+            }
+        }
+        found.sort_unstable();
+        found.dedup();
+        found.retain(|idx| overlaps(&self.rects[*idx]));
+        found
+    }
+
+    /// The children containing `point`, sorted by index.
+    fn hit_test(&self, point: Point) -> Vec<usize> {
+        let mut found = self.query(Rect::from_points(point, point));
+        found.retain(|idx| self.rects[*idx].contains(point));
+        found
+    }
+
+    /// The box containing every child and the origin.
+    fn bounds(&self) -> Rect {
+        self.rects
+            .iter()
+            .filter(|rect| rect.is_finite())
+            .fold(Rect::ZERO, |bounds, rect| bounds.union(*rect))
+    }
+}
+
+// --- Canvas2D ---
+
+impl Canvas2D {
+    /// Create a new, empty canvas.
+    pub fn new() -> Self {
+        Canvas2D {
+            children: Vec::new(),
+            index: SpatialIndex::default(),
+            bounds: Rect::ZERO,
+            pointer_children: Vec::new(),
+            exclusive_hot: false,
+        }
+    }
+
+    /// Builder-style method to only make the topmost child under the mouse hot.
+    ///
+    /// By default, all the children under the mouse are hot.
+    /// See [`LifeCycleCtx::set_exclusive_hot`].
+    pub fn with_exclusive_hot(mut self, exclusive_hot: bool) -> Self {
+        self.exclusive_hot = exclusive_hot;
+        self
+    }
+
+    /// Builder-style method to add a child at `pos`, in canvas coordinates.
+    pub fn with_child(mut self, pos: impl Into<Point>, widget: impl Widget) -> Self {
+        self.push_child(pos.into(), WidgetPod::new(widget).boxed());
+        self
+    }
+
+    /// Builder-style method to add a child with a given id at `pos`, in canvas coordinates.
+    pub fn with_child_id(
+        mut self,
+        pos: impl Into<Point>,
+        widget: impl Widget,
+        id: WidgetId,
+    ) -> Self {
+        self.push_child(pos.into(), WidgetPod::new_with_id(widget, id).boxed());
+        self
+    }
+
+    /// Builder-style method to add a child pod at `pos`, in canvas coordinates, eg one
+    /// [with an elevation](WidgetPod::with_elevation).
+    pub fn with_child_pod(
+        mut self,
+        pos: impl Into<Point>,
+        pod: WidgetPod<Box<dyn Widget>>,
+    ) -> Self {
+        self.push_child(pos.into(), pod);
+        self
+    }
+
+    /// The position of the canvas's top-left corner, in canvas coordinates.
+    ///
+    /// Add it to a point in the canvas widget's coordinates (eg the position
+    /// of a mouse event) to get the point in canvas coordinates.
+    pub fn canvas_origin(&self) -> Point {
+        self.bounds.origin()
+    }
+
+    /// The position of a child, in canvas coordinates.
+    pub fn child_pos(&self, id: WidgetId) -> Option<Point> {
+        let idx = self.child_idx(id)?;
+        Some(self.children[idx].pos)
+    }
+
+    /// The topmost child at `pos`, in canvas coordinates.
+    ///
+    /// Children added later are on top of children added earlier.
+    pub fn child_at(&self, pos: Point) -> Option<WidgetId> {
+        let idx = *self.index.hit_test(pos).last()?;
+        Some(self.children[idx].pod.id())
+    }
+
+    fn child_idx(&self, id: WidgetId) -> Option<usize> {
+        self.children.iter().position(|child| child.pod.id() == id)
+    }
+
+    fn push_child(&mut self, pos: Point, pod: WidgetPod<Box<dyn Widget>>) {
+        // The child's size is unknown until it's laid out.
+        self.index
+            .set(self.children.len(), Rect::from_origin_size(pos, Size::ZERO));
+        self.children.push(CanvasChild { pod, pos });
+    }
+}
+
+impl Default for Canvas2D {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, 'b> Canvas2DMut<'a, 'b> {
+    /// Add a child at `pos`, in canvas coordinates.
+    pub fn add_child(&mut self, pos: impl Into<Point>, widget: impl Widget) {
+        self.widget
+            .push_child(pos.into(), WidgetPod::new(widget).boxed());
+        self.ctx.children_changed();
+    }
+
+    /// Add a child with a given id at `pos`, in canvas coordinates.
+    pub fn add_child_id(&mut self, pos: impl Into<Point>, widget: impl Widget, id: WidgetId) {
+        self.widget
+            .push_child(pos.into(), WidgetPod::new_with_id(widget, id).boxed());
+        self.ctx.children_changed();
+    }
+
+    /// Move a child to `pos`, in canvas coordinates.
+    pub fn set_child_pos(&mut self, id: WidgetId, pos: impl Into<Point>) {
+        let Some(idx) = self.widget.child_idx(id) else {
+            return;
+        };
+        let pos = pos.into();
+        let child = &mut self.widget.children[idx];
+        child.pos = pos;
+        let size = child.pod.layout_rect().size();
+        self.widget
+            .index
+            .set(idx, Rect::from_origin_size(pos, size));
+        self.ctx.request_layout();
+    }
+
+    /// Set the z-index of a child.
+    ///
+    /// Where children overlap, the one with the highest z-index is painted on top and
+    /// receives the pointer events. See [`EventCtx::set_child_z_index`].
+    pub fn set_child_z_index(&mut self, id: WidgetId, z_index: i32) {
+        let Some(idx) = self.widget.child_idx(id) else {
+            return;
+        };
+        self.ctx
+            .set_child_z_index(&mut self.widget.children[idx].pod, z_index);
+    }
+
+    /// Remove a child.
+    pub fn remove_child(&mut self, id: WidgetId) {
+        let Some(idx) = self.widget.child_idx(id) else {
+            return;
+        };
+        self.widget.children.remove(idx);
+        self.widget.index.remove(idx);
+        self.widget.pointer_children.clear();
+        self.ctx.children_changed();
+    }
+
+    pub fn child_mut(&mut self, id: WidgetId) -> Option<WidgetMut<'_, 'b, Box<dyn Widget>>> {
+        let idx = self.widget.child_idx(id)?;
+        Some(self.ctx.get_mut(&mut self.widget.children[idx].pod))
+    }
+}
+
+// --- Trait impls ---
+
+impl Widget for Canvas2D {
+    fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, env: &Env) {
+        let mouse_pos = match event {
+            Event::MouseDown(mouse)
+            | Event::MouseUp(mouse)
+            | Event::MouseMove(mouse)
+            | Event::Wheel(mouse) => Some(mouse.pos),
+            _ => None,
+        };
+        let Some(mouse_pos) = mouse_pos else {
+            for child in &mut self.children {
+                child.pod.on_event(ctx, event, env);
+            }
+            return;
+        };
+
+        let mut targets = self
+            .index
+            .hit_test(mouse_pos + self.bounds.origin().to_vec2());
+        targets.extend(&self.pointer_children);
+        targets.sort_unstable();
+        targets.dedup();
+
+        for (idx, child) in self.children.iter_mut().enumerate() {
+            if targets.binary_search(&idx).is_ok() {
+                child.pod.on_event(ctx, event, env);
+            } else {
+                ctx.skip_child(&mut child.pod);
+            }
+        }
+
+        targets.retain(|idx| {
+            let state = self.children[*idx].pod.state();
+            state.is_hot || state.has_active
+        });
+        self.pointer_children = targets;
+    }
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange, _env: &Env) {}
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, env: &Env) {
+        if let LifeCycle::WidgetAdded = event {
+            ctx.set_exclusive_hot(self.exclusive_hot);
+        }
+        for child in &mut self.children {
+            child.pod.lifecycle(ctx, event, env);
+        }
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, env: &Env) -> Size {
+        let origin = self.bounds.origin().to_vec2();
+        let visible = ctx
+            .viewport_rect()
+            .map(|viewport| self.index.query(viewport + origin));
+
+        let mut laid_out = 0;
+        for (idx, child) in self.children.iter_mut().enumerate() {
+            let is_visible = visible
+                .as_ref()
+                .map_or(true, |visible| visible.binary_search(&idx).is_ok());
+            if is_visible || child.pod.state().needs_layout {
+                let size = child.pod.layout(ctx, &BoxConstraints::UNBOUNDED, env);
+                self.index.set(idx, Rect::from_origin_size(child.pos, size));
+                laid_out += 1;
+            } else {
+                ctx.skip_child(&mut child.pod);
+            }
+        }
+
+        // Children outside the viewport keep their previous size, but are still
+        // placed, in case the canvas grew.
+        self.bounds = self.index.bounds();
+        let origin = self.bounds.origin().to_vec2();
+        for child in &mut self.children {
+            ctx.place_child(&mut child.pod, child.pos - origin, env);
+        }
+
+        let size = bc.constrain(self.bounds.size());
+        trace!(
+            "Computed layout: size={}, laid out {} of {} children",
+            size,
+            laid_out,
+            self.children.len()
+        );
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, env: &Env) {
+        let origin = self.bounds.origin().to_vec2();
+        let region = ctx
+            .region()
+            .rects()
+            .iter()
+            .copied()
+            .reduce(|a, b| a.union(b));
+        let visible = match region {
+            Some(region) => self.index.query(region + origin),
+            None => Vec::new(),
+        };
+
+        let mut visible_children = Vec::with_capacity(visible.len());
+        for (idx, child) in self.children.iter_mut().enumerate() {
+            if visible.binary_search(&idx).is_ok() {
+                visible_children.push(&mut child.pod);
+            } else {
+                ctx.skip_child(&mut child.pod);
+            }
+        }
+        ctx.paint_children_in_z_order(visible_children, env);
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        self.children
+            .iter()
+            .map(|child| child.pod.as_dyn())
+            .collect()
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("Canvas2D")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shell::MouseButton;
+    use crate::testing::{widget_ids, Record, Recording, TestHarness, TestWidgetExt};
+    use crate::widget::{Portal, SizedBox};
+
+    fn node() -> SizedBox {
+        SizedBox::empty().width(100.0).height(50.0)
+    }
+
+    #[test]
+    fn spatial_index_queries() {
+        let mut index = SpatialIndex::default();
+        index.set(0, Rect::new(0.0, 0.0, 100.0, 50.0));
+        index.set(1, Rect::new(1000.0, 1000.0, 1100.0, 1050.0));
+        index.set(2, Rect::new(-5000.0, -5000.0, 5000.0, 5000.0));
+
+        assert_eq!(index.query(Rect::new(900.0, 900.0, 1200.0, 1200.0)), [1, 2]);
+        assert_eq!(index.hit_test(Point::new(50.0, 25.0)), [0, 2]);
+
+        index.set(0, Rect::new(2000.0, 2000.0, 2100.0, 2050.0));
+        assert_eq!(index.hit_test(Point::new(50.0, 25.0)), [2]);
+
+        index.remove(1);
+        assert_eq!(index.hit_test(Point::new(2050.0, 2025.0)), [0, 1]);
+        assert_eq!(index.bounds(), Rect::new(-5000.0, -5000.0, 5000.0, 5000.0));
+    }
+
+    #[test]
+    fn children_at_negative_positions() {
+        let [left_id, right_id] = widget_ids();
+        let canvas = Canvas2D::new()
+            .with_child_id((-200.0, -100.0), node(), left_id)
+            .with_child_id((300.0, 100.0), node(), right_id);
+
+        let harness = TestHarness::create(canvas);
+
+        let canvas = harness.root_widget();
+        let canvas = canvas.downcast::<Canvas2D>().unwrap();
+        assert_eq!(canvas.canvas_origin(), Point::new(-200.0, -100.0));
+        assert_eq!(canvas.child_at(Point::new(350.0, 120.0)), Some(right_id));
+        assert_eq!(canvas.child_at(Point::new(0.0, 0.0)), None);
+
+        let left_rect = harness.get_widget(left_id).state().layout_rect();
+        assert_eq!(left_rect, Rect::new(0.0, 0.0, 100.0, 50.0));
+    }
+
+    #[test]
+    fn mouse_events_reach_children_under_mouse() {
+        let [near_id, far_id] = widget_ids();
+        let near_recording = Recording::default();
+        let far_recording = Recording::default();
+        let canvas = Canvas2D::new()
+            .with_child_id((0.0, 0.0), node().record(&near_recording), near_id)
+            .with_child_id((5000.0, 5000.0), node().record(&far_recording), far_id);
+
+        let mut harness = TestHarness::create(canvas);
+        near_recording.clear();
+        far_recording.clear();
+
+        harness.mouse_move((10.0, 10.0));
+        harness.mouse_button_press(MouseButton::Left);
+
+        assert!(near_recording
+            .drain()
+            .iter()
+            .any(|record| matches!(record, Record::E(Event::MouseDown(_)))));
+        assert!(!far_recording
+            .drain()
+            .iter()
+            .any(|record| matches!(record, Record::E(_))));
+    }
+
+    #[test]
+    fn raised_child_receives_clicks_in_overlap() {
+        let [below_id, above_id] = widget_ids();
+        let below_recording = Recording::default();
+        let above_recording = Recording::default();
+        let canvas = Canvas2D::new()
+            .with_child_id((0.0, 0.0), node().record(&below_recording), below_id)
+            .with_child_id((50.0, 20.0), node().record(&above_recording), above_id);
+
+        let mut harness = TestHarness::create(canvas);
+        harness.edit_root_widget(|mut canvas, _| {
+            let mut canvas = canvas.downcast::<Canvas2D>().unwrap();
+            canvas.set_child_z_index(below_id, 1);
+        });
+        below_recording.clear();
+        above_recording.clear();
+
+        // Both children contain this point; the raised one is on top.
+        harness.mouse_move((70.0, 30.0));
+        harness.mouse_button_press(MouseButton::Left);
+
+        assert!(harness.get_widget(below_id).state().is_hot);
+        assert!(!harness.get_widget(above_id).state().is_hot);
+        assert!(below_recording
+            .drain()
+            .iter()
+            .any(|record| matches!(record, Record::E(Event::MouseDown(_)))));
+        assert!(!above_recording
+            .drain()
+            .iter()
+            .any(|record| matches!(record, Record::E(Event::MouseDown(_)))));
+    }
+
+    #[test]
+    fn exclusive_hot_only_highlights_topmost_child() {
+        let [below_id, above_id] = widget_ids();
+        let canvas = Canvas2D::new()
+            .with_exclusive_hot(true)
+            .with_child_id((0.0, 0.0), node(), below_id)
+            .with_child_id((50.0, 20.0), node(), above_id);
+
+        let mut harness = TestHarness::create(canvas);
+
+        harness.mouse_move((70.0, 30.0));
+        assert!(!harness.get_widget(below_id).state().is_hot);
+        assert!(harness.get_widget(above_id).state().is_hot);
+
+        harness.mouse_move((20.0, 10.0));
+        assert!(harness.get_widget(below_id).state().is_hot);
+        assert!(!harness.get_widget(above_id).state().is_hot);
+    }
+
+    #[test]
+    fn offscreen_children_are_culled() {
+        let far_recording = Recording::default();
+        let canvas = Canvas2D::new()
+            .with_child((0.0, 0.0), node())
+            .with_child((5000.0, 5000.0), node().record(&far_recording));
+
+        let mut harness =
+            TestHarness::create_with_size(Portal::new(canvas), Size::new(400.0, 400.0));
+        far_recording.clear();
+
+        // Neither layout nor paint reach the child outside the viewport.
+        harness.edit_root_widget(|mut portal, _| {
+            let mut portal = portal.downcast::<Portal<Canvas2D>>().unwrap();
+            portal.set_viewport_pos(Point::new(100.0, 100.0));
+        });
+        harness.render();
+        assert!(!far_recording
+            .drain()
+            .iter()
+            .any(|record| matches!(record, Record::Layout(_) | Record::Paint)));
+
+        // Panning to it lays it out and paints it again.
+        harness.edit_root_widget(|mut portal, _| {
+            let mut portal = portal.downcast::<Portal<Canvas2D>>().unwrap();
+            portal.pan_viewport_to(Rect::new(5000.0, 5000.0, 5100.0, 5050.0));
+        });
+        harness.render();
+        let records = far_recording.drain();
+        assert!(records
+            .iter()
+            .any(|record| matches!(record, Record::Layout(_))));
+        assert!(records.iter().any(|record| matches!(record, Record::Paint)));
+    }
+}
@@ -0,0 +1,590 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Simple line, bar and pie charts.
+
+use std::f64::consts::PI;
+
+use smallvec::SmallVec;
+use tracing::{trace, trace_span, Span};
+
+use crate::action::Action;
+use crate::kurbo::{BezPath, Circle, Line, Rect};
+use crate::text::TextLayout;
+use crate::widget::WidgetRef;
+use crate::{
+    theme, ArcStr, BoxConstraints, Color, Env, Event, EventCtx, KeyOrValue, LayoutCtx, LifeCycle,
+    LifeCycleCtx, PaintCtx, Point, RenderContext, Size, StatusChange, Vec2, Widget,
+};
+
+const DEFAULT_SIZE: Size = Size::new(200.0, 150.0);
+const PLOT_PADDING: f64 = 8.0;
+/// Fraction of each bar's slot left empty between bars.
+const BAR_GAP_RATIO: f64 = 0.2;
+/// How close the mouse must be to a data point of a line chart to hover it.
+const LINE_HIT_RADIUS: f64 = 10.0;
+const LINE_POINT_RADIUS: f64 = 3.0;
+/// Inner radius of a donut chart, as a fraction of its outer radius.
+const DONUT_HOLE_RATIO: f64 = 0.55;
+/// Length of the transition when data changes, in nanoseconds.
+const TRANSITION_DURATION: f64 = 250_000_000.0;
+const TOOLTIP_PADDING: f64 = 4.0;
+/// Tooltips are painted above every other widget in the window.
+const TOOLTIP_Z_INDEX: u32 = 1_000;
+
+static SLICE_COLORS: &[Color] = &[
+    Color::rgb8(0x5c, 0xc4, 0xff),
+    Color::rgb8(0xf5, 0x82, 0x31),
+    Color::rgb8(0x3c, 0xb4, 0x4b),
+    Color::rgb8(0xe6, 0x19, 0x4b),
+    Color::rgb8(0x91, 0x1e, 0xb4),
+    Color::rgb8(0xff, 0xe1, 0x19),
+    Color::rgb8(0x46, 0xf0, 0xf0),
+    Color::rgb8(0xaa, 0x6e, 0x28),
+];
+
+/// The kind of chart drawn by a [`Chart`] widget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChartKind {
+    /// Data points joined by straight lines.
+    Line,
+    /// One vertical bar per data point.
+    Bar,
+    /// One slice per data point, proportional to its share of the total.
+    Pie,
+    /// A pie chart with a hole in the middle.
+    Donut,
+}
+
+/// A chart displaying a single series of labeled values.
+///
+/// Hovering a data point shows a tooltip with its label and value; clicking
+/// it emits [`Action::ChartItemSelected`] with the index of the point. Both the
+/// press and the release must land on the same point.
+///
+/// When the data is changed through [`ChartMut`], the chart transitions
+/// smoothly from the old values to the new ones.
+pub struct Chart {
+    kind: ChartKind,
+    labels: Vec<ArcStr>,
+    values: Vec<f64>,
+    /// The values currently on screen; they differ from `values` during a transition.
+    displayed_values: Vec<f64>,
+    transition_start: Vec<f64>,
+    /// Between 0.0 and 1.0; 1.0 means no transition is running.
+    transition_progress: f64,
+    color: KeyOrValue<Color>,
+    hovered: Option<usize>,
+    /// The item the mouse was pressed on.
+    pressed: Option<usize>,
+    tooltip: TextLayout<ArcStr>,
+}
+
+crate::declare_widget!(ChartMut, Chart);
+
+impl Chart {
+    /// Create a chart of the given kind from `(label, value)` pairs.
+    pub fn new<L: Into<ArcStr>>(kind: ChartKind, data: impl IntoIterator<Item = (L, f64)>) -> Self {
+        let (labels, values): (Vec<ArcStr>, Vec<f64>) = data
+            .into_iter()
+            .map(|(label, value)| (label.into(), value))
+            .unzip();
+        Chart {
+            kind,
+            labels,
+            displayed_values: values.clone(),
+            transition_start: Vec::new(),
+            transition_progress: 1.0,
+            values,
+            color: theme::PRIMARY_LIGHT.into(),
+            hovered: None,
+            pressed: None,
+            tooltip: TextLayout::new(),
+        }
+    }
+
+    /// Create a line chart.
+    pub fn line<L: Into<ArcStr>>(data: impl IntoIterator<Item = (L, f64)>) -> Self {
+        Self::new(ChartKind::Line, data)
+    }
+
+    /// Create a bar chart.
+    pub fn bar<L: Into<ArcStr>>(data: impl IntoIterator<Item = (L, f64)>) -> Self {
+        Self::new(ChartKind::Bar, data)
+    }
+
+    /// Create a pie chart.
+    pub fn pie<L: Into<ArcStr>>(data: impl IntoIterator<Item = (L, f64)>) -> Self {
+        Self::new(ChartKind::Pie, data)
+    }
+
+    /// Create a donut chart.
+    pub fn donut<L: Into<ArcStr>>(data: impl IntoIterator<Item = (L, f64)>) -> Self {
+        Self::new(ChartKind::Donut, data)
+    }
+
+    /// Builder-style method for setting the color of lines and bars.
+    ///
+    /// Pie and donut slices use a fixed palette instead.
+    pub fn with_color(mut self, color: impl Into<KeyOrValue<Color>>) -> Self {
+        self.color = color.into();
+        self
+    }
+
+    /// The kind of chart drawn.
+    pub fn kind(&self) -> ChartKind {
+        self.kind
+    }
+
+    /// The target values of the chart, ignoring any running transition.
+    pub fn values(&self) -> &[f64] {
+        &self.values
+    }
+
+    /// The labels of the data points.
+    pub fn labels(&self) -> &[ArcStr] {
+        &self.labels
+    }
+
+    /// The index of the data point under the mouse, if any.
+    pub fn hovered_item(&self) -> Option<usize> {
+        self.hovered
+    }
+
+    fn start_transition(&mut self) {
+        let mut start = std::mem::take(&mut self.displayed_values);
+        start.resize(self.values.len(), 0.0);
+        self.displayed_values = start.clone();
+        self.transition_start = start;
+        self.transition_progress = 0.0;
+    }
+
+    fn advance_transition(&mut self, interval_ns: u64) {
+        self.transition_progress =
+            (self.transition_progress + interval_ns as f64 / TRANSITION_DURATION).min(1.0);
+        // ease-out
+        let t = 1.0 - (1.0 - self.transition_progress).powi(2);
+        self.displayed_values = self
+            .transition_start
+            .iter()
+            .zip(&self.values)
+            .map(|(start, end)| start + (end - start) * t)
+            .collect();
+    }
+
+    fn plot_rect(size: Size) -> Rect {
+        size.to_rect().inset(-PLOT_PADDING)
+    }
+
+    fn value_range(&self) -> (f64, f64) {
+        let min = self.displayed_values.iter().copied().fold(0.0, f64::min);
+        let max = self.displayed_values.iter().copied().fold(0.0, f64::max);
+        if max - min < f64::EPSILON {
+            (min, min + 1.0)
+        } else {
+            (min, max)
+        }
+    }
+
+    fn value_to_y(&self, plot: Rect, value: f64) -> f64 {
+        let (min, max) = self.value_range();
+        plot.y1 - (value - min) / (max - min) * plot.height()
+    }
+
+    fn bar_rect(&self, plot: Rect, index: usize) -> Rect {
+        let slot = plot.width() / self.displayed_values.len() as f64;
+        let gap = slot * BAR_GAP_RATIO;
+        let x0 = plot.x0 + slot * index as f64 + gap / 2.0;
+        let y_zero = self.value_to_y(plot, 0.0);
+        let y_value = self.value_to_y(plot, self.displayed_values[index]);
+        Rect::new(
+            x0,
+            y_zero.min(y_value),
+            x0 + slot - gap,
+            y_zero.max(y_value),
+        )
+    }
+
+    fn line_point(&self, plot: Rect, index: usize) -> Point {
+        let count = self.displayed_values.len();
+        let x = if count == 1 {
+            plot.center().x
+        } else {
+            plot.x0 + plot.width() * index as f64 / (count - 1) as f64
+        };
+        Point::new(x, self.value_to_y(plot, self.displayed_values[index]))
+    }
+
+    /// Start angle and sweep of each pie slice.
+    fn slice_angles(&self) -> Vec<(f64, f64)> {
+        let total: f64 = self.displayed_values.iter().map(|v| v.max(0.0)).sum();
+        let mut angle = -PI / 2.0;
+        self.displayed_values
+            .iter()
+            .map(|value| {
+                let sweep = if total > 0.0 {
+                    value.max(0.0) / total * 2.0 * PI
+                } else {
+                    0.0
+                };
+                let slice = (angle, sweep);
+                angle += sweep;
+                slice
+            })
+            .collect()
+    }
+
+    fn pie_radii(&self, plot: Rect) -> (f64, f64) {
+        let outer = plot.width().min(plot.height()) / 2.0;
+        let inner = if self.kind == ChartKind::Donut {
+            outer * DONUT_HOLE_RATIO
+        } else {
+            0.0
+        };
+        (outer, inner)
+    }
+
+    /// Find the data point at the given position, in widget coordinates.
+    fn item_at(&self, size: Size, pos: Point) -> Option<usize> {
+        if self.displayed_values.is_empty() {
+            return None;
+        }
+        let plot = Self::plot_rect(size);
+        match self.kind {
+            ChartKind::Bar => (0..self.displayed_values.len()).find(|&i| {
+                let bar = self.bar_rect(plot, i);
+                Rect::new(bar.x0, plot.y0, bar.x1, plot.y1).contains(pos)
+            }),
+            ChartKind::Line => (0..self.displayed_values.len())
+                .map(|i| (i, (self.line_point(plot, i) - pos).hypot()))
+                .filter(|(_, distance)| *distance <= LINE_HIT_RADIUS)
+                .min_by(|(_, a), (_, b)| a.total_cmp(b))
+                .map(|(i, _)| i),
+            ChartKind::Pie | ChartKind::Donut => {
+                let (outer, inner) = self.pie_radii(plot);
+                let offset = pos - plot.center();
+                let distance = offset.hypot();
+                if distance > outer || distance < inner {
+                    return None;
+                }
+                // Angles are measured clockwise from the top, like the slices.
+                let angle = (offset.atan2() + PI / 2.0).rem_euclid(2.0 * PI);
+                self.slice_angles().iter().position(|(start, sweep)| {
+                    let start = start + PI / 2.0;
+                    start <= angle && angle < start + sweep
+                })
+            }
+        }
+    }
+
+    /// The point the tooltip for the given data point is attached to.
+    fn item_anchor(&self, size: Size, index: usize) -> Point {
+        let plot = Self::plot_rect(size);
+        match self.kind {
+            ChartKind::Bar => {
+                let bar = self.bar_rect(plot, index);
+                Point::new(bar.center().x, bar.y0)
+            }
+            ChartKind::Line => self.line_point(plot, index),
+            ChartKind::Pie | ChartKind::Donut => {
+                let (outer, inner) = self.pie_radii(plot);
+                let (start, sweep) = self.slice_angles()[index];
+                plot.center() + Vec2::from_angle(start + sweep / 2.0) * ((outer + inner) / 2.0)
+            }
+        }
+    }
+
+    fn set_hovered(&mut self, hovered: Option<usize>) -> bool {
+        if self.hovered == hovered {
+            return false;
+        }
+        self.hovered = hovered;
+        if let Some(index) = hovered {
+            let text = format!("{}: {}", self.labels[index], self.values[index]);
+            self.tooltip.set_text(text.into());
+        }
+        true
+    }
+}
+
+impl<'a, 'b> ChartMut<'a, 'b> {
+    /// Replace the data of the chart.
+    ///
+    /// The chart transitions from the values currently displayed to the new ones.
+    pub fn set_data<L: Into<ArcStr>>(&mut self, data: impl IntoIterator<Item = (L, f64)>) {
+        let (labels, values) = data
+            .into_iter()
+            .map(|(label, value)| (label.into(), value))
+            .unzip();
+        self.widget.labels = labels;
+        self.widget.values = values;
+        self.widget.hovered = None;
+        self.widget.start_transition();
+        self.ctx.request_anim_frame();
+        self.ctx.request_paint();
+    }
+
+    /// Change a single value of the chart.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn set_value(&mut self, index: usize, value: f64) {
+        self.widget.values[index] = value;
+        self.widget.start_transition();
+        self.ctx.request_anim_frame();
+        self.ctx.request_paint();
+    }
+
+    /// Change the kind of chart drawn.
+    pub fn set_kind(&mut self, kind: ChartKind) {
+        self.widget.kind = kind;
+        self.widget.hovered = None;
+        self.ctx.request_paint();
+    }
+
+    /// Set the color of lines and bars.
+    pub fn set_color(&mut self, color: impl Into<KeyOrValue<Color>>) {
+        self.widget.color = color.into();
+        self.ctx.request_paint();
+    }
+}
+
+impl Widget for Chart {
+    fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, _env: &Env) {
+        match event {
+            Event::MouseMove(mouse) => {
+                let hovered = if ctx.is_hot() {
+                    self.item_at(ctx.size(), mouse.pos)
+                } else {
+                    None
+                };
+                if self.set_hovered(hovered) {
+                    ctx.request_paint();
+                }
+            }
+            Event::MouseDown(mouse) => {
+                if !ctx.is_disabled() {
+                    self.pressed = self.item_at(ctx.size(), mouse.pos);
+                    if self.pressed.is_some() {
+                        ctx.set_active(true);
+                    }
+                }
+            }
+            Event::MouseUp(mouse) => {
+                let pressed = self.pressed.take();
+                if ctx.is_active() && !ctx.is_disabled() {
+                    let released = self.item_at(ctx.size(), mouse.pos);
+                    if let Some(index) = pressed.filter(|index| released == Some(*index)) {
+                        trace!("Chart {:?} item {} selected", ctx.widget_id(), index);
+                        ctx.submit_action(Action::ChartItemSelected(index));
+                    }
+                }
+                ctx.set_active(false);
+            }
+            Event::AnimFrame(interval) => {
+                if self.transition_progress < 1.0 {
+                    self.advance_transition(*interval);
+                    if self.transition_progress < 1.0 {
+                        ctx.request_anim_frame();
+                    }
+                    ctx.request_paint();
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn on_status_change(&mut self, ctx: &mut LifeCycleCtx, event: &StatusChange, _env: &Env) {
+        if let StatusChange::HotChanged(false) = event {
+            if self.set_hovered(None) {
+                ctx.request_paint();
+            }
+        }
+    }
+
+    fn lifecycle(&mut self, _ctx: &mut LifeCycleCtx, _event: &LifeCycle, _env: &Env) {}
+
+    fn layout(&mut self, _ctx: &mut LayoutCtx, bc: &BoxConstraints, _env: &Env) -> Size {
+        let size = if bc.is_width_bounded() && bc.is_height_bounded() {
+            bc.max()
+        } else {
+            bc.constrain(DEFAULT_SIZE)
+        };
+        trace!("Computed size: {}", size);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, env: &Env) {
+        if self.displayed_values.is_empty() {
+            return;
+        }
+        let size = ctx.size();
+        let plot = Self::plot_rect(size);
+        let color = self.color.resolve(env);
+        let hover_color = env.get(theme::PRIMARY_DARK);
+
+        match self.kind {
+            ChartKind::Bar => {
+                let axis_y = self.value_to_y(plot, 0.0);
+                for i in 0..self.displayed_values.len() {
+                    let bar = self.bar_rect(plot, i);
+                    let bar_color = if self.hovered == Some(i) {
+                        &hover_color
+                    } else {
+                        &color
+                    };
+                    ctx.fill(bar, bar_color);
+                }
+                ctx.stroke(
+                    Line::new((plot.x0, axis_y), (plot.x1, axis_y)),
+                    &env.get(theme::BORDER_LIGHT),
+                    1.0,
+                );
+            }
+            ChartKind::Line => {
+                let mut path = BezPath::new();
+                path.move_to(self.line_point(plot, 0));
+                for i in 1..self.displayed_values.len() {
+                    path.line_to(self.line_point(plot, i));
+                }
+                ctx.stroke(path, &color, 2.0);
+                for i in 0..self.displayed_values.len() {
+                    let (radius, point_color) = if self.hovered == Some(i) {
+                        (LINE_POINT_RADIUS * 1.5, &hover_color)
+                    } else {
+                        (LINE_POINT_RADIUS, &color)
+                    };
+                    ctx.fill(Circle::new(self.line_point(plot, i), radius), point_color);
+                }
+            }
+            ChartKind::Pie | ChartKind::Donut => {
+                let (outer, inner) = self.pie_radii(plot);
+                let high_contrast = env.get(Env::HIGH_CONTRAST);
+                for (i, (start, sweep)) in self.slice_angles().into_iter().enumerate() {
+                    let slice = Circle::new(plot.center(), outer).segment(inner, start, sweep);
+                    let slice_color = &SLICE_COLORS[i % SLICE_COLORS.len()];
+                    ctx.fill(slice.clone(), slice_color);
+                    if self.hovered == Some(i) {
+                        ctx.stroke(slice, &env.get(theme::FOREGROUND_LIGHT), 2.0);
+                    } else if high_contrast {
+                        // Some slice colors are hard to tell apart without an outline.
+                        ctx.stroke(slice, &env.get(theme::BORDER_DARK), 1.0);
+                    }
+                }
+            }
+        }
+
+        if let Some(index) = self.hovered {
+            self.tooltip.rebuild_if_needed(ctx.text(), env);
+            let tooltip = self.tooltip.clone();
+            let text_size = tooltip.size();
+            let anchor = self.item_anchor(size, index);
+            let origin = Point::new(
+                anchor.x - text_size.width / 2.0,
+                anchor.y - text_size.height - 2.0 * TOOLTIP_PADDING,
+            );
+            let background = Rect::from_origin_size(origin, text_size)
+                .inset(TOOLTIP_PADDING)
+                .to_rounded_rect(2.0);
+            let background_color = env.get(theme::BACKGROUND_LIGHT);
+            let border_color = env.get(theme::BORDER_LIGHT);
+
+            ctx.paint_with_z_index(TOOLTIP_Z_INDEX, move |ctx| {
+                ctx.fill(background, &background_color);
+                ctx.stroke(background, &border_color, 1.0);
+                tooltip.draw(ctx, origin);
+            });
+        }
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        SmallVec::new()
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("Chart")
+    }
+
+    fn get_debug_text(&self) -> Option<String> {
+        Some(format!("{:?} {:?}", self.kind, self.values))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shell::MouseButton;
+    use crate::testing::{widget_ids, TestHarness, TestWidgetExt};
+
+    fn data() -> Vec<(&'static str, f64)> {
+        vec![("a", 1.0), ("b", 3.0), ("c", 2.0)]
+    }
+
+    #[test]
+    fn bar_hit_test() {
+        let chart = Chart::bar(data());
+        let size = Size::new(316.0, 100.0);
+        // Plot is 300px wide, so each bar has a 100px slot.
+        assert_eq!(chart.item_at(size, Point::new(58.0, 90.0)), Some(0));
+        assert_eq!(chart.item_at(size, Point::new(158.0, 20.0)), Some(1));
+        assert_eq!(chart.item_at(size, Point::new(258.0, 50.0)), Some(2));
+        // Gap between bars
+        assert_eq!(chart.item_at(size, Point::new(108.0, 90.0)), None);
+    }
+
+    #[test]
+    fn pie_hit_test() {
+        let chart = Chart::donut(vec![("a", 1.0), ("b", 1.0)]);
+        let size = Size::new(116.0, 116.0);
+        // First slice covers the right half, second slice the left half.
+        assert_eq!(chart.item_at(size, Point::new(100.0, 58.0)), Some(0));
+        assert_eq!(chart.item_at(size, Point::new(16.0, 58.0)), Some(1));
+        // Donut hole
+        assert_eq!(chart.item_at(size, Point::new(58.0, 58.0)), None);
+    }
+
+    #[test]
+    fn select_bar() {
+        let [chart_id] = widget_ids();
+        let widget = Chart::bar(vec![("a", 1.0)]).with_id(chart_id);
+
+        let mut harness = TestHarness::create(widget);
+        harness.mouse_click_on(chart_id);
+        assert_eq!(
+            harness.pop_action(),
+            Some((Action::ChartItemSelected(0), chart_id))
+        );
+    }
+
+    #[test]
+    fn release_on_other_item_selects_nothing() {
+        let chart = Chart::bar(data());
+        let mut harness = TestHarness::create_with_size(chart, Size::new(316.0, 100.0));
+
+        harness.mouse_move((58.0, 90.0));
+        harness.mouse_button_press(MouseButton::Left);
+        harness.mouse_move((258.0, 50.0));
+        harness.mouse_button_release(MouseButton::Left);
+        assert_eq!(harness.pop_action(), None);
+    }
+
+    #[test]
+    fn data_transition() {
+        let mut harness = TestHarness::create(Chart::line(data()));
+
+        harness.edit_root_widget(|mut chart, _| {
+            let mut chart = chart.downcast::<Chart>().unwrap();
+            chart.set_data(vec![("a", 3.0), ("b", 3.0), ("c", 3.0), ("d", 3.0)]);
+            assert_eq!(chart.displayed_values, vec![1.0, 3.0, 2.0, 0.0]);
+        });
+
+        harness.process_event(Event::AnimFrame(TRANSITION_DURATION as u64));
+
+        harness.edit_root_widget(|mut chart, _| {
+            let chart = chart.downcast::<Chart>().unwrap();
+            assert_eq!(chart.displayed_values, vec![3.0; 4]);
+        });
+    }
+}
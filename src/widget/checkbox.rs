@@ -7,19 +7,21 @@
 use smallvec::SmallVec;
 use tracing::{trace, trace_span, Span};
 
+use crate::access_keys::ACTIVATE;
 use crate::action::Action;
 use crate::kurbo::{BezPath, Size};
 use crate::piet::{LineCap, LineJoin, LinearGradient, RenderContext, StrokeStyle, UnitPoint};
-use crate::widget::{Label, WidgetMut, WidgetRef};
+use crate::widget::{InteractionState, Label, WidgetMut, WidgetRef};
 use crate::{
-    theme, ArcStr, BoxConstraints, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx,
-    PaintCtx, StatusChange, Widget, WidgetPod,
+    theme, ArcStr, BoxConstraints, Env, Event, EventCtx, InternalEvent, LayoutCtx, LifeCycle,
+    LifeCycleCtx, PaintCtx, StatusChange, Widget, WidgetPod,
 };
 
 /// A checkbox that can be toggled.
 pub struct Checkbox {
     checked: bool,
     label: WidgetPod<Label>,
+    interaction: InteractionState,
 }
 
 crate::declare_widget!(CheckboxMut, Checkbox);
@@ -30,6 +32,7 @@ impl Checkbox {
         Checkbox {
             checked,
             label: WidgetPod::new(Label::new(text)),
+            interaction: InteractionState::new(),
         }
     }
 
@@ -38,8 +41,14 @@ impl Checkbox {
         Checkbox {
             checked,
             label: WidgetPod::new(label),
+            interaction: InteractionState::new(),
         }
     }
+
+    /// Whether the checkbox is checked.
+    pub fn is_checked(&self) -> bool {
+        self.checked
+    }
 }
 
 impl<'a, 'b> CheckboxMut<'a, 'b> {
@@ -59,7 +68,7 @@ impl<'a, 'b> CheckboxMut<'a, 'b> {
 }
 
 impl Widget for Checkbox {
-    fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, _env: &Env) {
+    fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, env: &Env) {
         match event {
             Event::MouseDown(_) => {
                 if !ctx.is_disabled() {
@@ -79,15 +88,30 @@ impl Widget for Checkbox {
                 }
                 ctx.set_active(false);
             }
+            Event::Command(cmd) if cmd.is(ACTIVATE) => {
+                if !ctx.is_disabled() {
+                    self.checked = !self.checked;
+                    ctx.submit_action(Action::CheckboxChecked(self.checked));
+                    ctx.request_paint();
+                    ctx.set_handled();
+                    trace!("Checkbox {:?} activated", ctx.widget_id());
+                }
+            }
+            // Route the commands sent to the label, eg to show its access key.
+            Event::Internal(InternalEvent::TargetedCommand(_)) => {
+                self.label.on_event(ctx, event, env);
+            }
             _ => (),
         }
+        self.interaction.on_event(ctx, event);
     }
 
-    fn on_status_change(&mut self, ctx: &mut LifeCycleCtx, _event: &StatusChange, _env: &Env) {
-        ctx.request_paint();
+    fn on_status_change(&mut self, ctx: &mut LifeCycleCtx, event: &StatusChange, _env: &Env) {
+        self.interaction.on_status_change(ctx, event);
     }
 
     fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, env: &Env) {
+        self.interaction.lifecycle(ctx, event);
         self.label.lifecycle(ctx, event, env);
     }
 
@@ -130,11 +154,11 @@ impl Widget for Checkbox {
 
         ctx.fill(rect, &background_gradient);
 
-        let border_color = if ctx.is_hot() && !ctx.is_disabled() {
-            env.get(theme::BORDER_LIGHT)
-        } else {
-            env.get(theme::BORDER_DARK)
-        };
+        let border_color = self.interaction.color(
+            env.get(theme::BORDER_DARK),
+            env.get(theme::BORDER_LIGHT),
+            env.get(theme::BORDER_LIGHT),
+        );
 
         ctx.stroke(rect, &border_color, border_width);
 
@@ -205,6 +229,8 @@ mod tests {
             harness.pop_action(),
             Some((Action::CheckboxChecked(true), checkbox_id))
         );
+        // Finish the hover transition.
+        harness.process_event(Event::AnimFrame(1_000_000_000));
 
         assert_debug_snapshot!(harness.root_widget());
         assert_render_snapshot!(harness, "hello_checked");
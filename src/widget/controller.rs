@@ -0,0 +1,192 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! A widget-controlling wrapper.
+
+use smallvec::SmallVec;
+use tracing::{trace_span, Span};
+
+use crate::widget::{StoreInWidgetMut, WidgetPod, WidgetRef};
+use crate::{
+    BoxConstraints, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx, Point,
+    Size, StatusChange, Widget,
+};
+
+/// A type that can intercept the events and lifecycle calls of a widget.
+///
+/// A `Controller` is attached to a widget with [`ControllerHost`]. Its methods are
+/// called *instead* of the widget's; the default implementations just forward the
+/// call to the child. A controller that overrides a method should usually still
+/// call through to the child, unless it means to swallow the event.
+///
+/// A controller which doesn't forward an event must mark the child as skipped with
+/// [`EventCtx::skip_child`]; otherwise debug builds panic, because the child wasn't
+/// visited.
+///
+/// This is useful for adding a behavior to an existing widget, eg "click anywhere
+/// to dismiss" or logging, without writing a whole new widget.
+pub trait Controller<W: Widget> {
+    /// Called instead of the child's [`Widget::on_event`].
+    fn on_event(&mut self, child: &mut WidgetPod<W>, ctx: &mut EventCtx, event: &Event, env: &Env) {
+        child.on_event(ctx, event, env);
+    }
+
+    /// Called on the [`ControllerHost`]'s own hot and focus changes.
+    ///
+    /// The child gets its own status changes independently.
+    fn on_status_change(&mut self, ctx: &mut LifeCycleCtx, event: &StatusChange, env: &Env) {
+        #![allow(unused)]
+    }
+
+    /// Called instead of the child's [`Widget::lifecycle`].
+    fn lifecycle(
+        &mut self,
+        child: &mut WidgetPod<W>,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        env: &Env,
+    ) {
+        child.lifecycle(ctx, event, env);
+    }
+}
+
+/// A wrapper that lets a [`Controller`] intercept the events of its child.
+///
+/// The host has the same size as its child, and is otherwise invisible.
+#[derive(StoreInWidgetMut)]
+pub struct ControllerHost<W: Widget, C: Controller<W> + 'static> {
+    #[widget_mut(child)]
+    child: WidgetPod<W>,
+    controller: C,
+}
+
+impl<W: Widget, C: Controller<W> + 'static> ControllerHost<W, C> {
+    /// Create a new `ControllerHost`.
+    pub fn new(child: W, controller: C) -> Self {
+        ControllerHost {
+            child: WidgetPod::new(child),
+            controller,
+        }
+    }
+
+    /// Create a new `ControllerHost` whose child is in a given pod.
+    ///
+    /// This lets you set the child's id with [`WidgetPod::new_with_id`].
+    pub fn from_pod(child: WidgetPod<W>, controller: C) -> Self {
+        ControllerHost { child, controller }
+    }
+
+    /// Get a reference to the controller.
+    pub fn controller(&self) -> &C {
+        &self.controller
+    }
+}
+
+impl<'a, 'b, W: Widget, C: Controller<W> + 'static> ControllerHostMut<'a, 'b, W, C> {
+    /// Get a mutable reference to the controller.
+    ///
+    /// The controller isn't part of the widget tree, so changing it doesn't request
+    /// any pass.
+    pub fn controller_mut(&mut self) -> &mut C {
+        &mut self.widget.controller
+    }
+}
+
+impl<W: Widget, C: Controller<W> + 'static> Widget for ControllerHost<W, C> {
+    fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, env: &Env) {
+        self.controller.on_event(&mut self.child, ctx, event, env);
+    }
+
+    fn on_status_change(&mut self, ctx: &mut LifeCycleCtx, event: &StatusChange, env: &Env) {
+        self.controller.on_status_change(ctx, event, env);
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, env: &Env) {
+        self.controller.lifecycle(&mut self.child, ctx, event, env);
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, env: &Env) -> Size {
+        let size = self.child.layout(ctx, bc, env);
+        ctx.place_child(&mut self.child, Point::ZERO, env);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, env: &Env) {
+        self.child.paint(ctx, env);
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        self.pod_children()
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("ControllerHost")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{widget_ids, TestHarness};
+    use crate::widget::Button;
+    use crate::Action;
+
+    /// Swallows mouse events while `blocked` is set, and counts clicks.
+    #[derive(Default)]
+    struct ClickBlocker {
+        blocked: bool,
+        clicks: usize,
+    }
+
+    impl<W: Widget> Controller<W> for ClickBlocker {
+        fn on_event(
+            &mut self,
+            child: &mut WidgetPod<W>,
+            ctx: &mut EventCtx,
+            event: &Event,
+            env: &Env,
+        ) {
+            if let Event::MouseDown(_) = event {
+                self.clicks += 1;
+            }
+            let is_mouse_event = matches!(event, Event::MouseDown(_) | Event::MouseUp(_));
+            if self.blocked && is_mouse_event {
+                ctx.skip_child(child);
+            } else {
+                child.on_event(ctx, event, env);
+            }
+        }
+    }
+
+    #[test]
+    fn controller_intercepts_events() {
+        let [button_id] = widget_ids();
+        let widget = ControllerHost::from_pod(
+            WidgetPod::new_with_id(Button::new("Hello"), button_id),
+            ClickBlocker::default(),
+        );
+
+        let mut harness = TestHarness::create(widget);
+        harness.mouse_click_on(button_id);
+        assert_eq!(
+            harness.pop_action(),
+            Some((Action::ButtonPressed, button_id))
+        );
+
+        harness.edit_root_widget(|mut root, _| {
+            let mut host = root
+                .downcast::<ControllerHost<Button, ClickBlocker>>()
+                .unwrap();
+            host.controller_mut().blocked = true;
+        });
+        harness.mouse_click_on(button_id);
+        assert_eq!(harness.pop_action(), None);
+
+        let host = harness.root_widget();
+        let host = host
+            .downcast::<ControllerHost<Button, ClickBlocker>>()
+            .unwrap();
+        assert_eq!(host.controller().clicks, 2);
+    }
+}
@@ -0,0 +1,1022 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! A container of dockable panels, which the user can rearrange.
+
+use serde::{Deserialize, Serialize};
+use smallvec::SmallVec;
+use tracing::{trace, trace_span, warn, Span};
+
+use crate::piet::{FontFamily, Text, TextLayout as _, TextLayoutBuilder};
+use crate::widget::flex::Axis;
+use crate::widget::{WidgetMut, WidgetPod, WidgetRef};
+use crate::{
+    theme, ArcStr, BoxConstraints, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx,
+    PaintCtx, Point, Rect, RenderContext, Size, StatusChange, Value, Widget, WidgetCtx, WidgetId,
+    WindowDescription,
+};
+
+/// The height of the tab bar above each stack of panels.
+const TAB_HEIGHT: f64 = 24.0;
+
+/// The width of each tab.
+const TAB_WIDTH: f64 = 100.0;
+
+/// The space between the edge of a tab and its title.
+const TAB_PADDING: f64 = 6.0;
+
+/// The space between the two sides of a split.
+const SPLITTER_SIZE: f64 = 4.0;
+
+/// The size of the area along an unbounded axis.
+const UNBOUNDED_SIZE: f64 = 400.0;
+
+/// How close to the edge of a stack a panel must be dropped to be docked on
+/// that side, as a fraction of the stack's size.
+const EDGE_FRACTION: f64 = 0.25;
+
+/// Where a panel is docked, relative to another panel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DockSide {
+    /// In the same stack, as another tab.
+    Center,
+    /// In a new stack, left of the other panel's stack.
+    Left,
+    /// In a new stack, right of the other panel's stack.
+    Right,
+    /// In a new stack, above the other panel's stack.
+    Top,
+    /// In a new stack, below the other panel's stack.
+    Bottom,
+}
+
+/// The arrangement of panels in a [`DockArea`].
+///
+/// Every panel of the dock area appears exactly once in its layout.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DockLayout {
+    /// A stack of panels displayed as tabs; only the selected panel is visible.
+    Tabs {
+        /// The panels, in tab order.
+        panels: Vec<WidgetId>,
+        /// The index of the visible panel.
+        selected: usize,
+    },
+    /// Two layouts side by side (for [`Axis::Horizontal`]) or on top of each other.
+    Split {
+        /// The axis along which the area is split.
+        axis: Axis,
+        /// The fraction of the area given to `first`, between 0.0 and 1.0.
+        ratio: f64,
+        /// The left or top layout.
+        first: Box<DockLayout>,
+        /// The right or bottom layout.
+        second: Box<DockLayout>,
+    },
+}
+
+/// A container of panels which can be stacked as tabs, split side by side,
+/// rearranged by dragging their tabs, and floated into their own window.
+///
+/// Each panel is identified by the [`WidgetId`] of its widget. The arrangement of
+/// the panels is described by a [`DockLayout`], which is saved and restored with
+/// the rest of the [UI state](crate::ui_state); the panels themselves aren't, so
+/// they should have stable ids for their layout to be restored across restarts.
+///
+/// Dragging a tab onto another stack's tab bar adds it to that stack; dragging it
+/// near an edge of a stack docks it on that side. Dropping it outside the dock area
+/// moves the panel to a new window, unless [`floating`](Self::floating) is disabled.
+///
+/// Floated panels keep their widget's state, but can't be docked back yet.
+///
+/// The dock area takes all the space it's given.
+pub struct DockArea {
+    panels: Vec<Panel>,
+    layout: DockLayout,
+    floating: bool,
+    stacks: Vec<StackGeometry>,
+    drag: Option<Drag>,
+    drop_target: Option<DropTarget>,
+}
+crate::declare_widget!(DockAreaMut, DockArea);
+
+struct Panel {
+    title: ArcStr,
+    pod: WidgetPod<Box<dyn Widget>>,
+}
+
+/// The position of a stack of panels, computed during layout.
+struct StackGeometry {
+    panels: Vec<WidgetId>,
+    selected: Option<WidgetId>,
+    tab_bar: Rect,
+    content: Rect,
+}
+
+struct Drag {
+    panel: WidgetId,
+    start: Point,
+    dragging: bool,
+}
+
+struct DropTarget {
+    panel: WidgetId,
+    side: DockSide,
+    rect: Rect,
+}
+
+/// The form a [`DockLayout`] is saved in, with raw ids.
+#[derive(Serialize, Deserialize)]
+enum SavedLayout {
+    Tabs {
+        panels: Vec<u64>,
+        selected: usize,
+    },
+    Split {
+        vertical: bool,
+        ratio: f64,
+        first: Box<SavedLayout>,
+        second: Box<SavedLayout>,
+    },
+}
+
+// --- DockSide / DockLayout ---
+
+impl DockSide {
+    fn axis(self) -> Option<Axis> {
+        match self {
+            DockSide::Center => None,
+            DockSide::Left | DockSide::Right => Some(Axis::Horizontal),
+            DockSide::Top | DockSide::Bottom => Some(Axis::Vertical),
+        }
+    }
+
+    fn is_leading(self) -> bool {
+        matches!(self, DockSide::Left | DockSide::Top)
+    }
+}
+
+impl Default for DockLayout {
+    fn default() -> Self {
+        DockLayout::Tabs {
+            panels: Vec::new(),
+            selected: 0,
+        }
+    }
+}
+
+impl DockLayout {
+    fn is_empty(&self) -> bool {
+        match self {
+            DockLayout::Tabs { panels, .. } => panels.is_empty(),
+            DockLayout::Split { first, second, .. } => first.is_empty() && second.is_empty(),
+        }
+    }
+
+    fn contains(&self, id: WidgetId) -> bool {
+        match self {
+            DockLayout::Tabs { panels, .. } => panels.contains(&id),
+            DockLayout::Split { first, second, .. } => first.contains(id) || second.contains(id),
+        }
+    }
+
+    /// The panels which are the selected tab of their stack.
+    fn visible_panels(&self, visible: &mut Vec<WidgetId>) {
+        match self {
+            DockLayout::Tabs { panels, selected } => visible.extend(panels.get(*selected)),
+            DockLayout::Split { first, second, .. } => {
+                first.visible_panels(visible);
+                second.visible_panels(visible);
+            }
+        }
+    }
+
+    /// The first stack, in left-to-right and top-to-bottom order.
+    fn first_stack_mut(&mut self) -> &mut Vec<WidgetId> {
+        match self {
+            DockLayout::Tabs { panels, .. } => panels,
+            DockLayout::Split { first, .. } => first.first_stack_mut(),
+        }
+    }
+
+    /// Select the tab of `id` in its stack.
+    fn select(&mut self, id: WidgetId) -> bool {
+        match self {
+            DockLayout::Tabs { panels, selected } => {
+                let Some(idx) = panels.iter().position(|panel| *panel == id) else {
+                    return false;
+                };
+                *selected = idx;
+                true
+            }
+            DockLayout::Split { first, second, .. } => first.select(id) || second.select(id),
+        }
+    }
+
+    /// Remove `id` from the layout, removing the stacks and splits left empty.
+    fn remove_panel(&mut self, id: WidgetId) -> bool {
+        let replacement = match self {
+            DockLayout::Tabs { panels, selected } => {
+                let Some(idx) = panels.iter().position(|panel| *panel == id) else {
+                    return false;
+                };
+                panels.remove(idx);
+                if idx < *selected || *selected >= panels.len() {
+                    *selected = selected.saturating_sub(1);
+                }
+                return true;
+            }
+            DockLayout::Split { first, second, .. } => {
+                if !first.remove_panel(id) && !second.remove_panel(id) {
+                    return false;
+                }
+                if first.is_empty() {
+                    std::mem::take(&mut **second)
+                } else if second.is_empty() {
+                    std::mem::take(&mut **first)
+                } else {
+                    return true;
+                }
+            }
+        };
+        *self = replacement;
+        true
+    }
+
+    /// Insert `id` next to `target`, which must be in the layout.
+    fn insert_panel(&mut self, id: WidgetId, target: WidgetId, side: DockSide) -> bool {
+        match self {
+            DockLayout::Tabs { panels, .. } if !panels.contains(&target) => return false,
+            DockLayout::Tabs { panels, selected } if side == DockSide::Center => {
+                panels.push(id);
+                *selected = panels.len() - 1;
+                return true;
+            }
+            DockLayout::Tabs { .. } => {}
+            DockLayout::Split { first, second, .. } => {
+                return first.insert_panel(id, target, side)
+                    || second.insert_panel(id, target, side);
+            }
+        }
+
+        // We're the target's stack, and the panel goes in a new stack next to us.
+        let Some(axis) = side.axis() else {
+            return false;
+        };
+        let existing = std::mem::take(self);
+        let new_stack = DockLayout::Tabs {
+            panels: vec![id],
+            selected: 0,
+        };
+        let (first, second) = if side.is_leading() {
+            (new_stack, existing)
+        } else {
+            (existing, new_stack)
+        };
+        *self = DockLayout::Split {
+            axis,
+            ratio: 0.5,
+            first: Box::new(first),
+            second: Box::new(second),
+        };
+        true
+    }
+
+    /// Keep only the panels in `ids`, at most once each, and remove empty stacks.
+    fn retain_panels(&mut self, ids: &[WidgetId], seen: &mut Vec<WidgetId>) {
+        let replacement = match self {
+            DockLayout::Tabs { panels, selected } => {
+                panels.retain(|id| {
+                    let keep = ids.contains(id) && !seen.contains(id);
+                    seen.push(*id);
+                    keep
+                });
+                *selected = (*selected).min(panels.len().saturating_sub(1));
+                return;
+            }
+            DockLayout::Split {
+                ratio,
+                first,
+                second,
+                ..
+            } => {
+                *ratio = ratio.clamp(0.0, 1.0);
+                first.retain_panels(ids, seen);
+                second.retain_panels(ids, seen);
+                if first.is_empty() {
+                    std::mem::take(&mut **second)
+                } else if second.is_empty() {
+                    std::mem::take(&mut **first)
+                } else {
+                    return;
+                }
+            }
+        };
+        *self = replacement;
+    }
+
+    fn to_saved(&self) -> SavedLayout {
+        match self {
+            DockLayout::Tabs { panels, selected } => SavedLayout::Tabs {
+                panels: panels.iter().map(|id| id.to_raw()).collect(),
+                selected: *selected,
+            },
+            DockLayout::Split {
+                axis,
+                ratio,
+                first,
+                second,
+            } => SavedLayout::Split {
+                vertical: *axis == Axis::Vertical,
+                ratio: *ratio,
+                first: Box::new(first.to_saved()),
+                second: Box::new(second.to_saved()),
+            },
+        }
+    }
+
+    fn from_saved(saved: &SavedLayout, ids: &[WidgetId]) -> Self {
+        match saved {
+            SavedLayout::Tabs { panels, selected } => DockLayout::Tabs {
+                panels: panels
+                    .iter()
+                    .filter_map(|raw| ids.iter().copied().find(|id| id.to_raw() == *raw))
+                    .collect(),
+                selected: *selected,
+            },
+            SavedLayout::Split {
+                vertical,
+                ratio,
+                first,
+                second,
+            } => DockLayout::Split {
+                axis: if *vertical {
+                    Axis::Vertical
+                } else {
+                    Axis::Horizontal
+                },
+                ratio: *ratio,
+                first: Box::new(DockLayout::from_saved(first, ids)),
+                second: Box::new(DockLayout::from_saved(second, ids)),
+            },
+        }
+    }
+}
+
+// --- DockArea ---
+
+impl DockArea {
+    /// Create an empty dock area.
+    pub fn new() -> Self {
+        DockArea {
+            panels: Vec::new(),
+            layout: DockLayout::default(),
+            floating: true,
+            stacks: Vec::new(),
+            drag: None,
+            drop_target: None,
+        }
+    }
+
+    /// Builder-style method to add a panel as a tab of the first stack.
+    pub fn with_panel(self, title: impl Into<ArcStr>, widget: impl Widget) -> Self {
+        self.with_panel_pod(title, WidgetPod::new(widget).boxed())
+    }
+
+    /// Builder-style method to add a panel with a given id as a tab of the first stack.
+    pub fn with_panel_id(
+        self,
+        title: impl Into<ArcStr>,
+        widget: impl Widget,
+        id: WidgetId,
+    ) -> Self {
+        self.with_panel_pod(title, WidgetPod::new_with_id(widget, id).boxed())
+    }
+
+    fn with_panel_pod(mut self, title: impl Into<ArcStr>, pod: WidgetPod<Box<dyn Widget>>) -> Self {
+        self.layout.first_stack_mut().push(pod.id());
+        self.panels.push(Panel {
+            title: title.into(),
+            pod,
+        });
+        self
+    }
+
+    /// Builder-style method to move `panel` next to the panel `next_to`.
+    ///
+    /// Both panels must already have been added.
+    pub fn with_docked(mut self, panel: WidgetId, next_to: WidgetId, side: DockSide) -> Self {
+        self.move_panel(panel, next_to, side);
+        self
+    }
+
+    /// Builder-style method to set whether panels dragged out of the dock area are
+    /// moved to a new window.
+    ///
+    /// This is enabled by default.
+    pub fn floating(mut self, floating: bool) -> Self {
+        self.floating = floating;
+        self
+    }
+
+    /// The current arrangement of the panels.
+    pub fn layout(&self) -> &DockLayout {
+        &self.layout
+    }
+
+    /// Move `panel` next to `next_to`; return `false` if either isn't in the layout.
+    fn move_panel(&mut self, panel: WidgetId, next_to: WidgetId, side: DockSide) -> bool {
+        if panel == next_to || !self.layout.contains(next_to) {
+            return false;
+        }
+        if !self.layout.remove_panel(panel) {
+            return false;
+        }
+        self.layout.insert_panel(panel, next_to, side)
+    }
+
+    /// Stash the panels which aren't the selected tab of their stack, and unstash the others.
+    fn update_stashed(
+        &mut self,
+        mut set_stashed: impl FnMut(&mut WidgetPod<Box<dyn Widget>>, bool),
+    ) {
+        let mut visible = Vec::new();
+        self.layout.visible_panels(&mut visible);
+        for panel in &mut self.panels {
+            let stashed = !visible.contains(&panel.pod.id());
+            if panel.pod.state().is_stashed != stashed {
+                set_stashed(&mut panel.pod, stashed);
+            }
+        }
+    }
+
+    fn panel_ids(&self) -> Vec<WidgetId> {
+        self.panels.iter().map(|panel| panel.pod.id()).collect()
+    }
+
+    /// Make `layout` include each panel exactly once.
+    fn sanitize_layout(&mut self) {
+        let ids = self.panel_ids();
+        let mut seen = Vec::new();
+        self.layout.retain_panels(&ids, &mut seen);
+        for id in ids {
+            if !self.layout.contains(id) {
+                self.layout.first_stack_mut().push(id);
+            }
+        }
+    }
+
+    /// The panel whose tab is under `pos`.
+    fn tab_at(&self, pos: Point) -> Option<WidgetId> {
+        self.stacks.iter().find_map(|stack| {
+            stack
+                .panels
+                .iter()
+                .enumerate()
+                .find(|(idx, _)| tab_rect(stack.tab_bar, *idx).contains(pos))
+                .map(|(_, id)| *id)
+        })
+    }
+
+    fn drop_target_at(&self, pos: Point, dragged: WidgetId) -> Option<DropTarget> {
+        let stack = self
+            .stacks
+            .iter()
+            .find(|stack| stack.tab_bar.union(stack.content).contains(pos))?;
+        let panel = *stack.panels.iter().find(|id| **id != dragged)?;
+        let side = if stack.tab_bar.contains(pos) {
+            DockSide::Center
+        } else {
+            side_at(stack.content, pos)
+        };
+        Some(DropTarget {
+            panel,
+            side,
+            rect: side_rect(stack.content, side),
+        })
+    }
+
+    /// Move a panel to a new window.
+    fn float_panel(&mut self, ctx: &mut EventCtx, id: WidgetId) {
+        let Some(idx) = self.panels.iter().position(|panel| panel.pod.id() == id) else {
+            return;
+        };
+        trace!("float panel {:?}", id);
+        self.layout.remove_panel(id);
+        let panel = self.panels.remove(idx);
+        let size = panel.pod.layout_rect().size();
+        // The pod keeps its id and state, eg its focus, in the new window.
+        let mut widget_ctx = WidgetCtx {
+            global_state: ctx.global_state,
+            widget_state: ctx.widget_state,
+        };
+        let pod = panel.pod.take(&mut widget_ctx);
+        self.update_stashed(|pod, stashed| ctx.set_stashed(pod, stashed));
+
+        ctx.new_window(
+            WindowDescription::from_pod(pod)
+                .title(panel.title)
+                .window_size(size),
+        );
+    }
+}
+
+impl Default for DockArea {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn tab_rect(tab_bar: Rect, idx: usize) -> Rect {
+    let x0 = tab_bar.x0 + idx as f64 * TAB_WIDTH;
+    Rect::new(x0, tab_bar.y0, (x0 + TAB_WIDTH).min(tab_bar.x1), tab_bar.y1)
+}
+
+fn split_rect(rect: Rect, axis: Axis, ratio: f64) -> (Rect, Rect) {
+    let available = (axis.major(rect.size()) - SPLITTER_SIZE).max(0.0);
+    let first = (available * ratio).round();
+    match axis {
+        Axis::Horizontal => (
+            Rect::new(rect.x0, rect.y0, rect.x0 + first, rect.y1),
+            Rect::new(rect.x0 + first + SPLITTER_SIZE, rect.y0, rect.x1, rect.y1),
+        ),
+        Axis::Vertical => (
+            Rect::new(rect.x0, rect.y0, rect.x1, rect.y0 + first),
+            Rect::new(rect.x0, rect.y0 + first + SPLITTER_SIZE, rect.x1, rect.y1),
+        ),
+    }
+}
+
+fn compute_stacks(layout: &DockLayout, rect: Rect, stacks: &mut Vec<StackGeometry>) {
+    match layout {
+        DockLayout::Tabs { panels, selected } => {
+            let tab_bar = Rect::new(
+                rect.x0,
+                rect.y0,
+                rect.x1,
+                (rect.y0 + TAB_HEIGHT).min(rect.y1),
+            );
+            stacks.push(StackGeometry {
+                panels: panels.clone(),
+                selected: panels.get(*selected).copied(),
+                tab_bar,
+                content: Rect::new(rect.x0, tab_bar.y1, rect.x1, rect.y1),
+            });
+        }
+        DockLayout::Split {
+            axis,
+            ratio,
+            first,
+            second,
+        } => {
+            let (first_rect, second_rect) = split_rect(rect, *axis, *ratio);
+            compute_stacks(first, first_rect, stacks);
+            compute_stacks(second, second_rect, stacks);
+        }
+    }
+}
+
+/// The side of `rect` a panel dropped at `pos` is docked on.
+fn side_at(rect: Rect, pos: Point) -> DockSide {
+    let x = (pos.x - rect.x0) / rect.width();
+    let y = (pos.y - rect.y0) / rect.height();
+    let (distance, side) = [
+        (x, DockSide::Left),
+        (1.0 - x, DockSide::Right),
+        (y, DockSide::Top),
+        (1.0 - y, DockSide::Bottom),
+    ]
+    .into_iter()
+    .fold((f64::INFINITY, DockSide::Center), |closest, edge| {
+        if edge.0 < closest.0 {
+            edge
+        } else {
+            closest
+        }
+    });
+    if distance < EDGE_FRACTION {
+        side
+    } else {
+        DockSide::Center
+    }
+}
+
+/// The area a panel docked on `side` of `rect` would take.
+fn side_rect(rect: Rect, side: DockSide) -> Rect {
+    let center = rect.center();
+    match side {
+        DockSide::Center => rect,
+        DockSide::Left => Rect::new(rect.x0, rect.y0, center.x, rect.y1),
+        DockSide::Right => Rect::new(center.x, rect.y0, rect.x1, rect.y1),
+        DockSide::Top => Rect::new(rect.x0, rect.y0, rect.x1, center.y),
+        DockSide::Bottom => Rect::new(rect.x0, center.y, rect.x1, rect.y1),
+    }
+}
+
+// --- Mutate live tree ---
+
+impl<'a, 'b> DockAreaMut<'a, 'b> {
+    /// Add a panel as a tab of the first stack.
+    pub fn add_panel(&mut self, title: impl Into<ArcStr>, widget: impl Widget) {
+        self.add_panel_pod(title, WidgetPod::new(widget).boxed());
+    }
+
+    /// Add a panel with a given id as a tab of the first stack.
+    pub fn add_panel_id(&mut self, title: impl Into<ArcStr>, widget: impl Widget, id: WidgetId) {
+        self.add_panel_pod(title, WidgetPod::new_with_id(widget, id).boxed());
+    }
+
+    /// Add a panel taken from another container with [`WidgetPod::take`].
+    ///
+    /// The panel keeps its state, including focus.
+    pub fn adopt_panel(&mut self, title: impl Into<ArcStr>, pod: WidgetPod<Box<dyn Widget>>) {
+        let pod = pod.adopt(&mut self.ctx);
+        self.add_panel_pod(title, pod);
+    }
+
+    fn add_panel_pod(&mut self, title: impl Into<ArcStr>, pod: WidgetPod<Box<dyn Widget>>) {
+        self.widget.layout.first_stack_mut().push(pod.id());
+        self.widget.panels.push(Panel {
+            title: title.into(),
+            pod,
+        });
+        self.widget
+            .update_stashed(|pod, stashed| self.ctx.set_stashed(pod, stashed));
+        self.ctx.children_changed();
+    }
+
+    /// Remove a panel and return it, to move it to another container.
+    ///
+    /// See [`WidgetPod::take`].
+    pub fn take_panel(&mut self, id: WidgetId) -> Option<WidgetPod<Box<dyn Widget>>> {
+        let idx = self
+            .widget
+            .panels
+            .iter()
+            .position(|panel| panel.pod.id() == id)?;
+        self.widget.layout.remove_panel(id);
+        let mut pod = self.widget.panels.remove(idx).pod;
+        // The new parent doesn't know about our tabs.
+        self.ctx.set_stashed(&mut pod, false);
+        self.widget
+            .update_stashed(|pod, stashed| self.ctx.set_stashed(pod, stashed));
+        Some(pod.take(&mut self.ctx))
+    }
+
+    /// Remove a panel.
+    pub fn remove_panel(&mut self, id: WidgetId) {
+        self.take_panel(id);
+    }
+
+    /// Move `panel` next to the panel `next_to`.
+    pub fn dock_panel(&mut self, panel: WidgetId, next_to: WidgetId, side: DockSide) {
+        if self.widget.move_panel(panel, next_to, side) {
+            self.widget
+                .update_stashed(|pod, stashed| self.ctx.set_stashed(pod, stashed));
+            self.ctx.request_layout();
+        }
+    }
+
+    /// Make a panel the visible tab of its stack.
+    pub fn select_panel(&mut self, id: WidgetId) {
+        if self.widget.layout.select(id) {
+            self.widget
+                .update_stashed(|pod, stashed| self.ctx.set_stashed(pod, stashed));
+            self.ctx.request_layout();
+        }
+    }
+
+    /// Replace the arrangement of the panels.
+    ///
+    /// Unknown ids are ignored, and panels missing from `layout` are added to its
+    /// first stack.
+    pub fn set_layout(&mut self, layout: DockLayout) {
+        self.widget.layout = layout;
+        self.widget.sanitize_layout();
+        self.widget
+            .update_stashed(|pod, stashed| self.ctx.set_stashed(pod, stashed));
+        self.ctx.request_layout();
+    }
+
+    /// Set whether panels dragged out of the dock area are moved to a new window.
+    pub fn set_floating(&mut self, floating: bool) {
+        self.widget.floating = floating;
+    }
+
+    /// Get a mutable reference to a panel's widget.
+    pub fn panel_mut(&mut self, id: WidgetId) -> Option<WidgetMut<'_, 'b, Box<dyn Widget>>> {
+        let panel = self
+            .widget
+            .panels
+            .iter_mut()
+            .find(|panel| panel.pod.id() == id)?;
+        Some(self.ctx.get_mut(&mut panel.pod))
+    }
+}
+
+// --- Trait impls ---
+
+impl Widget for DockArea {
+    fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, env: &Env) {
+        match event {
+            Event::MouseDown(mouse) if mouse.button.is_left() => {
+                if let Some(id) = self.tab_at(mouse.pos) {
+                    if self.layout.select(id) {
+                        self.update_stashed(|pod, stashed| ctx.set_stashed(pod, stashed));
+                        ctx.request_layout();
+                    }
+                    self.drag = Some(Drag {
+                        panel: id,
+                        start: mouse.pos,
+                        dragging: false,
+                    });
+                    ctx.set_active(true);
+                    ctx.set_handled();
+                }
+            }
+            Event::MouseMove(mouse) if ctx.is_active() => {
+                if let Some(drag) = &mut self.drag {
+                    if mouse.pos.distance(drag.start) > env.get(theme::DRAG_THRESHOLD) {
+                        drag.dragging = true;
+                    }
+                    if drag.dragging {
+                        let dragged = drag.panel;
+                        self.drop_target = self.drop_target_at(mouse.pos, dragged);
+                        ctx.request_paint();
+                    }
+                }
+                ctx.set_handled();
+            }
+            Event::MouseUp(mouse) if ctx.is_active() && mouse.button.is_left() => {
+                ctx.set_active(false);
+                ctx.set_handled();
+                let drag = self.drag.take();
+                let drop_target = self.drop_target.take();
+                if let Some(Drag {
+                    panel,
+                    dragging: true,
+                    ..
+                }) = drag
+                {
+                    if !ctx.size().to_rect().contains(mouse.pos) {
+                        if self.floating {
+                            self.float_panel(ctx, panel);
+                        }
+                    } else if let Some(target) = drop_target {
+                        if self.move_panel(panel, target.panel, target.side) {
+                            self.update_stashed(|pod, stashed| ctx.set_stashed(pod, stashed));
+                            ctx.request_layout();
+                        }
+                    }
+                }
+                ctx.request_paint();
+            }
+            _ => {}
+        }
+
+        for panel in &mut self.panels {
+            if !panel.pod.state().is_stashed || event.should_propagate_to_hidden() {
+                panel.pod.on_event(ctx, event, env);
+            }
+        }
+    }
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange, _env: &Env) {}
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, env: &Env) {
+        if let LifeCycle::WidgetAdded = event {
+            self.update_stashed(|pod, stashed| ctx.set_stashed(pod, stashed));
+        }
+        for panel in &mut self.panels {
+            if !panel.pod.state().is_stashed || event.should_propagate_to_hidden() {
+                panel.pod.lifecycle(ctx, event, env);
+            }
+        }
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, env: &Env) -> Size {
+        if !bc.is_width_bounded() || !bc.is_height_bounded() {
+            warn!("A DockArea was given unbounded constraints.");
+        }
+        let bounded = |max: f64| if max.is_finite() { max } else { UNBOUNDED_SIZE };
+        let size = bc.constrain(Size::new(bounded(bc.max().width), bounded(bc.max().height)));
+
+        self.stacks.clear();
+        compute_stacks(&self.layout, size.to_rect(), &mut self.stacks);
+        for stack in &self.stacks {
+            let Some(selected) = stack.selected else {
+                continue;
+            };
+            let Some(panel) = self
+                .panels
+                .iter_mut()
+                .find(|panel| panel.pod.id() == selected)
+            else {
+                continue;
+            };
+            let content_bc = BoxConstraints::tight(stack.content.size());
+            panel.pod.layout(ctx, &content_bc, env);
+            ctx.place_child(&mut panel.pod, stack.content.origin(), env);
+        }
+
+        trace!("Computed layout: size={}", size);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, env: &Env) {
+        let tab_bar_color = env.get(theme::BACKGROUND_DARK);
+        let selected_color = env.get(theme::BACKGROUND_LIGHT);
+        let text_color = env.get(theme::TEXT_COLOR);
+        let text_size = env.get(theme::TEXT_SIZE_NORMAL);
+
+        for stack in &self.stacks {
+            ctx.fill(stack.tab_bar, &tab_bar_color);
+            for (idx, id) in stack.panels.iter().enumerate() {
+                let rect = tab_rect(stack.tab_bar, idx);
+                if stack.selected == Some(*id) {
+                    ctx.fill(rect, &selected_color);
+                }
+                let Some(panel) = self.panels.iter().find(|panel| panel.pod.id() == *id) else {
+                    continue;
+                };
+                let layout = ctx
+                    .text()
+                    .new_text_layout(panel.title.clone())
+                    .font(FontFamily::SYSTEM_UI, text_size)
+                    .text_color(text_color.clone())
+                    .build();
+                let layout = match layout {
+                    Ok(layout) => layout,
+                    Err(err) => {
+                        warn!("DockArea: can't build text layout: {}", err);
+                        continue;
+                    }
+                };
+                let text_pos = Point::new(
+                    rect.x0 + TAB_PADDING,
+                    rect.y0 + (rect.height() - layout.size().height) / 2.0,
+                );
+                ctx.with_save(|ctx| {
+                    ctx.clip(rect);
+                    ctx.draw_text(&layout, text_pos);
+                });
+            }
+        }
+
+        let panels = self
+            .panels
+            .iter_mut()
+            .map(|panel| &mut panel.pod)
+            .filter(|pod| !pod.state().is_stashed);
+        ctx.paint_children_in_z_order(panels, env);
+
+        if let Some(target) = &self.drop_target {
+            let color = env.get(theme::PRIMARY_LIGHT).with_alpha(0.3);
+            ctx.fill(target.rect, &color);
+        }
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        self.panels.iter().map(|panel| panel.pod.as_dyn()).collect()
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("DockArea")
+    }
+
+    fn save_state(&self) -> Option<Value> {
+        match serde_json::to_string(&self.layout.to_saved()) {
+            Ok(json) => Some(Value::String(json.into())),
+            Err(err) => {
+                warn!("DockArea: can't save layout: {}", err);
+                None
+            }
+        }
+    }
+
+    fn restore_state(&mut self, ctx: &mut LifeCycleCtx, state: &Value, _env: &Env) {
+        let Value::String(json) = state else {
+            return;
+        };
+        let saved: SavedLayout = match serde_json::from_str(json) {
+            Ok(saved) => saved,
+            Err(err) => {
+                warn!("DockArea: can't restore layout: {}", err);
+                return;
+            }
+        };
+        self.layout = DockLayout::from_saved(&saved, &self.panel_ids());
+        self.sanitize_layout();
+        self.update_stashed(|pod, stashed| ctx.set_stashed(pod, stashed));
+        ctx.request_layout();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shell::MouseButton;
+    use crate::testing::{widget_ids, TestHarness};
+    use crate::widget::{Flex, Label};
+
+    fn dock_area() -> (DockArea, [WidgetId; 2]) {
+        let [files, search] = widget_ids();
+        let dock = DockArea::new()
+            .with_panel_id("Files", Label::new("Files"), files)
+            .with_panel_id("Search", Label::new("Search"), search);
+        (dock, [files, search])
+    }
+
+    fn layout(harness: &TestHarness) -> DockLayout {
+        let root = harness.root_widget();
+        root.downcast::<DockArea>().unwrap().layout().clone()
+    }
+
+    fn is_stashed(harness: &TestHarness, id: WidgetId) -> bool {
+        harness.get_widget(id).state().is_stashed
+    }
+
+    #[test]
+    fn clicking_tab_selects_panel() {
+        let (dock, [files, search]) = dock_area();
+        let mut harness = TestHarness::create_with_size(dock, Size::new(400.0, 400.0));
+        assert!(!is_stashed(&harness, files));
+        assert!(is_stashed(&harness, search));
+
+        harness.mouse_move((TAB_WIDTH * 1.5, TAB_HEIGHT / 2.0));
+        harness.mouse_button_press(MouseButton::Left);
+        harness.mouse_button_release(MouseButton::Left);
+
+        assert!(is_stashed(&harness, files));
+        assert!(!is_stashed(&harness, search));
+    }
+
+    #[test]
+    fn drag_tab_to_edge_splits_stack() {
+        let (dock, [files, search]) = dock_area();
+        let mut harness = TestHarness::create_with_size(dock, Size::new(400.0, 400.0));
+
+        harness.mouse_move((TAB_WIDTH * 1.5, TAB_HEIGHT / 2.0));
+        harness.mouse_button_press(MouseButton::Left);
+        harness.mouse_move((390.0, 200.0));
+        harness.mouse_button_release(MouseButton::Left);
+
+        assert_eq!(
+            layout(&harness),
+            DockLayout::Split {
+                axis: Axis::Horizontal,
+                ratio: 0.5,
+                first: Box::new(DockLayout::Tabs {
+                    panels: vec![files],
+                    selected: 0,
+                }),
+                second: Box::new(DockLayout::Tabs {
+                    panels: vec![search],
+                    selected: 0,
+                }),
+            }
+        );
+        assert!(!is_stashed(&harness, files));
+        assert!(!is_stashed(&harness, search));
+        assert_eq!(harness.get_widget(search).state().layout_rect().x0, 202.0);
+    }
+
+    #[test]
+    fn unbounded_dock_area_has_default_size() {
+        let (dock, _) = dock_area();
+        let [dock_id] = widget_ids();
+        let column = Flex::column().with_child_id(dock, dock_id);
+        let harness = TestHarness::create_with_size(column, Size::new(400.0, 400.0));
+
+        let size = harness.get_widget(dock_id).state().layout_rect().size();
+        assert_eq!(size, Size::new(400.0, UNBOUNDED_SIZE));
+    }
+
+    #[test]
+    fn layout_is_restored() {
+        let (dock, [files, search]) = dock_area();
+        let dock = dock.with_docked(search, files, DockSide::Bottom);
+        let mut harness = TestHarness::create_with_size(dock, Size::new(400.0, 400.0));
+        let docked_layout = layout(&harness);
+        let mut ui_state = harness.window().save_ui_state();
+        ui_state.geometry = None;
+
+        harness.edit_root_widget(|mut root, _| {
+            let mut dock = root.downcast::<DockArea>().unwrap();
+            dock.dock_panel(search, files, DockSide::Center);
+        });
+        assert_eq!(
+            layout(&harness),
+            DockLayout::Tabs {
+                panels: vec![files, search],
+                selected: 1,
+            }
+        );
+
+        harness.restore_ui_state(ui_state);
+        assert_eq!(layout(&harness), docked_layout);
+        assert!(!is_stashed(&harness, files));
+        assert!(!is_stashed(&harness, search));
+    }
+}
@@ -0,0 +1,89 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! The elevation of a widget: how high it floats above its siblings.
+
+use crate::kurbo::{Insets, Rect, Vec2};
+use crate::widget::InteractionState;
+use crate::{theme, Env, PaintCtx, RenderContext};
+
+/// How far the shadow is moved down, per level of elevation.
+const OFFSET_PER_LEVEL: f64 = 0.5;
+/// How blurry the shadow is, per level of elevation.
+const BLUR_PER_LEVEL: f64 = 1.5;
+/// How far a blurred shadow extends beyond its rect, in multiples of the blur radius.
+const BLUR_EXTENT: f64 = 2.5;
+
+/// The elevation levels of a widget, and its animated hover and press state.
+///
+/// See [`WidgetPod::with_elevation`](crate::WidgetPod::with_elevation).
+#[derive(Clone, Debug)]
+pub(crate) struct Elevation {
+    resting: f64,
+    hovered: f64,
+    pressed: f64,
+    pub(crate) interaction: InteractionState,
+}
+
+impl Elevation {
+    pub(crate) fn new(resting: f64, hovered: f64, pressed: f64) -> Self {
+        Elevation {
+            resting: resting.max(0.0),
+            hovered: hovered.max(0.0),
+            pressed: pressed.max(0.0),
+            interaction: InteractionState::new(),
+        }
+    }
+
+    /// The level when the widget isn't hovered or pressed, which sets its z-order.
+    pub(crate) fn resting(&self) -> f64 {
+        self.resting
+    }
+
+    /// The current level, between the resting, hovered and pressed ones.
+    pub(crate) fn level(&self) -> f64 {
+        self.interaction
+            .value(self.resting, self.hovered, self.pressed)
+    }
+
+    /// How far the shadow of a widget can extend beyond its border box.
+    pub(crate) fn shadow_insets(&self) -> Insets {
+        let level = self.resting.max(self.hovered).max(self.pressed);
+        let (offset, blur) = shadow_geometry(level);
+        let extent = blur * BLUR_EXTENT;
+        Insets::new(extent, extent - offset.y, extent, extent + offset.y)
+    }
+
+    /// Paint the shadow of a widget's border box, at the current level.
+    pub(crate) fn paint_shadow(&self, ctx: &mut PaintCtx, border_box: Rect, env: &Env) {
+        let level = self.level();
+        if level <= 0.0 {
+            return;
+        }
+        let (offset, blur) = shadow_geometry(level);
+        ctx.blurred_rect(border_box + offset, blur, &env.get(theme::SHADOW_COLOR));
+    }
+}
+
+/// The offset and blur radius of the shadow at `level`.
+fn shadow_geometry(level: f64) -> (Vec2, f64) {
+    (
+        Vec2::new(0.0, level * OFFSET_PER_LEVEL),
+        level * BLUR_PER_LEVEL,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shadow_insets_cover_highest_level() {
+        let elevation = Elevation::new(1.0, 2.0, 4.0);
+        let insets = elevation.shadow_insets();
+        // At level 4, the shadow is 2 points lower and blurred by 6 points.
+        assert_eq!(insets, Insets::new(15.0, 13.0, 15.0, 17.0));
+        assert_eq!(elevation.level(), 1.0);
+    }
+}
@@ -462,6 +462,30 @@ impl<'a, 'b> FlexMut<'a, 'b> {
         self.ctx.widget_state.needs_layout = true;
     }
 
+    /// Remove the child at `idx` and return it, to move it to another container.
+    ///
+    /// The child keeps its state, including focus; see [`WidgetPod::take`].
+    /// Returns `None` and leaves the flex unchanged if `idx` is a spacer.
+    pub fn take_child(&mut self, idx: usize) -> Option<WidgetPod<Box<dyn Widget>>> {
+        self.widget.children[idx].widget_mut()?;
+        let widget = match self.widget.children.remove(idx) {
+            Child::Fixed { widget, .. } | Child::Flex { widget, .. } => widget,
+            Child::FixedSpacer(..) | Child::FlexedSpacer(..) => unreachable!(),
+        };
+        Some(widget.take(&mut self.ctx))
+    }
+
+    /// Add a non-flex child which was taken from another container.
+    ///
+    /// See [`WidgetPod::adopt`].
+    pub fn adopt_child(&mut self, child: WidgetPod<Box<dyn Widget>>) {
+        let child = Child::Fixed {
+            widget: child.adopt(&mut self.ctx),
+            alignment: None,
+        };
+        self.widget.children.push(child);
+    }
+
     // FIXME - Remove Box
     pub fn child_mut(&mut self, idx: usize) -> Option<WidgetMut<'_, 'b, Box<dyn Widget>>> {
         let child = match &mut self.widget.children[idx] {
@@ -481,17 +505,15 @@ impl<'a, 'b> FlexMut<'a, 'b> {
 
 impl Widget for Flex {
     fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, env: &Env) {
-        for child in self.children.iter_mut().filter_map(|x| x.widget_mut()) {
-            child.on_event(ctx, event, env);
-        }
+        let children = self.children.iter_mut().filter_map(|x| x.widget_mut());
+        ctx.visit_children(children, |child, ctx| child.on_event(ctx, event, env));
     }
 
     fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange, _env: &Env) {}
 
     fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, env: &Env) {
-        for child in self.children.iter_mut().filter_map(|x| x.widget_mut()) {
-            child.lifecycle(ctx, event, env);
-        }
+        let children = self.children.iter_mut().filter_map(|x| x.widget_mut());
+        ctx.visit_children(children, |child, ctx| child.lifecycle(ctx, event, env));
     }
 
     fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, env: &Env) -> Size {
@@ -694,9 +716,8 @@ impl Widget for Flex {
     }
 
     fn paint(&mut self, ctx: &mut PaintCtx, env: &Env) {
-        for child in self.children.iter_mut().filter_map(|x| x.widget_mut()) {
-            child.paint(ctx, env);
-        }
+        let children = self.children.iter_mut().filter_map(|x| x.widget_mut());
+        ctx.paint_children_in_z_order(children, env);
 
         // paint the baseline if we're debugging layout
         if env.get(Env::DEBUG_PAINT) && ctx.widget_state.baseline_offset != 0.0 {
@@ -841,7 +862,7 @@ impl CrossAxisAlignment {
     /// Given the difference between the size of the container and the size
     /// of the child (on their minor axis) return the necessary offset for
     /// this alignment.
-    fn align(self, val: f64) -> f64 {
+    pub(super) fn align(self, val: f64) -> f64 {
         match self {
             CrossAxisAlignment::Start => 0.0,
             // in vertical layout, baseline is equivalent to center
@@ -870,7 +891,7 @@ impl Data for MainAxisAlignment {
     }
 }
 
-struct Spacing {
+pub(super) struct Spacing {
     alignment: MainAxisAlignment,
     extra: f64,
     n_children: usize,
@@ -884,7 +905,7 @@ impl Spacing {
     /// this returns an iterator of `f64` spacing,
     /// where the first element is the spacing before any children
     /// and all subsequent elements are the spacing after children.
-    fn new(alignment: MainAxisAlignment, extra: f64, n_children: usize) -> Spacing {
+    pub(super) fn new(alignment: MainAxisAlignment, extra: f64, n_children: usize) -> Spacing {
         let extra = if extra.is_finite() { extra } else { 0. };
         let equal_space = if n_children > 0 {
             match alignment {
@@ -0,0 +1,355 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! A widget that arranges its children in rows, wrapping them like words.
+
+use smallvec::SmallVec;
+use tracing::{trace, trace_span, Span};
+
+use crate::widget::flex::Spacing;
+use crate::widget::{CrossAxisAlignment, MainAxisAlignment, WidgetMut, WidgetRef};
+use crate::{
+    theme, BoxConstraints, Env, Event, EventCtx, KeyOrValue, LayoutCtx, LifeCycle, LifeCycleCtx,
+    PaintCtx, Point, Size, StatusChange, Widget, WidgetId, WidgetPod,
+};
+
+/// A container which lays out its children from left to right, and starts a new
+/// row when the next child doesn't fit, eg for tags or chips.
+///
+/// Each row is aligned horizontally with a [`MainAxisAlignment`], and its children
+/// are aligned vertically with a [`CrossAxisAlignment`]; with
+/// [`CrossAxisAlignment::Baseline`], the text of the children of each row lines up.
+/// The baseline of the flow is the baseline of its last row.
+///
+/// Children are never wider than the flow; a child which doesn't fit next to the
+/// others gets its own row.
+pub struct Flow {
+    children: Vec<WidgetPod<Box<dyn Widget>>>,
+    spacing: KeyOrValue<f64>,
+    row_spacing: KeyOrValue<f64>,
+    main_alignment: MainAxisAlignment,
+    cross_alignment: CrossAxisAlignment,
+}
+
+crate::declare_widget!(FlowMut, Flow);
+
+/// A row of children, computed during layout.
+struct Row {
+    /// The range of children in the row.
+    start: usize,
+    end: usize,
+    width: f64,
+    height: f64,
+    /// The height of the tallest child above its baseline.
+    above_baseline: f64,
+}
+
+// --- METHODS ---
+
+impl Flow {
+    /// Create an empty flow.
+    ///
+    /// Children are separated by [`theme::WIDGET_PADDING_HORIZONTAL`], and rows by
+    /// [`theme::WIDGET_PADDING_VERTICAL`].
+    pub fn new() -> Self {
+        Flow {
+            children: Vec::new(),
+            spacing: theme::WIDGET_PADDING_HORIZONTAL.into(),
+            row_spacing: theme::WIDGET_PADDING_VERTICAL.into(),
+            main_alignment: MainAxisAlignment::Start,
+            cross_alignment: CrossAxisAlignment::Baseline,
+        }
+    }
+
+    /// Builder-style method to add a child.
+    pub fn with_child(mut self, child: impl Widget) -> Self {
+        self.children.push(WidgetPod::new(child).boxed());
+        self
+    }
+
+    /// Builder-style variant of `with_child`, that takes the id that the child will have.
+    ///
+    /// Useful for unit tests.
+    pub fn with_child_id(mut self, child: impl Widget, id: WidgetId) -> Self {
+        self.children
+            .push(WidgetPod::new_with_id(child, id).boxed());
+        self
+    }
+
+    /// Builder-style method to set the horizontal space between children.
+    pub fn with_spacing(mut self, spacing: impl Into<KeyOrValue<f64>>) -> Self {
+        self.spacing = spacing.into();
+        self
+    }
+
+    /// Builder-style method to set the vertical space between rows.
+    pub fn with_row_spacing(mut self, spacing: impl Into<KeyOrValue<f64>>) -> Self {
+        self.row_spacing = spacing.into();
+        self
+    }
+
+    /// Builder-style method to set how each row is aligned horizontally.
+    ///
+    /// With any alignment but [`MainAxisAlignment::Start`], the flow takes all the
+    /// width it's given.
+    pub fn main_axis_alignment(mut self, alignment: MainAxisAlignment) -> Self {
+        self.main_alignment = alignment;
+        self
+    }
+
+    /// Builder-style method to set how the children of a row are aligned vertically.
+    ///
+    /// The default is [`CrossAxisAlignment::Baseline`].
+    pub fn cross_axis_alignment(mut self, alignment: CrossAxisAlignment) -> Self {
+        self.cross_alignment = alignment;
+        self
+    }
+
+    /// Split the children into rows no wider than `max_width`.
+    fn break_rows(&self, max_width: f64, spacing: f64) -> Vec<Row> {
+        let mut rows: Vec<Row> = Vec::new();
+        for (idx, child) in self.children.iter().enumerate() {
+            let size = child.layout_rect().size();
+            let above_baseline = size.height - child.baseline_offset();
+            match rows.last_mut() {
+                Some(row) if row.width + spacing + size.width <= max_width => {
+                    row.end = idx + 1;
+                    row.width += spacing + size.width;
+                    row.height = row.height.max(size.height);
+                    row.above_baseline = row.above_baseline.max(above_baseline);
+                }
+                _ => rows.push(Row {
+                    start: idx,
+                    end: idx + 1,
+                    width: size.width,
+                    height: size.height,
+                    above_baseline,
+                }),
+            }
+        }
+
+        if self.cross_alignment == CrossAxisAlignment::Baseline {
+            for row in &mut rows {
+                let below_baseline = self.children[row.start..row.end]
+                    .iter()
+                    .map(|child| child.baseline_offset())
+                    .fold(0.0, f64::max);
+                row.height = row.height.max(row.above_baseline + below_baseline);
+            }
+        }
+        rows
+    }
+}
+
+impl Default for Flow {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, 'b> FlowMut<'a, 'b> {
+    /// Add a child at the end of the flow.
+    pub fn add_child(&mut self, child: impl Widget) {
+        self.widget.children.push(WidgetPod::new(child).boxed());
+        self.ctx.children_changed();
+    }
+
+    /// Add a child with a given id at the end of the flow.
+    pub fn add_child_id(&mut self, child: impl Widget, id: WidgetId) {
+        self.widget
+            .children
+            .push(WidgetPod::new_with_id(child, id).boxed());
+        self.ctx.children_changed();
+    }
+
+    /// Insert a child before the child at `idx`.
+    pub fn insert_child(&mut self, idx: usize, child: impl Widget) {
+        self.widget
+            .children
+            .insert(idx, WidgetPod::new(child).boxed());
+        self.ctx.children_changed();
+    }
+
+    /// Remove the child at `idx`.
+    pub fn remove_child(&mut self, idx: usize) {
+        self.widget.children.remove(idx);
+        self.ctx.children_changed();
+    }
+
+    /// Get a [`WidgetMut`] to the child at `idx`, if there is one.
+    pub fn child_mut(&mut self, idx: usize) -> Option<WidgetMut<'_, 'b, Box<dyn Widget>>> {
+        let child = self.widget.children.get_mut(idx)?;
+        Some(self.ctx.get_mut(child))
+    }
+
+    /// Remove every child.
+    pub fn clear(&mut self) {
+        self.widget.children.clear();
+        self.ctx.children_changed();
+    }
+
+    /// Set the horizontal space between children.
+    pub fn set_spacing(&mut self, spacing: impl Into<KeyOrValue<f64>>) {
+        self.widget.spacing = spacing.into();
+        self.ctx.request_layout();
+    }
+
+    /// Set the vertical space between rows.
+    pub fn set_row_spacing(&mut self, spacing: impl Into<KeyOrValue<f64>>) {
+        self.widget.row_spacing = spacing.into();
+        self.ctx.request_layout();
+    }
+
+    /// Set how each row is aligned horizontally.
+    pub fn set_main_axis_alignment(&mut self, alignment: MainAxisAlignment) {
+        self.widget.main_alignment = alignment;
+        self.ctx.request_layout();
+    }
+
+    /// Set how the children of a row are aligned vertically.
+    pub fn set_cross_axis_alignment(&mut self, alignment: CrossAxisAlignment) {
+        self.widget.cross_alignment = alignment;
+        self.ctx.request_layout();
+    }
+}
+
+// --- TRAIT IMPLS ---
+
+impl Widget for Flow {
+    fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, env: &Env) {
+        ctx.visit_children(self.children.iter_mut(), |child, ctx| {
+            child.on_event(ctx, event, env)
+        });
+    }
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange, _env: &Env) {}
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, env: &Env) {
+        ctx.visit_children(self.children.iter_mut(), |child, ctx| {
+            child.lifecycle(ctx, event, env)
+        });
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, env: &Env) -> Size {
+        let spacing = self.spacing.resolve(env).max(0.0);
+        let row_spacing = self.row_spacing.resolve(env).max(0.0);
+        let max_width = bc.max().width;
+
+        let child_bc = BoxConstraints::new(Size::ZERO, Size::new(max_width, f64::INFINITY));
+        for child in &mut self.children {
+            child.layout(ctx, &child_bc, env);
+        }
+        let rows = self.break_rows(max_width, spacing);
+
+        let widest_row = rows.iter().map(|row| row.width).fold(0.0, f64::max);
+        let width = if max_width.is_finite() && self.main_alignment != MainAxisAlignment::Start {
+            max_width
+        } else {
+            widest_row
+        };
+
+        let mut y = 0.0;
+        for row in &rows {
+            let mut spacing_iter =
+                Spacing::new(self.main_alignment, width - row.width, row.end - row.start);
+            let mut x = spacing_iter.next().unwrap_or(0.0);
+            for child in &mut self.children[row.start..row.end] {
+                let size = child.layout_rect().size();
+                let y_offset = match self.cross_alignment {
+                    CrossAxisAlignment::Baseline => {
+                        row.above_baseline - (size.height - child.baseline_offset())
+                    }
+                    CrossAxisAlignment::Fill => {
+                        let fill_bc = BoxConstraints::tight(Size::new(size.width, row.height));
+                        child.layout(ctx, &fill_bc, env);
+                        0.0
+                    }
+                    alignment => alignment.align(row.height - size.height),
+                };
+                ctx.place_child(child, Point::new(x, y + y_offset), env);
+                x += size.width + spacing + spacing_iter.next().unwrap_or(0.0);
+            }
+            y += row.height + row_spacing;
+        }
+        let height = if rows.is_empty() {
+            0.0
+        } else {
+            y - row_spacing
+        };
+        let size = bc.constrain(Size::new(width, height));
+
+        // Like a column, the flow's baseline is the baseline of its last row.
+        let baseline_offset = rows
+            .last()
+            .map(|row| {
+                let child = &self.children[row.start];
+                child.baseline_offset() + size.height - child.layout_rect().max_y()
+            })
+            .unwrap_or(0.0);
+        ctx.set_baseline_offset(baseline_offset);
+
+        trace!(
+            "Computed layout: size={}, rows={}, baseline_offset={}",
+            size,
+            rows.len(),
+            baseline_offset
+        );
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, env: &Env) {
+        ctx.paint_children_in_z_order(&mut self.children, env);
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        self.children.iter().map(|child| child.as_dyn()).collect()
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("Flow")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{widget_ids, TestHarness};
+    use crate::widget::{Label, SizedBox};
+
+    fn chip() -> SizedBox {
+        SizedBox::empty().width(40.0).height(20.0)
+    }
+
+    #[test]
+    fn wrap_children_into_rows() {
+        let [a, b, c] = widget_ids();
+        let flow = Flow::new()
+            .with_spacing(10.0)
+            .with_row_spacing(5.0)
+            .with_child_id(chip(), a)
+            .with_child_id(chip(), b)
+            .with_child_id(chip(), c);
+        let harness = TestHarness::create_with_size(flow, Size::new(100.0, 400.0));
+
+        let origin = |id| harness.get_widget(id).state().layout_rect().origin();
+        assert_eq!(origin(a), Point::new(0.0, 0.0));
+        assert_eq!(origin(b), Point::new(50.0, 0.0));
+        assert_eq!(origin(c), Point::new(0.0, 25.0));
+    }
+
+    #[test]
+    fn align_rows_on_baseline() {
+        let [small, large] = widget_ids();
+        let flow = Flow::new()
+            .with_child_id(Label::new("small").with_text_size(10.0), small)
+            .with_child_id(Label::new("large").with_text_size(30.0), large);
+        let harness = TestHarness::create_with_size(flow, Size::new(400.0, 400.0));
+
+        let baseline_y = |id| {
+            let widget = harness.get_widget(id);
+            widget.state().layout_rect().max_y() - widget.state().baseline_offset
+        };
+        assert_eq!(baseline_y(small), baseline_y(large));
+    }
+}
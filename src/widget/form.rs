@@ -0,0 +1,298 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! A container that aggregates the validation state of its fields.
+
+use std::collections::HashMap;
+
+use smallvec::{smallvec, SmallVec};
+use tracing::{trace, trace_span, Span};
+
+use crate::action::Action;
+use crate::widget::{WidgetId, WidgetMut, WidgetPod, WidgetRef};
+use crate::{
+    ArcStr, BoxConstraints, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx,
+    Point, Selector, Size, StatusChange, Value, Widget,
+};
+
+/// The name of a field in a [`Form`].
+pub type FieldId = ArcStr;
+
+/// The current value and validity of a form field.
+///
+/// Input widgets inside a [`Form`] send this as the payload of a
+/// [`FIELD_UPDATED`] notification whenever their value changes.
+#[derive(Clone, Debug)]
+pub struct FieldUpdate {
+    /// The name of the field.
+    pub id: FieldId,
+    /// The current value of the field.
+    pub value: Value,
+    /// Whether the current value passes the field's validation.
+    pub is_valid: bool,
+}
+
+/// Notification sent by input widgets to report their value to the enclosing [`Form`].
+pub const FIELD_UPDATED: Selector<FieldUpdate> =
+    Selector::new("masonry-builtin.form-field-updated");
+
+/// Ask a [`Form`] to submit its values.
+///
+/// This can either be sent as a command targeting the form, or as a
+/// notification from one of its descendants (eg a "Submit" button).
+pub const SUBMIT_FORM: Selector = Selector::new("masonry-builtin.form-submit");
+
+struct FieldRecord {
+    widget_id: WidgetId,
+    value: Value,
+    is_valid: bool,
+}
+
+/// A container that collects the values of the input widgets it contains.
+///
+/// Fields report their state with [`FIELD_UPDATED`] notifications. When the form
+/// is asked to submit (see [`SUBMIT_FORM`]), it either emits a single
+/// [`Action::FormSubmitted`] with every field value, or, if some fields are
+/// invalid, gives focus to the first of them.
+///
+/// Fields are ordered by when they first reported to the form.
+pub struct Form {
+    child: WidgetPod<Box<dyn Widget>>,
+    field_order: Vec<FieldId>,
+    fields: HashMap<FieldId, FieldRecord>,
+}
+
+crate::declare_widget!(FormMut, Form);
+
+impl Form {
+    /// Create a new form around the given widget.
+    pub fn new(child: impl Widget) -> Self {
+        Form {
+            child: WidgetPod::new(child).boxed(),
+            field_order: Vec::new(),
+            fields: HashMap::new(),
+        }
+    }
+
+    /// Whether every field reported so far is valid.
+    pub fn is_valid(&self) -> bool {
+        self.fields.values().all(|field| field.is_valid)
+    }
+
+    /// The ids of the fields that are currently invalid, in order.
+    pub fn invalid_fields(&self) -> Vec<FieldId> {
+        self.field_order
+            .iter()
+            .filter(|id| !self.fields[*id].is_valid)
+            .cloned()
+            .collect()
+    }
+
+    /// The current value of the given field, if it has reported one.
+    pub fn value(&self, id: &str) -> Option<&Value> {
+        self.fields.get(id).map(|field| &field.value)
+    }
+
+    fn update_field(&mut self, source: WidgetId, update: &FieldUpdate) {
+        if !self.fields.contains_key(&update.id) {
+            self.field_order.push(update.id.clone());
+        }
+        self.fields.insert(
+            update.id.clone(),
+            FieldRecord {
+                widget_id: source,
+                value: update.value.clone(),
+                is_valid: update.is_valid,
+            },
+        );
+    }
+
+    fn submit(&mut self, ctx: &mut EventCtx) {
+        let first_invalid = self
+            .field_order
+            .iter()
+            .map(|id| &self.fields[id])
+            .find(|field| !field.is_valid);
+
+        if let Some(field) = first_invalid {
+            trace!(
+                "Form {:?} has invalid fields, not submitting",
+                ctx.widget_id()
+            );
+            ctx.set_focus(field.widget_id);
+            return;
+        }
+
+        let values = self
+            .fields
+            .iter()
+            .map(|(id, field)| (id.clone(), field.value.clone()))
+            .collect();
+        ctx.submit_action(Action::FormSubmitted(values));
+    }
+}
+
+impl<'a, 'b> FormMut<'a, 'b> {
+    /// Get a mutable reference to the content of the form.
+    pub fn child_mut(&mut self) -> WidgetMut<'_, 'b, Box<dyn Widget>> {
+        self.ctx.get_mut(&mut self.widget.child)
+    }
+
+    /// Submit the form, as if it had received [`SUBMIT_FORM`].
+    ///
+    /// Unlike the selector, this can't move focus to an invalid field, so it
+    /// only submits if every field is valid. Returns whether the form was submitted.
+    pub fn submit(&mut self) -> bool {
+        if !self.widget.is_valid() {
+            return false;
+        }
+        let values = self
+            .widget
+            .fields
+            .iter()
+            .map(|(id, field)| (id.clone(), field.value.clone()))
+            .collect();
+        self.ctx.submit_action(Action::FormSubmitted(values));
+        true
+    }
+}
+
+impl Widget for Form {
+    fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, env: &Env) {
+        self.child.on_event(ctx, event, env);
+
+        match event {
+            Event::Notification(notification) => {
+                if let Some(update) = notification.try_get(FIELD_UPDATED) {
+                    self.update_field(notification.source(), update);
+                    ctx.set_handled();
+                } else if notification.is(SUBMIT_FORM) {
+                    self.submit(ctx);
+                    ctx.set_handled();
+                }
+            }
+            Event::Command(cmd) if cmd.is(SUBMIT_FORM) => {
+                self.submit(ctx);
+                ctx.set_handled();
+            }
+            _ => (),
+        }
+    }
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange, _env: &Env) {}
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, env: &Env) {
+        if let LifeCycle::WidgetAdded = event {
+            ctx.register_for_commands();
+        }
+        self.child.lifecycle(ctx, event, env);
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, env: &Env) -> Size {
+        let size = self.child.layout(ctx, bc, env);
+        ctx.place_child(&mut self.child, Point::ORIGIN, env);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, env: &Env) {
+        self.child.paint(ctx, env);
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        smallvec![self.child.as_dyn()]
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("Form")
+    }
+
+    fn get_debug_text(&self) -> Option<String> {
+        Some(format!("valid: {}", self.is_valid()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{widget_ids, ModularWidget, TestHarness};
+    use crate::widget::Flex;
+    use crate::Data;
+
+    const SET_VALUE: Selector<(String, bool)> = Selector::new("masonry-test.set-value");
+
+    /// A field that reports its value on startup and whenever it receives `SET_VALUE`.
+    fn field(name: &'static str, value: &str, is_valid: bool) -> impl Widget {
+        ModularWidget::new((value.to_string(), is_valid))
+            .event_fn(move |state, ctx, event, _| {
+                let mut changed = matches!(event, Event::WindowConnected);
+                if let Event::Command(cmd) = event {
+                    if let Some(new_state) = cmd.try_get(SET_VALUE) {
+                        *state = new_state.clone();
+                        changed = true;
+                    }
+                }
+                if changed {
+                    ctx.submit_notification(FIELD_UPDATED.with(FieldUpdate {
+                        id: name.into(),
+                        value: Value::String(state.0.as_str().into()),
+                        is_valid: state.1,
+                    }));
+                }
+            })
+            .lifecycle_fn(|_, ctx, event, _| {
+                if let LifeCycle::BuildFocusChain = event {
+                    ctx.register_for_focus();
+                }
+            })
+    }
+
+    #[test]
+    fn submit_valid_form() {
+        let form = Form::new(
+            Flex::column()
+                .with_child(field("name", "Alice", true))
+                .with_child(field("email", "alice@example.com", true)),
+        );
+        let mut harness = TestHarness::create(form);
+        let form_id = harness.root_widget().id();
+
+        harness.submit_command(SUBMIT_FORM);
+
+        let (action, widget_id) = harness.pop_action().unwrap();
+        assert_eq!(widget_id, form_id);
+        let Action::FormSubmitted(values) = action else {
+            panic!("expected FormSubmitted, got {:?}", action);
+        };
+        assert_eq!(values.len(), 2);
+        assert!(values["name"].same(&Value::String("Alice".into())));
+    }
+
+    #[test]
+    fn focus_first_invalid_field() {
+        let [name_id, email_id, age_id] = widget_ids();
+        let form = Form::new(
+            Flex::column()
+                .with_child_id(field("name", "Alice", true), name_id)
+                .with_child_id(field("email", "", false), email_id)
+                .with_child_id(field("age", "", false), age_id),
+        );
+        let mut harness = TestHarness::create(form);
+
+        harness.submit_command(SUBMIT_FORM);
+        assert_eq!(harness.pop_action(), None);
+        assert_eq!(harness.window().focus, Some(email_id));
+
+        harness.submit_command(SET_VALUE.with(("a@b.c".into(), true)).to(email_id));
+        harness.submit_command(SUBMIT_FORM);
+        assert_eq!(harness.pop_action(), None);
+        assert_eq!(harness.window().focus, Some(age_id));
+
+        harness.submit_command(SET_VALUE.with(("42".into(), true)).to(age_id));
+        harness.submit_command(SUBMIT_FORM);
+        assert!(matches!(
+            harness.pop_action(),
+            Some((Action::FormSubmitted(values), _)) if values.len() == 3
+        ));
+    }
+}
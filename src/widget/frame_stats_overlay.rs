@@ -0,0 +1,142 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! A wrapper displaying frame statistics on top of its child.
+
+use std::time::Duration;
+
+use druid_shell::TimerToken;
+use smallvec::SmallVec;
+use tracing::{trace_span, warn, Span};
+
+use crate::piet::{FontFamily, Text, TextLayoutBuilder};
+use crate::widget::{StoreInWidgetMut, WidgetPod, WidgetRef};
+use crate::{
+    BoxConstraints, Color, Env, Event, EventCtx, FrameStats, LayoutCtx, LifeCycle, LifeCycleCtx,
+    PaintCtx, Point, Rect, RenderContext, Size, StatusChange, Widget,
+};
+
+/// How often the displayed stats are updated.
+const REFRESH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The box the stats are displayed in, relative to the overlay's origin.
+const STATS_RECT: Rect = Rect::new(0.0, 0.0, 360.0, 18.0);
+
+/// A wrapper which displays the window's [`FrameStats`] in its top-left corner.
+///
+/// This is meant to diagnose performance issues: wrap the root widget of a window in
+/// it to see the frame rate, the time spent in each pass, and how much of the window
+/// is repainted.
+///
+/// The stats are refreshed twice per second; only the stats box is repainted.
+#[derive(StoreInWidgetMut)]
+pub struct FrameStatsOverlay<W: Widget> {
+    #[widget_mut(child)]
+    child: WidgetPod<W>,
+    refresh_timer: TimerToken,
+}
+
+impl<W: Widget> FrameStatsOverlay<W> {
+    /// Create a new overlay on top of `child`.
+    pub fn new(child: W) -> Self {
+        FrameStatsOverlay {
+            child: WidgetPod::new(child),
+            refresh_timer: TimerToken::INVALID,
+        }
+    }
+}
+
+fn stats_text(stats: &FrameStats) -> String {
+    let timings = stats.last_frame;
+    format!(
+        "{:.1} fps | {:.1} ms (anim {:.1}, layout {:.1}, paint {:.1}) | {} dropped | {:.0}% damage",
+        stats.fps,
+        timings.total().as_secs_f64() * 1000.0,
+        timings.anim_frame.as_secs_f64() * 1000.0,
+        timings.layout.as_secs_f64() * 1000.0,
+        timings.paint.as_secs_f64() * 1000.0,
+        stats.dropped_frames,
+        stats.damage_fraction() * 100.0,
+    )
+}
+
+impl<W: Widget> Widget for FrameStatsOverlay<W> {
+    fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, env: &Env) {
+        if let Event::Timer(timer) = event {
+            if timer.token == self.refresh_timer {
+                ctx.request_paint_rect(STATS_RECT);
+                self.refresh_timer = ctx.request_timer(REFRESH_INTERVAL);
+                ctx.skip_child(&mut self.child);
+                return;
+            }
+        }
+        self.child.on_event(ctx, event, env);
+    }
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange, _env: &Env) {}
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, env: &Env) {
+        if let LifeCycle::WidgetAdded = event {
+            self.refresh_timer = ctx.request_timer(REFRESH_INTERVAL);
+        }
+        self.child.lifecycle(ctx, event, env);
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, env: &Env) -> Size {
+        let size = self.child.layout(ctx, bc, env);
+        ctx.place_child(&mut self.child, Point::ZERO, env);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, env: &Env) {
+        self.child.paint(ctx, env);
+
+        let text = stats_text(&ctx.frame_stats());
+        let layout = ctx
+            .text()
+            .new_text_layout(text)
+            .font(FontFamily::MONOSPACE, 11.0)
+            .text_color(Color::WHITE)
+            .build();
+        let layout = match layout {
+            Ok(layout) => layout,
+            Err(err) => {
+                warn!("FrameStatsOverlay: can't build text layout: {}", err);
+                return;
+            }
+        };
+        ctx.with_save(|ctx| {
+            ctx.clip(STATS_RECT);
+            ctx.fill(STATS_RECT, &Color::rgba8(0, 0, 0, 180));
+            ctx.draw_text(&layout, Point::new(4.0, 2.0));
+        });
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        self.pod_children()
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("FrameStatsOverlay")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestHarness;
+    use crate::widget::Label;
+
+    #[test]
+    fn refresh_repaints_stats_box() {
+        let widget = FrameStatsOverlay::new(Label::new("Hello"));
+
+        let mut harness = TestHarness::create(widget);
+        harness.render();
+        assert!(harness.window().invalid().is_empty());
+
+        harness.move_timers_forward(REFRESH_INTERVAL);
+        assert_eq!(harness.window().invalid().rects(), &[STATS_RECT]);
+    }
+}
@@ -5,15 +5,17 @@
 //! An Image widget.
 //! Please consider using SVG and the SVG widget as it scales much better.
 
+use std::time::Duration;
+
 use smallvec::SmallVec;
 use tracing::{trace, trace_span, Span};
 
 use crate::kurbo::Rect;
 use crate::piet::{Image as _, ImageBuf, InterpolationMode, PietImage};
-use crate::widget::{FillStrat, WidgetRef};
+use crate::widget::{FillStrat, MemoryUsage, WidgetMut, WidgetRef};
 use crate::{
-    BoxConstraints, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx,
-    RenderContext, Size, StatusChange, Widget,
+    AnimatedImage, BoxConstraints, Env, Event, EventCtx, ImageSource, LayoutCtx, LifeCycle,
+    LifeCycleCtx, PaintCtx, RenderContext, Size, StatusChange, Widget, IMAGE_LOADED,
 };
 
 /// A widget that renders a bitmap Image.
@@ -23,6 +25,24 @@ pub struct Image {
     fill: FillStrat,
     interpolation: InterpolationMode,
     clip_area: Option<Rect>,
+    /// Set if the image is loaded from the app's image cache.
+    source: Option<ImageSource>,
+    /// Set if the image has more than one frame.
+    animation: Option<Animation>,
+}
+
+struct Animation {
+    image: AnimatedImage,
+    frame: usize,
+    /// How long the current frame has been shown.
+    elapsed: Duration,
+    /// Whether the animation was paused with [`ImageMut::set_playing`].
+    playing: bool,
+    /// Whether the widget was visible in the last layout pass.
+    on_screen: bool,
+    /// Set when the widget stops requesting animation frames because it isn't
+    /// visible, or the user prefers reduced motion.
+    suspended: bool,
 }
 
 crate::declare_widget!(ImageMut, Image);
@@ -42,6 +62,31 @@ impl Image {
             fill: FillStrat::default(),
             interpolation: InterpolationMode::Bilinear,
             clip_area: None,
+            source: None,
+            animation: None,
+        }
+    }
+
+    /// Create an image drawing widget which plays the given animation in a loop.
+    ///
+    /// The animation is paused while the widget is offscreen or stashed, and while
+    /// [`reduced_motion`](EventCtx::reduced_motion) is set.
+    pub fn animated(image: AnimatedImage) -> Self {
+        let mut widget = Image::new(ImageBuf::empty());
+        widget.set_animated_image(image);
+        widget
+    }
+
+    /// Create an image drawing widget which loads its image through the app's
+    /// [`ImageCache`](crate::ImageCache).
+    ///
+    /// The widget is empty until the image is decoded. The variant of `source`
+    /// matching the window's scale factor is used. Animated images are played as
+    /// with [`Image::animated`].
+    pub fn from_source(source: ImageSource) -> Self {
+        Image {
+            source: Some(source),
+            ..Image::new(ImageBuf::empty())
         }
     }
 
@@ -67,6 +112,48 @@ impl Image {
         self.clip_area = clip_area;
         self
     }
+
+    /// Show the first frame of `image`, and return whether it needs animation frames.
+    fn set_animated_image(&mut self, image: AnimatedImage) -> bool {
+        self.image_data = image.first_frame().clone();
+        self.paint_data = None;
+        self.animation = image.is_animated().then(|| Animation {
+            image,
+            frame: 0,
+            elapsed: Duration::ZERO,
+            playing: true,
+            on_screen: true,
+            suspended: false,
+        });
+        self.animation.is_some()
+    }
+
+    /// Advance the animation by `interval`, and return whether the frame changed.
+    fn advance_animation(&mut self, interval: Duration) -> bool {
+        let Some(animation) = &mut self.animation else {
+            return false;
+        };
+        let frames = animation.image.frames();
+        let total: Duration = frames.iter().map(|frame| frame.delay).sum();
+        if total.is_zero() {
+            return false;
+        }
+
+        let start_frame = animation.frame;
+        // Skip whole loops at once, eg after a long stall.
+        let elapsed = (animation.elapsed + interval).as_nanos() % total.as_nanos();
+        animation.elapsed = Duration::from_nanos(elapsed as u64);
+        while animation.elapsed >= frames[animation.frame].delay {
+            animation.elapsed -= frames[animation.frame].delay;
+            animation.frame = (animation.frame + 1) % frames.len();
+        }
+        if animation.frame == start_frame {
+            return false;
+        }
+        self.image_data = frames[animation.frame].image.clone();
+        self.paint_data = None;
+        true
+    }
 }
 
 impl<'a, 'b> ImageMut<'a, 'b> {
@@ -98,18 +185,103 @@ impl<'a, 'b> ImageMut<'a, 'b> {
     pub fn set_image_data(&mut self, image_data: ImageBuf) {
         self.widget.image_data = image_data;
         self.widget.paint_data = None;
+        self.widget.source = None;
+        self.widget.animation = None;
         self.ctx.request_layout();
     }
+
+    /// Set a new animation, played from its first frame.
+    pub fn set_animated_image(&mut self, image: AnimatedImage) {
+        self.widget.source = None;
+        if self.widget.set_animated_image(image) {
+            self.ctx.request_anim_frame();
+        }
+        self.ctx.request_layout();
+    }
+
+    /// Pause or resume the animation, if the image is animated.
+    pub fn set_playing(&mut self, playing: bool) {
+        let Some(animation) = &mut self.widget.animation else {
+            return;
+        };
+        animation.playing = playing;
+        if playing {
+            self.ctx.request_anim_frame();
+        }
+    }
 }
 
 impl Widget for Image {
-    fn on_event(&mut self, _ctx: &mut EventCtx, _event: &Event, _env: &Env) {}
+    fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, _env: &Env) {
+        match event {
+            Event::AnimFrame(interval) => {
+                let Some(animation) = &mut self.animation else {
+                    return;
+                };
+                if !animation.playing {
+                    return;
+                }
+                if ctx.is_stashed() || !animation.on_screen || ctx.reduced_motion() {
+                    // Resumed by `layout` or `lifecycle`.
+                    animation.suspended = true;
+                    return;
+                }
+                if self.advance_animation(Duration::from_nanos(*interval)) {
+                    ctx.request_paint();
+                }
+                ctx.request_anim_frame();
+            }
+            Event::Command(cmd) => {
+                let Some(loaded) = cmd.try_get(IMAGE_LOADED) else {
+                    return;
+                };
+                ctx.set_handled();
+                if loaded.error.is_some() {
+                    return;
+                }
+                let Some(source) = &self.source else {
+                    return;
+                };
+                if let Some(image) = ctx.load_animated_image(source) {
+                    if self.set_animated_image(image) {
+                        ctx.request_anim_frame();
+                    }
+                    ctx.request_layout();
+                }
+            }
+            _ => {}
+        }
+    }
 
     fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange, _env: &Env) {}
 
-    fn lifecycle(&mut self, _ctx: &mut LifeCycleCtx, _event: &LifeCycle, _env: &Env) {}
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, _env: &Env) {
+        match event {
+            LifeCycle::WidgetAdded => {
+                if let Some(source) = &self.source {
+                    if let Some(image) = ctx.load_animated_image(source) {
+                        self.set_animated_image(image);
+                        ctx.request_layout();
+                    }
+                }
+                if self.animation.is_some() {
+                    ctx.request_anim_frame();
+                }
+            }
+            LifeCycle::EnvChanged => {
+                let Some(animation) = &mut self.animation else {
+                    return;
+                };
+                if animation.suspended && !ctx.reduced_motion() {
+                    animation.suspended = false;
+                    ctx.request_anim_frame();
+                }
+            }
+            _ => {}
+        }
+    }
 
-    fn layout(&mut self, _ctx: &mut LayoutCtx, bc: &BoxConstraints, _env: &Env) -> Size {
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, _env: &Env) -> Size {
         // If either the width or height is constrained calculate a value so that the image fits
         // in the size exactly. If it is unconstrained by both width and height take the size of
         // the image.
@@ -125,6 +297,18 @@ impl Widget for Image {
             bc.constrain(self.image_data.size())
         };
         trace!("Computed size: {}", size);
+
+        if let Some(animation) = &mut self.animation {
+            animation.on_screen = ctx.viewport_rect().map_or(true, |viewport| {
+                !viewport.intersect(size.to_rect()).is_empty()
+            });
+            if animation.suspended && animation.on_screen && !ctx.reduced_motion() {
+                animation.suspended = false;
+                ctx.mutate_self_later(|mut image: WidgetMut<Image>| {
+                    image.ctx.request_anim_frame();
+                });
+            }
+        }
         size
     }
 
@@ -179,6 +363,16 @@ impl Widget for Image {
     fn make_trace_span(&self) -> Span {
         trace_span!("Image")
     }
+
+    fn retained_memory(&self, memory: &mut MemoryUsage) {
+        // The pixels may be shared with the image cache and other widgets.
+        let pixels = self.image_data.raw_pixels();
+        memory.add_shared(pixels);
+        // The platform keeps its own copy of the pixels once the image is painted.
+        if self.paint_data.is_some() {
+            memory.add(pixels.len());
+        }
+    }
 }
 
 #[allow(unused)]
@@ -193,6 +387,7 @@ mod tests {
     use crate::piet::ImageFormat;
     use crate::testing::{widget_ids, TestHarness, TestWidgetExt};
     use crate::theme::PRIMARY_LIGHT;
+    use crate::{AnimationFrame, ImageCache};
 
     /// Painting an empty image shouldn't crash.
     #[test]
@@ -297,4 +492,61 @@ mod tests {
         // We don't use assert_eq because we don't want rich assert
         assert!(render_1 == render_2);
     }
+
+    #[test]
+    fn retained_memory_of_subtree() {
+        let image_data = ImageBuf::from_raw(vec![255; 3 * 8 * 8], ImageFormat::Rgb, 8, 8);
+        let widget = crate::widget::Flex::column()
+            .with_child(Image::new(image_data.clone()))
+            .with_child(Image::new(image_data));
+
+        // The pixels shared by both images are counted once.
+        let harness = TestHarness::create(widget);
+        assert_eq!(harness.root_widget().subtree_memory(), 3 * 8 * 8);
+    }
+
+    #[test]
+    fn from_source_uses_cached_image() {
+        let image_data = ImageBuf::from_raw(vec![255; 3 * 8 * 8], ImageFormat::Rgb, 8, 8);
+        let image_cache = ImageCache::new();
+        image_cache.insert("icon.png", image_data);
+        let widget = Image::from_source(ImageSource::new("icon.png"));
+
+        let harness = TestHarness::create_with_image_cache(widget, image_cache);
+        let image = harness.root_widget();
+        let image = image.downcast::<Image>().unwrap();
+        assert_eq!(image.image_data.size(), Size::new(8.0, 8.0));
+    }
+
+    #[test]
+    fn animation_advances_with_frame_delays() {
+        let frame = |value: u8| AnimationFrame {
+            image: ImageBuf::from_raw(vec![value; 3 * 2 * 2], ImageFormat::Rgb, 2, 2),
+            delay: Duration::from_millis(100),
+        };
+        let animation = AnimatedImage::new(vec![frame(0), frame(128), frame(255)]);
+        let mut harness = TestHarness::create(Image::animated(animation));
+
+        let current_frame = |harness: &TestHarness| {
+            let image = harness.root_widget();
+            let image = image.downcast::<Image>().unwrap();
+            image.animation.as_ref().unwrap().frame
+        };
+        let millis = |ms: u64| Event::AnimFrame(Duration::from_millis(ms).as_nanos() as u64);
+
+        harness.process_event(millis(50));
+        assert_eq!(current_frame(&harness), 0);
+        harness.process_event(millis(100));
+        assert_eq!(current_frame(&harness), 1);
+        // Loops back to the start.
+        harness.process_event(millis(150));
+        assert_eq!(current_frame(&harness), 0);
+
+        harness.edit_root_widget(|mut image, _| {
+            let mut image = image.downcast::<Image>().unwrap();
+            image.set_playing(false);
+        });
+        harness.process_event(millis(100));
+        assert_eq!(current_frame(&harness), 0);
+    }
 }
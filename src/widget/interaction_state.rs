@@ -0,0 +1,361 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Animated hover/pressed/focused feedback shared by interactive widgets.
+
+use std::time::Duration;
+
+use tracing::trace;
+
+use crate::{Color, Event, EventCtx, LifeCycle, LifeCycleCtx, StatusChange, WidgetId};
+
+const DEFAULT_TRANSITION: Duration = Duration::from_millis(120);
+
+/// The progress of a transition between an "off" state (0.0) and an "on" state (1.0).
+#[derive(Clone, Copy, Debug, Default)]
+struct Transition {
+    target: bool,
+    value: f64,
+}
+
+impl Transition {
+    fn set_target(&mut self, target: bool) -> bool {
+        let changed = self.target != target;
+        self.target = target;
+        changed
+    }
+
+    fn is_settled(&self) -> bool {
+        self.value == if self.target { 1.0 } else { 0.0 }
+    }
+
+    fn advance(&mut self, step: f64) {
+        if self.target {
+            self.value = (self.value + step).min(1.0);
+        } else {
+            self.value = (self.value - step).max(0.0);
+        }
+    }
+
+    fn jump_to_target(&mut self) {
+        self.value = if self.target { 1.0 } else { 0.0 };
+    }
+}
+
+/// Tracks the interaction state of a widget, and animates transitions between states.
+///
+/// A widget owning an `InteractionState` must forward its [`on_event`](crate::Widget::on_event),
+/// [`on_status_change`](crate::Widget::on_status_change) and [`lifecycle`](crate::Widget::lifecycle)
+/// calls to it; the state is read from the context (hot, active, focused, disabled),
+/// so the widget should call these *after* handling the event itself.
+///
+/// In [`paint`](crate::Widget::paint), the widget can then use the `*_amount` methods, or
+/// helpers like [`color`](Self::color), to smoothly interpolate between the look of
+/// each state.
+///
+/// ```
+/// # use masonry::widget::InteractionState;
+/// # use masonry::Color;
+/// let interaction = InteractionState::new();
+/// let background = interaction.color(Color::grey(0.3), Color::grey(0.4), Color::grey(0.2));
+/// # assert_eq!(background, Color::grey(0.3));
+/// ```
+#[derive(Clone, Debug)]
+pub struct InteractionState {
+    hover: Transition,
+    press: Transition,
+    focus: Transition,
+    disabled: Transition,
+    duration: Duration,
+}
+
+impl Default for InteractionState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InteractionState {
+    /// Create a new interaction state, with a default transition duration.
+    pub fn new() -> Self {
+        InteractionState {
+            hover: Transition::default(),
+            press: Transition::default(),
+            focus: Transition::default(),
+            disabled: Transition::default(),
+            duration: DEFAULT_TRANSITION,
+        }
+    }
+
+    /// Builder-style method for setting the duration of transitions.
+    ///
+    /// A duration of zero disables animations.
+    pub fn with_duration(mut self, duration: Duration) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    /// Whether the mouse is over the widget.
+    pub fn is_hovered(&self) -> bool {
+        self.hover.target
+    }
+
+    /// Whether the widget is being pressed.
+    pub fn is_pressed(&self) -> bool {
+        self.press.target
+    }
+
+    /// Whether the widget has keyboard focus.
+    pub fn is_focused(&self) -> bool {
+        self.focus.target
+    }
+
+    /// Whether the widget is disabled.
+    pub fn is_disabled(&self) -> bool {
+        self.disabled.target
+    }
+
+    /// How "hovered" the widget currently looks, from 0.0 to 1.0.
+    pub fn hover_amount(&self) -> f64 {
+        self.hover.value
+    }
+
+    /// How "pressed" the widget currently looks, from 0.0 to 1.0.
+    pub fn press_amount(&self) -> f64 {
+        self.press.value
+    }
+
+    /// How "focused" the widget currently looks, from 0.0 to 1.0.
+    pub fn focus_amount(&self) -> f64 {
+        self.focus.value
+    }
+
+    /// How "disabled" the widget currently looks, from 0.0 to 1.0.
+    pub fn disabled_amount(&self) -> f64 {
+        self.disabled.value
+    }
+
+    /// Whether a transition is in progress.
+    pub fn is_animating(&self) -> bool {
+        !(self.hover.is_settled()
+            && self.press.is_settled()
+            && self.focus.is_settled()
+            && self.disabled.is_settled())
+    }
+
+    /// Interpolate between colors for the resting, hovered and pressed states.
+    pub fn color(&self, normal: Color, hovered: Color, pressed: Color) -> Color {
+        let color = lerp_color(&normal, &hovered, self.hover_amount());
+        lerp_color(&color, &pressed, self.press_amount())
+    }
+
+    /// Interpolate between values (eg elevations) for the resting, hovered and pressed states.
+    pub fn value(&self, normal: f64, hovered: f64, pressed: f64) -> f64 {
+        let value = lerp(normal, hovered, self.hover_amount());
+        lerp(value, pressed, self.press_amount())
+    }
+
+    /// Update the state after an event, and advance transitions on [`Event::AnimFrame`].
+    pub fn on_event(&mut self, ctx: &mut EventCtx, event: &Event) {
+        let changed = self.set_targets(
+            ctx.is_hot(),
+            ctx.is_active(),
+            ctx.is_focused(),
+            ctx.is_disabled(),
+        );
+        if changed {
+            self.start_transition(ctx.widget_id(), ctx.reduced_motion());
+            ctx.request_anim_frame();
+            ctx.request_paint();
+        }
+
+        if let Event::AnimFrame(interval) = event {
+            if self.is_animating() {
+                self.advance(*interval);
+                if self.is_animating() {
+                    ctx.request_anim_frame();
+                }
+                ctx.request_paint();
+            }
+        }
+    }
+
+    /// Update the state after a hot or focus change.
+    pub fn on_status_change(&mut self, ctx: &mut LifeCycleCtx, _event: &StatusChange) {
+        self.update_from_lifecycle_ctx(ctx);
+    }
+
+    /// Update the state after the widget is added or disabled.
+    ///
+    /// The state jumps directly to its initial value when the widget is added.
+    pub fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle) {
+        match event {
+            LifeCycle::WidgetAdded => {
+                self.update_from_lifecycle_ctx(ctx);
+                self.hover.jump_to_target();
+                self.press.jump_to_target();
+                self.focus.jump_to_target();
+                self.disabled.jump_to_target();
+            }
+            LifeCycle::DisabledChanged(_) => self.update_from_lifecycle_ctx(ctx),
+            _ => (),
+        }
+    }
+
+    fn update_from_lifecycle_ctx(&mut self, ctx: &mut LifeCycleCtx) {
+        let changed = self.set_targets(
+            ctx.is_hot(),
+            ctx.is_active(),
+            ctx.is_focused(),
+            ctx.is_disabled(),
+        );
+        if changed {
+            self.start_transition(ctx.widget_id(), ctx.reduced_motion());
+            ctx.request_anim_frame();
+            ctx.request_paint();
+        }
+    }
+
+    fn set_targets(&mut self, hot: bool, active: bool, focused: bool, disabled: bool) -> bool {
+        let enabled = !disabled;
+        // Use `|` rather than `||` so that every target is updated.
+        self.hover.set_target(hot && enabled)
+            | self.press.set_target(active && enabled)
+            | self.focus.set_target(focused)
+            | self.disabled.set_target(disabled)
+    }
+
+    /// Transitions are skipped when `reduced_motion` is set, see
+    /// [`EventCtx::reduced_motion`](crate::EventCtx::reduced_motion).
+    fn start_transition(&mut self, widget_id: WidgetId, reduced_motion: bool) {
+        trace!(
+            "Widget {:?}: interaction state changed to hovered={}, pressed={}, focused={}, disabled={}",
+            widget_id,
+            self.hover.target,
+            self.press.target,
+            self.focus.target,
+            self.disabled.target,
+        );
+        if self.duration.is_zero() || reduced_motion {
+            self.hover.jump_to_target();
+            self.press.jump_to_target();
+            self.focus.jump_to_target();
+            self.disabled.jump_to_target();
+        }
+    }
+
+    fn advance(&mut self, interval_ns: u64) {
+        let step = if self.duration.is_zero() {
+            1.0
+        } else {
+            interval_ns as f64 / self.duration.as_nanos() as f64
+        };
+        self.hover.advance(step);
+        self.press.advance(step);
+        self.focus.advance(step);
+        self.disabled.advance(step);
+    }
+}
+
+fn lerp(start: f64, end: f64, t: f64) -> f64 {
+    start + (end - start) * t
+}
+
+fn lerp_color(start: &Color, end: &Color, t: f64) -> Color {
+    if t <= 0.0 {
+        return start.clone();
+    }
+    if t >= 1.0 {
+        return end.clone();
+    }
+    let (r0, g0, b0, a0) = start.as_rgba();
+    let (r1, g1, b1, a1) = end.as_rgba();
+    Color::rgba(
+        lerp(r0, r1, t),
+        lerp(g0, g1, t),
+        lerp(b0, b1, t),
+        lerp(a0, a1, t),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::testing::{widget_ids, ModularWidget, TestHarness};
+    use crate::widget::Flex;
+    use crate::Size;
+
+    fn interactive_widget(
+        interaction: Rc<RefCell<InteractionState>>,
+    ) -> ModularWidget<Rc<RefCell<InteractionState>>> {
+        ModularWidget::new(interaction)
+            .event_fn(|interaction, ctx, event, _| {
+                interaction.borrow_mut().on_event(ctx, event);
+            })
+            .status_change_fn(|interaction, ctx, event, _| {
+                interaction.borrow_mut().on_status_change(ctx, event);
+            })
+            .lifecycle_fn(|interaction, ctx, event, _| {
+                interaction.borrow_mut().lifecycle(ctx, event);
+            })
+            .layout_fn(|_, _, _, _| Size::new(20.0, 20.0))
+    }
+
+    #[test]
+    fn hover_transition() {
+        let [widget_id] = widget_ids();
+        let interaction = Rc::new(RefCell::new(InteractionState::new()));
+        let widget = interactive_widget(interaction.clone());
+
+        let mut harness = TestHarness::create(Flex::row().with_child_id(widget, widget_id));
+
+        harness.mouse_move_to(widget_id);
+        assert!(interaction.borrow().is_hovered());
+        assert_eq!(interaction.borrow().hover_amount(), 0.0);
+
+        let half_duration = DEFAULT_TRANSITION.as_nanos() as u64 / 2;
+        harness.process_event(Event::AnimFrame(half_duration));
+        assert_eq!(interaction.borrow().hover_amount(), 0.5);
+
+        harness.process_event(Event::AnimFrame(half_duration));
+        assert_eq!(interaction.borrow().hover_amount(), 1.0);
+        assert!(!interaction.borrow().is_animating());
+    }
+
+    #[test]
+    fn no_transition_in_low_power_mode() {
+        let [widget_id] = widget_ids();
+        let interaction = Rc::new(RefCell::new(InteractionState::new()));
+        let widget = interactive_widget(interaction.clone());
+
+        let mut harness = TestHarness::create(Flex::row().with_child_id(widget, widget_id));
+        harness.submit_command(crate::command::SET_LOW_POWER_MODE.with(Some(true)));
+
+        harness.mouse_move_to(widget_id);
+        assert_eq!(interaction.borrow().hover_amount(), 1.0);
+        assert!(!interaction.borrow().is_animating());
+    }
+
+    #[test]
+    fn color_interpolation() {
+        let mut interaction = InteractionState::new();
+        assert_eq!(
+            interaction.color(Color::BLACK, Color::WHITE, Color::BLACK),
+            Color::BLACK
+        );
+
+        interaction.hover = Transition {
+            target: true,
+            value: 1.0,
+        };
+        assert_eq!(
+            interaction.color(Color::BLACK, Color::WHITE, Color::BLACK),
+            Color::WHITE
+        );
+        assert_eq!(interaction.value(0.0, 2.0, 4.0), 2.0);
+    }
+}
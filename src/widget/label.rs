@@ -8,29 +8,48 @@
 // - set text
 // - set text attributes
 
+use std::ops::Range;
+
 use druid_shell::Cursor;
 use smallvec::SmallVec;
 use tracing::{trace, trace_span, Span};
 
-use crate::kurbo::Vec2;
-use crate::text::{FontDescriptor, TextAlignment, TextLayout};
-use crate::widget::WidgetRef;
+use crate::access_keys::SHOW_ACCESS_KEYS;
+use crate::kurbo::{Insets, Line, Rect, Vec2};
+use crate::piet::{PietText, TextLayout as _};
+use crate::text::{FontDescriptor, FontFeature, Hyphens, TextAlignment, TextLayout};
+use crate::widget::{MemoryUsage, WidgetRef};
 use crate::{
-    ArcStr, BoxConstraints, Color, Data, Env, Event, EventCtx, KeyOrValue, LayoutCtx, LifeCycle,
-    LifeCycleCtx, PaintCtx, Point, RenderContext, Size, StatusChange, Widget,
+    theme, ArcStr, BoxConstraints, Color, Data, Env, Event, EventCtx, KeyOrValue, LayoutCtx,
+    LifeCycle, LifeCycleCtx, PaintCtx, Point, RenderContext, Size, StatusChange, Widget, WidgetId,
 };
 
 // added padding between the edges of the widget and the text.
 const LABEL_X_PADDING: f64 = 2.0;
+const ELLIPSIS: &str = "\u{2026}";
+/// Space between the full text of a truncated label and the border of its tooltip.
+const FULL_TEXT_PADDING: f64 = 4.0;
+/// The tooltip showing the full text overflows the label, so it's painted above the
+/// label's siblings.
+const FULL_TEXT_Z_INDEX: u32 = 1_000;
 
 /// A widget displaying non-editable text.
 pub struct Label {
     current_text: ArcStr,
     text_layout: TextLayout<ArcStr>,
     line_break_mode: LineBreaking,
+    max_lines: Option<usize>,
+    // The layout which is painted, when the text doesn't fit.
+    truncated_layout: Option<TextLayout<ArcStr>>,
+    truncation_width: f64,
+    highlights: Vec<Range<usize>>,
 
     disabled: bool,
     default_text_color: KeyOrValue<Color>,
+
+    access_key: Option<char>,
+    access_key_target: Option<WidgetId>,
+    shows_access_key: bool,
 }
 
 crate::declare_widget!(LabelMut, Label);
@@ -44,6 +63,9 @@ pub enum LineBreaking {
     Clip,
     /// Lines overflow the label.
     Overflow,
+    /// Lines are truncated to the width of the label, and the text ends with
+    /// an ellipsis at the first truncated line.
+    Ellipsis,
 }
 
 // --- METHODS ---
@@ -59,8 +81,15 @@ impl Label {
             current_text,
             text_layout,
             line_break_mode: LineBreaking::Overflow,
+            max_lines: None,
+            truncated_layout: None,
+            truncation_width: f64::NAN,
+            highlights: Vec::new(),
             disabled: false,
             default_text_color: crate::theme::TEXT_COLOR.into(),
+            access_key: None,
+            access_key_target: None,
+            shows_access_key: false,
         }
     }
 
@@ -70,8 +99,15 @@ impl Label {
             current_text: "".into(),
             text_layout: TextLayout::new(),
             line_break_mode: LineBreaking::Overflow,
+            max_lines: None,
+            truncated_layout: None,
+            truncation_width: f64::NAN,
+            highlights: Vec::new(),
             disabled: false,
             default_text_color: crate::theme::TEXT_COLOR.into(),
+            access_key: None,
+            access_key_target: None,
+            shows_access_key: false,
         }
     }
 
@@ -118,26 +154,114 @@ impl Label {
         self
     }
 
+    /// Builder-style method for setting the OpenType features of the text,
+    /// eg [`FontFeature::TABULAR_NUMBERS`].
+    pub fn with_font_features(mut self, features: impl Into<Vec<FontFeature>>) -> Self {
+        self.text_layout.set_font_features(features);
+        self
+    }
+
+    /// Builder-style method for setting the extra space after each character.
+    pub fn with_letter_spacing(mut self, spacing: impl Into<KeyOrValue<f64>>) -> Self {
+        self.text_layout.set_letter_spacing(spacing);
+        self
+    }
+
+    /// Builder-style method for setting the extra space added to each space character.
+    pub fn with_word_spacing(mut self, spacing: impl Into<KeyOrValue<f64>>) -> Self {
+        self.text_layout.set_word_spacing(spacing);
+        self
+    }
+
     /// Builder-style method to set the [`LineBreaking`] behaviour.
     pub fn with_line_break_mode(mut self, mode: LineBreaking) -> Self {
         self.line_break_mode = mode;
         self
     }
 
+    /// Builder-style method to set whether words may be hyphenated when wrapping lines.
+    pub fn with_hyphens(mut self, hyphens: Hyphens) -> Self {
+        self.text_layout.set_hyphens(hyphens);
+        self
+    }
+
+    /// Builder-style method to set the locale of the text, eg `"en-US"`.
+    ///
+    /// By default, the [system locale](crate::text::system_locale) is used.
+    pub fn with_locale(mut self, locale: impl Into<ArcStr>) -> Self {
+        self.text_layout.set_locale(Some(locale.into()));
+        self
+    }
+
+    /// Builder-style method to limit the number of lines displayed.
+    ///
+    /// If the text has more lines, the last displayed line ends with an ellipsis.
+    /// This is mostly useful with [`LineBreaking::WordWrap`].
+    pub fn with_max_lines(mut self, max_lines: impl Into<Option<usize>>) -> Self {
+        self.max_lines = max_lines.into();
+        self
+    }
+
     /// Builder-style method to set the [`TextAlignment`].
     pub fn with_text_alignment(mut self, alignment: TextAlignment) -> Self {
         self.text_layout.set_text_alignment(alignment);
         self
     }
 
+    /// Builder-style method to set the access key, which activates the label's parent,
+    /// eg the [`Button`](crate::widget::Button) it's in, when pressed with Alt.
+    ///
+    /// The first occurrence of the key in the text is underlined while Alt is held.
+    /// See the [`access_keys`](crate::access_keys) module.
+    pub fn with_access_key(mut self, key: char) -> Self {
+        self.access_key = Some(key);
+        self
+    }
+
+    /// Builder-style method to activate `target` with the access key instead of the
+    /// label's parent, eg for a label next to a text box.
+    pub fn with_access_key_target(mut self, target: WidgetId) -> Self {
+        self.access_key_target = Some(target);
+        self
+    }
+
     /// Return the current value of the label's text.
     pub fn text(&self) -> ArcStr {
         self.current_text.clone()
     }
 
+    /// Returns `true` if the text was truncated during the last layout pass.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated_layout.is_some()
+    }
+
+    /// The full text, if it was truncated.
+    ///
+    /// The label displays it in a tooltip when hovered; widgets which display
+    /// their own tooltips can use this instead.
+    pub fn tooltip_text(&self) -> Option<ArcStr> {
+        if !self.is_truncated() {
+            return None;
+        }
+        self.text_layout.text().cloned()
+    }
+
+    /// The ranges of text painted with a highlight behind them.
+    pub fn highlighted_ranges(&self) -> &[Range<usize>] {
+        &self.highlights
+    }
+
+    /// The bounding box of the text in `range`, in the label's coordinates.
+    ///
+    /// This is not valid until `layout` has been called.
+    pub fn rect_for_range(&self, range: Range<usize>) -> Option<Rect> {
+        let bounds = self.displayed_layout().range_bounds(range)?;
+        Some(bounds + Vec2::new(LABEL_X_PADDING, 0.0))
+    }
+
     /// Return the offset of the first baseline relative to the bottom of the widget.
     pub fn baseline_offset(&self) -> f64 {
-        let text_metrics = self.text_layout.layout_metrics();
+        let text_metrics = self.displayed_layout().layout_metrics();
         text_metrics.size.height - text_metrics.first_baseline
     }
 
@@ -147,7 +271,92 @@ impl Label {
     /// of managing a dynamic or localized string, but want finer control
     /// over where the text is drawn.
     pub fn draw_at(&self, ctx: &mut PaintCtx, origin: impl Into<Point>) {
-        self.text_layout.draw(ctx, origin)
+        self.displayed_layout().draw(ctx, origin)
+    }
+
+    /// The range of the first occurrence of the access key in the displayed text.
+    fn access_key_range(&self) -> Option<Range<usize>> {
+        let key = self.access_key?;
+        let text = self.displayed_layout().text()?;
+        text.char_indices()
+            .find(|(_, c)| c.to_lowercase().eq(key.to_lowercase()))
+            .map(|(idx, c)| idx..idx + c.len_utf8())
+    }
+
+    fn displayed_layout(&self) -> &TextLayout<ArcStr> {
+        self.truncated_layout.as_ref().unwrap_or(&self.text_layout)
+    }
+
+    /// The origin of the full text in the tooltip of a truncated label, and the
+    /// rect of the tooltip, below the label.
+    fn full_text_tooltip(&self, label_size: Size) -> (Point, Rect) {
+        let origin = Point::new(LABEL_X_PADDING, label_size.height + 2.0 * FULL_TEXT_PADDING);
+        let rect = Rect::from_origin_size(origin, self.text_layout.size()).inset(FULL_TEXT_PADDING);
+        (origin, rect)
+    }
+
+    /// Build the layout of the truncated text, if the text doesn't fit in
+    /// `max_width` with the current line breaking mode, or has more than `max_lines`.
+    fn truncate(
+        &self,
+        factory: &mut PietText,
+        env: &Env,
+        max_width: f64,
+    ) -> Option<TextLayout<ArcStr>> {
+        let text_layout = &self.text_layout;
+        let layout = text_layout.layout()?;
+        let text = text_layout.text()?;
+        let line_count = layout.line_count();
+        // The offsets of the inner layout differ from the text's if it was hyphenated.
+        let line_range = |line| {
+            let metric = layout.line_metric(line).unwrap();
+            let start = text_layout.to_text_offset(metric.start_offset);
+            let end = metric.end_offset - metric.trailing_whitespace;
+            (start, text_layout.to_text_offset(end))
+        };
+
+        let mut last_line = match self.max_lines {
+            Some(max_lines) if line_count > max_lines.max(1) => Some(max_lines.max(1) - 1),
+            _ => None,
+        };
+        if self.line_break_mode == LineBreaking::Ellipsis && max_width.is_finite() {
+            let kept_lines = last_line.map_or(line_count, |line| line + 1);
+            let line_width = |line| {
+                let (_, end) = line_range(line);
+                text_layout.point_for_text_position(end).x
+            };
+            if let Some(line) = (0..kept_lines).find(|line| line_width(*line) > max_width) {
+                last_line = Some(line);
+            }
+        }
+        let last_line = last_line?;
+        let line = layout.line_metric(last_line)?;
+        let (line_start, line_end) = line_range(last_line);
+
+        let mut ellipsis = self.text_layout.clone();
+        ellipsis.set_text(ELLIPSIS.into());
+        ellipsis.rebuild_if_needed(factory, env);
+        let limit = max_width - ellipsis.size().width;
+
+        // Keep as much of the last line as fits next to the ellipsis.
+        let mut cut = if limit.is_finite() {
+            let y = line.y_offset + line.height / 2.0;
+            let idx = text_layout.text_position_for_point(Point::new(limit, y));
+            idx.clamp(line_start, line_end)
+        } else {
+            line_end
+        };
+        while cut > line_start && text_layout.point_for_text_position(cut).x > limit {
+            cut = text[..cut]
+                .char_indices()
+                .next_back()
+                .map_or(line_start, |(idx, _)| idx);
+        }
+
+        let mut truncated = text_layout.clone();
+        truncated.set_text(format!("{}{}", text[..cut].trim_end(), ELLIPSIS).into());
+        truncated.rebuild_if_needed(factory, env);
+        Some(truncated)
     }
 }
 
@@ -192,9 +401,51 @@ impl LabelMut<'_, '_> {
         self.ctx.request_layout();
     }
 
+    /// Set the OpenType features of the text.
+    pub fn set_font_features(&mut self, features: impl Into<Vec<FontFeature>>) {
+        self.widget.text_layout.set_font_features(features);
+        self.ctx.request_layout();
+    }
+
+    /// Set the extra space after each character.
+    pub fn set_letter_spacing(&mut self, spacing: impl Into<KeyOrValue<f64>>) {
+        self.widget.text_layout.set_letter_spacing(spacing);
+        self.ctx.request_layout();
+    }
+
+    /// Set the extra space added to each space character.
+    pub fn set_word_spacing(&mut self, spacing: impl Into<KeyOrValue<f64>>) {
+        self.widget.text_layout.set_word_spacing(spacing);
+        self.ctx.request_layout();
+    }
+
     /// Set the [`LineBreaking`] behaviour.
     pub fn set_line_break_mode(&mut self, mode: LineBreaking) {
         self.widget.line_break_mode = mode;
+        // Force the truncated text to be computed again.
+        self.widget.truncation_width = f64::NAN;
+        self.ctx.request_layout();
+    }
+
+    /// Set whether words may be hyphenated when wrapping lines.
+    pub fn set_hyphens(&mut self, hyphens: Hyphens) {
+        self.widget.text_layout.set_hyphens(hyphens);
+        self.ctx.request_layout();
+    }
+
+    /// Set the locale of the text, or use the system locale if `None`.
+    pub fn set_locale(&mut self, locale: Option<ArcStr>) {
+        self.widget.text_layout.set_locale(locale);
+        self.ctx.request_layout();
+    }
+
+    /// Limit the number of lines displayed.
+    ///
+    /// If the text has more lines, the last displayed line ends with an ellipsis.
+    pub fn set_max_lines(&mut self, max_lines: impl Into<Option<usize>>) {
+        self.widget.max_lines = max_lines.into();
+        // Force the truncated text to be computed again.
+        self.widget.truncation_width = f64::NAN;
         self.ctx.request_layout();
     }
 
@@ -203,6 +454,24 @@ impl LabelMut<'_, '_> {
         self.widget.text_layout.set_text_alignment(alignment);
         self.ctx.request_layout();
     }
+
+    /// Paint a highlight behind each range of text, eg to show search results.
+    ///
+    /// This replaces the previous highlights; pass an empty `Vec` to remove them.
+    /// The color is [`theme::TEXT_HIGHLIGHT_COLOR`].
+    pub fn highlight_ranges(&mut self, ranges: Vec<Range<usize>>) {
+        self.widget.highlights = ranges;
+        self.ctx.request_paint();
+    }
+
+    /// Scroll the text in `range` into view, if the label is in a [`Portal`].
+    ///
+    /// [`Portal`]: crate::widget::Portal
+    pub fn scroll_to_range(&mut self, range: Range<usize>) {
+        if let Some(rect) = self.widget.rect_for_range(range) {
+            self.ctx.request_pan_to_rect(rect);
+        }
+    }
 }
 
 // --- TRAIT IMPLS ---
@@ -229,11 +498,21 @@ impl Widget for Label {
                     ctx.clear_cursor();
                 }
             }
+            Event::Command(cmd) if cmd.is(SHOW_ACCESS_KEYS) => {
+                self.shows_access_key = *cmd.get(SHOW_ACCESS_KEYS);
+                ctx.request_paint();
+            }
             _ => {}
         }
     }
 
-    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange, _env: &Env) {}
+    fn on_status_change(&mut self, ctx: &mut LifeCycleCtx, event: &StatusChange, _env: &Env) {
+        if let StatusChange::HotChanged(_) = event {
+            if self.is_truncated() {
+                ctx.request_paint();
+            }
+        }
+    }
 
     fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, _env: &Env) {
         match event {
@@ -246,37 +525,114 @@ impl Widget for Label {
                 self.text_layout.set_text_color(color);
                 ctx.request_layout();
             }
+            LifeCycle::WidgetAdded => {
+                if let Some(key) = self.access_key {
+                    let target = self
+                        .access_key_target
+                        .or_else(|| ctx.parent_id())
+                        .unwrap_or_else(|| ctx.widget_id());
+                    ctx.register_access_key(key, target);
+                }
+            }
             _ => {}
         }
     }
 
     fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, env: &Env) -> Size {
+        let available_width = bc.max().width - LABEL_X_PADDING * 2.0;
         let width = match self.line_break_mode {
-            LineBreaking::WordWrap => bc.max().width - LABEL_X_PADDING * 2.0,
+            LineBreaking::WordWrap => available_width,
             _ => f64::INFINITY,
         };
 
         self.text_layout.set_wrap_width(width);
+        // The truncated text is computed again only if the full text changed.
+        let needs_truncation =
+            self.text_layout.needs_rebuild() || available_width != self.truncation_width;
         self.text_layout.rebuild_if_needed(ctx.text(), env);
+        if needs_truncation {
+            self.truncated_layout = self.truncate(ctx.text(), env, available_width);
+            self.truncation_width = available_width;
+        }
 
-        let text_metrics = self.text_layout.layout_metrics();
+        let text_metrics = self.displayed_layout().layout_metrics();
         ctx.set_baseline_offset(text_metrics.size.height - text_metrics.first_baseline);
         let size = bc.constrain(Size::new(
             text_metrics.size.width + 2. * LABEL_X_PADDING,
             text_metrics.size.height,
         ));
+
+        // The paint rect covers the tooltip, so that it's invalidated when the label
+        // is hovered or unhovered.
+        if self.is_truncated() {
+            let (_, tooltip_rect) = self.full_text_tooltip(size);
+            // Add the half of the border which is outside the tooltip rect.
+            let tooltip_rect = tooltip_rect.inflate(0.5, 0.5);
+            ctx.set_paint_insets(Insets::new(
+                -tooltip_rect.x0,
+                -tooltip_rect.y0,
+                tooltip_rect.x1 - size.width,
+                tooltip_rect.y1 - size.height,
+            ));
+        } else {
+            ctx.set_paint_insets(Insets::ZERO);
+        }
         trace!("Computed size: {}", size);
         size
     }
 
-    fn paint(&mut self, ctx: &mut PaintCtx, _env: &Env) {
+    fn paint(&mut self, ctx: &mut PaintCtx, env: &Env) {
         let origin = Point::new(LABEL_X_PADDING, 0.0);
         let label_size = ctx.size();
 
+        if self.is_truncated() && ctx.is_hot() {
+            let tooltip = self.text_layout.clone();
+            let (origin, background) = self.full_text_tooltip(label_size);
+            let background = background.to_rounded_rect(2.0);
+            let background_color = env.get(theme::BACKGROUND_LIGHT);
+            let border_color = env.get(theme::BORDER_LIGHT);
+
+            ctx.paint_with_z_index(FULL_TEXT_Z_INDEX, move |ctx| {
+                ctx.fill(background, &background_color);
+                ctx.stroke(background, &border_color, 1.0);
+                tooltip.draw(ctx, origin);
+            });
+        }
+
         if self.line_break_mode == LineBreaking::Clip {
             ctx.clip(label_size.to_rect());
         }
-        self.draw_at(ctx, origin)
+        if !self.highlights.is_empty() {
+            let layout = self.displayed_layout();
+            let text_len = layout.text_len();
+            let color = env.get(theme::TEXT_HIGHLIGHT_COLOR);
+            for range in &self.highlights {
+                // The highlight may cover text which was truncated.
+                let range = range.start.min(text_len)..range.end.min(text_len);
+                if range.is_empty() {
+                    continue;
+                }
+                for rect in layout.selection_rects(range, ctx.text(), env) {
+                    ctx.fill(rect + origin.to_vec2(), &color);
+                }
+            }
+        }
+        self.draw_at(ctx, origin);
+
+        if self.shows_access_key {
+            let rect = self
+                .access_key_range()
+                .and_then(|range| self.rect_for_range(range));
+            if let Some(rect) = rect {
+                let color = if ctx.is_disabled() {
+                    env.get(theme::DISABLED_TEXT_COLOR)
+                } else {
+                    self.default_text_color.resolve(env)
+                };
+                let y = rect.y1 - 0.5;
+                ctx.stroke(Line::new((rect.x0, y), (rect.x1, y)), &color, 1.0);
+            }
+        }
     }
 
     fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
@@ -290,6 +646,13 @@ impl Widget for Label {
     fn get_debug_text(&self) -> Option<String> {
         Some(self.current_text.to_string())
     }
+
+    fn retained_memory(&self, memory: &mut MemoryUsage) {
+        memory.add(self.text_layout.estimated_memory());
+        if let Some(layout) = &self.truncated_layout {
+            memory.add(layout.estimated_memory());
+        }
+    }
 }
 
 impl Data for LineBreaking {
@@ -300,14 +663,14 @@ impl Data for LineBreaking {
 
 #[cfg(test)]
 mod tests {
-    use crate::piet::FontFamily;
+    use crate::piet::{FontFamily, TextLayout as _};
     use insta::assert_debug_snapshot;
 
     use super::*;
     use crate::assert_render_snapshot;
-    use crate::testing::TestHarness;
+    use crate::testing::{widget_ids, TestHarness};
     use crate::theme::{PRIMARY_DARK, PRIMARY_LIGHT};
-    use crate::widget::{Flex, SizedBox};
+    use crate::widget::{Flex, Portal, SizedBox};
 
     #[test]
     fn simple_label() {
@@ -367,6 +730,80 @@ mod tests {
         assert_render_snapshot!(harness, "line_break_modes");
     }
 
+    #[test]
+    fn ellipsis_and_max_lines() {
+        let text = "The quick brown fox jumps over the lazy dog";
+        let [ellipsis_id, clamped_id, fitting_id] = widget_ids();
+        let widget = Flex::column()
+            .with_child_id(
+                SizedBox::new(Label::new(text).with_line_break_mode(LineBreaking::Ellipsis))
+                    .width(100.0),
+                ellipsis_id,
+            )
+            .with_child_id(
+                SizedBox::new(
+                    Label::new(text)
+                        .with_line_break_mode(LineBreaking::WordWrap)
+                        .with_max_lines(2),
+                )
+                .width(100.0),
+                clamped_id,
+            )
+            .with_child_id(
+                SizedBox::new(Label::new("Hello").with_line_break_mode(LineBreaking::Ellipsis))
+                    .width(100.0),
+                fitting_id,
+            );
+
+        let harness = TestHarness::create(widget);
+        let label = |id| {
+            let sized_box = harness.get_widget(id);
+            let sized_box = sized_box.downcast::<SizedBox>().unwrap();
+            let label = sized_box.children()[0].downcast::<Label>().unwrap();
+            (
+                label.displayed_layout().text().unwrap().to_string(),
+                label.displayed_layout().layout().unwrap().line_count(),
+                label.tooltip_text(),
+            )
+        };
+
+        let (displayed, line_count, tooltip) = label(ellipsis_id);
+        assert!(displayed.ends_with(ELLIPSIS));
+        assert_eq!(line_count, 1);
+        assert_eq!(tooltip.as_deref(), Some(text));
+
+        let (displayed, line_count, _) = label(clamped_id);
+        assert!(displayed.ends_with(ELLIPSIS));
+        assert_eq!(line_count, 2);
+
+        let (displayed, _, tooltip) = label(fitting_id);
+        assert_eq!(displayed, "Hello");
+        assert_eq!(tooltip, None);
+    }
+
+    #[test]
+    fn changing_line_break_mode_updates_truncation() {
+        let label = Label::new("The quick brown fox jumps over the lazy dog")
+            .with_line_break_mode(LineBreaking::Ellipsis);
+        let mut harness = TestHarness::create_with_size(label, Size::new(100.0, 100.0));
+
+        // The paint rect covers the tooltip below the label.
+        let root = harness.root_widget();
+        assert!(root.downcast::<Label>().unwrap().is_truncated());
+        let state = root.state();
+        assert!(state.paint_rect().y1 > state.layout_rect().y1);
+
+        harness.edit_root_widget(|mut label, _| {
+            let mut label = label.downcast::<Label>().unwrap();
+            label.set_line_break_mode(LineBreaking::WordWrap);
+        });
+
+        let root = harness.root_widget();
+        assert!(!root.downcast::<Label>().unwrap().is_truncated());
+        let state = root.state();
+        assert_eq!(state.paint_rect(), state.layout_rect());
+    }
+
     #[test]
     fn edit_label() {
         let image_1 = {
@@ -405,4 +842,49 @@ mod tests {
         // We don't use assert_eq because we don't want rich assert
         assert!(image_1 == image_2);
     }
+
+    #[test]
+    fn highlight_and_scroll_to_range() {
+        let text: Vec<_> = (0..40).map(|i| format!("Line {}", i)).collect();
+        let text = text.join("\n");
+        let last_line = text.rfind("Line 39").unwrap();
+        let range = last_line..last_line + 4;
+
+        let widget = Portal::new(Label::new(text));
+        let mut harness = TestHarness::create_with_size(widget, Size::new(100.0, 100.0));
+
+        harness.edit_root_widget(|mut portal, _| {
+            let mut portal = portal.downcast::<Portal<Label>>().unwrap();
+            let mut label = portal.child_mut();
+            label.highlight_ranges(vec![range.clone()]);
+            label.scroll_to_range(range.clone());
+        });
+
+        let portal = harness.root_widget();
+        let portal = portal.downcast::<Portal<Label>>().unwrap();
+        assert_eq!(portal.child().highlighted_ranges(), [range.clone()]);
+        let range_rect = portal.child().rect_for_range(range).unwrap();
+        let viewport = Rect::from_origin_size(portal.get_viewport_pos(), (100.0, 100.0));
+        assert!(viewport.contains(range_rect.center()));
+    }
+
+    #[test]
+    fn spacing_widens_label() {
+        let text = "Hello world";
+        let [plain_id, letters_id, words_id] = widget_ids();
+        let widget = Flex::column()
+            .with_child_id(Label::new(text), plain_id)
+            .with_child_id(Label::new(text).with_letter_spacing(2.0), letters_id)
+            .with_child_id(Label::new(text).with_word_spacing(10.0), words_id);
+        let harness = TestHarness::create(widget);
+
+        let width = |id| harness.get_widget(id).state().layout_rect().width();
+        let plain_width = width(plain_id);
+        // Each of the 11 graphemes is followed by the letter spacing. Graphemes laid
+        // out separately aren't kerned, hence the tolerance.
+        let added = width(letters_id) - plain_width;
+        assert!((added - 22.0).abs() < 1.0, "letter spacing added {added}");
+        let added = width(words_id) - plain_width;
+        assert!((added - 10.0).abs() < 1.0, "word spacing added {added}");
+    }
 }
@@ -0,0 +1,282 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! A scaled-down view of a portal's content.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use smallvec::SmallVec;
+use tracing::{trace, trace_span, Span};
+
+use crate::piet::{Image as _, ImageBuf, InterpolationMode, PietImage};
+use crate::widget::{MemoryUsage, WidgetRef};
+use crate::{
+    theme, BoxConstraints, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx,
+    Point, Rect, RenderContext, Selector, Size, StatusChange, Vec2, Widget, WidgetId,
+};
+
+/// The width of a minimap given unbounded constraints.
+const DEFAULT_WIDTH: f64 = 150.0;
+
+/// Sent by a [`Portal`](crate::widget::Portal) to its minimap after each layout
+/// where its content or viewport changed.
+pub(crate) const MINIMAP_UPDATED: Selector<MinimapUpdate> =
+    Selector::new("masonry-builtin.minimap-updated");
+
+/// Sent by a minimap to its portal to center the viewport on a point of the
+/// portal's content.
+pub(crate) const CENTER_VIEWPORT_ON: Selector<Point> =
+    Selector::new("masonry-builtin.center-viewport-on");
+
+/// A snapshot of a portal's content, painted by the portal and displayed by its minimap.
+#[derive(Default)]
+pub(crate) struct PaintLayer {
+    pub(crate) image: Option<ImageBuf>,
+    /// Incremented each time `image` is repainted.
+    pub(crate) generation: u64,
+}
+
+/// The state of a portal, as seen by its minimap.
+pub(crate) struct MinimapUpdate {
+    pub(crate) portal: WidgetId,
+    /// The size of the portal's child, unzoomed.
+    pub(crate) content_size: Size,
+    /// The visible part of the child, in the child's coordinates.
+    pub(crate) viewport: Rect,
+    pub(crate) layer: Rc<RefCell<PaintLayer>>,
+}
+
+/// A scaled-down view of a [`Portal`](crate::widget::Portal)'s content, with a
+/// rectangle showing the part which is visible.
+///
+/// Link a minimap to a portal with [`Portal::with_minimap`](crate::widget::Portal::with_minimap).
+/// Clicking the minimap centers the portal's viewport on that point; the viewport
+/// rectangle can also be dragged.
+///
+/// The portal paints a snapshot of its content when it changes, and the minimap
+/// displays that snapshot, so the content isn't painted twice on every frame.
+pub struct Minimap {
+    portal: Option<WidgetId>,
+    layer: Option<Rc<RefCell<PaintLayer>>>,
+    content_size: Size,
+    viewport: Rect,
+    drag_offset: Option<Vec2>,
+    paint_data: Option<(u64, PietImage)>,
+}
+crate::declare_widget!(MinimapMut, Minimap);
+
+// --- Minimap impl ---
+
+impl Minimap {
+    /// Create a minimap, which stays empty until a portal is linked to it.
+    pub fn new() -> Self {
+        Minimap {
+            portal: None,
+            layer: None,
+            content_size: Size::ZERO,
+            viewport: Rect::ZERO,
+            drag_offset: None,
+            paint_data: None,
+        }
+    }
+
+    /// The factor from the portal's content to the minimap's coordinates.
+    fn scale(&self, size: Size) -> f64 {
+        if self.content_size.is_empty() {
+            return 1.0;
+        }
+        (size.width / self.content_size.width).min(size.height / self.content_size.height)
+    }
+
+    fn viewport_rect(&self, size: Size) -> Rect {
+        self.viewport.scale_from_origin(self.scale(size))
+    }
+
+    /// Ask the portal to center its viewport on the content under `pos`.
+    fn center_on(&self, ctx: &mut EventCtx, pos: Point) {
+        let Some(portal) = self.portal else {
+            return;
+        };
+        let scale = self.scale(ctx.size());
+        let center = (pos.to_vec2() / scale).to_point();
+        ctx.submit_command(CENTER_VIEWPORT_ON.with(center).to(portal));
+    }
+}
+
+impl Default for Minimap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// --- Trait impls ---
+
+impl Widget for Minimap {
+    fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, _env: &Env) {
+        match event {
+            Event::MouseDown(mouse) if mouse.button.is_left() => {
+                let viewport_rect = self.viewport_rect(ctx.size());
+                // Dragging the viewport rectangle keeps the point under the mouse
+                // in place; clicking elsewhere centers the viewport there.
+                let offset = if viewport_rect.contains(mouse.pos) {
+                    mouse.pos - viewport_rect.center()
+                } else {
+                    Vec2::ZERO
+                };
+                self.drag_offset = Some(offset);
+                self.center_on(ctx, mouse.pos - offset);
+                ctx.set_active(true);
+                ctx.set_handled();
+            }
+            Event::MouseMove(mouse) if ctx.is_active() => {
+                if let Some(offset) = self.drag_offset {
+                    self.center_on(ctx, mouse.pos - offset);
+                }
+                ctx.set_handled();
+            }
+            Event::MouseUp(mouse) if ctx.is_active() && mouse.button.is_left() => {
+                self.drag_offset = None;
+                ctx.set_active(false);
+                ctx.set_handled();
+            }
+            Event::Command(cmd) => {
+                if let Some(update) = cmd.try_get(MINIMAP_UPDATED) {
+                    if self.content_size != update.content_size {
+                        ctx.request_layout();
+                    }
+                    self.portal = Some(update.portal);
+                    self.layer = Some(update.layer.clone());
+                    self.content_size = update.content_size;
+                    self.viewport = update.viewport;
+                    ctx.request_paint();
+                    // The portal repaints its snapshot during the paint pass, maybe
+                    // after we're painted; check for it on the next frame.
+                    ctx.request_anim_frame();
+                    ctx.set_handled();
+                }
+            }
+            Event::AnimFrame(_) => {
+                let painted_generation =
+                    self.paint_data.as_ref().map(|(generation, _)| *generation);
+                let layer_generation = self.layer.as_ref().map(|layer| layer.borrow().generation);
+                if layer_generation != painted_generation {
+                    ctx.request_paint();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange, _env: &Env) {}
+
+    fn lifecycle(&mut self, _ctx: &mut LifeCycleCtx, _event: &LifeCycle, _env: &Env) {}
+
+    fn layout(&mut self, _ctx: &mut LayoutCtx, bc: &BoxConstraints, _env: &Env) -> Size {
+        let width = if bc.is_width_bounded() {
+            bc.max().width
+        } else {
+            DEFAULT_WIDTH
+        };
+        let height = if self.content_size.is_empty() {
+            width
+        } else {
+            width * self.content_size.height / self.content_size.width
+        };
+        let size = bc.constrain(Size::new(width, height));
+        trace!("Computed layout: size={}", size);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, env: &Env) {
+        let size = ctx.size();
+        ctx.fill(size.to_rect(), &env.get(theme::BACKGROUND_DARK));
+
+        let scale = self.scale(size);
+        if let Some(layer) = &self.layer {
+            let layer = layer.borrow();
+            if let Some(image) = &layer.image {
+                let stale = !matches!(&self.paint_data, Some((generation, _)) if *generation == layer.generation);
+                if stale {
+                    self.paint_data = Some((layer.generation, image.to_image(ctx.render_ctx)));
+                }
+                if let Some((_, image)) = &self.paint_data {
+                    let content_rect = (self.content_size * scale).to_rect();
+                    ctx.draw_image(image, content_rect, InterpolationMode::Bilinear);
+                }
+            }
+        }
+
+        if self.portal.is_some() {
+            let viewport_rect = self.viewport_rect(size);
+            let color = env.get(theme::PRIMARY_LIGHT);
+            ctx.fill(viewport_rect, &color.clone().with_alpha(0.2));
+            ctx.stroke(viewport_rect.inset(-0.5), &color, 1.0);
+        }
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        SmallVec::new()
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("Minimap")
+    }
+
+    fn retained_memory(&self, memory: &mut MemoryUsage) {
+        // The snapshot of the portal's content, as 32-bit pixels.
+        if let Some((_, image)) = &self.paint_data {
+            let size = image.size();
+            memory.add(size.width as usize * size.height as usize * 4);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shell::MouseButton;
+    use crate::testing::{widget_ids, TestHarness};
+    use crate::widget::{Flex, Portal, SizedBox};
+
+    #[test]
+    fn minimap_follows_portal_and_pans_it() {
+        let [portal_id, minimap_id] = widget_ids();
+        let widget = Flex::row()
+            .with_child_id(
+                SizedBox::new(
+                    Portal::new(SizedBox::empty().width(100.0).height(1000.0))
+                        .with_minimap(minimap_id),
+                )
+                .width(100.0)
+                .height(100.0),
+                portal_id,
+            )
+            .with_child_id(SizedBox::new(Minimap::new()).width(10.0), minimap_id);
+
+        let mut harness = TestHarness::create_with_size(widget, Size::new(400.0, 400.0));
+        harness.render();
+
+        let minimap = harness.get_widget(minimap_id);
+        let sized_box = minimap.downcast::<SizedBox>().unwrap();
+        let minimap = sized_box.children()[0].downcast::<Minimap>().unwrap();
+        assert_eq!(minimap.content_size, Size::new(100.0, 1000.0));
+        assert_eq!(minimap.viewport, Rect::new(0.0, 0.0, 100.0, 100.0));
+        assert!(minimap.layer.as_ref().unwrap().borrow().image.is_some());
+
+        // The minimap is 10 wide and 100 high; clicking at its bottom centers the
+        // viewport on the bottom of the content, which is as far as it can go.
+        let minimap_rect = harness.get_widget(minimap_id).state().window_layout_rect();
+        harness.mouse_move(Point::new(minimap_rect.center().x, minimap_rect.y1 - 1.0));
+        harness.mouse_button_press(MouseButton::Left);
+        harness.mouse_button_release(MouseButton::Left);
+
+        let portal = harness.get_widget(portal_id);
+        let sized_box = portal.downcast::<SizedBox>().unwrap();
+        let portal = sized_box.children()[0]
+            .downcast::<Portal<SizedBox>>()
+            .unwrap();
+        assert_eq!(portal.get_viewport_pos(), Point::new(0.0, 900.0));
+    }
+}
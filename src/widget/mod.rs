@@ -15,32 +15,71 @@ mod widget_state;
 mod tests;
 
 mod align;
+mod autocomplete;
+mod backdrop_region;
+mod binding;
 mod button;
+mod canvas_2d;
+mod chart;
 mod checkbox;
+mod controller;
+mod dock_area;
+mod elevation;
 mod flex;
+mod flow;
+mod form;
+mod frame_stats_overlay;
 mod image;
+mod interaction_state;
 mod label;
+mod minimap;
 mod portal;
+mod radial;
 mod scroll_bar;
 mod sized_box;
 mod spinner;
 mod split;
+mod sticky_section;
 mod textbox;
+mod titlebar;
+mod toasts;
+#[cfg(feature = "video")]
+mod video;
 
 pub use align::Align;
+pub use autocomplete::{Autocomplete, AutocompleteMut};
+pub use backdrop_region::{BackdropRegion, BackdropRegionMut};
+pub use binding::{Binding, BindingMut};
 pub use button::Button;
+pub use canvas_2d::Canvas2D;
+pub use chart::{Chart, ChartKind};
 pub use checkbox::Checkbox;
+pub use controller::{Controller, ControllerHost};
+pub use dock_area::{DockArea, DockLayout, DockSide};
 pub use flex::{Axis, CrossAxisAlignment, Flex, FlexParams, MainAxisAlignment};
+pub use flow::{Flow, FlowMut};
+pub use form::{FieldId, FieldUpdate, Form, FIELD_UPDATED, SUBMIT_FORM};
+pub use frame_stats_overlay::FrameStatsOverlay;
+pub use interaction_state::InteractionState;
 pub use label::{Label, LineBreaking};
-pub use portal::Portal;
+pub use masonry_derive::StoreInWidgetMut;
+pub use minimap::Minimap;
+pub use portal::{OverscrollEffect, Portal, REFRESH_FINISHED};
+pub use radial::{Radial, RadialMut};
 pub use scroll_bar::ScrollBar;
 pub use sized_box::SizedBox;
 pub use spinner::Spinner;
 pub use split::Split;
+pub use sticky_section::{StickySection, StickySectionMut};
 pub use textbox::TextBox;
+pub(crate) use titlebar::titlebar_zone_at;
+pub use titlebar::{TitlebarRegion, TitlebarRegionMut, TitlebarZone, TITLEBAR_ZONE_HOVERED};
+pub use toasts::{ToastSpec, Toasts, SHOW_TOAST};
+#[cfg(feature = "video")]
+pub use video::Video;
 pub use widget::StoreInWidgetMut;
 #[doc(hidden)]
-pub use widget::{Widget, WidgetId};
+pub use widget::{DeterministicIds, MemoryUsage, Widget, WidgetId};
 //#[doc(hidden)]
 //pub use widget_ext::WidgetExt;
 //pub use widget_wrapper::WidgetWrapper;
@@ -64,6 +103,8 @@ pub(crate) enum FocusChange {
     Next,
     /// Focus should pass to the previous focusable widget
     Previous,
+    /// Focus should pass to the nearest focusable widget in the given direction
+    Direction(crate::gamepad::NavDirection),
 }
 
 /// The possible cursor states for a widget.
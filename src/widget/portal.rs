@@ -4,19 +4,76 @@
 
 #![allow(missing_docs)]
 
+use std::cell::RefCell;
 use std::ops::Range;
+use std::rc::Rc;
 
 use smallvec::{smallvec, SmallVec};
 use tracing::{trace_span, Span};
 
-use crate::kurbo::{Point, Rect, Size, Vec2};
+use druid_shell::Region;
+
+use crate::kurbo::{Affine, Arc, Circle, Ellipse, Point, Rect, Size, Vec2};
+use crate::widget::minimap::{MinimapUpdate, PaintLayer, CENTER_VIEWPORT_ON, MINIMAP_UPDATED};
 use crate::widget::scroll_bar::SCROLLBAR_MOVED;
 use crate::widget::{Axis, ScrollBar, StoreInWidgetMut, WidgetMut, WidgetRef};
 use crate::{
-    BoxConstraints, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx,
-    RenderContext, StatusChange, Widget, WidgetPod,
+    theme, Action, BoxConstraints, Color, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx,
+    PaintCtx, RenderContext, Selector, StatusChange, Value, Widget, WidgetId, WidgetPod,
 };
 
+/// Sent to a portal with pull-to-refresh once the refresh requested with
+/// [`Action::RefreshRequested`] is done, to hide the indicator.
+pub const REFRESH_FINISHED: Selector = Selector::new("masonry-builtin.refresh-finished");
+
+/// Sent by a portal to the portals linked to it, when its viewport moved.
+const SCROLL_LINKED: Selector<ScrollSync> = Selector::new("masonry-builtin.scroll-linked");
+
+/// How much a wheel delta of one pixel changes the zoom factor, with ctrl held.
+const WHEEL_ZOOM_SPEED: f64 = 0.002;
+
+/// The largest dimension, in pixels, of the snapshot painted for a minimap.
+const MINIMAP_LAYER_SIZE: f64 = 512.0;
+
+/// How far the content must be pulled down to request a refresh.
+const PULL_THRESHOLD: f64 = 64.0;
+/// How far the content can be pulled down.
+const MAX_PULL: f64 = 96.0;
+/// How much a wheel delta of one pixel pulls the content.
+const PULL_RESISTANCE: f64 = 0.5;
+/// How fast the content goes back up once released, in pixels per second.
+const PULL_RELEASE_SPEED: f64 = 240.0;
+/// The radius of the pull-to-refresh indicator.
+const REFRESH_INDICATOR_RADIUS: f64 = 10.0;
+
+/// How much a wheel delta of one pixel past the edge adds to the overscroll.
+const OVERSCROLL_RESISTANCE: f64 = 0.5;
+/// How fast the overscroll fades once released; it's divided by e every
+/// `1 / OVERSCROLL_DECAY` seconds.
+const OVERSCROLL_DECAY: f64 = 8.0;
+
+/// What a [`Portal`] shows when scrolled past the edge of its content.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverscrollEffect {
+    /// Nothing, the content stops at the edge.
+    None,
+    /// A glow along the edge, colored with [`theme::OVERSCROLL_GLOW_COLOR`].
+    Glow,
+    /// The content is stretched away from the edge.
+    Stretch,
+}
+
+impl Default for OverscrollEffect {
+    /// The effect of the platform: a stretch on macOS, nothing on other desktops.
+    fn default() -> Self {
+        if cfg!(target_os = "macos") {
+            OverscrollEffect::Stretch
+        } else {
+            OverscrollEffect::None
+        }
+    }
+}
+
 // TODO - refactor - see issue #15
 // TODO - rename "Portal" to "ScrollPortal"?
 // Conceptually, a Portal is a Widget giving a restricted view of a child widget
@@ -36,6 +93,58 @@ pub struct Portal<W: Widget> {
     scrollbar_horizontal_visible: bool,
     scrollbar_vertical: WidgetPod<ScrollBar>,
     scrollbar_vertical_visible: bool,
+    // The viewport pos is in zoomed coordinates: at a zoom of 2, scrolling by
+    // 10 pixels moves the child by 5 of its own pixels.
+    zoom: f64,
+    zoomable: bool,
+    min_zoom: f64,
+    max_zoom: f64,
+    mouse_pos: Option<Point>,
+    minimap: Option<MinimapLink>,
+    scroll_links: Vec<ScrollLink>,
+    pull_to_refresh: Option<PullToRefresh>,
+    overscroll_effect: OverscrollEffect,
+    /// How far the user scrolled past the edges; negative values are past the top
+    /// or the left.
+    overscroll: Vec2,
+}
+
+/// The state of the pull-to-refresh gesture.
+#[derive(Default)]
+struct PullToRefresh {
+    /// How far the content is pulled down past its top.
+    pull: f64,
+    /// Whether the app is refreshing the content, until it sends [`REFRESH_FINISHED`].
+    refreshing: bool,
+    /// The rotation of the indicator while refreshing, in turns.
+    phase: f64,
+}
+
+/// Another portal whose viewport follows ours on one axis.
+struct ScrollLink {
+    id: WidgetId,
+    axis: Axis,
+    /// The offset last sent to the other portal, or received from it.
+    ///
+    /// Offsets are only sent when they differ from this one, so that a position
+    /// received from a portal isn't sent back to it.
+    last_offset: Option<f64>,
+}
+
+/// The payload of [`SCROLL_LINKED`].
+struct ScrollSync {
+    source: WidgetId,
+    axis: Axis,
+    offset: f64,
+}
+
+/// A [`Minimap`](super::Minimap) displaying the portal's content.
+struct MinimapLink {
+    id: WidgetId,
+    layer: Rc<RefCell<PaintLayer>>,
+    layer_dirty: bool,
+    /// The content size and viewport last sent to the minimap.
+    last_sent: Option<(Size, Rect)>,
 }
 
 crate::declare_widget!(PortalMut, Portal<W: (Widget)>);
@@ -53,6 +162,16 @@ impl<W: Widget> Portal<W> {
             scrollbar_horizontal_visible: false,
             scrollbar_vertical: WidgetPod::new(ScrollBar::new(Axis::Vertical, 1.0, 1.0)),
             scrollbar_vertical_visible: false,
+            zoom: 1.0,
+            zoomable: false,
+            min_zoom: 0.1,
+            max_zoom: 10.0,
+            mouse_pos: None,
+            minimap: None,
+            scroll_links: Vec::new(),
+            pull_to_refresh: None,
+            overscroll_effect: OverscrollEffect::default(),
+            overscroll: Vec2::ZERO,
         }
     }
 
@@ -60,6 +179,79 @@ impl<W: Widget> Portal<W> {
         self.viewport_pos
     }
 
+    /// The factor the child is scaled by. The default is 1.0.
+    pub fn zoom(&self) -> f64 {
+        self.zoom
+    }
+
+    /// Builder-style method to let the user zoom with ctrl+wheel and pinch gestures.
+    ///
+    /// The default is `false`. Each change of zoom from user input emits an
+    /// [`Action::ZoomChanged`].
+    pub fn zoomable(mut self, zoomable: bool) -> Self {
+        self.zoomable = zoomable;
+        self
+    }
+
+    /// Builder-style method to set the range the zoom factor is clamped to.
+    ///
+    /// The default is `0.1..=10.0`.
+    pub fn zoom_range(mut self, min: f64, max: f64) -> Self {
+        self.min_zoom = min;
+        self.max_zoom = max;
+        self.zoom = self.zoom.clamp(min, max);
+        self
+    }
+
+    /// Builder-style method to let the user pull the content down past its top to
+    /// refresh it, with the mouse wheel or a touchpad.
+    ///
+    /// The default is `false`. Pulling far enough shows a spinning indicator and emits an
+    /// [`Action::RefreshRequested`]; the indicator stays until the portal receives
+    /// [`REFRESH_FINISHED`].
+    pub fn pull_to_refresh(mut self, enabled: bool) -> Self {
+        self.pull_to_refresh = enabled.then(PullToRefresh::default);
+        self
+    }
+
+    /// Builder-style method to set what the portal shows when scrolled past the edge
+    /// of its content.
+    ///
+    /// The default is the platform's effect, see [`OverscrollEffect::default`]. There is
+    /// no effect when [`reduced_motion`](EventCtx::reduced_motion) is set.
+    pub fn overscroll_effect(mut self, effect: OverscrollEffect) -> Self {
+        self.overscroll_effect = effect;
+        self
+    }
+
+    /// Builder-style method to display the portal's content in the [`Minimap`](super::Minimap)
+    /// with the given id.
+    ///
+    /// The minimap and the portal stay in sync: panning either one moves the other.
+    pub fn with_minimap(mut self, minimap: WidgetId) -> Self {
+        self.minimap = Some(MinimapLink {
+            id: minimap,
+            layer: Rc::default(),
+            layer_dirty: true,
+            last_sent: None,
+        });
+        self
+    }
+
+    /// Builder-style method to make the portal with the given id follow this portal's
+    /// scroll offset on `axis`.
+    ///
+    /// The link goes one way: link the other portal back to this one to keep them in
+    /// sync whichever is scrolled, eg a row-header pane and the main grid.
+    pub fn with_linked_scroll(mut self, portal: WidgetId, axis: Axis) -> Self {
+        self.scroll_links.push(ScrollLink {
+            id: portal,
+            axis,
+            last_offset: None,
+        });
+        self
+    }
+
     pub fn child(&self) -> WidgetRef<'_, W> {
         self.child.as_ref()
     }
@@ -129,7 +321,113 @@ fn compute_pan_range(mut viewport: Range<f64>, target: Range<f64>) -> Range<f64>
     viewport
 }
 
+impl PullToRefresh {
+    /// Pull or release the content with a vertical wheel delta.
+    ///
+    /// Returns the part of the delta left to scroll the viewport.
+    fn on_wheel(&mut self, viewport_y: f64, delta_y: f64) -> f64 {
+        if delta_y < 0.0 && viewport_y <= 0.0 {
+            self.pull = (self.pull - delta_y * PULL_RESISTANCE).min(MAX_PULL);
+            0.0
+        } else if delta_y > 0.0 {
+            // The indicator stays visible while refreshing.
+            let min_pull = if self.refreshing { PULL_THRESHOLD } else { 0.0 };
+            let released = delta_y.min(self.pull - min_pull).max(0.0);
+            self.pull -= released;
+            delta_y - released
+        } else {
+            delta_y
+        }
+    }
+
+    /// Move the content back up, and spin the indicator.
+    ///
+    /// Returns `true` while the animation should go on.
+    fn animate(&mut self, seconds: f64) -> bool {
+        let rest = if self.refreshing { PULL_THRESHOLD } else { 0.0 };
+        self.pull = (self.pull - PULL_RELEASE_SPEED * seconds).max(rest.min(self.pull));
+        if self.refreshing {
+            self.phase = (self.phase + seconds).fract();
+        }
+        self.refreshing || self.pull > 0.0
+    }
+
+    fn paint_indicator(&self, ctx: &mut PaintCtx, env: &Env) {
+        let progress = (self.pull / PULL_THRESHOLD).min(1.0);
+        let center = Point::new(ctx.size().width / 2.0, self.pull / 2.0);
+        let radius = REFRESH_INDICATOR_RADIUS;
+        ctx.fill(
+            Circle::new(center, radius * 1.5),
+            &env.get(theme::BACKGROUND_LIGHT),
+        );
+        let start_angle = if self.refreshing {
+            self.phase * std::f64::consts::TAU
+        } else {
+            -std::f64::consts::FRAC_PI_2
+        };
+        let arc = Arc {
+            center,
+            radii: Vec2::new(radius, radius),
+            start_angle,
+            sweep_angle: progress * 0.8 * std::f64::consts::TAU,
+            x_rotation: 0.0,
+        };
+        ctx.stroke(arc, &env.get(theme::PRIMARY_LIGHT), 2.5);
+    }
+}
+
 impl<W: Widget> Portal<W> {
+    /// The transform stretching the content for the [`OverscrollEffect::Stretch`] effect.
+    fn overscroll_stretch(&self, portal_size: Size) -> Affine {
+        if self.overscroll_effect != OverscrollEffect::Stretch || self.overscroll == Vec2::ZERO {
+            return Affine::IDENTITY;
+        }
+        let overscroll = self.overscroll;
+        let scale_x = 1.0 + overscroll.x.abs() / portal_size.width.max(1.0);
+        let scale_y = 1.0 + overscroll.y.abs() / portal_size.height.max(1.0);
+        // The content is stretched from the edge the user scrolled past.
+        let anchor = Vec2::new(
+            if overscroll.x > 0.0 {
+                portal_size.width
+            } else {
+                0.0
+            },
+            if overscroll.y > 0.0 {
+                portal_size.height
+            } else {
+                0.0
+            },
+        );
+        Affine::translate(anchor)
+            * Affine::scale_non_uniform(scale_x, scale_y)
+            * Affine::translate(-anchor)
+    }
+
+    fn paint_overscroll_glow(&self, ctx: &mut PaintCtx, env: &Env) {
+        let size = ctx.size();
+        let max_distance = env.get(theme::OVERSCROLL_MAX_DISTANCE);
+        let (r, g, b, a) = env.get(theme::OVERSCROLL_GLOW_COLOR).as_rgba();
+        let mut glow = |center: Point, radii: Vec2, distance: f64| {
+            let strength = (distance.abs() / max_distance).min(1.0);
+            ctx.fill(
+                Ellipse::new(center, radii, 0.0),
+                &Color::rgba(r, g, b, a * strength),
+            );
+        };
+
+        let overscroll = self.overscroll;
+        if overscroll.y != 0.0 {
+            let y = if overscroll.y < 0.0 { 0.0 } else { size.height };
+            let radii = Vec2::new(size.width * 0.75, overscroll.y.abs());
+            glow(Point::new(size.width / 2.0, y), radii, overscroll.y);
+        }
+        if overscroll.x != 0.0 {
+            let x = if overscroll.x < 0.0 { 0.0 } else { size.width };
+            let radii = Vec2::new(overscroll.x.abs(), size.height * 0.75);
+            glow(Point::new(x, size.height / 2.0), radii, overscroll.x);
+        }
+    }
+
     // TODO - rename
     fn set_viewport_pos_raw(&mut self, portal_size: Size, content_size: Size, pos: Point) -> bool {
         let viewport_max_pos =
@@ -146,6 +444,76 @@ impl<W: Widget> Portal<W> {
             false
         }
     }
+
+    /// Set the zoom factor, keeping the content under `anchor` in place.
+    ///
+    /// `anchor` is in the portal's coordinates. The viewport pos is clamped in the
+    /// next layout pass.
+    fn set_zoom_raw(&mut self, zoom: f64, anchor: Point) -> bool {
+        let zoom = zoom.clamp(self.min_zoom, self.max_zoom);
+        if (zoom - self.zoom).abs() < 1e-9 {
+            return false;
+        }
+        let content_point = (self.viewport_pos + anchor.to_vec2()).to_vec2() / self.zoom;
+        self.viewport_pos = (content_point * zoom - anchor.to_vec2()).to_point();
+        self.zoom = zoom;
+        true
+    }
+
+    /// Handle the child invalidating part of its paint rect.
+    fn child_invalidated(&mut self, request_paint: impl FnOnce()) {
+        if let Some(minimap) = &mut self.minimap {
+            minimap.layer_dirty = true;
+            minimap.last_sent = None;
+        }
+        // The child's invalidated rects aren't scaled; repaint the whole viewport instead.
+        if self.zoom != 1.0 {
+            request_paint();
+        }
+    }
+
+    /// The size of the child once zoomed.
+    fn zoomed_content_size(&self) -> Size {
+        self.child.layout_rect().size() * self.zoom
+    }
+
+    /// The event to send to the child, if it must be scaled to the child's coordinates.
+    fn unzoom_event(&self, event: &Event) -> Option<Event> {
+        if self.zoom == 1.0 {
+            return None;
+        }
+        let unzoom = |mouse_event: &crate::MouseEvent| {
+            let mut mouse_event = mouse_event.clone();
+            mouse_event.pos = (mouse_event.pos.to_vec2() / self.zoom).to_point();
+            mouse_event
+        };
+        match event {
+            Event::MouseDown(e) => Some(Event::MouseDown(unzoom(e))),
+            Event::MouseUp(e) => Some(Event::MouseUp(unzoom(e))),
+            Event::MouseMove(e) => Some(Event::MouseMove(unzoom(e))),
+            Event::Wheel(e) => Some(Event::Wheel(unzoom(e))),
+            _ => None,
+        }
+    }
+
+    /// The smallest move of the viewport that brings `target` into view.
+    // Note - Rect is in child coordinates
+    fn viewport_pos_for_target(&self, portal_size: Size, target: Rect) -> Point {
+        let viewport = Rect::from_origin_size(self.viewport_pos, portal_size);
+
+        let new_pos_x = compute_pan_range(
+            viewport.min_x()..viewport.max_x(),
+            target.min_x()..target.max_x(),
+        )
+        .start;
+        let new_pos_y = compute_pan_range(
+            viewport.min_y()..viewport.max_y(),
+            target.min_y()..target.max_y(),
+        )
+        .start;
+
+        Point::new(new_pos_x, new_pos_y)
+    }
 }
 
 impl<'a, 'b, W: Widget> PortalMut<'a, 'b, W> {
@@ -198,7 +566,7 @@ impl<'a, 'b, W: Widget> PortalMut<'a, 'b, W> {
 
     pub fn set_viewport_pos(&mut self, position: Point) -> bool {
         let portal_size = self.ctx.widget_state.layout_rect().size();
-        let content_size = self.widget.child.layout_rect().size();
+        let content_size = self.widget.zoomed_content_size();
 
         let pos_changed = self
             .widget
@@ -221,40 +589,165 @@ impl<'a, 'b, W: Widget> PortalMut<'a, 'b, W> {
 
     // Note - Rect is in child coordinates
     pub fn pan_viewport_to(&mut self, target: Rect) -> bool {
-        let viewport = Rect::from_origin_size(self.widget.viewport_pos, self.ctx.widget_state.size);
+        let target = target.scale_from_origin(self.widget.zoom);
+        let new_pos = self
+            .widget
+            .viewport_pos_for_target(self.ctx.widget_state.size, target);
+        self.set_viewport_pos(new_pos)
+    }
 
-        let new_pos_x = compute_pan_range(
-            viewport.min_x()..viewport.max_x(),
-            target.min_x()..target.max_x(),
-        )
-        .start;
-        let new_pos_y = compute_pan_range(
-            viewport.min_y()..viewport.max_y(),
-            target.min_y()..target.max_y(),
-        )
-        .start;
+    /// Make the portal with the given id follow this portal's scroll offset on `axis`.
+    ///
+    /// See [`Portal::with_linked_scroll`].
+    pub fn link_scroll(&mut self, portal: WidgetId, axis: Axis) {
+        self.widget.scroll_links.push(ScrollLink {
+            id: portal,
+            axis,
+            last_offset: None,
+        });
+        self.ctx.request_layout();
+    }
 
-        self.set_viewport_pos(Point::new(new_pos_x, new_pos_y))
+    /// Stop sending this portal's scroll offset to the portal with the given id.
+    pub fn unlink_scroll(&mut self, portal: WidgetId) {
+        self.widget.scroll_links.retain(|link| link.id != portal);
+    }
+
+    /// Set the factor the child is scaled by, keeping the center of the viewport in place.
+    ///
+    /// The zoom is clamped to the portal's [`zoom_range`](Portal::zoom_range).
+    pub fn set_zoom(&mut self, zoom: f64) -> bool {
+        let anchor = (self.ctx.widget_state.size.to_vec2() / 2.0).to_point();
+        let zoom_changed = self.widget.set_zoom_raw(zoom, anchor);
+        if zoom_changed {
+            self.ctx.request_layout();
+            self.ctx.request_paint();
+        }
+        zoom_changed
     }
 }
 
 impl<W: Widget> Widget for Portal<W> {
     fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, env: &Env) {
         let portal_size = ctx.size();
-        let content_size = self.child.layout_rect().size();
+        let content_size = self.zoomed_content_size();
 
         // TODO - handle Home/End keys, etc
         match event {
+            Event::MouseMove(mouse_event) => {
+                self.mouse_pos = Some(mouse_event.pos);
+            }
+            Event::Wheel(wheel_event) if self.zoomable && wheel_event.mods.ctrl() => {
+                let zoom = self.zoom * (-wheel_event.wheel_delta.y * WHEEL_ZOOM_SPEED).exp();
+                if self.set_zoom_raw(zoom, wheel_event.pos) {
+                    ctx.submit_action(Action::ZoomChanged(self.zoom));
+                    ctx.request_paint();
+                }
+                ctx.set_handled();
+            }
+            Event::Zoom(delta) if self.zoomable => {
+                let anchor = self
+                    .mouse_pos
+                    .unwrap_or_else(|| (portal_size.to_vec2() / 2.0).to_point());
+                if self.set_zoom_raw(self.zoom * (1.0 + delta), anchor) {
+                    ctx.submit_action(Action::ZoomChanged(self.zoom));
+                    ctx.request_paint();
+                }
+                ctx.set_handled();
+            }
             Event::Wheel(wheel_event) => {
-                self.set_viewport_pos_raw(
-                    portal_size,
-                    content_size,
-                    self.viewport_pos + wheel_event.wheel_delta,
-                );
+                let mut wheel_delta = wheel_event.wheel_delta;
+                if let Some(refresh) = &mut self.pull_to_refresh {
+                    wheel_delta.y = refresh.on_wheel(self.viewport_pos.y, wheel_delta.y);
+                    if refresh.pull >= PULL_THRESHOLD && !refresh.refreshing {
+                        refresh.refreshing = true;
+                        ctx.submit_action(Action::RefreshRequested);
+                    }
+                    if refresh.pull > 0.0 {
+                        ctx.request_anim_frame();
+                        ctx.request_paint();
+                    }
+                }
+                let target_pos = self.viewport_pos + wheel_delta;
+                self.set_viewport_pos_raw(portal_size, content_size, target_pos);
+                if self.overscroll_effect != OverscrollEffect::None && !ctx.reduced_motion() {
+                    let max_distance = env.get(theme::OVERSCROLL_MAX_DISTANCE);
+                    let overscroll =
+                        self.overscroll + (target_pos - self.viewport_pos) * OVERSCROLL_RESISTANCE;
+                    self.overscroll = Vec2::new(
+                        overscroll.x.clamp(-max_distance, max_distance),
+                        overscroll.y.clamp(-max_distance, max_distance),
+                    );
+                    if self.overscroll != Vec2::ZERO {
+                        ctx.request_anim_frame();
+                        ctx.request_paint();
+                    }
+                }
                 // TODO - horizontal scrolling?
                 ctx.get_mut(&mut self.scrollbar_vertical)
                     .set_cursor_progress(self.viewport_pos.y / (content_size - portal_size).height);
             }
+            Event::AnimFrame(interval) => {
+                let seconds = *interval as f64 * 1e-9;
+                let mut animating = false;
+                if let Some(refresh) = &mut self.pull_to_refresh {
+                    animating |= refresh.animate(seconds);
+                    ctx.request_paint();
+                }
+                if self.overscroll != Vec2::ZERO {
+                    self.overscroll *= (-seconds * OVERSCROLL_DECAY).exp();
+                    if self.overscroll.hypot() < 0.5 {
+                        self.overscroll = Vec2::ZERO;
+                    }
+                    animating |= self.overscroll != Vec2::ZERO;
+                    ctx.request_paint();
+                }
+                if animating {
+                    ctx.request_anim_frame();
+                }
+            }
+            Event::Command(cmd) if cmd.is(REFRESH_FINISHED) => {
+                if let Some(refresh) = &mut self.pull_to_refresh {
+                    refresh.refreshing = false;
+                    ctx.request_anim_frame();
+                }
+                ctx.set_handled();
+            }
+            Event::Command(cmd) => {
+                if let Some(center) = cmd.try_get(CENTER_VIEWPORT_ON) {
+                    let pos = center.to_vec2() * self.zoom - portal_size.to_vec2() / 2.0;
+                    if self.set_viewport_pos_raw(portal_size, content_size, pos.to_point()) {
+                        let progress_x = self.viewport_pos.x / (content_size - portal_size).width;
+                        ctx.get_mut(&mut self.scrollbar_horizontal)
+                            .set_cursor_progress(progress_x);
+                        let progress_y = self.viewport_pos.y / (content_size - portal_size).height;
+                        ctx.get_mut(&mut self.scrollbar_vertical)
+                            .set_cursor_progress(progress_y);
+                    }
+                    ctx.set_handled();
+                } else if let Some(sync) = cmd.try_get(SCROLL_LINKED) {
+                    let pos = sync
+                        .axis
+                        .pack(sync.offset, sync.axis.minor_pos(self.viewport_pos));
+                    if self.set_viewport_pos_raw(portal_size, content_size, pos.into()) {
+                        let progress_x = self.viewport_pos.x / (content_size - portal_size).width;
+                        ctx.get_mut(&mut self.scrollbar_horizontal)
+                            .set_cursor_progress(progress_x);
+                        let progress_y = self.viewport_pos.y / (content_size - portal_size).height;
+                        ctx.get_mut(&mut self.scrollbar_vertical)
+                            .set_cursor_progress(progress_y);
+                    }
+                    // Don't send the position back to the portal it comes from, even
+                    // if we clamped it differently.
+                    let offset = sync.axis.major_pos(self.viewport_pos);
+                    for link in &mut self.scroll_links {
+                        if link.id == sync.source && link.axis == sync.axis {
+                            link.last_offset = Some(offset);
+                        }
+                    }
+                    ctx.set_handled();
+                }
+            }
             Event::Notification(notif) => {
                 if let Some((axis, progress)) = notif.try_get(SCROLLBAR_MOVED) {
                     self.viewport_pos = axis
@@ -269,7 +762,13 @@ impl<W: Widget> Widget for Portal<W> {
             _ => (),
         }
 
-        self.child.on_event(ctx, event, env);
+        let invalid_rects = ctx.widget_state.invalid.rects().len();
+        let child_event = self.unzoom_event(event);
+        self.child
+            .on_event(ctx, child_event.as_ref().unwrap_or(event), env);
+        if ctx.widget_state.invalid.rects().len() != invalid_rects {
+            self.child_invalidated(|| ctx.request_paint());
+        }
         self.scrollbar_horizontal.on_event(ctx, event, env);
         self.scrollbar_vertical.on_event(ctx, event, env);
         ctx.request_layout();
@@ -282,12 +781,36 @@ impl<W: Widget> Widget for Portal<W> {
             LifeCycle::WidgetAdded => {
                 ctx.register_as_portal();
             }
-            //TODO
-            //LifeCycle::RequestPanToChild(target_rect) => {}
+            LifeCycle::RequestPanToChild(target_rect) => {
+                // The rect is in our coordinate space; translate it to the child's,
+                // then to zoomed coordinates.
+                let target_rect = (*target_rect - self.child.layout_rect().origin().to_vec2())
+                    .scale_from_origin(self.zoom);
+                let portal_size = ctx.size();
+                let content_size = self.zoomed_content_size();
+                let new_pos = self.viewport_pos_for_target(portal_size, target_rect);
+                if self.set_viewport_pos_raw(portal_size, content_size, new_pos) {
+                    let progress_x = self.viewport_pos.x / (content_size - portal_size).width;
+                    ctx.get_mut(&mut self.scrollbar_horizontal)
+                        .set_cursor_progress(progress_x);
+                    let progress_y = self.viewport_pos.y / (content_size - portal_size).height;
+                    ctx.get_mut(&mut self.scrollbar_vertical)
+                        .set_cursor_progress(progress_y);
+                    ctx.request_layout();
+                }
+            }
             _ => {}
         }
 
+        let invalid_rects = ctx.widget_state.invalid.rects().len();
         self.child.lifecycle(ctx, event, env);
+        if ctx.widget_state.invalid.rects().len() != invalid_rects {
+            self.child_invalidated(|| ctx.request_paint());
+            if self.minimap.is_some() {
+                // The minimap is sent the new snapshot after the next layout.
+                ctx.request_layout();
+            }
+        }
         self.scrollbar_horizontal.lifecycle(ctx, event, env);
         self.scrollbar_vertical.lifecycle(ctx, event, env);
     }
@@ -302,9 +825,33 @@ impl<W: Widget> Widget for Portal<W> {
             max_child_size.height = f64::INFINITY
         };
 
-        let child_bc = BoxConstraints::new(min_child_size, max_child_size);
+        // The child is laid out in its own, unzoomed, coordinates.
+        let child_bc = BoxConstraints::new(min_child_size / self.zoom, max_child_size / self.zoom);
+        let mouse_pos = ctx.mouse_pos;
+        ctx.mouse_pos = mouse_pos.map(|pos| (pos.to_vec2() / self.zoom).to_point());
 
-        let content_size = self.child.layout(ctx, &child_bc, env);
+        // Only the part of the child within our bounds is visible.
+        let viewport = ctx.viewport;
+        let visible_rect = if bc.is_width_bounded() && bc.is_height_bounded() {
+            let own_rect = bc.max().to_rect();
+            Some(viewport.map_or(own_rect, |rect| rect.intersect(own_rect)))
+        } else {
+            viewport
+        };
+        // The child's layout translates the viewport by its previous origin, which is
+        // stale if we just panned; compensate so the child sees its visible rect.
+        let child_prev_origin = self.child.state.origin.to_vec2();
+        ctx.viewport = visible_rect.map(|rect| {
+            (rect + self.viewport_pos.to_vec2()).scale_from_origin(1.0 / self.zoom)
+                + child_prev_origin
+        });
+
+        let invalid_rects = ctx.widget_state.invalid.rects().len();
+        let content_size = self.child.layout(ctx, &child_bc, env) * self.zoom;
+        if ctx.widget_state.invalid.rects().len() != invalid_rects {
+            let paint_rect = ctx.widget_state.paint_rect() - ctx.widget_state.origin.to_vec2();
+            self.child_invalidated(|| ctx.widget_state.invalid.set_rect(paint_rect));
+        }
         let portal_size = bc.constrain(content_size);
 
         // TODO - document better
@@ -312,7 +859,16 @@ impl<W: Widget> Widget for Portal<W> {
         self.set_viewport_pos_raw(portal_size, content_size, self.viewport_pos);
         // TODO - recompute portal progress
 
-        ctx.place_child(&mut self.child, Point::new(0.0, -self.viewport_pos.y), env);
+        let pull = self
+            .pull_to_refresh
+            .as_ref()
+            .map_or(0.0, |refresh| refresh.pull);
+        let child_origin =
+            ((Vec2::new(0.0, pull) - self.viewport_pos.to_vec2()) / self.zoom).to_point();
+        ctx.set_child_scale(&mut self.child, self.zoom);
+        ctx.place_child(&mut self.child, child_origin, env);
+        ctx.mouse_pos = mouse_pos;
+        ctx.viewport = viewport;
 
         self.scrollbar_horizontal_visible =
             !self.constrain_horizontal && portal_size.width < content_size.width;
@@ -344,6 +900,45 @@ impl<W: Widget> Widget for Portal<W> {
             ctx.skip_child(&mut self.scrollbar_vertical);
         }
 
+        for link in &mut self.scroll_links {
+            let offset = link.axis.major_pos(self.viewport_pos);
+            if link
+                .last_offset
+                .map_or(true, |last_offset| (offset - last_offset).abs() > 1e-9)
+            {
+                link.last_offset = Some(offset);
+                let sync = ScrollSync {
+                    source: ctx.widget_id(),
+                    axis: link.axis,
+                    offset,
+                };
+                ctx.submit_command(SCROLL_LINKED.with(sync).to(link.id));
+            }
+        }
+
+        if let Some(minimap) = &mut self.minimap {
+            let state = (
+                self.child.layout_rect().size(),
+                Rect::from_origin_size(
+                    (self.viewport_pos.to_vec2() / self.zoom).to_point(),
+                    portal_size / self.zoom,
+                ),
+            );
+            if minimap.last_sent.map(|(size, _)| size) != Some(state.0) {
+                minimap.layer_dirty = true;
+            }
+            if minimap.last_sent != Some(state) {
+                minimap.last_sent = Some(state);
+                let update = MinimapUpdate {
+                    portal: ctx.widget_id(),
+                    content_size: state.0,
+                    viewport: state.1,
+                    layer: minimap.layer.clone(),
+                };
+                ctx.submit_command(MINIMAP_UPDATED.with(update).to(minimap.id));
+            }
+        }
+
         portal_size
     }
 
@@ -352,7 +947,50 @@ impl<W: Widget> Widget for Portal<W> {
         let clip_rect = ctx.size().to_rect();
         ctx.clip(clip_rect);
 
-        self.child.paint(ctx, env);
+        let transform = self.overscroll_stretch(ctx.size()) * Affine::scale(self.zoom);
+        if transform == Affine::IDENTITY {
+            self.child.paint(ctx, env);
+        } else {
+            let mut region = Region::EMPTY;
+            let inverse = transform.inverse();
+            for rect in ctx.region().rects() {
+                region.add_rect(inverse.transform_rect_bbox(*rect));
+            }
+            let child = &mut self.child;
+            ctx.with_save(|ctx| {
+                ctx.transform(transform);
+                ctx.with_child_ctx(region, |ctx| child.paint(ctx, env));
+            });
+        }
+
+        if self.overscroll_effect == OverscrollEffect::Glow && self.overscroll != Vec2::ZERO {
+            self.paint_overscroll_glow(ctx, env);
+        }
+
+        if let Some(refresh) = self
+            .pull_to_refresh
+            .as_ref()
+            .filter(|refresh| refresh.pull > 0.0)
+        {
+            refresh.paint_indicator(ctx, env);
+        }
+
+        if let Some(minimap) = self.minimap.as_mut().filter(|minimap| minimap.layer_dirty) {
+            let content_size = self.child.layout_rect().size();
+            let largest_side = content_size.width.max(content_size.height);
+            if content_size.is_empty() || !largest_side.is_finite() {
+                minimap.layer.borrow_mut().image = None;
+            } else {
+                let scale = (MINIMAP_LAYER_SIZE / largest_side).min(1.0);
+                let child = &mut self.child;
+                let image =
+                    ctx.paint_to_image(content_size, scale, |ctx| child.paint_raw(ctx, env));
+                let mut layer = minimap.layer.borrow_mut();
+                layer.image = image;
+                layer.generation += 1;
+            }
+            minimap.layer_dirty = false;
+        }
 
         if self.scrollbar_horizontal_visible {
             self.scrollbar_horizontal.paint(ctx, env);
@@ -373,16 +1011,33 @@ impl<W: Widget> Widget for Portal<W> {
     fn make_trace_span(&self) -> Span {
         trace_span!("Portal")
     }
+
+    fn save_state(&self) -> Option<Value> {
+        Some(Value::Point(self.viewport_pos))
+    }
+
+    fn restore_state(&mut self, ctx: &mut LifeCycleCtx, state: &Value, _env: &Env) {
+        if let Value::Point(viewport_pos) = state {
+            // The position is clamped to the content size in the next layout pass.
+            self.viewport_pos = *viewport_pos;
+            ctx.request_layout();
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use druid_shell::{Modifiers, MouseButton};
     use insta::assert_debug_snapshot;
 
     use super::*;
     use crate::assert_render_snapshot;
-    use crate::testing::{widget_ids, TestHarness};
+    use crate::testing::{mouse_scroll, widget_ids, ModularWidget, TestHarness, TestWidgetExt};
     use crate::widget::{Button, Flex, SizedBox};
+    use crate::{MouseEvent, Selector};
 
     fn button(text: &str) -> impl Widget {
         SizedBox::new(Button::new(text)).width(70.0).height(40.0)
@@ -453,6 +1108,263 @@ mod tests {
         assert_render_snapshot!(harness, "button_list_scroll_to_item_13");
     }
 
+    #[test]
+    fn pan_to_focused_widget() {
+        const REQUEST_FOCUS: Selector = Selector::new("masonry-test.request-focus");
+
+        let [item_id] = widget_ids();
+        let focusable = ModularWidget::new(())
+            .event_fn(|_, ctx, event, _| {
+                if let Event::Command(cmd) = event {
+                    if cmd.is(REQUEST_FOCUS) {
+                        ctx.request_focus();
+                    }
+                }
+            })
+            .lifecycle_fn(|_, ctx, event, _| {
+                if let LifeCycle::BuildFocusChain = event {
+                    ctx.register_for_focus();
+                }
+            })
+            .layout_fn(|_, _, _, _| Size::new(70.0, 40.0));
+
+        let widget = Portal::new(
+            Flex::column()
+                .with_spacer(1000.0)
+                .with_child_id(focusable, item_id),
+        );
+        let mut harness = TestHarness::create_with_size(widget, Size::new(400., 400.));
+
+        harness.submit_command(REQUEST_FOCUS.to(item_id));
+
+        let viewport_pos = harness
+            .root_widget()
+            .downcast::<Portal<Flex>>()
+            .unwrap()
+            .get_viewport_pos();
+        assert_eq!(viewport_pos, Point::new(0.0, 640.0));
+    }
+
+    #[test]
+    fn restore_viewport_from_ui_state() {
+        let [portal_id] = widget_ids();
+        let make_portal = || {
+            Flex::row().with_child_id(
+                Portal::new(SizedBox::empty().width(100.0).height(1000.0)),
+                portal_id,
+            )
+        };
+
+        let mut harness = TestHarness::create_with_size(make_portal(), Size::new(400., 400.));
+        harness.edit_root_widget(|mut root, _| {
+            let mut flex = root.downcast::<Flex>().unwrap();
+            let mut child = flex.child_mut(0).unwrap();
+            let mut portal = child.downcast::<Portal<SizedBox>>().unwrap();
+            portal.set_viewport_pos(Point::new(0.0, 300.0));
+        });
+
+        let mut state = harness.window().save_ui_state();
+        assert!(state.widgets.contains_key(&portal_id));
+
+        // Only restore widget states, since the harness window doesn't have a real position.
+        state.geometry = None;
+        let mut harness = TestHarness::create_with_size(make_portal(), Size::new(400., 400.));
+        harness.restore_ui_state(state);
+
+        let viewport_pos = harness
+            .get_widget(portal_id)
+            .downcast::<Portal<SizedBox>>()
+            .unwrap()
+            .get_viewport_pos();
+        assert_eq!(viewport_pos, Point::new(0.0, 300.0));
+    }
+
+    #[test]
+    fn zoomed_child_receives_scaled_mouse_pos() {
+        let click_pos = Rc::new(Cell::new(None));
+        let child = ModularWidget::new(click_pos.clone())
+            .event_fn(|click_pos, _, event, _| {
+                if let Event::MouseDown(mouse_event) = event {
+                    click_pos.set(Some(mouse_event.pos));
+                }
+            })
+            .layout_fn(|_, _, _, _| Size::new(400.0, 400.0));
+
+        let mut harness = TestHarness::create_with_size(Portal::new(child), Size::new(400., 400.));
+        harness.edit_root_widget(|mut portal, _| {
+            let mut portal = portal
+                .downcast::<Portal<ModularWidget<Rc<Cell<Option<Point>>>>>>()
+                .unwrap();
+            assert!(portal.set_zoom(2.0));
+        });
+
+        // Zooming keeps the center of the viewport in place.
+        let portal = harness.root_widget();
+        let portal = portal
+            .downcast::<Portal<ModularWidget<Rc<Cell<Option<Point>>>>>>()
+            .unwrap();
+        assert_eq!(portal.get_viewport_pos(), Point::new(200.0, 200.0));
+
+        harness.mouse_move((150.0, 150.0));
+        harness.mouse_button_press(MouseButton::Left);
+        assert_eq!(click_pos.get(), Some(Point::new(175.0, 175.0)));
+    }
+
+    #[test]
+    fn zoomed_descendants_have_scaled_window_rects() {
+        let [item_id] = widget_ids();
+        let content = Flex::column()
+            .with_spacer(100.0)
+            .with_child_id(SizedBox::empty().width(50.0).height(50.0), item_id);
+        let widget = Portal::new(SizedBox::new(content).width(400.0).height(400.0));
+        let mut harness = TestHarness::create_with_size(widget, Size::new(400., 400.));
+        let unzoomed_rect = harness.get_widget(item_id).state().window_layout_rect();
+
+        harness.edit_root_widget(|mut portal, _| {
+            let mut portal = portal.downcast::<Portal<SizedBox>>().unwrap();
+            assert!(portal.set_zoom(2.0));
+        });
+
+        // The viewport is scrolled by (200, 200) to keep its center in place.
+        let expected_rect = unzoomed_rect.scale_from_origin(2.0) - Vec2::new(200.0, 200.0);
+        assert_eq!(
+            harness.get_widget(item_id).state().window_layout_rect(),
+            expected_rect
+        );
+    }
+
+    #[test]
+    fn ctrl_wheel_zooms() {
+        let widget = Portal::new(SizedBox::empty().width(400.0).height(400.0)).zoomable(true);
+        let mut harness = TestHarness::create_with_size(widget, Size::new(400., 400.));
+
+        let wheel_event = MouseEvent {
+            mods: Modifiers::CONTROL,
+            ..mouse_scroll((100.0, 100.0), (0.0, -100.0))
+        };
+        harness.process_event(Event::Wheel(wheel_event));
+
+        let zoom = harness
+            .root_widget()
+            .downcast::<Portal<SizedBox>>()
+            .unwrap()
+            .zoom();
+        assert!(zoom > 1.0);
+        assert_eq!(harness.pop_action().unwrap().0, Action::ZoomChanged(zoom));
+    }
+
+    #[test]
+    fn linked_portals_scroll_together() {
+        let [grid_id, header_id] = widget_ids();
+        let widget = Flex::row()
+            .with_child(
+                SizedBox::new_with_id(
+                    Portal::new(SizedBox::empty().width(100.0).height(1000.0))
+                        .with_linked_scroll(header_id, Axis::Vertical),
+                    grid_id,
+                )
+                .width(100.0)
+                .height(100.0),
+            )
+            .with_child(
+                SizedBox::new_with_id(
+                    Portal::new(SizedBox::empty().width(20.0).height(500.0))
+                        .with_linked_scroll(grid_id, Axis::Vertical),
+                    header_id,
+                )
+                .width(20.0)
+                .height(100.0),
+            );
+        let mut harness = TestHarness::create_with_size(widget, Size::new(400.0, 400.0));
+
+        let set_viewport_pos = |harness: &mut TestHarness, index: usize, pos: Point| {
+            harness.edit_root_widget(|mut root, _| {
+                let mut flex = root.downcast::<Flex>().unwrap();
+                let mut child = flex.child_mut(index).unwrap();
+                let mut sized_box = child.downcast::<SizedBox>().unwrap();
+                let mut child = sized_box.child_mut().unwrap();
+                let mut portal = child.downcast::<Portal<SizedBox>>().unwrap();
+                portal.set_viewport_pos(pos);
+            });
+        };
+        let viewport_pos = |harness: &TestHarness, id: WidgetId| {
+            harness
+                .get_widget(id)
+                .downcast::<Portal<SizedBox>>()
+                .unwrap()
+                .get_viewport_pos()
+        };
+
+        set_viewport_pos(&mut harness, 0, Point::new(0.0, 300.0));
+        assert_eq!(viewport_pos(&harness, header_id), Point::new(0.0, 300.0));
+
+        set_viewport_pos(&mut harness, 1, Point::new(0.0, 100.0));
+        assert_eq!(viewport_pos(&harness, grid_id), Point::new(0.0, 100.0));
+
+        // The header is clamped to its shorter content, without pulling the grid back.
+        set_viewport_pos(&mut harness, 0, Point::new(0.0, 700.0));
+        assert_eq!(viewport_pos(&harness, header_id), Point::new(0.0, 400.0));
+        assert_eq!(viewport_pos(&harness, grid_id), Point::new(0.0, 700.0));
+    }
+
+    #[test]
+    fn pull_to_refresh() {
+        let [content_id] = widget_ids();
+        let widget = Portal::new(
+            SizedBox::empty()
+                .width(100.0)
+                .height(1000.0)
+                .with_id(content_id),
+        )
+        .pull_to_refresh(true);
+        let mut harness = TestHarness::create_with_size(widget, Size::new(100.0, 100.0));
+        let content_y =
+            |harness: &TestHarness| harness.get_widget(content_id).state().window_origin().y;
+
+        harness.mouse_move((50.0, 50.0));
+        harness.mouse_wheel(Vec2::new(0.0, -60.0));
+        assert_eq!(content_y(&harness), 30.0);
+        assert!(harness.pop_action().is_none());
+
+        harness.mouse_wheel(Vec2::new(0.0, -100.0));
+        assert_eq!(harness.pop_action().unwrap().0, Action::RefreshRequested);
+
+        // The content goes back up to the indicator, which stays until the refresh is done.
+        harness.process_event(Event::AnimFrame(1_000_000_000));
+        assert_eq!(content_y(&harness), PULL_THRESHOLD);
+
+        let portal_id = harness.root_widget().id();
+        harness.submit_command(REFRESH_FINISHED.to(portal_id));
+        harness.process_event(Event::AnimFrame(1_000_000_000));
+        assert_eq!(content_y(&harness), 0.0);
+    }
+
+    #[test]
+    fn overscroll_glow() {
+        let widget = Portal::new(SizedBox::empty().width(100.0).height(1000.0))
+            .overscroll_effect(OverscrollEffect::Glow);
+        let mut harness = TestHarness::create_with_size(widget, Size::new(100.0, 100.0));
+        let overscroll = |harness: &TestHarness| {
+            harness
+                .root_widget()
+                .downcast::<Portal<SizedBox>>()
+                .unwrap()
+                .overscroll
+        };
+
+        harness.mouse_move((50.0, 50.0));
+        harness.mouse_wheel(Vec2::new(0.0, -40.0));
+        assert_eq!(overscroll(&harness), Vec2::new(0.0, -20.0));
+
+        harness.process_event(Event::AnimFrame(1_000_000_000));
+        assert_eq!(overscroll(&harness), Vec2::ZERO);
+
+        // There is no effect with reduced motion.
+        harness.submit_command(crate::command::SET_LOW_POWER_MODE.with(Some(true)));
+        harness.mouse_wheel(Vec2::new(0.0, -40.0));
+        assert_eq!(overscroll(&harness), Vec2::ZERO);
+    }
+
     // Helper function for panning tests
     fn make_range(repr: &str) -> Range<f64> {
         let repr = &repr[repr.find('_').unwrap()..];
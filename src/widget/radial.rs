@@ -0,0 +1,394 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! A widget that arranges its children around a circle.
+
+use std::f64::consts::TAU;
+
+use smallvec::SmallVec;
+use tracing::{trace, trace_span, Span};
+
+use crate::widget::{WidgetMut, WidgetRef};
+use crate::{
+    BoxConstraints, Env, Event, EventCtx, InternalEvent, LayoutCtx, LifeCycle, LifeCycleCtx,
+    PaintCtx, Point, Size, StatusChange, Vec2, Widget, WidgetId, WidgetPod,
+};
+
+/// A container which places its children at even intervals around a circle, eg
+/// for a pie menu or a clock face, with an optional child in the middle.
+///
+/// Children are placed clockwise, starting at the top of the circle; use
+/// [`with_start_angle`](Self::with_start_angle) and [`with_sweep`](Self::with_sweep)
+/// to place them on an arc instead. Unless a radius is set, the circle is as large
+/// as the container, or just large enough for the children not to overlap if the
+/// container is unbounded.
+///
+/// Where children overlap, pointer events go to the child whose center is closest
+/// to the pointer. The baseline of the container is the baseline of the center
+/// child, if any.
+pub struct Radial {
+    children: Vec<WidgetPod<Box<dyn Widget>>>,
+    center: Option<WidgetPod<Box<dyn Widget>>>,
+    radius: Option<f64>,
+    start_angle: f64,
+    sweep: f64,
+}
+
+crate::declare_widget!(RadialMut, Radial);
+
+// --- METHODS ---
+
+impl Radial {
+    /// Create an empty container.
+    pub fn new() -> Self {
+        Radial {
+            children: Vec::new(),
+            center: None,
+            radius: None,
+            start_angle: 0.0,
+            sweep: TAU,
+        }
+    }
+
+    /// Builder-style method to add a child after the others on the circle.
+    pub fn with_child(mut self, child: impl Widget) -> Self {
+        self.children.push(WidgetPod::new(child).boxed());
+        self
+    }
+
+    /// Builder-style variant of `with_child`, that takes the id that the child will have.
+    ///
+    /// Useful for unit tests.
+    pub fn with_child_id(mut self, child: impl Widget, id: WidgetId) -> Self {
+        self.children
+            .push(WidgetPod::new_with_id(child, id).boxed());
+        self
+    }
+
+    /// Builder-style method to set the child in the middle of the circle.
+    pub fn with_center_child(mut self, child: impl Widget) -> Self {
+        self.center = Some(WidgetPod::new(child).boxed());
+        self
+    }
+
+    /// Builder-style method to set the distance between the center of the container
+    /// and the centers of the children.
+    pub fn with_radius(mut self, radius: f64) -> Self {
+        self.radius = Some(radius.max(0.0));
+        self
+    }
+
+    /// Builder-style method to set the angle of the first child, in radians
+    /// clockwise from the top of the circle.
+    pub fn with_start_angle(mut self, angle: f64) -> Self {
+        self.start_angle = angle;
+        self
+    }
+
+    /// Builder-style method to place the children on an arc of `sweep` radians,
+    /// instead of the whole circle.
+    ///
+    /// The first and last children are at the ends of the arc.
+    pub fn with_sweep(mut self, sweep: f64) -> Self {
+        self.sweep = sweep.clamp(0.0, TAU);
+        self
+    }
+
+    /// The angle of the child at `idx`, clockwise from the top.
+    fn child_angle(&self, idx: usize) -> f64 {
+        let count = self.children.len();
+        // On a full circle, the last child isn't on top of the first one.
+        let intervals = if self.sweep >= TAU {
+            count
+        } else {
+            count.saturating_sub(1)
+        };
+        if intervals == 0 {
+            return self.start_angle;
+        }
+        self.start_angle + self.sweep * idx as f64 / intervals as f64
+    }
+
+    /// The index of the child under `pos`, whose center is closest to `pos` if
+    /// several children overlap.
+    fn child_at(&self, pos: Point) -> Option<usize> {
+        self.children
+            .iter()
+            .enumerate()
+            .filter(|(_, child)| child.layout_rect().contains(pos))
+            .min_by(|(_, a), (_, b)| {
+                let distance = |child: &WidgetPod<Box<dyn Widget>>| {
+                    (child.layout_rect().center() - pos).hypot2()
+                };
+                distance(a).total_cmp(&distance(b))
+            })
+            .map(|(idx, _)| idx)
+    }
+}
+
+impl Default for Radial {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, 'b> RadialMut<'a, 'b> {
+    /// Add a child after the others on the circle.
+    pub fn add_child(&mut self, child: impl Widget) {
+        self.widget.children.push(WidgetPod::new(child).boxed());
+        self.ctx.children_changed();
+    }
+
+    /// Remove the child at `idx` from the circle.
+    pub fn remove_child(&mut self, idx: usize) {
+        self.widget.children.remove(idx);
+        self.ctx.children_changed();
+    }
+
+    /// Get a [`WidgetMut`] to the child at `idx` on the circle, if there is one.
+    pub fn child_mut(&mut self, idx: usize) -> Option<WidgetMut<'_, 'b, Box<dyn Widget>>> {
+        let child = self.widget.children.get_mut(idx)?;
+        Some(self.ctx.get_mut(child))
+    }
+
+    /// Set the child in the middle of the circle, or remove it.
+    pub fn set_center_child(&mut self, child: Option<Box<dyn Widget>>) {
+        self.widget.center = child.map(WidgetPod::new);
+        self.ctx.children_changed();
+    }
+
+    /// Get a [`WidgetMut`] to the child in the middle of the circle, if there is one.
+    pub fn center_child_mut(&mut self) -> Option<WidgetMut<'_, 'b, Box<dyn Widget>>> {
+        let child = self.widget.center.as_mut()?;
+        Some(self.ctx.get_mut(child))
+    }
+
+    /// Set the distance between the center of the container and the centers of the
+    /// children, or fit the circle to the container if `None`.
+    pub fn set_radius(&mut self, radius: Option<f64>) {
+        self.widget.radius = radius.map(|radius| radius.max(0.0));
+        self.ctx.request_layout();
+    }
+
+    /// Set the angle of the first child, in radians clockwise from the top.
+    pub fn set_start_angle(&mut self, angle: f64) {
+        self.widget.start_angle = angle;
+        self.ctx.request_layout();
+    }
+
+    /// Set the arc the children are placed on, in radians.
+    pub fn set_sweep(&mut self, sweep: f64) {
+        self.widget.sweep = sweep.clamp(0.0, TAU);
+        self.ctx.request_layout();
+    }
+}
+
+// --- TRAIT IMPLS ---
+
+impl Widget for Radial {
+    fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, env: &Env) {
+        if let Some(center) = &mut self.center {
+            center.on_event(ctx, event, env);
+        }
+
+        let pointer_pos = match event {
+            Event::MouseDown(mouse)
+            | Event::MouseUp(mouse)
+            | Event::MouseMove(mouse)
+            | Event::Wheel(mouse) => Some(mouse.pos),
+            Event::Pen(pen) => Some(pen.pos),
+            _ => None,
+        };
+        let Some(pointer_pos) = pointer_pos else {
+            for child in &mut self.children {
+                child.on_event(ctx, event, env);
+            }
+            return;
+        };
+
+        // Only the child closest to the pointer gets presses and scrolls. Children
+        // which were active still get the other events, eg to be released, and
+        // children which were hot are told that the pointer left them.
+        let target = self.child_at(pointer_pos);
+        let is_press = matches!(event, Event::MouseDown(_) | Event::Wheel(_));
+        for (idx, child) in self.children.iter_mut().enumerate() {
+            let state = child.state();
+            if Some(idx) == target || (state.has_active && !is_press) {
+                child.on_event(ctx, event, env);
+            } else if state.is_hot {
+                child.on_event(ctx, &Event::Internal(InternalEvent::MouseLeave), env);
+            } else {
+                ctx.skip_child(child);
+            }
+        }
+    }
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange, _env: &Env) {}
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, env: &Env) {
+        if let Some(center) = &mut self.center {
+            center.lifecycle(ctx, event, env);
+        }
+        ctx.visit_children(self.children.iter_mut(), |child, ctx| {
+            child.lifecycle(ctx, event, env)
+        });
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, env: &Env) -> Size {
+        let child_bc = bc.loosen();
+        let center_size = match &mut self.center {
+            Some(center) => center.layout(ctx, &child_bc, env),
+            None => Size::ZERO,
+        };
+        // The distance from the center of a child to its furthest edge.
+        let mut child_extent: f64 = 0.0;
+        for child in &mut self.children {
+            let size = child.layout(ctx, &child_bc, env);
+            child_extent = child_extent.max(size.width.max(size.height) / 2.0);
+        }
+
+        let radius = match self.radius {
+            Some(radius) => radius,
+            None if bc.max().width.is_finite() && bc.max().height.is_finite() => {
+                let max = bc.max();
+                (max.width.min(max.height) / 2.0 - child_extent).max(0.0)
+            }
+            None => {
+                // Leave a child's width between the centers of neighbouring children,
+                // and keep them clear of the center child.
+                let count = self.children.len() as f64;
+                let arc_radius = if self.sweep > 0.0 {
+                    count * 2.0 * child_extent / self.sweep
+                } else {
+                    0.0
+                };
+                let center_radius = center_size.width.hypot(center_size.height) / 2.0;
+                arc_radius.max(center_radius + child_extent)
+            }
+        };
+
+        let side = 2.0 * (radius + child_extent);
+        let size = bc.constrain(Size::new(
+            side.max(center_size.width),
+            side.max(center_size.height),
+        ));
+        let center = (size / 2.0).to_vec2().to_point();
+
+        for idx in 0..self.children.len() {
+            let angle = self.child_angle(idx);
+            let child = &mut self.children[idx];
+            let child_size = child.layout_rect().size();
+            let child_center = center + Vec2::new(angle.sin(), -angle.cos()) * radius;
+            let origin = child_center - (child_size / 2.0).to_vec2();
+            ctx.place_child(child, origin, env);
+        }
+
+        let mut baseline_offset = 0.0;
+        if let Some(child) = &mut self.center {
+            let origin = center - (center_size / 2.0).to_vec2();
+            ctx.place_child(child, origin, env);
+            baseline_offset = child.baseline_offset() + size.height - child.layout_rect().max_y();
+        }
+        ctx.set_baseline_offset(baseline_offset);
+
+        trace!("Computed layout: size={}, radius={}", size, radius);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, env: &Env) {
+        ctx.paint_children_in_z_order(self.center.iter_mut().chain(&mut self.children), env);
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        self.center
+            .iter()
+            .chain(&self.children)
+            .map(|child| child.as_dyn())
+            .collect()
+    }
+
+    fn get_child_at_pos(&self, pos: Point) -> Option<WidgetRef<'_, dyn Widget>> {
+        match self.child_at(pos) {
+            Some(idx) => Some(self.children[idx].as_dyn()),
+            None => self
+                .center
+                .as_ref()
+                .filter(|center| center.layout_rect().contains(pos))
+                .map(|center| center.as_dyn()),
+        }
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("Radial")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{widget_ids, TestHarness};
+    use crate::widget::SizedBox;
+
+    fn dot() -> SizedBox {
+        SizedBox::empty().width(20.0).height(20.0)
+    }
+
+    #[test]
+    fn place_children_around_circle() {
+        let [top, right, bottom, left] = widget_ids();
+        let radial = Radial::new()
+            .with_radius(50.0)
+            .with_child_id(dot(), top)
+            .with_child_id(dot(), right)
+            .with_child_id(dot(), bottom)
+            .with_child_id(dot(), left);
+        let harness = TestHarness::create_with_size(radial, Size::new(120.0, 120.0));
+
+        let center = |id| harness.get_widget(id).state().layout_rect().center();
+        let assert_near = |a: Point, b: Point| assert!((a - b).hypot() < 1e-9, "{} != {}", a, b);
+        assert_near(center(top), Point::new(60.0, 10.0));
+        assert_near(center(right), Point::new(110.0, 60.0));
+        assert_near(center(bottom), Point::new(60.0, 110.0));
+        assert_near(center(left), Point::new(10.0, 60.0));
+    }
+
+    #[test]
+    fn overlapping_children_hit_closest_center() {
+        let [first, second] = widget_ids();
+        // Two wide children on a half circle of radius 10, which overlap in the middle.
+        let radial = Radial::new()
+            .with_radius(10.0)
+            .with_start_angle(-TAU / 4.0)
+            .with_sweep(TAU / 2.0)
+            .with_child_id(SizedBox::empty().width(40.0).height(10.0), first)
+            .with_child_id(SizedBox::empty().width(40.0).height(10.0), second);
+        let harness = TestHarness::create_with_size(radial, Size::new(60.0, 60.0));
+
+        let root = harness.root_widget();
+        let hit = |x| root.get_child_at_pos(Point::new(x, 30.0)).map(|w| w.id());
+        assert_eq!(hit(22.0), Some(first));
+        assert_eq!(hit(38.0), Some(second));
+    }
+
+    #[test]
+    fn only_closest_child_is_hot() {
+        let [first, second] = widget_ids();
+        let radial = Radial::new()
+            .with_radius(10.0)
+            .with_start_angle(-TAU / 4.0)
+            .with_sweep(TAU / 2.0)
+            .with_child_id(SizedBox::empty().width(40.0).height(10.0), first)
+            .with_child_id(SizedBox::empty().width(40.0).height(10.0), second);
+        let mut harness = TestHarness::create_with_size(radial, Size::new(60.0, 60.0));
+
+        let is_hot = |harness: &TestHarness, id| harness.get_widget(id).state().is_hot;
+        harness.mouse_move((22.0, 30.0));
+        assert!(is_hot(&harness, first));
+        assert!(!is_hot(&harness, second));
+
+        harness.mouse_move((38.0, 30.0));
+        assert!(!is_hot(&harness, first));
+        assert!(is_hot(&harness, second));
+    }
+}
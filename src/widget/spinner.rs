@@ -109,11 +109,15 @@ impl Widget for Spinner {
         let center = Point::new(width / 2.0, height / 2.0);
         let (r, g, b, original_alpha) = Color::as_rgba(self.color.resolve(env));
         let scale_factor = width.min(height) / 40.0;
+        // Faded ambits are hard to see in high-contrast mode, so they're either fully
+        // drawn or hidden.
+        let high_contrast = env.get(Env::HIGH_CONTRAST);
 
         for step in 1..=12 {
             let step = f64::from(step);
             let fade_t = (t * 12.0 + 1.0).trunc();
             let fade = ((fade_t + step).rem_euclid(12.0) / 12.0) + 1.0 / 12.0;
+            let fade = if high_contrast { fade.round() } else { fade };
             let angle = Vec2::from_angle((step / 12.0) * -2.0 * PI);
             let ambit_start = center + (10.0 * scale_factor * angle);
             let ambit_end = center + (20.0 * scale_factor * angle);
@@ -0,0 +1,154 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! A section of a scrollable list, whose header stays visible while the section is.
+
+use smallvec::{smallvec, SmallVec};
+use tracing::{trace_span, Span};
+
+use crate::widget::{WidgetMut, WidgetPod, WidgetRef};
+use crate::{
+    BoxConstraints, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx, Point,
+    Size, StatusChange, Widget,
+};
+
+/// A header above a body, where the header sticks to the top of the enclosing
+/// [`Portal`](super::Portal) while the section is scrolled through.
+///
+/// Put several sections in a [`Flex`](super::Flex) column inside a portal to get a
+/// list with sticky headers: each header stays pinned until the end of its section,
+/// where it's pushed out by the next header.
+///
+/// The header is drawn over the body, so it should paint an opaque background.
+/// Outside of a portal, the header stays at the top of the section.
+pub struct StickySection {
+    header: WidgetPod<Box<dyn Widget>>,
+    body: WidgetPod<Box<dyn Widget>>,
+}
+
+crate::declare_widget!(StickySectionMut, StickySection);
+
+impl StickySection {
+    /// Create a section with the given header and body.
+    pub fn new(header: impl Widget + 'static, body: impl Widget + 'static) -> Self {
+        StickySection {
+            header: WidgetPod::new(header).boxed(),
+            body: WidgetPod::new(body).boxed(),
+        }
+    }
+}
+
+impl<'a, 'b> StickySectionMut<'a, 'b> {
+    /// Get mutable access to the header.
+    pub fn header_mut(&mut self) -> WidgetMut<'_, 'b, Box<dyn Widget>> {
+        self.ctx.get_mut(&mut self.widget.header)
+    }
+
+    /// Get mutable access to the body.
+    pub fn body_mut(&mut self) -> WidgetMut<'_, 'b, Box<dyn Widget>> {
+        self.ctx.get_mut(&mut self.widget.body)
+    }
+}
+
+impl Widget for StickySection {
+    fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, env: &Env) {
+        // The header is on top of the body.
+        self.header.on_event(ctx, event, env);
+        self.body.on_event(ctx, event, env);
+    }
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange, _env: &Env) {}
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, env: &Env) {
+        if let LifeCycle::WidgetAdded = event {
+            // So the pointer hits the header where it covers the body.
+            ctx.set_child_z_index(&mut self.header, 1);
+        }
+        self.header.lifecycle(ctx, event, env);
+        self.body.lifecycle(ctx, event, env);
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, env: &Env) -> Size {
+        let child_bc = BoxConstraints::new(
+            Size::new(bc.min().width, 0.0),
+            Size::new(bc.max().width, f64::INFINITY),
+        );
+        let header_size = self.header.layout(ctx, &child_bc, env);
+        let body_size = self.body.layout(ctx, &child_bc, env);
+        let size = bc.constrain(Size::new(
+            header_size.width.max(body_size.width),
+            header_size.height + body_size.height,
+        ));
+
+        // Pin the header to the top of the viewport, without leaving the section.
+        let max_offset = (size.height - header_size.height).max(0.0);
+        let header_offset = ctx
+            .viewport_rect()
+            .map_or(0.0, |viewport| viewport.y0.clamp(0.0, max_offset));
+
+        ctx.place_child(&mut self.body, Point::new(0.0, header_size.height), env);
+        ctx.place_child(&mut self.header, Point::new(0.0, header_offset), env);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, env: &Env) {
+        self.body.paint(ctx, env);
+        self.header.paint(ctx, env);
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        smallvec![self.header.as_dyn(), self.body.as_dyn()]
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("StickySection")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{widget_ids, TestHarness, TestWidgetExt};
+    use crate::widget::{Flex, Portal, SizedBox};
+    use crate::WidgetId;
+
+    #[test]
+    fn headers_stick_and_push_each_other_out() {
+        let [header_1, header_2] = widget_ids();
+        let section = |header: WidgetId| {
+            StickySection::new(
+                SizedBox::empty().width(100.0).height(20.0).with_id(header),
+                SizedBox::empty().width(100.0).height(100.0),
+            )
+        };
+        let widget = Portal::new(
+            Flex::column()
+                .with_child(section(header_1))
+                .with_child(section(header_2)),
+        );
+        let mut harness = TestHarness::create_with_size(widget, Size::new(100.0, 100.0));
+
+        let scroll_to = |harness: &mut TestHarness, y: f64| {
+            harness.edit_root_widget(|mut portal, _| {
+                let mut portal = portal.downcast::<Portal<Flex>>().unwrap();
+                portal.set_viewport_pos(Point::new(0.0, y));
+            });
+        };
+        let header_y =
+            |harness: &TestHarness, id: WidgetId| harness.get_widget(id).state().window_origin().y;
+
+        assert_eq!(header_y(&harness, header_1), 0.0);
+        assert_eq!(header_y(&harness, header_2), 120.0);
+
+        // The first header is pinned to the top of the viewport.
+        scroll_to(&mut harness, 50.0);
+        assert_eq!(header_y(&harness, header_1), 0.0);
+        assert_eq!(header_y(&harness, header_2), 70.0);
+
+        // At the end of its section, the first header is pushed out by the second one.
+        scroll_to(&mut harness, 110.0);
+        assert_eq!(header_y(&harness, header_1), -10.0);
+        assert_eq!(header_y(&harness, header_2), 10.0);
+    }
+}
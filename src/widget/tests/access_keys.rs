@@ -0,0 +1,111 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Tests for access keys.
+
+use smallvec::smallvec;
+
+use crate::shell::{KeyEvent, RawMods};
+use crate::testing::{widget_ids, ModularWidget, TestHarness};
+use crate::widget::{Button, Flex, Label, TextBox};
+use crate::*;
+
+fn press_with_alt(harness: &mut TestHarness, key: &str) {
+    harness.process_event(Event::KeyDown(KeyEvent::for_test(RawMods::Alt, key)));
+}
+
+/// A container which is an access key scope.
+fn scope(child: impl Widget) -> impl Widget {
+    ModularWidget::new(WidgetPod::new(child))
+        .event_fn(|child, ctx, event, env| child.on_event(ctx, event, env))
+        .lifecycle_fn(|child, ctx, event, env| {
+            if let LifeCycle::WidgetAdded = event {
+                ctx.register_access_key_scope();
+            }
+            child.lifecycle(ctx, event, env);
+        })
+        .layout_fn(|child, ctx, bc, env| {
+            let size = child.layout(ctx, bc, env);
+            ctx.place_child(child, Point::ZERO, env);
+            size
+        })
+        .children_fn(|child| smallvec![child.as_dyn()])
+}
+
+#[test]
+fn access_key_activates_label_parent() {
+    let [save_id, copy_id, paste_id] = widget_ids();
+    let widget = Flex::row()
+        .with_child_id(
+            Button::from_label(Label::new("Save").with_access_key('s')),
+            save_id,
+        )
+        .with_child_id(
+            Button::from_label(Label::new("Copy").with_access_key('p')),
+            copy_id,
+        )
+        .with_child_id(
+            Button::from_label(Label::new("Paste").with_access_key('p')),
+            paste_id,
+        );
+    let mut harness = TestHarness::create(widget);
+
+    press_with_alt(&mut harness, "S");
+    assert_eq!(harness.pop_action(), Some((Action::ButtonPressed, save_id)));
+
+    press_with_alt(&mut harness, "x");
+    assert_eq!(harness.pop_action(), None);
+
+    // Shared keys don't activate either widget.
+    press_with_alt(&mut harness, "p");
+    assert_eq!(harness.pop_action(), None);
+}
+
+#[test]
+fn access_key_focuses_text_box() {
+    let [text_box_id] = widget_ids();
+    let widget = Flex::row()
+        .with_child(
+            Label::new("Name")
+                .with_access_key('n')
+                .with_access_key_target(text_box_id),
+        )
+        .with_child_id(TextBox::new(""), text_box_id);
+    let mut harness = TestHarness::create(widget);
+
+    press_with_alt(&mut harness, "n");
+    assert_eq!(harness.window().focus, Some(text_box_id));
+}
+
+#[test]
+fn access_keys_are_scoped() {
+    let [window_button_id, menu_button_id, text_box_id] = widget_ids();
+    let menu = Flex::column()
+        .with_child_id(TextBox::new(""), text_box_id)
+        .with_child_id(
+            Button::from_label(Label::new("Open").with_access_key('o')),
+            menu_button_id,
+        );
+    let widget = Flex::row()
+        .with_child_id(
+            Button::from_label(Label::new("Other").with_access_key('o')),
+            window_button_id,
+        )
+        .with_child(scope(menu));
+    let mut harness = TestHarness::create(widget);
+
+    press_with_alt(&mut harness, "o");
+    assert_eq!(
+        harness.pop_action(),
+        Some((Action::ButtonPressed, window_button_id))
+    );
+
+    harness.mouse_click_on(text_box_id);
+    assert_eq!(harness.window().focus, Some(text_box_id));
+    press_with_alt(&mut harness, "o");
+    assert_eq!(
+        harness.pop_action(),
+        Some((Action::ButtonPressed, menu_button_id))
+    );
+}
@@ -0,0 +1,81 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Tests for events sent by the platform about the app as a whole.
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use crate::testing::{
+    widget_ids, ModularWidget, Record, Recording, TestHarness, TestWidgetExt as _,
+};
+use crate::widget::{Flex, SizedBox};
+use crate::*;
+
+#[test]
+fn suspend_resume_reach_every_widget() {
+    let [child_id] = widget_ids();
+    let record = Recording::default();
+
+    let widget = Flex::row().with_child_id(SizedBox::empty().record(&record), child_id);
+    let mut harness = TestHarness::create(widget);
+    record.clear();
+
+    harness.process_event(Event::Suspend);
+    harness.process_event(Event::Resume);
+
+    let events: Vec<_> = record
+        .drain()
+        .into_iter()
+        .filter_map(|record| match record {
+            Record::E(event) => Some(event.short_name()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(events, ["Suspend", "Resume"]);
+}
+
+#[test]
+fn memory_warning_releases_paint_caches() {
+    let mut harness = TestHarness::create(SizedBox::empty());
+    let mut env = Env::with_theme();
+    env.set(Env::DEBUG_WIDGET_ID, true);
+    harness.set_env(env);
+    harness.render();
+    assert!(!harness.window().root.debug_widget_text.needs_rebuild());
+
+    harness.process_event(Event::MemoryWarning);
+    assert!(harness.window().root.debug_widget_text.needs_rebuild());
+}
+
+#[test]
+fn unsaved_changes_prevent_close() {
+    let record = Recording::default();
+    let has_unsaved_changes = Rc::new(Cell::new(true));
+
+    let editor = ModularWidget::new(has_unsaved_changes.clone()).event_fn(
+        |has_unsaved_changes, ctx, event, _| {
+            if let Event::WindowCloseRequested = event {
+                if has_unsaved_changes.get() {
+                    ctx.prevent_close();
+                }
+            }
+        },
+    );
+    let widget = Flex::row()
+        .with_child(editor)
+        .with_child(SizedBox::empty().record(&record));
+    let mut harness = TestHarness::create(widget);
+    record.clear();
+
+    assert!(!harness.request_close());
+    // Other widgets still see the request.
+    assert!(matches!(
+        record.next(),
+        Record::E(Event::WindowCloseRequested)
+    ));
+
+    has_unsaved_changes.set(false);
+    assert!(harness.request_close());
+}
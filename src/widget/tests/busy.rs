@@ -0,0 +1,69 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Tests related to blocking input to busy widgets.
+
+use std::sync::mpsc::{self, Receiver};
+
+use instant::Duration;
+use smallvec::smallvec;
+
+use crate::testing::{widget_ids, ModularWidget, TestHarness};
+use crate::widget::Button;
+use crate::*;
+
+const SAVE: Selector<()> = Selector::new("masonry-test.save");
+
+/// A form which is busy while it's being saved, until `saved` receives a message.
+fn form(button_id: WidgetId, saved: Receiver<()>) -> impl Widget {
+    let button = WidgetPod::new_with_id(Button::new("Save"), button_id);
+    ModularWidget::new((button, Some(saved)))
+        .event_fn(|(button, saved), ctx, event, env| {
+            if let Event::Command(cmd) = event {
+                if cmd.is(SAVE) {
+                    let saved = saved.take().unwrap();
+                    let token = ctx.begin_busy_with_veil();
+                    let promise = ctx.compute_in_background(move |_| saved.recv().unwrap());
+                    ctx.end_busy_on_promise(token, promise);
+                }
+            }
+            button.on_event(ctx, event, env);
+        })
+        .lifecycle_fn(|(button, _), ctx, event, env| button.lifecycle(ctx, event, env))
+        .layout_fn(|(button, _), ctx, bc, env| {
+            let size = button.layout(ctx, bc, env);
+            ctx.place_child(button, Point::ZERO, env);
+            size
+        })
+        .paint_fn(|(button, _), ctx, env| button.paint(ctx, env))
+        .children_fn(|(button, _)| smallvec![button.as_dyn()])
+}
+
+#[test]
+fn busy_form_blocks_input_until_promise_resolves() {
+    let [form_id, button_id] = widget_ids();
+    let (done, saved) = mpsc::channel();
+    let widget = Flex::row().with_child_id(form(button_id, saved), form_id);
+    let mut harness = TestHarness::create(widget);
+
+    harness.mouse_click_on(button_id);
+    assert_eq!(
+        harness.pop_action(),
+        Some((Action::ButtonPressed, button_id))
+    );
+
+    harness.submit_command(SAVE.to(form_id));
+    assert!(harness.get_widget(form_id).state().is_busy());
+    harness.mouse_click_on(button_id);
+    assert_eq!(harness.pop_action(), None);
+
+    done.send(()).unwrap();
+    harness.wait_for_external_events(Duration::from_secs(5));
+    assert!(!harness.get_widget(form_id).state().is_busy());
+    harness.mouse_click_on(button_id);
+    assert_eq!(
+        harness.pop_action(),
+        Some((Action::ButtonPressed, button_id))
+    );
+}
@@ -0,0 +1,97 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Tests related to painting a widget subtree into bitmaps: captures and printing.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::command::{CaptureWidget, CAPTURE_WIDGET};
+use crate::request::RequestToken;
+use crate::testing::{widget_ids, ModularWidget, TestHarness};
+use crate::widget::{Flex, SizedBox};
+use crate::*;
+
+fn red_box() -> SizedBox {
+    SizedBox::empty()
+        .width(10.0)
+        .height(20.0)
+        .background(Color::rgb8(255, 0, 0))
+}
+
+#[test]
+fn render_widget_to_image() {
+    let [box_id] = widget_ids();
+    let widget = Flex::column()
+        .with_child(SizedBox::empty().width(30.0).height(30.0))
+        .with_child_id(red_box(), box_id);
+
+    let mut harness = TestHarness::create(widget);
+    let image = harness.render_widget_to_image(box_id);
+
+    assert_eq!(image.size(), Size::new(10.0, 20.0));
+    // Only the red box is painted, at the image's origin.
+    assert_eq!(&image.raw_pixels()[0..4], &[255, 0, 0, 255]);
+}
+
+#[test]
+fn capture_widget_command() {
+    let [requester_id, box_id] = widget_ids();
+    let captured = Rc::new(RefCell::new(None::<ImageBuf>));
+
+    let requester = ModularWidget::new((None::<RequestToken<ImageBuf>>, captured.clone()))
+        .event_fn(move |(token, captured), ctx, event, _| match event {
+            Event::MouseDown(_) => {
+                let capture = CaptureWidget {
+                    widget: box_id,
+                    scale: 2.0,
+                };
+                *token = Some(ctx.send_request(CAPTURE_WIDGET, capture));
+            }
+            Event::PromiseResult(result) => {
+                if let Some(Ok(image)) = token.and_then(|token| result.try_get(token)) {
+                    *captured.borrow_mut() = Some(image);
+                }
+            }
+            _ => {}
+        })
+        .layout_fn(|_, _, _, _| Size::new(20.0, 20.0));
+    let widget = Flex::row()
+        .with_child_id(requester, requester_id)
+        .with_child_id(red_box(), box_id);
+
+    let mut harness = TestHarness::create(widget);
+    harness.mouse_click_on(requester_id);
+
+    let image = captured.borrow_mut().take().expect("no capture received");
+    assert_eq!(image.size(), Size::new(20.0, 40.0));
+}
+
+#[test]
+fn print_widget_onto_pages() {
+    use crate::print::{PageBreak, PageSetup};
+
+    // A block which asks not to be split across pages.
+    let unbreakable = ModularWidget::new(()).layout_fn(|_, ctx, bc, _| {
+        ctx.set_page_break(PageBreak::Avoid);
+        bc.constrain(Size::new(100.0, 40.0))
+    });
+    let [column_id] = widget_ids();
+    let widget = Flex::column()
+        .with_child(SizedBox::empty().width(50.0).height(70.0))
+        .with_child(unbreakable);
+    let widget = Flex::row().with_child_id(widget, column_id);
+
+    let setup = PageSetup {
+        size: Size::new(120.0, 120.0),
+        margins: Insets::uniform(10.0),
+        scale: 1.0,
+    };
+    let mut harness = TestHarness::create(widget);
+    let pages = harness.print_widget(column_id, setup);
+
+    // The block from 70 to 110 doesn't fit in the first 100 points.
+    assert_eq!(pages.len(), 2);
+    assert_eq!(pages[0].size(), Size::new(120.0, 120.0));
+}
@@ -0,0 +1,48 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use instant::Duration;
+
+use crate::shell::MouseButton;
+use crate::testing::{ModularWidget, TestHarness};
+use crate::*;
+
+fn click_logger(log: Rc<RefCell<Vec<u8>>>) -> impl Widget {
+    ModularWidget::new(log).event_fn(|log, _, event, _| {
+        if let Event::MouseDown(mouse) = event {
+            log.borrow_mut().push(mouse.count);
+        }
+    })
+}
+
+fn click(harness: &mut TestHarness) {
+    harness.mouse_button_press(MouseButton::Left);
+    harness.mouse_button_release(MouseButton::Left);
+}
+
+#[test]
+fn clicks_are_counted_with_env_interval() {
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let mut harness = TestHarness::create(click_logger(log.clone()));
+    let interval = Duration::from_millis(harness.env().get(theme::DOUBLE_CLICK_INTERVAL));
+
+    harness.mouse_move(Point::new(10.0, 10.0));
+    click(&mut harness);
+    click(&mut harness);
+    click(&mut harness);
+    assert_eq!(*log.borrow(), [1, 2, 3]);
+
+    // Too slow.
+    harness.move_timers_forward(interval + Duration::from_millis(1));
+    click(&mut harness);
+    // Too far away.
+    harness.mouse_move(Point::new(50.0, 50.0));
+    click(&mut harness);
+    // Another button.
+    harness.mouse_button_press(MouseButton::Right);
+    assert_eq!(*log.borrow(), [1, 2, 3, 1, 1, 1]);
+}
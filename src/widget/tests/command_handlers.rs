@@ -0,0 +1,86 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Tests for commands routed to registered handlers.
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use crate::testing::{ModularWidget, TestHarness};
+use crate::widget::Flex;
+use crate::*;
+
+const REGISTERED: Selector = Selector::new("masonry-test.registered");
+const BROADCAST: Selector = Selector::new("masonry-test.broadcast");
+
+fn command_counter(
+    register: impl Fn(&mut LifeCycleCtx) + 'static,
+) -> (ModularWidget<Rc<Cell<usize>>>, Rc<Cell<usize>>) {
+    let count = Rc::new(Cell::new(0));
+    let widget = ModularWidget::new(count.clone())
+        .lifecycle_fn(move |_, ctx, event, _| {
+            if let LifeCycle::WidgetAdded = event {
+                register(ctx);
+            }
+        })
+        .event_fn(|count, _, event, _| {
+            if let Event::Command(_) = event {
+                count.set(count.get() + 1);
+            }
+        });
+    (widget, count)
+}
+
+#[test]
+fn registered_commands_skip_other_widgets() {
+    let (handler, handler_count) = command_counter(|ctx| ctx.register_command_handler(REGISTERED));
+    let (bystander, bystander_count) = command_counter(|_| {});
+    let widget = Flex::row().with_child(handler).with_child(bystander);
+
+    let mut harness = TestHarness::create(widget);
+
+    harness.submit_command(REGISTERED);
+    assert_eq!(handler_count.get(), 1);
+    assert_eq!(bystander_count.get(), 0);
+
+    harness.submit_command(BROADCAST);
+    assert_eq!(handler_count.get(), 2);
+    assert_eq!(bystander_count.get(), 1);
+}
+
+#[test]
+fn handled_commands_reach_every_handler() {
+    let (first, first_count) = command_counter(|ctx| ctx.register_command_handler(REGISTERED));
+    let first = first.event_fn(|count, ctx, event, _| {
+        if let Event::Command(_) = event {
+            count.set(count.get() + 1);
+            ctx.set_handled();
+        }
+    });
+    let (second, second_count) = command_counter(|ctx| ctx.register_command_handler(REGISTERED));
+    let widget = Flex::row().with_child(first).with_child(second);
+
+    let mut harness = TestHarness::create(widget);
+
+    harness.submit_command(REGISTERED);
+    assert_eq!(first_count.get(), 1);
+    assert_eq!(second_count.get(), 1);
+}
+
+#[test]
+fn opt_in_window_only_reaches_listeners() {
+    let (listener, listener_count) = command_counter(|ctx| ctx.register_for_commands());
+    let (bystander, bystander_count) = command_counter(|_| {});
+    let widget = Flex::row().with_child(listener).with_child(bystander);
+
+    let mut harness = TestHarness::create(widget);
+    harness.submit_command(BROADCAST);
+    assert_eq!(listener_count.get(), 1);
+    assert_eq!(bystander_count.get(), 1);
+
+    harness.window_mut().command_broadcast_opt_in = true;
+    harness.submit_command(BROADCAST);
+    assert_eq!(listener_count.get(), 2);
+    assert_eq!(bystander_count.get(), 1);
+}
@@ -0,0 +1,79 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Tests for the background and foreground brushes, and the elevation, of [`WidgetPod`].
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use smallvec::smallvec;
+
+use crate::testing::{widget_ids, ModularWidget, TestHarness};
+use crate::widget::{BackgroundBrush, Canvas2D, SizedBox};
+use crate::*;
+
+type Log = Rc<RefCell<Vec<&'static str>>>;
+
+fn logging_brush(log: &Log, name: &'static str) -> BackgroundBrush {
+    let log = log.clone();
+    BackgroundBrush::PainterFn(Box::new(move |ctx, _| {
+        assert_eq!(ctx.size(), Size::new(40.0, 30.0));
+        log.borrow_mut().push(name);
+    }))
+}
+
+#[test]
+fn decorations_paint_around_widget() {
+    let log = Log::default();
+
+    let leaf = ModularWidget::new(log.clone())
+        .layout_fn(|_, _, _, _| Size::new(40.0, 30.0))
+        .paint_fn(|log, _, _| log.borrow_mut().push("widget"));
+    let child = WidgetPod::new(leaf)
+        .with_background_brush(logging_brush(&log, "background"))
+        .with_foreground_brush(logging_brush(&log, "foreground"));
+
+    let parent = ModularWidget::new(child)
+        .lifecycle_fn(|child, ctx, event, env| child.lifecycle(ctx, event, env))
+        .layout_fn(|child, ctx, bc, env| {
+            let size = child.layout(ctx, &bc.loosen(), env);
+            ctx.place_child(child, Point::new(10.0, 10.0), env);
+            size
+        })
+        .paint_fn(|child, ctx, env| child.paint(ctx, env))
+        .children_fn(|child| smallvec![child.as_dyn()]);
+
+    let mut harness = TestHarness::create(parent);
+    harness.render();
+    assert_eq!(*log.borrow(), ["background", "widget", "foreground"]);
+}
+
+#[test]
+fn elevation_raises_widget_above_siblings() {
+    let [raised_id, flat_id] = widget_ids();
+    let card = || SizedBox::empty().width(40.0).height(30.0);
+    let raised = WidgetPod::new_with_id(card(), raised_id)
+        .with_elevation(2.0)
+        .boxed();
+
+    // Both children are at the same place; without elevation, the flat one, added
+    // last, would be on top.
+    let canvas = Canvas2D::new()
+        .with_child_pod((10.0, 10.0), raised)
+        .with_child_id((10.0, 10.0), card(), flat_id);
+
+    let mut harness = TestHarness::create(canvas);
+    harness.mouse_move((30.0, 25.0));
+
+    assert!(harness.get_widget(raised_id).state().is_hot);
+    assert!(!harness.get_widget(flat_id).state().is_hot);
+    let hit = harness
+        .root_widget()
+        .get_child_at_pos(Point::new(30.0, 25.0));
+    assert_eq!(hit.map(|child| child.id()), Some(raised_id));
+
+    // The shadow is painted below the widget.
+    let raised = harness.get_widget(raised_id);
+    assert!(raised.state().paint_rect().y1 > raised.state().layout_rect().y1);
+}
@@ -0,0 +1,71 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Tests for the `StoreInWidgetMut` derive macro.
+
+use smallvec::SmallVec;
+
+use crate::testing::{widget_ids, TestHarness};
+use crate::widget::{SizedBox, StoreInWidgetMut, WidgetRef};
+use crate::*;
+
+#[derive(StoreInWidgetMut)]
+struct Padded {
+    #[widget_mut(child)]
+    child: WidgetPod<SizedBox>,
+    #[widget_mut(set)]
+    padding: f64,
+}
+
+impl Widget for Padded {
+    fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, env: &Env) {
+        self.child.on_event(ctx, event, env);
+    }
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange, _env: &Env) {}
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, env: &Env) {
+        self.child.lifecycle(ctx, event, env);
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, env: &Env) -> Size {
+        let padding = Size::new(self.padding * 2.0, self.padding * 2.0);
+        let child_size = self.child.layout(ctx, &bc.loosen().shrink(padding), env);
+        ctx.place_child(&mut self.child, Point::new(self.padding, self.padding), env);
+        bc.constrain(child_size + padding)
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, env: &Env) {
+        self.child.paint(ctx, env);
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        self.pod_children()
+    }
+}
+
+#[test]
+fn derived_accessors() {
+    let [child_id] = widget_ids();
+    let widget = Padded {
+        child: WidgetPod::new_with_id(SizedBox::empty().width(20.0).height(20.0), child_id),
+        padding: 5.0,
+    };
+
+    let mut harness = TestHarness::create(widget);
+    assert_eq!(
+        harness.get_widget(child_id).state().layout_rect(),
+        Rect::new(5.0, 5.0, 25.0, 25.0)
+    );
+
+    harness.edit_root_widget(|mut root, _| {
+        let mut padded = root.downcast::<Padded>().unwrap();
+        padded.set_padding(10.0);
+        padded.child_mut().set_width(30.0);
+    });
+    assert_eq!(
+        harness.get_widget(child_id).state().layout_rect(),
+        Rect::new(10.0, 10.0, 40.0, 30.0)
+    );
+}
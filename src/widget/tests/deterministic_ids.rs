@@ -0,0 +1,79 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Tests for deterministic widget ids and the debug paths of widgets.
+
+use crate::debug_logger::DebugLogger;
+use crate::testing::TestHarness;
+use crate::widget::{Button, DeterministicIds, Flex, Label, WidgetRef};
+use crate::*;
+
+fn tree() -> impl Widget {
+    Flex::column()
+        .with_child(Label::new("Title"))
+        .with_child(Flex::row().with_child(Button::new("OK")))
+}
+
+fn all_ids(widget: WidgetRef<'_, dyn Widget>, ids: &mut Vec<WidgetId>) {
+    ids.push(widget.state().id);
+    for child in widget.children() {
+        all_ids(child, ids);
+    }
+}
+
+#[test]
+fn same_seed_gives_same_ids() {
+    let ids_of_run = |seed| {
+        let _ids = DeterministicIds::new(seed);
+        let harness = TestHarness::create(tree());
+        let mut ids = Vec::new();
+        all_ids(harness.root_widget(), &mut ids);
+        ids
+    };
+
+    let first_run = ids_of_run(0);
+    assert_eq!(first_run, ids_of_run(0));
+    assert_ne!(first_run, ids_of_run(1));
+
+    // The guard is dropped, so ids come from the global counter again.
+    assert!(!first_run.contains(&WidgetId::next()));
+}
+
+#[test]
+fn from_path_is_stable() {
+    let id = WidgetId::from_path("settings/sidebar/search");
+    assert_eq!(id, WidgetId::from_path("settings/sidebar/search"));
+    assert_ne!(id, WidgetId::from_path("settings/sidebar/filter"));
+    assert_eq!(serde_json::to_string(&id).unwrap(), id.to_raw().to_string());
+}
+
+/// Log the widgets children first, like the passes do.
+fn log_tree(logger: &mut DebugLogger, widget: WidgetRef<'_, dyn Widget>) {
+    for child in widget.children() {
+        log_tree(logger, child);
+    }
+    logger.update_widget_state(widget);
+}
+
+#[test]
+fn debug_logger_maps_paths_to_ids() {
+    let mut harness = TestHarness::create(tree());
+    let mut logger = DebugLogger::new(true);
+    log_tree(&mut logger, harness.root_widget());
+
+    let button_id = logger.widget_at_path("Flex/Flex[1]/Button[0]").unwrap();
+    assert!(harness.get_widget(button_id).downcast::<Button>().is_some());
+    assert_eq!(
+        logger.widget_path(button_id).as_deref(),
+        Some("Flex/Flex[1]/Button[0]")
+    );
+
+    harness.edit_root_widget(|mut root, _| {
+        root.downcast::<Flex>().unwrap().remove_child(1);
+    });
+    logger.update_widget_state(harness.root_widget());
+
+    assert_eq!(logger.widget_path(button_id), None);
+    assert_eq!(logger.widget_at_path("Flex/Flex[1]/Button[0]"), None);
+}
@@ -0,0 +1,193 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Tests related to events seen by ancestors before their descendants.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use smallvec::smallvec;
+
+use crate::shell::{KbKey, MouseButton};
+use crate::testing::{widget_ids, ModularWidget, TestHarness};
+use crate::*;
+
+const REQUEST_FOCUS: Selector<()> = Selector::new("masonry-test.request-focus");
+
+type Log = Rc<RefCell<Vec<String>>>;
+
+fn focusable_leaf(log: Log) -> impl Widget {
+    ModularWidget::new(log)
+        .event_fn(|log, ctx, event, _| match event {
+            Event::Command(cmd) if cmd.is(REQUEST_FOCUS) => ctx.request_focus(),
+            Event::KeyDown(_) => log.borrow_mut().push("leaf down".to_string()),
+            Event::KeyUp(_) => log.borrow_mut().push("leaf up".to_string()),
+            _ => (),
+        })
+        .lifecycle_fn(|_, ctx, event, _| {
+            if let LifeCycle::BuildFocusChain = event {
+                ctx.register_for_focus();
+            }
+        })
+}
+
+/// A container that registers as a key filter, and swallows the "x" key.
+fn key_filter(name: &'static str, log: Log, child: impl Widget) -> impl Widget {
+    ModularWidget::new((WidgetPod::new(child), log))
+        .event_fn(move |(child, log), ctx, event, env| {
+            if let Event::KeyFilter(key_event) = event {
+                log.borrow_mut().push(format!("{} filter", name));
+                if key_event.key == KbKey::Character("x".into()) {
+                    ctx.set_handled();
+                }
+            }
+            child.on_event(ctx, event, env);
+        })
+        .lifecycle_fn(|(child, _), ctx, event, env| {
+            if let LifeCycle::WidgetAdded = event {
+                ctx.register_key_filter();
+            }
+            child.lifecycle(ctx, event, env);
+        })
+        .layout_fn(|(child, _), ctx, bc, env| {
+            let size = child.layout(ctx, bc, env);
+            ctx.place_child(child, Point::ZERO, env);
+            size
+        })
+        .children_fn(|(child, _)| smallvec![child.as_dyn()])
+}
+
+#[test]
+fn key_filters_run_outside_in() {
+    let [leaf_id] = widget_ids();
+    let log = Log::default();
+
+    let widget = key_filter(
+        "outer",
+        log.clone(),
+        key_filter(
+            "inner",
+            log.clone(),
+            Flex::row().with_child_id(focusable_leaf(log.clone()), leaf_id),
+        ),
+    );
+    let mut harness = TestHarness::create(widget);
+    harness.submit_command(REQUEST_FOCUS.to(leaf_id));
+
+    harness.keyboard_type_chars("a");
+    assert_eq!(
+        *log.borrow(),
+        [
+            "outer filter",
+            "inner filter",
+            "leaf down",
+            "outer filter",
+            "inner filter",
+            "leaf up",
+        ]
+    );
+}
+
+#[test]
+fn key_filter_swallows_event() {
+    let [leaf_id] = widget_ids();
+    let log = Log::default();
+
+    let widget = key_filter(
+        "outer",
+        log.clone(),
+        Flex::row().with_child_id(focusable_leaf(log.clone()), leaf_id),
+    );
+    let mut harness = TestHarness::create(widget);
+    harness.submit_command(REQUEST_FOCUS.to(leaf_id));
+
+    harness.keyboard_type_chars("x");
+    assert_eq!(*log.borrow(), ["outer filter", "outer filter"]);
+}
+
+fn draggable_leaf(log: Log) -> impl Widget {
+    ModularWidget::new(log)
+        .event_fn(|log, ctx, event, _| match event {
+            Event::MouseDown(_) => {
+                ctx.set_active(true);
+                log.borrow_mut().push("leaf down".to_string());
+            }
+            Event::MouseMove(_) if ctx.is_active() => {
+                log.borrow_mut().push("leaf drag".to_string());
+            }
+            Event::PointerCaptureLost => log.borrow_mut().push("leaf lost".to_string()),
+            _ => (),
+        })
+        .layout_fn(|_, _, _, _| Size::new(100.0, 100.0))
+}
+
+/// A container that takes over drags longer than 10 pixels.
+fn drag_stealer(log: Log, child: impl Widget) -> impl Widget {
+    ModularWidget::new((WidgetPod::new(child), log, None::<Point>))
+        .event_fn(|(child, log, drag_start), ctx, event, env| {
+            if let Event::MouseFilter(mouse_event) = event {
+                match &**mouse_event {
+                    Event::MouseDown(mouse) => *drag_start = Some(mouse.pos),
+                    Event::MouseMove(mouse) if !ctx.is_active() => {
+                        if let Some(start) = *drag_start {
+                            if (mouse.pos - start).hypot() > 10.0 {
+                                ctx.capture_pointer();
+                                log.borrow_mut().push("parent captured".to_string());
+                            }
+                        }
+                    }
+                    _ => (),
+                }
+            }
+            if let Event::MouseMove(_) = event {
+                if ctx.is_active() {
+                    log.borrow_mut().push("parent drag".to_string());
+                }
+            }
+            child.on_event(ctx, event, env);
+        })
+        .lifecycle_fn(|(child, _, _), ctx, event, env| {
+            if let LifeCycle::WidgetAdded = event {
+                ctx.register_pointer_filter();
+            }
+            child.lifecycle(ctx, event, env);
+        })
+        .layout_fn(|(child, _, _), ctx, bc, env| {
+            let size = child.layout(ctx, bc, env);
+            ctx.place_child(child, Point::ZERO, env);
+            size
+        })
+        .children_fn(|(child, _, _)| smallvec![child.as_dyn()])
+}
+
+#[test]
+fn capture_pointer_from_child() {
+    let [leaf_id] = widget_ids();
+    let log = Log::default();
+
+    let widget = drag_stealer(
+        log.clone(),
+        Flex::row().with_child_id(draggable_leaf(log.clone()), leaf_id),
+    );
+    let mut harness = TestHarness::create(widget);
+
+    let start = harness
+        .get_widget(leaf_id)
+        .state()
+        .window_layout_rect()
+        .center();
+    harness.mouse_move(start);
+    harness.mouse_button_press(MouseButton::Left);
+    harness.mouse_move(start + Vec2::new(5.0, 0.0));
+    assert_eq!(*log.borrow(), ["leaf down", "leaf drag"]);
+    log.borrow_mut().clear();
+
+    harness.mouse_move(start + Vec2::new(20.0, 0.0));
+    assert_eq!(*log.borrow(), ["parent captured", "leaf lost"]);
+    assert!(!harness.get_widget(leaf_id).state().is_active);
+    log.borrow_mut().clear();
+
+    harness.mouse_move(start + Vec2::new(30.0, 0.0));
+    assert_eq!(*log.borrow(), ["parent drag"]);
+}
@@ -0,0 +1,79 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Tests for the event routing recorded by the debug logger.
+
+use crate::debug_logger::EventRouting;
+use crate::shell::MouseButton;
+use crate::testing::{widget_ids, ModularWidget, TestHarness};
+use crate::*;
+
+fn clickable(handle_clicks: bool) -> impl Widget {
+    ModularWidget::new(())
+        .event_fn(move |_, ctx, event, _| {
+            if let Event::MouseDown(_) = event {
+                if handle_clicks {
+                    ctx.set_handled();
+                }
+            }
+        })
+        .layout_fn(|_, _, _, _| Size::new(50.0, 50.0))
+}
+
+#[test]
+fn trace_click_routing() {
+    let [row_id, inert_id, handler_id, other_id] = widget_ids();
+
+    let widget = Flex::row()
+        .with_child_id(
+            Flex::row()
+                .with_child_id(clickable(false), inert_id)
+                .with_child_id(clickable(true), handler_id),
+            row_id,
+        )
+        .with_child_id(clickable(true), other_id);
+    let mut harness = TestHarness::create(widget);
+
+    harness.mouse_move_to(handler_id);
+    harness.mouse_button_press(MouseButton::Left);
+
+    let trace = harness.event_trace();
+    assert_eq!(trace.event_name, "MouseDown");
+    assert_eq!(trace.handled_by(), Some(handler_id));
+    assert_eq!(trace.routing_of(row_id), Some(EventRouting::Delivered));
+    assert_eq!(trace.routing_of(inert_id), Some(EventRouting::Skipped));
+    assert_eq!(trace.routing_of(handler_id), Some(EventRouting::Handled));
+    assert_eq!(
+        trace.routing_of(other_id),
+        Some(EventRouting::AlreadyHandled)
+    );
+}
+
+#[test]
+fn trace_submitted_commands() {
+    const PING: Selector = Selector::new("masonry-test.ping");
+    let [widget_id] = widget_ids();
+
+    let widget = ModularWidget::new(()).event_fn(move |_, ctx, event, _| match event {
+        Event::MouseDown(_) => {
+            ctx.submit_command(PING.to(widget_id));
+            ctx.set_handled();
+        }
+        Event::Command(cmd) if cmd.is(PING) => ctx.set_handled(),
+        _ => {}
+    });
+    let mut harness = TestHarness::create(Flex::row().with_child_id(widget, widget_id));
+
+    harness.mouse_move_to(widget_id);
+    harness.mouse_button_press(MouseButton::Left);
+
+    let trace_names: Vec<_> = harness
+        .event_traces()
+        .iter()
+        .map(|trace| trace.event_name)
+        .collect();
+    assert_eq!(trace_names, ["MouseDown", "TargetedCommand"]);
+    assert_eq!(harness.event_trace().event_name, "TargetedCommand");
+    assert_eq!(harness.event_trace().handled_by(), Some(widget_id));
+}
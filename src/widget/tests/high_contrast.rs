@@ -0,0 +1,31 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Tests for the high-contrast palette.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::testing::{ModularWidget, TestHarness};
+use crate::*;
+
+#[test]
+fn high_contrast_replaces_palette() {
+    let text_color = Rc::new(RefCell::new(Color::TRANSPARENT));
+    let widget =
+        ModularWidget::new(text_color.clone()).lifecycle_fn(|text_color, _, event, env| {
+            if let LifeCycle::WidgetAdded | LifeCycle::EnvChanged = event {
+                *text_color.borrow_mut() = env.get(theme::TEXT_COLOR);
+            }
+        });
+    let mut harness = TestHarness::create(widget);
+    let normal_text_color = text_color.borrow().clone();
+    assert_ne!(normal_text_color, Color::WHITE);
+
+    harness.set_env(Env::with_theme().adding(Env::HIGH_CONTRAST, true));
+    assert_eq!(*text_color.borrow(), Color::WHITE);
+
+    harness.set_env(Env::with_theme());
+    assert_eq!(*text_color.borrow(), normal_text_color);
+}
@@ -4,8 +4,10 @@
 
 //! Tests related to propagation of invalid rects.
 
-use crate::testing::{widget_ids, TestHarness};
+use crate::testing::{widget_ids, ModularWidget, TestHarness};
+use crate::theme;
 use crate::widget::{Button, Flex};
+use crate::{Env, Event, Size};
 
 #[test]
 fn invalidate_union() {
@@ -37,4 +39,35 @@ fn invalidate_union() {
     );
 }
 
+#[test]
+fn invalidate_focus_ring() {
+    let [id_child] = widget_ids();
+
+    let focusable = ModularWidget::new(())
+        .event_fn(|_, ctx, event, _| {
+            if let Event::MouseDown(_) = event {
+                ctx.request_focus();
+            }
+        })
+        .layout_fn(|_, _, _, _| Size::new(20.0, 20.0));
+    let widget = Flex::column()
+        .with_spacer(10.0)
+        .with_child_id(focusable, id_child);
+
+    let mut harness = TestHarness::create(widget);
+    let _ = harness.render();
+
+    harness.mouse_click_on(id_child);
+    assert_eq!(harness.window().focus, Some(id_child));
+
+    let env = Env::with_theme();
+    let outset = env.get(theme::FOCUS_RING_OFFSET) + env.get(theme::FOCUS_RING_WIDTH);
+    let ring_rect = harness
+        .get_widget(id_child)
+        .state()
+        .window_layout_rect()
+        .inflate(outset, outset);
+    assert!(harness.window().invalid().rects().contains(&ring_rect));
+}
+
 // TODO: Add a test with scrolling/viewport
@@ -0,0 +1,79 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use instant::Duration;
+
+use crate::shell::{Code, KbKey, KeyEvent, KeyState};
+use crate::testing::{widget_ids, ModularWidget, TestHarness};
+use crate::widget::Flex;
+use crate::*;
+
+const REQUEST_FOCUS: Selector<()> = Selector::new("masonry-test.request-focus");
+
+fn key_logger(key_repeat: KeyRepeat, log: Rc<RefCell<Vec<bool>>>) -> impl Widget {
+    ModularWidget::new(log)
+        .event_fn(|log, ctx, event, _| match event {
+            Event::Command(cmd) if cmd.is(REQUEST_FOCUS) => ctx.request_focus(),
+            Event::KeyDown(key) => log.borrow_mut().push(key.repeat),
+            _ => (),
+        })
+        .lifecycle_fn(move |_, ctx, event, _| match event {
+            LifeCycle::WidgetAdded => ctx.set_key_repeat(key_repeat),
+            LifeCycle::BuildFocusChain => ctx.register_for_focus(),
+            _ => (),
+        })
+}
+
+fn key_event(state: KeyState, repeat: bool) -> KeyEvent {
+    KeyEvent {
+        state,
+        key: KbKey::Character("a".into()),
+        code: Code::KeyA,
+        repeat,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn disabled_key_repeat() {
+    let [id] = widget_ids();
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let widget = Flex::row().with_child_id(key_logger(KeyRepeat::Disabled, log.clone()), id);
+    let mut harness = TestHarness::create(widget);
+    harness.submit_command(REQUEST_FOCUS.to(id));
+
+    harness.process_event(Event::KeyDown(key_event(KeyState::Down, false)));
+    harness.process_event(Event::KeyDown(key_event(KeyState::Down, true)));
+    harness.process_event(Event::KeyDown(key_event(KeyState::Down, true)));
+    assert_eq!(*log.borrow(), [false]);
+}
+
+#[test]
+fn custom_key_repeat() {
+    let [id] = widget_ids();
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let key_repeat = KeyRepeat::Custom {
+        delay: Duration::from_millis(300),
+        interval: Duration::from_millis(50),
+    };
+    let widget = Flex::row().with_child_id(key_logger(key_repeat, log.clone()), id);
+    let mut harness = TestHarness::create(widget);
+    harness.submit_command(REQUEST_FOCUS.to(id));
+
+    harness.process_event(Event::KeyDown(key_event(KeyState::Down, false)));
+    // Platform repeats are ignored.
+    harness.process_event(Event::KeyDown(key_event(KeyState::Down, true)));
+    assert_eq!(*log.borrow(), [false]);
+
+    harness.move_timers_forward(Duration::from_millis(300));
+    harness.move_timers_forward(Duration::from_millis(50));
+    assert_eq!(*log.borrow(), [false, true, true]);
+
+    harness.process_event(Event::KeyUp(key_event(KeyState::Up, false)));
+    harness.move_timers_forward(Duration::from_millis(100));
+    assert_eq!(*log.borrow(), [false, true, true]);
+}
@@ -6,10 +6,14 @@
 
 #![allow(unused_imports)]
 
-use druid_shell::kurbo::{Insets, Size};
+use druid_shell::kurbo::{Insets, Point, Rect, Size};
+use smallvec::smallvec;
 
+use crate::debug_logger::LayoutViolationKind;
+use crate::style::{BorderStyle, StyleProperties, StyleSelector, StyleSheet, WidgetStyle};
 use crate::testing::{widget_ids, ModularWidget, TestHarness, TestWidgetExt};
 use crate::widget::{Flex, SizedBox};
+use crate::{Color, WidgetPod};
 
 #[test]
 fn layout_simple() {
@@ -75,3 +79,178 @@ fn layout_insets() {
 // TODO - insets + flex
 // TODO - viewport
 // TODO - insets + viewport
+
+#[test]
+fn layout_pod_style() {
+    const BOX_WIDTH: f64 = 50.;
+
+    let [styled_id, next_id] = widget_ids();
+
+    let widget = Flex::column()
+        .with_child_id(
+            SizedBox::empty().width(BOX_WIDTH).height(BOX_WIDTH),
+            styled_id,
+        )
+        .with_child_id(
+            SizedBox::empty().width(BOX_WIDTH).height(BOX_WIDTH),
+            next_id,
+        );
+
+    let mut harness = TestHarness::create(widget);
+
+    harness.edit_root_widget(|mut root, _| {
+        let mut flex = root.downcast::<Flex>().unwrap();
+        let mut child = flex.child_mut(0).unwrap();
+        child.set_style(WidgetStyle {
+            border: Some(BorderStyle {
+                width: 2.0.into(),
+                color: Color::BLACK.into(),
+            }),
+            padding: Insets::uniform(8.0).into(),
+            ..Default::default()
+        });
+    });
+
+    let styled_rect = harness.get_widget(styled_id).state().layout_rect();
+    let styled_border_rect = harness.get_widget(styled_id).state().border_rect();
+    let next_rect = harness.get_widget(next_id).state().layout_rect();
+
+    // The widget itself keeps its size; padding and border are added around it.
+    assert_eq!(styled_rect, Rect::new(10., 10., 60., 60.));
+    assert_eq!(styled_border_rect, Rect::new(0., 0., 70., 70.));
+    assert_eq!(next_rect.y0, 70.0);
+}
+
+#[test]
+fn layout_style_sheet_hover() {
+    const BOX_WIDTH: f64 = 50.;
+
+    let [styled_id, next_id] = widget_ids();
+
+    let widget = Flex::column()
+        .with_child_id(
+            SizedBox::empty().width(BOX_WIDTH).height(BOX_WIDTH),
+            styled_id,
+        )
+        .with_child_id(
+            SizedBox::empty().width(BOX_WIDTH).height(BOX_WIDTH),
+            next_id,
+        );
+
+    let mut harness = TestHarness::create(widget);
+
+    harness.edit_root_widget(|mut root, _| {
+        let mut flex = root.downcast::<Flex>().unwrap();
+        flex.child_mut(0).unwrap().add_class("grow");
+    });
+    harness.set_style_sheet(StyleSheet::new().with_rule(
+        StyleSelector::any().class("grow").hover(),
+        StyleProperties::new().padding(Insets::uniform(5.0)),
+    ));
+
+    let styled_rect = harness.get_widget(styled_id).state().border_rect();
+    assert_eq!(styled_rect.size(), Size::new(BOX_WIDTH, BOX_WIDTH));
+
+    harness.mouse_move_to(styled_id);
+
+    let styled_rect = harness.get_widget(styled_id).state().border_rect();
+    let next_rect = harness.get_widget(next_id).state().layout_rect();
+    assert_eq!(styled_rect.size(), Size::new(60., 60.));
+    assert_eq!(next_rect.y0, 60.0);
+}
+
+#[test]
+fn pod_reports_requested_layout() {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use smallvec::smallvec;
+
+    use crate::{Event, Point, Selector, WidgetPod};
+
+    const REQUEST_LAYOUT: Selector = Selector::new("masonry-test.request-layout");
+
+    let [child_id] = widget_ids();
+    let child_needed_layout = Rc::new(Cell::new(false));
+
+    let child = ModularWidget::new(())
+        .event_fn(|_, ctx, event, _| {
+            if let Event::Command(cmd) = event {
+                if cmd.is(REQUEST_LAYOUT) {
+                    ctx.request_layout();
+                }
+            }
+        })
+        .layout_fn(|_, _, _, _| Size::new(10.0, 10.0));
+    let parent = ModularWidget::new((
+        WidgetPod::new_with_id(child, child_id),
+        child_needed_layout.clone(),
+    ))
+    .event_fn(|(child, needed_layout), ctx, event, env| {
+        child.on_event(ctx, event, env);
+        needed_layout.set(child.needs_layout());
+    })
+    .lifecycle_fn(|(child, _), ctx, event, env| child.lifecycle(ctx, event, env))
+    .layout_fn(|(child, _), ctx, bc, env| {
+        let size = child.layout(ctx, bc, env);
+        ctx.place_child(child, Point::ZERO, env);
+        size
+    })
+    .children_fn(|(child, _)| smallvec![child.as_dyn()]);
+
+    let mut harness = TestHarness::create(parent);
+    assert!(!harness.get_widget(child_id).state().needs_layout);
+
+    harness.submit_command(REQUEST_LAYOUT.to(child_id));
+    assert!(child_needed_layout.get());
+}
+
+#[test]
+fn oversized_layout_is_reported() {
+    let [widget_id] = widget_ids();
+
+    let widget = ModularWidget::new(()).layout_fn(|_, _, _, _| Size::new(500.0, 20.0));
+    let widget = SizedBox::new_with_id(widget, widget_id)
+        .width(100.0)
+        .height(100.0);
+
+    let mut harness = TestHarness::create(widget);
+
+    if cfg!(debug_assertions) {
+        let violations = harness.take_layout_violations();
+        let violation = violations
+            .iter()
+            .find(|violation| violation.widget_id == widget_id)
+            .expect("the violation should be reported");
+        assert_eq!(violation.kind, LayoutViolationKind::SizeOutsideConstraints);
+        assert_eq!(violation.constraints.max(), Size::new(100.0, 100.0));
+        assert_eq!(violation.size, Size::new(500.0, 20.0));
+    }
+}
+
+#[test]
+fn integer_layout_rounds_sizes_and_positions() {
+    let [child_id] = widget_ids();
+
+    let child = ModularWidget::new(()).layout_fn(|_, _, _, _| Size::new(20.6, 10.2));
+    let parent = ModularWidget::new(WidgetPod::new_with_id(child, child_id))
+        .lifecycle_fn(|child, ctx, event, env| child.lifecycle(ctx, event, env))
+        .layout_fn(|child, ctx, bc, env| {
+            child.layout(ctx, &bc.loosen(), env);
+            ctx.place_child(child, Point::new(5.3, 7.8), env);
+            bc.max()
+        })
+        .children_fn(|child| smallvec![child.as_dyn()]);
+
+    let mut harness = TestHarness::create(parent);
+    assert_eq!(
+        harness.get_widget(child_id).state().layout_rect().origin(),
+        Point::new(5.3, 7.8)
+    );
+
+    harness.set_integer_layout(true);
+    assert_eq!(
+        harness.get_widget(child_id).state().layout_rect(),
+        Rect::new(5.0, 8.0, 26.0, 18.0)
+    );
+}
@@ -2,10 +2,14 @@
 // "as-is" basis without warranties of any kind. See the LICENSE file for
 // details.
 
+use std::cell::RefCell;
+use std::rc::Rc;
+
 use insta::assert_debug_snapshot;
 
 use crate::testing::{
-    widget_ids, Record, Recording, ReplaceChild, TestHarness, TestWidgetExt as _, REPLACE_CHILD,
+    widget_ids, ModularWidget, Record, Recording, ReplaceChild, TestHarness, TestWidgetExt as _,
+    REPLACE_CHILD,
 };
 use crate::widget::{Flex, Label, SizedBox};
 use crate::*;
@@ -116,3 +120,58 @@ fn register_after_adding_child() {
     assert!(root_state.children.may_contain(&id_2));
     assert!(root_state.children.may_contain(&id_3));
 }
+
+/// Test that widgets know their parent when they receive `WidgetAdded`.
+#[test]
+fn parent_id_in_widget_added() {
+    let [root_id, flex_id] = widget_ids();
+    let parents: Rc<RefCell<Vec<Option<WidgetId>>>> = Default::default();
+
+    let make_child = |parents: Rc<RefCell<Vec<Option<WidgetId>>>>| {
+        ModularWidget::new(parents).lifecycle_fn(|parents, ctx, event, _| {
+            if let LifeCycle::WidgetAdded = event {
+                parents.borrow_mut().push(ctx.parent_id());
+            }
+        })
+    };
+    let widget =
+        SizedBox::new_with_id(Flex::row().with_child(make_child(parents.clone())), root_id);
+    let widget = Flex::row()
+        .with_child(widget)
+        .with_child(make_child(parents.clone()))
+        .with_id(flex_id);
+
+    let harness = TestHarness::create(widget);
+
+    let flex_child = harness.get_widget(root_id).children()[0].id();
+    assert_eq!(*parents.borrow(), vec![Some(flex_child), Some(flex_id)]);
+    assert_eq!(harness.get_widget(root_id).state().parent_id, Some(flex_id));
+    assert_eq!(harness.root_widget().state().parent_id, None);
+}
+
+/// Test that widgets are notified when the safe area changes, and can read it in layout.
+#[test]
+fn safe_area_change() {
+    let [widget_id] = widget_ids();
+    let safe_area = Insets::new(0.0, 20.0, 0.0, 40.0);
+
+    let widget = ModularWidget::new(())
+        .lifecycle_fn(|_, ctx, event, _| {
+            if let LifeCycle::SafeAreaChanged(_) = event {
+                ctx.request_layout();
+            }
+        })
+        .layout_fn(|_, ctx, _, _| Size::new(10.0, 100.0 - ctx.safe_area().y_value()));
+
+    let mut harness = TestHarness::create(Flex::row().with_child_id(widget, widget_id));
+    assert_eq!(
+        harness.get_widget(widget_id).state().layout_rect().height(),
+        100.0
+    );
+
+    harness.set_safe_area(safe_area);
+    assert_eq!(
+        harness.get_widget(widget_id).state().layout_rect().height(),
+        40.0
+    );
+}
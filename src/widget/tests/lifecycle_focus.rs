@@ -232,3 +232,47 @@ fn resign_focus_on_disable() {
     assert_eq!(harness.window().focus_chain(), &[focus_2]);
     assert_eq!(harness.window().focus, None);
 }
+
+/// Check that the D-pad moves focus to the nearest widget in that direction,
+/// and repeats while held.
+#[test]
+fn gamepad_moves_focus() {
+    use crate::gamepad::{GamepadButton, GamepadEvent, GamepadEventKind, GamepadId, REPEAT_DELAY};
+
+    let [id_1, id_2, id_3, id_4] = widget_ids();
+    let dpad = |kind| GamepadEvent {
+        gamepad: GamepadId(0),
+        kind,
+    };
+
+    let widget = Flex::column()
+        .with_child(
+            Flex::row()
+                .with_child_id(FocusTaker::new(), id_1)
+                .with_child_id(FocusTaker::new(), id_2)
+                .with_child_id(FocusTaker::new(), id_3),
+        )
+        .with_child_id(FocusTaker::new(), id_4);
+
+    let mut harness = TestHarness::create(widget);
+    harness.submit_command(REQUEST_FOCUS.to(id_1));
+
+    harness.gamepad_event(dpad(GamepadEventKind::ButtonPressed(
+        GamepadButton::DPadRight,
+    )));
+    assert_eq!(harness.window().focus, Some(id_2));
+
+    harness.move_timers_forward(REPEAT_DELAY);
+    assert_eq!(harness.window().focus, Some(id_3));
+
+    harness.gamepad_event(dpad(GamepadEventKind::ButtonReleased(
+        GamepadButton::DPadRight,
+    )));
+    harness.move_timers_forward(REPEAT_DELAY);
+    assert_eq!(harness.window().focus, Some(id_3));
+
+    harness.gamepad_event(dpad(GamepadEventKind::ButtonPressed(
+        GamepadButton::DPadDown,
+    )));
+    assert_eq!(harness.window().focus, Some(id_4));
+}
@@ -0,0 +1,30 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Tests for low-power mode.
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use crate::testing::{ModularWidget, TestHarness};
+use crate::*;
+
+#[test]
+fn low_power_override_notifies_widgets() {
+    let reduced_motion = Rc::new(Cell::new(None));
+    let widget =
+        ModularWidget::new(reduced_motion.clone()).lifecycle_fn(|reduced_motion, ctx, event, _| {
+            if let LifeCycle::WidgetAdded | LifeCycle::EnvChanged = event {
+                reduced_motion.set(Some(ctx.reduced_motion()));
+            }
+        });
+    let mut harness = TestHarness::create(widget);
+    assert_eq!(reduced_motion.get(), Some(false));
+
+    harness.submit_command(command::SET_LOW_POWER_MODE.with(Some(true)));
+    assert_eq!(reduced_motion.get(), Some(true));
+
+    harness.submit_command(command::SET_LOW_POWER_MODE.with(None));
+    assert_eq!(reduced_motion.get(), Some(false));
+}
@@ -2,16 +2,36 @@
 // "as-is" basis without warranties of any kind. See the LICENSE file for
 // details.
 
+mod access_keys;
+mod app_lifecycle;
 mod aspect_ratio;
+mod busy;
+mod capture;
+mod click_count;
+mod command_handlers;
+mod decorations;
+mod derive_widget_mut;
+mod deterministic_ids;
+mod event_capture;
 mod event_notification;
+mod event_trace;
+mod high_contrast;
 mod invalidation;
+mod key_repeat;
 mod layout;
 mod lifecycle_basic;
 mod lifecycle_disable;
 mod lifecycle_focus;
+mod low_power;
+mod mutate_later;
+mod refine_layout;
+mod reparenting;
 mod safety_rails;
+mod shortcuts;
 mod status_change;
 mod timers;
+mod ui_scale;
+mod visibility;
 
 // TODO
 // - InternalLifeCycle::RouteDisabledChanged
@@ -0,0 +1,67 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Tests related to mutations scheduled with `mutate_later`.
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use smallvec::smallvec;
+
+use crate::testing::{widget_ids, ModularWidget, TestHarness};
+use crate::widget::{Button, Flex, Label, WidgetMut};
+use crate::*;
+
+/// A container that renames its child label the first time it's laid out.
+fn rename_in_layout(label_id: WidgetId, renamed: Rc<Cell<bool>>) -> impl Widget {
+    let child = WidgetPod::new_with_id(Label::new("Old"), label_id);
+    ModularWidget::new((child, renamed))
+        .lifecycle_fn(|(child, _), ctx, event, env| child.lifecycle(ctx, event, env))
+        .layout_fn(move |(child, renamed), ctx, bc, env| {
+            if !renamed.replace(true) {
+                ctx.mutate_later(label_id, |mut label: WidgetMut<Label>| {
+                    label.set_text("New");
+                });
+            }
+            let size = child.layout(ctx, bc, env);
+            ctx.place_child(child, Point::ZERO, env);
+            size
+        })
+        .children_fn(|(child, _)| smallvec![child.as_dyn()])
+}
+
+#[test]
+fn mutate_child_from_layout() {
+    let [label_id] = widget_ids();
+    let renamed = Rc::new(Cell::new(false));
+
+    let harness = TestHarness::create(rename_in_layout(label_id, renamed.clone()));
+
+    assert!(renamed.get());
+    let label = harness.get_widget(label_id);
+    assert_eq!(&*label.downcast::<Label>().unwrap().text(), "New");
+}
+
+#[test]
+fn skip_mutation_of_wrong_type() {
+    let [label_id] = widget_ids();
+
+    let widget = ModularWidget::new(())
+        .event_fn(move |_, ctx, event, _| {
+            if let Event::WindowConnected = event {
+                ctx.mutate_later(label_id, |mut button: WidgetMut<Button>| {
+                    button.set_text("Unreachable");
+                });
+            }
+        })
+        .layout_fn(|_, _, _, _| Size::ZERO);
+    let widget = Flex::row()
+        .with_child(widget)
+        .with_child_id(Label::new("Label"), label_id);
+
+    let harness = TestHarness::create(widget);
+
+    let label = harness.get_widget(label_id);
+    assert_eq!(&*label.downcast::<Label>().unwrap().text(), "Label");
+}
@@ -0,0 +1,76 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Tests related to refining the layout of widgets with an estimated size.
+
+use crate::testing::{widget_ids, ModularWidget, Record, Recording, TestHarness, TestWidgetExt};
+use crate::widget::Flex;
+use crate::*;
+
+const MEASURED: Selector<Size> = Selector::new("masonry-test.measured");
+
+const ESTIMATE: Size = Size::new(50.0, 20.0);
+
+/// A widget whose size is estimated until it receives `MEASURED`.
+fn measured_widget() -> ModularWidget<Size> {
+    ModularWidget::new(ESTIMATE)
+        .event_fn(|size, ctx, event, _| {
+            if let Event::Command(command) = event {
+                if let Some(measured) = command.get(MEASURED) {
+                    *size = *measured;
+                    ctx.request_refine_layout();
+                }
+            }
+        })
+        .layout_fn(|size, _, bc, _| bc.constrain(*size))
+}
+
+fn layout_count(recording: &Recording) -> usize {
+    recording
+        .drain()
+        .iter()
+        .filter(|record| matches!(record, Record::Layout(_)))
+        .count()
+}
+
+#[test]
+fn accurate_estimate_only_lays_out_widget() {
+    let [measured_id] = widget_ids();
+    let measured_recording = Recording::default();
+    let parent_recording = Recording::default();
+    let widget = Flex::row()
+        .with_child_id(measured_widget().record(&measured_recording), measured_id)
+        .record(&parent_recording);
+
+    let mut harness = TestHarness::create(widget);
+    measured_recording.clear();
+    parent_recording.clear();
+
+    harness.submit_command(MEASURED.with(ESTIMATE));
+    assert_eq!(layout_count(&measured_recording), 1);
+    assert_eq!(layout_count(&parent_recording), 0);
+    assert_eq!(
+        harness.get_widget(measured_id).state().layout_rect().size(),
+        ESTIMATE
+    );
+}
+
+#[test]
+fn wrong_estimate_lays_out_parent() {
+    let [measured_id] = widget_ids();
+    let parent_recording = Recording::default();
+    let widget = Flex::row()
+        .with_child_id(measured_widget(), measured_id)
+        .record(&parent_recording);
+
+    let mut harness = TestHarness::create(widget);
+    parent_recording.clear();
+
+    harness.submit_command(MEASURED.with(Size::new(80.0, 30.0)));
+    assert_eq!(layout_count(&parent_recording), 1);
+    assert_eq!(
+        harness.get_widget(measured_id).state().layout_rect().size(),
+        Size::new(80.0, 30.0)
+    );
+}
@@ -0,0 +1,176 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Tests for moving widgets between containers with `WidgetPod::take` and `adopt`,
+//! and for adopting widgets built ahead of time with `EventCtx::prewarm_child`.
+
+use crate::testing::{
+    widget_ids, ModularWidget, Record, Recording, TestHarness, TestWidgetExt as _,
+};
+use crate::widget::{Flex, SizedBox, TextBox, WidgetMut};
+use crate::*;
+
+fn child_ids(harness: &TestHarness, id: WidgetId) -> Vec<WidgetId> {
+    harness
+        .get_widget(id)
+        .children()
+        .iter()
+        .map(|child| child.id())
+        .collect()
+}
+
+#[test]
+fn move_focused_textbox_between_panes() {
+    let [textbox_id, pane_a, pane_b] = widget_ids();
+    let widget = Flex::row()
+        .with_child_id(
+            Flex::column().with_child_id(TextBox::new("Hello"), textbox_id),
+            pane_a,
+        )
+        .with_child_id(Flex::column(), pane_b);
+
+    let mut harness = TestHarness::create(widget);
+    harness.mouse_click_on(textbox_id);
+    assert_eq!(harness.focused_widget().unwrap().id(), textbox_id);
+
+    harness.edit_root_widget(|mut root, _| {
+        let mut root = root.downcast::<Flex>().unwrap();
+        let textbox = {
+            let mut pane_a = root.child_mut(0).unwrap();
+            let mut pane_a = pane_a.downcast::<Flex>().unwrap();
+            pane_a.take_child(0).unwrap()
+        };
+        let mut pane_b = root.child_mut(1).unwrap();
+        let mut pane_b = pane_b.downcast::<Flex>().unwrap();
+        pane_b.adopt_child(textbox);
+    });
+
+    assert_eq!(child_ids(&harness, pane_a), vec![]);
+    assert_eq!(child_ids(&harness, pane_b), vec![textbox_id]);
+    assert_eq!(
+        harness.get_widget(textbox_id).state().parent_id,
+        Some(pane_b)
+    );
+    assert_eq!(harness.focused_widget().unwrap().id(), textbox_id);
+    assert!(!harness.get_widget(pane_a).state().has_focus);
+    assert!(harness.get_widget(pane_b).state().has_focus);
+    assert_eq!(
+        harness
+            .get_widget(textbox_id)
+            .downcast::<TextBox>()
+            .unwrap()
+            .text(),
+        "Hello"
+    );
+
+    // The moved textbox still gets keyboard input.
+    harness.keyboard_type_chars("a");
+    assert!(matches!(
+        harness.pop_action(),
+        Some((Action::TextChanged(_), id)) if id == textbox_id
+    ));
+}
+
+#[test]
+fn moved_widget_is_not_added_twice() {
+    let [pane_a, pane_b] = widget_ids();
+    let record = Recording::default();
+    let widget = Flex::row()
+        .with_child_id(
+            Flex::column().with_child(SizedBox::empty().record(&record)),
+            pane_a,
+        )
+        .with_child_id(Flex::column(), pane_b);
+
+    let mut harness = TestHarness::create(widget);
+    record.clear();
+
+    harness.edit_root_widget(|mut root, _| {
+        let mut root = root.downcast::<Flex>().unwrap();
+        let child = {
+            let mut pane_a = root.child_mut(0).unwrap();
+            let mut pane_a = pane_a.downcast::<Flex>().unwrap();
+            pane_a.take_child(0).unwrap()
+        };
+        let mut pane_b = root.child_mut(1).unwrap();
+        let mut pane_b = pane_b.downcast::<Flex>().unwrap();
+        pane_b.adopt_child(child);
+    });
+
+    assert!(!record
+        .drain()
+        .into_iter()
+        .any(|rec| matches!(rec, Record::L(LifeCycle::WidgetAdded))));
+    assert_eq!(child_ids(&harness, pane_b).len(), 1);
+}
+
+#[test]
+fn prewarmed_widget_is_not_added_twice() {
+    const SHOW_PAGE: Selector = Selector::new("masonry-test.show-page");
+    const PAGE_READY: Selector = Selector::new("masonry-test.page-ready");
+
+    let [pages_id, prewarmer_id, page_id] = widget_ids();
+    let record = Recording::default();
+    // The page sends itself a command when it's initialized, which it can only
+    // receive once it's adopted.
+    let page = ModularWidget::new(())
+        .lifecycle_fn(|_, ctx, event, _| {
+            if let LifeCycle::WidgetAdded = event {
+                ctx.submit_command(PAGE_READY.to(ctx.widget_id()));
+            }
+        })
+        .layout_fn(|_, _, bc, _| bc.max());
+    let page = WidgetPod::new_with_id(page.record(&record), page_id);
+
+    let prewarmer = ModularWidget::new(Some(page.boxed()))
+        .event_fn(move |next, ctx, event, env| match event {
+            Event::Command(cmd) if cmd.is(command::PREWARM) => {
+                let bc = BoxConstraints::tight(Size::new(50.0, 50.0));
+                ctx.prewarm_child(next.as_mut().unwrap(), &bc, env);
+            }
+            Event::Command(cmd) if cmd.is(SHOW_PAGE) => {
+                let page = next.take().unwrap();
+                ctx.mutate_later(pages_id, move |mut pages: WidgetMut<'_, '_, Flex>| {
+                    pages.adopt_child(page);
+                });
+            }
+            _ => {}
+        })
+        .lifecycle_fn(|_, ctx, event, _| {
+            if let LifeCycle::WidgetAdded = event {
+                ctx.request_prewarm();
+            }
+        });
+    let pages = Flex::column().with_child_id(prewarmer, prewarmer_id);
+
+    let mut harness = TestHarness::create(Flex::row().with_child_id(pages, pages_id));
+
+    // The page was initialized and laid out before being shown, but its command
+    // was held back.
+    let records = record.drain();
+    assert!(records
+        .iter()
+        .any(|rec| matches!(rec, Record::L(LifeCycle::WidgetAdded))));
+    assert!(records
+        .iter()
+        .any(|rec| matches!(rec, Record::Layout(size) if *size == Size::new(50.0, 50.0))));
+    assert!(!records
+        .iter()
+        .any(|rec| matches!(rec, Record::E(Event::Command(cmd)) if cmd.is(PAGE_READY))));
+
+    harness.submit_command(SHOW_PAGE.to(prewarmer_id));
+
+    let records = record.drain();
+    assert!(!records
+        .iter()
+        .any(|rec| matches!(rec, Record::L(LifeCycle::WidgetAdded))));
+    assert!(records
+        .iter()
+        .any(|rec| matches!(rec, Record::E(Event::Command(cmd)) if cmd.is(PAGE_READY))));
+    assert_eq!(child_ids(&harness, pages_id), vec![prewarmer_id, page_id]);
+    assert_eq!(
+        harness.get_widget(page_id).state().parent_id,
+        Some(pages_id)
+    );
+}
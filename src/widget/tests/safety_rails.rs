@@ -49,6 +49,35 @@ fn check_forget_to_recurse_lifecycle() {
     let _harness = TestHarness::create(widget);
 }
 
+#[test]
+fn visit_children_marks_skipped_children() {
+    let children = vec![WidgetPod::new(Flex::row()), WidgetPod::new(Flex::row())];
+    let widget = ModularWidget::new(children)
+        .event_fn(|children, ctx, event, env| {
+            // Only the first child gets events.
+            ctx.visit_children(children.iter_mut().take(1), |child, ctx| {
+                child.on_event(ctx, event, env);
+            });
+            ctx.visit_children(children.iter_mut().skip(1), |_, _| {});
+        })
+        .lifecycle_fn(|children, ctx, event, env| {
+            ctx.visit_children(children.iter_mut(), |child, ctx| {
+                child.lifecycle(ctx, event, env);
+            });
+        })
+        .layout_fn(|children, ctx, bc, env| {
+            for child in children.iter_mut() {
+                child.layout(ctx, bc, env);
+                ctx.place_child(child, Point::ZERO, env);
+            }
+            bc.max()
+        })
+        .children_fn(|children| children.iter().map(|child| child.as_dyn()).collect());
+
+    let mut harness = TestHarness::create(widget);
+    harness.mouse_move(Point::ZERO);
+}
+
 #[should_panic(expected = "before receiving WidgetAdded.")]
 #[test]
 fn check_forget_to_recurse_widget_added() {
@@ -64,6 +93,28 @@ fn check_forget_to_recurse_widget_added() {
     let _harness = TestHarness::create(widget);
 }
 
+#[test]
+fn emit_invariant_violation_as_action() {
+    set_invariant_policy(InvariantPolicy::EmitAction);
+    let widget = make_parent_widget(Flex::row()).lifecycle_fn(|child, ctx, event, env| {
+        if let LifeCycle::WidgetAdded = event {
+            ctx.skip_child(child);
+        } else {
+            child.lifecycle(ctx, event, env);
+        }
+    });
+
+    let mut harness = TestHarness::create(widget);
+    set_invariant_policy(InvariantPolicy::Panic);
+
+    match harness.pop_action() {
+        Some((Action::InvariantViolation(message), _)) => {
+            assert!(message.contains("before receiving WidgetAdded"));
+        }
+        other => panic!("expected an invariant violation, got {:?}", other),
+    }
+}
+
 #[should_panic(expected = "not visited in method layout")]
 #[test]
 fn check_forget_to_recurse_layout() {
@@ -0,0 +1,86 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Tests for window-level shortcuts and key chords.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use instant::Duration;
+
+use crate::shell::{KeyEvent, RawMods};
+use crate::shortcuts::{Shortcuts, PENDING_CHORD_CHANGED};
+use crate::testing::{ModularWidget, TestHarness};
+use crate::*;
+
+const COMMENT: Selector = Selector::new("masonry-test.comment");
+const SAVE: Selector = Selector::new("masonry-test.save");
+
+fn command_recorder() -> (
+    ModularWidget<Rc<RefCell<Vec<String>>>>,
+    Rc<RefCell<Vec<String>>>,
+) {
+    let commands = Rc::new(RefCell::new(Vec::new()));
+    let widget = ModularWidget::new(commands.clone()).event_fn(|commands, _, event, _| {
+        if let Event::Command(cmd) = event {
+            let record = match cmd.try_get(PENDING_CHORD_CHANGED) {
+                Some(chord) => format!("pending: {}", chord),
+                None if cmd.is(COMMENT) => "comment".to_string(),
+                None if cmd.is(SAVE) => "save".to_string(),
+                None => return,
+            };
+            commands.borrow_mut().push(record);
+        }
+    });
+    (widget, commands)
+}
+
+fn press(harness: &mut TestHarness, mods: RawMods, key: &str) {
+    harness.process_event(Event::KeyDown(KeyEvent::for_test(mods, key)));
+}
+
+fn harness_with_shortcuts() -> (TestHarness, Rc<RefCell<Vec<String>>>) {
+    let (widget, commands) = command_recorder();
+    let mut harness = TestHarness::create(widget);
+    harness.window_mut().shortcuts = Shortcuts::new()
+        .with_shortcut("Ctrl+K Ctrl+C".parse().unwrap(), COMMENT)
+        .with_shortcut("Ctrl+S".parse().unwrap(), SAVE);
+    (harness, commands)
+}
+
+#[test]
+fn chord_submits_command() {
+    let (mut harness, commands) = harness_with_shortcuts();
+
+    press(&mut harness, RawMods::Ctrl, "s");
+    press(&mut harness, RawMods::Ctrl, "k");
+    assert_eq!(harness.window().pending_chord().len(), 1);
+    press(&mut harness, RawMods::Ctrl, "c");
+
+    assert_eq!(
+        *commands.borrow(),
+        ["save", "pending: Ctrl+K", "comment", "pending: "]
+    );
+    assert!(harness.window().pending_chord().is_empty());
+}
+
+#[test]
+fn chord_is_cancelled() {
+    let (mut harness, commands) = harness_with_shortcuts();
+
+    // A stroke which doesn't continue the chord cancels it.
+    press(&mut harness, RawMods::Ctrl, "k");
+    press(&mut harness, RawMods::None, "x");
+    assert_eq!(*commands.borrow(), ["pending: Ctrl+K", "pending: "]);
+    commands.borrow_mut().clear();
+
+    // So does waiting too long for the next stroke.
+    press(&mut harness, RawMods::Ctrl, "k");
+    harness.move_timers_forward(Duration::from_millis(1000));
+    assert_eq!(harness.window().pending_chord().len(), 1);
+    harness.move_timers_forward(Duration::from_millis(1000));
+    assert!(harness.window().pending_chord().is_empty());
+    press(&mut harness, RawMods::Ctrl, "c");
+    assert_eq!(*commands.borrow(), ["pending: Ctrl+K", "pending: "]);
+}
@@ -22,8 +22,8 @@ fn basic_timer() {
             _ => {}
         })
         .event_fn(|state, _ctx, event, _| {
-            if let Event::Timer(token) = event {
-                if *token == state.0.unwrap() {
+            if let Event::Timer(timer) = event {
+                if timer.token == state.0.unwrap() {
                     state.1.set(true);
                 }
             }
@@ -39,3 +39,154 @@ fn basic_timer() {
     harness.move_timers_forward(Duration::from_secs(2));
     assert_eq!(timer_handled.get(), true);
 }
+
+#[test]
+fn late_timer_reports_lateness() {
+    let lateness: Rc<Cell<Option<Duration>>> = Rc::new(None.into());
+
+    let widget = ModularWidget::new(lateness.clone())
+        .lifecycle_fn(|_, ctx, event, _| {
+            if let LifeCycle::WidgetAdded = event {
+                let _ = ctx.request_timer(Duration::from_millis(100));
+            }
+        })
+        .event_fn(|lateness, _ctx, event, _| {
+            if let Event::Timer(timer) = event {
+                assert!(timer.deadline <= timer.fired_at);
+                lateness.set(Some(timer.lateness()));
+            }
+        });
+
+    let mut harness = TestHarness::create(widget);
+
+    harness.move_timers_forward(Duration::from_millis(250));
+    assert_eq!(lateness.get(), Some(Duration::from_millis(150)));
+}
+
+#[test]
+fn caret_blink() {
+    let caret_visible: Rc<Cell<Option<bool>>> = Rc::new(None.into());
+
+    let widget = ModularWidget::new(caret_visible.clone())
+        .lifecycle_fn(|_, ctx, event, _| match event {
+            LifeCycle::WidgetAdded => ctx.register_for_caret_blink(),
+            LifeCycle::BuildFocusChain => ctx.register_for_focus(),
+            _ => {}
+        })
+        .event_fn(|caret_visible, ctx, event, _| match event {
+            Event::WindowConnected => ctx.request_focus(),
+            Event::CaretBlink(visible) => caret_visible.set(Some(*visible)),
+            _ => {}
+        });
+
+    let mut harness = TestHarness::create(widget);
+    let env = Env::with_theme();
+    let interval = Duration::from_millis(env.get(theme::CURSOR_BLINK_INTERVAL));
+
+    // The caret stays visible for a whole cycle after the widget gets focus.
+    harness.move_timers_forward(interval);
+    assert_eq!(caret_visible.get(), Some(true));
+
+    harness.move_timers_forward(interval);
+    assert_eq!(caret_visible.get(), Some(false));
+
+    harness.move_timers_forward(interval);
+    assert_eq!(caret_visible.get(), Some(true));
+
+    // Blinking pauses while the window isn't focused.
+    let event = harness
+        .window_mut()
+        .set_window_focused(false, &env)
+        .unwrap();
+    harness.process_event(event);
+    assert_eq!(caret_visible.get(), Some(false));
+
+    harness.move_timers_forward(interval * 2);
+    assert_eq!(caret_visible.get(), Some(false));
+}
+
+#[test]
+fn timers_are_held_back_while_window_is_hidden() {
+    let fired: Rc<Cell<Option<bool>>> = Rc::new(None.into());
+
+    let widget = ModularWidget::new(fired.clone())
+        .lifecycle_fn(|_, ctx, event, _| {
+            if let LifeCycle::WidgetAdded = event {
+                let _ = ctx.request_timer(Duration::from_millis(100));
+            }
+        })
+        .event_fn(|fired, ctx, event, _| {
+            if let Event::Timer(_) = event {
+                fired.set(Some(ctx.is_window_visible()));
+            }
+        });
+
+    let mut harness = TestHarness::create(widget);
+
+    harness.set_window_visible(false);
+    harness.move_timers_forward(Duration::from_millis(250));
+    assert_eq!(fired.get(), None);
+
+    // The timer is delivered once the window is shown again.
+    harness.set_window_visible(true);
+    assert_eq!(fired.get(), Some(true));
+}
+
+fn hidden_window_timers_widget(fired: Rc<Cell<u32>>) -> impl Widget {
+    ModularWidget::new(fired)
+        .lifecycle_fn(|_, ctx, event, _| {
+            if let LifeCycle::VisibilityChanged(false) = event {
+                let _ = ctx.request_timer(Duration::from_millis(100));
+                let _ = ctx.request_timer(Duration::from_millis(300));
+            }
+        })
+        .event_fn(|fired, _, event, _| {
+            if let Event::Timer(_) = event {
+                fired.set(fired.get() + 1);
+            }
+        })
+}
+
+#[test]
+fn timers_requested_while_window_is_hidden_fire_together() {
+    let fired = Rc::new(Cell::new(0));
+    let mut harness = TestHarness::create(hidden_window_timers_widget(fired.clone()));
+
+    harness.set_window_visible(false);
+    harness.move_timers_forward(Duration::from_millis(500));
+    assert_eq!(fired.get(), 0);
+
+    // Both timers are delayed to the next whole second.
+    harness.move_timers_forward(Duration::from_millis(500));
+    assert_eq!(fired.get(), 2);
+}
+
+#[test]
+fn timers_requested_while_window_is_hidden_fire_at_deadline_once_shown() {
+    let fired = Rc::new(Cell::new(0));
+    let mut harness = TestHarness::create(hidden_window_timers_widget(fired.clone()));
+
+    harness.set_window_visible(false);
+    harness.set_window_visible(true);
+    harness.move_timers_forward(Duration::from_millis(100));
+    assert_eq!(fired.get(), 1);
+    harness.move_timers_forward(Duration::from_millis(200));
+    assert_eq!(fired.get(), 2);
+
+    // The coalesced platform timers don't fire them again.
+    harness.move_timers_forward(Duration::from_secs(1));
+    assert_eq!(fired.get(), 2);
+}
+
+#[test]
+fn due_timers_requested_while_window_is_hidden_fire_when_shown() {
+    let fired = Rc::new(Cell::new(0));
+    let mut harness = TestHarness::create(hidden_window_timers_widget(fired.clone()));
+
+    harness.set_window_visible(false);
+    harness.move_timers_forward(Duration::from_millis(400));
+    assert_eq!(fired.get(), 0);
+
+    harness.set_window_visible(true);
+    assert_eq!(fired.get(), 2);
+}
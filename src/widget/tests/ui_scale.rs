@@ -0,0 +1,68 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Tests for the per-window UI scale.
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use crate::shell::{KeyEvent, RawMods};
+use crate::testing::{ModularWidget, TestHarness};
+use crate::widget::Flex;
+use crate::*;
+
+/// A widget as tall as `BASIC_WIDGET_HEIGHT`, which records the UI scale.
+fn scaled_widget(ui_scale: Rc<Cell<f64>>) -> ModularWidget<Rc<Cell<f64>>> {
+    ModularWidget::new(ui_scale).layout_fn(|ui_scale, ctx, _, env| {
+        ui_scale.set(ctx.ui_scale());
+        Size::new(10.0, env.get(theme::BASIC_WIDGET_HEIGHT))
+    })
+}
+
+#[test]
+fn set_ui_scale_scales_metrics() {
+    let ui_scale = Rc::new(Cell::new(0.0));
+    let id = WidgetId::next();
+    let widget = Flex::column().with_child_id(scaled_widget(ui_scale.clone()), id);
+    let mut harness = TestHarness::create(widget);
+    let height = |harness: &TestHarness| harness.get_widget(id).state().layout_rect().height();
+    let base_height = height(&harness);
+    assert_eq!(ui_scale.get(), 1.0);
+
+    harness.submit_command(command::SET_UI_SCALE.with(2.0));
+    assert_eq!(ui_scale.get(), 2.0);
+    assert_eq!(height(&harness), base_height * 2.0);
+
+    // The scale is clamped.
+    harness.submit_command(command::SET_UI_SCALE.with(100.0));
+    assert_eq!(ui_scale.get(), 3.0);
+}
+
+#[test]
+fn zoom_shortcuts() {
+    let ui_scale = Rc::new(Cell::new(0.0));
+    let mut harness = TestHarness::create(scaled_widget(ui_scale.clone()));
+    let (primary, primary_shift) = if cfg!(target_os = "macos") {
+        (RawMods::Meta, RawMods::MetaShift)
+    } else {
+        (RawMods::Ctrl, RawMods::CtrlShift)
+    };
+    let press = |harness: &mut TestHarness, mods, key: &str| {
+        harness.process_event(Event::KeyDown(KeyEvent::for_test(mods, key)));
+        ui_scale.get()
+    };
+
+    assert_eq!(press(&mut harness, primary, "="), 1.1);
+    // Layouts where `+` is typed with Shift.
+    assert_eq!(press(&mut harness, primary_shift, "+"), 1.2);
+    assert_eq!(press(&mut harness, primary, "-"), 1.1);
+    // Shift is only ignored for `+`.
+    assert_eq!(press(&mut harness, primary_shift, "-"), 1.1);
+    assert_eq!(press(&mut harness, primary_shift, "0"), 1.1);
+    assert_eq!(press(&mut harness, primary, "0"), 1.0);
+
+    let env = harness.env().clone();
+    harness.set_env(env.adding(theme::ZOOM_SHORTCUTS_ENABLED, false));
+    assert_eq!(press(&mut harness, primary, "="), 1.0);
+}
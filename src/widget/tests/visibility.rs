@@ -0,0 +1,59 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Tests related to widget visibility.
+
+use crate::testing::{widget_ids, Record, Recording, TestHarness, TestWidgetExt};
+use crate::widget::{Flex, Portal, SizedBox};
+use crate::*;
+
+fn is_visible(harness: &TestHarness, id: WidgetId) -> bool {
+    harness.get_widget(id).state().is_visible
+}
+
+#[test]
+fn scrolled_out_widgets_are_invisible() {
+    let [top_id, bottom_id] = widget_ids();
+    let bottom_recording = Recording::default();
+    let content = Flex::column()
+        .with_child_id(SizedBox::empty().width(100.0).height(500.0), top_id)
+        .with_child_id(
+            SizedBox::empty()
+                .width(100.0)
+                .height(500.0)
+                .record(&bottom_recording),
+            bottom_id,
+        );
+
+    let mut harness = TestHarness::create_with_size(Portal::new(content), Size::new(400.0, 400.0));
+    assert!(is_visible(&harness, top_id));
+    assert!(!is_visible(&harness, bottom_id));
+    bottom_recording.clear();
+
+    harness.edit_root_widget(|mut portal, _| {
+        let mut portal = portal.downcast::<Portal<Flex>>().unwrap();
+        portal.set_viewport_pos(Point::new(0.0, 600.0));
+    });
+    assert!(!is_visible(&harness, top_id));
+    assert!(is_visible(&harness, bottom_id));
+    assert!(bottom_recording
+        .drain()
+        .iter()
+        .any(|record| matches!(record, Record::L(LifeCycle::VisibilityChanged(true)))));
+}
+
+#[test]
+fn minimized_window_hides_widgets() {
+    let [id] = widget_ids();
+    let widget = Flex::row().with_child_id(SizedBox::empty().width(50.0).height(50.0), id);
+
+    let mut harness = TestHarness::create(widget);
+    assert!(is_visible(&harness, id));
+
+    harness.set_window_visible(false);
+    assert!(!is_visible(&harness, id));
+
+    harness.set_window_visible(true);
+    assert!(is_visible(&harness, id));
+}
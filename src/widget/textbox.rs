@@ -4,24 +4,27 @@
 
 //! A textbox widget.
 
+use std::ops::Range;
+use std::rc::Rc;
 use std::sync::Arc;
-use std::time::Duration;
 
 use smallvec::{smallvec, SmallVec};
 use tracing::{trace_span, Span};
 
+use crate::access_keys::ACTIVATE;
 use crate::action::Action;
 use crate::kurbo::{Affine, Insets};
 use crate::piet::{RenderContext as _, TextLayout as _};
-use crate::shell::{HotKey, KeyEvent, SysMods, TimerToken};
-use crate::text::{ImeInvalidation, Selection, TextAlignment, TextComponent, TextLayout};
+use crate::shell::{HotKey, KeyEvent, SysMods};
+use crate::text::{
+    ImeInvalidation, Selection, SpellChecker, TextAlignment, TextComponent, TextLayout,
+};
 use crate::widget::{Portal, WidgetMut, WidgetRef};
 use crate::{
     theme, ArcStr, BoxConstraints, Command, Env, Event, EventCtx, LayoutCtx, LifeCycle,
     LifeCycleCtx, PaintCtx, Point, Rect, Size, StatusChange, Vec2, Widget, WidgetPod,
 };
 
-const CURSOR_BLINK_DURATION: Duration = Duration::from_millis(500);
 const MAC_OR_LINUX: bool = cfg!(any(target_os = "macos", target_os = "linux"));
 
 // TODO - Implement formatters (TextBox where the text represents a value of some other type).
@@ -47,7 +50,6 @@ pub struct TextBox {
     /// then we select our entire contents.
     was_focused_from_click: bool,
     cursor_on: bool,
-    cursor_timer: TimerToken,
     /// if `true` (the default), this textbox will attempt to change focus on tab.
     ///
     /// You can override this in a controller if you want to customize tab
@@ -79,7 +81,6 @@ impl TextBox {
             multiline: false,
             was_focused_from_click: false,
             cursor_on: false,
-            cursor_timer: TimerToken::INVALID,
             handles_tab_notifications: true,
             text_pos: Point::ZERO,
         }
@@ -263,6 +264,18 @@ impl TextBox {
 }
 
 impl TextBox {
+    /// Builder-style method to set a spell checker, which underlines misspelled words.
+    ///
+    /// Right-clicking an underlined word shows a context menu of suggestions.
+    pub fn with_spell_checker(self, checker: Rc<dyn SpellChecker>) -> Self {
+        self.inner
+            .as_ref()
+            .child()
+            .borrow_mut()
+            .set_spell_checker(Some(checker));
+        self
+    }
+
     /// Builder-style method to set the `TextBox`'s placeholder text.
     pub fn with_placeholder(mut self, placeholder: impl Into<ArcStr>) -> Self {
         self.set_placeholder(placeholder);
@@ -293,13 +306,35 @@ impl TextBox {
             .to_string()
     }
 
-    fn text_len(&self) -> usize {
-        self.inner.as_ref().child().borrow().layout.text_len()
+    /// The ranges of the words the spell checker found misspelled.
+    ///
+    /// This is updated on layout.
+    pub fn misspelled_ranges(&self) -> Vec<Range<usize>> {
+        self.inner
+            .as_ref()
+            .child()
+            .borrow()
+            .misspelled_ranges()
+            .to_vec()
+    }
+
+    /// The ranges of text painted with a highlight behind them.
+    pub fn highlighted_ranges(&self) -> Vec<Range<usize>> {
+        self.inner
+            .as_ref()
+            .child()
+            .borrow()
+            .highlighted_ranges()
+            .to_vec()
+    }
+
+    /// The bounding box of the text in `range`, in the coordinates of the text.
+    fn rect_for_range(&self, range: Range<usize>) -> Option<Rect> {
+        self.inner.as_ref().child().borrow().rect_for_range(range)
     }
 
-    fn reset_cursor_blink(&mut self, token: TimerToken) {
-        self.cursor_on = true;
-        self.cursor_timer = token;
+    fn text_len(&self) -> usize {
+        self.inner.as_ref().child().borrow().layout.text_len()
     }
 
     fn should_draw_cursor(&self) -> bool {
@@ -319,6 +354,39 @@ impl<'a, 'b> TextBoxMut<'a, 'b> {
     pub fn set_text(&mut self, new_text: impl Into<String>) {
         self.inner_mut().child_mut().set_text(new_text.into());
     }
+
+    /// Set the selection, eg to move the caret to the end of the text.
+    pub fn set_selection(&mut self, selection: Selection) {
+        self.inner_mut().child_mut().set_selection(selection);
+    }
+
+    /// Set the spell checker, or remove it with `None`.
+    pub fn set_spell_checker(&mut self, checker: Option<Rc<dyn SpellChecker>>) {
+        self.inner_mut().child_mut().set_spell_checker(checker);
+    }
+
+    /// Paint a highlight behind each range of text, eg to show search results.
+    ///
+    /// This replaces the previous highlights; pass an empty `Vec` to remove them.
+    /// Highlights aren't moved when the text is edited.
+    pub fn highlight_ranges(&mut self, ranges: Vec<Range<usize>>) {
+        self.inner_mut().child_mut().set_highlight_ranges(ranges);
+    }
+
+    /// Scroll the text in `range` into view.
+    ///
+    /// This pans the box's own viewport, then any [`Portal`] the box is in.
+    /// This is not valid until `layout` has been called.
+    pub fn scroll_to_range(&mut self, range: Range<usize>) {
+        let rect = match self.widget.rect_for_range(range) {
+            Some(rect) => rect,
+            None => return,
+        };
+        self.inner_mut().pan_viewport_to(rect);
+        // The viewport is at the box's origin.
+        let viewport_pos = self.widget.inner.widget().get_viewport_pos();
+        self.ctx.request_pan_to_rect(rect - viewport_pos.to_vec2());
+    }
 }
 
 impl TextBox {
@@ -443,26 +511,26 @@ impl Widget for TextBox {
                     if !mouse.focus {
                         ctx.request_focus();
                         self.was_focused_from_click = true;
-                        self.reset_cursor_blink(ctx.request_timer(CURSOR_BLINK_DURATION));
+                        self.cursor_on = true;
+                        ctx.reset_caret_blink();
                     } else {
                         ctx.set_handled();
                     }
                 }
             }
-            Event::Timer(id) => {
+            Event::Command(cmd) if cmd.is(ACTIVATE) && self.inner.as_ref().child().can_write() => {
                 if !ctx.is_disabled() {
-                    if *id == self.cursor_timer && ctx.has_focus() {
-                        self.cursor_on = !self.cursor_on;
-                        ctx.request_paint();
-                        self.cursor_timer = ctx.request_timer(CURSOR_BLINK_DURATION);
-                    }
-                } else if self.cursor_on {
-                    self.cursor_on = false;
-                    ctx.request_paint();
+                    ctx.request_focus();
+                    ctx.set_handled();
                 }
             }
+            Event::CaretBlink(visible) => {
+                self.cursor_on = *visible && !ctx.is_disabled();
+                ctx.request_paint();
+            }
             Event::ImeStateChange => {
-                self.reset_cursor_blink(ctx.request_timer(CURSOR_BLINK_DURATION));
+                self.cursor_on = true;
+                ctx.reset_caret_blink();
                 // TODO - external_text_change.is_some()
             }
             Event::Command(ref cmd)
@@ -537,7 +605,8 @@ impl Widget for TextBox {
                     let mut child = ctx.get_mut(&mut self.inner);
                     child.child_mut().set_focused(true);
                 }
-                self.reset_cursor_blink(ctx.request_timer(CURSOR_BLINK_DURATION));
+                self.cursor_on = true;
+                ctx.reset_caret_blink();
                 self.was_focused_from_click = false;
                 ctx.request_paint();
             }
@@ -563,7 +632,7 @@ impl Widget for TextBox {
                     }
                 }
 
-                self.cursor_timer = TimerToken::INVALID;
+                self.cursor_on = false;
                 self.was_focused_from_click = false;
                 ctx.request_paint();
             }
@@ -575,6 +644,10 @@ impl Widget for TextBox {
         match event {
             LifeCycle::WidgetAdded => {
                 ctx.register_text_input(self.inner.as_ref().child().input_handler());
+                ctx.set_draws_own_focus_ring(true);
+                ctx.register_for_caret_blink();
+                // Clipboard commands are sent to the window.
+                ctx.register_for_commands();
             }
             LifeCycle::BuildFocusChain => {
                 //TODO: make this a configurable option? maybe?
@@ -732,6 +805,7 @@ mod tests {
     use super::*;
     use crate::action::Action;
     use crate::assert_render_snapshot;
+    use crate::context_menu::ContextMenuItem;
     use crate::testing::{widget_ids, TestHarness, TestWidgetExt as _};
 
     #[test]
@@ -775,6 +849,87 @@ mod tests {
         );
     }
 
+    #[cfg(all(feature = "gtk", target_os = "linux"))]
+    #[test]
+    fn middle_click_pastes_primary_selection() {
+        use crate::clipboard::{put_string, ClipboardKind};
+        use crate::shell::MouseButton;
+
+        let [textbox_id] = widget_ids();
+        let textbox = TextBox::new("").with_id(textbox_id);
+        let mut harness = TestHarness::create(textbox);
+
+        put_string(ClipboardKind::PrimarySelection, "World");
+        harness.mouse_move_to(textbox_id);
+        harness.mouse_button_press(MouseButton::Middle);
+        harness.mouse_button_release(MouseButton::Middle);
+
+        assert_eq!(
+            harness.pop_action(),
+            Some((Action::TextChanged("World".to_string()), textbox_id))
+        );
+    }
+
+    #[test]
+    fn spelling_suggestions() {
+        use crate::shell::MouseButton;
+
+        struct Checker;
+
+        impl SpellChecker for Checker {
+            fn check(&self, text: &str) -> Vec<Range<usize>> {
+                text.match_indices("cta")
+                    .map(|(i, word)| i..i + word.len())
+                    .collect()
+            }
+
+            fn suggestions(&self, _word: &str) -> Vec<String> {
+                vec!["cat".to_string(), "act".to_string()]
+            }
+        }
+
+        let [textbox_id] = widget_ids();
+        let textbox = TextBox::new("a cta sat")
+            .with_spell_checker(Rc::new(Checker))
+            .with_id(textbox_id);
+        let mut harness = TestHarness::create(textbox);
+
+        let textbox = harness.get_widget(textbox_id);
+        let textbox = textbox.downcast::<TextBox>().unwrap();
+        assert_eq!(textbox.misspelled_ranges(), [2..5]);
+        let word_pos = textbox
+            .inner
+            .as_ref()
+            .child()
+            .borrow()
+            .layout
+            .point_for_text_position(3);
+        let word_pos = word_pos + textbox.text_position().to_vec2();
+
+        harness.mouse_move(word_pos);
+        harness.mouse_button_press(MouseButton::Right);
+        harness.mouse_button_release(MouseButton::Right);
+
+        let labels: Vec<_> = harness
+            .window()
+            .context_menu()
+            .unwrap()
+            .items()
+            .iter()
+            .map(|item| match item {
+                ContextMenuItem::Command { label, .. } => label.to_string(),
+                _ => String::new(),
+            })
+            .collect();
+        assert_eq!(labels, ["cat", "act"]);
+
+        harness.select_context_menu_item("cat");
+        assert_eq!(
+            harness.pop_action(),
+            Some((Action::TextChanged("a cat sat".to_string()), textbox_id))
+        );
+    }
+
     #[test]
     fn simple_textbox_placeholder() {
         let textbox = TextBox::new("").with_placeholder("placeholder text");
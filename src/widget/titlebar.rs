@@ -0,0 +1,302 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Standard titlebar behaviors for the custom titlebar of an undecorated window.
+
+use druid_shell::{MouseButton, WindowState};
+use smallvec::{smallvec, SmallVec};
+use tracing::{trace, trace_span, Span};
+
+use crate::command::{CLOSE_WINDOW, CONFIGURE_WINDOW};
+use crate::context_menu::ContextMenu;
+use crate::widget::{WidgetMut, WidgetRef};
+use crate::{
+    BoxConstraints, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx, Point,
+    Selector, Size, StatusChange, Target, Widget, WidgetPod, WindowConfig,
+};
+
+/// Submitted to a window when the pointer moves to another zone of its custom
+/// titlebar, or leaves it, with the zone now under the pointer.
+///
+/// druid-shell can't report the window buttons to the OS, so platform integrations
+/// which do, eg to show the Windows 11 snap layouts when the maximize button is
+/// hovered, can handle this in their [`AppDelegate`](crate::AppDelegate).
+pub const TITLEBAR_ZONE_HOVERED: Selector<Option<TitlebarZone>> =
+    Selector::new("masonry-builtin.titlebar-zone-hovered");
+
+/// The part of a custom titlebar a [`TitlebarRegion`] stands for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TitlebarZone {
+    /// The caption, which moves the window when dragged.
+    Caption,
+    /// The button which minimizes the window.
+    MinimizeButton,
+    /// The button which maximizes and restores the window.
+    ///
+    /// On Windows 11, hovering it shows the snap layouts.
+    MaximizeButton,
+    /// The button which closes the window.
+    CloseButton,
+}
+
+/// A region of the custom titlebar of a window created with
+/// [`show_titlebar(false)`](crate::WindowDescription::show_titlebar), which behaves
+/// like the platform's titlebar.
+///
+/// A [`Caption`](TitlebarZone::Caption) region:
+///
+/// - moves the window when dragged;
+/// - maximizes or restores the window when double-clicked;
+/// - shows a context menu with the usual window commands (restore, minimize,
+///   maximize and close) when right-clicked. This is Masonry's own menu, not the
+///   OS's window menu.
+///
+/// The behaviors only apply to the events the child doesn't handle, so buttons and
+/// text inside the caption keep working. Regions for the window buttons don't
+/// change the events of their child, usually a button; the window submits
+/// [`TITLEBAR_ZONE_HOVERED`] when the pointer moves between zones, for platform
+/// integrations which report the buttons to the OS.
+pub struct TitlebarRegion {
+    child: WidgetPod<Box<dyn Widget>>,
+    zone: TitlebarZone,
+    double_click_maximizes: bool,
+    window_menu: bool,
+}
+
+crate::declare_widget!(TitlebarRegionMut, TitlebarRegion);
+
+// --- METHODS ---
+
+impl TitlebarRegion {
+    /// Create a caption region containing `child`, eg the window's title.
+    pub fn caption(child: impl Widget) -> Self {
+        Self::new(child, TitlebarZone::Caption)
+    }
+
+    /// Create a region for `zone` containing `child`, eg a window button.
+    pub fn new(child: impl Widget, zone: TitlebarZone) -> Self {
+        TitlebarRegion {
+            child: WidgetPod::new(child).boxed(),
+            zone,
+            double_click_maximizes: true,
+            window_menu: true,
+        }
+    }
+
+    /// Builder-style method to set whether double-clicking the caption maximizes and
+    /// restores the window. Defaults to `true`.
+    pub fn with_double_click_maximizes(mut self, double_click_maximizes: bool) -> Self {
+        self.double_click_maximizes = double_click_maximizes;
+        self
+    }
+
+    /// Builder-style method to set whether right-clicking the caption shows a menu
+    /// with the window commands. Defaults to `true`.
+    pub fn with_window_menu(mut self, window_menu: bool) -> Self {
+        self.window_menu = window_menu;
+        self
+    }
+
+    /// The part of the titlebar the region stands for.
+    pub fn zone(&self) -> TitlebarZone {
+        self.zone
+    }
+}
+
+impl<'a, 'b> TitlebarRegionMut<'a, 'b> {
+    /// Set whether double-clicking the caption maximizes and restores the window.
+    pub fn set_double_click_maximizes(&mut self, double_click_maximizes: bool) {
+        self.widget.double_click_maximizes = double_click_maximizes;
+    }
+
+    /// Set whether right-clicking the caption shows a menu with the window commands.
+    pub fn set_window_menu(&mut self, window_menu: bool) {
+        self.widget.window_menu = window_menu;
+    }
+
+    pub fn child_mut(&mut self) -> WidgetMut<'_, 'b, Box<dyn Widget>> {
+        self.ctx.get_mut(&mut self.widget.child)
+    }
+}
+
+/// The menu shown when the caption is right-clicked, with the usual window commands.
+fn window_menu(ctx: &EventCtx, state: WindowState) -> ContextMenu {
+    let window = Target::Window(ctx.window_id());
+    let set_state = |state| {
+        CONFIGURE_WINDOW
+            .with(WindowConfig::default().set_window_state(state))
+            .to(window)
+    };
+    let menu = if state == WindowState::Restored {
+        ContextMenu::new().with_disabled_item("Restore")
+    } else {
+        ContextMenu::new().with_item("Restore", set_state(WindowState::Restored))
+    };
+    let menu = menu.with_item("Minimize", set_state(WindowState::Minimized));
+    let menu = if state == WindowState::Maximized {
+        menu.with_disabled_item("Maximize")
+    } else {
+        menu.with_item("Maximize", set_state(WindowState::Maximized))
+    };
+    menu.with_separator()
+        .with_item("Close", CLOSE_WINDOW.to(window))
+}
+
+// --- TRAIT IMPLS ---
+
+impl Widget for TitlebarRegion {
+    fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, env: &Env) {
+        self.child.on_event(ctx, event, env);
+        if ctx.is_handled() || self.zone != TitlebarZone::Caption {
+            return;
+        }
+
+        match event {
+            Event::MouseDown(mouse) if mouse.button == MouseButton::Left && mouse.count == 2 => {
+                if self.double_click_maximizes {
+                    let state = match ctx.window().get_window_state() {
+                        WindowState::Maximized => WindowState::Restored,
+                        _ => WindowState::Maximized,
+                    };
+                    trace!(
+                        "Double-click on caption, setting window state to {:?}",
+                        state
+                    );
+                    ctx.window().set_window_state(state);
+                    ctx.set_handled();
+                }
+            }
+            Event::MouseDown(mouse) if mouse.button == MouseButton::Left => {
+                // The platform moves the window while the button is held.
+                ctx.window().handle_titlebar(true);
+            }
+            Event::MouseUp(mouse) if mouse.button == MouseButton::Right => {
+                if self.window_menu {
+                    let menu = window_menu(ctx, ctx.window().get_window_state());
+                    ctx.show_context_menu(menu, mouse.pos);
+                    ctx.set_handled();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange, _env: &Env) {}
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, env: &Env) {
+        self.child.lifecycle(ctx, event, env);
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, env: &Env) -> Size {
+        let size = self.child.layout(ctx, bc, env);
+        ctx.place_child(&mut self.child, Point::ORIGIN, env);
+        ctx.set_paint_insets(self.child.compute_parent_paint_insets(size));
+        ctx.set_baseline_offset(self.child.baseline_offset());
+        trace!("Computed layout: size={}", size);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, env: &Env) {
+        self.child.paint(ctx, env);
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        smallvec![self.child.as_dyn()]
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("TitlebarRegion")
+    }
+}
+
+/// The zone of the innermost titlebar region under `pos`, in window coordinates.
+pub(crate) fn titlebar_zone_at(
+    widget: WidgetRef<'_, dyn Widget>,
+    pos: Point,
+) -> Option<TitlebarZone> {
+    let state = widget.state();
+    if state.is_stashed || !state.window_layout_rect().contains(pos) {
+        return None;
+    }
+    let inner_zone = widget
+        .children()
+        .into_iter()
+        .find_map(|child| titlebar_zone_at(child, pos));
+    inner_zone.or_else(|| {
+        widget
+            .downcast::<TitlebarRegion>()
+            .map(|region| region.zone())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context_menu::ContextMenuItem;
+    use crate::testing::{widget_ids, TestHarness};
+    use crate::widget::{Flex, Label, SizedBox};
+    use crate::WidgetId;
+
+    fn titlebar(maximize_button: WidgetId) -> impl Widget {
+        let buttons = Flex::row().with_child_id(
+            TitlebarRegion::new(
+                SizedBox::empty().width(40.0).height(30.0),
+                TitlebarZone::MaximizeButton,
+            ),
+            maximize_button,
+        );
+        let row = Flex::row()
+            .with_flex_child(Label::new("My window"), 1.0)
+            .with_child(buttons);
+        TitlebarRegion::caption(SizedBox::new(row).width(300.0).height(30.0))
+    }
+
+    #[test]
+    fn innermost_zone_is_found() {
+        let [maximize_button] = widget_ids();
+        let harness =
+            TestHarness::create_with_size(titlebar(maximize_button), Size::new(300.0, 30.0));
+
+        let button_center = harness
+            .get_widget(maximize_button)
+            .state()
+            .window_layout_rect()
+            .center();
+        let root = harness.root_widget();
+        assert_eq!(
+            titlebar_zone_at(root, button_center),
+            Some(TitlebarZone::MaximizeButton)
+        );
+        assert_eq!(
+            titlebar_zone_at(root, Point::new(10.0, 10.0)),
+            Some(TitlebarZone::Caption)
+        );
+        assert_eq!(titlebar_zone_at(root, Point::new(10.0, 50.0)), None);
+    }
+
+    #[test]
+    fn right_click_shows_window_menu() {
+        let [maximize_button] = widget_ids();
+        let mut harness =
+            TestHarness::create_with_size(titlebar(maximize_button), Size::new(300.0, 30.0));
+
+        harness.mouse_move(Point::new(10.0, 10.0));
+        harness.mouse_button_press(MouseButton::Right);
+        harness.mouse_button_release(MouseButton::Right);
+
+        let items: Vec<_> = harness
+            .window()
+            .context_menu()
+            .expect("no window menu")
+            .items()
+            .iter()
+            .map(|item| match item {
+                ContextMenuItem::Command { label, .. } => format!("{label}"),
+                ContextMenuItem::Disabled { label } => format!("({label})"),
+                ContextMenuItem::Separator => "-".to_string(),
+            })
+            .collect();
+        assert_eq!(items, ["(Restore)", "Minimize", "Maximize", "-", "Close"]);
+    }
+}
@@ -0,0 +1,372 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! A wrapper displaying short-lived notifications on top of its child.
+
+use std::time::Duration;
+
+use druid_shell::TimerToken;
+use smallvec::SmallVec;
+use tracing::{trace, trace_span, Span};
+
+use crate::kurbo::Vec2;
+use crate::text::TextLayout;
+use crate::widget::{StoreInWidgetMut, WidgetPod, WidgetRef};
+use crate::{
+    theme, ArcStr, BoxConstraints, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx,
+    PaintCtx, Point, Rect, RenderContext, Selector, Size, StatusChange, Widget,
+};
+
+/// Show a toast in the window's [`Toasts`] overlay.
+///
+/// This is usually sent with [`EventCtx::show_toast`].
+pub const SHOW_TOAST: Selector<ToastSpec> = Selector::new("masonry-builtin.show-toast");
+
+/// How long a toast is shown by default.
+const DEFAULT_DURATION: Duration = Duration::from_secs(4);
+
+/// How long toasts take to slide in and out.
+const ANIMATION_DURATION: Duration = Duration::from_millis(150);
+
+/// How far toasts slide when entering and leaving.
+const SLIDE_DISTANCE: f64 = 40.0;
+
+/// Toasts beyond this number push the oldest ones out.
+const MAX_TOASTS: usize = 5;
+
+const MAX_WIDTH: f64 = 300.0;
+const PADDING: f64 = 8.0;
+const MARGIN: f64 = 12.0;
+const SPACING: f64 = 6.0;
+const CORNER_RADIUS: f64 = 4.0;
+
+/// The content of a toast.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ToastSpec {
+    /// The text displayed.
+    pub text: ArcStr,
+    /// How long the toast is displayed, not counting the time it's hovered.
+    pub duration: Duration,
+}
+
+impl ToastSpec {
+    /// Create a toast displaying `text` for four seconds.
+    pub fn new(text: impl Into<ArcStr>) -> Self {
+        ToastSpec {
+            text: text.into(),
+            duration: DEFAULT_DURATION,
+        }
+    }
+
+    /// Builder-style method to set how long the toast is displayed.
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = duration;
+        self
+    }
+}
+
+/// A wrapper which displays toasts, ie short notifications, in the bottom-right
+/// corner of its child.
+///
+/// This is meant to wrap the root widget of a window. Widgets show a toast with
+/// [`EventCtx::show_toast`]; toasts are stacked with the newest at the bottom, and
+/// are painted on top of the rest of the window.
+///
+/// Each toast is dismissed after its [duration](ToastSpec::duration), or when it's
+/// clicked. A hovered toast stays open; its duration starts over when the mouse
+/// leaves it.
+#[derive(StoreInWidgetMut)]
+pub struct Toasts<W: Widget> {
+    #[widget_mut(child)]
+    child: WidgetPod<W>,
+    toasts: Vec<Toast>,
+}
+
+struct Toast {
+    spec: ToastSpec,
+    text: TextLayout<ArcStr>,
+    /// The dismissal timer; invalid if it fired while the toast was hovered.
+    timer: TimerToken,
+    /// How far the toast has slid in, between 0.0 and 1.0.
+    progress: f64,
+    leaving: bool,
+    hovered: bool,
+    rect: Rect,
+}
+
+impl<W: Widget> Toasts<W> {
+    /// Create a new toast overlay on top of `child`.
+    pub fn new(child: W) -> Self {
+        Toasts {
+            child: WidgetPod::new(child),
+            toasts: Vec::new(),
+        }
+    }
+
+    /// The toasts currently displayed, oldest first.
+    pub fn toasts(&self) -> impl Iterator<Item = &ToastSpec> {
+        self.toasts.iter().map(|toast| &toast.spec)
+    }
+
+    fn show(&mut self, ctx: &mut EventCtx, spec: ToastSpec) {
+        trace!("show toast {:?}", spec.text);
+        let mut text = TextLayout::new();
+        text.set_text(spec.text.clone());
+        text.set_wrap_width(MAX_WIDTH - 2.0 * PADDING);
+        let timer = ctx.request_timer(spec.duration);
+        self.toasts.push(Toast {
+            spec,
+            text,
+            timer,
+            progress: 0.0,
+            leaving: false,
+            hovered: false,
+            rect: Rect::ZERO,
+        });
+
+        let shown = self.toasts.iter().filter(|toast| !toast.leaving).count();
+        for toast in self
+            .toasts
+            .iter_mut()
+            .filter(|toast| !toast.leaving)
+            .take(shown.saturating_sub(MAX_TOASTS))
+        {
+            toast.leaving = true;
+        }
+
+        ctx.request_anim_frame();
+        ctx.request_layout();
+    }
+
+    fn update_hovered(
+        &mut self,
+        pos: Option<Point>,
+        mut request_timer: impl FnMut(Duration) -> TimerToken,
+    ) -> bool {
+        let mut changed = false;
+        for toast in &mut self.toasts {
+            let hovered = pos.map_or(false, |pos| toast.rect.contains(pos));
+            if hovered == toast.hovered {
+                continue;
+            }
+            toast.hovered = hovered;
+            changed = true;
+            if !hovered && !toast.leaving && toast.timer == TimerToken::INVALID {
+                toast.timer = request_timer(toast.spec.duration);
+            }
+        }
+        changed
+    }
+
+    fn animate(&mut self, ctx: &mut EventCtx, interval: u64) {
+        let step = interval as f64 / ANIMATION_DURATION.as_nanos() as f64;
+        for toast in &mut self.toasts {
+            if toast.leaving {
+                toast.progress = (toast.progress - step).max(0.0);
+            } else {
+                toast.progress = (toast.progress + step).min(1.0);
+            }
+        }
+
+        let count = self.toasts.len();
+        self.toasts
+            .retain(|toast| !(toast.leaving && toast.progress == 0.0));
+        if self.toasts.len() != count {
+            ctx.request_layout();
+        }
+        if self
+            .toasts
+            .iter()
+            .any(|toast| toast.leaving || toast.progress < 1.0)
+        {
+            ctx.request_anim_frame();
+        }
+        ctx.request_paint();
+    }
+}
+
+impl<W: Widget> Widget for Toasts<W> {
+    fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, env: &Env) {
+        match event {
+            Event::Command(cmd) if cmd.is(SHOW_TOAST) => {
+                self.show(ctx, cmd.get(SHOW_TOAST).clone());
+                ctx.set_handled();
+            }
+            Event::Timer(timer) => {
+                if let Some(toast) = self
+                    .toasts
+                    .iter_mut()
+                    .find(|toast| toast.timer == timer.token)
+                {
+                    toast.timer = TimerToken::INVALID;
+                    if !toast.hovered {
+                        toast.leaving = true;
+                        ctx.request_anim_frame();
+                    }
+                    ctx.set_handled();
+                }
+            }
+            Event::MouseMove(mouse) => {
+                if self.update_hovered(Some(mouse.pos), |duration| ctx.request_timer(duration)) {
+                    ctx.request_paint();
+                }
+            }
+            Event::MouseDown(mouse) => {
+                if let Some(toast) = self
+                    .toasts
+                    .iter_mut()
+                    .find(|toast| !toast.leaving && toast.rect.contains(mouse.pos))
+                {
+                    toast.leaving = true;
+                    ctx.request_anim_frame();
+                    ctx.set_handled();
+                }
+            }
+            Event::AnimFrame(interval) => self.animate(ctx, *interval),
+            _ => {}
+        }
+        self.child.on_event(ctx, event, env);
+    }
+
+    fn on_status_change(&mut self, ctx: &mut LifeCycleCtx, event: &StatusChange, _env: &Env) {
+        if let StatusChange::HotChanged(false) = event {
+            if self.update_hovered(None, |duration| ctx.request_timer(duration)) {
+                ctx.request_paint();
+            }
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, env: &Env) {
+        if let LifeCycle::WidgetAdded = event {
+            ctx.register_command_handler(SHOW_TOAST);
+        }
+        self.child.lifecycle(ctx, event, env);
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, env: &Env) -> Size {
+        let size = self.child.layout(ctx, bc, env);
+        ctx.place_child(&mut self.child, Point::ZERO, env);
+
+        // The newest toast is at the bottom, and older ones are stacked above it.
+        let mut bottom = size.height - MARGIN;
+        for toast in self.toasts.iter_mut().rev() {
+            toast.text.rebuild_if_needed(ctx.text(), env);
+            let text_size = toast.text.size();
+            let toast_size = Size::new(
+                text_size.width + 2.0 * PADDING,
+                text_size.height + 2.0 * PADDING,
+            );
+            let origin = Point::new(
+                size.width - MARGIN - toast_size.width,
+                bottom - toast_size.height,
+            );
+            toast.rect = Rect::from_origin_size(origin, toast_size);
+            bottom = toast.rect.y0 - SPACING;
+        }
+
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, env: &Env) {
+        self.child.paint(ctx, env);
+
+        for toast in &self.toasts {
+            let rect = toast.rect + Vec2::new((1.0 - toast.progress) * SLIDE_DISTANCE, 0.0);
+            let rounded_rect = rect.to_rounded_rect(CORNER_RADIUS);
+            let background = env.get(theme::BACKGROUND_LIGHT).with_alpha(toast.progress);
+            let border = env.get(theme::BORDER_DARK).with_alpha(toast.progress);
+            ctx.fill(rounded_rect, &background);
+            ctx.stroke(rounded_rect, &border, 1.0);
+            ctx.with_save(|ctx| {
+                ctx.clip(rect);
+                toast
+                    .text
+                    .draw(ctx, rect.origin() + Vec2::new(PADDING, PADDING));
+            });
+        }
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        self.pod_children()
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("Toasts")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shell::MouseButton;
+    use crate::testing::{ModularWidget, TestHarness};
+
+    const TOAST_DURATION: Duration = Duration::from_secs(2);
+
+    type Root = Toasts<ModularWidget<()>>;
+
+    fn toasting_widget() -> Root {
+        let child = ModularWidget::new(())
+            .event_fn(|_, ctx, event, _| {
+                if let Event::MouseDown(_) = event {
+                    ctx.show_toast(ToastSpec::new("Saved").duration(TOAST_DURATION));
+                }
+            })
+            .layout_fn(|_, _, bc, _| bc.max());
+        Toasts::new(child)
+    }
+
+    fn toasts(harness: &TestHarness) -> Vec<(ToastSpec, bool)> {
+        let root = harness.root_widget();
+        let root = root.downcast::<Root>().unwrap();
+        root.toasts
+            .iter()
+            .map(|toast| (toast.spec.clone(), toast.leaving))
+            .collect()
+    }
+
+    fn finish_animation(harness: &mut TestHarness) {
+        harness.process_event(Event::AnimFrame(ANIMATION_DURATION.as_nanos() as u64));
+    }
+
+    #[test]
+    fn toast_is_dismissed_after_timeout() {
+        let mut harness = TestHarness::create_with_size(toasting_widget(), Size::new(400., 400.));
+        harness.mouse_move((10.0, 10.0));
+        harness.mouse_button_press(MouseButton::Left);
+        finish_animation(&mut harness);
+
+        let saved = ToastSpec::new("Saved").duration(TOAST_DURATION);
+        assert_eq!(toasts(&harness), vec![(saved.clone(), false)]);
+
+        harness.move_timers_forward(TOAST_DURATION);
+        assert_eq!(toasts(&harness), vec![(saved, true)]);
+
+        finish_animation(&mut harness);
+        assert_eq!(toasts(&harness), vec![]);
+    }
+
+    #[test]
+    fn hovered_toast_stays_open() {
+        let mut harness = TestHarness::create_with_size(toasting_widget(), Size::new(400., 400.));
+        harness.mouse_move((10.0, 10.0));
+        harness.mouse_button_press(MouseButton::Left);
+        finish_animation(&mut harness);
+
+        let toast_rect = {
+            let root = harness.root_widget();
+            root.downcast::<Root>().unwrap().toasts[0].rect
+        };
+        assert!(toast_rect.x1 <= 400.0 && toast_rect.y1 <= 400.0);
+
+        harness.mouse_move(toast_rect.center());
+        harness.move_timers_forward(TOAST_DURATION);
+        assert_eq!(toasts(&harness).len(), 1);
+        assert!(!toasts(&harness)[0].1);
+
+        harness.mouse_move((10.0, 10.0));
+        harness.move_timers_forward(TOAST_DURATION);
+        assert!(toasts(&harness)[0].1);
+    }
+}
@@ -0,0 +1,343 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! A widget that plays video files, backed by GStreamer.
+
+use std::time::Duration;
+
+use gstreamer as gst;
+use gstreamer::prelude::*;
+use gstreamer_app as gst_app;
+use gstreamer_video as gst_video;
+use smallvec::SmallVec;
+use tracing::{trace, trace_span, warn, Span};
+
+use crate::ext_event::ExtEventSink;
+use crate::piet::{Image as _, ImageBuf, ImageFormat, InterpolationMode, PietImage};
+use crate::widget::{FillStrat, MemoryUsage, WidgetId, WidgetRef};
+use crate::{
+    Action, BoxConstraints, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx,
+    RenderContext, Selector, Size, StatusChange, Widget,
+};
+
+/// A decoded frame, sent from the GStreamer streaming thread to the widget.
+struct VideoFrame {
+    image: ImageBuf,
+    position: Duration,
+}
+
+const VIDEO_FRAME: Selector<VideoFrame> = Selector::new("masonry-builtin.video-frame");
+
+/// A widget that decodes and displays a video.
+///
+/// Decoding happens on GStreamer's streaming threads; each decoded frame is
+/// sent back to the widget through an [`ExtEventSink`], and painted as a
+/// regular Piet image.
+///
+/// Emits [`Action::VideoPositionChanged`] every time a new frame is displayed.
+///
+/// This widget is only available with the `video` feature.
+pub struct Video {
+    uri: String,
+    pipeline: Option<gst::Element>,
+    autoplay: bool,
+    is_playing: bool,
+    position: Duration,
+    frame: Option<ImageBuf>,
+    paint_data: Option<PietImage>,
+    fill: FillStrat,
+    interpolation: InterpolationMode,
+}
+
+crate::declare_widget!(VideoMut, Video);
+
+impl Video {
+    /// Create a video widget that will play the media at the given URI.
+    ///
+    /// The URI must be in a form GStreamer understands, eg `file:///path/to/movie.webm`.
+    /// The pipeline is started when the widget is added to the widget tree.
+    pub fn new(uri: impl Into<String>) -> Self {
+        Video {
+            uri: uri.into(),
+            pipeline: None,
+            autoplay: false,
+            is_playing: false,
+            position: Duration::ZERO,
+            frame: None,
+            paint_data: None,
+            fill: FillStrat::default(),
+            interpolation: InterpolationMode::Bilinear,
+        }
+    }
+
+    /// Builder-style method for starting playback as soon as the widget is added.
+    ///
+    /// The default is `false`: the first frame is shown, and the video stays paused.
+    pub fn autoplay(mut self, autoplay: bool) -> Self {
+        self.autoplay = autoplay;
+        self
+    }
+
+    /// Builder-style method for specifying the fill strategy.
+    pub fn fill_mode(mut self, mode: FillStrat) -> Self {
+        self.fill = mode;
+        self
+    }
+
+    /// Builder-style method for specifying the interpolation strategy.
+    pub fn interpolation_mode(mut self, interpolation: InterpolationMode) -> Self {
+        self.interpolation = interpolation;
+        self
+    }
+
+    /// Whether the video is currently playing.
+    pub fn is_playing(&self) -> bool {
+        self.is_playing
+    }
+
+    /// The timestamp of the last frame displayed.
+    pub fn position(&self) -> Duration {
+        self.position
+    }
+
+    /// The total length of the video, if known.
+    pub fn duration(&self) -> Option<Duration> {
+        let pipeline = self.pipeline.as_ref()?;
+        let duration = pipeline.query_duration::<gst::ClockTime>()?;
+        Some(Duration::from_nanos(duration.nseconds()))
+    }
+
+    fn set_pipeline_state(&self, state: gst::State) {
+        if let Some(pipeline) = &self.pipeline {
+            if let Err(err) = pipeline.set_state(state) {
+                warn!("Video: cannot set pipeline state to {:?}: {}", state, err);
+            }
+        }
+    }
+}
+
+impl<'a, 'b> VideoMut<'a, 'b> {
+    /// Start or resume playback.
+    pub fn play(&mut self) {
+        self.widget.is_playing = true;
+        self.widget.set_pipeline_state(gst::State::Playing);
+    }
+
+    /// Pause playback, keeping the current frame on screen.
+    pub fn pause(&mut self) {
+        self.widget.is_playing = false;
+        self.widget.set_pipeline_state(gst::State::Paused);
+    }
+
+    /// Jump to the given position.
+    ///
+    /// The frame at the new position is displayed once it has been decoded,
+    /// even if the video is paused.
+    pub fn seek(&mut self, position: Duration) {
+        let Some(pipeline) = &self.widget.pipeline else {
+            return;
+        };
+        let target = gst::ClockTime::from_nseconds(position.as_nanos() as u64);
+        if let Err(err) =
+            pipeline.seek_simple(gst::SeekFlags::FLUSH | gst::SeekFlags::KEY_UNIT, target)
+        {
+            warn!("Video: cannot seek to {:?}: {}", position, err);
+        }
+    }
+
+    /// Modify the widget's fill strategy.
+    pub fn set_fill_mode(&mut self, fill: FillStrat) {
+        self.widget.fill = fill;
+        self.ctx.request_paint();
+    }
+
+    /// Modify the widget's interpolation mode.
+    pub fn set_interpolation_mode(&mut self, interpolation: InterpolationMode) {
+        self.widget.interpolation = interpolation;
+        self.ctx.request_paint();
+    }
+}
+
+fn make_pipeline(
+    uri: &str,
+    ext_event_sink: ExtEventSink,
+    widget_id: WidgetId,
+) -> Result<gst::Element, String> {
+    gst::init().map_err(|err| err.to_string())?;
+
+    let pipeline = gst::ElementFactory::make("playbin")
+        .property("uri", uri)
+        .build()
+        .map_err(|err| err.to_string())?;
+
+    let caps = gst_video::VideoCapsBuilder::new()
+        .format(gst_video::VideoFormat::Rgba)
+        .build();
+    let appsink = gst_app::AppSink::builder().caps(&caps).build();
+
+    appsink.set_callbacks(
+        gst_app::AppSinkCallbacks::builder()
+            .new_sample(move |sink| {
+                let sample = sink.pull_sample().map_err(|_| gst::FlowError::Eos)?;
+                let frame = frame_from_sample(&sample).ok_or(gst::FlowError::Error)?;
+                ext_event_sink
+                    .submit_command(VIDEO_FRAME, frame, widget_id)
+                    .map_err(|_| gst::FlowError::Flushing)?;
+                Ok(gst::FlowSuccess::Ok)
+            })
+            .build(),
+    );
+    pipeline.set_property("video-sink", &appsink);
+
+    Ok(pipeline)
+}
+
+/// Copy a decoded RGBA sample into an `ImageBuf`, dropping any row padding.
+fn frame_from_sample(sample: &gst::Sample) -> Option<VideoFrame> {
+    let buffer = sample.buffer()?;
+    let info = gst_video::VideoInfo::from_caps(sample.caps()?).ok()?;
+    let map = buffer.map_readable().ok()?;
+
+    let width = info.width() as usize;
+    let height = info.height() as usize;
+    let stride = info.stride()[0] as usize;
+    let row_len = width * 4;
+
+    let pixels = if stride == row_len {
+        map.as_slice().to_vec()
+    } else {
+        map.as_slice()
+            .chunks(stride)
+            .take(height)
+            .flat_map(|row| &row[..row_len])
+            .copied()
+            .collect()
+    };
+
+    let position = buffer
+        .pts()
+        .map(|pts| Duration::from_nanos(pts.nseconds()))
+        .unwrap_or_default();
+
+    Some(VideoFrame {
+        image: ImageBuf::from_raw(pixels, ImageFormat::RgbaSeparate, width, height),
+        position,
+    })
+}
+
+impl Widget for Video {
+    fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, _env: &Env) {
+        if let Event::Command(cmd) = event {
+            if let Some(frame) = cmd.try_get(VIDEO_FRAME) {
+                let size_changed = self
+                    .frame
+                    .as_ref()
+                    .map(|old| old.size() != frame.image.size())
+                    .unwrap_or(true);
+                self.frame = Some(frame.image.clone());
+                self.paint_data = None;
+                self.position = frame.position;
+
+                if size_changed {
+                    ctx.request_layout();
+                }
+                ctx.request_paint();
+                ctx.submit_action(Action::VideoPositionChanged(frame.position));
+                ctx.set_handled();
+            }
+        }
+    }
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange, _env: &Env) {}
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, _env: &Env) {
+        // Don't decode frames nobody can see; playback resumes where it stopped.
+        if let LifeCycle::VisibilityChanged(is_visible) = event {
+            if self.is_playing {
+                self.set_pipeline_state(if *is_visible {
+                    gst::State::Playing
+                } else {
+                    gst::State::Paused
+                });
+            }
+        }
+        if let LifeCycle::WidgetAdded = event {
+            match make_pipeline(&self.uri, ctx.get_external_handle(), ctx.widget_id()) {
+                Ok(pipeline) => self.pipeline = Some(pipeline),
+                Err(err) => {
+                    warn!("Video: cannot open '{}': {}", self.uri, err);
+                    return;
+                }
+            }
+            self.is_playing = self.autoplay;
+            if self.autoplay {
+                self.set_pipeline_state(gst::State::Playing);
+            } else {
+                self.set_pipeline_state(gst::State::Paused);
+            }
+        }
+    }
+
+    fn layout(&mut self, _ctx: &mut LayoutCtx, bc: &BoxConstraints, _env: &Env) -> Size {
+        let frame_size = self
+            .frame
+            .as_ref()
+            .map(|frame| frame.size())
+            .unwrap_or_default();
+        let size = bc.constrain(frame_size);
+        trace!("Computed size: {}", size);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, _env: &Env) {
+        let Some(frame) = &self.frame else {
+            return;
+        };
+        let offset_matrix = self.fill.affine_to_fill(ctx.size(), frame.size());
+
+        let clip_rect = ctx.size().to_rect();
+        ctx.clip(clip_rect);
+
+        let piet_image = self
+            .paint_data
+            .get_or_insert_with(|| frame.to_image(ctx.render_ctx));
+        if piet_image.size().is_empty() {
+            return;
+        }
+        let interpolation = self.interpolation;
+        ctx.with_save(|ctx| {
+            ctx.transform(offset_matrix);
+            ctx.draw_image(piet_image, frame.size().to_rect(), interpolation);
+        });
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        SmallVec::new()
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("Video")
+    }
+
+    fn retained_memory(&self, memory: &mut MemoryUsage) {
+        let Some(frame) = &self.frame else {
+            return;
+        };
+        memory.add_shared(frame.raw_pixels());
+        // The platform keeps its own copy of the pixels once the frame is painted.
+        if self.paint_data.is_some() {
+            memory.add(frame.raw_pixels().len());
+        }
+    }
+
+    fn get_debug_text(&self) -> Option<String> {
+        Some(self.uri.clone())
+    }
+}
+
+impl Drop for Video {
+    fn drop(&mut self) {
+        self.set_pipeline_state(gst::State::Null);
+    }
+}
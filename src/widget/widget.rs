@@ -3,9 +3,12 @@
 // details.
 
 use std::any::Any;
+use std::cell::Cell;
+use std::collections::HashMap;
 use std::num::NonZeroU64;
 use std::ops::{Deref, DerefMut};
 
+use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
 use tracing::{trace_span, Span};
 
@@ -13,7 +16,7 @@ use crate::event::StatusChange;
 use crate::widget::WidgetRef;
 use crate::{
     AsAny, BoxConstraints, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx,
-    Point, Size, WidgetCtx,
+    Point, Size, Value, WidgetCtx,
 };
 
 /// A unique identifier for a single [`Widget`].
@@ -37,9 +40,71 @@ use crate::{
 ///
 /// If you set a `WidgetId` directly, you are resposible for ensuring that it
 /// is unique. Two widgets must not be created with the same id.
-#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+///
+/// ## Deterministic ids
+///
+/// By default, ids are allocated from a global counter, so they depend on every
+/// widget created before, eg by other tests. To get the same ids on every run, eg for
+/// recorded event traces or snapshot files, build the widget tree while a
+/// [`DeterministicIds`] guard lives, or derive ids from stable names with
+/// [`WidgetId::from_path`].
+///
+/// Ids are serialized as integers.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
 pub struct WidgetId(NonZeroU64);
 
+/// The first id allocated in deterministic mode, far from the ids of the global counter.
+///
+/// Each seed gets a range of 2^32 ids above this, so deterministic ids stay below the
+/// ids of [`WidgetId::from_path`], which are 2^63 and above.
+const DETERMINISTIC_ID_BASE: u64 = 1 << 62;
+
+thread_local! {
+    /// The next id allocated on this thread, while a [`DeterministicIds`] guard lives.
+    static NEXT_DETERMINISTIC_ID: Cell<Option<u64>> = Cell::new(None);
+}
+
+/// A guard which makes [`WidgetId::next`] allocate ids deterministically on the
+/// current thread, until it's dropped.
+///
+/// Ids are allocated in sequence from a value derived from the seed, so building the
+/// same widget tree with the same seed gives the same ids. Ids created before the
+/// guard, eg with [`widget_ids`](crate::testing::widget_ids), aren't affected. Each
+/// seed has its own range of ids, so trees built with different seeds, eg one per
+/// window, can't share ids.
+///
+/// ```
+/// # use masonry::widget::{DeterministicIds, Label};
+/// # use masonry::WidgetPod;
+/// let first_run = {
+///     let _ids = DeterministicIds::new(0);
+///     WidgetPod::new(Label::new("Hello")).id()
+/// };
+/// let second_run = {
+///     let _ids = DeterministicIds::new(0);
+///     WidgetPod::new(Label::new("Hello")).id()
+/// };
+/// assert_eq!(first_run, second_run);
+/// ```
+#[must_use = "ids are only deterministic while the guard lives"]
+pub struct DeterministicIds {
+    previous: Option<u64>,
+}
+
+/// An estimate of the heap memory retained by a set of widgets.
+///
+/// Widgets report their buffers in [`Widget::retained_memory`]. Buffers which may
+/// be shared with other widgets or caches, eg the pixels of an
+/// [`ImageBuf`](crate::piet::ImageBuf), are counted once however many widgets
+/// report them.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryUsage {
+    owned: usize,
+    /// The length of each shared buffer, by address.
+    shared: HashMap<usize, usize>,
+}
+
 // TODO - Add tutorial: implementing a widget - See issue #5
 /// The trait implemented by all widgets.
 ///
@@ -62,6 +127,13 @@ pub struct WidgetId(NonZeroU64);
 /// [`WidgetPods`](crate::WidgetPod). Widget methods are called by WidgetPods, and the
 /// widget is mutated either during a method call (eg `on_event` or `lifecycle`) or
 /// through a [`WidgetMut`](crate::widget::WidgetMut). See tutorials for detail.
+///
+/// Widget methods don't need any setup call: the `WidgetPod` builds their context, and
+/// checks that the widget received [`LifeCycle::WidgetAdded`] before any other method.
+/// In debug builds, it also checks that container widgets forwarded each method to
+/// every one of their children's `WidgetPod`s, eg by calling
+/// [`WidgetPod::on_event`](crate::WidgetPod::on_event) rather than the child's
+/// `Widget::on_event` directly.
 pub trait Widget: AsAny {
     /// Handle an event - usually user interaction.
     ///
@@ -143,20 +215,59 @@ pub trait Widget: AsAny {
         None
     }
 
+    /// Return the part of this widget's state that should persist across restarts.
+    ///
+    /// This is meant for UI state that isn't part of the app's data, eg a scroll
+    /// offset or a selected tab. It is collected by
+    /// [`WindowRoot::save_ui_state`](crate::WindowRoot::save_ui_state), and handed
+    /// back to [`restore_state`](Self::restore_state), keyed by [`WidgetId`]; widgets
+    /// should have stable ids for this to work across restarts.
+    fn save_state(&self) -> Option<Value> {
+        None
+    }
+
+    /// Restore the state previously returned by [`save_state`](Self::save_state).
+    ///
+    /// Widgets should request a layout or paint pass if the state changes their appearance.
+    fn restore_state(&mut self, ctx: &mut LifeCycleCtx, state: &Value, env: &Env) {
+        #![allow(unused)]
+    }
+
+    /// Add an estimate of the heap memory this widget retains, eg text layouts,
+    /// decoded images or cached layers, to `memory`.
+    ///
+    /// This doesn't include the widget's children. It's only used for debugging,
+    /// to find the widgets responsible for high memory usage; see
+    /// [`WidgetRef::subtree_memory`]. The estimate doesn't need to be precise, but
+    /// widgets holding large buffers should report them.
+    fn retained_memory(&self, memory: &mut MemoryUsage) {
+        #![allow(unused)]
+    }
+
     // --- Auto-generated implementations ---
 
     /// Return which child, if any, has the given `pos` in its layout rect.
     ///
     /// The child return is a direct child, not eg a grand-child. The position is in
     /// relative cordinates. (Eg `(0,0)` is the top-left corner of `self`).
+    /// If several children overlap, the one with the highest z-index is returned.
     ///
     /// Has a default implementation, that can be overriden to search children more
     /// efficiently.
     fn get_child_at_pos(&self, pos: Point) -> Option<WidgetRef<'_, dyn Widget>> {
         // layout_rect() is in parent coordinate space
-        self.children()
-            .into_iter()
-            .find(|child| child.state().layout_rect().contains(pos))
+        let mut found: Option<WidgetRef<'_, dyn Widget>> = None;
+        for child in self.children() {
+            if !child.state().layout_rect().contains(pos) {
+                continue;
+            }
+            if found.map_or(true, |found| {
+                child.state().stacking_order() > found.state().stacking_order()
+            }) {
+                found = Some(child);
+            }
+        }
+        found
     }
 
     /// Get the (verbose) type name of the widget for debugging purposes.
@@ -179,28 +290,92 @@ pub trait Widget: AsAny {
             .unwrap_or(name)
     }
 
-    // FIXME
-    /// Cast as Any.
+    /// Cast as Any, to downcast to a concrete widget type.
     ///
-    /// Mainly intended to be overriden in `Box<dyn Widget>`.
-    #[doc(hidden)]
+    /// You should not override this method; it's only overriden in `Box<dyn Widget>`,
+    /// so that a boxed widget downcasts to the type it contains rather than to the box.
+    ///
+    /// See also `<dyn Widget>::downcast_ref`.
     fn as_any(&self) -> &dyn Any {
         self.as_dyn_any()
     }
 
-    // FIXME
-    /// Cast as Any.
+    /// Cast as Any, to downcast to a concrete widget type.
     ///
-    /// Mainly intended to be overriden in `Box<dyn Widget>`.
-    #[doc(hidden)]
+    /// You should not override this method; it's only overriden in `Box<dyn Widget>`,
+    /// so that a boxed widget downcasts to the type it contains rather than to the box.
+    ///
+    /// See also `<dyn Widget>::downcast_mut`.
     fn as_mut_any(&mut self) -> &mut dyn Any {
         self.as_mut_dyn_any()
     }
 }
 
+impl dyn Widget {
+    /// Whether the widget is of type `W`.
+    ///
+    /// Boxed widgets are looked through: this returns true for a `Box<dyn Widget>`
+    /// containing a `W`.
+    pub fn is<W: Widget>(&self) -> bool {
+        self.as_any().is::<W>()
+    }
+
+    /// Attempt to downcast to a reference to a concrete widget type.
+    pub fn downcast_ref<W: Widget>(&self) -> Option<&W> {
+        self.as_any().downcast_ref()
+    }
+
+    /// Attempt to downcast to a mutable reference to a concrete widget type.
+    ///
+    /// Mutating a widget directly bypasses invalidation; outside of the widget's own
+    /// methods, you usually want [`WidgetMut::downcast`](crate::widget::WidgetMut::downcast)
+    /// instead.
+    pub fn downcast_mut<W: Widget>(&mut self) -> Option<&mut W> {
+        self.as_mut_any().downcast_mut()
+    }
+}
+
 /// Trait that widgets must implement to be in [`WidgetMut`](crate::widget::WidgetMut).
 ///
-/// This trait should usually be implemented with [`declare_widget`](crate::declare_widget).
+/// This trait should usually be implemented with `#[derive(StoreInWidgetMut)]`, or with
+/// [`declare_widget`](crate::declare_widget).
+///
+/// ## Deriving
+///
+/// The derive macro declares a `MyWidgetMut` type, the same way `declare_widget` does.
+/// The name can be changed with `#[widget_mut(name = "OtherName")]` on the struct.
+///
+/// Fields can be annotated to generate the most common methods:
+///
+/// - `#[widget_mut(child)]` on a `WidgetPod<T>` field named `foo` generates a
+///   `MyWidgetMut::foo_mut()` method returning a `WidgetMut<T>`. These fields are
+///   also listed, in declaration order, by a private `MyWidget::pod_children()` method
+///   which [`Widget::children`] can return.
+/// - `#[widget_mut(set)]` on a field named `foo` generates a `MyWidgetMut::set_foo()`
+///   method which sets the field and requests a layout. With `#[widget_mut(set(paint))]`,
+///   the method only requests a paint.
+///
+/// Other methods can be added to `MyWidgetMut` by hand.
+///
+/// ```ignore
+/// #[derive(StoreInWidgetMut)]
+/// pub struct Labeled {
+///     #[widget_mut(child)]
+///     label: WidgetPod<Label>,
+///     #[widget_mut(set)]
+///     spacing: f64,
+///     #[widget_mut(set(paint))]
+///     color: Color,
+/// }
+///
+/// impl Widget for Labeled {
+///     // ...
+///
+///     fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+///         self.pod_children()
+///     }
+/// }
+/// ```
 #[allow(missing_docs)]
 pub trait StoreInWidgetMut: Widget {
     type Mut<'a, 'b: 'a>: Deref<Target = Self>;
@@ -227,7 +402,6 @@ pub trait StoreInWidgetMut: Widget {
     ) -> (&'r mut Self, &'r mut WidgetCtx<'a, 'b>);
 }
 
-// TODO - Generate a struct instead. See #27.
 /// Declare a mutable reference type for your widget.
 ///
 /// The general syntax is:
@@ -323,6 +497,15 @@ impl WidgetId {
     /// widget at a time.
     pub fn next() -> WidgetId {
         use druid_shell::Counter;
+        let deterministic_id = NEXT_DETERMINISTIC_ID.with(|next_id| {
+            let id = next_id.get()?;
+            next_id.set(Some(id + 1));
+            Some(id)
+        });
+        if let Some(id) = deterministic_id {
+            return WidgetId(NonZeroU64::new(id).unwrap());
+        }
+
         static WIDGET_ID_COUNTER: Counter = Counter::new();
         WidgetId(WIDGET_ID_COUNTER.next_nonzero())
     }
@@ -342,11 +525,73 @@ impl WidgetId {
         WidgetId(unsafe { std::num::NonZeroU64::new_unchecked(id) })
     }
 
+    /// Derive an id from a stable name, eg the widget's path in the tree like
+    /// `"settings/sidebar/search"`.
+    ///
+    /// The same path always gives the same id, on every run and platform. As with
+    /// [`reserved`](Self::reserved) ids, the caller is responsible for using each path
+    /// for a single widget.
+    pub const fn from_path(path: &str) -> WidgetId {
+        // 64-bit FNV-1a, which is stable, unlike the std hashers.
+        let bytes = path.as_bytes();
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        let mut i = 0;
+        while i < bytes.len() {
+            hash ^= bytes[i] as u64;
+            hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+            i += 1;
+        }
+        // Keep path ids above the counter ids and the deterministic ids.
+        let id = (1 << 63) | (hash >> 1);
+        match NonZeroU64::new(id) {
+            Some(id) => WidgetId(id),
+            None => unreachable!(),
+        }
+    }
+
     pub(crate) fn to_raw(self) -> u64 {
         self.0.into()
     }
 }
 
+impl DeterministicIds {
+    /// Allocate ids deterministically on the current thread, starting from `seed`.
+    pub fn new(seed: u16) -> Self {
+        let first_id = DETERMINISTIC_ID_BASE + ((seed as u64) << 32);
+        let previous = NEXT_DETERMINISTIC_ID.with(|next_id| next_id.replace(Some(first_id)));
+        DeterministicIds { previous }
+    }
+}
+
+impl Drop for DeterministicIds {
+    fn drop(&mut self) {
+        NEXT_DETERMINISTIC_ID.with(|next_id| next_id.set(self.previous));
+    }
+}
+
+impl MemoryUsage {
+    /// Count `bytes` owned by a single widget.
+    pub fn add(&mut self, bytes: usize) {
+        self.owned += bytes;
+    }
+
+    /// Count a buffer which may be shared, unless it was already counted.
+    pub fn add_shared(&mut self, buffer: &[u8]) {
+        self.shared.insert(buffer.as_ptr() as usize, buffer.len());
+    }
+
+    /// Add the memory counted by `other`, counting the buffers both share once.
+    pub fn merge(&mut self, other: &MemoryUsage) {
+        self.owned += other.owned;
+        self.shared.extend(&other.shared);
+    }
+
+    /// The total, in bytes.
+    pub fn bytes(&self) -> usize {
+        self.owned + self.shared.values().sum::<usize>()
+    }
+}
+
 // TODO - remove
 impl Widget for Box<dyn Widget> {
     fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, env: &Env) {
@@ -377,6 +622,10 @@ impl Widget for Box<dyn Widget> {
         self.deref().children()
     }
 
+    fn get_child_at_pos(&self, pos: Point) -> Option<WidgetRef<'_, dyn Widget>> {
+        self.deref().get_child_at_pos(pos)
+    }
+
     fn make_trace_span(&self) -> Span {
         self.deref().make_trace_span()
     }
@@ -385,6 +634,18 @@ impl Widget for Box<dyn Widget> {
         self.deref().get_debug_text()
     }
 
+    fn save_state(&self) -> Option<Value> {
+        self.deref().save_state()
+    }
+
+    fn retained_memory(&self, memory: &mut MemoryUsage) {
+        self.deref().retained_memory(memory);
+    }
+
+    fn restore_state(&mut self, ctx: &mut LifeCycleCtx, state: &Value, env: &Env) {
+        self.deref_mut().restore_state(ctx, state, env);
+    }
+
     fn as_any(&self) -> &dyn Any {
         self.deref().as_dyn_any()
     }
@@ -4,8 +4,9 @@
 
 use std::ops::{Deref, DerefMut};
 
+use crate::style::WidgetStyle;
 use crate::widget::StoreInWidgetMut;
-use crate::{Widget, WidgetCtx, WidgetId, WidgetState};
+use crate::{ArcStr, Widget, WidgetCtx, WidgetId, WidgetState};
 
 /// A mutable reference to a [`Widget`].
 ///
@@ -56,7 +57,18 @@ impl<'a, 'b, W: StoreInWidgetMut> DerefMut for WidgetMut<'a, 'b, W> {
 }
 
 impl<'a, 'b> WidgetMut<'a, 'b, Box<dyn Widget>> {
+    /// Whether the widget is of type `W2`.
+    pub fn is<W2: Widget>(&mut self) -> bool {
+        Box::<dyn Widget>::get_widget(&mut self.inner)
+            .as_any()
+            .is::<W2>()
+    }
+
     /// Attempt to downcast to `WidgetMut` of concrete Widget type.
+    ///
+    /// This is how widgets stored as `Box<dyn Widget>`, eg the children of a
+    /// [`Flex`](crate::widget::Flex) or the root widget of a
+    /// [`TestHarness`](crate::testing::TestHarness), can be mutated.
     pub fn downcast<'s, W2: Widget + StoreInWidgetMut>(
         &'s mut self,
     ) -> Option<WidgetMut<'_, 'b, W2>> {
@@ -83,6 +95,102 @@ impl<W: StoreInWidgetMut> WidgetMut<'_, '_, W> {
     pub fn id(&mut self) -> WidgetId {
         W::get_ctx(&mut self.inner).widget_state.id
     }
+
+    /// Add a style class to the widget.
+    ///
+    /// See [`StyleSheet`](crate::style::StyleSheet) for details.
+    pub fn add_class(&mut self, class: impl Into<ArcStr>) {
+        let class = class.into();
+        let ctx = W::get_ctx(&mut self.inner);
+        if !ctx.widget_state.classes.contains(&class) {
+            ctx.widget_state.classes.push(class);
+            ctx.request_layout();
+        }
+    }
+
+    /// Remove a style class from the widget.
+    pub fn remove_class(&mut self, class: &str) {
+        let ctx = W::get_ctx(&mut self.inner);
+        let class_count = ctx.widget_state.classes.len();
+        ctx.widget_state.classes.retain(|c| &**c != class);
+        if ctx.widget_state.classes.len() != class_count {
+            ctx.request_layout();
+        }
+    }
+
+    /// Whether the widget has the given style class.
+    pub fn has_class(&mut self, class: &str) -> bool {
+        let ctx = W::get_ctx(&mut self.inner);
+        ctx.widget_state.classes.iter().any(|c| &**c == class)
+    }
+
+    /// Get the style properties the widget's pod applies around it.
+    pub fn style(&mut self) -> &WidgetStyle {
+        &W::get_ctx(&mut self.inner).widget_state.style
+    }
+
+    /// Replace the background, border, corner radius and padding of the widget.
+    ///
+    /// See [`WidgetStyle`] for details.
+    pub fn set_style(&mut self, style: WidgetStyle) {
+        self.edit_style(|current| *current = style);
+    }
+
+    /// Modify some of the style properties of the widget.
+    ///
+    /// Requests a layout pass if the padding or border changed, and a paint pass
+    /// otherwise.
+    pub fn edit_style(&mut self, f: impl FnOnce(&mut WidgetStyle)) {
+        let ctx = W::get_ctx(&mut self.inner);
+        let old_style = ctx.widget_state.style.clone();
+        f(&mut ctx.widget_state.style);
+
+        let new_style = &ctx.widget_state.style;
+        let needs_layout =
+            new_style.padding != old_style.padding || new_style.border != old_style.border;
+        let needs_paint = *new_style != old_style;
+        if needs_layout {
+            ctx.request_layout();
+        } else if needs_paint {
+            ctx.request_paint();
+        }
+    }
 }
 
-// TODO - unit tests
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+
+    use crate::testing::{widget_ids, TestHarness};
+    use crate::widget::{Button, Flex, Label};
+    use crate::Widget;
+
+    #[test]
+    fn downcast_dyn_widget() {
+        let mut widget: Box<dyn Widget> = Box::new(Label::new("Hello"));
+        assert!(widget.is::<Label>());
+        assert!(!widget.is::<Box<dyn Widget>>());
+        assert_matches!(widget.downcast_ref::<Label>(), Some(_));
+        assert_matches!(widget.downcast_mut::<Button>(), None);
+    }
+
+    #[test]
+    fn downcast_widget_mut() {
+        let [label_id] = widget_ids();
+        let widget = Flex::row().with_child_id(Label::new("Hello"), label_id);
+        let mut harness = TestHarness::create(widget);
+
+        harness.edit_root_widget(|mut root, _| {
+            assert!(root.is::<Flex>());
+            assert_matches!(root.downcast::<Label>(), None);
+
+            let mut flex = root.downcast::<Flex>().unwrap();
+            let mut child = flex.child_mut(0).unwrap();
+            assert!(child.is::<Label>());
+            child.downcast::<Label>().unwrap().set_text("World");
+        });
+
+        let label = harness.get_widget(label_id);
+        assert_eq!(&*label.downcast::<Label>().unwrap().text(), "World");
+    }
+}
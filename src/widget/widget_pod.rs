@@ -6,14 +6,20 @@ use std::collections::VecDeque;
 
 use tracing::{info_span, trace, warn};
 
-use crate::contexts::GlobalPassCtx;
-use crate::kurbo::{Affine, Insets, Point, Rect, Shape, Size};
+use crate::contexts::{paint_offscreen, DetachedEffects, GlobalPassCtx};
+use crate::debug_logger::{EventRouting, LayoutViolation, LayoutViolationKind};
+use crate::kurbo::{Affine, Insets, Point, Rect, RoundedRectRadii, Shape, Size, Vec2};
+use crate::piet::ImageBuf;
+use crate::print::{self, PageBreak, PageSetup};
+use crate::promise::PromiseResult;
+use crate::style::{BorderStyle, PseudoClassState, WidgetStyle};
 use crate::text::TextLayout;
-use crate::widget::{FocusChange, WidgetRef, WidgetState};
+use crate::widget::elevation::Elevation;
+use crate::widget::{BackgroundBrush, FocusChange, WidgetRef, WidgetState};
 use crate::{
-    ArcStr, BoxConstraints, Color, Env, Event, EventCtx, InternalEvent, InternalLifeCycle,
-    LayoutCtx, LifeCycle, LifeCycleCtx, Notification, PaintCtx, RenderContext, StatusChange,
-    Target, Widget, WidgetId,
+    theme, ArcStr, BoxConstraints, Color, Env, Event, EventCtx, InternalEvent, InternalLifeCycle,
+    KeyOrValue, LayoutCtx, LifeCycle, LifeCycleCtx, Notification, PaintCtx, RenderContext,
+    StatusChange, Target, Widget, WidgetCtx, WidgetId,
 };
 
 // TODO - rewrite links in doc
@@ -30,6 +36,11 @@ pub struct WidgetPod<W> {
     pub(crate) state: WidgetState,
     pub(crate) inner: W,
     pub(crate) env: Option<Env>,
+    pub(crate) background: Option<BackgroundBrush>,
+    pub(crate) foreground: Option<BackgroundBrush>,
+    pub(crate) elevation: Option<Elevation>,
+    /// What the widget requested while it was prewarmed, before being adopted.
+    pub(crate) detached_effects: Option<Box<DetachedEffects>>,
     // stashed layout so we don't recompute this when debugging
     pub(crate) debug_widget_text: TextLayout<ArcStr>,
 }
@@ -55,10 +66,209 @@ impl<W: Widget> WidgetPod<W> {
             state,
             inner,
             env: None,
+            background: None,
+            foreground: None,
+            elevation: None,
+            detached_effects: None,
             debug_widget_text: TextLayout::new(),
         }
     }
 
+    /// Builder-style method for setting the background color painted behind the widget.
+    ///
+    /// The background also covers the padding and border, and follows the corner radius.
+    /// It's a style property, which a [`StyleSheet`](crate::style::StyleSheet) can also
+    /// set; to paint a gradient or a custom painter instead, see
+    /// [`with_background_brush`](Self::with_background_brush).
+    pub fn with_background(mut self, color: impl Into<KeyOrValue<Color>>) -> Self {
+        self.state.style.background = Some(color.into());
+        self
+    }
+
+    /// Builder-style method for painting a border around the widget and its padding.
+    ///
+    /// The border takes up space in the layout, like padding.
+    pub fn with_border(
+        mut self,
+        color: impl Into<KeyOrValue<Color>>,
+        width: impl Into<KeyOrValue<f64>>,
+    ) -> Self {
+        self.state.style.border = Some(BorderStyle {
+            color: color.into(),
+            width: width.into(),
+        });
+        self
+    }
+
+    /// Builder-style method for rounding the corners of the background and border.
+    pub fn with_corner_radius(mut self, radius: impl Into<KeyOrValue<RoundedRectRadii>>) -> Self {
+        self.state.style.corner_radius = radius.into();
+        self
+    }
+
+    /// Builder-style method for adding space between the widget and its border.
+    pub fn with_padding(mut self, padding: impl Into<KeyOrValue<Insets>>) -> Self {
+        self.state.style.padding = padding.into();
+        self
+    }
+
+    /// Builder-style method for adding a style class to the widget.
+    ///
+    /// Classes are used to select the [`StyleSheet`](crate::style::StyleSheet)
+    /// rules that apply to the widget.
+    pub fn with_class(mut self, class: impl Into<ArcStr>) -> Self {
+        let class = class.into();
+        if !self.state.classes.contains(&class) {
+            self.state.classes.push(class);
+        }
+        self
+    }
+
+    /// Builder-style method for replacing all style properties at once.
+    pub fn with_style(mut self, style: WidgetStyle) -> Self {
+        self.state.style = style;
+        self
+    }
+
+    /// Builder-style method for painting a brush behind the widget.
+    ///
+    /// See [`set_background_brush`](Self::set_background_brush).
+    pub fn with_background_brush(mut self, brush: impl Into<BackgroundBrush>) -> Self {
+        self.background = Some(brush.into());
+        self
+    }
+
+    /// Builder-style method for painting a brush over the widget.
+    ///
+    /// See [`set_foreground_brush`](Self::set_foreground_brush).
+    pub fn with_foreground_brush(mut self, brush: impl Into<BackgroundBrush>) -> Self {
+        self.foreground = Some(brush.into());
+        self
+    }
+
+    /// Builder-style method for raising the widget above its siblings, with a drop
+    /// shadow.
+    ///
+    /// The elevation is a level, usually between 1 and 8: it's added to the widget's
+    /// z-index (see [`EventCtx::set_child_z_index`](crate::EventCtx::set_child_z_index)),
+    /// and the shadow painted behind the widget grows with it, in the color of
+    /// [`theme::SHADOW_COLOR`]. The widget rises one level when hovered and three when
+    /// pressed, see [`with_elevation_levels`](Self::with_elevation_levels).
+    pub fn with_elevation(self, level: f64) -> Self {
+        self.with_elevation_levels(level, level + 1.0, level + 3.0)
+    }
+
+    /// Builder-style method for setting the elevation of the widget when it's resting,
+    /// hovered and pressed.
+    ///
+    /// The shadow is animated between levels like an
+    /// [`InteractionState`](crate::widget::InteractionState); only the
+    /// resting level changes the z-order. See [`with_elevation`](Self::with_elevation).
+    pub fn with_elevation_levels(mut self, resting: f64, hovered: f64, pressed: f64) -> Self {
+        self.set_elevation_levels(resting, hovered, pressed);
+        self
+    }
+
+    /// Set the elevation of the widget when it's resting, hovered and pressed.
+    ///
+    /// The change takes effect on the next layout pass; a parent calling this after the
+    /// pod is added should request a layout.
+    pub fn set_elevation_levels(&mut self, resting: f64, hovered: f64, pressed: f64) {
+        let mut elevation = Elevation::new(resting, hovered, pressed);
+        if let Some(previous) = &self.elevation {
+            elevation.interaction = previous.interaction.clone();
+        }
+        self.state.elevation = elevation.resting();
+        self.elevation = Some(elevation);
+    }
+
+    /// Remove the elevation and shadow of the widget.
+    pub fn clear_elevation(&mut self) {
+        self.state.elevation = 0.0;
+        self.elevation = None;
+    }
+
+    /// Paint a brush behind the widget, before its own [`paint`](Widget::paint) method.
+    ///
+    /// The brush can be a color, a gradient, or a painter function (see
+    /// [`BackgroundBrush`]), and fills the widget's layout rect. It's painted on top
+    /// of the style background set by [`with_background`](Self::with_background), which
+    /// also covers the padding and border.
+    ///
+    /// `parent_ctx` is the context of the pod's container, which repaints the pod.
+    pub fn set_background_brush(
+        &mut self,
+        parent_ctx: &mut WidgetCtx,
+        brush: impl Into<BackgroundBrush>,
+    ) {
+        self.background = Some(brush.into());
+        parent_ctx.request_paint_rect(self.state.paint_rect());
+    }
+
+    /// Remove the brush set by [`set_background_brush`](Self::set_background_brush).
+    pub fn clear_background_brush(&mut self, parent_ctx: &mut WidgetCtx) {
+        self.background = None;
+        parent_ctx.request_paint_rect(self.state.paint_rect());
+    }
+
+    /// Paint a brush over the widget, after its own [`paint`](Widget::paint) method.
+    ///
+    /// This is useful for overlays, eg dimming a disabled area or showing a selection
+    /// highlight, without wrapping the widget. The brush fills the widget's layout rect.
+    ///
+    /// `parent_ctx` is the context of the pod's container, which repaints the pod.
+    pub fn set_foreground_brush(
+        &mut self,
+        parent_ctx: &mut WidgetCtx,
+        brush: impl Into<BackgroundBrush>,
+    ) {
+        self.foreground = Some(brush.into());
+        parent_ctx.request_paint_rect(self.state.paint_rect());
+    }
+
+    /// Remove the brush set by [`set_foreground_brush`](Self::set_foreground_brush).
+    pub fn clear_foreground_brush(&mut self, parent_ctx: &mut WidgetCtx) {
+        self.foreground = None;
+        parent_ctx.request_paint_rect(self.state.paint_rect());
+    }
+
+    /// Detach a live pod from its parent, so it can be moved to another container.
+    ///
+    /// `parent_ctx` is the context of the container the pod is removed from; it must
+    /// be called when the container has already dropped the pod from its children.
+    /// The pod keeps its id and its state: text, scroll offsets, focus, timers and
+    /// command registrations are all preserved, since they're keyed by [`WidgetId`].
+    ///
+    /// The returned pod must be passed to [`adopt`](Self::adopt) by its new parent
+    /// during the same mutation pass; a pod that's dropped instead is removed from the
+    /// tree as usual.
+    pub fn take(mut self, parent_ctx: &mut WidgetCtx) -> Self {
+        trace!("take {}", self.state.id);
+        parent_ctx.children_changed();
+        // The pod will be placed somewhere else, so its layout and window origin
+        // are stale. Its hot state is recomputed when the new parent places it.
+        self.state.needs_layout = true;
+        self.state.needs_window_origin = true;
+        self
+    }
+
+    /// Attach a pod returned by [`take`](Self::take), or built ahead of time with
+    /// [`EventCtx::prewarm_child`](crate::EventCtx::prewarm_child), to a new parent.
+    ///
+    /// `parent_ctx` is the context of the container the pod is added to. The pod
+    /// doesn't receive [`LifeCycle::WidgetAdded`] again; the parent's focus chain and
+    /// bloom filter are rebuilt to include it, and if the pod contained the focused
+    /// widget, it stays focused. What a prewarmed pod requested, eg timers or
+    /// commands, is passed on to the window.
+    pub fn adopt(mut self, parent_ctx: &mut WidgetCtx) -> Self {
+        trace!("adopt {}", self.state.id);
+        if let Some(effects) = self.detached_effects.take() {
+            effects.apply(parent_ctx.global_state, parent_ctx.widget_state);
+        }
+        parent_ctx.children_changed();
+        self
+    }
+
     /// Read-only access to state. We don't mark the field as `pub` because
     /// we want to control mutation.
     pub(crate) fn state(&self) -> &WidgetState {
@@ -111,11 +321,36 @@ impl<W: Widget> WidgetPod<W> {
         self.state.is_hot
     }
 
+    /// Return `true` if the widget or any descendant has requested a layout pass.
+    ///
+    /// Containers can use this to decide whether to lay out a child, without
+    /// triggering a layout themselves.
+    pub fn needs_layout(&self) -> bool {
+        self.state.needs_layout
+    }
+
+    /// Return `true` if the widget or any descendant has requested a paint pass.
+    pub fn needs_paint(&self) -> bool {
+        !self.state.invalid.is_empty()
+    }
+
+    /// Return `true` if the widget or any descendant has requested an animation frame.
+    pub fn needs_anim_frame(&self) -> bool {
+        self.state.request_anim
+    }
+
     /// Get the identity of the widget.
     pub fn id(&self) -> WidgetId {
         self.state.id
     }
 
+    /// The z-index of the widget among its siblings.
+    ///
+    /// See [`EventCtx::set_child_z_index`].
+    pub fn z_index(&self) -> i32 {
+        self.state.z_index
+    }
+
     /// Return the layout rectangle.
     ///
     /// This will be a [`Rect`] with a [`Size`] determined by the child's [`layout`]
@@ -123,6 +358,9 @@ impl<W: Widget> WidgetPod<W> {
     ///
     /// Two sibling widgets' layout rects will almost never intersect.
     ///
+    /// If the pod has a [style](crate::style), the rect includes its padding and
+    /// border.
+    ///
     /// This rect wil also be used to detect whether any given pointer event (eg clicks)
     /// intersects with the rectangle.
     ///
@@ -131,7 +369,7 @@ impl<W: Widget> WidgetPod<W> {
     /// [`layout`]: trait.Widget.html#tymethod.layout
     /// [`place_child`]: LayoutCtx::place_child
     pub fn layout_rect(&self) -> Rect {
-        self.state.layout_rect()
+        self.state.border_rect()
     }
 
     /// Get the widget's paint rectangle.
@@ -183,7 +421,7 @@ impl<W: Widget> WidgetPod<W> {
 
     /// The distance from the bottom of this widget to the baseline.
     pub fn baseline_offset(&self) -> f64 {
-        self.state.baseline_offset
+        self.state.baseline_offset + self.state.style_insets.y1
     }
 
     // FIXME - Remove
@@ -224,9 +462,58 @@ impl<W: Widget> WidgetPod<W> {
     // - A concept of "cursor moved to inner widget" (though I think's that's not super useful outside the browser).
     // - Multiple pointers handling.
 
+    /// Release the busy guards waiting for the given promise.
+    fn release_busy_guards(&mut self, promise_result: &PromiseResult) {
+        let token_id = promise_result.token_id();
+        let had_veil = self.state.has_busy_veil();
+        self.state
+            .busy_guards
+            .retain(|guard| guard.promise != Some(token_id));
+        if had_veil && !self.state.has_busy_veil() {
+            self.state
+                .invalid
+                .set_rect(self.state.paint_rect() - self.state.layout_rect().origin().to_vec2());
+        }
+    }
+
+    /// Record which children are under `pos`, but below an overlapping sibling
+    /// with a higher z-index.
+    ///
+    /// The record is kept until the next pointer event, so that children moved by
+    /// [`LayoutCtx::place_child`] don't become hot under an overlapping sibling.
+    ///
+    /// With exclusive hot state, every child under `pos` but the topmost one is
+    /// occluded. `pos` is in our content coordinates.
+    fn find_occluded_children(&mut self, pos: Point) {
+        let under_pointer: Vec<_> = self
+            .inner
+            .children()
+            .into_iter()
+            .map(|child| child.state())
+            .filter(|state| !state.is_stashed && state.layout_rect().contains(pos))
+            .map(|state| (state.id, state.stacking_order()))
+            .collect();
+        self.state.occluded_children.clear();
+        // max_by_key returns the last of the children with the highest z-index,
+        // which is the one painted on top.
+        let Some(&(top_id, top_z_index)) = under_pointer.iter().max_by_key(|(_, z_index)| *z_index)
+        else {
+            return;
+        };
+        let exclusive_hot = self.state.exclusive_hot;
+        self.state.occluded_children.extend(
+            under_pointer
+                .iter()
+                .filter(|(id, z_index)| *z_index < top_z_index || (exclusive_hot && *id != top_id))
+                .map(|(id, _)| *id),
+        );
+    }
+
     /// Determines if the provided `mouse_pos` is inside `rect`
     /// and if so updates the hot state and sends `LifeCycle::HotChanged`.
     ///
+    /// An `is_occluded` widget, ie one below an overlapping sibling, is never hot.
+    ///
     /// Return `true` if the hot state changed.
     ///
     /// The provided `child_state` should be merged up if this returns `true`.
@@ -236,11 +523,12 @@ impl<W: Widget> WidgetPod<W> {
         global_state: &mut GlobalPassCtx,
         rect: Rect,
         mouse_pos: Option<Point>,
+        is_occluded: bool,
         env: &Env,
     ) -> bool {
         let had_hot = inner_state.is_hot;
         inner_state.is_hot = match mouse_pos {
-            Some(pos) => rect.winding(pos) != 0,
+            Some(pos) => rect.winding(pos) != 0 && !is_occluded,
             None => false,
         };
         // FIXME - don't send event, update flags instead
@@ -271,6 +559,27 @@ impl<W: Widget> WidgetPod<W> {
         false
     }
 
+    /// Request a layout pass if the widget changed state (eg became hot) and
+    /// the style sheet has rules depending on that state.
+    fn check_style_state(&mut self, global_state: &GlobalPassCtx) {
+        if self.state.is_new {
+            return;
+        }
+        let pseudo_classes = PseudoClassState::new(&self.state);
+        if pseudo_classes != self.state.style_pseudo_classes
+            && global_state
+                .style_sheet
+                .depends_on_state(self.inner.short_type_name(), &self.state.classes)
+        {
+            trace!(
+                "Widget '{}' #{}: state changed, recomputing style",
+                self.inner.short_type_name(),
+                self.state.id.to_raw(),
+            );
+            self.state.needs_layout = true;
+        }
+    }
+
     // TODO - document
     // TODO - This method should take a 'can_skip: Fn(WidgetRef) -> bool'
     // predicate and only panic if can_skip returns false.
@@ -279,6 +588,18 @@ impl<W: Widget> WidgetPod<W> {
         &mut self,
         method_name: &str,
         visit: impl FnOnce(&mut Self) -> Ret,
+    ) -> Ret {
+        let (return_value, env_keys) = crate::env::record_reads(|| {
+            self.call_widget_method_and_check_children(method_name, visit)
+        });
+        self.state.env_keys.extend(env_keys);
+        return_value
+    }
+
+    fn call_widget_method_and_check_children<Ret>(
+        &mut self,
+        method_name: &str,
+        visit: impl FnOnce(&mut Self) -> Ret,
     ) -> Ret {
         if cfg!(not(debug_assertions)) {
             return visit(self);
@@ -337,7 +658,15 @@ impl<W: Widget + 'static> WidgetPod<W> {
     /// Convert a `WidgetPod` containing a widget of a specific concrete type
     /// into a dynamically boxed widget.
     pub fn boxed(self) -> WidgetPod<Box<dyn Widget>> {
-        WidgetPod::new_with_id(Box::new(self.inner), self.state.id)
+        let mut pod = WidgetPod::new_with_id(Box::new(self.inner), self.state.id)
+            .with_style(self.state.style);
+        pod.state.classes = self.state.classes;
+        pod.background = self.background;
+        pod.foreground = self.foreground;
+        pod.state.elevation = self.state.elevation;
+        pod.elevation = self.elevation;
+        pod.detached_effects = self.detached_effects;
+        pod
     }
 }
 
@@ -366,12 +695,20 @@ impl<W: Widget> WidgetPod<W> {
             .global_state
             .debug_logger
             .push_span(self.inner.short_type_name());
+        let trace_index = parent_ctx
+            .global_state
+            .debug_logger
+            .begin_event_route(self.id(), self.inner.short_type_name());
 
         // TODO - explain this
         self.mark_as_visited();
         self.check_initialized("on_event");
 
         if parent_ctx.is_handled {
+            parent_ctx
+                .global_state
+                .debug_logger
+                .end_event_route(trace_index, EventRouting::AlreadyHandled);
             parent_ctx.global_state.debug_logger.pop_span();
             // If the event was already handled, we quit early.
             return;
@@ -379,6 +716,7 @@ impl<W: Widget> WidgetPod<W> {
 
         let had_active = self.state.has_active;
         let rect = self.layout_rect();
+        let content_origin = self.state.origin.to_vec2();
 
         // If we need to replace either the event or its data.
         let mut modified_event = None;
@@ -393,6 +731,7 @@ impl<W: Widget> WidgetPod<W> {
                         parent_ctx.global_state,
                         rect,
                         None,
+                        false,
                         env,
                     );
                     had_active || hot_changed
@@ -400,6 +739,12 @@ impl<W: Widget> WidgetPod<W> {
                 InternalEvent::TargetedCommand(cmd) => {
                     match cmd.target() {
                         Target::Widget(id) if id == self.id() => {
+                            if cmd.is(crate::command::STORE_CHANGED) {
+                                self.state.invalid.set_rect(
+                                    self.state.paint_rect()
+                                        - self.state.layout_rect().origin().to_vec2(),
+                                );
+                            }
                             modified_event = Some(Event::Command(cmd.clone()));
                             true
                         }
@@ -415,9 +760,9 @@ impl<W: Widget> WidgetPod<W> {
                         _ => false,
                     }
                 }
-                InternalEvent::RouteTimer(token, widget_id) => {
+                InternalEvent::RouteTimer(timer, widget_id) => {
                     if *widget_id == self.id() {
-                        modified_event = Some(Event::Timer(*token));
+                        modified_event = Some(Event::Timer(*timer));
                         true
                     } else {
                         self.state.children.may_contain(widget_id)
@@ -425,6 +770,7 @@ impl<W: Widget> WidgetPod<W> {
                 }
                 InternalEvent::RoutePromiseResult(promise_result, widget_id) => {
                     if *widget_id == self.id() {
+                        self.release_busy_guards(promise_result);
                         modified_event = Some(Event::PromiseResult(promise_result.clone()));
                         true
                     } else {
@@ -439,25 +785,50 @@ impl<W: Widget> WidgetPod<W> {
                         self.state.children.may_contain(widget_id)
                     }
                 }
+                InternalEvent::RoutePanToWidget(widget_id, rect) => {
+                    if *widget_id == self.id() {
+                        // The rect is in our parent's coordinate space, which is
+                        // where the parent expects it.
+                        let target = match rect {
+                            Some(rect) => *rect + self.state.origin.to_vec2(),
+                            None => self.state.layout_rect(),
+                        };
+                        parent_ctx.request_pan_to_child = Some(target);
+                        false
+                    } else {
+                        self.state.children.may_contain(widget_id)
+                    }
+                }
             },
             Event::WindowConnected | Event::WindowCloseRequested => true,
             Event::WindowDisconnected => true,
+            Event::Suspend | Event::Resume => true,
+            Event::MemoryWarning => {
+                // Rebuilt on the next paint if it's still needed.
+                self.debug_widget_text = TextLayout::new();
+                true
+            }
             Event::WindowSize(_) => {
                 self.state.needs_layout = true;
                 parent_ctx.is_root
             }
             Event::MouseDown(mouse_event) => {
+                let is_occluded = parent_ctx
+                    .widget_state
+                    .occluded_children
+                    .contains(&self.state.id);
                 WidgetPod::update_hot_state(
                     &mut self.inner,
                     &mut self.state,
                     parent_ctx.global_state,
                     rect,
                     Some(mouse_event.pos),
+                    is_occluded,
                     env,
                 );
                 if (had_active || self.state.is_hot) && !self.state.is_stashed {
                     let mut mouse_event = mouse_event.clone();
-                    mouse_event.pos -= rect.origin().to_vec2();
+                    mouse_event.pos -= content_origin;
                     modified_event = Some(Event::MouseDown(mouse_event));
                     true
                 } else {
@@ -465,17 +836,22 @@ impl<W: Widget> WidgetPod<W> {
                 }
             }
             Event::MouseUp(mouse_event) => {
+                let is_occluded = parent_ctx
+                    .widget_state
+                    .occluded_children
+                    .contains(&self.state.id);
                 WidgetPod::update_hot_state(
                     &mut self.inner,
                     &mut self.state,
                     parent_ctx.global_state,
                     rect,
                     Some(mouse_event.pos),
+                    is_occluded,
                     env,
                 );
                 if (had_active || self.state.is_hot) && !self.state.is_stashed {
                     let mut mouse_event = mouse_event.clone();
-                    mouse_event.pos -= rect.origin().to_vec2();
+                    mouse_event.pos -= content_origin;
                     modified_event = Some(Event::MouseUp(mouse_event));
                     true
                 } else {
@@ -483,12 +859,17 @@ impl<W: Widget> WidgetPod<W> {
                 }
             }
             Event::MouseMove(mouse_event) => {
+                let is_occluded = parent_ctx
+                    .widget_state
+                    .occluded_children
+                    .contains(&self.state.id);
                 let hot_changed = WidgetPod::update_hot_state(
                     &mut self.inner,
                     &mut self.state,
                     parent_ctx.global_state,
                     rect,
                     Some(mouse_event.pos),
+                    is_occluded,
                     env,
                 );
                 // MouseMove is recursed even if the widget is not active and not hot,
@@ -496,25 +877,51 @@ impl<W: Widget> WidgetPod<W> {
                 // e.g. drag functionality where the widget wants to follow the mouse.
                 if (had_active || self.state.is_hot || hot_changed) && !self.state.is_stashed {
                     let mut mouse_event = mouse_event.clone();
-                    mouse_event.pos -= rect.origin().to_vec2();
+                    mouse_event.pos -= content_origin;
                     modified_event = Some(Event::MouseMove(mouse_event));
                     true
                 } else {
                     false
                 }
             }
+            Event::Pen(pen_event) => {
+                let is_occluded = parent_ctx
+                    .widget_state
+                    .occluded_children
+                    .contains(&self.state.id);
+                WidgetPod::update_hot_state(
+                    &mut self.inner,
+                    &mut self.state,
+                    parent_ctx.global_state,
+                    rect,
+                    Some(pen_event.pos),
+                    is_occluded,
+                    env,
+                );
+                if (had_active || self.state.is_hot) && !self.state.is_stashed {
+                    modified_event = Some(Event::Pen(pen_event.translated(content_origin)));
+                    true
+                } else {
+                    false
+                }
+            }
             Event::Wheel(mouse_event) => {
+                let is_occluded = parent_ctx
+                    .widget_state
+                    .occluded_children
+                    .contains(&self.state.id);
                 WidgetPod::update_hot_state(
                     &mut self.inner,
                     &mut self.state,
                     parent_ctx.global_state,
                     rect,
                     Some(mouse_event.pos),
+                    is_occluded,
                     env,
                 );
                 if (had_active || self.state.is_hot) && !self.state.is_stashed {
                     let mut mouse_event = mouse_event.clone();
-                    mouse_event.pos -= rect.origin().to_vec2();
+                    mouse_event.pos -= content_origin;
                     modified_event = Some(Event::Wheel(mouse_event));
                     true
                 } else {
@@ -531,7 +938,15 @@ impl<W: Widget> WidgetPod<W> {
             }
             Event::KeyDown(_) => self.state.has_focus,
             Event::KeyUp(_) => self.state.has_focus,
+            Event::Gamepad(_) => self.state.has_focus,
             Event::Paste(_) => self.state.has_focus,
+            Event::CaretBlink(_) => self.state.has_focus,
+            // Filter events are only sent to the widget registered as a filter.
+            Event::KeyFilter(_) | Event::MouseFilter(_) => false,
+            Event::PointerCaptureLost => {
+                self.state.is_active = false;
+                had_active
+            }
             Event::Zoom(_) => had_active || self.state.is_hot,
             Event::Timer(_) => false, // This event was targeted only to our parent
             Event::ImeStateChange => true, // once delivered to the focus widget, recurse to the component?
@@ -540,7 +955,59 @@ impl<W: Widget> WidgetPod<W> {
             Event::PromiseResult(_) => false,
         };
 
+        // Input to a busy subtree is swallowed, except to finish pointer interactions
+        // already in progress.
+        let call_inner = if call_inner && self.state.is_busy() && event.is_user_input() {
+            if !had_active {
+                trace!("Blocking {} to busy widget", event.short_name());
+                if !matches!(event, Event::MouseMove(_)) {
+                    parent_ctx.set_handled();
+                }
+                false
+            } else {
+                matches!(
+                    event,
+                    Event::MouseMove(_) | Event::MouseUp(_) | Event::Pen(_)
+                )
+            }
+        } else {
+            call_inner
+        };
+
         if call_inner {
+            let inner_event = modified_event.as_ref().unwrap_or(event);
+            let filter_event = match inner_event {
+                Event::KeyDown(key_event) | Event::KeyUp(key_event) if self.state.is_key_filter => {
+                    Some(Event::KeyFilter(key_event.clone()))
+                }
+                Event::MouseDown(_) | Event::MouseUp(_) | Event::MouseMove(_) | Event::Wheel(_)
+                    if self.state.is_pointer_filter =>
+                {
+                    Some(Event::MouseFilter(Box::new(inner_event.clone())))
+                }
+                _ => None,
+            };
+            if let Some(filter_event) = filter_event {
+                self.call_filter(parent_ctx, &filter_event, env);
+            }
+        }
+
+        if call_inner && (self.state.has_z_indexed_children || self.state.exclusive_hot) {
+            let inner_event = modified_event.as_ref().unwrap_or(event);
+            let pointer_pos = match inner_event {
+                Event::MouseDown(mouse)
+                | Event::MouseUp(mouse)
+                | Event::MouseMove(mouse)
+                | Event::Wheel(mouse) => Some(mouse.pos),
+                Event::Pen(pen) => Some(pen.pos),
+                _ => None,
+            };
+            if let Some(pos) = pointer_pos {
+                self.find_occluded_children(pos);
+            }
+        }
+
+        if call_inner && !parent_ctx.is_handled {
             self.call_widget_method_with_checks("event", |widget_pod| {
                 // widget_pod is a reborrow of `self`
                 let mut notifications = VecDeque::new();
@@ -556,8 +1023,15 @@ impl<W: Widget> WidgetPod<W> {
                 inner_ctx.widget_state.has_active = false;
 
                 widget_pod.inner.on_event(&mut inner_ctx, inner_event, env);
+                if let Some(elevation) = &mut widget_pod.elevation {
+                    elevation.interaction.on_event(&mut inner_ctx, inner_event);
+                }
 
                 inner_ctx.widget_state.has_active |= inner_ctx.widget_state.is_active;
+                if inner_ctx.is_handled {
+                    let id = inner_ctx.widget_state.id;
+                    inner_ctx.global_state.set_event_handler(id);
+                }
                 parent_ctx.is_handled |= inner_ctx.is_handled;
 
                 // TODO - there's some dubious logic here
@@ -573,6 +1047,41 @@ impl<W: Widget> WidgetPod<W> {
             });
         }
 
+        // The event wasn't handled when we entered this method, so if it's handled
+        // now, it was by this widget or one of its descendants.
+        let routing = if !call_inner {
+            EventRouting::Skipped
+        } else if parent_ctx.is_handled {
+            EventRouting::Handled
+        } else {
+            EventRouting::Delivered
+        };
+
+        if self.state.is_capturing_pointer {
+            self.state.is_capturing_pointer = false;
+            self.call_widget_method_with_checks("event", |widget_pod| {
+                let mut notifications = VecDeque::new();
+                let mut inner_ctx = EventCtx {
+                    global_state: parent_ctx.global_state,
+                    widget_state: &mut widget_pod.state,
+                    notifications: &mut notifications,
+                    is_handled: false,
+                    is_root: false,
+                    request_pan_to_child: None,
+                };
+                inner_ctx.widget_state.has_active = false;
+
+                widget_pod
+                    .inner
+                    .on_event(&mut inner_ctx, &Event::PointerCaptureLost, env);
+
+                inner_ctx.widget_state.has_active |= inner_ctx.widget_state.is_active;
+                widget_pod.process_notifications(parent_ctx, &mut notifications, env);
+            });
+        }
+
+        self.check_style_state(parent_ctx.global_state);
+
         // Always merge even if not needed, because merging is idempotent and gives us simpler code.
         // Doing this conditionally only makes sense when there's a measurable performance boost.
         parent_ctx.widget_state.merge_up(&mut self.state);
@@ -585,10 +1094,60 @@ impl<W: Widget> WidgetPod<W> {
             .global_state
             .debug_logger
             .push_log(false, "updated state");
+        parent_ctx
+            .global_state
+            .debug_logger
+            .end_event_route(trace_index, routing);
 
         parent_ctx.global_state.debug_logger.pop_span();
     }
 
+    /// Send a [`KeyFilter`](Event::KeyFilter) or [`MouseFilter`](Event::MouseFilter)
+    /// event to the widget, before the event it's derived from.
+    fn call_filter(&mut self, parent_ctx: &mut EventCtx, filter_event: &Event, env: &Env) {
+        self.call_widget_method_with_checks("event", |widget_pod| {
+            let mut notifications = VecDeque::new();
+            let mut inner_ctx = EventCtx {
+                global_state: parent_ctx.global_state,
+                widget_state: &mut widget_pod.state,
+                notifications: &mut notifications,
+                is_handled: false,
+                is_root: false,
+                request_pan_to_child: None,
+            };
+
+            widget_pod.inner.on_event(&mut inner_ctx, filter_event, env);
+
+            if inner_ctx.is_handled {
+                let id = inner_ctx.widget_state.id;
+                inner_ctx.global_state.set_event_handler(id);
+            }
+            parent_ctx.is_handled |= inner_ctx.is_handled;
+            widget_pod.process_notifications(parent_ctx, &mut notifications, env);
+        });
+    }
+
+    /// Run the scheduled mutations targeting this widget.
+    fn run_mutate_callbacks(&mut self, parent_ctx: &mut LifeCycleCtx) {
+        let id = self.id();
+        let callbacks = &mut *parent_ctx.global_state.mutate_callbacks;
+        if !callbacks.iter().any(|callback| callback.id == id) {
+            return;
+        }
+        let (own_callbacks, other_callbacks): (Vec<_>, Vec<_>) = std::mem::take(callbacks)
+            .into_iter()
+            .partition(|callback| callback.id == id);
+        *callbacks = other_callbacks;
+
+        for callback in own_callbacks {
+            let ctx = WidgetCtx {
+                global_state: parent_ctx.global_state,
+                widget_state: &mut self.state,
+            };
+            callback.call(&mut self.inner, ctx, parent_ctx.widget_state);
+        }
+    }
+
     fn pan_to_child(&mut self, parent_ctx: &mut EventCtx, env: &Env, rect: Rect) {
         let mut inner_ctx = LifeCycleCtx {
             global_state: parent_ctx.global_state,
@@ -662,6 +1221,16 @@ impl<W: Widget> WidgetPod<W> {
         // TODO - explain this
         self.mark_as_visited();
 
+        // Widgets moved to another parent are visited by `RouteWidgetAdded` too, so
+        // this also keeps the parent id up to date after reparenting.
+        if let LifeCycle::WidgetAdded | LifeCycle::Internal(InternalLifeCycle::RouteWidgetAdded) =
+            event
+        {
+            // The root widget's parent state is a placeholder with the root's own id.
+            let parent_id = parent_ctx.widget_state.id;
+            self.state.parent_id = (parent_id != self.id()).then_some(parent_id);
+        }
+
         // when routing a status change event, if we are at our target
         // we may send an extra event after the actual event
         let mut extra_event = None;
@@ -724,7 +1293,11 @@ impl<W: Widget> WidgetPod<W> {
                     }
                 }
                 InternalLifeCycle::RouteFocusChanged { old, new } => {
-                    let this_changed = if *old == Some(self.state.id) {
+                    // When `old == new`, focus didn't change, but the focused widget may
+                    // have been reparented; we only refresh `has_focus` along the way.
+                    let this_changed = if old == new {
+                        None
+                    } else if *old == Some(self.state.id) {
                         Some(false)
                     } else if *new == Some(self.state.id) {
                         Some(true)
@@ -736,7 +1309,7 @@ impl<W: Widget> WidgetPod<W> {
                         self.state.has_focus = change;
                         extra_event = Some(StatusChange::FocusChanged(change));
                     } else {
-                        self.state.has_focus = false;
+                        self.state.has_focus = *new == Some(self.state.id);
                     }
 
                     // Recurse when the target widgets could be our descendants.
@@ -747,10 +1320,82 @@ impl<W: Widget> WidgetPod<W> {
                         _ => false,
                     }
                 }
+                InternalLifeCycle::RouteMutations => {
+                    self.run_mutate_callbacks(parent_ctx);
+                    parent_ctx
+                        .global_state
+                        .mutate_callbacks
+                        .iter()
+                        .any(|callback| self.state.children.may_contain(&callback.id))
+                }
+                InternalLifeCycle::RouteRestoreState(states) => {
+                    if let Some(state) = states.get(&self.id()) {
+                        // Not checked for visited children: `restore_state` only restores
+                        // the widget itself, and the routing recurses into the children.
+                        let mut inner_ctx = LifeCycleCtx {
+                            global_state: parent_ctx.global_state,
+                            widget_state: &mut self.state,
+                        };
+                        self.inner.restore_state(&mut inner_ctx, state, env);
+                    }
+                    true
+                }
+                InternalLifeCycle::RouteEnvChanged(changed_keys) => {
+                    if self
+                        .state
+                        .env_keys
+                        .iter()
+                        .any(|key| changed_keys.contains(key))
+                    {
+                        self.state.needs_layout = true;
+                        self.state.invalid.set_rect(
+                            self.state.paint_rect() - self.state.layout_rect().origin().to_vec2(),
+                        );
+                        self.call_widget_method_with_checks("lifecycle", |widget_pod| {
+                            let mut inner_ctx = LifeCycleCtx {
+                                global_state: parent_ctx.global_state,
+                                widget_state: &mut widget_pod.state,
+                            };
+                            widget_pod
+                                .inner
+                                .lifecycle(&mut inner_ctx, &LifeCycle::EnvChanged, env);
+                        });
+                    }
+                    // Descendants may have read the keys even if we didn't.
+                    true
+                }
+                InternalLifeCycle::RouteRefineLayout(mouse_pos) => {
+                    if self.state.request_refine_layout {
+                        self.refine_layout(parent_ctx, *mouse_pos, env);
+                        false
+                    } else {
+                        self.state.needs_refine_layout
+                    }
+                }
+                InternalLifeCycle::RouteCapture(capture) => {
+                    if capture.target != self.state.id {
+                        self.state.children.may_contain(&capture.target)
+                    } else {
+                        let image = self.paint_to_image(parent_ctx, capture.scale, env);
+                        *capture.image.borrow_mut() = image;
+                        false
+                    }
+                }
+                InternalLifeCycle::RoutePrint(job) => {
+                    if job.target != self.state.id {
+                        self.state.children.may_contain(&job.target)
+                    } else {
+                        let pages = self.print_pages(parent_ctx, &job.setup, env);
+                        *job.pages.borrow_mut() = Some(pages);
+                        false
+                    }
+                }
                 InternalLifeCycle::ParentWindowOrigin => {
                     self.state.parent_window_origin = parent_ctx.widget_state.window_origin();
+                    self.state.window_scale =
+                        parent_ctx.widget_state.window_scale * self.state.scale;
                     self.state.needs_window_origin = false;
-                    // TODO - self.state.is_hidden
+                    self.update_visibility(parent_ctx, env);
                     true
                 }
             },
@@ -806,6 +1451,12 @@ impl<W: Widget> WidgetPod<W> {
             }
             // This is called by children when going up the widget tree.
             LifeCycle::RequestPanToChild(_) => false,
+            // Sent by `RouteEnvChanged` to each widget that needs it; containers
+            // forwarding it to their children don't send it further.
+            LifeCycle::EnvChanged => false,
+            LifeCycle::SafeAreaChanged(_) => true,
+            // Sent by `update_visibility` to each widget that needs it.
+            LifeCycle::VisibilityChanged(_) => false,
         };
 
         // widget_pod is a reborrow of `self`
@@ -817,6 +1468,9 @@ impl<W: Widget> WidgetPod<W> {
                 };
 
                 widget_pod.inner.lifecycle(&mut inner_ctx, event, env);
+                if let Some(elevation) = &mut widget_pod.elevation {
+                    elevation.interaction.lifecycle(&mut inner_ctx, event);
+                }
             });
         }
 
@@ -829,6 +1483,11 @@ impl<W: Widget> WidgetPod<W> {
             // We add a span so that inner logs are marked as being in an on_status_change pass
             let _span = info_span!("on_status_change").entered();
             self.inner.on_status_change(&mut inner_ctx, event, env);
+            if let Some(elevation) = &mut self.elevation {
+                elevation
+                    .interaction
+                    .on_status_change(&mut inner_ctx, event);
+            }
         }
 
         // Sync our state with our parent's state after the event!
@@ -837,6 +1496,8 @@ impl<W: Widget> WidgetPod<W> {
             // we need to (re)register children in case of one of the following events
             LifeCycle::WidgetAdded | LifeCycle::Internal(InternalLifeCycle::RouteWidgetAdded) => {
                 self.state.children_changed = false;
+                // Like a z-index, an elevation changes how the parent hit-tests its children.
+                parent_ctx.widget_state.has_z_indexed_children |= self.state.elevation != 0.0;
                 parent_ctx.widget_state.children =
                     parent_ctx.widget_state.children.union(self.state.children);
                 parent_ctx.register_child(self.id());
@@ -855,6 +1516,9 @@ impl<W: Widget> WidgetPod<W> {
                 // recursions.
                 self.state.is_explicitly_disabled_new = self.state.is_explicitly_disabled;
             }
+            LifeCycle::Internal(InternalLifeCycle::RouteRefineLayout(_)) => {
+                self.state.needs_refine_layout = false;
+            }
             // Update focus-chain of our parent
             LifeCycle::BuildFocusChain => {
                 self.state.update_focus_chain = false;
@@ -879,6 +1543,8 @@ impl<W: Widget> WidgetPod<W> {
             _ => (),
         }
 
+        self.check_style_state(parent_ctx.global_state);
+
         parent_ctx.widget_state.merge_up(&mut self.state);
 
         parent_ctx
@@ -893,6 +1559,83 @@ impl<W: Widget> WidgetPod<W> {
         parent_ctx.global_state.debug_logger.pop_span();
     }
 
+    /// Lay out the widget again with the constraints of its last layout, at the same
+    /// position, after it called [`request_refine_layout`](EventCtx::request_refine_layout).
+    ///
+    /// Its parent is only laid out again if the widget's size changed.
+    /// `mouse_pos` is in window coordinates.
+    fn refine_layout(
+        &mut self,
+        parent_ctx: &mut LifeCycleCtx,
+        mouse_pos: Option<Point>,
+        env: &Env,
+    ) {
+        let Some(bc) = self.state.layout_constraints else {
+            // The widget was never laid out; the next layout pass will do it.
+            self.state.needs_layout = true;
+            return;
+        };
+        let old_size = self.state.size;
+        let old_paint_rect = self.state.local_paint_rect;
+        let style_insets = self.state.style_insets;
+        let origin = self.state.origin - Vec2::new(style_insets.x0, style_insets.y0);
+        let parent_window_origin = parent_ctx.widget_state.window_origin().to_vec2();
+        let parent_window_scale = parent_ctx.widget_state.window_scale;
+
+        let mut layout_ctx = LayoutCtx {
+            global_state: parent_ctx.global_state,
+            widget_state: parent_ctx.widget_state,
+            mouse_pos: mouse_pos.map(|pos| {
+                ((pos - parent_window_origin).to_vec2() / parent_window_scale).to_point()
+            }),
+            viewport: None,
+        };
+        self.layout(&mut layout_ctx, &bc, env);
+        layout_ctx.place_child(self, origin, env);
+
+        if self.state.size != old_size {
+            self.state.needs_layout = true;
+        }
+        // Our children may have moved.
+        self.state.needs_window_origin = true;
+        self.state.invalid.add_rect(old_paint_rect);
+        self.state.invalid.add_rect(self.state.local_paint_rect);
+    }
+
+    /// Recompute whether the widget is visible, from its window position and the
+    /// state of its parent, and send [`LifeCycle::VisibilityChanged`] if it changed.
+    fn update_visibility(&mut self, parent_ctx: &mut LifeCycleCtx, env: &Env) {
+        let parent_state = &*parent_ctx.widget_state;
+        let rect = self.state.window_layout_rect();
+        let clip = parent_state.visible_clip;
+        let is_visible = parent_state.is_visible
+            && parent_ctx.global_state.is_window_visible
+            && !self.state.is_stashed
+            && overlaps(rect.x0, rect.x1, clip.x0, clip.x1)
+            && overlaps(rect.y0, rect.y1, clip.y0, clip.y1);
+        self.state.visible_clip = if self.state.is_portal {
+            clip.intersect(rect)
+        } else {
+            parent_state.visible_clip
+        };
+
+        if self.state.is_visible == is_visible {
+            return;
+        }
+        self.state.is_visible = is_visible;
+        self.call_widget_method_with_checks("lifecycle", |widget_pod| {
+            let mut inner_ctx = LifeCycleCtx {
+                global_state: parent_ctx.global_state,
+                widget_state: &mut widget_pod.state,
+            };
+            widget_pod.inner.lifecycle(
+                &mut inner_ctx,
+                &LifeCycle::VisibilityChanged(is_visible),
+                env,
+            );
+        });
+    }
+
     // --- LAYOUT ---
 
     /// Compute layout of a widget.
@@ -924,19 +1667,39 @@ impl<W: Widget> WidgetPod<W> {
         self.check_initialized("layout");
 
         self.state.needs_layout = false;
+        self.state.request_refine_layout = false;
+        self.state.needs_refine_layout = false;
+        self.state.layout_constraints = Some(*bc);
         self.state.needs_window_origin = false;
         self.state.is_expecting_place_child_call = true;
 
-        bc.debug_check(self.inner.short_type_name());
-
         let inner_mouse_pos = parent_ctx
             .mouse_pos
-            .map(|pos| pos - self.layout_rect().origin().to_vec2());
+            .map(|pos| pos - self.state.origin.to_vec2());
+        let inner_viewport = parent_ctx
+            .viewport
+            .map(|rect| rect - self.state.origin.to_vec2());
 
         // TODO - remove ?
         let _prev_size = self.state.size;
 
         self.state.local_paint_rect = Rect::ZERO;
+        self.state.page_break = PageBreak::Auto;
+
+        let pseudo_classes = PseudoClassState::new(&self.state);
+        self.state.computed_style = parent_ctx.global_state.style_sheet.resolve(
+            self.inner.short_type_name(),
+            &self.state.classes,
+            pseudo_classes,
+            &self.state.style,
+        );
+        self.state.style_pseudo_classes = pseudo_classes;
+        let style_insets = self.state.computed_style.insets(env);
+        self.state.style_insets = style_insets;
+        let mut content_bc = bc.shrink(style_insets.size());
+        if let Some(scale) = parent_ctx.global_state.pixel_scale {
+            content_bc = content_bc.snap_to_pixels(scale);
+        }
 
         let new_size = self.call_widget_method_with_checks("layout", |widget_pod| {
             // widget_pod is a reborrow of `self`
@@ -945,15 +1708,24 @@ impl<W: Widget> WidgetPod<W> {
                 widget_state: &mut widget_pod.state,
                 global_state: parent_ctx.global_state,
                 mouse_pos: inner_mouse_pos,
+                viewport: inner_viewport,
             };
 
-            widget_pod.inner.layout(&mut inner_ctx, bc, env)
+            widget_pod.inner.layout(&mut inner_ctx, &content_bc, env)
         });
+        let new_size = parent_ctx.global_state.snap_size(new_size);
 
         self.state.local_paint_rect = self
             .state
             .local_paint_rect
-            .union(new_size.to_rect() + self.state.paint_insets);
+            .union(new_size.to_rect() + self.state.paint_insets)
+            .union(new_size.to_rect() + style_insets);
+        if let Some(elevation) = &self.elevation {
+            self.state.local_paint_rect = self
+                .state
+                .local_paint_rect
+                .union(new_size.to_rect() + style_insets + elevation.shadow_insets());
+        }
 
         if cfg!(debug_assertions) {
             for child in self.inner.children() {
@@ -997,7 +1769,11 @@ impl<W: Widget> WidgetPod<W> {
 
         parent_ctx.widget_state.merge_up(&mut self.state);
         self.state.size = new_size;
-        self.log_layout_issues(new_size);
+        if cfg!(debug_assertions) {
+            self.check_layout_protocol(&content_bc, new_size, parent_ctx.global_state);
+        } else {
+            self.log_layout_issues(new_size);
+        }
 
         parent_ctx
             .global_state
@@ -1010,7 +1786,52 @@ impl<W: Widget> WidgetPod<W> {
 
         parent_ctx.global_state.debug_logger.pop_span();
 
-        new_size
+        new_size + style_insets.size()
+    }
+
+    /// Report the ways the constraints passed to our widget, or the size it returned,
+    /// break the layout protocol.
+    fn check_layout_protocol(
+        &self,
+        bc: &BoxConstraints,
+        size: Size,
+        global_state: &mut GlobalPassCtx,
+    ) {
+        let (min, max) = (bc.min(), bc.max());
+        // Constraints are aligned to integers, or to physical pixels in integer
+        // layout mode.
+        let is_aligned = match global_state.pixel_scale {
+            Some(scale) => {
+                let snapped = bc.snap_to_pixels(scale);
+                snapped.min() == min && snapped.max() == max
+            }
+            None => min.expand() == min && max.expand() == max,
+        };
+        let valid_constraints = min.width.is_finite()
+            && min.height.is_finite()
+            && 0.0 <= min.width
+            && min.width <= max.width
+            && 0.0 <= min.height
+            && min.height <= max.height
+            && is_aligned;
+        let kind = if !valid_constraints {
+            LayoutViolationKind::InvalidConstraints
+        } else if !(size.width.is_finite() && size.height.is_finite()) {
+            LayoutViolationKind::NonFiniteSize
+        } else if !bc.contains(size) {
+            LayoutViolationKind::SizeOutsideConstraints
+        } else {
+            return;
+        };
+        global_state
+            .debug_logger
+            .report_layout_violation(LayoutViolation {
+                widget_id: self.state.id,
+                widget_name: self.inner.short_type_name(),
+                kind,
+                constraints: *bc,
+                size,
+            });
     }
 
     fn log_layout_issues(&self, size: Size) {
@@ -1057,7 +1878,26 @@ impl<W: Widget> WidgetPod<W> {
                 region: ctx.region.clone(),
                 depth: ctx.depth,
             };
+            let style_insets = widget_pod.state.style_insets;
+            if let Some(elevation) = &widget_pod.elevation {
+                let border_box = widget_pod.state.size.to_rect() + style_insets;
+                elevation.paint_shadow(&mut inner_ctx, border_box, env);
+            }
+            widget_pod
+                .state
+                .computed_style
+                .paint(&mut inner_ctx, style_insets, env);
+            if let Some(background) = &mut widget_pod.background {
+                inner_ctx.with_save(|ctx| background.paint(ctx, env));
+            }
             widget_pod.inner.paint(&mut inner_ctx, env);
+            if let Some(foreground) = &mut widget_pod.foreground {
+                inner_ctx.with_save(|ctx| foreground.paint(ctx, env));
+            }
+            if widget_pod.state.has_busy_veil() {
+                let rect = widget_pod.state.size.to_rect();
+                inner_ctx.fill(rect, &env.get(theme::BUSY_VEIL_COLOR));
+            }
 
             let debug_ids = widget_pod.state.is_hot && env.get(Env::DEBUG_WIDGET_ID);
             if debug_ids {
@@ -1110,7 +1950,7 @@ impl<W: Widget> WidgetPod<W> {
         }
 
         parent_ctx.with_save(|ctx| {
-            let layout_origin = self.layout_rect().origin().to_vec2();
+            let layout_origin = self.state.origin.to_vec2();
             ctx.transform(Affine::translate(layout_origin));
             let mut visible = ctx.region().clone();
             visible.intersect_with(self.state.paint_rect());
@@ -1119,6 +1959,83 @@ impl<W: Widget> WidgetPod<W> {
         });
     }
 
+    /// Paint this widget and its children into a new bitmap, as they were last
+    /// laid out.
+    fn paint_to_image(
+        &mut self,
+        parent_ctx: &mut LifeCycleCtx,
+        scale: f64,
+        env: &Env,
+    ) -> Option<ImageBuf> {
+        let size = self.state.size;
+        let global_state = &mut *parent_ctx.global_state;
+        let parent_state = &*parent_ctx.widget_state;
+        paint_offscreen(global_state, parent_state, 0, size, scale, |ctx| {
+            self.paint_raw(ctx, env);
+        })
+    }
+
+    /// Lay out this widget at the width of a page, and paint it onto as many
+    /// pages as needed.
+    ///
+    /// The widget is laid out again with its usual constraints afterwards.
+    fn print_pages(
+        &mut self,
+        parent_ctx: &mut LifeCycleCtx,
+        setup: &PageSetup,
+        env: &Env,
+    ) -> Vec<ImageBuf> {
+        let content_rect = setup.content_rect();
+        let origin = self.state.origin;
+        let bc = BoxConstraints::new(
+            Size::new(content_rect.width(), 0.0),
+            Size::new(content_rect.width(), f64::INFINITY),
+        );
+        let mut layout_ctx = LayoutCtx {
+            global_state: parent_ctx.global_state,
+            widget_state: parent_ctx.widget_state,
+            mouse_pos: None,
+            viewport: None,
+        };
+        let size = self.layout(&mut layout_ctx, &bc, env);
+        // The parent doesn't place the widget during this pass.
+        self.state.origin = origin;
+        self.state.is_expecting_place_child_call = false;
+
+        let mut page_breaks = Vec::new();
+        print::collect_page_breaks(self.as_dyn(), Point::ORIGIN, &mut page_breaks);
+        let pages = print::paginate(size.height, content_rect.height(), &page_breaks);
+
+        let global_state = &mut *parent_ctx.global_state;
+        let parent_state = &*parent_ctx.widget_state;
+        let pages = pages
+            .into_iter()
+            .filter_map(|range| {
+                paint_offscreen(
+                    global_state,
+                    parent_state,
+                    0,
+                    setup.size,
+                    setup.scale,
+                    |ctx| {
+                        ctx.fill(setup.size.to_rect(), &Color::WHITE);
+                        ctx.clip(content_rect);
+                        ctx.transform(Affine::translate((
+                            content_rect.x0,
+                            content_rect.y0 - range.start,
+                        )));
+                        // Only the content of this page is painted.
+                        ctx.region = Rect::new(0.0, range.start, size.width, range.end).into();
+                        self.paint_raw(ctx, env);
+                    },
+                )
+            })
+            .collect();
+
+        self.state.needs_layout = true;
+        pages
+    }
+
     // FIXME - Add snapshot test for debug_widget_text
 
     fn make_widget_id_layout_if_needed(&mut self, id: WidgetId, ctx: &mut PaintCtx, env: &Env) {
@@ -1173,3 +2090,15 @@ fn rect_contains(larger: &Rect, smaller: &Rect) -> bool {
         && smaller.y0 >= larger.y0
         && smaller.y1 <= larger.y1
 }
+
+/// Return `true` if the range `a_min..a_max` overlaps `b_min..b_max`.
+///
+/// An empty range overlaps the ranges containing it, so that zero-sized widgets
+/// can be visible.
+fn overlaps(a_min: f64, a_max: f64, b_min: f64, b_max: f64) -> bool {
+    if a_min == a_max {
+        b_min <= a_min && a_min <= b_max
+    } else {
+        a_min < b_max && b_min < a_max
+    }
+}
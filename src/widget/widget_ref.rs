@@ -7,6 +7,7 @@ use std::ops::Deref;
 use smallvec::SmallVec;
 
 use crate::kurbo::Point;
+use crate::widget::MemoryUsage;
 use crate::{Widget, WidgetId, WidgetState};
 
 /// A rich reference to a [`Widget`].
@@ -121,6 +122,23 @@ impl<'w> WidgetRef<'w, dyn Widget> {
         self.widget.children()
     }
 
+    /// Estimate the heap memory, in bytes, retained by this widget and its descendants.
+    ///
+    /// This is the sum of [`Widget::retained_memory`] over the subtree, with shared
+    /// buffers counted once.
+    pub fn subtree_memory(&self) -> usize {
+        let mut memory = MemoryUsage::default();
+        self.add_subtree_memory(&mut memory);
+        memory.bytes()
+    }
+
+    fn add_subtree_memory(&self, memory: &mut MemoryUsage) {
+        self.widget.retained_memory(memory);
+        for child in self.children() {
+            child.add_subtree_memory(memory);
+        }
+    }
+
     /// Recursively find child widget with given id.
     pub fn find_widget_by_id(&self, id: WidgetId) -> Option<WidgetRef<'w, dyn Widget>> {
         if self.state().id == id {
@@ -4,15 +4,19 @@
 
 #![cfg(not(tarpaulin_include))]
 
+use std::collections::HashSet;
 use std::sync::atomic::{AtomicBool, Ordering};
 
 use druid_shell::{Cursor, Region};
 
 use crate::bloom::Bloom;
+use crate::busy::BusyGuard;
 use crate::kurbo::{Insets, Point, Rect, Size};
+use crate::print::PageBreak;
+use crate::style::{PseudoClassState, WidgetStyle};
 use crate::text::TextFieldRegistration;
 use crate::widget::{CursorChange, FocusChange};
-use crate::WidgetId;
+use crate::{ArcStr, BoxConstraints, KeyRepeat, WidgetId};
 
 // FIXME #5 - Make a note documenting this: the only way to get a &mut WidgetState should be in a pass.
 // A pass should reborrow the parent widget state (to avoid crossing wires) and call merge_up at
@@ -39,6 +43,8 @@ use crate::WidgetId;
 #[derive(Clone, Debug)]
 pub struct WidgetState {
     pub(crate) id: WidgetId,
+    /// The id of the parent widget, set during `WidgetAdded`; `None` for the root widget.
+    pub(crate) parent_id: Option<WidgetId>,
 
     // --- LAYOUT ---
     /// The size of the child; this is the value returned by the child's layout
@@ -49,6 +55,12 @@ pub struct WidgetState {
     pub(crate) origin: Point,
     /// The origin of the parent in the window coordinate space;
     pub(crate) parent_window_origin: Point,
+    /// The factor the parent scales the child by when painting it; see
+    /// [`LayoutCtx::set_child_scale`](crate::LayoutCtx::set_child_scale).
+    pub(crate) scale: f64,
+    /// The size of one unit of the child's coordinate space in window coordinates,
+    /// ie the product of the scales of the child and its ancestors.
+    pub(crate) window_scale: f64,
     /// The insets applied to the layout rect to generate the paint rect.
     /// In general, these will be zero; the exception is for things like
     /// drop shadows or overflowing text.
@@ -62,8 +74,23 @@ pub struct WidgetState {
     /// the baseline. Widgets that contain text or controls that expect to be
     /// laid out alongside text can set this as appropriate.
     pub(crate) baseline_offset: f64,
+    /// How the widget may be split across printed pages, set during layout.
+    pub(crate) page_break: PageBreak,
     // TODO - Document
     pub(crate) is_portal: bool,
+    /// The background, border and padding set on the widget's pod.
+    pub(crate) style: WidgetStyle,
+    /// The style classes of the widget, used to match style sheet rules.
+    pub(crate) classes: Vec<ArcStr>,
+    /// `style` combined with the matching style sheet rules, resolved during layout.
+    pub(crate) computed_style: WidgetStyle,
+    /// The widget's states when `computed_style` was resolved.
+    pub(crate) style_pseudo_classes: PseudoClassState,
+    /// The insets of `computed_style`, resolved during layout.
+    ///
+    /// The layout rect is the widget's content box; these insets are applied to it
+    /// to get the area seen by the parent.
+    pub(crate) style_insets: Insets,
 
     // --- PASSES ---
 
@@ -86,6 +113,15 @@ pub struct WidgetState {
 
     pub(crate) needs_layout: bool,
 
+    /// The widget asked to be laid out again with its last constraints.
+    pub(crate) request_refine_layout: bool,
+
+    /// The widget or a descendant asked to be laid out again with its last constraints.
+    pub(crate) needs_refine_layout: bool,
+
+    /// The constraints passed to the widget's last layout, used to refine its layout.
+    pub(crate) layout_constraints: Option<BoxConstraints>,
+
     /// Because of some scrolling or something, `parent_window_origin` needs to be updated.
     pub(crate) needs_window_origin: bool,
 
@@ -107,6 +143,11 @@ pub struct WidgetState {
 
     pub(crate) text_registrations: Vec<TextFieldRegistration>,
 
+    /// The [`Env`](crate::Env) keys the widget read in its methods, not counting
+    /// its descendants. It gets [`LifeCycle::EnvChanged`](crate::LifeCycle::EnvChanged)
+    /// when one of them changes.
+    pub(crate) env_keys: HashSet<ArcStr>,
+
     // --- STATUS ---
     // `true` if one of our ancestors is disabled (meaning we are also disabled).
     pub(crate) ancestor_disabled: bool,
@@ -126,9 +167,63 @@ pub struct WidgetState {
     /// Descendants of the focused widget are not in the focused path.
     pub(crate) has_focus: bool,
 
+    /// The widget paints its own focus indicator, so the window shouldn't
+    /// paint a focus ring around it.
+    pub(crate) draws_own_focus_ring: bool,
+
+    /// The widget receives [`Event::KeyFilter`](crate::Event::KeyFilter) before key
+    /// events are sent to its descendants.
+    pub(crate) is_key_filter: bool,
+
+    /// The widget receives [`Event::MouseFilter`](crate::Event::MouseFilter) before
+    /// mouse events are sent to its descendants.
+    pub(crate) is_pointer_filter: bool,
+
+    /// The widget called [`EventCtx::capture_pointer`](crate::EventCtx::capture_pointer)
+    /// during the current event, and its descendants need to be told.
+    pub(crate) is_capturing_pointer: bool,
+
+    /// The widget receives [`Event::CaretBlink`](crate::Event::CaretBlink) while focused.
+    pub(crate) wants_caret_blink: bool,
+
+    /// How held keys repeat while the widget is focused.
+    pub(crate) key_repeat: KeyRepeat,
+
+    /// The paint and hit-test order of the widget among its siblings.
+    pub(crate) z_index: i32,
+
+    /// The resting elevation of the widget, which raises it above its siblings.
+    pub(crate) elevation: f64,
+
+    /// Whether a child of the widget has a non-zero z-index.
+    pub(crate) has_z_indexed_children: bool,
+
+    /// Whether only the topmost child under the pointer is hot.
+    pub(crate) exclusive_hot: bool,
+
+    /// The children which were under the pointer, but below an overlapping sibling
+    /// with a higher z-index, at the last pointer event. They aren't hot.
+    pub(crate) occluded_children: HashSet<WidgetId>,
+
+    /// The reasons the widget is busy. Input isn't sent to busy widgets or their
+    /// descendants.
+    pub(crate) busy_guards: Vec<BusyGuard>,
+
+    /// The widget (or a descendant) asked for the caret blink cycle to restart.
+    pub(crate) reset_caret_blink: bool,
+
     // TODO - document
     pub(crate) is_stashed: bool,
 
+    /// Whether the widget can be seen: it and its ancestors aren't stashed, part of
+    /// it is inside the window and every portal containing it, and the window isn't
+    /// minimized. Updated after layout.
+    pub(crate) is_visible: bool,
+
+    /// The part of the window where the widget's children can be seen, in window
+    /// coordinates.
+    pub(crate) visible_clip: Rect,
+
     // --- DEBUG INFO ---
     // Used in event/lifecycle/etc methods that are expected to be called recursively
     // on a widget's children, to make sure each child was visited.
@@ -148,25 +243,50 @@ impl WidgetState {
     pub(crate) fn new(id: WidgetId, size: Option<Size>, widget_name: &'static str) -> WidgetState {
         WidgetState {
             id,
+            parent_id: None,
             origin: Point::ORIGIN,
             parent_window_origin: Point::ORIGIN,
+            scale: 1.0,
+            window_scale: 1.0,
             size: size.unwrap_or_default(),
             is_expecting_place_child_call: false,
             paint_insets: Insets::ZERO,
             local_paint_rect: Rect::ZERO,
             invalid: Region::EMPTY,
             is_portal: false,
+            style: WidgetStyle::default(),
+            classes: Vec::new(),
+            computed_style: WidgetStyle::default(),
+            style_pseudo_classes: PseudoClassState::default(),
+            style_insets: Insets::ZERO,
             is_new: true,
             children_disabled_changed: false,
             ancestor_disabled: false,
             is_explicitly_disabled: false,
             baseline_offset: 0.0,
+            page_break: PageBreak::Auto,
             is_hot: false,
             needs_layout: false,
+            request_refine_layout: false,
+            needs_refine_layout: false,
+            layout_constraints: None,
             needs_window_origin: false,
             is_active: false,
             has_active: false,
             has_focus: false,
+            draws_own_focus_ring: false,
+            is_key_filter: false,
+            is_pointer_filter: false,
+            is_capturing_pointer: false,
+            wants_caret_blink: false,
+            key_repeat: KeyRepeat::Platform,
+            z_index: 0,
+            elevation: 0.0,
+            has_z_indexed_children: false,
+            exclusive_hot: false,
+            occluded_children: HashSet::new(),
+            busy_guards: Vec::new(),
+            reset_caret_blink: false,
             request_anim: false,
             request_focus: None,
             focus_chain: Vec::new(),
@@ -176,8 +296,11 @@ impl WidgetState {
             cursor: None,
             is_explicitly_disabled_new: false,
             text_registrations: Vec::new(),
+            env_keys: HashSet::new(),
             update_focus_chain: false,
             is_stashed: false,
+            is_visible: true,
+            visible_clip: size.unwrap_or_default().to_rect(),
             #[cfg(debug_assertions)]
             needs_visit: VisitBool(false.into()),
             #[cfg(debug_assertions)]
@@ -198,6 +321,14 @@ impl WidgetState {
         self.needs_visit.0.load(Ordering::SeqCst)
     }
 
+    pub(crate) fn is_busy(&self) -> bool {
+        !self.busy_guards.is_empty()
+    }
+
+    pub(crate) fn has_busy_veil(&self) -> bool {
+        self.busy_guards.iter().any(|guard| guard.veil)
+    }
+
     pub(crate) fn is_disabled(&self) -> bool {
         self.is_explicitly_disabled || self.ancestor_disabled
     }
@@ -234,6 +365,7 @@ impl WidgetState {
         child_state.invalid.clear();
 
         self.needs_layout |= child_state.needs_layout;
+        self.needs_refine_layout |= child_state.needs_refine_layout;
         self.needs_window_origin |= child_state.needs_window_origin;
         self.request_anim |= child_state.request_anim;
         self.children_disabled_changed |= child_state.children_disabled_changed;
@@ -246,6 +378,7 @@ impl WidgetState {
         self.text_registrations
             .append(&mut child_state.text_registrations);
         self.update_focus_chain |= child_state.update_focus_chain;
+        self.reset_caret_blink |= std::mem::take(&mut child_state.reset_caret_blink);
 
         // We reset `child_state.cursor` no matter what, so that on the every pass through the tree,
         // things will be recalculated just from `cursor_change`.
@@ -289,16 +422,30 @@ impl WidgetState {
         Rect::from_origin_size(self.origin, self.size)
     }
 
+    /// The paint and hit-test order of the widget among its siblings: its z-index,
+    /// raised by its elevation.
+    pub(crate) fn stacking_order(&self) -> i32 {
+        self.z_index + self.elevation.round() as i32
+    }
+
+    /// The layout rect with the padding and border of the widget's style applied.
+    ///
+    /// This is the area the widget's parent lays out; it is the same as the
+    /// layout rect for widgets without style.
+    pub fn border_rect(&self) -> Rect {
+        self.layout_rect() + self.style_insets
+    }
+
     /// The [layout_rect](crate::WidgetPod::layout_rect) in window coordinates.
     ///
     /// This might not map to a visible area of the screen, eg if the widget is scrolled
     /// away.
     pub fn window_layout_rect(&self) -> Rect {
-        Rect::from_origin_size(self.window_origin(), self.size)
+        Rect::from_origin_size(self.window_origin(), self.size * self.window_scale)
     }
 
     pub(crate) fn window_origin(&self) -> Point {
-        self.parent_window_origin + self.origin.to_vec2()
+        self.parent_window_origin + self.origin.to_vec2() * self.window_scale
     }
 }
 